@@ -0,0 +1,106 @@
+//! Programs annotated with the errors they're expected to have, so that they check themselves.
+//!
+//! A `// ERROR: <text>` comment at the end of a line expects an error to be reported at a
+//! statement on that line, whose message contains the text, or whose error code is the text:
+//!
+//! ```notrust
+//! x = 4; // ERROR: cannot assign to `x` because it is borrowed
+//! y = &'L_x mut x; // ERROR: E0502
+//! ```
+//!
+//! A line can expect multiple errors, with multiple comments. The program must have exactly the
+//! annotated errors: each reported error needs an annotation on its line, and each annotation
+//! needs an error.
+
+use crate::diagnostics::{diagnose, Diagnostic};
+use crate::fact_emitter::{emit_facts, Facts};
+use crate::source_map::SourceMap;
+use std::fmt;
+
+#[cfg(test)]
+mod test;
+
+const ANNOTATION: &str = "// ERROR:";
+
+/// The differences between the errors a program is annotated with, and the errors reported for
+/// it. The lines start at 1.
+#[derive(Debug, Default)]
+pub struct AnnotationMismatches {
+    /// The expected errors which are not reported: their line, and the annotation's text.
+    pub missing: Vec<(usize, String)>,
+
+    /// The reported errors which are not expected: their line, and their message.
+    pub unexpected: Vec<(usize, String)>,
+}
+
+impl AnnotationMismatches {
+    /// Whether the program has exactly the errors it's annotated with.
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl fmt::Display for AnnotationMismatches {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (line, text) in &self.missing {
+            writeln!(
+                f,
+                "line {}: expected error `{}` is not reported",
+                line, text
+            )?;
+        }
+        for (line, message) in &self.unexpected {
+            writeln!(f, "line {}: unexpected error: {}", line, message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Analyzes the program and compares the errors reported for it with its `// ERROR`
+/// annotations.
+pub fn check_annotations(input: &str) -> eyre::Result<AnnotationMismatches> {
+    let facts = emit_facts(input)?;
+
+    // The expected errors which are not matched by a reported error yet
+    let mut missing: Vec<_> = annotations(input).collect();
+    let mut unexpected = Vec::new();
+    for diagnostic in diagnose(&facts) {
+        let line = node_line(input, &facts, &diagnostic);
+        let expected = missing.iter().position(|(expected_line, text)| {
+            *expected_line == line && is_match(&diagnostic, text)
+        });
+        match expected {
+            Some(idx) => {
+                missing.remove(idx);
+            }
+            None => unexpected.push((line, diagnostic.message)),
+        }
+    }
+
+    Ok(AnnotationMismatches {
+        missing,
+        unexpected,
+    })
+}
+
+// The expected errors of the input: the line of each annotation, and its text.
+fn annotations(input: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    input.lines().enumerate().flat_map(|(idx, line)| {
+        line.split(ANNOTATION)
+            .skip(1)
+            .map(move |text| (idx + 1, text.trim().to_string()))
+    })
+}
+
+// The line of the statement the diagnostic is reported at.
+fn node_line(input: &str, facts: &Facts, diagnostic: &Diagnostic) -> usize {
+    let start = facts
+        .node_span(&diagnostic.node)
+        .map_or(0, |span| span.start());
+    let (line, _) = SourceMap::new(input).line_col(start);
+    line
+}
+
+fn is_match(diagnostic: &Diagnostic, text: &str) -> bool {
+    diagnostic.code == Some(text) || diagnostic.message.contains(text)
+}
@@ -0,0 +1,40 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+#[test]
+fn annotated_errors_are_reported() {
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+        let z: &'z mut i32;
+
+        bb0: {
+            y = &'L_x x;
+            x = 4; // ERROR: cannot assign to `x` because it is borrowed
+            z = &'L_mut_x mut x; // ERROR: E0502
+            use(copy y);
+        }
+    ";
+    assert!(check_annotations(program).unwrap().is_empty());
+}
+
+#[test]
+fn missing_and_unexpected_errors() {
+    // The error is reported on the line of the assignment, not of the access
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            y = &'L_x x;
+            x = 4;
+            use(copy y); // ERROR: E0506
+            x = 5; // ERROR: E0499
+        }
+    ";
+    assert_display_snapshot!(check_annotations(program).unwrap(), @r###"
+    line 8: expected error `E0506` is not reported
+    line 9: expected error `E0499` is not reported
+    line 7: unexpected error: cannot assign to `x` because it is borrowed
+    "###);
+}
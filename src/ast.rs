@@ -1,11 +1,117 @@
-use crate::span::Spanned as Sp;
+//! The syntax tree of programs, as parsed and lowered from their text: the intermediate
+//! representation the facts are emitted from, see `lowering` for how it's lowered from the
+//! program as written, `surface::Program`.
+
+use crate::span::{Span, Spanned as Sp};
+use crate::symbol::Symbol;
+use std::fmt;
+
+mod fold;
+#[cfg(test)]
+mod macros;
+mod print;
+mod visit;
+pub use fold::{noop_fold_basic_block, noop_fold_expr, noop_fold_statement, Folder};
+#[cfg(test)]
+pub(crate) use macros::program;
+pub use visit::{walk_expr, Visitor};
 
 #[derive(Clone, Debug)]
 pub struct Program {
     pub struct_decls: Vec<StructDecl>,
     pub fn_prototypes: Vec<FnPrototype>,
+
+    /// The universal origins of the analyzed function, declared with `placeholder 'a, 'b;`.
+    pub placeholders: Vec<Name>,
+
+    /// The outlives relationships known to hold between placeholders, declared with
+    /// `placeholder 'a, 'b where 'a: 'b;`.
+    pub known_placeholder_subsets: Vec<(Name, Name)>,
+
     pub variables: Vec<VariableDecl>,
     pub basic_blocks: Vec<BasicBlock>,
+
+    /// The attributes written on statements (`#[two_phase] x = ..;`), spanning the statement
+    /// they're on. They also apply to the statements lowered from it.
+    pub statement_attributes: Vec<Sp<Attribute>>,
+
+    /// The locations in the Rust source a program was transcribed from, written after
+    /// statements (`x = ..; @ main.rs:42`), spanning the statement they're on. They also apply
+    /// to the statements lowered from it.
+    pub source_locations: Vec<Sp<SourceLocation>>,
+
+    /// The facts written in `facts { .. }` sections, merged verbatim into the emitted facts, to
+    /// express what the emitter doesn't generate yet.
+    pub raw_facts: Vec<Sp<RawFact>>,
+}
+
+/// A fact of a `facts { .. }` section: a global fact (`mark_as_loan_origin('a)`), or a fact at
+/// a node (`bb1[0]: invalidate_origin('a)`), whose node is its last atom.
+#[derive(Clone, Debug)]
+pub struct RawFact {
+    pub node: Option<Name>,
+    pub relation: Name,
+    pub arguments: Vec<Name>,
+}
+
+/// A line of a file of the Rust source a statement was transcribed from, `main.rs:42`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+impl Program {
+    /// The location in the Rust source of the statement at the given span, or of the statement
+    /// it's lowered from.
+    pub fn source_location(&self, span: Span) -> Option<&SourceLocation> {
+        self.source_locations
+            .iter()
+            .find(|candidate| {
+                candidate.span().start() <= span.start() && span.end() <= candidate.span().end()
+            })
+            .map(|location| &**location)
+    }
+
+    /// Whether the statement at the given span has the attribute, or is lowered from a
+    /// statement which has it.
+    pub fn has_attribute(&self, span: Span, attribute: Attribute) -> bool {
+        self.statement_attributes.iter().any(|candidate| {
+            **candidate == attribute
+                && candidate.span().start() <= span.start()
+                && span.end() <= candidate.span().end()
+        })
+    }
+
+    /// Whether values of the given type are copied when they're read, instead of moved: shared
+    /// references, raw pointers, scalars, and the structs declared `Copy`.
+    pub fn is_copy(&self, ty: &Ty) -> bool {
+        match ty {
+            Ty::Ref { .. } | Ty::RawPtr { .. } | Ty::RawPtrMut { .. } | Ty::I32 | Ty::Unit => true,
+            Ty::RefMut { .. } => false,
+            Ty::Array { ty, .. } => self.is_copy(ty),
+            Ty::Struct { name, .. } => self
+                .struct_decls
+                .iter()
+                .any(|decl| &decl.name == name && decl.is_copy),
+            Ty::Infer => unreachable!("Can't know if an uninferred type is `Copy`"),
+        }
+    }
+
+    /// Whether values of the given type need to be dropped: the structs which are not `Copy`,
+    /// as their drop can't be known to do nothing, and the arrays of such structs.
+    pub fn needs_drop(&self, ty: &Ty) -> bool {
+        match ty {
+            Ty::Array { ty, .. } => self.needs_drop(ty),
+            _ => matches!(ty, Ty::Struct { .. }) && !self.is_copy(ty),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -13,20 +119,35 @@ pub struct StructDecl {
     pub name: Name,
     pub generic_decls: Vec<GenericDecl>,
     pub field_decls: Vec<VariableDecl>,
+
+    /// Whether the struct is declared `Copy`, with `#[derive(Copy)]`.
+    pub is_copy: bool,
 }
 
 #[derive(Clone, Debug)]
 pub struct VariableDecl {
     pub name: Name,
     pub ty: Ty,
+
+    /// The span of the variable's name in the input, or of the expression a temporary is
+    /// evaluating.
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct FnPrototype {
     pub name: Name,
+
+    /// The span of the function's name in the input.
+    pub span: Span,
     pub generic_decls: Vec<GenericDecl>,
     pub arg_tys: Vec<Ty>,
     pub ret_ty: Ty,
+
+    /// Whether the function is a method declared in an `impl` block with a `self` receiver,
+    /// which is its first argument. Methods are named after the type they're declared on, like
+    /// `Vec::push`.
+    pub is_method: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -38,8 +159,86 @@ pub enum GenericDecl {
 #[derive(Clone, Debug)]
 pub struct BasicBlock {
     pub name: Name,
+
+    /// The span of the block's label in the input, or of the label of the block it was split
+    /// from by lowering.
+    pub span: Span,
     pub statements: Vec<Sp<Statement>>,
-    pub successors: Vec<Name>,
+    pub terminator: Terminator,
+}
+
+/// How a block ends: the blocks which can be executed next, and the operands read to choose
+/// them. The operands are evaluated by the last statement of the block, which spans the
+/// terminator, so that their accesses are emitted at its node like any statement's: the
+/// terminator records them as they were lowered.
+#[derive(Clone, Debug)]
+pub enum Terminator {
+    /// `goto bb1, bb2;`: any of the blocks can be executed next.
+    Goto(Vec<Name>),
+
+    /// `return;`, or no terminator: the function returns at the end of the block.
+    Return,
+
+    /// `switch(operand) -> [bb1, bb2];`: the operand is read, to choose which of the blocks is
+    /// executed next.
+    Switch {
+        discriminant: Expr,
+        targets: Vec<Name>,
+    },
+
+    /// `place = f(args) -> [return: bb1, unwind: bb2];`: the call returns to the `target` block,
+    /// or unwinds to the `unwind` block when it panics.
+    Call {
+        destination: Option<Place>,
+        call: Expr,
+        target: Name,
+        unwind: Option<Name>,
+    },
+
+    /// `assert(condition) -> [success: bb1, unwind: bb2];`: the condition is read, execution
+    /// continues at the `success` block when it holds, and unwinds to the `unwind` block
+    /// otherwise, like the bounds checks of MIR.
+    Assert {
+        condition: Expr,
+        success: Name,
+        unwind: Option<Name>,
+    },
+
+    /// `unreachable;`: the end of the block is never reached.
+    Unreachable,
+}
+
+impl Terminator {
+    /// The blocks which can be executed next, in order.
+    pub fn successors(&self) -> Vec<Name> {
+        match self {
+            Terminator::Goto(targets) | Terminator::Switch { targets, .. } => targets.clone(),
+            Terminator::Call { target, unwind, .. } => {
+                std::iter::once(*target).chain(*unwind).collect()
+            }
+            Terminator::Assert {
+                success, unwind, ..
+            } => std::iter::once(*success).chain(*unwind).collect(),
+            Terminator::Return | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    /// The block unwound to when the call or the assert panics.
+    pub fn unwind(&self) -> Option<Name> {
+        match self {
+            Terminator::Call { unwind, .. } | Terminator::Assert { unwind, .. } => *unwind,
+            _ => None,
+        }
+    }
+
+    /// Whether the function returns at the end of the block, which has no successors.
+    pub fn is_return(&self) -> bool {
+        match self {
+            Terminator::Return => true,
+            Terminator::Goto(targets) => targets.is_empty(),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -49,13 +248,72 @@ pub enum Statement {
 
     /// A bare expression (`expr;`).
     Expr(Expr),
+
+    /// `end('a);`: the origin is cleared at the statement's node, as if all the references with
+    /// that origin were overwritten. Used to test the rules at a precise node.
+    End(Name),
+
+    /// `kill(place);`: the loans of the place are killed at the statement's node, i.e. their
+    /// origins are cleared. Used to test the rules at a precise node.
+    Kill(Place),
+}
+
+impl Statement {
+    /// The expression the statement evaluates, if it evaluates one.
+    pub fn expr(&self) -> Option<&Expr> {
+        match self {
+            Statement::Assign(_, expr) | Statement::Expr(expr) => Some(expr),
+            Statement::End(_) | Statement::Kill(_) => None,
+        }
+    }
+
+    pub fn expr_mut(&mut self) -> Option<&mut Expr> {
+        match self {
+            Statement::Assign(_, expr) | Statement::Expr(expr) => Some(expr),
+            Statement::End(_) | Statement::Kill(_) => None,
+        }
+    }
+}
+
+/// The attributes which can be written on statements, to change the facts emitted for them
+/// while experimenting with the rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Attribute {
+    /// `#[two_phase]`: the statement's mutable borrows are two-phase borrows, which are only
+    /// reserved by the statement, and don't invalidate the existing loans of their place. Their
+    /// activation, when they're first used, is not modeled.
+    TwoPhase,
+
+    /// `#[no_invalidate]`: the statement invalidates no loans.
+    NoInvalidate,
 }
 
 #[derive(Clone, Debug)]
 pub enum Expr {
-    Access { kind: AccessKind, place: Place },
-    Number { value: i32 },
-    Call { name: Name, arguments: Vec<Expr> },
+    Access {
+        kind: AccessKind,
+        place: Place,
+    },
+    Number {
+        value: i32,
+    },
+    Call {
+        name: Name,
+
+        /// The generic arguments given with a turbofish (`id::<&'a i32>(copy x)`), in the order
+        /// of the function's generic parameters. Its origins can be left out, and are then fresh
+        /// at each call, and so can all its types, which are then inferred from the arguments.
+        generic_args: Vec<Parameter>,
+        arguments: Vec<Expr>,
+    },
+
+    /// A cast (`operand as ty`). Casting a reference to a raw pointer reads the reference, and
+    /// the loans it contains are no longer tracked through the pointer.
+    Cast {
+        operand: Box<Expr>,
+        ty: Ty,
+    },
+
     Unit,
 }
 
@@ -63,12 +321,29 @@ pub enum Expr {
 pub enum AccessKind {
     Copy,
     Move,
+
+    /// A shared borrow, with its origin. Borrows written without an origin (`&x`) have the
+    /// anonymous origin `'_`, which is given a fresh name before emitting facts.
     Borrow(Name),
+
+    /// A mutable borrow, with its origin, which can also be anonymous like shared borrows.
     BorrowMut(Name),
+
+    /// The access of a call argument written without an access kind (`f(x)`), inferred from
+    /// the type of the function's parameter before emitting facts.
+    Infer,
 }
 
+/// The origin of references valid for the entire program, which outlives all the others.
+pub const STATIC_ORIGIN: &str = "'static";
+
+/// The name of the origin of borrows, and reference types, written without one.
+pub const ANONYMOUS_ORIGIN: &str = "'_";
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Ty {
+    /// A shared reference. Its origin can be elided in variable declarations (`&i32`), and is
+    /// then anonymous until it's inferred before emitting facts.
     Ref {
         origin: Name,
         ty: Box<Ty>,
@@ -79,10 +354,30 @@ pub enum Ty {
         ty: Box<Ty>,
     },
 
+    /// A raw pointer (`*const T`), which has no origin: the borrow checker doesn't track what
+    /// it points to.
+    RawPtr {
+        ty: Box<Ty>,
+    },
+
+    RawPtrMut {
+        ty: Box<Ty>,
+    },
+
+    /// An array of `len` elements (`[T; 2]`), which can be indexed.
+    Array {
+        ty: Box<Ty>,
+        len: usize,
+    },
+
     I32,
 
     Unit,
 
+    /// The type of a variable declared without one (`let x;`), which is inferred from the
+    /// variable's first assignment before emitting facts.
+    Infer,
+
     Struct {
         name: Name,
         parameters: Vec<Parameter>,
@@ -90,13 +385,20 @@ pub enum Ty {
 }
 
 impl Ty {
-    /// If this is a reference type, returns the type of the target of that reference.
+    /// If this is a reference or raw pointer type, returns the type of its target.
     pub fn target(&self) -> Option<&Ty> {
         match self {
-            Self::Ref { ty, .. } | Self::RefMut { ty, .. } => Some(&*ty),
+            Self::Ref { ty, .. }
+            | Self::RefMut { ty, .. }
+            | Self::RawPtr { ty }
+            | Self::RawPtrMut { ty } => Some(&*ty),
             _ => None,
         }
     }
+
+    pub fn is_raw_ptr(&self) -> bool {
+        matches!(self, Self::RawPtr { .. } | Self::RawPtrMut { .. })
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -105,13 +407,17 @@ pub enum Parameter {
     Ty(Ty),
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Projection {
     Field(Name),
     Deref,
+
+    /// An index into an array (`a[i]`), by a variable or by a constant (`a[0]`). The
+    /// variable is read when the place is used.
+    Index(Name),
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Place {
     pub base: Name,
 
@@ -121,4 +427,83 @@ pub struct Place {
     pub projections: Vec<Projection>,
 }
 
-pub type Name = String;
+impl Place {
+    /// The move path of this place: its base, and the fields projected before any deref or
+    /// index. For example, the move path of `(*x.f).g` is `x.f`, as moving out of a reference is
+    /// not possible, and neither is moving out of an array by index.
+    pub fn move_path(&self) -> String {
+        let mut path = self.base.to_string();
+        for projection in &self.projections {
+            match projection {
+                Projection::Field(field) => path = format!("{}.{}", path, field),
+                Projection::Deref | Projection::Index(_) => break,
+            }
+        }
+        path
+    }
+
+    pub fn has_deref(&self) -> bool {
+        self.projections.contains(&Projection::Deref)
+    }
+
+    /// Whether this place contains the other place: they have the same base, and the projections
+    /// of this place are the first ones of the other place. Places are prefixes of themselves.
+    pub fn is_prefix_of(&self, other: &Place) -> bool {
+        self.base == other.base && other.projections.starts_with(&self.projections)
+    }
+
+    /// Whether the two places can refer to the same memory: when one contains the other, or when
+    /// they index the same array. Different fields of a struct don't overlap, but all the indices
+    /// of an array do, even different constants, like in rustc's borrow checker.
+    ///
+    /// Only the paths of the places are compared, like in the rest of the analysis: places
+    /// dereferencing different references are disjoint, even though the references could point
+    /// to the same memory.
+    pub fn overlaps(&self, other: &Place) -> bool {
+        if self.base != other.base {
+            return false;
+        }
+        for pair in self.projections.iter().zip(&other.projections) {
+            if let (Projection::Field(field1), Projection::Field(field2)) = pair {
+                if field1 != field2 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// The variables indexing arrays in this place, which are read when the place is used.
+    pub fn index_variables(&self) -> impl Iterator<Item = &Name> {
+        self.projections
+            .iter()
+            .filter_map(|projection| match projection {
+                Projection::Index(index) if !index.bytes().all(|b| b.is_ascii_digit()) => {
+                    Some(index)
+                }
+                _ => None,
+            })
+    }
+}
+
+impl fmt::Display for Place {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Projections of a deref need parentheses: `(*x).f`, unlike the deref of a projection
+        let mut text = self.base.to_string();
+        let mut is_deref = false;
+        for projection in &self.projections {
+            if is_deref && *projection != Projection::Deref {
+                text = format!("({})", text);
+            }
+            text = match projection {
+                Projection::Deref => format!("*{}", text),
+                Projection::Field(field) => format!("{}.{}", text, field),
+                Projection::Index(index) => format!("{}[{}]", text, index),
+            };
+            is_deref = *projection == Projection::Deref;
+        }
+        write!(f, "{}", text)
+    }
+}
+
+pub type Name = Symbol;
@@ -0,0 +1,245 @@
+//! Rewriting of the AST: a `Folder` takes each node of the tree by value, and returns the node
+//! replacing it. Its methods rebuild the node from its folded children by default, with the
+//! `noop_fold_*` function of the same name, so that a pass only overrides the methods of the
+//! nodes it rewrites, and passes compose by folding the program one after the other.
+//!
+//! Statements are folded into a list of statements, so that a pass can remove a statement, or
+//! introduce new ones around it, e.g. the temporaries it evaluates. The statements returned by
+//! the default methods keep the span of the statement they're folded from, and so should the
+//! statements a pass introduces, so that they're reported at the statement they come from.
+//!
+//! Like with `Visitor`, the operands of terminators are folded twice: as the block's last
+//! statement's, and as the terminator's.
+
+use super::*;
+
+#[cfg(test)]
+mod test;
+
+pub trait Folder {
+    fn fold_program(&mut self, program: Program) -> Program {
+        noop_fold_program(self, program)
+    }
+
+    fn fold_basic_block(&mut self, bb: BasicBlock) -> BasicBlock {
+        noop_fold_basic_block(self, bb)
+    }
+
+    /// The statements replacing the statement, in order.
+    fn fold_statement(&mut self, statement: Sp<Statement>) -> Vec<Sp<Statement>> {
+        noop_fold_statement(self, statement)
+    }
+
+    fn fold_terminator(&mut self, terminator: Terminator) -> Terminator {
+        noop_fold_terminator(self, terminator)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        noop_fold_expr(self, expr)
+    }
+
+    fn fold_place(&mut self, place: Place) -> Place {
+        place
+    }
+
+    fn fold_ty(&mut self, ty: Ty) -> Ty {
+        noop_fold_ty(self, ty)
+    }
+
+    /// An origin, of a type, a generic argument, a borrow, or of `end('a)`.
+    fn fold_origin(&mut self, origin: Name) -> Name {
+        origin
+    }
+}
+
+/// Folds the types of the declarations, and the blocks, in program order.
+pub fn noop_fold_program<F: Folder + ?Sized>(folder: &mut F, program: Program) -> Program {
+    let struct_decls = program
+        .struct_decls
+        .into_iter()
+        .map(|decl| StructDecl {
+            field_decls: decl
+                .field_decls
+                .into_iter()
+                .map(|field| VariableDecl {
+                    ty: folder.fold_ty(field.ty),
+                    ..field
+                })
+                .collect(),
+            ..decl
+        })
+        .collect();
+    let fn_prototypes = program
+        .fn_prototypes
+        .into_iter()
+        .map(|prototype| FnPrototype {
+            arg_tys: prototype
+                .arg_tys
+                .into_iter()
+                .map(|ty| folder.fold_ty(ty))
+                .collect(),
+            ret_ty: folder.fold_ty(prototype.ret_ty),
+            ..prototype
+        })
+        .collect();
+    let variables = program
+        .variables
+        .into_iter()
+        .map(|variable| VariableDecl {
+            ty: folder.fold_ty(variable.ty),
+            ..variable
+        })
+        .collect();
+    let basic_blocks = program
+        .basic_blocks
+        .into_iter()
+        .map(|bb| folder.fold_basic_block(bb))
+        .collect();
+    Program {
+        struct_decls,
+        fn_prototypes,
+        variables,
+        basic_blocks,
+        ..program
+    }
+}
+
+pub fn noop_fold_basic_block<F: Folder + ?Sized>(folder: &mut F, bb: BasicBlock) -> BasicBlock {
+    let statements = bb
+        .statements
+        .into_iter()
+        .flat_map(|statement| folder.fold_statement(statement))
+        .collect();
+    BasicBlock {
+        statements,
+        terminator: folder.fold_terminator(bb.terminator),
+        ..bb
+    }
+}
+
+/// The statement with its children folded, at the same span.
+pub fn noop_fold_statement<F: Folder + ?Sized>(
+    folder: &mut F,
+    statement: Sp<Statement>,
+) -> Vec<Sp<Statement>> {
+    let statement = Sp::map(statement, |statement| match statement {
+        Statement::Assign(place, expr) => {
+            Statement::Assign(folder.fold_place(place), folder.fold_expr(expr))
+        }
+        Statement::Expr(expr) => Statement::Expr(folder.fold_expr(expr)),
+        Statement::End(origin) => Statement::End(folder.fold_origin(origin)),
+        Statement::Kill(place) => Statement::Kill(folder.fold_place(place)),
+    });
+    vec![statement]
+}
+
+pub fn noop_fold_terminator<F: Folder + ?Sized>(
+    folder: &mut F,
+    terminator: Terminator,
+) -> Terminator {
+    match terminator {
+        Terminator::Switch {
+            discriminant,
+            targets,
+        } => Terminator::Switch {
+            discriminant: folder.fold_expr(discriminant),
+            targets,
+        },
+        Terminator::Call {
+            destination,
+            call,
+            target,
+            unwind,
+        } => Terminator::Call {
+            destination: destination.map(|place| folder.fold_place(place)),
+            call: folder.fold_expr(call),
+            target,
+            unwind,
+        },
+        Terminator::Assert {
+            condition,
+            success,
+            unwind,
+        } => Terminator::Assert {
+            condition: folder.fold_expr(condition),
+            success,
+            unwind,
+        },
+        Terminator::Goto(_) | Terminator::Return | Terminator::Unreachable => terminator,
+    }
+}
+
+pub fn noop_fold_expr<F: Folder + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    match expr {
+        Expr::Access { kind, place } => {
+            let kind = match kind {
+                AccessKind::Borrow(origin) => AccessKind::Borrow(folder.fold_origin(origin)),
+                AccessKind::BorrowMut(origin) => AccessKind::BorrowMut(folder.fold_origin(origin)),
+                AccessKind::Copy | AccessKind::Move | AccessKind::Infer => kind,
+            };
+            Expr::Access {
+                kind,
+                place: folder.fold_place(place),
+            }
+        }
+        Expr::Call {
+            name,
+            generic_args,
+            arguments,
+        } => Expr::Call {
+            name,
+            generic_args: fold_parameters(folder, generic_args),
+            arguments: arguments
+                .into_iter()
+                .map(|argument| folder.fold_expr(argument))
+                .collect(),
+        },
+        Expr::Cast { operand, ty } => Expr::Cast {
+            operand: Box::new(folder.fold_expr(*operand)),
+            ty: folder.fold_ty(ty),
+        },
+        Expr::Number { .. } | Expr::Unit => expr,
+    }
+}
+
+pub fn noop_fold_ty<F: Folder + ?Sized>(folder: &mut F, ty: Ty) -> Ty {
+    let fold_boxed = |folder: &mut F, ty: Box<Ty>| Box::new(folder.fold_ty(*ty));
+    match ty {
+        Ty::Ref { origin, ty } => Ty::Ref {
+            origin: folder.fold_origin(origin),
+            ty: fold_boxed(folder, ty),
+        },
+        Ty::RefMut { origin, ty } => Ty::RefMut {
+            origin: folder.fold_origin(origin),
+            ty: fold_boxed(folder, ty),
+        },
+        Ty::RawPtr { ty } => Ty::RawPtr {
+            ty: fold_boxed(folder, ty),
+        },
+        Ty::RawPtrMut { ty } => Ty::RawPtrMut {
+            ty: fold_boxed(folder, ty),
+        },
+        Ty::Array { ty, len } => Ty::Array {
+            ty: fold_boxed(folder, ty),
+            len,
+        },
+        Ty::Struct { name, parameters } => Ty::Struct {
+            name,
+            parameters: fold_parameters(folder, parameters),
+        },
+        Ty::I32 | Ty::Unit | Ty::Infer => ty,
+    }
+}
+
+fn fold_parameters<F: Folder + ?Sized>(
+    folder: &mut F,
+    parameters: Vec<Parameter>,
+) -> Vec<Parameter> {
+    parameters
+        .into_iter()
+        .map(|parameter| match parameter {
+            Parameter::Origin(origin) => Parameter::Origin(folder.fold_origin(origin)),
+            Parameter::Ty(ty) => Parameter::Ty(folder.fold_ty(ty)),
+        })
+        .collect()
+}
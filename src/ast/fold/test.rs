@@ -0,0 +1,79 @@
+use super::*;
+use crate::ast_parser::test::expect_parse;
+
+// Kills the assigned places before their assignments, and renames an origin.
+struct KillAndRename {
+    from: Name,
+    to: Name,
+}
+
+impl Folder for KillAndRename {
+    fn fold_statement(&mut self, statement: Sp<Statement>) -> Vec<Sp<Statement>> {
+        let span = statement.span();
+        let mut statements = Vec::new();
+        if let Statement::Assign(place, _) = &*statement {
+            statements.push(Sp::new(Statement::Kill(place.clone()), span));
+        }
+        statements.extend(noop_fold_statement(self, statement));
+        statements
+    }
+
+    fn fold_origin(&mut self, origin: Name) -> Name {
+        if origin == self.from {
+            self.to
+        } else {
+            origin
+        }
+    }
+}
+
+#[test]
+fn rewrites_statements_at_their_spans() {
+    let program = expect_parse(
+        "
+        let x: i32;
+        let r: &'a i32;
+        bb0: {
+            r = &'a x;
+            end('a);
+        }
+    ",
+    );
+    let mut folder = KillAndRename {
+        from: "'a".into(),
+        to: "'b".into(),
+    };
+    let program = folder.fold_program(program);
+
+    assert!(matches!(program.variables[1].ty, Ty::Ref { origin, .. } if origin == "'b"));
+    let statements: Vec<_> = program.basic_blocks[0]
+        .statements
+        .iter()
+        .map(|s| (format!("{:?}", **s), s.span()))
+        .collect();
+    insta::assert_debug_snapshot!(statements, @r###"
+    [
+        (
+            "Kill(Place { base: \"r\", projections: [] })",
+            Span {
+                start: 72,
+                end: 82,
+            },
+        ),
+        (
+            "Assign(Place { base: \"r\", projections: [] }, Access { kind: Borrow(\"'b\"), place: Place { base: \"x\", projections: [] } })",
+            Span {
+                start: 72,
+                end: 82,
+            },
+        ),
+        (
+            "End(\"'b\")",
+            Span {
+                start: 95,
+                end: 103,
+            },
+        ),
+    ]
+    "###);
+}
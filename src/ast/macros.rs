@@ -0,0 +1,358 @@
+//! `program!`, which builds the AST of a program in Rust, for tests which need a `Program`
+//! without going through its text: its syntax is the one of the parser's, for the subset of
+//! programs which are already lowered, like the AST:
+//!
+//! ```ignore
+//! program! {
+//!     struct S<'s> { f: &'s i32 }
+//!     fn f<'a>(x: &'a i32) -> &'a i32;
+//!     let x: i32;
+//!     let r: &'r i32;
+//!     bb0: {
+//!         r = &'L x;
+//!         r = f(move r);
+//!         end('r);
+//!         goto bb1;
+//!     }
+//!     bb1: { }
+//! }
+//! ```
+//!
+//! - structs and functions are only generic over origins, and the origins of the types of
+//!   functions are not elided, they're used as written.
+//! - places are variables with fields and derefs (`*x.f`), and expressions are accesses of
+//!   places, numbers, `()`, and calls. Like in the lowered AST, the arguments of calls are read
+//!   or moved, or are places without an access kind: borrows and calls are evaluated into
+//!   temporaries beforehand.
+//! - the terminators are `goto`, `switch`, `return`, and `unreachable`. Unlike in the parser, the
+//!   discriminant of a `switch` is not also read by a statement before it.
+//!
+//! Nodes are spanned with dummy spans. The macro parses statements and types a token at a time,
+//! so the programs it builds are small, within the recursion limit of macros.
+
+#[cfg(test)]
+mod test;
+
+macro_rules! program {
+    // Declarations and blocks
+    (@items $p:ident) => {};
+    (@items $p:ident struct $s:ident $(< $($g:lifetime),* >)? { $($fields:tt)* } $($rest:tt)*) => {
+        let field_decls: Vec<(&str, $crate::ast::Ty)> =
+            $crate::ast::program!(@fields [] [] $($fields)*);
+        $p.struct_decls.push($crate::ast::StructDecl {
+            name: stringify!($s).into(),
+            generic_decls: vec![$($($crate::ast::GenericDecl::Origin(stringify!($g).into())),*)?],
+            field_decls: field_decls
+                .into_iter()
+                .map(|(name, ty)| $crate::ast::VariableDecl {
+                    name: name.into(),
+                    ty,
+                    span: $crate::span::Span::dummy(),
+                })
+                .collect(),
+            is_copy: false,
+        });
+        $crate::ast::program!(@items $p $($rest)*);
+    };
+    (@items $p:ident fn $f:ident $(< $($g:lifetime),* >)? ( $($args:tt)* ) -> $($rest:tt)*) => {
+        $crate::ast::program!(@fn $p $f [$($($g),*)?] [$($args)*] [] $($rest)*);
+    };
+    (@items $p:ident let $v:ident ; $($rest:tt)*) => {
+        $crate::ast::program!(@let $p $v [_] ; $($rest)*);
+    };
+    (@items $p:ident let $v:ident : $($rest:tt)*) => {
+        $crate::ast::program!(@let $p $v [] $($rest)*);
+    };
+    (@items $p:ident $bb:ident : { $($statements:tt)* } $($rest:tt)*) => {
+        $p.basic_blocks.push($crate::ast::BasicBlock {
+            name: stringify!($bb).into(),
+            span: $crate::span::Span::dummy(),
+            statements: Vec::new(),
+            terminator: $crate::ast::Terminator::Return,
+        });
+        $crate::ast::program!(@statements $p [] $($statements)*);
+        $crate::ast::program!(@items $p $($rest)*);
+    };
+
+    // The return type of a function, up to its `;`
+    (@fn $p:ident $f:ident [$($g:lifetime),*] [$($args:tt)*] [$($ret:tt)*] ; $($rest:tt)*) => {
+        let arg_tys: Vec<(&str, $crate::ast::Ty)> =
+            $crate::ast::program!(@fields [] [] $($args)*);
+        $p.fn_prototypes.push($crate::ast::FnPrototype {
+            name: stringify!($f).into(),
+            span: $crate::span::Span::dummy(),
+            generic_decls: vec![$($crate::ast::GenericDecl::Origin(stringify!($g).into())),*],
+            arg_tys: arg_tys.into_iter().map(|(_, ty)| ty).collect(),
+            ret_ty: $crate::ast::program!(@ty $($ret)*),
+            is_method: false,
+        });
+        $crate::ast::program!(@items $p $($rest)*);
+    };
+    (@fn $p:ident $f:ident $g:tt $args:tt [$($ret:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::ast::program!(@fn $p $f $g $args [$($ret)* $t] $($rest)*);
+    };
+
+    // The type of a variable, up to its `;`
+    (@let $p:ident $v:ident [$($ty:tt)*] ; $($rest:tt)*) => {
+        $p.variables.push($crate::ast::VariableDecl {
+            name: stringify!($v).into(),
+            ty: $crate::ast::program!(@ty $($ty)*),
+            span: $crate::span::Span::dummy(),
+        });
+        $crate::ast::program!(@items $p $($rest)*);
+    };
+    (@let $p:ident $v:ident [$($ty:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::ast::program!(@let $p $v [$($ty)* $t] $($rest)*);
+    };
+
+    // The `name: ty` fields of a struct, or parameters of a function, separated by commas
+    (@fields [$($done:tt)*] []) => {
+        vec![$($done)*]
+    };
+    (@fields [$($done:tt)*] [$n:ident : $($ty:tt)+]) => {
+        vec![$($done)* (stringify!($n), $crate::ast::program!(@ty $($ty)+))]
+    };
+    (@fields [$($done:tt)*] [$n:ident : $($ty:tt)+] , $($rest:tt)*) => {
+        $crate::ast::program!(
+            @fields [$($done)* (stringify!($n), $crate::ast::program!(@ty $($ty)+)),] [] $($rest)*
+        )
+    };
+    (@fields $done:tt [$($cur:tt)*] $s:ident < $($o:lifetime),* > $($rest:tt)*) => {
+        $crate::ast::program!(@fields $done [$($cur)* $s < $($o),* >] $($rest)*)
+    };
+    (@fields $done:tt [$($cur:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::ast::program!(@fields $done [$($cur)* $t] $($rest)*)
+    };
+
+    // The statements of the last block, separated by semicolons
+    (@statements $p:ident []) => {};
+    (@statements $p:ident [$($statement:tt)*] ; $($rest:tt)*) => {
+        $crate::ast::program!(@statement $p $($statement)*);
+        $crate::ast::program!(@statements $p [] $($rest)*);
+    };
+    (@statements $p:ident [$($statement:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::ast::program!(@statements $p [$($statement)* $t] $($rest)*);
+    };
+
+    (@statement $p:ident goto $($target:ident),*) => {
+        $crate::ast::program!(
+            @terminator $p $crate::ast::Terminator::Goto(vec![$(stringify!($target).into()),*])
+        );
+    };
+    (@statement $p:ident return) => {
+        $crate::ast::program!(@terminator $p $crate::ast::Terminator::Return);
+    };
+    (@statement $p:ident unreachable) => {
+        $crate::ast::program!(@terminator $p $crate::ast::Terminator::Unreachable);
+    };
+    (@statement $p:ident switch ( $($discriminant:tt)+ ) -> [ $($target:ident),* ]) => {
+        $crate::ast::program!(
+            @terminator $p
+            $crate::ast::Terminator::Switch {
+                discriminant: $crate::ast::program!(@expr $($discriminant)+),
+                targets: vec![$(stringify!($target).into()),*],
+            }
+        );
+    };
+    (@statement $p:ident end ( $o:lifetime )) => {
+        $crate::ast::program!(@push $p $crate::ast::Statement::End(stringify!($o).into()));
+    };
+    (@statement $p:ident kill ( $($place:tt)+ )) => {
+        $crate::ast::program!(
+            @push $p $crate::ast::Statement::Kill($crate::ast::program!(@place $($place)+))
+        );
+    };
+    (@statement $p:ident $($base:ident).+ = $($expr:tt)+) => {
+        $crate::ast::program!(
+            @push $p
+            $crate::ast::Statement::Assign(
+                $crate::ast::program!(@place $($base).+),
+                $crate::ast::program!(@expr $($expr)+),
+            )
+        );
+    };
+    (@statement $p:ident * $($base:ident).+ = $($expr:tt)+) => {
+        $crate::ast::program!(
+            @push $p
+            $crate::ast::Statement::Assign(
+                $crate::ast::program!(@place * $($base).+),
+                $crate::ast::program!(@expr $($expr)+),
+            )
+        );
+    };
+    (@statement $p:ident $($expr:tt)+) => {
+        $crate::ast::program!(
+            @push $p $crate::ast::Statement::Expr($crate::ast::program!(@expr $($expr)+))
+        );
+    };
+    (@push $p:ident $statement:expr) => {
+        $p.basic_blocks
+            .last_mut()
+            .unwrap()
+            .statements
+            .push($crate::span::Spanned::new($statement, $crate::span::Span::dummy()));
+    };
+    (@terminator $p:ident $terminator:expr) => {
+        $p.basic_blocks.last_mut().unwrap().terminator = $terminator;
+    };
+
+    (@expr & $o:lifetime mut $($place:tt)+) => {
+        $crate::ast::program!(
+            @access [$crate::ast::AccessKind::BorrowMut(stringify!($o).into())] $($place)+
+        )
+    };
+    (@expr & mut $($place:tt)+) => {
+        $crate::ast::program!(
+            @access [$crate::ast::AccessKind::BorrowMut($crate::ast::ANONYMOUS_ORIGIN.into())]
+            $($place)+
+        )
+    };
+    (@expr & $o:lifetime $($place:tt)+) => {
+        $crate::ast::program!(
+            @access [$crate::ast::AccessKind::Borrow(stringify!($o).into())] $($place)+
+        )
+    };
+    (@expr & $($place:tt)+) => {
+        $crate::ast::program!(
+            @access [$crate::ast::AccessKind::Borrow($crate::ast::ANONYMOUS_ORIGIN.into())]
+            $($place)+
+        )
+    };
+    (@expr copy $($place:tt)+) => {
+        $crate::ast::program!(@access [$crate::ast::AccessKind::Copy] $($place)+)
+    };
+    (@expr move $($place:tt)+) => {
+        $crate::ast::program!(@access [$crate::ast::AccessKind::Move] $($place)+)
+    };
+    (@expr ()) => {
+        $crate::ast::Expr::Unit
+    };
+    (@expr $n:literal) => {
+        $crate::ast::Expr::Number { value: $n }
+    };
+    (@expr $f:ident ( $($arguments:tt)* )) => {
+        $crate::ast::Expr::Call {
+            name: stringify!($f).into(),
+            generic_args: Vec::new(),
+            arguments: $crate::ast::program!(@arguments [] [] $($arguments)*),
+        }
+    };
+    (@access [$kind:expr] $($place:tt)+) => {
+        $crate::ast::Expr::Access {
+            kind: $kind,
+            place: $crate::ast::program!(@place $($place)+),
+        }
+    };
+
+    // The arguments of a call, separated by commas: the places without an access kind are
+    // inferred, like in the parser.
+    (@arguments [$($done:tt)*] []) => {
+        vec![$($done)*]
+    };
+    (@arguments [$($done:tt)*] [$($argument:tt)+]) => {
+        vec![$($done)* $crate::ast::program!(@argument $($argument)+)]
+    };
+    (@arguments [$($done:tt)*] [$($argument:tt)+] , $($rest:tt)*) => {
+        $crate::ast::program!(
+            @arguments [$($done)* $crate::ast::program!(@argument $($argument)+),] [] $($rest)*
+        )
+    };
+    (@arguments $done:tt [$($argument:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::ast::program!(@arguments $done [$($argument)* $t] $($rest)*)
+    };
+    (@argument $($base:ident).+) => {
+        $crate::ast::program!(@access [$crate::ast::AccessKind::Infer] $($base).+)
+    };
+    (@argument * $($place:tt)+) => {
+        $crate::ast::program!(@access [$crate::ast::AccessKind::Infer] * $($place)+)
+    };
+    (@argument $($expr:tt)+) => {
+        $crate::ast::program!(@expr $($expr)+)
+    };
+
+    // Like in the parser, the deref of `*x.f` is applied to `x.f`
+    (@place * $($place:tt)+) => {{
+        let mut place = $crate::ast::program!(@place $($place)+);
+        place.projections.push($crate::ast::Projection::Deref);
+        place
+    }};
+    (@place $base:ident $(. $field:ident)*) => {
+        $crate::ast::Place {
+            base: stringify!($base).into(),
+            projections: vec![$($crate::ast::Projection::Field(stringify!($field).into())),*],
+        }
+    };
+
+    (@ty _) => {
+        $crate::ast::Ty::Infer
+    };
+    (@ty i32) => {
+        $crate::ast::Ty::I32
+    };
+    (@ty ()) => {
+        $crate::ast::Ty::Unit
+    };
+    (@ty & $o:lifetime mut $($ty:tt)+) => {
+        $crate::ast::Ty::RefMut {
+            origin: stringify!($o).into(),
+            ty: Box::new($crate::ast::program!(@ty $($ty)+)),
+        }
+    };
+    (@ty & mut $($ty:tt)+) => {
+        $crate::ast::Ty::RefMut {
+            origin: $crate::ast::ANONYMOUS_ORIGIN.into(),
+            ty: Box::new($crate::ast::program!(@ty $($ty)+)),
+        }
+    };
+    (@ty & $o:lifetime $($ty:tt)+) => {
+        $crate::ast::Ty::Ref {
+            origin: stringify!($o).into(),
+            ty: Box::new($crate::ast::program!(@ty $($ty)+)),
+        }
+    };
+    (@ty & $($ty:tt)+) => {
+        $crate::ast::Ty::Ref {
+            origin: $crate::ast::ANONYMOUS_ORIGIN.into(),
+            ty: Box::new($crate::ast::program!(@ty $($ty)+)),
+        }
+    };
+    (@ty * const $($ty:tt)+) => {
+        $crate::ast::Ty::RawPtr {
+            ty: Box::new($crate::ast::program!(@ty $($ty)+)),
+        }
+    };
+    (@ty * mut $($ty:tt)+) => {
+        $crate::ast::Ty::RawPtrMut {
+            ty: Box::new($crate::ast::program!(@ty $($ty)+)),
+        }
+    };
+    (@ty $s:ident $(< $($o:lifetime),* >)?) => {
+        $crate::ast::Ty::Struct {
+            name: stringify!($s).into(),
+            parameters: vec![$($($crate::ast::Parameter::Origin(stringify!($o).into())),*)?],
+        }
+    };
+
+    (@ $($tokens:tt)*) => {
+        compile_error!(concat!("unsupported syntax: ", stringify!($($tokens)*)))
+    };
+
+    ($($items:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut program = $crate::ast::Program {
+            struct_decls: Vec::new(),
+            fn_prototypes: Vec::new(),
+            placeholders: Vec::new(),
+            known_placeholder_subsets: Vec::new(),
+            variables: Vec::new(),
+            basic_blocks: Vec::new(),
+            statement_attributes: Vec::new(),
+            source_locations: Vec::new(),
+            raw_facts: Vec::new(),
+        };
+        $crate::ast::program!(@items program $($items)*);
+        program
+    }};
+}
+
+pub(crate) use program;
@@ -0,0 +1,57 @@
+use crate::ast::*;
+use crate::ast_parser::test::{expect_parse, without_spans};
+
+#[test]
+fn builds_the_parsed_program() {
+    let built = program! {
+        struct S<'s> { f: &'s i32, g: S<'s> }
+        fn f<'a, 'b>(x: &'a i32, y: S<'b>) -> &'a i32;
+        fn g() -> ();
+        let x: i32;
+        let r: &'r mut i32;
+        let s: S<'s>;
+        let v;
+        bb0: {
+            r = &'L mut x;
+            *r = 4;
+            v = f(x, move s);
+            end('r);
+            goto bb1, bb2;
+        }
+        bb1: {
+            kill(*s.f);
+            g();
+            unreachable;
+        }
+        bb2: {
+            s.f = copy *r;
+        }
+    };
+    let parsed = expect_parse(
+        "
+        struct S<'s> { f: &'s i32, g: S<'s> }
+        fn f<'a, 'b>(x: &'a i32, y: S<'b>) -> &'a i32;
+        fn g() -> ();
+        let x: i32;
+        let r: &'r mut i32;
+        let s: S<'s>;
+        let v;
+        bb0: {
+            r = &'L mut x;
+            *r = 4;
+            v = f(x, move s);
+            end('r);
+            goto bb1, bb2;
+        }
+        bb1: {
+            kill(*s.f);
+            g();
+            unreachable;
+        }
+        bb2: {
+            s.f = copy *r;
+        }
+    ",
+    );
+    assert_eq!(without_spans(built), without_spans(parsed));
+}
@@ -0,0 +1,282 @@
+//! Printing of the AST in the syntax it's parsed from: the printed program is the lowered
+//! program, whose temporaries are declared and assigned like variables, and parsing it gives
+//! back the same program, up to spans.
+//!
+//! The operands of terminators are printed as the terminator's, rather than as the block's last
+//! statement evaluating them, which parsing recreates. The attributes and locations of statements
+//! are printed on each statement lowered from the statement they were written on.
+
+use super::*;
+
+#[cfg(test)]
+mod test;
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for decl in &self.struct_decls {
+            if decl.is_copy {
+                write!(f, "#[derive(Copy)] ")?;
+            }
+            write!(f, "struct {}", decl.name)?;
+            write_generic_decls(f, &decl.generic_decls)?;
+            let fields = decl.field_decls.iter().map(field_text);
+            writeln!(f, " {{ {} }}", comma_separated(fields))?;
+        }
+
+        for prototype in &self.fn_prototypes {
+            write_prototype(f, prototype)?;
+        }
+
+        if !self.placeholders.is_empty() {
+            write!(f, "placeholder {}", comma_separated(&self.placeholders))?;
+            if !self.known_placeholder_subsets.is_empty() {
+                let subsets = self
+                    .known_placeholder_subsets
+                    .iter()
+                    .map(|(o1, o2)| format!("{}: {}", o1, o2));
+                write!(f, " where {}", comma_separated(subsets))?;
+            }
+            writeln!(f, ";")?;
+        }
+
+        for variable in &self.variables {
+            match variable.ty {
+                Ty::Infer => writeln!(f, "let {};", variable.name)?,
+                _ => writeln!(f, "let {};", field_text(variable))?,
+            }
+        }
+
+        for bb in &self.basic_blocks {
+            writeln!(f, "{}: {{", bb.name)?;
+            let statements = match bb.terminator {
+                Terminator::Switch { .. } | Terminator::Call { .. } | Terminator::Assert { .. } => {
+                    &bb.statements[..bb.statements.len() - 1]
+                }
+                _ => &bb.statements[..],
+            };
+            for s in statements {
+                write!(f, "    ")?;
+                if self.has_attribute(s.span(), Attribute::TwoPhase) {
+                    write!(f, "#[two_phase] ")?;
+                }
+                if self.has_attribute(s.span(), Attribute::NoInvalidate) {
+                    write!(f, "#[no_invalidate] ")?;
+                }
+                write!(f, "{};", **s)?;
+                if let Some(location) = self.source_location(s.span()) {
+                    write!(f, " @ {}", location)?;
+                }
+                writeln!(f)?;
+            }
+            if !bb.terminator.is_return() {
+                writeln!(f, "    {};", bb.terminator)?;
+            }
+            writeln!(f, "}}")?;
+        }
+
+        if !self.raw_facts.is_empty() {
+            writeln!(f, "facts {{")?;
+            for fact in &self.raw_facts {
+                write!(f, "    ")?;
+                if let Some(node) = fact.node {
+                    write!(f, "{}: ", node)?;
+                }
+                writeln!(f, "{}({})", fact.relation, comma_separated(&fact.arguments))?;
+            }
+            writeln!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+// The functions declared in `impl` blocks are printed in a block of their own, on the type of
+// their receiver, with all their generic parameters.
+fn write_prototype(f: &mut fmt::Formatter<'_>, prototype: &FnPrototype) -> fmt::Result {
+    let (impl_name, name) = match prototype.name.split_once("::") {
+        Some((impl_name, name)) => (Some(impl_name), name),
+        None => (None, &*prototype.name),
+    };
+    let mut arguments: Vec<_> = prototype
+        .arg_tys
+        .iter()
+        .enumerate()
+        .map(|(idx, ty)| format!("x{}: {}", idx, ty))
+        .collect();
+    if let Some(impl_name) = impl_name {
+        let receiver = prototype.arg_tys.first().filter(|_| prototype.is_method);
+        let self_ty = match receiver {
+            Some(Ty::Ref { origin, ty }) => {
+                arguments[0] = format!("&{} self", origin);
+                &**ty
+            }
+            Some(Ty::RefMut { origin, ty }) => {
+                arguments[0] = format!("&{} mut self", origin);
+                &**ty
+            }
+            Some(ty) => {
+                arguments[0] = "self".to_string();
+                ty
+            }
+            None => {
+                write!(f, "impl {} {{ ", impl_name)?;
+                return write_fn(f, prototype, name, &arguments).and_then(|_| writeln!(f, " }}"));
+            }
+        };
+        write!(f, "impl {} {{ ", self_ty)?;
+        write_fn(f, prototype, name, &arguments)?;
+        writeln!(f, " }}")
+    } else {
+        write_fn(f, prototype, name, &arguments)?;
+        writeln!(f)
+    }
+}
+
+fn write_fn(
+    f: &mut fmt::Formatter<'_>,
+    prototype: &FnPrototype,
+    name: &str,
+    arguments: &[String],
+) -> fmt::Result {
+    write!(f, "fn {}", name)?;
+    write_generic_decls(f, &prototype.generic_decls)?;
+    write!(
+        f,
+        "({}) -> {};",
+        comma_separated(arguments),
+        prototype.ret_ty
+    )
+}
+
+fn write_generic_decls(f: &mut fmt::Formatter<'_>, decls: &[GenericDecl]) -> fmt::Result {
+    if decls.is_empty() {
+        return Ok(());
+    }
+    let decls = decls.iter().map(|decl| match decl {
+        GenericDecl::Origin(name) | GenericDecl::Ty(name) => name,
+    });
+    write!(f, "<{}>", comma_separated(decls))
+}
+
+fn field_text(field: &VariableDecl) -> String {
+    format!("{}: {}", field.name, field.ty)
+}
+
+fn comma_separated<T: fmt::Display>(items: impl IntoIterator<Item = T>) -> String {
+    let items: Vec<_> = items.into_iter().map(|item| item.to_string()).collect();
+    items.join(", ")
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::Assign(place, expr) => write!(f, "{} = {}", place, expr),
+            Statement::Expr(expr) => write!(f, "{}", expr),
+            Statement::End(origin) => write!(f, "end({})", origin),
+            Statement::Kill(place) => write!(f, "kill({})", place),
+        }
+    }
+}
+
+impl fmt::Display for Terminator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unwind_text = |unwind: &Option<Name>| match unwind {
+            Some(unwind) => format!(", unwind: {}", unwind),
+            None => String::new(),
+        };
+        match self {
+            Terminator::Goto(targets) => write!(f, "goto {}", comma_separated(targets)),
+            Terminator::Return => write!(f, "return"),
+            Terminator::Switch {
+                discriminant,
+                targets,
+            } => write!(
+                f,
+                "switch({}) -> [{}]",
+                discriminant,
+                comma_separated(targets)
+            ),
+            Terminator::Call {
+                destination,
+                call,
+                target,
+                unwind,
+            } => {
+                if let Some(destination) = destination {
+                    write!(f, "{} = ", destination)?;
+                }
+                write!(f, "{} -> [return: {}{}]", call, target, unwind_text(unwind))
+            }
+            Terminator::Assert {
+                condition,
+                success,
+                unwind,
+            } => write!(
+                f,
+                "assert({}) -> [success: {}{}]",
+                condition,
+                success,
+                unwind_text(unwind)
+            ),
+            Terminator::Unreachable => write!(f, "unreachable"),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Access { kind, place } => match kind {
+                AccessKind::Copy => write!(f, "copy {}", place),
+                AccessKind::Move => write!(f, "move {}", place),
+                AccessKind::Borrow(origin) => write!(f, "&{} {}", origin, place),
+                AccessKind::BorrowMut(origin) => write!(f, "&{} mut {}", origin, place),
+                AccessKind::Infer => write!(f, "{}", place),
+            },
+            Expr::Number { value } => write!(f, "{}", value),
+            Expr::Call {
+                name,
+                generic_args,
+                arguments,
+            } => {
+                write!(f, "{}", name)?;
+                if !generic_args.is_empty() {
+                    write!(f, "::<{}>", comma_separated(generic_args))?;
+                }
+                write!(f, "({})", comma_separated(arguments))
+            }
+            Expr::Cast { operand, ty } => write!(f, "{} as {}", operand, ty),
+            Expr::Unit => write!(f, "()"),
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Ref { origin, ty } => write!(f, "&{} {}", origin, ty),
+            Ty::RefMut { origin, ty } => write!(f, "&{} mut {}", origin, ty),
+            Ty::RawPtr { ty } => write!(f, "*const {}", ty),
+            Ty::RawPtrMut { ty } => write!(f, "*mut {}", ty),
+            Ty::Array { ty, len } => write!(f, "[{}; {}]", ty, len),
+            Ty::I32 => write!(f, "i32"),
+            Ty::Unit => write!(f, "()"),
+            Ty::Infer => write!(f, "_"),
+            Ty::Struct { name, parameters } => {
+                write!(f, "{}", name)?;
+                if !parameters.is_empty() {
+                    write!(f, "<{}>", comma_separated(parameters))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Parameter::Origin(origin) => write!(f, "{}", origin),
+            Parameter::Ty(ty) => write!(f, "{}", ty),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use super::*;
+use crate::ast_parser::test::{expect_parse, without_spans};
+use crate::convert::facts_to_json;
+use crate::fact_emitter::emit_facts;
+use crate::fuzz::FuzzOptions;
+use crate::stress::{generate_stress_program, StressOptions};
+
+#[test]
+fn prints_the_lowered_program() {
+    let input = "
+        #[derive(Copy)] struct P<'p> { x: &'p i32 }
+        struct V<T> { items: [T; 2] }
+        fn f<'a>(x: &'a i32, y: V<i32>) -> &'a i32;
+        impl<T> V<T> { fn push(&mut self, item: T) -> (); fn new() -> V<T>; }
+        placeholder 'a, 'b where 'a: 'b;
+        let x: i32;
+        let r: &'a i32;
+        let v: V<i32>;
+        bb0: {
+            let p = &x as *const i32;
+            #[two_phase] v.push(copy x); @ main.rs:4
+            while copy x { end('a); }
+            r = f(&x, move v) -> [return: bb1, unwind: bb2];
+        }
+        bb1: { kill(v.items[0]); switch(copy x) -> [bb2]; }
+        bb2: { }
+        facts { bb2[0]: access_origin('a) mark_as_loan_origin('a) }
+    ";
+    assert_round_trips(input);
+    insta::assert_display_snapshot!(expect_parse(input), @r###"
+    #[derive(Copy)] struct P<'p> { x: &'p i32 }
+    struct V<T> { items: [T; 2] }
+    fn f<'a>(x0: &'a i32, x1: V<i32>) -> &'a i32;
+    impl V<T> { fn push<T, '_0>(&'_0 mut self, x1: T) -> (); }
+    impl V { fn new<T>() -> V<T>; }
+    placeholder 'a, 'b where 'a: 'b;
+    let x: i32;
+    let r: &'a i32;
+    let v: V<i32>;
+    let p;
+    let _tmp0;
+    let _tmp1;
+    let _tmp2;
+    bb0: {
+        _tmp0 = &'_ x;
+        p = move _tmp0 as *const i32;
+        #[two_phase] _tmp1 = &'_ mut v; @ main.rs:4
+        #[two_phase] V::push(move _tmp1, copy x); @ main.rs:4
+        goto bb0_1;
+    }
+    bb0_1: {
+        copy x;
+        goto bb0_2, bb0_3;
+    }
+    bb0_2: {
+        end('a);
+        goto bb0_1;
+    }
+    bb0_3: {
+        _tmp2 = &'_ x;
+        r = f(move _tmp2, move v) -> [return: bb1, unwind: bb2];
+    }
+    bb1: {
+        kill(v.items[0]);
+        switch(copy x) -> [bb2];
+    }
+    bb2: {
+    }
+    facts {
+        bb2[0]: access_origin('a)
+        mark_as_loan_origin('a)
+    }
+    "###);
+}
+
+// Prints the parsed program, and parses it again: it must be the same program, which emits the
+// same facts, and which prints the same.
+fn assert_round_trips(input: &str) {
+    let program = expect_parse(input);
+    let printed = program.to_string();
+    let reparsed = expect_parse(&printed);
+    assert_eq!(printed, reparsed.to_string(), "the printed program changed");
+
+    // The attributes and locations are printed on each statement they apply to, instead of the
+    // statement they were written on
+    let without_annotations = |mut program: Program| {
+        program.statement_attributes.clear();
+        program.source_locations.clear();
+        without_spans(program)
+    };
+    assert_eq!(
+        without_annotations(program),
+        without_annotations(reparsed),
+        "the program changed when printed as:\n{}",
+        printed
+    );
+
+    // The facts are the same, but for the text, spans and locations of their nodes
+    let facts_json = |input: &str| {
+        let mut facts = emit_facts(input).unwrap();
+        facts.node_text.clear();
+        facts.node_span.clear();
+        facts.node_location.clear();
+        facts_to_json(&facts).to_string()
+    };
+    assert_eq!(facts_json(input), facts_json(&printed));
+}
+
+#[test]
+fn examples_round_trip() {
+    for entry in std::fs::read_dir("examples").unwrap() {
+        let input = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        assert_round_trips(&input);
+    }
+}
+
+#[test]
+fn generated_programs_round_trip() {
+    let shape = FuzzOptions::default().program;
+    for seed in 0..50 {
+        let program = generate_stress_program(&StressOptions {
+            seed,
+            ..shape.clone()
+        });
+        assert_round_trips(&program);
+    }
+}
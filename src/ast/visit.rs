@@ -0,0 +1,156 @@
+//! Traversal of the AST: a `Visitor` is called on each node of the tree, and its methods walk
+//! the node's children by default, with the `walk_*` function of the same name. Overriding a
+//! method changes what's done at that kind of node, and it calls the `walk_*` function to keep
+//! walking its children.
+//!
+//! The operands of terminators are also evaluated by the last statement of their block, see
+//! `Terminator`: walking a block visits them twice, as the statement's and as the terminator's.
+
+use super::*;
+
+#[cfg(test)]
+mod test;
+
+pub trait Visitor<'ast> {
+    fn visit_program(&mut self, program: &'ast Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_basic_block(&mut self, bb: &'ast BasicBlock) {
+        walk_basic_block(self, bb);
+    }
+
+    fn visit_statement(&mut self, statement: &'ast Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_terminator(&mut self, terminator: &'ast Terminator) {
+        walk_terminator(self, terminator);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        walk_expr(self, expr);
+    }
+
+    /// A place, read, written, or borrowed. Its base and projections are names, which are not
+    /// visited.
+    fn visit_place(&mut self, _place: &'ast Place) {}
+
+    fn visit_ty(&mut self, ty: &'ast Ty) {
+        walk_ty(self, ty);
+    }
+
+    /// An origin, of a type, a generic argument, a borrow, or of `end('a)`.
+    fn visit_origin(&mut self, _origin: &'ast Name) {}
+}
+
+/// Visits the types of the declarations, and the blocks, in program order.
+pub fn walk_program<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, program: &'ast Program) {
+    for decl in &program.struct_decls {
+        for field in &decl.field_decls {
+            visitor.visit_ty(&field.ty);
+        }
+    }
+    for prototype in &program.fn_prototypes {
+        for ty in prototype.arg_tys.iter().chain([&prototype.ret_ty]) {
+            visitor.visit_ty(ty);
+        }
+    }
+    for variable in &program.variables {
+        visitor.visit_ty(&variable.ty);
+    }
+    for bb in &program.basic_blocks {
+        visitor.visit_basic_block(bb);
+    }
+}
+
+pub fn walk_basic_block<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, bb: &'ast BasicBlock) {
+    for statement in &bb.statements {
+        visitor.visit_statement(statement);
+    }
+    visitor.visit_terminator(&bb.terminator);
+}
+
+pub fn walk_statement<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    statement: &'ast Statement,
+) {
+    match statement {
+        Statement::Assign(place, expr) => {
+            visitor.visit_place(place);
+            visitor.visit_expr(expr);
+        }
+        Statement::Expr(expr) => visitor.visit_expr(expr),
+        Statement::End(origin) => visitor.visit_origin(origin),
+        Statement::Kill(place) => visitor.visit_place(place),
+    }
+}
+
+pub fn walk_terminator<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    terminator: &'ast Terminator,
+) {
+    match terminator {
+        Terminator::Switch { discriminant, .. } => visitor.visit_expr(discriminant),
+        Terminator::Call {
+            destination, call, ..
+        } => {
+            if let Some(place) = destination {
+                visitor.visit_place(place);
+            }
+            visitor.visit_expr(call);
+        }
+        Terminator::Assert { condition, .. } => visitor.visit_expr(condition),
+        Terminator::Goto(_) | Terminator::Return | Terminator::Unreachable => {}
+    }
+}
+
+pub fn walk_expr<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, expr: &'ast Expr) {
+    match expr {
+        Expr::Access { kind, place } => {
+            if let AccessKind::Borrow(origin) | AccessKind::BorrowMut(origin) = kind {
+                visitor.visit_origin(origin);
+            }
+            visitor.visit_place(place);
+        }
+        Expr::Call {
+            generic_args,
+            arguments,
+            ..
+        } => {
+            walk_parameters(visitor, generic_args);
+            for argument in arguments {
+                visitor.visit_expr(argument);
+            }
+        }
+        Expr::Cast { operand, ty } => {
+            visitor.visit_expr(operand);
+            visitor.visit_ty(ty);
+        }
+        Expr::Number { .. } | Expr::Unit => {}
+    }
+}
+
+pub fn walk_ty<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, ty: &'ast Ty) {
+    match ty {
+        Ty::Ref { origin, ty } | Ty::RefMut { origin, ty } => {
+            visitor.visit_origin(origin);
+            visitor.visit_ty(ty);
+        }
+        Ty::RawPtr { ty } | Ty::RawPtrMut { ty } | Ty::Array { ty, .. } => visitor.visit_ty(ty),
+        Ty::Struct { parameters, .. } => walk_parameters(visitor, parameters),
+        Ty::I32 | Ty::Unit | Ty::Infer => {}
+    }
+}
+
+fn walk_parameters<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    parameters: &'ast [Parameter],
+) {
+    for parameter in parameters {
+        match parameter {
+            Parameter::Origin(origin) => visitor.visit_origin(origin),
+            Parameter::Ty(ty) => visitor.visit_ty(ty),
+        }
+    }
+}
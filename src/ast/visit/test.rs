@@ -0,0 +1,59 @@
+use super::*;
+
+#[derive(Default)]
+struct Counter {
+    places: Vec<String>,
+    origins: Vec<Name>,
+    exprs: usize,
+}
+
+impl<'ast> Visitor<'ast> for Counter {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        self.exprs += 1;
+        walk_expr(self, expr);
+    }
+
+    fn visit_place(&mut self, place: &'ast Place) {
+        self.places.push(place.to_string());
+    }
+
+    fn visit_origin(&mut self, origin: &'ast Name) {
+        self.origins.push(*origin);
+    }
+}
+
+#[test]
+fn walks_the_whole_program() {
+    let program = program! {
+        struct S<'s> { f: &'s i32 }
+        fn f<'a>(x: &'a i32) -> S<'a>;
+        let x: i32;
+        let s: S<'v>;
+        let t;
+        bb0: {
+            t = &'L x;
+            s = f(move t);
+            end('v);
+            copy s.f;
+            switch(copy s.f) -> [bb1];
+        }
+        bb1: { }
+    };
+    let mut counter = Counter::default();
+    counter.visit_program(&program);
+
+    // The discriminant of the `switch` is visited as the terminator's operand, after the block's
+    // last statement reading it, like the parser lowers it
+    assert_eq!(counter.places, ["t", "x", "s", "t", "s.f", "s.f"]);
+    insta::assert_debug_snapshot!(counter.origins, @r###"
+    [
+        "'s",
+        "'a",
+        "'a",
+        "'v",
+        "'L",
+        "'v",
+    ]
+    "###);
+    assert_eq!(counter.exprs, 5);
+}
@@ -9,26 +9,46 @@
 //! String     := "[^"]*"   /* regular expression */
 //! ```
 
-use std::str::FromStr;
+use std::convert::TryFrom;
+
+use bumpalo::boxed::Box as ArenaBox;
+use bumpalo::collections::Vec as ArenaVec;
+use bumpalo::Bump;
 
 use crate::ast;
+use crate::lowering::lower_program;
 use crate::span::{Span, Spanned as Sp, WithSpan};
+use crate::surface;
+
+mod lexer;
 
 #[cfg(test)]
 pub(crate) mod test;
 
 peg::parser! {
-    grammar ast_parser() for str {
-        pub rule program() -> ast::Program = (
+    grammar ast_parser<'a>(arena: &'a Bump) for str {
+        pub rule program() -> surface::Program<'a> = (
             _ struct_decls:struct_decl()**__ _
-            fn_prototypes:fn_prototype()**__ _
+            fn_decls:fn_decls()**__ _
+            placeholders:placeholder_decl()**__ _
             variables:var_decl()**__ _
-            basic_blocks:basic_block()**__ _ {
-                ast::Program {
+            basic_blocks:basic_block()**__ _
+            raw_facts:raw_facts()**__ _ {
+                let placeholder_names: Vec<_> =
+                    placeholders.iter().flat_map(|(p, _)| p.clone()).collect();
+                let mut fn_prototypes: Vec<_> = fn_decls.into_iter().flatten().collect();
+                for prototype in &mut fn_prototypes {
+                    declare_signature_origins(prototype, &placeholder_names);
+                }
+
+                surface::Program {
                     struct_decls,
                     fn_prototypes,
+                    placeholders: placeholder_names,
+                    known_placeholder_subsets: placeholders.into_iter().flat_map(|(_, s)| s).collect(),
                     variables,
                     basic_blocks,
+                    raw_facts: raw_facts.into_iter().flatten().collect(),
                 }
             }
         )
@@ -40,20 +60,99 @@ peg::parser! {
         rule __ = quiet!{skip()+}
 
         rule struct_decl() -> ast::StructDecl = (
-            "struct" _ name:ident() _ generic_decls:generic_decls() _
+            is_copy:(derive_copy() _)? "struct" _ name:ident() _ generic_decls:generic_decls() _
             "{" _ field_decls:field_decl()**comma() _ comma()? "}" {
-                ast::StructDecl { name, generic_decls, field_decls }
+                ast::StructDecl { name, generic_decls, field_decls, is_copy: is_copy.is_some() }
             }
         )
 
+        rule derive_copy() = "#" _ "[" _ "derive" _ "(" _ "Copy" _ ")" _ "]"
+
+        rule fn_decls() -> Vec<ast::FnPrototype> = (
+            prototype:fn_prototype() { vec![prototype] } /
+            impl_block()
+        )
+
         rule fn_prototype() -> ast::FnPrototype = (
-            "fn" _ name:ident() _ generic_decls:generic_decls() _
+            "fn" _ name:sp(<ident()>) _ generic_decls:generic_decls() _
             "(" _ arg_decls:field_decl()**comma() _ ")" _ "->" _ ret_ty:ty() _ ";" {
                 let arg_tys = arg_decls.into_iter().map(|a| a.ty).collect();
-                ast::FnPrototype { name, generic_decls, arg_tys, ret_ty }
+                let span = name.span();
+                ast::FnPrototype {
+                    name: Sp::into_inner(name),
+                    span,
+                    generic_decls,
+                    arg_tys,
+                    ret_ty,
+                    is_method: false,
+                }
+            }
+        )
+
+        // The functions of an `impl` block are declared like the other functions, with the
+        // generic parameters of the block, and named after its type.
+        rule impl_block() -> Vec<ast::FnPrototype> = (
+            "impl" _ generic_decls:generic_decls() _ self_ty:struct_ty() _
+            "{" _ methods:method((&generic_decls), (&self_ty))**__ _ "}" { methods }
+        )
+
+        rule method(impl_generic_decls: &[ast::GenericDecl], self_ty: &ast::Ty) -> ast::FnPrototype = (
+            "fn" _ name:sp(<ident()>) _ generic_decls:generic_decls() _
+            "(" _ receiver:(r:receiver(self_ty) _ (comma() / &")") { r })? _
+            arg_decls:field_decl()**comma() _ ")" _ "->" _ ret_ty:ty() _ ";" {
+                let mut arg_tys: Vec<_> = receiver
+                    .iter()
+                    .cloned()
+                    .chain(arg_decls.into_iter().map(|a| a.ty))
+                    .collect();
+                let mut ret_ty = ret_ty;
+                for ty in arg_tys.iter_mut().chain([&mut ret_ty]) {
+                    replace_self_ty(ty, self_ty);
+                }
+
+                let self_name = match self_ty {
+                    ast::Ty::Struct { name, .. } => name,
+                    _ => unreachable!("`impl` blocks are declared on structs"),
+                };
+                let span = name.span();
+                ast::FnPrototype {
+                    name: format!("{}::{}", self_name, Sp::into_inner(name)).into(),
+                    span,
+                    generic_decls: impl_generic_decls.iter().cloned().chain(generic_decls).collect(),
+                    arg_tys,
+                    ret_ty,
+                    is_method: receiver.is_some(),
+                }
             }
         )
 
+        rule receiver(self_ty: &ast::Ty) -> ast::Ty = (
+            "&" _ origin:origin_ident() _ "mut" __ "self" {
+                ast::Ty::RefMut { origin, ty: Box::new(self_ty.clone()) }
+            } /
+            "&" _ "mut" __ "self" {
+                let origin = ast::ANONYMOUS_ORIGIN.into();
+                ast::Ty::RefMut { origin, ty: Box::new(self_ty.clone()) }
+            } /
+            "&" _ origin:origin_ident() _ "self" {
+                ast::Ty::Ref { origin, ty: Box::new(self_ty.clone()) }
+            } /
+            "&" _ "self" {
+                let origin = ast::ANONYMOUS_ORIGIN.into();
+                ast::Ty::Ref { origin, ty: Box::new(self_ty.clone()) }
+            } /
+            "self" { self_ty.clone() }
+        )
+
+        rule placeholder_decl() -> (Vec<ast::Name>, Vec<(ast::Name, ast::Name)>) =
+            "placeholder" __ origins:origin_ident()**comma() _
+            subsets:("where" __ s:outlives()**comma() { s })? _ ";" {
+                (origins, subsets.unwrap_or_default())
+            }
+
+        rule outlives() -> (ast::Name, ast::Name) =
+            o1:origin_ident() _ ":" _ o2:origin_ident() { (o1, o2) }
+
         rule generic_decls() -> Vec<ast::GenericDecl> = (
             "<" _ g:generic_decl()**comma() _ ">" { g } /
             () { vec![] }
@@ -64,22 +163,52 @@ peg::parser! {
             n:ident() { ast::GenericDecl::Ty(n) }
         )
 
-        rule field_decl() -> ast::VariableDecl = name:ident() _ ":" _ ty:ty() {
-            ast::VariableDecl { name, ty }
+        rule field_decl() -> ast::VariableDecl = name:sp(<ident()>) _ ":" _ ty:ty() {
+            let span = name.span();
+            ast::VariableDecl { name: Sp::into_inner(name), ty, span }
         }
 
-        rule var_decl() -> ast::VariableDecl = "let" _ name:ident() _ ":" _ ty:ty() _ ";" {
-            ast::VariableDecl { name, ty }
-        }
+        rule var_decl() -> ast::VariableDecl = (
+            "let" _ name:sp(<ident()>) _ ":" _ ty:ty() _ ";" {
+                let span = name.span();
+                ast::VariableDecl { name: Sp::into_inner(name), ty, span }
+            } /
+            "let" _ name:sp(<ident()>) _ ";" {
+                let span = name.span();
+                ast::VariableDecl { name: Sp::into_inner(name), ty: ast::Ty::Infer, span }
+            }
+        )
 
-        rule ty() -> ast::Ty = ref_mut_ty() / ref_ty() / i32_ty() / unit_ty() / struct_ty()
+        rule ty() -> ast::Ty = (
+            ref_mut_ty() / ref_ty() / raw_ptr_ty() / array_ty() / i32_ty() / unit_ty() / struct_ty()
+        )
 
-        rule ref_ty() -> ast::Ty = "&" _ origin:origin_ident() _ ty:ty() {
-            ast::Ty::Ref { origin, ty: Box::new(ty) }
-        }
+        rule ref_ty() -> ast::Ty = (
+            "&" _ origin:origin_ident() _ ty:ty() {
+                ast::Ty::Ref { origin, ty: Box::new(ty) }
+            } /
+            "&" _ ty:ty() {
+                ast::Ty::Ref { origin: ast::ANONYMOUS_ORIGIN.into(), ty: Box::new(ty) }
+            }
+        )
+
+        rule ref_mut_ty() -> ast::Ty = (
+            "&" _ origin:origin_ident() _ "mut" _ ty:ty() {
+                ast::Ty::RefMut { origin, ty: Box::new(ty) }
+            } /
+            "&" _ "mut" __ ty:ty() {
+                ast::Ty::RefMut { origin: ast::ANONYMOUS_ORIGIN.into(), ty: Box::new(ty) }
+            }
+        )
+
+        rule raw_ptr_ty() -> ast::Ty = (
+            "*" _ "const" __ ty:ty() { ast::Ty::RawPtr { ty: Box::new(ty) } } /
+            "*" _ "mut" __ ty:ty() { ast::Ty::RawPtrMut { ty: Box::new(ty) } }
+        )
 
-        rule ref_mut_ty() -> ast::Ty = "&" _ origin:origin_ident() _ "mut" _ ty:ty() {
-            ast::Ty::RefMut { origin, ty: Box::new(ty) }
+        rule array_ty() -> ast::Ty = "[" _ ty:ty() _ ";" _ len:number() _ "]" {?
+            let len = usize::try_from(len).or(Err("an array length"))?;
+            Ok(ast::Ty::Array { ty: Box::new(ty), len })
         }
 
         rule i32_ty() -> ast::Ty = "i32" {
@@ -106,69 +235,458 @@ peg::parser! {
 
         rule comma() -> () = _ "," _ { }
 
-        rule basic_block() -> ast::BasicBlock = (
-            name:ident() _ ":" _ "{" _ statements:sp(<statement()>)**__ _ successors:goto() _ "}" {
-                ast::BasicBlock { name, statements, successors }
+        rule basic_block() -> surface::BasicBlock<'a> = (
+            name:sp(<ident()>) _ ":" _ "{" _ statements:sp(<statement()>)**__ _ terminator:terminator() _ "}" {
+                let span = name.span();
+                let statements = ArenaVec::from_iter_in(statements, arena);
+                surface::BasicBlock { name: Sp::into_inner(name), span, statements, terminator }
             }
         )
 
-        rule goto() -> Vec<ast::Name> = (
-            "goto" _ names:ident()**comma() _ ";" { names } /
-            () { vec![] }
+        rule terminator() -> surface::Terminator<'a> = (
+            "goto" _ names:ident()**comma() _ ";" { surface::Terminator::Goto(names) } /
+            "return" _ ";" { surface::Terminator::Return } /
+            "unreachable" _ ";" { surface::Terminator::Unreachable } /
+            "switch" _ "(" _ discriminant:sp(<expr()>) _ ")" _ "->" _
+            "[" _ targets:ident()**comma() _ "]" _ ";" {
+                surface::Terminator::Switch { discriminant, targets }
+            } /
+            "assert" _ "(" _ condition:sp(<expr()>) _ ")" _ "->" _
+            "[" _ "success" _ ":" _ success:ident() unwind:unwind_target()? _ "]" _ ";" {
+                surface::Terminator::Assert { condition, success, unwind }
+            } /
+            // The call is quiet, so that statements are expected instead at the end of blocks
+            call:quiet!{sp(<call_terminator()>)} _
+            "->" _ "[" _ "return" _ ":" _ target:ident() unwind:unwind_target()? _ "]" _ ";" {
+                // The call's statement spans its destination
+                let span = call.span();
+                let (destination, call) = Sp::into_inner(call);
+                let call = call.at(span);
+                surface::Terminator::Call { destination, call, target, unwind }
+            } /
+            () { surface::Terminator::Return }
         )
 
-        rule statement() -> ast::Statement = (
-            place:place() _ "=" _ expr:expr() _ ";" { ast::Statement::Assign(place, expr) } /
-            expr:expr() _ ";" { ast::Statement::Expr(expr) }
+        rule call_terminator() -> (Option<ast::Place>, surface::Expr<'a>) =
+            destination:(place:place() _ "=" _ { place })? call:(method_call() / call()) {
+                (destination, call)
+            }
+
+        rule unwind_target() -> ast::Name = comma() "unwind" _ ":" _ target:ident() { target }
+
+        // Statements can be followed by their location in the Rust source they're transcribed
+        // from.
+        rule statement() -> surface::Statement<'a> = (
+            s:sp(<unlocated_statement()>) _ location:source_location() {
+                surface::Statement::Located(location, ArenaBox::new_in(s, arena))
+            } /
+            unlocated_statement()
+        )
+
+        rule source_location() -> ast::SourceLocation =
+            "@" _ file:$([^':' | ' ' | '\n']+) ":" line:$(['0'..='9']+) {?
+                let line = line.parse().or(Err("a line number"))?;
+                Ok(ast::SourceLocation { file: file.to_string(), line })
+            }
+
+        rule unlocated_statement() -> surface::Statement<'a> = (
+            attributes:attribute()++_ _ s:sp(<unlocated_statement()>) {
+                surface::Statement::Attributed(attributes, ArenaBox::new_in(s, arena))
+            } /
+            if_statement() /
+            "while" __ condition:sp(<expr()>) _ body:statement_block() {
+                surface::Statement::While { condition, body }
+            } /
+            "loop" _ body:statement_block() { surface::Statement::Loop { body } } /
+            "break" _ ";" { surface::Statement::Break } /
+            "continue" _ ";" { surface::Statement::Continue } /
+            "end" _ "(" _ origin:origin_ident() _ ")" _ ";" { surface::Statement::End(origin) } /
+            "kill" _ "(" _ place:place() _ ")" _ ";" { surface::Statement::Kill(place) } /
+            "let" __ name:sp(<ident()>) _ ty:(":" _ ty:ty() { ty })? _ "=" _ expr:expr() _ ";" {
+                let span = name.span();
+                let ty = ty.unwrap_or(ast::Ty::Infer);
+                let variable = ast::VariableDecl { name: Sp::into_inner(name), ty, span };
+                surface::Statement::Let(variable, expr)
+            } /
+            place:place() _ "=" _ expr:expr() _ ";" { surface::Statement::Assign(place, expr) } /
+            expr:expr() _ ";" { surface::Statement::Expr(expr) }
+        )
+
+        // Facts merged verbatim into the emitted facts, in the fact file format, with the node
+        // of local facts written before them. The keyword is quiet, so that it's not expected in
+        // the errors at the end of the blocks.
+        rule raw_facts() -> Vec<Sp<ast::RawFact>> =
+            quiet!{"facts"} _ "{" _ facts:sp(<raw_fact()>)**__ _ "}" { facts }
+
+        rule raw_fact() -> ast::RawFact =
+            node:(node:fact_atom() _ ":" _ { node })? relation:ident() _
+            "(" _ arguments:fact_atom()**comma() _ ")" {
+                ast::RawFact { node, relation, arguments }
+            }
+
+        rule fact_atom() -> ast::Name =
+            t:$("'"? ['a'..='z' | 'A'..='Z' | '_' | '0' ..= '9' | '*' | '[' | ']']+) { t.into() }
+
+        rule attribute() -> ast::Attribute = "#" _ "[" _ name:ident() _ "]" {?
+            match name.as_str() {
+                "two_phase" => Ok(ast::Attribute::TwoPhase),
+                "no_invalidate" => Ok(ast::Attribute::NoInvalidate),
+                _ => Err("a known attribute, `two_phase` or `no_invalidate`"),
+            }
+        }
+
+        rule if_statement() -> surface::Statement<'a> =
+            "if" __ condition:sp(<expr()>) _ then_block:statement_block()
+            else_block:(
+                _ "else" _ block:statement_block() { block } /
+                _ "else" __ s:sp(<if_statement()>) { ArenaVec::from_iter_in(Some(s), arena) }
+            )? {
+                surface::Statement::If { condition, then_block, else_block }
+            }
+
+        rule statement_block() -> ArenaVec<'a, Sp<surface::Statement<'a>>> =
+            "{" _ statements:sp(<statement()>)**__ _ "}" {
+                ArenaVec::from_iter_in(statements, arena)
+            }
+
+        // Expressions can be nested: they are lowered to simple operations on temporaries.
+        rule expr() -> surface::Expr<'a> =
+            start:position!() operand:simple_expr() operand_end:position!()
+            casts:(__ "as" __ ty:ty() end:position!() { (ty, end) })* {
+                // Casts are left-associative: `x as A as B` casts `x as A` to `B`
+                let mut expr = operand;
+                let mut end = operand_end;
+                for (ty, cast_end) in casts {
+                    let operand = ArenaBox::new_in(expr.at(Span::new(start, end)), arena);
+                    expr = surface::Expr::Cast { operand, ty };
+                    end = cast_end;
+                }
+                expr
+            }
+
+        rule simple_expr() -> surface::Expr<'a> = (
+            kind:access_kind() _ place:nested_place() { surface::Expr::Access { kind, place } } /
+            n:number() {?
+                let value = i32::try_from(n).or(Err("a number fitting in `i32`"))?;
+                Ok(surface::Expr::Number { value })
+            } /
+            method_call() /
+            place:nested_place() {?
+                // Projections of a temporary, like `foo().f`, move out of it
+                if place.is_projected_temp() {
+                    Ok(surface::Expr::Access { kind: ast::AccessKind::Move, place })
+                } else {
+                    Err("an expression")
+                }
+            } /
+            call() /
+            "(" _ ")" { surface::Expr::Unit }
         )
 
-        rule expr() -> ast::Expr = (
-            kind:access_kind() _ place:place() { ast::Expr::Access { kind, place } } /
-            n:$(['0'..='9']+) { ast::Expr::Number { value: i32::from_str(n).unwrap() } } /
-            name:ident() _ "(" _ arguments:expr()**comma() _ ")" { ast::Expr::Call { name, arguments} } /
-            "(" _ ")" { ast::Expr::Unit }
+        rule call() -> surface::Expr<'a> =
+            name:fn_path() _ generic_args:turbofish() _ "(" _ arguments:sp(<argument()>)**comma() _ ")" {
+                let arguments = ArenaVec::from_iter_in(arguments, arena);
+                surface::Expr::Call { name, generic_args, arguments }
+            }
+
+        // A call argument, which can be a place without an access kind: how it's accessed is
+        // inferred from the function's signature.
+        rule argument() -> surface::Expr<'a> = (
+            expr() /
+            place:nested_place() { surface::Expr::Access { kind: ast::AccessKind::Infer, place } }
         )
 
-        pub rule place() -> ast::Place = precedence!{
-            "*" _ inner:@ {
+        // The name of a function, or of a function declared in an `impl` block (`Vec::new`).
+        rule fn_path() -> ast::Name = p:$(ident() "::" ident()) { p.into() } / ident()
+
+        // A method call (`v.push(x)`): the method is the last field of the place, and the rest
+        // of the place is its receiver.
+        rule method_call() -> surface::Expr<'a> =
+            start:position!() place:nested_place() _ generic_args:turbofish() _
+            "(" _ arguments:sp(<argument()>)**comma() _ ")" end:position!() {?
+                let mut receiver = place;
+                match receiver.projections.pop() {
+                    Some(ast::Projection::Field(method)) => Ok(surface::Expr::MethodCall {
+                        receiver,
+                        method,
+                        generic_args,
+                        arguments: ArenaVec::from_iter_in(arguments, arena),
+                        span: Span::new(start, end),
+                    }),
+                    _ => Err("a method call"),
+                }
+            }
+
+        rule turbofish() -> Vec<ast::Parameter> = (
+            "::" _ "<" _ p:parameter()**comma() _ ">" { p } /
+            () { vec![] }
+        )
+
+        // Derefs bind less tightly than fields and indices: `*x.f` is `*(x.f)`.
+        rule nested_place() -> surface::Place<'a> = (
+            "*" _ inner:nested_place() {
                 let mut inner = inner;
                 inner.projections.push(ast::Projection::Deref);
                 inner
+            } /
+            inner:nested_place_base() projections:(_ p:projection() { p })* {
+                let mut inner = inner;
+                inner.projections.extend(projections);
+                inner
             }
-            --
-            inner:@ _ "." _ field:ident() {
+        )
+
+        rule nested_place_base() -> surface::Place<'a> = (
+            call:sp(<call()>) {
+                let base = surface::PlaceBase::Temp(ArenaBox::new_in(call, arena));
+                surface::Place { base, projections: vec![] }
+            } /
+            base:ident() {
+                surface::Place { base: surface::PlaceBase::Variable(base), projections: vec![] }
+            } /
+            "(" _ inner:nested_place() _ ")" { inner }
+        )
+
+        pub rule place() -> ast::Place = (
+            "*" _ inner:place() {
+                let mut inner = inner;
+                inner.projections.push(ast::Projection::Deref);
+                inner
+            } /
+            inner:place_base() projections:(_ p:projection() { p })* {
                 let mut inner = inner;
-                inner.projections.push(ast::Projection::Field(field));
+                inner.projections.extend(projections);
                 inner
             }
-            --
-            base:ident() { ast::Place { base, projections: vec![] } }
+        )
+
+        rule place_base() -> ast::Place = (
+            base:ident() { ast::Place { base, projections: vec![] } } /
             "(" _ inner:place() _ ")" { inner }
-        }
+        )
+
+        // The projections applied after a place's base, a field or an index.
+        rule projection() -> ast::Projection = (
+            "." _ field:ident() { ast::Projection::Field(field) } /
+            "[" _ index:ident() _ "]" { ast::Projection::Index(index) }
+        )
 
         rule access_kind() -> ast::AccessKind = (
             "copy" { ast::AccessKind::Copy } /
             "move" { ast::AccessKind::Move } /
             "&" _ o:origin_ident() _ "mut" { ast::AccessKind::BorrowMut(o) } /
-            "&" _ o:origin_ident() { ast::AccessKind::Borrow(o) }
+            "&" _ o:origin_ident() { ast::AccessKind::Borrow(o) } /
+            "&" _ "mut" __ { ast::AccessKind::BorrowMut(ast::ANONYMOUS_ORIGIN.into()) } /
+            "&" { ast::AccessKind::Borrow(ast::ANONYMOUS_ORIGIN.into()) }
         )
 
-        rule ident() -> ast::Name = t:$(['a'..='z' | 'A'..='Z' | '_' | '0' ..= '9' ]+) {
-            t.to_string()
-        }
+        // Identifiers can be non-ASCII, like in Rust, for the programs lowered from Rust code.
+        rule ident() -> ast::Name =
+            quiet!{ t:$(ident_char()+) { t.into() } } / expected!("an identifier")
 
-        rule origin_ident() -> ast::Name = t:$("'"['a'..='z' | 'A'..='Z' | '_' | '0' ..= '9' | '*' ]+) {
-            t.to_string()
+        rule origin_ident() -> ast::Name = t:$("'" (ident_char() / "*")+) {
+            t.into()
         }
 
+        rule ident_char() = [c if c.is_alphanumeric() || c == '_']
+
+        // Numbers are decimal, or hexadecimal like `0x1f`, and their digits can be separated by
+        // underscores, like `1_000`.
+        rule number() -> u64 = quiet!{
+            "0x" digits:$(['0'..='9' | 'a'..='f' | 'A'..='F' | '_']+) {?
+                u64::from_str_radix(&digits.replace('_', ""), 16).or(Err("a number"))
+            } /
+            digits:$(['0'..='9'] ['0'..='9' | '_']*) {?
+                digits.replace('_', "").parse().or(Err("a number"))
+            }
+        } / expected!("a number")
+
         rule sp<T>(t: rule<T>) -> Sp<T> = start:position!() inner:t() end:position!() {
             inner.at(Span::new(start, end))
         }
     }
 }
 
+/// Parses the input program, and lowers it to the AST the facts are emitted from, see
+/// `lowering`.
+#[tracing::instrument(skip_all, fields(len = input.len()))]
 pub fn parse_ast(input: &str) -> eyre::Result<ast::Program> {
-    Ok(ast_parser::program(input)?)
+    let arena = Bump::new();
+    let program = lower_program(parse_surface(input, &arena)?)?;
+    tracing::debug!(
+        blocks = program.basic_blocks.len(),
+        variables = program.variables.len(),
+        "parsed"
+    );
+    Ok(program)
 }
 
-pub use self::ast_parser::place;
+/// Parses the input program as it's written, without lowering it, see `surface::Program`. Its
+/// statements and expressions are allocated in the arena.
+pub(crate) fn parse_surface<'a>(
+    input: &str,
+    arena: &'a Bump,
+) -> eyre::Result<surface::Program<'a>> {
+    Ok(ast_parser::program(input, arena)?)
+}
+
+/// Parses the input program like `parse_ast`, but keeps going after syntax errors to report all
+/// of them: the statement or declaration where parsing fails is blanked out, keeping the spans of
+/// the rest of the input, and the input is parsed again. The chars the grammar doesn't know are
+/// blanked out first, each reported as an error at its span. Returns the program parsed without
+/// the erroneous statements, unless an error can't be recovered from, and the syntax errors, in
+/// program order, at the span of the token where parsing failed.
+pub(crate) fn parse_ast_recovering(input: &str) -> (Option<ast::Program>, Vec<(Span, String)>) {
+    let mut input = input.to_string();
+    let mut errors = Vec::new();
+    for token in lexer::tokenize(&input) {
+        if token.kind == lexer::TokenKind::Error {
+            let text = &input[token.span.start()..token.span.end()];
+            errors.push((token.span, format!("unknown character `{}`", text)));
+            blank(&mut input, token.span);
+        }
+    }
+
+    let program = loop {
+        // The errors of lowering are reported at their span, and recovered from like syntax
+        // errors, by blanking out their statement
+        let arena = Bump::new();
+        let (span, message, offset) = match ast_parser::program(&input, &arena).map(lower_program) {
+            Ok(Ok(program)) => break Some(program),
+            Ok(Err(error)) => (error.span, error.to_string(), error.span.start()),
+            Err(error) => {
+                let offset = error.location.offset;
+                let tokens = lexer::tokenize(&input);
+                let token_span = lexer::token_at(&tokens, offset)
+                    .map_or(Span::new(offset, offset), |token| token.span);
+                let message = format!("syntax error, expected {}", error.expected);
+                (token_span, message, offset)
+            }
+        };
+        errors.push((span, message));
+        match erroneous_statement(&input, offset) {
+            Some(span) => blank(&mut input, span),
+            None => break None,
+        }
+    };
+    errors.sort_by_key(|(span, _)| span.start());
+    (program, errors)
+}
+
+// Replaces the text at the span by spaces, keeping its newlines and its length in bytes, for the
+// lines and offsets of the rest of the input to stay the same.
+fn blank(input: &mut String, span: Span) {
+    let blank: String = input[span.start()..span.end()]
+        .chars()
+        .map(|c| {
+            if c == '\n' {
+                "\n".to_string()
+            } else {
+                " ".repeat(c.len_utf8())
+            }
+        })
+        .collect();
+    input.replace_range(span.start()..span.end(), &blank);
+}
+
+// The span of the statement or declaration containing the offset: since the previous `;` or
+// brace, until the next `;` or closing brace. There is none when it's only whitespace, e.g. at
+// the end of the input.
+fn erroneous_statement(input: &str, offset: usize) -> Option<Span> {
+    let start = input[..offset]
+        .rfind([';', '{', '}'])
+        .map_or(0, |idx| idx + 1);
+    let start = start + (input[start..].len() - input[start..].trim_start().len());
+    let end = match input[offset..].find([';', '}']) {
+        Some(idx) if input[offset + idx..].starts_with(';') => offset + idx + 1,
+        Some(idx) => offset + idx,
+        None => input.len(),
+    };
+    let span = Span::new(start, end.max(start));
+    if input[span.start()..span.end()].trim().is_empty() {
+        None
+    } else {
+        Some(span)
+    }
+}
+
+// Parses a single place, for the tests building places from their text
+#[cfg(test)]
+pub(crate) fn place(input: &str) -> Result<ast::Place, peg::error::ParseError<peg::str::LineCol>> {
+    ast_parser::place(input, &Bump::new())
+}
+
+// Replaces `Self` by the type of the `impl` block in the signature of one of its functions.
+fn replace_self_ty(ty: &mut ast::Ty, self_ty: &ast::Ty) {
+    match ty {
+        ast::Ty::Struct { name, parameters } if name == "Self" && parameters.is_empty() => {
+            *ty = self_ty.clone();
+        }
+        ast::Ty::Struct { parameters, .. } => {
+            for parameter in parameters {
+                if let ast::Parameter::Ty(ty) = parameter {
+                    replace_self_ty(ty, self_ty);
+                }
+            }
+        }
+        ast::Ty::Ref { ty, .. }
+        | ast::Ty::RefMut { ty, .. }
+        | ast::Ty::RawPtr { ty }
+        | ast::Ty::RawPtrMut { ty }
+        | ast::Ty::Array { ty, .. } => replace_self_ty(ty, self_ty),
+        ast::Ty::I32 | ast::Ty::Unit | ast::Ty::Infer => {}
+    }
+}
+
+// Declares the origins used in the signature of a function as its generic parameters, like Rust's
+// lifetime elision rules, unless they're placeholders of the analyzed function:
+// - the named origins which are not declared, like `'a` in `fn f(x: &'a i32) -> &'a i32;`
+// - the origins elided from the arguments, which are each given a fresh name: `'_0`, `'_1`, etc.
+// - the origins elided from the return type are the origin of the `self` receiver if it's a
+//   reference, or the single origin of the arguments if there's only one. Otherwise, they're fresh
+//   as well, and unrelated to the arguments.
+fn declare_signature_origins(prototype: &mut ast::FnPrototype, placeholders: &[ast::Name]) {
+    let generic_decls = &mut prototype.generic_decls;
+    let mut declare = |origin: &ast::Name| {
+        let is_declared = origin == ast::STATIC_ORIGIN
+            || placeholders.contains(origin)
+            || generic_decls
+                .iter()
+                .any(|decl| matches!(decl, ast::GenericDecl::Origin(o) if o == origin));
+        if !is_declared {
+            generic_decls.push(ast::GenericDecl::Origin(*origin));
+        }
+    };
+
+    let mut fresh_origin_count = 0;
+    let mut fresh_origin = || {
+        fresh_origin_count += 1;
+        ast::Name::from(format!("'_{}", fresh_origin_count - 1))
+    };
+    let mut arg_origins = Vec::new();
+    for ty in &mut prototype.arg_tys {
+        ty.visit_origins_mut(&mut |origin| {
+            if origin == ast::ANONYMOUS_ORIGIN {
+                *origin = fresh_origin();
+            }
+            declare(origin);
+            arg_origins.push(*origin);
+        });
+    }
+
+    let elided_ret_origin = match prototype.arg_tys.first() {
+        Some(ast::Ty::Ref { origin, .. } | ast::Ty::RefMut { origin, .. })
+            if prototype.is_method =>
+        {
+            Some(*origin)
+        }
+        _ if arg_origins.len() == 1 => Some(arg_origins[0]),
+        _ => None,
+    };
+    prototype.ret_ty.visit_origins_mut(&mut |origin| {
+        if origin == ast::ANONYMOUS_ORIGIN {
+            *origin = elided_ret_origin.unwrap_or_else(&mut fresh_origin);
+        }
+        declare(origin);
+    });
+}
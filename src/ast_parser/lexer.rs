@@ -0,0 +1,98 @@
+//! The tokens of the input program, each with its span, for the syntax errors to point at the
+//! exact token where parsing fails, and to recover from the characters the grammar doesn't know.
+//!
+//! The grammar still parses the text itself: the tokens are computed before parsing, so that
+//! unknown characters are reported as error tokens, and blanked out of the input, instead of
+//! stopping the parse at the first of them.
+
+use crate::span::Span;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// A name, keyword, or number, e.g. `x`, `let`, `42`, `0x2a`. Names can be non-ASCII.
+    Ident,
+
+    /// An origin, e.g. `'a`, or `'_`.
+    Origin,
+
+    /// A punctuation, of one char, or `->` and `::`.
+    Punct,
+
+    /// The location of a statement in the Rust source, e.g. `@ main.rs:42`.
+    Location,
+
+    /// A char which is not part of the grammar.
+    Error,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub(crate) kind: TokenKind,
+    pub(crate) span: Span,
+}
+
+const PUNCTS: &[&str] = &[
+    "->", "::", "{", "}", "(", ")", "[", "]", "<", ">", ";", ":", ",", ".", "=", "&", "*", "#",
+    "@", "'",
+];
+
+/// The tokens of the input, in order, without its whitespace and comments.
+pub(crate) fn tokenize(input: &str) -> Vec<Token> {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    while let Some(c) = input[offset..].chars().next() {
+        let rest = &input[offset..];
+        let (kind, len) = if c == ' ' || c == '\n' {
+            offset += 1;
+            continue;
+        } else if rest.starts_with("//") {
+            offset += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        } else if is_ident_char(c) {
+            (
+                TokenKind::Ident,
+                rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len()),
+            )
+        } else if c == '\'' && rest[1..].starts_with(|c| is_ident_char(c) || c == '*') {
+            let len = rest[1..]
+                .find(|c| !is_ident_char(c) && c != '*')
+                .map_or(rest.len(), |len| len + 1);
+            (TokenKind::Origin, len)
+        } else if let Some(len) = location_len(rest) {
+            (TokenKind::Location, len)
+        } else if let Some(punct) = PUNCTS.iter().find(|punct| rest.starts_with(**punct)) {
+            (TokenKind::Punct, punct.len())
+        } else {
+            (TokenKind::Error, c.len_utf8())
+        };
+        tokens.push(Token {
+            kind,
+            span: Span::new(offset, offset + len),
+        });
+        offset += len;
+    }
+    tokens
+}
+
+// The length of the source location the text starts with, `@`, a file name, and a line number.
+fn location_len(text: &str) -> Option<usize> {
+    let file = text.strip_prefix('@')?.trim_start_matches(' ');
+    let file_len = file.find([':', ' ', '\n']).filter(|&len| len > 0)?;
+    let line = file[file_len..].strip_prefix(':')?;
+    let line_len = line
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(line.len());
+    if line_len == 0 {
+        return None;
+    }
+    Some(text.len() - line.len() + line_len)
+}
+
+/// The token at the offset, or the first one after it, e.g. where parsing failed.
+pub(crate) fn token_at(tokens: &[Token], offset: usize) -> Option<&Token> {
+    tokens.iter().find(|token| token.span.end() > offset)
+}
@@ -0,0 +1,245 @@
+use super::*;
+use insta::assert_debug_snapshot;
+
+// The kind and text of each token of the input.
+fn tokens(input: &str) -> Vec<(TokenKind, &str)> {
+    tokenize(input)
+        .into_iter()
+        .map(|token| (token.kind, &input[token.span.start()..token.span.end()]))
+        .collect()
+}
+
+#[test]
+fn tokens_of_statements() {
+    let input = "
+        // a comment
+        bb0: {
+            r = &'a mut x.f; @ src/main.rs:42
+            x = f::<'_>(copy *r) -> [return: bb1];
+        }
+    ";
+    assert_debug_snapshot!(tokens(input), @r###"
+    [
+        (
+            Ident,
+            "bb0",
+        ),
+        (
+            Punct,
+            ":",
+        ),
+        (
+            Punct,
+            "{",
+        ),
+        (
+            Ident,
+            "r",
+        ),
+        (
+            Punct,
+            "=",
+        ),
+        (
+            Punct,
+            "&",
+        ),
+        (
+            Origin,
+            "'a",
+        ),
+        (
+            Ident,
+            "mut",
+        ),
+        (
+            Ident,
+            "x",
+        ),
+        (
+            Punct,
+            ".",
+        ),
+        (
+            Ident,
+            "f",
+        ),
+        (
+            Punct,
+            ";",
+        ),
+        (
+            Location,
+            "@ src/main.rs:42",
+        ),
+        (
+            Ident,
+            "x",
+        ),
+        (
+            Punct,
+            "=",
+        ),
+        (
+            Ident,
+            "f",
+        ),
+        (
+            Punct,
+            "::",
+        ),
+        (
+            Punct,
+            "<",
+        ),
+        (
+            Origin,
+            "'_",
+        ),
+        (
+            Punct,
+            ">",
+        ),
+        (
+            Punct,
+            "(",
+        ),
+        (
+            Ident,
+            "copy",
+        ),
+        (
+            Punct,
+            "*",
+        ),
+        (
+            Ident,
+            "r",
+        ),
+        (
+            Punct,
+            ")",
+        ),
+        (
+            Punct,
+            "->",
+        ),
+        (
+            Punct,
+            "[",
+        ),
+        (
+            Ident,
+            "return",
+        ),
+        (
+            Punct,
+            ":",
+        ),
+        (
+            Ident,
+            "bb1",
+        ),
+        (
+            Punct,
+            "]",
+        ),
+        (
+            Punct,
+            ";",
+        ),
+        (
+            Punct,
+            "}",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn unknown_chars_are_error_tokens() {
+    assert_debug_snapshot!(tokens("x = $y + €;"), @r###"
+    [
+        (
+            Ident,
+            "x",
+        ),
+        (
+            Punct,
+            "=",
+        ),
+        (
+            Error,
+            "$",
+        ),
+        (
+            Ident,
+            "y",
+        ),
+        (
+            Error,
+            "+",
+        ),
+        (
+            Error,
+            "€",
+        ),
+        (
+            Punct,
+            ";",
+        ),
+    ]
+    "###);
+
+    // The spans of the tokens are in bytes
+    let error = tokenize("é x")[1];
+    assert_eq!(error.span, Span::new(3, 4));
+}
+
+#[test]
+fn tokens_at_offsets() {
+    let tokens = tokenize("x = = 2;");
+    assert_eq!(token_at(&tokens, 4).unwrap().span, Span::new(4, 5));
+    assert_eq!(token_at(&tokens, 3).unwrap().span, Span::new(4, 5));
+    assert_eq!(token_at(&tokens, 8), None);
+}
+
+#[test]
+fn numbers_and_non_ascii_names() {
+    assert_debug_snapshot!(tokens("größe = 1_000; x = 0xff_ff;"), @r###"
+    [
+        (
+            Ident,
+            "größe",
+        ),
+        (
+            Punct,
+            "=",
+        ),
+        (
+            Ident,
+            "1_000",
+        ),
+        (
+            Punct,
+            ";",
+        ),
+        (
+            Ident,
+            "x",
+        ),
+        (
+            Punct,
+            "=",
+        ),
+        (
+            Ident,
+            "0xff_ff",
+        ),
+        (
+            Punct,
+            ";",
+        ),
+    ]
+    "###);
+}
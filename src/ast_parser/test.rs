@@ -1,8 +1,8 @@
 use super::*;
 
 pub fn expect_parse(s: &str) -> ast::Program {
-    match super::ast_parser::program(s) {
-        Ok(p) => p,
+    match super::ast_parser::program(s, &Bump::new()) {
+        Ok(p) => lower_program(p).unwrap(),
         Err(e) => {
             let offset = e.location.offset;
             panic!(
@@ -15,6 +15,36 @@ pub fn expect_parse(s: &str) -> ast::Program {
     }
 }
 
+// The program, with all its spans replaced by dummy spans, as debug output.
+pub(crate) fn without_spans(mut program: ast::Program) -> String {
+    let decls = program
+        .struct_decls
+        .iter_mut()
+        .flat_map(|s| &mut s.field_decls);
+    for decl in decls.chain(&mut program.variables) {
+        decl.span = Span::dummy();
+    }
+    for prototype in &mut program.fn_prototypes {
+        prototype.span = Span::dummy();
+    }
+    for bb in &mut program.basic_blocks {
+        bb.span = Span::dummy();
+        for s in &mut bb.statements {
+            *s = Sp::new((**s).clone(), Span::dummy());
+        }
+    }
+    for attribute in &mut program.statement_attributes {
+        *attribute = Sp::new(**attribute, Span::dummy());
+    }
+    for location in &mut program.source_locations {
+        *location = Sp::new((**location).clone(), Span::dummy());
+    }
+    for fact in &mut program.raw_facts {
+        *fact = Sp::new((**fact).clone(), Span::dummy());
+    }
+    format!("{:#?}", program)
+}
+
 #[test]
 fn let_test() {
     let p = expect_parse(
@@ -27,13 +57,22 @@ fn let_test() {
     Program {
         struct_decls: [],
         fn_prototypes: [],
+        placeholders: [],
+        known_placeholder_subsets: [],
         variables: [
             VariableDecl {
                 name: "x",
                 ty: I32,
+                span: Span {
+                    start: 13,
+                    end: 14,
+                },
             },
         ],
         basic_blocks: [],
+        statement_attributes: [],
+        source_locations: [],
+        raw_facts: [],
     }
     "###);
 }
@@ -52,10 +91,16 @@ fn statement_test() {
     Program {
         struct_decls: [],
         fn_prototypes: [],
+        placeholders: [],
+        known_placeholder_subsets: [],
         variables: [],
         basic_blocks: [
             BasicBlock {
                 name: "bb0",
+                span: Span {
+                    start: 9,
+                    end: 12,
+                },
                 statements: [
                     Spanned {
                         span: Span {
@@ -73,9 +118,12 @@ fn statement_test() {
                         ),
                     },
                 ],
-                successors: [],
+                terminator: Return,
             },
         ],
+        statement_attributes: [],
+        source_locations: [],
+        raw_facts: [],
     }
     "###);
 }
@@ -100,10 +148,16 @@ fn borrow_test() {
     Program {
         struct_decls: [],
         fn_prototypes: [],
+        placeholders: [],
+        known_placeholder_subsets: [],
         variables: [],
         basic_blocks: [
             BasicBlock {
                 name: "bb0",
+                span: Span {
+                    start: 9,
+                    end: 12,
+                },
                 statements: [
                     Spanned {
                         span: Span {
@@ -163,22 +217,35 @@ fn borrow_test() {
                         ),
                     },
                 ],
-                successors: [
-                    "bb1",
-                    "bb2",
-                ],
+                terminator: Goto(
+                    [
+                        "bb1",
+                        "bb2",
+                    ],
+                ),
             },
             BasicBlock {
                 name: "bb1",
+                span: Span {
+                    start: 132,
+                    end: 135,
+                },
                 statements: [],
-                successors: [],
+                terminator: Return,
             },
             BasicBlock {
                 name: "bb2",
+                span: Span {
+                    start: 149,
+                    end: 152,
+                },
                 statements: [],
-                successors: [],
+                terminator: Return,
             },
         ],
+        statement_attributes: [],
+        source_locations: [],
+        raw_facts: [],
     }
     "###);
 }
@@ -202,23 +269,41 @@ fn copy_move_test() {
     Program {
         struct_decls: [],
         fn_prototypes: [],
+        placeholders: [],
+        known_placeholder_subsets: [],
         variables: [
             VariableDecl {
                 name: "x",
                 ty: I32,
+                span: Span {
+                    start: 13,
+                    end: 14,
+                },
             },
             VariableDecl {
                 name: "y",
                 ty: I32,
+                span: Span {
+                    start: 33,
+                    end: 34,
+                },
             },
             VariableDecl {
                 name: "z",
                 ty: I32,
+                span: Span {
+                    start: 53,
+                    end: 54,
+                },
             },
         ],
         basic_blocks: [
             BasicBlock {
                 name: "bb0",
+                span: Span {
+                    start: 69,
+                    end: 72,
+                },
                 statements: [
                     Spanned {
                         span: Span {
@@ -274,13 +359,67 @@ fn copy_move_test() {
                         ),
                     },
                 ],
-                successors: [],
+                terminator: Return,
             },
         ],
+        statement_attributes: [],
+        source_locations: [],
+        raw_facts: [],
     }
     "###);
 }
 
+#[test]
+fn anonymous_borrow_test() {
+    let p = expect_parse(
+        "
+        bb0: {
+            y = &x;
+            z = &mut x;
+            w = &mutable;
+        }
+    ",
+    );
+
+    // Borrows without an origin have the anonymous origin
+    let rhs: Vec<_> = p.basic_blocks[0]
+        .statements
+        .iter()
+        .map(|s| s.expr().unwrap().clone())
+        .collect();
+    insta::assert_debug_snapshot!(rhs, @r###"
+    [
+        Access {
+            kind: Borrow(
+                "'_",
+            ),
+            place: Place {
+                base: "x",
+                projections: [],
+            },
+        },
+        Access {
+            kind: BorrowMut(
+                "'_",
+            ),
+            place: Place {
+                base: "x",
+                projections: [],
+            },
+        },
+        Access {
+            kind: Borrow(
+                "'_",
+            ),
+            place: Place {
+                base: "mutable",
+                projections: [],
+            },
+        },
+    ]
+    "###);
+}
+
 #[test]
 fn struct_test() {
     let p = expect_parse(
@@ -320,12 +459,21 @@ fn struct_test() {
                                 ],
                             },
                         },
+                        span: Span {
+                            start: 22,
+                            end: 25,
+                        },
                     },
                     VariableDecl {
                         name: "position",
                         ty: I32,
+                        span: Span {
+                            start: 40,
+                            end: 48,
+                        },
                     },
                 ],
+                is_copy: false,
             },
             StructDecl {
                 name: "Vec",
@@ -341,13 +489,23 @@ fn struct_test() {
                             name: "T",
                             parameters: [],
                         },
+                        span: Span {
+                            start: 80,
+                            end: 85,
+                        },
                     },
                 ],
+                is_copy: false,
             },
         ],
         fn_prototypes: [],
+        placeholders: [],
+        known_placeholder_subsets: [],
         variables: [],
         basic_blocks: [],
+        statement_attributes: [],
+        source_locations: [],
+        raw_facts: [],
     }
     "###);
 }
@@ -378,13 +536,22 @@ fn fn_test() {
                             name: "T",
                             parameters: [],
                         },
+                        span: Span {
+                            start: 25,
+                            end: 32,
+                        },
                     },
                 ],
+                is_copy: false,
             },
         ],
         fn_prototypes: [
             FnPrototype {
                 name: "Vec_push",
+                span: Span {
+                    start: 49,
+                    end: 57,
+                },
                 generic_decls: [
                     Origin(
                         "'v",
@@ -414,10 +581,16 @@ fn fn_test() {
                     },
                 ],
                 ret_ty: Unit,
+                is_method: false,
             },
         ],
+        placeholders: [],
+        known_placeholder_subsets: [],
         variables: [],
         basic_blocks: [],
+        statement_attributes: [],
+        source_locations: [],
+        raw_facts: [],
     }
     "###);
 }
@@ -476,3 +649,1138 @@ fn example_issue_47680() {
     ";
     insta::assert_debug_snapshot!(expect_parse(program));
 }
+
+#[test]
+fn let_initializer_test() {
+    let p = expect_parse(
+        "
+        let x: i32;
+        bb0: {
+            let y: &'y i32 = &'L_x x;
+            let z = copy y;
+        }
+    ",
+    );
+
+    // Declarations with an initializer are lowered to a declaration and an assignment
+    insta::assert_debug_snapshot!((&p.variables, &p.basic_blocks[0].statements), @r###"
+    (
+        [
+            VariableDecl {
+                name: "x",
+                ty: I32,
+                span: Span {
+                    start: 13,
+                    end: 14,
+                },
+            },
+            VariableDecl {
+                name: "y",
+                ty: Ref {
+                    origin: "'y",
+                    ty: I32,
+                },
+                span: Span {
+                    start: 52,
+                    end: 53,
+                },
+            },
+            VariableDecl {
+                name: "z",
+                ty: Infer,
+                span: Span {
+                    start: 90,
+                    end: 91,
+                },
+            },
+        ],
+        [
+            Spanned {
+                span: Span {
+                    start: 48,
+                    end: 73,
+                },
+                inner: Assign(
+                    Place {
+                        base: "y",
+                        projections: [],
+                    },
+                    Access {
+                        kind: Borrow(
+                            "'L_x",
+                        ),
+                        place: Place {
+                            base: "x",
+                            projections: [],
+                        },
+                    },
+                ),
+            },
+            Spanned {
+                span: Span {
+                    start: 86,
+                    end: 101,
+                },
+                inner: Assign(
+                    Place {
+                        base: "z",
+                        projections: [],
+                    },
+                    Access {
+                        kind: Copy,
+                        place: Place {
+                            base: "y",
+                            projections: [],
+                        },
+                    },
+                ),
+            },
+        ],
+    )
+    "###);
+}
+
+#[test]
+fn nested_expr_test() {
+    let p = expect_parse(
+        "
+        bb0: {
+            x = foo(&'a y.f).g;
+        }
+    ",
+    );
+
+    // Nested expressions are evaluated into temporaries by the preceding statements
+    insta::assert_debug_snapshot!((&p.variables, &p.basic_blocks[0].statements), @r###"
+    (
+        [
+            VariableDecl {
+                name: "_tmp0",
+                ty: Infer,
+                span: Span {
+                    start: 36,
+                    end: 43,
+                },
+            },
+            VariableDecl {
+                name: "_tmp1",
+                ty: Infer,
+                span: Span {
+                    start: 32,
+                    end: 44,
+                },
+            },
+        ],
+        [
+            Spanned {
+                span: Span {
+                    start: 36,
+                    end: 43,
+                },
+                inner: Assign(
+                    Place {
+                        base: "_tmp0",
+                        projections: [],
+                    },
+                    Access {
+                        kind: Borrow(
+                            "'a",
+                        ),
+                        place: Place {
+                            base: "y",
+                            projections: [
+                                Field(
+                                    "f",
+                                ),
+                            ],
+                        },
+                    },
+                ),
+            },
+            Spanned {
+                span: Span {
+                    start: 32,
+                    end: 44,
+                },
+                inner: Assign(
+                    Place {
+                        base: "_tmp1",
+                        projections: [],
+                    },
+                    Call {
+                        name: "foo",
+                        generic_args: [],
+                        arguments: [
+                            Access {
+                                kind: Move,
+                                place: Place {
+                                    base: "_tmp0",
+                                    projections: [],
+                                },
+                            },
+                        ],
+                    },
+                ),
+            },
+            Spanned {
+                span: Span {
+                    start: 28,
+                    end: 47,
+                },
+                inner: Assign(
+                    Place {
+                        base: "x",
+                        projections: [],
+                    },
+                    Access {
+                        kind: Move,
+                        place: Place {
+                            base: "_tmp1",
+                            projections: [
+                                Field(
+                                    "g",
+                                ),
+                            ],
+                        },
+                    },
+                ),
+            },
+        ],
+    )
+    "###);
+}
+
+#[test]
+fn cast_test() {
+    let p = expect_parse(
+        "
+        let p: *const i32;
+
+        bb0: {
+            p = &'a x as *const i32 as *mut i32;
+        }
+    ",
+    );
+
+    // Casts are left-associative, and their borrowed operands are evaluated into temporaries
+    insta::assert_debug_snapshot!((&p.variables, &p.basic_blocks[0].statements), @r###"
+    (
+        [
+            VariableDecl {
+                name: "p",
+                ty: RawPtr {
+                    ty: I32,
+                },
+                span: Span {
+                    start: 13,
+                    end: 14,
+                },
+            },
+            VariableDecl {
+                name: "_tmp0",
+                ty: Infer,
+                span: Span {
+                    start: 60,
+                    end: 65,
+                },
+            },
+            VariableDecl {
+                name: "_tmp1",
+                ty: Infer,
+                span: Span {
+                    start: 60,
+                    end: 79,
+                },
+            },
+        ],
+        [
+            Spanned {
+                span: Span {
+                    start: 60,
+                    end: 65,
+                },
+                inner: Assign(
+                    Place {
+                        base: "_tmp0",
+                        projections: [],
+                    },
+                    Access {
+                        kind: Borrow(
+                            "'a",
+                        ),
+                        place: Place {
+                            base: "x",
+                            projections: [],
+                        },
+                    },
+                ),
+            },
+            Spanned {
+                span: Span {
+                    start: 60,
+                    end: 79,
+                },
+                inner: Assign(
+                    Place {
+                        base: "_tmp1",
+                        projections: [],
+                    },
+                    Cast {
+                        operand: Access {
+                            kind: Move,
+                            place: Place {
+                                base: "_tmp0",
+                                projections: [],
+                            },
+                        },
+                        ty: RawPtr {
+                            ty: I32,
+                        },
+                    },
+                ),
+            },
+            Spanned {
+                span: Span {
+                    start: 56,
+                    end: 92,
+                },
+                inner: Assign(
+                    Place {
+                        base: "p",
+                        projections: [],
+                    },
+                    Cast {
+                        operand: Access {
+                            kind: Move,
+                            place: Place {
+                                base: "_tmp1",
+                                projections: [],
+                            },
+                        },
+                        ty: RawPtrMut {
+                            ty: I32,
+                        },
+                    },
+                ),
+            },
+        ],
+    )
+    "###);
+}
+
+#[test]
+fn control_flow_test() {
+    let p = expect_parse(
+        "
+        bb0: {
+            x = 1;
+            if copy x {
+                y = 2;
+            } else {
+                while copy y {
+                    y = 3;
+                }
+            }
+            loop {
+                break;
+            }
+            z = 4;
+            goto bb1;
+        }
+
+        bb1: { }
+    ",
+    );
+
+    // Structured control flow is desugared to basic blocks, whose statements span the
+    // original statements and conditions
+    let cfg: Vec<_> = p
+        .basic_blocks
+        .iter()
+        .map(|bb| {
+            let statements: Vec<_> = bb.statements.iter().map(|s| s.span()).collect();
+            (&bb.name, statements, bb.terminator.successors())
+        })
+        .collect();
+    insta::assert_debug_snapshot!(cfg, @r###"
+    [
+        (
+            "bb0",
+            [
+                Span {
+                    start: 28,
+                    end: 34,
+                },
+                Span {
+                    start: 50,
+                    end: 56,
+                },
+            ],
+            [
+                "bb0_1",
+                "bb0_2",
+            ],
+        ),
+        (
+            "bb0_1",
+            [
+                Span {
+                    start: 75,
+                    end: 81,
+                },
+            ],
+            [
+                "bb0_3",
+            ],
+        ),
+        (
+            "bb0_2",
+            [],
+            [
+                "bb0_4",
+            ],
+        ),
+        (
+            "bb0_3",
+            [],
+            [
+                "bb0_7",
+            ],
+        ),
+        (
+            "bb0_4",
+            [
+                Span {
+                    start: 125,
+                    end: 131,
+                },
+            ],
+            [
+                "bb0_5",
+                "bb0_6",
+            ],
+        ),
+        (
+            "bb0_5",
+            [
+                Span {
+                    start: 154,
+                    end: 160,
+                },
+            ],
+            [
+                "bb0_4",
+            ],
+        ),
+        (
+            "bb0_6",
+            [],
+            [
+                "bb0_3",
+            ],
+        ),
+        (
+            "bb0_7",
+            [],
+            [
+                "bb0_8",
+            ],
+        ),
+        (
+            "bb0_8",
+            [
+                Span {
+                    start: 261,
+                    end: 267,
+                },
+            ],
+            [
+                "bb1",
+            ],
+        ),
+        (
+            "bb0_9",
+            [],
+            [
+                "bb0_7",
+            ],
+        ),
+        (
+            "bb1",
+            [],
+            [],
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn break_outside_of_loop() {
+    let result = parse_ast("bb0: { break; }");
+    assert_eq!(result.unwrap_err().to_string(), "`break` outside of a loop");
+}
+
+#[test]
+fn continue_test() {
+    let p = expect_parse(
+        "
+        bb0: {
+            while copy x {
+                if copy y {
+                    continue;
+                }
+                loop {
+                    continue;
+                }
+            }
+        }
+    ",
+    );
+
+    // `continue` goes back to the condition of the `while`, and to the body of the `loop`
+    let cfg: Vec<_> = p
+        .basic_blocks
+        .iter()
+        .map(|bb| (&bb.name, bb.terminator.successors()))
+        .collect();
+    insta::assert_debug_snapshot!(cfg, @r###"
+    [
+        (
+            "bb0",
+            [
+                "bb0_1",
+            ],
+        ),
+        (
+            "bb0_1",
+            [
+                "bb0_2",
+                "bb0_3",
+            ],
+        ),
+        (
+            "bb0_2",
+            [
+                "bb0_4",
+                "bb0_5",
+            ],
+        ),
+        (
+            "bb0_3",
+            [],
+        ),
+        (
+            "bb0_4",
+            [
+                "bb0_1",
+            ],
+        ),
+        (
+            "bb0_5",
+            [
+                "bb0_7",
+            ],
+        ),
+        (
+            "bb0_6",
+            [
+                "bb0_5",
+            ],
+        ),
+        (
+            "bb0_7",
+            [
+                "bb0_7",
+            ],
+        ),
+        (
+            "bb0_8",
+            [
+                "bb0_1",
+            ],
+        ),
+        (
+            "bb0_9",
+            [
+                "bb0_7",
+            ],
+        ),
+    ]
+    "###);
+
+    let result = parse_ast("bb0: { continue; }");
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "`continue` outside of a loop"
+    );
+}
+
+#[test]
+fn turbofish_test() {
+    let p = expect_parse(
+        "
+        fn id<'a, T>(x: T) -> T;
+
+        let x: &'x i32;
+
+        bb0: {
+            x = id::<'b, &'c i32>(copy x);
+        }
+    ",
+    );
+
+    let expr = p.basic_blocks[0].statements[0].expr().unwrap();
+    insta::assert_debug_snapshot!(expr, @r###"
+    Call {
+        name: "id",
+        generic_args: [
+            Origin(
+                "'b",
+            ),
+            Ty(
+                Ref {
+                    origin: "'c",
+                    ty: I32,
+                },
+            ),
+        ],
+        arguments: [
+            Access {
+                kind: Copy,
+                place: Place {
+                    base: "x",
+                    projections: [],
+                },
+            },
+        ],
+    }
+    "###);
+}
+
+#[test]
+fn impl_test() {
+    // The origins elided from the signatures are declared, and the ones of the return types are
+    // the origin of `self`
+    let p = expect_parse(
+        "
+        struct Vec<T> { item: T }
+        impl<T> Vec<T> {
+            fn new() -> Self;
+            fn push(&mut self, value: T) -> ();
+            fn first(&self) -> &T;
+        }
+
+        let v: Vec<i32>;
+
+        bb0: {
+            v.push(1);
+        }
+    ",
+    );
+
+    let prototypes: Vec<_> = p
+        .fn_prototypes
+        .iter()
+        .map(|f| {
+            (
+                &f.name,
+                &f.generic_decls,
+                &f.arg_tys,
+                &f.ret_ty,
+                f.is_method,
+            )
+        })
+        .collect();
+    insta::assert_debug_snapshot!(prototypes, @r###"
+    [
+        (
+            "Vec::new",
+            [
+                Ty(
+                    "T",
+                ),
+            ],
+            [],
+            Struct {
+                name: "Vec",
+                parameters: [
+                    Ty(
+                        Struct {
+                            name: "T",
+                            parameters: [],
+                        },
+                    ),
+                ],
+            },
+            false,
+        ),
+        (
+            "Vec::push",
+            [
+                Ty(
+                    "T",
+                ),
+                Origin(
+                    "'_0",
+                ),
+            ],
+            [
+                RefMut {
+                    origin: "'_0",
+                    ty: Struct {
+                        name: "Vec",
+                        parameters: [
+                            Ty(
+                                Struct {
+                                    name: "T",
+                                    parameters: [],
+                                },
+                            ),
+                        ],
+                    },
+                },
+                Struct {
+                    name: "T",
+                    parameters: [],
+                },
+            ],
+            Unit,
+            true,
+        ),
+        (
+            "Vec::first",
+            [
+                Ty(
+                    "T",
+                ),
+                Origin(
+                    "'_0",
+                ),
+            ],
+            [
+                Ref {
+                    origin: "'_0",
+                    ty: Struct {
+                        name: "Vec",
+                        parameters: [
+                            Ty(
+                                Struct {
+                                    name: "T",
+                                    parameters: [],
+                                },
+                            ),
+                        ],
+                    },
+                },
+            ],
+            Ref {
+                origin: "'_0",
+                ty: Struct {
+                    name: "T",
+                    parameters: [],
+                },
+            },
+            true,
+        ),
+    ]
+    "###);
+
+    let expr = p.basic_blocks[0].statements[1].expr().unwrap();
+    insta::assert_debug_snapshot!(expr, @r###"
+    Call {
+        name: "Vec::push",
+        generic_args: [],
+        arguments: [
+            Access {
+                kind: Move,
+                place: Place {
+                    base: "_tmp0",
+                    projections: [],
+                },
+            },
+            Number {
+                value: 1,
+            },
+        ],
+    }
+    "###);
+}
+
+#[test]
+fn unknown_method() {
+    let program = "
+        struct Vec<T> { item: T }
+        impl<T> Vec<T> {
+            fn push(&mut self, value: T) -> ();
+        }
+
+        let v: Vec<i32>;
+
+        bb0: {
+            v.pop();
+        }
+    ";
+    let error = parse_ast(program).unwrap_err();
+    assert_eq!(error.to_string(), "unknown method `pop`");
+}
+
+#[test]
+fn statement_attributes() {
+    // Attributes span the statement they're on, without themselves
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            #[two_phase] #[no_invalidate]
+            x = 1;
+            if copy x {
+                #[no_invalidate] y = &x;
+            }
+        }
+    ";
+    let p = expect_parse(program);
+    let attributes: Vec<_> = p
+        .statement_attributes
+        .iter()
+        .map(|attribute| {
+            (
+                **attribute,
+                &program[attribute.span().start()..attribute.span().end()],
+            )
+        })
+        .collect();
+    insta::assert_debug_snapshot!(attributes, @r###"
+    [
+        (
+            TwoPhase,
+            "x = 1;",
+        ),
+        (
+            NoInvalidate,
+            "x = 1;",
+        ),
+        (
+            NoInvalidate,
+            "y = &x;",
+        ),
+    ]
+    "###);
+
+    let error = super::ast_parser::program("bb0: { #[unknown] x = 1; }", &Bump::new()).unwrap_err();
+    assert!(error
+        .expected
+        .tokens()
+        .any(|token| token == "a known attribute, `two_phase` or `no_invalidate`"));
+}
+
+#[test]
+fn terminators() {
+    let p = expect_parse(
+        "
+        bb0: {
+            switch(copy x) -> [bb1, bb2];
+        }
+        bb1: {
+            y = f(&x.f) -> [return: bb2, unwind: bb3];
+        }
+        bb2: {
+            return;
+        }
+        bb3: {
+            unreachable;
+        }
+        bb4: { }
+    ",
+    );
+
+    // The operands are evaluated by the last statement of the block
+    let blocks: Vec<_> = p
+        .basic_blocks
+        .iter()
+        .map(|bb| {
+            let statements: Vec<_> = bb.statements.iter().map(|s| &**s).collect();
+            (statements, &bb.terminator)
+        })
+        .collect();
+    insta::assert_debug_snapshot!(blocks, @r###"
+    [
+        (
+            [
+                Expr(
+                    Access {
+                        kind: Copy,
+                        place: Place {
+                            base: "x",
+                            projections: [],
+                        },
+                    },
+                ),
+            ],
+            Switch {
+                discriminant: Access {
+                    kind: Copy,
+                    place: Place {
+                        base: "x",
+                        projections: [],
+                    },
+                },
+                targets: [
+                    "bb1",
+                    "bb2",
+                ],
+            },
+        ),
+        (
+            [
+                Assign(
+                    Place {
+                        base: "_tmp0",
+                        projections: [],
+                    },
+                    Access {
+                        kind: Borrow(
+                            "'_",
+                        ),
+                        place: Place {
+                            base: "x",
+                            projections: [
+                                Field(
+                                    "f",
+                                ),
+                            ],
+                        },
+                    },
+                ),
+                Assign(
+                    Place {
+                        base: "y",
+                        projections: [],
+                    },
+                    Call {
+                        name: "f",
+                        generic_args: [],
+                        arguments: [
+                            Access {
+                                kind: Move,
+                                place: Place {
+                                    base: "_tmp0",
+                                    projections: [],
+                                },
+                            },
+                        ],
+                    },
+                ),
+            ],
+            Call {
+                destination: Some(
+                    Place {
+                        base: "y",
+                        projections: [],
+                    },
+                ),
+                call: Call {
+                    name: "f",
+                    generic_args: [],
+                    arguments: [
+                        Access {
+                            kind: Move,
+                            place: Place {
+                                base: "_tmp0",
+                                projections: [],
+                            },
+                        },
+                    ],
+                },
+                target: "bb2",
+                unwind: Some(
+                    "bb3",
+                ),
+            },
+        ),
+        (
+            [],
+            Return,
+        ),
+        (
+            [],
+            Unreachable,
+        ),
+        (
+            [],
+            Return,
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn end_and_kill_statements() {
+    let p = expect_parse(
+        "
+        let x: i32;
+        bb0: {
+            end('a);
+            kill(x.f);
+        }
+    ",
+    );
+    let statements: Vec<_> = p.basic_blocks[0].statements.iter().map(|s| &**s).collect();
+    insta::assert_debug_snapshot!(statements, @r###"
+    [
+        End(
+            "'a",
+        ),
+        Kill(
+            Place {
+                base: "x",
+                projections: [
+                    Field(
+                        "f",
+                    ),
+                ],
+            },
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn index_test() {
+    let p = expect_parse(
+        "
+        let a: [&'a i32; 3];
+
+        bb0: {
+            x = copy (*a[i]).f[0];
+        }
+    ",
+    );
+    insta::assert_debug_snapshot!((&p.variables[0].ty, &p.basic_blocks[0].statements[0]), @r###"
+    (
+        Array {
+            ty: Ref {
+                origin: "'a",
+                ty: I32,
+            },
+            len: 3,
+        },
+        Spanned {
+            span: Span {
+                start: 58,
+                end: 80,
+            },
+            inner: Assign(
+                Place {
+                    base: "x",
+                    projections: [],
+                },
+                Access {
+                    kind: Copy,
+                    place: Place {
+                        base: "a",
+                        projections: [
+                            Index(
+                                "i",
+                            ),
+                            Deref,
+                            Field(
+                                "f",
+                            ),
+                            Index(
+                                "0",
+                            ),
+                        ],
+                    },
+                },
+            ),
+        },
+    )
+    "###);
+}
+
+#[test]
+fn place_overlaps() {
+    let overlaps = |a: &str, b: &str| place(a).unwrap().overlaps(&place(b).unwrap());
+    assert!(overlaps("x", "x.f.g"));
+    assert!(overlaps("x.f", "x"));
+    assert!(overlaps("(*x).f", "*x"));
+    assert!(overlaps("x[i]", "x[0]"));
+    assert!(overlaps("x[0].f", "x[0]"));
+    assert!(overlaps("x[0]", "x[1]"));
+    assert!(!overlaps("x.f", "x.g"));
+    assert!(!overlaps("x[0].f", "x[1].g"));
+    assert!(!overlaps("x", "y"));
+
+    let is_prefix_of = |a: &str, b: &str| place(a).unwrap().is_prefix_of(&place(b).unwrap());
+    assert!(is_prefix_of("x", "x"));
+    assert!(is_prefix_of("*x", "(*x).f"));
+    assert!(!is_prefix_of("x.f", "x"));
+    assert!(!is_prefix_of("x[0]", "x[i]"));
+
+    let display = |a: &str| place(a).unwrap().to_string();
+    assert_eq!(display("(*x.f)[i]"), "(*x.f)[i]");
+    assert_eq!(display("**x"), "**x");
+    assert_eq!(display("*(*x).f"), "*(*x).f");
+}
+
+#[test]
+fn source_locations() {
+    // The location of a statement applies to the statements lowered from it
+    let p = expect_parse(
+        "
+        let x: i32;
+        let y: &'y i32;
+        bb0: {
+            x = 1; @ main.rs:2
+            y = &'L_x x;
+            use(id(copy y)); @ src/lib.rs:14
+        }
+    ",
+    );
+    let locations: Vec<_> = p.basic_blocks[0]
+        .statements
+        .iter()
+        .map(|s| p.source_location(s.span()).map(ToString::to_string))
+        .collect();
+    insta::assert_debug_snapshot!(locations, @r###"
+    [
+        Some(
+            "main.rs:2",
+        ),
+        None,
+        Some(
+            "src/lib.rs:14",
+        ),
+        Some(
+            "src/lib.rs:14",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn numeric_literals_and_non_ascii_names() {
+    let p = expect_parse(
+        "
+        let größe: [i32; 0x10];
+        bb0: {
+            größe = 1_000;
+            größe = 0xff_ff;
+        }
+    ",
+    );
+    insta::assert_display_snapshot!(p, @r###"
+    let größe: [i32; 16];
+    bb0: {
+        größe = 1000;
+        größe = 65535;
+    }
+    "###);
+
+    let error =
+        ast_parser::program("let x: i32; bb0: { x = 3_000_000_000; }", &Bump::new()).unwrap_err();
+    insta::assert_display_snapshot!(error.expected, @r###"
+    one of "(", ".", "::", "[", a number fitting in `i32`, an expression
+    "###);
+}
@@ -0,0 +1,247 @@
+//! Analysis of many programs at once, e.g. a corpus directory, in parallel.
+//!
+//! Each program is parsed, validated, has its facts emitted, and is optionally solved, on one
+//! of a pool of worker threads. The programs are independent: a program failing to parse or
+//! exceeding the limits is reported as such, and doesn't stop the others. The reports are in the
+//! order of the programs, whichever thread finished first.
+//!
+//! Legacy fact sets, e.g. the ones rustc dumps for each function of a crate, are analyzed the
+//! same way, see `analyze_fact_sets`.
+
+use crate::diagnostics::{diagnose_with_limits, Diagnostic};
+use crate::fact_emitter::{emit_facts_with_options, EmitOptions};
+use crate::json::Json;
+use crate::legacy::LegacyFacts;
+use crate::sources::Sources;
+use eyre::WrapErr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod test;
+
+/// How the programs of a batch are analyzed.
+#[derive(Clone, Debug)]
+pub struct BatchOptions {
+    /// How the facts of each program are emitted, and the limits of each analysis.
+    pub emit: EmitOptions,
+
+    /// Whether the facts are solved, to report the errors of each program, or only emitted.
+    pub solve: bool,
+
+    /// The number of worker threads, at least 1.
+    pub jobs: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            emit: EmitOptions::default(),
+            solve: true,
+            jobs: std::thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        }
+    }
+}
+
+/// The result of analyzing one program of a batch.
+#[derive(Debug)]
+pub struct ProgramReport {
+    pub path: PathBuf,
+
+    /// The outcome of the analysis, or why the program couldn't be analyzed, e.g. its parse or
+    /// validation errors, rendered.
+    pub outcome: eyre::Result<ProgramOutcome>,
+
+    /// The time the analysis of this program took.
+    pub elapsed: Duration,
+}
+
+/// What was found in a program which could be analyzed.
+#[derive(Debug)]
+pub struct ProgramOutcome {
+    /// The warnings of the validation of the program, and of its lints, rendered.
+    pub warnings: Vec<String>,
+
+    /// The number of facts emitted for the program.
+    pub fact_count: usize,
+
+    /// The errors of the program, when the facts were solved.
+    pub errors: Option<Vec<Diagnostic>>,
+
+    /// The errors, rendered with the lines of the program's files they're at, see
+    /// `Sources::render_diagnostic`.
+    pub rendered_errors: Vec<String>,
+
+    /// The warnings and errors as JSON, located in the program's files, see
+    /// `Sources::diagnostic_to_json`.
+    pub json: Vec<Json>,
+}
+
+impl ProgramReport {
+    /// Whether the program was analyzed, and has no errors.
+    pub fn is_ok(&self) -> bool {
+        match &self.outcome {
+            Ok(outcome) => outcome.errors.as_ref().is_none_or(Vec::is_empty),
+            Err(_) => false,
+        }
+    }
+}
+
+/// The programs of the paths: the files themselves, and the `.txt` files under the directories,
+/// in path order.
+pub fn collect_programs(paths: &[PathBuf]) -> eyre::Result<Vec<PathBuf>> {
+    let mut programs = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut found = Vec::new();
+            collect_dir(path, &mut found)?;
+            found.sort();
+            programs.extend(found);
+        } else {
+            programs.push(path.clone());
+        }
+    }
+    Ok(programs)
+}
+
+fn collect_dir(dir: &Path, programs: &mut Vec<PathBuf>) -> eyre::Result<()> {
+    let entries =
+        std::fs::read_dir(dir).wrap_err_with(|| format!("failed to read `{}`", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_dir(&path, programs)?;
+        } else if path.extension().is_some_and(|extension| extension == "txt") {
+            programs.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Analyzes the programs in parallel, and returns their reports, in the same order.
+pub fn analyze_programs(programs: &[PathBuf], options: &BatchOptions) -> Vec<ProgramReport> {
+    analyze_in_parallel(programs, options, analyze_program)
+}
+
+/// Analyzes the legacy fact set directories in parallel, like `analyze_programs`. Their facts
+/// are not emitted, so only the solver's limits apply, and their errors have no spans.
+pub fn analyze_fact_sets(dirs: &[PathBuf], options: &BatchOptions) -> Vec<ProgramReport> {
+    analyze_in_parallel(dirs, options, analyze_fact_set)
+}
+
+fn analyze_in_parallel(
+    programs: &[PathBuf],
+    options: &BatchOptions,
+    analyze: fn(&Path, &BatchOptions) -> eyre::Result<ProgramOutcome>,
+) -> Vec<ProgramReport> {
+    // The workers take the next program to analyze until there are none left
+    let next = AtomicUsize::new(0);
+    let reports: Mutex<Vec<Option<ProgramReport>>> =
+        Mutex::new(programs.iter().map(|_| None).collect());
+    let jobs = options.jobs.clamp(1, programs.len().max(1));
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let path = match programs.get(idx) {
+                    Some(path) => path,
+                    None => break,
+                };
+                let start = Instant::now();
+                let outcome = analyze(path, options);
+                let report = ProgramReport {
+                    path: path.clone(),
+                    outcome,
+                    elapsed: start.elapsed(),
+                };
+                reports.lock().unwrap()[idx] = Some(report);
+            });
+        }
+    });
+    reports
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|report| report.expect("all the programs are analyzed"))
+        .collect()
+}
+
+#[tracing::instrument(skip(options))]
+fn analyze_program(path: &Path, options: &BatchOptions) -> eyre::Result<ProgramOutcome> {
+    // The timeout of each program starts with its analysis
+    let mut emit = options.emit.clone();
+    emit.limits.started = Some(Instant::now());
+
+    let sources = Sources::load(path)?;
+    let (errors, warnings): (Vec<_>, Vec<_>) = sources
+        .validate()?
+        .into_iter()
+        .partition(|diagnostic| diagnostic.is_error());
+    if !errors.is_empty() {
+        let rendered: String = errors.iter().map(|error| sources.render(error)).collect();
+        eyre::bail!("{}found {} error(s)", rendered, errors.len());
+    }
+    let facts = emit_facts_with_options(sources.text(), &emit)?;
+    let warnings: Vec<_> = warnings
+        .into_iter()
+        .chain(sources.lint(&facts, &emit.limits)?)
+        .collect();
+    let errors = if options.solve {
+        Some(diagnose_with_limits(&facts, &emit.limits)?)
+    } else {
+        None
+    };
+    let json = warnings
+        .iter()
+        .map(|warning| sources.to_json(warning))
+        .chain(
+            errors
+                .iter()
+                .flatten()
+                .map(|error| sources.diagnostic_to_json(error, &facts)),
+        )
+        .collect();
+    let rendered_errors = errors
+        .iter()
+        .flatten()
+        .map(|error| sources.render_diagnostic(error, &facts))
+        .collect();
+    Ok(ProgramOutcome {
+        warnings: warnings
+            .iter()
+            .map(|warning| sources.render(warning))
+            .collect(),
+        fact_count: facts.fact_count(),
+        errors,
+        rendered_errors,
+        json,
+    })
+}
+
+#[tracing::instrument(skip(options))]
+fn analyze_fact_set(dir: &Path, options: &BatchOptions) -> eyre::Result<ProgramOutcome> {
+    let facts = LegacyFacts::read(dir)?.to_facts();
+    let errors = if options.solve {
+        Some(diagnose_with_limits(&facts, &options.emit.limits)?)
+    } else {
+        None
+    };
+    let file_name = dir.display().to_string();
+    let json = errors
+        .iter()
+        .flatten()
+        .map(|error| error.to_json(&facts, &file_name, ""))
+        .collect();
+
+    // Fact sets have no spans: their errors are rendered without snippets
+    let rendered_errors = errors.iter().flatten().map(ToString::to_string).collect();
+    Ok(ProgramOutcome {
+        warnings: Vec::new(),
+        fact_count: facts.fact_count(),
+        errors,
+        rendered_errors,
+        json,
+    })
+}
@@ -0,0 +1,115 @@
+use super::*;
+use crate::test_util::write_files;
+use insta::assert_display_snapshot;
+
+// One line per report, with the paths relative to the directory.
+fn summarize(reports: &[ProgramReport], dir: &Path) -> String {
+    reports
+        .iter()
+        .map(|report| {
+            let path = report.path.strip_prefix(dir).unwrap().display();
+            match &report.outcome {
+                Ok(outcome) => format!(
+                    "{}: {} facts, {:?} error(s)\n",
+                    path,
+                    outcome.fact_count,
+                    outcome.errors.as_ref().map(Vec::len)
+                ),
+                Err(e) => {
+                    let message = e.to_string().replace(&dir.display().to_string(), "<dir>");
+                    format!("{}: failed: {}\n", path, message.lines().last().unwrap())
+                }
+            }
+        })
+        .collect()
+}
+
+const VALID: &str = "
+let x: i32;
+let r: &'r i32;
+bb0: {
+    r = &'L_x x;
+    use(copy r);
+    x = 4;
+}
+";
+
+const INVALID: &str = "
+let x: i32;
+let r: &'r i32;
+bb0: {
+    r = &'L_x x;
+    x = 4;
+    use(copy r);
+}
+";
+
+#[test]
+fn directories() {
+    // The `.txt` files of directories are found recursively, in path order
+    let dir = write_files(
+        "batch-directories",
+        &[
+            ("b.txt", VALID),
+            ("a/c.txt", VALID),
+            ("a/notes.md", ""),
+            ("a.txt", VALID),
+        ],
+    );
+    let programs = collect_programs(&[dir.join("b.txt"), dir.clone()]).unwrap();
+    let programs: Vec<_> = programs
+        .iter()
+        .map(|path| path.strip_prefix(&dir).unwrap().display().to_string())
+        .collect();
+    assert_eq!(programs, ["b.txt", "a/c.txt", "a.txt", "b.txt"]);
+}
+
+#[test]
+fn reports_are_in_program_order() {
+    let dir = write_files(
+        "batch-reports",
+        &[
+            ("valid.txt", VALID),
+            ("invalid.txt", INVALID),
+            ("unparsable.txt", "let x: i32;\nbb0: { x = ; }\n"),
+            ("unknown-variable.txt", "bb0: { use(copy x); }\n"),
+        ],
+    );
+    let programs: Vec<_> = [
+        "valid.txt",
+        "invalid.txt",
+        "unparsable.txt",
+        "unknown-variable.txt",
+        "missing.txt",
+    ]
+    .iter()
+    .map(|path| dir.join(path))
+    .collect();
+
+    let options = BatchOptions {
+        jobs: 3,
+        ..BatchOptions::default()
+    };
+    let reports = analyze_programs(&programs, &options);
+    assert_display_snapshot!(summarize(&reports, &dir), @r###"
+    valid.txt: 31 facts, Some(0) error(s)
+    invalid.txt: 32 facts, Some(1) error(s)
+    unparsable.txt: failed: found 1 error(s)
+    unknown-variable.txt: failed: found 1 error(s)
+    missing.txt: failed: failed to read `<dir>/missing.txt`
+    "###);
+    let oks: Vec<_> = reports.iter().map(ProgramReport::is_ok).collect();
+    assert_eq!(oks, [true, false, false, false, false]);
+
+    // Without solving, the programs with borrow errors are fine
+    let options = BatchOptions {
+        solve: false,
+        ..options
+    };
+    let reports = analyze_programs(&programs[..2], &options);
+    assert_display_snapshot!(summarize(&reports, &dir), @r###"
+    valid.txt: 31 facts, None error(s)
+    invalid.txt: 32 facts, None error(s)
+    "###);
+    assert!(reports.iter().all(ProgramReport::is_ok));
+}
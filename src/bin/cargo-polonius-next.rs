@@ -0,0 +1,20 @@
+//! The `cargo polonius-next` subcommand: runs the `cargo` command of the `polonius` binary,
+//! installed next to this one, on the crate of the current directory.
+
+use eyre::WrapErr;
+use std::process::Command;
+
+fn main() -> eyre::Result<()> {
+    // Cargo passes the name of the subcommand first
+    let args = std::env::args()
+        .skip(1)
+        .skip_while(|arg| arg == "polonius-next");
+    let polonius = std::env::current_exe()?
+        .with_file_name(format!("polonius{}", std::env::consts::EXE_SUFFIX));
+    let status = Command::new(&polonius)
+        .arg("cargo")
+        .args(args)
+        .status()
+        .wrap_err_with(|| format!("failed to run `{}`", polonius.display()))?;
+    std::process::exit(status.code().unwrap_or(2))
+}
@@ -0,0 +1,80 @@
+//! Analysis of real crates: cargo builds the crate with rustc's `-Znll-facts`, which dumps the
+//! legacy fact set of each of its functions in a directory named after the function, and each
+//! fact set is then translated and analyzed like a batch of programs, see
+//! `analyze_fact_sets`.
+//!
+//! `-Znll-facts` is unstable: the crate has to be built with a nightly toolchain, e.g. selected
+//! with `RUSTUP_TOOLCHAIN=nightly`. Only the crate's own functions are dumped, not the ones of
+//! its dependencies.
+
+use eyre::WrapErr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(test)]
+mod test;
+
+/// Builds the crate with cargo, in the current directory, with `cargo rustc` and the given
+/// arguments, e.g. `--lib` or `--bin <name>`, dumping the fact sets of its functions in the
+/// directory. Its previous contents are removed, so that only the functions of this build are
+/// there. Arguments after `--` are passed to rustc, like with `cargo rustc`.
+///
+/// The crate is always rebuilt, even when cargo considers it fresh: rustc only dumps the facts
+/// when it compiles the crate, so each build gets its own `-C metadata`, which cargo doesn't
+/// consider fresh. The metadata only adds to the hashes of the crate's symbols.
+pub fn dump_nll_facts(cargo_args: &[String], facts_dir: &Path) -> eyre::Result<()> {
+    if facts_dir.exists() {
+        std::fs::remove_dir_all(facts_dir)
+            .wrap_err_with(|| format!("failed to remove `{}`", facts_dir.display()))?;
+    }
+    std::fs::create_dir_all(facts_dir)
+        .wrap_err_with(|| format!("failed to create `{}`", facts_dir.display()))?;
+
+    // rustc runs in the directory of the package, which may not be the current one
+    let facts_dir = std::fs::canonicalize(facts_dir)
+        .wrap_err_with(|| format!("failed to resolve `{}`", facts_dir.display()))?;
+    let (cargo_args, rustc_args) = match cargo_args.iter().position(|arg| arg == "--") {
+        Some(idx) => (&cargo_args[..idx], &cargo_args[idx + 1..]),
+        None => (cargo_args, &[][..]),
+    };
+
+    // Cargo tells its subcommands which cargo runs them
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let build_id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let status = Command::new(cargo)
+        .arg("rustc")
+        .args(cargo_args)
+        .arg("--")
+        .args(rustc_args)
+        .arg("-Znll-facts")
+        .arg(format!("-Znll-facts-dir={}", facts_dir.display()))
+        .arg(format!("-Cmetadata=polonius-next-{}", build_id))
+        .status()
+        .wrap_err("failed to run cargo")?;
+    if !status.success() {
+        eyre::bail!("cargo failed to build the crate, `-Znll-facts` needs a nightly toolchain");
+    }
+    Ok(())
+}
+
+/// The fact set directories under the directory, one per function, in path order.
+pub fn collect_fact_sets(facts_dir: &Path) -> eyre::Result<Vec<PathBuf>> {
+    let pattern = facts_dir.join("**").join("cfg_edge.facts");
+    let pattern = pattern
+        .to_str()
+        .ok_or_else(|| eyre::eyre!("invalid fact directory `{}`", facts_dir.display()))?;
+
+    let mut dirs = Vec::new();
+    for cfg_edge_path in glob::glob(pattern)? {
+        let dir = cfg_edge_path?
+            .parent()
+            .expect("fact files are in a directory")
+            .to_path_buf();
+        dirs.push(dir);
+    }
+    dirs.sort();
+    Ok(dirs)
+}
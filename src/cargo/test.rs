@@ -0,0 +1,38 @@
+use super::*;
+use crate::batch::{analyze_fact_sets, BatchOptions};
+use crate::test_util::{write_fact_set, ASSIGN_TO_BORROWED};
+
+// fn empty() {}
+const EMPTY: &[(&str, &str)] = &[("cfg_edge", r#""Start(bb0[0])" "Mid(bb0[0])""#)];
+
+#[test]
+fn analyze_dumped_functions() {
+    // The functions' fact sets, as dumped by rustc, are found under the directory, in path
+    // order, and analyzed like programs
+    let dir = std::env::temp_dir().join(format!("polonius-cargo-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    write_fact_set(&dir.join("main"), EMPTY);
+    write_fact_set(&dir.join("assign_to_borrowed"), ASSIGN_TO_BORROWED);
+    write_fact_set(&dir.join("nested").join("empty"), EMPTY);
+    std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+    let fact_sets = collect_fact_sets(&dir).unwrap();
+    let functions: Vec<_> = fact_sets
+        .iter()
+        .map(|path| path.strip_prefix(&dir).unwrap().display().to_string())
+        .collect();
+    assert_eq!(functions, ["assign_to_borrowed", "main", "nested/empty"]);
+
+    let reports = analyze_fact_sets(&fact_sets, &BatchOptions::default());
+    std::fs::remove_dir_all(&dir).unwrap();
+    let oks: Vec<_> = reports.iter().map(|report| report.is_ok()).collect();
+    assert_eq!(oks, [false, true, true]);
+    let errors = reports[0]
+        .outcome
+        .as_ref()
+        .unwrap()
+        .errors
+        .as_ref()
+        .unwrap();
+    assert_eq!(errors.len(), 1);
+}
@@ -0,0 +1,139 @@
+//! Colors for the terminal output of the fact listings and diagnostics, with ANSI escape codes,
+//! to make large outputs easier to read.
+//!
+//! The outputs are colored after they're rendered, line by line, so that their plain text stays
+//! the reference format: in fact listings, node names are cyan, relation names blue, and
+//! origins yellow. In diagnostics, errors are red, warnings yellow, and the locations blue.
+
+#[cfg(test)]
+mod test;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[33m";
+const BOLD_YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[36m";
+
+fn paint(text: &str, color: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    format!("{}{}{}", color, text, RESET)
+}
+
+/// Colors a fact listing, in the format of the facts' `Display`.
+pub fn colorize_facts(listing: &str) -> String {
+    listing
+        .split_inclusive('\n')
+        .map(|line| {
+            let (content, newline) = split_newline(line);
+            let indent_len = content.len() - content.trim_start().len();
+            let (indent, content) = content.split_at(indent_len);
+
+            let colored = if let Some(successors) = content.strip_prefix("goto") {
+                let successors: String = successors
+                    .split(' ')
+                    .map(|successor| paint(successor, CYAN))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("{}{}", paint("goto", BOLD), successors)
+            } else if let (Some((node, text)), true) =
+                (content.split_once(": "), content.ends_with('{'))
+            {
+                format!("{}: {}", paint(node, CYAN), text)
+            } else if let Some((relation, arguments)) = content.split_once('(') {
+                let arguments = arguments.strip_suffix(')').unwrap_or(arguments);
+                let arguments: Vec<_> = arguments
+                    .split(", ")
+                    .map(|argument| {
+                        if argument.starts_with('\'') {
+                            paint(argument, YELLOW)
+                        } else {
+                            argument.to_string()
+                        }
+                    })
+                    .collect();
+                format!("{}({})", paint(relation, BLUE), arguments.join(", "))
+            } else {
+                content.to_string()
+            };
+            format!("{}{}{}", indent, colored, newline)
+        })
+        .collect()
+}
+
+/// Colors diagnostics, either the diagnostics of the analysis, or of the validation of the
+/// program.
+pub fn colorize_diagnostics(diagnostics: &str) -> String {
+    diagnostics
+        .split_inclusive('\n')
+        .map(|line| {
+            let (content, newline) = split_newline(line);
+            let colored = if let Some((level, message)) = content.split_once(": ") {
+                if level.starts_with("error") {
+                    format!("{}{}", paint(&format!("{}:", level), RED), bold(message))
+                } else if level.starts_with("warning") {
+                    format!(
+                        "{}{}",
+                        paint(&format!("{}:", level), BOLD_YELLOW),
+                        bold(message)
+                    )
+                } else {
+                    colorize_location(content)
+                }
+            } else {
+                colorize_location(content)
+            };
+            format!("{}{}", colored, newline)
+        })
+        .collect()
+}
+
+fn bold(message: &str) -> String {
+    paint(&format!(" {}", message), BOLD)
+}
+
+// Colors the lines of a diagnostic under its message: the `-->` and `:::` locations, and the
+// gutter of the labels and snippets, with the underline of the primary span in red, and of the
+// other spans in blue.
+fn colorize_location(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    for arrow in ["-->", ":::"] {
+        if let Some(location) = trimmed.strip_prefix(arrow) {
+            return format!("{}{}{}", indent, paint(arrow, BLUE), location);
+        }
+    }
+    if let Some(help) = trimmed.strip_prefix('=') {
+        return format!("{}{}{}", indent, paint("=", BLUE), help);
+    }
+
+    // `<line number> | <text>`, or `| <underline> <label>`
+    match line.split_once('|') {
+        Some((gutter, text)) => {
+            let underline = text.trim_start();
+            let indent = &text[..text.len() - underline.len()];
+            let marker = underline.chars().next().filter(|c| *c == '^' || *c == '-');
+            let text = match marker {
+                Some(marker) if gutter.trim().is_empty() => {
+                    let len = underline.len() - underline.trim_start_matches(marker).len();
+                    let color = if marker == '^' { RED } else { BLUE };
+                    let (underline, label) = underline.split_at(len);
+                    format!("{}{}{}", indent, paint(underline, color), label)
+                }
+                _ => text.to_string(),
+            };
+            format!("{}{}", paint(&format!("{}|", gutter), BLUE), text)
+        }
+        None => line.to_string(),
+    }
+}
+
+fn split_newline(line: &str) -> (&str, &str) {
+    match line.strip_suffix('\n') {
+        Some(content) => (content, "\n"),
+        None => (line, ""),
+    }
+}
@@ -0,0 +1,106 @@
+use super::*;
+use crate::fact_emitter::test::expect_facts;
+use crate::source_map::SourceMap;
+use crate::validation::validate;
+use insta::assert_display_snapshot;
+
+// Replaces the escape codes by readable tags.
+fn tags(colored: &str) -> String {
+    colored
+        .replace(RESET, "</>")
+        .replace(BOLD_YELLOW, "<bold-yellow>")
+        .replace(RED, "<red>")
+        .replace(YELLOW, "<yellow>")
+        .replace(BLUE, "<blue>")
+        .replace(CYAN, "<cyan>")
+        .replace(BOLD, "<bold>")
+}
+
+#[test]
+fn colored_facts() {
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            y = &'L_x x;
+            x = 4;
+        }
+    ";
+    let listing = expect_facts(program).to_string();
+    assert_display_snapshot!(tags(&colorize_facts(&listing)), @r###"
+    <blue>mark_as_loan_origin</>(<yellow>'L_x</>)
+
+    <cyan>a</>: "y = &'L_x x" {
+    	<blue>clear_origin</>(<yellow>'y</>)
+    	<blue>clear_origin</>(<yellow>'L_x</>)
+    	<blue>loan_issued_at</>(<yellow>'L_x</>, bw0)
+    	<blue>introduce_subset</>(<yellow>'L_x</>, <yellow>'y</>)
+    	<bold>goto</> <cyan>b</>
+    }
+
+    <cyan>b</>: "x = 4" {
+    	<blue>invalidate_origin</>(<yellow>'L_x</>)
+    	<bold>goto</>
+    }
+    "###);
+}
+
+#[test]
+fn colored_diagnostics() {
+    let program = "
+        let x: i32;
+
+        bb0: {
+            x = copy y;
+        }
+
+        bb1: {
+            x = 1;
+        }
+    ";
+    let diagnostics: String = validate(program)
+        .unwrap()
+        .iter()
+        .map(|diagnostic| diagnostic.render(&SourceMap::new(program)))
+        .collect();
+    assert_display_snapshot!(tags(&colorize_diagnostics(&diagnostics)), @r###"
+    <red>error:</><bold> unknown variable `y`</>
+     <blue>--></> 5:13
+    <blue>  |</>
+    <blue>5 |</>             x = copy y;
+    <blue>  |</>             <red>^^^^^^^^^^^</>
+    <bold-yellow>warning:</><bold> unreachable block `bb1`</>
+     <blue>--></> 8:9
+    <blue>  |</>
+    <blue>8 |</>         bb1: {
+    <blue>  |</>         <red>^^^</>
+    "###);
+}
+
+#[test]
+fn colored_borrow_errors() {
+    // The primary span is underlined in red, the other labels in blue
+    let program = "let p: i32;\nlet x: &'x i32;\nbb0: {\n    x = &'L_p p;\n    p = 33;\n    use(move x);\n}\n";
+    let facts = expect_facts(program);
+    let diagnostics: String = crate::diagnose(&facts)
+        .iter()
+        .map(|diagnostic| diagnostic.render(&facts, "main.txt", program))
+        .collect();
+    assert_display_snapshot!(tags(&colorize_diagnostics(&diagnostics)), @r###"
+    <red>error[E0506]:</><bold> cannot assign to `p` because it is borrowed</>
+     <blue>--></> main.txt:5:5
+    <blue>  |</>
+    <blue>4 |</>     x = &'L_p p;
+    <blue>  |</>     <blue>-----------</> `p` is borrowed here
+    <blue>5 |</>     p = 33;
+    <blue>  |</>     <red>^^^^^^</> `p` is assigned to here but it was already borrowed
+    <blue>6 |</>     use(move x);
+    <blue>  |</>     <blue>-----------</> borrow later used here
+    <blue>  |</>
+      <blue>=</> note: the shortest path through the borrow, its invalidation, and the later use:
+              a `x = &'L_p p`
+              b `p = 33`
+              c `use(move x)`
+    "###);
+}
@@ -0,0 +1,172 @@
+//! The options of an experiment, read from a `polonius-next.toml` file, so that the way facts
+//! are emitted and solved can be reproduced and shared, instead of living in shell history:
+//!
+//! ```toml
+//! [emit]
+//! raw-pointer-casts = "keep-live"
+//! exclude-unreachable = true
+//! keep-dead-loans = true
+//! node-names = "hashed"
+//!
+//! # Only emit the facts matching these criteria, like `--only`
+//! relations = ["loan_issued_at", "invalidate_origin"]
+//! origins = ["'a"]
+//! nodes = ["bb0[1]"]
+//!
+//! [check]
+//! compare = true
+//! loans = true
+//!
+//! [output]
+//! color = true
+//!
+//! # The limits of the analysis, the timeout is in seconds
+//! [limits]
+//! max-nodes = 100000
+//! max-facts = 1000000
+//! max-iterations = 10000
+//! timeout = 60
+//! ```
+//!
+//! All the sections and keys are optional, and default to the defaults of the command-line.
+//! Unknown sections and keys are errors, so that a typo doesn't silently change an experiment.
+
+use crate::fact_emitter::{EmitOptions, FactFilter};
+use eyre::WrapErr;
+use std::path::Path;
+use std::time::Duration;
+use toml::Value;
+
+#[cfg(test)]
+mod test;
+
+/// The name of the configuration file looked up in the current directory.
+pub const CONFIG_FILE_NAME: &str = "polonius-next.toml";
+
+/// The options of the commands, set by a configuration file. The command-line flags are added
+/// on top of them.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// How the facts are modeled, the `[emit]` options, and the `[limits]` of the analysis.
+    pub emit: EmitOptions,
+
+    /// The facts to emit, the `relations`, `origins` and `nodes` of `[emit]`.
+    pub filter: FactFilter,
+
+    /// Whether the naive solver also runs, and its errors must match the optimized solver's.
+    pub compare_solvers: bool,
+
+    /// Whether the loans each origin may contain at each node are printed.
+    pub print_loans: bool,
+
+    /// Whether the output is colored.
+    pub color: bool,
+}
+
+impl Config {
+    /// Parses the configuration in its TOML format.
+    pub fn parse(text: &str) -> eyre::Result<Self> {
+        let root: Value = text.parse()?;
+        let mut config = Config::default();
+        for (section, values) in table(&root, "the configuration")? {
+            let values = table(values, section)?;
+            for (key, value) in values {
+                let name = format!("{}.{}", section, key);
+                match (section.as_str(), key.as_str()) {
+                    ("emit", "raw-pointer-casts") => {
+                        config.emit.raw_pointer_casts = string(value, &name)?.parse()?
+                    }
+                    ("emit", "exclude-unreachable") => {
+                        config.emit.exclude_unreachable_blocks = boolean(value, &name)?
+                    }
+                    ("emit", "keep-dead-loans") => {
+                        config.emit.keep_dead_loans = boolean(value, &name)?
+                    }
+                    ("emit", "node-names") => {
+                        config.emit.node_names = string(value, &name)?.parse()?
+                    }
+                    ("emit", "relations") | ("emit", "origins") | ("emit", "nodes") => {
+                        // The criteria are validated like the `--only` ones
+                        let kind = key.trim_end_matches('s');
+                        for value in strings(value, &name)? {
+                            config
+                                .filter
+                                .add_criterion(&format!("{}={}", kind, value))?;
+                        }
+                    }
+                    ("check", "compare") => config.compare_solvers = boolean(value, &name)?,
+                    ("check", "loans") => config.print_loans = boolean(value, &name)?,
+                    ("output", "color") => config.color = boolean(value, &name)?,
+                    ("limits", "max-nodes") => {
+                        config.emit.limits.max_nodes = Some(count(value, &name)?)
+                    }
+                    ("limits", "max-facts") => {
+                        config.emit.limits.max_facts = Some(count(value, &name)?)
+                    }
+                    ("limits", "max-iterations") => {
+                        config.emit.limits.max_iterations = Some(count(value, &name)?)
+                    }
+                    ("limits", "timeout") => {
+                        let seconds = count(value, &name)? as u64;
+                        config.emit.limits.timeout = Some(Duration::from_secs(seconds))
+                    }
+                    _ => eyre::bail!("unknown configuration option `{}`", name),
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Reads the configuration file at the given path.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+        Self::parse(&text).wrap_err_with(|| format!("invalid configuration `{}`", path.display()))
+    }
+
+    /// Reads the `polonius-next.toml` file of the directory, if there is one, and returns the
+    /// default configuration otherwise.
+    pub fn discover(dir: &Path) -> eyre::Result<Self> {
+        let path = dir.join(CONFIG_FILE_NAME);
+        if path.is_file() {
+            Self::load(&path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
+fn table<'a>(value: &'a Value, name: &str) -> eyre::Result<&'a toml::value::Table> {
+    value
+        .as_table()
+        .ok_or_else(|| eyre::eyre!("`{}` must be a table", name))
+}
+
+fn boolean(value: &Value, name: &str) -> eyre::Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| eyre::eyre!("`{}` must be a boolean", name))
+}
+
+fn count(value: &Value, name: &str) -> eyre::Result<usize> {
+    value
+        .as_integer()
+        .filter(|&count| count >= 0)
+        .map(|count| count as usize)
+        .ok_or_else(|| eyre::eyre!("`{}` must be a positive integer", name))
+}
+
+fn string<'a>(value: &'a Value, name: &str) -> eyre::Result<&'a str> {
+    value
+        .as_str()
+        .ok_or_else(|| eyre::eyre!("`{}` must be a string", name))
+}
+
+fn strings<'a>(value: &'a Value, name: &str) -> eyre::Result<Vec<&'a str>> {
+    value
+        .as_array()
+        .ok_or_else(|| eyre::eyre!("`{}` must be an array of strings", name))?
+        .iter()
+        .map(|value| string(value, name))
+        .collect()
+}
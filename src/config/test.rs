@@ -0,0 +1,68 @@
+use super::*;
+use crate::fact_emitter::{NodeNames, RawPointerCasts};
+use insta::assert_display_snapshot;
+
+#[test]
+fn all_options() {
+    let config = Config::parse(
+        r#"
+        [emit]
+        raw-pointer-casts = "keep-live"
+        exclude-unreachable = true
+        keep-dead-loans = true
+        node-names = "hashed"
+        relations = ["loan_issued_at", "invalidate_origin"]
+        origins = ["'a"]
+        nodes = ["bb0[1]"]
+
+        [check]
+        compare = true
+        loans = true
+
+        [output]
+        color = true
+
+        [limits]
+        max-nodes = 10
+        max-iterations = 20
+        timeout = 30
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.emit.raw_pointer_casts, RawPointerCasts::KeepLive);
+    assert!(config.emit.exclude_unreachable_blocks);
+    assert!(config.emit.keep_dead_loans);
+    assert_eq!(config.emit.node_names, NodeNames::Hashed);
+    assert_eq!(config.filter.relations.len(), 2);
+    assert_eq!(config.filter.origins, vec!["'a".into()]);
+    assert_eq!(config.filter.nodes, vec!["bb0[1]".into()]);
+    assert!(config.compare_solvers && config.print_loans && config.color);
+    assert_eq!(config.emit.limits.max_nodes, Some(10));
+    assert_eq!(config.emit.limits.max_facts, None);
+    assert_eq!(config.emit.limits.max_iterations, Some(20));
+    assert_eq!(config.emit.limits.timeout, Some(Duration::from_secs(30)));
+}
+
+#[test]
+fn missing_options_are_defaults() {
+    let config = Config::parse("[check]\nloans = true\n").unwrap();
+    assert_eq!(config.emit.raw_pointer_casts, RawPointerCasts::EndTracking);
+    assert!(!config.emit.exclude_unreachable_blocks);
+    assert!(!config.emit.keep_dead_loans);
+    assert!(config.filter.relations.is_empty());
+    assert!(config.print_loans);
+    assert!(!config.compare_solvers && !config.color);
+}
+
+#[test]
+fn invalid_options() {
+    let error = |text| Config::parse(text).unwrap_err().to_string();
+    assert_display_snapshot!(error("[emit]\nexclude-unreachabel = true"), @"unknown configuration option `emit.exclude-unreachabel`");
+    assert_display_snapshot!(error("[solver]\nnaive = true"), @"unknown configuration option `solver.naive`");
+    assert_display_snapshot!(error("[output]\ncolor = \"yes\""), @"`output.color` must be a boolean");
+    assert_display_snapshot!(error("[emit]\nrelations = [\"loan_issued\"]"), @"unknown relation `loan_issued`");
+    assert_display_snapshot!(error("[emit]\nraw-pointer-casts = \"forget\""), @"unknown raw pointer cast model `forget`, expected `end-tracking` or `keep-live`");
+    assert_display_snapshot!(error("[emit]\nnode-names = \"numbered\""), @"unknown node naming `numbered`, expected `indexed` or `hashed`");
+    assert_display_snapshot!(error("output = true"), @"`output` must be a table");
+    assert_display_snapshot!(error("[limits]\ntimeout = -1"), @"`limits.timeout` must be a positive integer");
+}
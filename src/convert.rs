@@ -0,0 +1,526 @@
+//! Conversions of facts between the formats they're read and written in, so that handwritten
+//! fact files, the inputs of the Soufflé rules, and external dumps can all be analyzed the same
+//! way:
+//! - `text`: the fact file format, of `read_facts` and of the facts' `Display`,
+//! - `souffle`: a directory of tab-separated `.facts` files, one per relation, the inputs of
+//!   `polonius.dl`,
+//! - `json`: an object mapping each relation to its rows, each row an array of atoms,
+//! - `legacy`: a fact set directory of upstream Polonius, see `LegacyFacts`,
+//! - `csv`: a directory of `.csv` files, one per relation, with a header naming their columns,
+//!   to load the facts into dataframes or databases,
+//! - `sqlite`: a SQLite database, with a table per relation, indexed on their node and origin
+//!   columns, to query the facts in SQL,
+//! - `binary`: a compact encoding of all the facts, with `bincode`, to cache the large fact sets
+//!   of real crates on disk and reload them without parsing text.
+//!
+//! The `text`, `souffle`, `json`, `csv` and `sqlite` formats have the same relations, the inputs
+//! of the rules, with the node of a local fact as its last atom. Facts written in the legacy
+//! format are exported, and facts read from it translated: see `LegacyFacts::from_facts` and
+//! `LegacyFacts::to_facts`. The other relations emitted for programs, e.g. the liveness of
+//! variables, are only kept in the legacy and binary formats.
+//!
+//! The `souffle`, `json`, `csv` and `sqlite` formats also have the line and column where each
+//! node's statement starts, `node_location`, for frontends which only have the facts. The `text`
+//! format doesn't.
+//!
+//! The `binary` format keeps all the relations of the facts, but not the provenance of the
+//! emitted facts. Its files start with a header and a version, so that the files of another
+//! version of the crate are rejected instead of being misread.
+
+use crate::fact_emitter::Facts;
+use crate::fact_parser::{facts_from_rows, read_facts};
+use crate::json::{parse_json, Json};
+use crate::legacy::LegacyFacts;
+use bincode::Options;
+use eyre::WrapErr;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[cfg(test)]
+mod test;
+
+/// A format of facts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FactFormat {
+    Text,
+    Souffle,
+    Json,
+    Legacy,
+    Csv,
+    Sqlite,
+    Binary,
+}
+
+impl std::str::FromStr for FactFormat {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "souffle" => Ok(Self::Souffle),
+            "json" => Ok(Self::Json),
+            "legacy" => Ok(Self::Legacy),
+            "csv" => Ok(Self::Csv),
+            "sqlite" => Ok(Self::Sqlite),
+            "binary" => Ok(Self::Binary),
+            _ => eyre::bail!(
+                "unknown fact format `{}`, expected `text`, `souffle`, `json`, `legacy`, `csv`, \
+                `sqlite` or `binary`",
+                s
+            ),
+        }
+    }
+}
+
+impl FactFormat {
+    /// The name of the format, as it's parsed.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Souffle => "souffle",
+            Self::Json => "json",
+            Self::Legacy => "legacy",
+            Self::Csv => "csv",
+            Self::Sqlite => "sqlite",
+            Self::Binary => "binary",
+        }
+    }
+
+    /// Whether the facts in this format are a directory of files, rather than a single file.
+    pub fn is_directory(self) -> bool {
+        matches!(self, Self::Souffle | Self::Legacy | Self::Csv)
+    }
+}
+
+// The relations of the `text`, `souffle`, `json` and `csv` formats, with the names of their
+// columns.
+pub(crate) const RELATIONS: &[(&str, &[&str])] = &[
+    ("mark_as_loan_origin", &["origin"]),
+    ("placeholder_origin", &["origin"]),
+    ("known_placeholder_subset", &["origin1", "origin2"]),
+    ("node_text", &["text", "node"]),
+    ("source_location", &["location", "node"]),
+    ("node_location", &["line", "column", "node"]),
+    ("cfg_edge", &["node1", "node2"]),
+    ("cfg_edge_kind", &["node1", "node2", "kind"]),
+    ("access_origin", &["origin", "node"]),
+    ("clear_origin", &["origin", "node"]),
+    ("introduce_subset", &["origin1", "origin2", "node"]),
+    ("invalidate_origin", &["origin", "node"]),
+    ("loan_issued_at", &["origin", "loan", "node"]),
+];
+
+/// Reads the facts at the path, a file or a directory depending on the format.
+pub fn read_facts_in(format: FactFormat, path: &Path) -> eyre::Result<Facts> {
+    match format {
+        FactFormat::Text => read_facts(&read_file(path)?),
+        FactFormat::Souffle => {
+            let mut rows = HashMap::new();
+            for &(name, columns) in RELATIONS {
+                rows.insert(name.to_string(), read_tsv(path, name, columns.len())?);
+            }
+            Ok(facts_from_rows(&rows))
+        }
+        FactFormat::Json => {
+            let json = parse_json(&read_file(path)?)
+                .wrap_err_with(|| format!("invalid JSON in `{}`", path.display()))?;
+            facts_from_json(&json)
+        }
+        FactFormat::Legacy => Ok(LegacyFacts::read(path)?.to_facts()),
+        FactFormat::Csv => {
+            let mut rows = HashMap::new();
+            for &(name, columns) in RELATIONS {
+                rows.insert(name.to_string(), read_csv(path, name, columns)?);
+            }
+            Ok(facts_from_rows(&rows))
+        }
+        FactFormat::Sqlite => read_sqlite(path)
+            .wrap_err_with(|| format!("failed to read the database `{}`", path.display())),
+        FactFormat::Binary => {
+            let bytes = std::fs::read(path)
+                .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+            facts_from_binary(&bytes)
+                .wrap_err_with(|| format!("invalid binary facts in `{}`", path.display()))
+        }
+    }
+}
+
+/// Writes the facts at the path, a file or a directory depending on the format. The
+/// directories are created if they don't exist.
+pub fn write_facts_in(facts: &Facts, format: FactFormat, path: &Path) -> eyre::Result<()> {
+    match format {
+        FactFormat::Text => write_file(path, &facts.to_string()),
+        FactFormat::Souffle => {
+            std::fs::create_dir_all(path)
+                .wrap_err_with(|| format!("failed to create `{}`", path.display()))?;
+            for (name, rows) in to_rows(facts) {
+                let contents: String = rows.iter().map(|row| row.join("\t") + "\n").collect();
+                write_file(&path.join(name).with_extension("facts"), &contents)?;
+            }
+            Ok(())
+        }
+        FactFormat::Json => write_file(path, &format!("{}\n", facts_to_json(facts))),
+        FactFormat::Legacy => LegacyFacts::from_facts(facts).write(path),
+        FactFormat::Csv => {
+            std::fs::create_dir_all(path)
+                .wrap_err_with(|| format!("failed to create `{}`", path.display()))?;
+            for ((name, rows), (_, columns)) in to_rows(facts).into_iter().zip(RELATIONS) {
+                write_csv(&path.join(name).with_extension("csv"), columns, &rows)?;
+            }
+            Ok(())
+        }
+        FactFormat::Sqlite => write_sqlite(facts, path)
+            .wrap_err_with(|| format!("failed to write the database `{}`", path.display())),
+        FactFormat::Binary => std::fs::write(path, facts_to_binary(facts))
+            .wrap_err_with(|| format!("failed to write `{}`", path.display())),
+    }
+}
+
+// The start of the files of the `binary` format: a magic string, and the version of the
+// encoding, to increase when the fields of `Facts` change. The lengths and numbers are encoded
+// as varints, so most of them take a single byte.
+const BINARY_HEADER: &[u8] = b"polonius-facts\x01";
+
+/// The facts in the `binary` format.
+pub fn facts_to_binary(facts: &Facts) -> Vec<u8> {
+    let mut bytes = BINARY_HEADER.to_vec();
+    bincode::DefaultOptions::new()
+        .serialize_into(&mut bytes, facts)
+        .expect("the facts can be serialized");
+    bytes
+}
+
+/// The facts of bytes in the `binary` format, written by `facts_to_binary`.
+pub fn facts_from_binary(bytes: &[u8]) -> eyre::Result<Facts> {
+    let encoded = match bytes.strip_prefix(BINARY_HEADER) {
+        Some(encoded) => encoded,
+        None if bytes.starts_with(&BINARY_HEADER[..BINARY_HEADER.len() - 1]) => {
+            eyre::bail!("the facts were written by another version of polonius")
+        }
+        None => eyre::bail!("expected binary facts, written with `--to binary`"),
+    };
+    bincode::DefaultOptions::new()
+        .deserialize(encoded)
+        .wrap_err("the binary facts are corrupted")
+}
+
+/// The facts as a JSON object, mapping each relation to its rows.
+pub fn facts_to_json(facts: &Facts) -> Json {
+    let relations = to_rows(facts).into_iter().map(|(name, rows)| {
+        let rows = rows
+            .into_iter()
+            .map(|row| Json::Array(row.into_iter().map(Json::String).collect()))
+            .collect();
+        (name.to_string(), Json::Array(rows))
+    });
+    Json::Object(relations.collect())
+}
+
+/// The facts of a JSON object, in the format of `facts_to_json`. Missing relations are empty.
+pub fn facts_from_json(json: &Json) -> eyre::Result<Facts> {
+    let members = match json {
+        Json::Object(members) => members,
+        _ => eyre::bail!("expected an object of relations"),
+    };
+    let mut rows: HashMap<String, Vec<Vec<String>>> = RELATIONS
+        .iter()
+        .map(|(name, _)| (name.to_string(), Vec::new()))
+        .collect();
+    for (name, relation) in members {
+        let arity = match RELATIONS.iter().find(|(candidate, _)| candidate == name) {
+            Some(&(_, columns)) => columns.len(),
+            None => eyre::bail!("unknown relation `{}`", name),
+        };
+        let relation = match relation {
+            Json::Array(relation) => relation,
+            _ => eyre::bail!("expected the rows of `{}` to be an array", name),
+        };
+        for row in relation {
+            let atoms: Option<Vec<_>> = match row {
+                Json::Array(atoms) => atoms
+                    .iter()
+                    .map(|atom| atom.as_str().map(str::to_string))
+                    .collect(),
+                _ => None,
+            };
+            match atoms {
+                Some(atoms) if atoms.len() == arity => rows.get_mut(name).unwrap().push(atoms),
+                _ => eyre::bail!(
+                    "expected the rows of `{}` to be arrays of {} strings, found `{}`",
+                    name,
+                    arity,
+                    row
+                ),
+            }
+        }
+    }
+    Ok(facts_from_rows(&rows))
+}
+
+// The rows of each relation, in the order of `RELATIONS`, with the node last.
+fn to_rows(facts: &Facts) -> Vec<(&'static str, Vec<Vec<String>>)> {
+    let atoms = |atoms: &[&str]| -> Vec<String> { atoms.iter().map(|a| a.to_string()).collect() };
+    vec![
+        (
+            "mark_as_loan_origin",
+            facts
+                .mark_as_loan_origin
+                .iter()
+                .map(|o| atoms(&[&o.0]))
+                .collect(),
+        ),
+        (
+            "placeholder_origin",
+            facts
+                .placeholder_origin
+                .iter()
+                .map(|o| atoms(&[&o.0]))
+                .collect(),
+        ),
+        (
+            "known_placeholder_subset",
+            facts
+                .known_placeholder_subset
+                .iter()
+                .map(|(o1, o2)| atoms(&[&o1.0, &o2.0]))
+                .collect(),
+        ),
+        (
+            "node_text",
+            facts
+                .node_text
+                .iter()
+                .map(|(text, n)| atoms(&[text, &n.0]))
+                .collect(),
+        ),
+        (
+            "source_location",
+            facts
+                .source_location
+                .iter()
+                .map(|(n, location)| atoms(&[location, &n.0]))
+                .collect(),
+        ),
+        (
+            "node_location",
+            facts
+                .node_location
+                .iter()
+                .map(|(n, line, column)| atoms(&[&line.to_string(), &column.to_string(), &n.0]))
+                .collect(),
+        ),
+        (
+            "cfg_edge",
+            facts
+                .cfg_edge
+                .iter()
+                .map(|(n1, n2)| atoms(&[&n1.0, &n2.0]))
+                .collect(),
+        ),
+        (
+            "cfg_edge_kind",
+            facts
+                .cfg_edge_kind
+                .iter()
+                .map(|(n1, n2, kind)| atoms(&[&n1.0, &n2.0, kind.name()]))
+                .collect(),
+        ),
+        (
+            "access_origin",
+            facts
+                .access_origin
+                .iter()
+                .map(|(o, n)| atoms(&[&o.0, &n.0]))
+                .collect(),
+        ),
+        (
+            "clear_origin",
+            facts
+                .clear_origin
+                .iter()
+                .map(|(o, n)| atoms(&[&o.0, &n.0]))
+                .collect(),
+        ),
+        (
+            "introduce_subset",
+            facts
+                .introduce_subset
+                .iter()
+                .map(|(o1, o2, n)| atoms(&[&o1.0, &o2.0, &n.0]))
+                .collect(),
+        ),
+        (
+            "invalidate_origin",
+            facts
+                .invalidate_origin
+                .iter()
+                .map(|(o, n)| atoms(&[&o.0, &n.0]))
+                .collect(),
+        ),
+        (
+            "loan_issued_at",
+            facts
+                .loan_issued_at
+                .iter()
+                .map(|(o, l, n)| atoms(&[&o.0, &l.0, &n.0]))
+                .collect(),
+        ),
+    ]
+}
+
+// The rows of the relation's `.facts` file in the directory, which is empty if it's missing.
+fn read_tsv(dir: &Path, name: &str, arity: usize) -> eyre::Result<Vec<Vec<String>>> {
+    let path = dir.join(name).with_extension("facts");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut rows = Vec::new();
+    for (idx, line) in read_file(&path)?.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let row: Vec<String> = line.split('\t').map(str::to_string).collect();
+        if row.len() != arity {
+            eyre::bail!(
+                "expected {} columns at line {} of `{}`, found {}",
+                arity,
+                idx + 1,
+                path.display(),
+                row.len()
+            );
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+// The rows of the relation's `.csv` file in the directory, which is empty if it's missing. Its
+// header must name the relation's columns.
+fn read_csv(dir: &Path, name: &str, columns: &[&str]) -> eyre::Result<Vec<Vec<String>>> {
+    let path = dir.join(name).with_extension("csv");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut reader = csv::Reader::from_path(&path)
+        .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+    let header = reader
+        .headers()
+        .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+    if header.iter().ne(columns.iter().copied()) {
+        eyre::bail!(
+            "expected the columns `{}` in `{}`, found `{}`",
+            columns.join(","),
+            path.display(),
+            header.iter().collect::<Vec<_>>().join(",")
+        );
+    }
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+        rows.push(record.iter().map(str::to_string).collect());
+    }
+    Ok(rows)
+}
+
+// Writes the rows of a relation in a `.csv` file, after a header naming its columns.
+fn write_csv(path: &Path, columns: &[&str], rows: &[Vec<String>]) -> eyre::Result<()> {
+    let write = || -> csv::Result<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record(columns)?;
+        for row in rows {
+            writer.write_record(row)?;
+        }
+        writer.flush()?;
+        Ok(())
+    };
+    write().wrap_err_with(|| format!("failed to write `{}`", path.display()))
+}
+
+// The facts of the SQLite database at the path. The missing tables are empty.
+fn read_sqlite(path: &Path) -> eyre::Result<Facts> {
+    if !path.exists() {
+        eyre::bail!("the database doesn't exist");
+    }
+    let db =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut rows = HashMap::new();
+    for &(name, columns) in RELATIONS {
+        let mut table = Vec::new();
+        let is_table = db
+            .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")?
+            .exists([name])?;
+        if is_table {
+            let columns: Vec<_> = columns
+                .iter()
+                .map(|column| format!("\"{}\"", column))
+                .collect();
+            let mut select = db.prepare(&format!("SELECT {} FROM {}", columns.join(", "), name))?;
+            let mut results = select.query([])?;
+            while let Some(result) = results.next()? {
+                let mut row = Vec::new();
+                for idx in 0..columns.len() {
+                    // The numbers are stored as integers, and the other atoms as text
+                    let atom = match result.get_ref(idx)? {
+                        rusqlite::types::ValueRef::Integer(number) => number.to_string(),
+                        value => value.as_str()?.to_string(),
+                    };
+                    row.push(atom);
+                }
+                table.push(row);
+            }
+        }
+        rows.insert(name.to_string(), table);
+    }
+    Ok(facts_from_rows(&rows))
+}
+
+// Writes the facts in a new SQLite database at the path, replacing the existing one: a table per
+// relation, with an index on each of its node and origin columns. The lines and columns of
+// `node_location` are integers, and the other atoms text.
+fn write_sqlite(facts: &Facts, path: &Path) -> eyre::Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let mut db = rusqlite::Connection::open(path)?;
+    let transaction = db.transaction()?;
+    for ((name, rows), (_, columns)) in to_rows(facts).into_iter().zip(RELATIONS) {
+        let definitions: Vec<_> = columns
+            .iter()
+            .map(|&column| match column {
+                "line" | "column" => format!("\"{}\" INTEGER", column),
+                _ => format!("\"{}\" TEXT", column),
+            })
+            .collect();
+        transaction.execute_batch(&format!(
+            "CREATE TABLE {} ({})",
+            name,
+            definitions.join(", ")
+        ))?;
+        for column in columns
+            .iter()
+            .filter(|column| column.starts_with("node") || column.starts_with("origin"))
+        {
+            transaction.execute_batch(&format!(
+                "CREATE INDEX {0}_{1} ON {0} (\"{1}\")",
+                name, column
+            ))?;
+        }
+
+        let parameters = vec!["?"; columns.len()].join(", ");
+        let mut insert =
+            transaction.prepare(&format!("INSERT INTO {} VALUES ({})", name, parameters))?;
+        for row in rows {
+            insert.execute(rusqlite::params_from_iter(row))?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+fn read_file(path: &Path) -> eyre::Result<String> {
+    std::fs::read_to_string(path).wrap_err_with(|| format!("failed to read `{}`", path.display()))
+}
+
+fn write_file(path: &Path, contents: &str) -> eyre::Result<()> {
+    std::fs::write(path, contents).wrap_err_with(|| format!("failed to write `{}`", path.display()))
+}
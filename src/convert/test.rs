@@ -0,0 +1,188 @@
+use super::*;
+use crate::fact_emitter::emit_facts;
+use crate::test_util::write_files;
+use insta::assert_display_snapshot;
+
+const PROGRAM: &str = "
+fn f<'a>(x: &'a i32) -> &'a i32;
+placeholder 'p;
+let x: i32;
+let r: &'r i32;
+let p: &'p i32;
+bb0: {
+    r = &'L_x x;
+    p = f(copy r);
+    x = 4;
+    goto bb1;
+}
+bb1: {
+    use(copy r);
+}
+";
+
+#[test]
+fn round_trips() {
+    // Converting the facts from one format to the next keeps them
+    let expected = emit_facts(PROGRAM).unwrap();
+    let dir = write_files("convert-round-trips", &[]);
+    let mut facts = expected.clone();
+    for (format, path) in [
+        (FactFormat::Text, "facts.txt"),
+        (FactFormat::Json, "facts.json"),
+        (FactFormat::Souffle, "souffle"),
+        (FactFormat::Csv, "csv"),
+        (FactFormat::Sqlite, "facts.sqlite"),
+        (FactFormat::Binary, "facts.bin"),
+        (FactFormat::Text, "facts-again.txt"),
+    ] {
+        let path = dir.join(path);
+        write_facts_in(&facts, format, &path).unwrap();
+        facts = read_facts_in(format, &path).unwrap();
+        assert_eq!(facts.to_string(), expected.to_string());
+    }
+}
+
+#[test]
+fn json() {
+    let facts = emit_facts("let x: i32; let r: &'r i32; bb0: { r = &'L_x x; x = 1; }").unwrap();
+    assert_display_snapshot!(facts_to_json(&facts), @r###"
+    {"mark_as_loan_origin":[["'L_x"]],"placeholder_origin":[],"known_placeholder_subset":[],"node_text":[["r = &'L_x x","bb0[0]"],["x = 1","bb0[1]"]],"source_location":[],"node_location":[["1","36","bb0[0]"],["1","49","bb0[1]"]],"cfg_edge":[["bb0[0]","bb0[1]"]],"cfg_edge_kind":[["bb0[0]","bb0[1]","normal"]],"access_origin":[],"clear_origin":[["'r","bb0[0]"],["'L_x","bb0[0]"]],"introduce_subset":[["'L_x","'r","bb0[0]"]],"invalidate_origin":[],"loan_issued_at":[["'L_x","bw0","bb0[0]"]]}
+    "###);
+
+    let error = |input: &str| facts_from_json(&parse_json(input).unwrap()).unwrap_err();
+    assert_display_snapshot!(error("[]"), @"expected an object of relations");
+    assert_display_snapshot!(error(r#"{"loan_issued": []}"#), @"unknown relation `loan_issued`");
+    assert_display_snapshot!(error(r#"{"cfg_edge": [["a"]]}"#), @r###"
+    expected the rows of `cfg_edge` to be arrays of 2 strings, found `["a"]`
+    "###);
+}
+
+#[test]
+fn souffle_files() {
+    // The missing relations are empty, and the rows must have the relation's arity
+    let dir = write_files("convert-souffle", &[("cfg_edge.facts", "a\tb\n")]);
+    let facts = read_facts_in(FactFormat::Souffle, &dir).unwrap();
+    assert_display_snapshot!(facts, @r###"
+    a: "(pass)" {
+    	goto b
+    }
+
+    b: "(pass)" {
+    	goto
+    }
+    "###);
+
+    std::fs::write(dir.join("access_origin.facts"), "'a\n").unwrap();
+    let error = read_facts_in(FactFormat::Souffle, &dir).unwrap_err();
+    let error = error
+        .to_string()
+        .replace(&dir.display().to_string(), "<dir>");
+    assert_display_snapshot!(error, @"expected 2 columns at line 1 of `<dir>/access_origin.facts`, found 1");
+}
+
+#[test]
+fn csv_files() {
+    // Each relation has a header, and the atoms with commas or quotes are quoted
+    let facts = emit_facts("let x: i32; let r: &'r i32; bb0: { r = &'L_x x; x = 1; }").unwrap();
+    let dir = write_files("convert-csv", &[]);
+    write_facts_in(&facts, FactFormat::Csv, &dir).unwrap();
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert_display_snapshot!(read("loan_issued_at.csv"), @r###"
+    origin,loan,node
+    'L_x,bw0,bb0[0]
+    "###);
+
+    let facts = emit_facts(
+        "fn f(a: i32, b: i32) -> i32; let x: i32; let y: i32; bb0: { y = f(copy x, copy x); }",
+    )
+    .unwrap();
+    write_facts_in(&facts, FactFormat::Csv, &dir).unwrap();
+    assert_display_snapshot!(read("node_text.csv"), @r###"
+    text,node
+    "y = f(copy x, copy x)",bb0[0]
+    "###);
+
+    std::fs::write(dir.join("node_text.csv"), "text,statement\n").unwrap();
+    let error = read_facts_in(FactFormat::Csv, &dir).unwrap_err();
+    let error = error
+        .to_string()
+        .replace(&dir.display().to_string(), "<dir>");
+    assert_display_snapshot!(error, @"expected the columns `text,node` in `<dir>/node_text.csv`, found `text,statement`");
+}
+
+#[test]
+fn sqlite_database() {
+    // The accesses after an invalidation, of the origins the invalidated origin flows into, can
+    // be queried in SQL
+    let facts = emit_facts(PROGRAM).unwrap();
+    let path = write_files("convert-sqlite", &[]).join("facts.sqlite");
+    write_facts_in(&facts, FactFormat::Sqlite, &path).unwrap();
+    write_facts_in(&facts, FactFormat::Sqlite, &path).unwrap();
+
+    let db = rusqlite::Connection::open(&path).unwrap();
+    let query = |sql: &str| -> String {
+        let mut statement = db.prepare(sql).unwrap();
+        let rows = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .unwrap()
+            .map(Result::unwrap);
+        rows.collect::<Vec<_>>().join("\n")
+    };
+    assert_display_snapshot!(query(
+        "SELECT i.origin || ' invalidated at ' || i.node || ', ' || a.origin || ' accessed at ' \
+            || a.node \
+        FROM invalidate_origin i \
+        JOIN introduce_subset s ON s.origin1 = i.origin \
+        JOIN access_origin a ON a.origin = s.origin2 \
+        JOIN node_location li ON li.node = i.node \
+        JOIN node_location la ON la.node = a.node \
+        WHERE la.line > li.line"
+    ), @"'L_x invalidated at bb0[2], 'r accessed at bb1[0]");
+    assert_display_snapshot!(query(
+        "SELECT node || ': ' || line FROM node_location WHERE line > 8 ORDER BY line"
+    ), @r###"
+    bb0[1]: 9
+    bb0[2]: 10
+    bb1[0]: 14
+    "###);
+    assert_display_snapshot!(query(
+        "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'introduce_subset'"
+    ), @r###"
+    introduce_subset_origin1
+    introduce_subset_origin2
+    introduce_subset_node
+    "###);
+}
+
+#[test]
+fn binary() {
+    // All the relations are kept, including the ones only emitted for programs
+    let expected = emit_facts(PROGRAM).unwrap();
+    let facts = facts_from_binary(&facts_to_binary(&expected)).unwrap();
+    assert_eq!(facts.to_string(), expected.to_string());
+    assert!(!facts.var_used_at.is_empty());
+    assert_eq!(facts.var_used_at, expected.var_used_at);
+    assert_eq!(facts.loan_place, expected.loan_place);
+    assert_eq!(facts.path_accessed_at, expected.path_accessed_at);
+
+    let error = |bytes: &[u8]| format!("{:#}", facts_from_binary(bytes).unwrap_err());
+    assert_display_snapshot!(error(b"bb0: {}"), @"expected binary facts, written with `--to binary`");
+    assert_display_snapshot!(error(b"polonius-facts\x00"), @"the facts were written by another version of polonius");
+    assert_display_snapshot!(error(&facts_to_binary(&expected)[..40]), @"the binary facts are corrupted: io error:");
+}
+
+#[test]
+fn formats() {
+    assert_eq!(
+        "souffle".parse::<FactFormat>().unwrap(),
+        FactFormat::Souffle
+    );
+    assert!(FactFormat::Legacy.is_directory());
+    assert!(!FactFormat::Json.is_directory());
+    assert!(FactFormat::Csv.is_directory());
+    assert_eq!(
+        FactFormat::Binary.name().parse::<FactFormat>().unwrap(),
+        FactFormat::Binary
+    );
+    assert_display_snapshot!("tsv".parse::<FactFormat>().unwrap_err(), @"unknown fact format `tsv`, expected `text`, `souffle`, `json`, `legacy`, `csv`, `sqlite` or `binary`");
+}
@@ -0,0 +1,793 @@
+//! Diagnostics for the errors computed by the solver, in the style of rustc's, so that the
+//! analysis results are directly comparable to what rustc reports for the same program.
+//!
+//! Each invalidation of a loan which is accessed later is reported where the conflicting action
+//! happens, like rustc does:
+//! - an assignment to a borrowed place is `E0506`,
+//! - a mutable borrow of a place borrowed immutably is `E0502`,
+//! - a mutable borrow of a place borrowed mutably is `E0499`.
+//!
+//! The errors of invalidated loans have a note listing a shortest execution demonstrating them:
+//! the path along the CFG from where the loan is issued, through its invalidation, to where it's
+//! used afterwards.
+//!
+//! A loan of a local flowing into a placeholder is `E0597`: the local doesn't live long enough
+//! for the data it's borrowed for. Missing outlives requirements between placeholders have no
+//! error code in rustc, and are reported as `lifetime may not live long enough`.
+//!
+//! Accessing a value after it's been moved out of is `E0382`, whether the value was moved as a
+//! whole, or only some of its fields.
+//!
+//! Facts read from a fact file don't know the loans' kinds and places, nor what produced the
+//! invalidations: those errors are reported without an error code.
+//!
+//! Diagnostics of programs are rendered with the lines of the statements they label, at their
+//! file, line and column, see `Diagnostic::render`. Their `Display` names the nodes instead,
+//! which is all facts read from a fact file have.
+//!
+//! Diagnostics can also be emitted as JSON, in the structure of rustc's `--error-format=json`,
+//! so that harnesses can match them against rustc's structurally: the labels are spans at the
+//! byte offsets of their nodes' statements, the one at the reported node being primary, and the
+//! help and notes are child diagnostics. Facts read from a fact file have no spans.
+
+use crate::fact_emitter::{ExprPathElem, Facts, LoanKind, Node, Origin, Relation};
+use crate::json::Json;
+use crate::limits::{Budget, Limits};
+use crate::moves::{move_errors, MoveError};
+use crate::solver::{solve_within, Derivation, SolverOutput};
+use crate::source_map::SourceMap;
+use crate::span::Span;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+#[cfg(test)]
+mod test;
+
+/// An error, reported at a node, with labels explaining it at the nodes involved.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// The rustc error code, e.g. `E0506`, when the error has one.
+    pub code: Option<&'static str>,
+    pub message: String,
+
+    /// The node where the error is reported.
+    pub node: Node,
+
+    /// The location in the Rust source of the node's statement, when it has one.
+    pub location: Option<String>,
+
+    /// The labels, in node order.
+    pub labels: Vec<Label>,
+    pub help: Option<String>,
+
+    /// The notes explaining the error, e.g. how the rules derived it, see `explain_diagnostics`.
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Label {
+    pub node: Node,
+
+    /// The text of the labeled node, and its location in the Rust source.
+    pub text: String,
+    pub location: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => writeln!(f, "error[{}]: {}", code, self.message)?,
+            None => writeln!(f, "error: {}", self.message)?,
+        }
+        write!(f, "  --> {}", self.node.0)?;
+        if let Some(location) = &self.location {
+            write!(f, " ({})", location)?;
+        }
+        writeln!(f)?;
+        for label in &self.labels {
+            write!(f, "   | {} `{}`", label.node.0, label.text)?;
+            if let Some(location) = &label.location {
+                write!(f, " ({})", location)?;
+            }
+            writeln!(f, ": {}", label.message)?;
+        }
+        if let Some(help) = &self.help {
+            writeln!(f, "   = help: {}", help)?;
+        }
+        for note in &self.notes {
+            // The lines of a note, e.g. of a derivation tree, are aligned under its first line
+            let mut lines = note.lines();
+            writeln!(f, "   = note: {}", lines.next().unwrap_or_default())?;
+            for line in lines {
+                writeln!(f, "           {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Diagnostic {
+    /// Renders the diagnostic in the style of rustc's, like its `Display`, with the lines and
+    /// columns of its nodes' statements in the input whose facts were emitted, from the file
+    /// with the given name, and the lines they're on. Facts read from a fact file have no spans,
+    /// and their diagnostics are rendered like their `Display`.
+    pub fn render(&self, facts: &Facts, file_name: &str, input: &str) -> String {
+        self.render_located(facts, &|span| (file_name.to_string(), input, span))
+    }
+
+    /// Renders the diagnostic like `render`, with the spans located in their file by `locate`:
+    /// the name of the file, its text, and the span in that text.
+    pub(crate) fn render_located<'a>(
+        &self,
+        facts: &Facts,
+        locate: &dyn Fn(Span) -> (String, &'a str, Span),
+    ) -> String {
+        let primary = match facts.node_span(&self.node) {
+            Some(span) => locate(span),
+            None => return self.to_string(),
+        };
+
+        // The located labels, with the line their span starts on, to size the gutter
+        let labels: Vec<_> = self
+            .labels
+            .iter()
+            .map(|label| {
+                let located = facts.node_span(&label.node).map(|span| {
+                    let (file_name, text, span) = locate(span);
+                    let source_map = SourceMap::new(text);
+                    let (line, _) = source_map.line_col(span.start());
+                    (file_name, source_map, span, line)
+                });
+                (label, located)
+            })
+            .collect();
+        let (file_name, text, span) = &primary;
+        let (line, column) = SourceMap::new(text).line_col(span.start());
+        let width = labels
+            .iter()
+            .filter_map(|(_, located)| located.as_ref().map(|(.., line)| *line))
+            .chain(std::iter::once(line))
+            .max()
+            .unwrap_or(line)
+            .to_string()
+            .len();
+        let gutter = " ".repeat(width);
+
+        let mut rendered = match self.code {
+            Some(code) => format!("error[{}]: {}\n", code, self.message),
+            None => format!("error: {}\n", self.message),
+        };
+        rendered += &format!("{}--> {}:{}:{}", gutter, file_name, line, column);
+        if let Some(location) = &self.location {
+            rendered += &format!(" ({})", location);
+        }
+        rendered += &format!("\n{} |\n", gutter);
+
+        // Each label underlines the first line of its statement, with `^` at the reported node,
+        // like rustc's primary span, and `-` elsewhere. The labels in another file than the
+        // previous one start with that file's location.
+        let mut current_file = file_name.clone();
+        for (label, located) in &labels {
+            let location = label
+                .location
+                .as_ref()
+                .map_or(String::new(), |location| format!(" ({})", location));
+            let (file_name, source_map, span, line) = match located {
+                Some(located) => located,
+                None => {
+                    rendered += &format!(
+                        "{} | {} `{}`{}: {}\n",
+                        gutter, label.node.0, label.text, location, label.message
+                    );
+                    continue;
+                }
+            };
+            if *file_name != current_file {
+                let (_, column) = source_map.line_col(span.start());
+                rendered += &format!("{}::: {}:{}:{}\n", gutter, file_name, line, column);
+                current_file = file_name.clone();
+            }
+            let (column, len) = source_map.underline(*span);
+            let marker = if label.node == self.node { "^" } else { "-" };
+            rendered += &format!(
+                "{:>width$} | {}\n",
+                line,
+                source_map.line(*line),
+                width = width
+            );
+            rendered += &format!(
+                "{} | {}{} {}{}\n",
+                gutter,
+                " ".repeat(column - 1),
+                marker.repeat(len),
+                label.message,
+                location
+            );
+        }
+
+        if self.help.is_some() || !self.notes.is_empty() {
+            rendered += &format!("{} |\n", gutter);
+        }
+        if let Some(help) = &self.help {
+            rendered += &format!("{} = help: {}\n", gutter, help);
+        }
+        for note in &self.notes {
+            // The lines of a note are aligned under its first line, like in `Display`
+            let mut lines = note.lines();
+            let indent = " ".repeat(gutter.len() + " = note: ".len());
+            rendered += &format!("{} = note: {}\n", gutter, lines.next().unwrap_or_default());
+            for line in lines {
+                rendered += &format!("{}{}\n", indent, line);
+            }
+        }
+        rendered
+    }
+
+    /// The diagnostic as JSON, in the structure of rustc's `--error-format=json`, with the spans
+    /// of its nodes in the input whose facts were emitted, from the file with the given name.
+    /// The rendered diagnostic is the one of `render`.
+    pub fn to_json(&self, facts: &Facts, file_name: &str, input: &str) -> Json {
+        self.to_json_located(facts, &|span| (file_name.to_string(), input, span))
+    }
+
+    /// The diagnostic as JSON like `to_json`, with the spans located in their file by `locate`,
+    /// like `render_located`.
+    pub(crate) fn to_json_located<'a>(
+        &self,
+        facts: &Facts,
+        locate: &dyn Fn(Span) -> (String, &'a str, Span),
+    ) -> Json {
+        let span_json = |span: Span, is_primary: bool, label: Option<&str>| {
+            let (file_name, text, span) = locate(span);
+            let mut members = span_location_json(&file_name, &SourceMap::new(text), span);
+            members.push(("is_primary".to_string(), Json::Bool(is_primary)));
+            members.push((
+                "label".to_string(),
+                label.map_or(Json::Null, |label| Json::String(label.to_string())),
+            ));
+            Json::Object(members)
+        };
+
+        // The reported node is the primary span, even when it's not labeled
+        let mut spans = Vec::new();
+        if !self.labels.iter().any(|label| label.node == self.node) {
+            spans.extend(
+                facts
+                    .node_span(&self.node)
+                    .map(|span| span_json(span, true, None)),
+            );
+        }
+        for label in &self.labels {
+            if let Some(span) = facts.node_span(&label.node) {
+                let is_primary = label.node == self.node;
+                spans.push(span_json(span, is_primary, Some(&label.message)));
+            }
+        }
+
+        let help = self.help.iter().map(|help| ("help", help));
+        let notes = self.notes.iter().map(|note| ("note", note));
+        let children = help.chain(notes).map(|(level, message)| {
+            Json::Object(vec![
+                ("message".to_string(), Json::String(message.clone())),
+                ("code".to_string(), Json::Null),
+                ("level".to_string(), Json::String(level.to_string())),
+                ("spans".to_string(), Json::Array(Vec::new())),
+                ("children".to_string(), Json::Array(Vec::new())),
+                ("rendered".to_string(), Json::Null),
+            ])
+        });
+        let code = self.code.map_or(Json::Null, |code| {
+            Json::Object(vec![
+                ("code".to_string(), Json::String(code.to_string())),
+                ("explanation".to_string(), Json::Null),
+            ])
+        });
+        Json::Object(vec![
+            ("message".to_string(), Json::String(self.message.clone())),
+            ("code".to_string(), code),
+            ("level".to_string(), Json::String("error".to_string())),
+            ("spans".to_string(), Json::Array(spans)),
+            ("children".to_string(), Json::Array(children.collect())),
+            (
+                "rendered".to_string(),
+                Json::String(self.render_located(facts, locate)),
+            ),
+        ])
+    }
+}
+
+/// The location of a span of a file, in the members of rustc's JSON spans: the file's name, the
+/// byte offsets of the span, and its lines and columns, which start at 1.
+pub(crate) fn span_location_json(
+    file_name: &str,
+    source_map: &SourceMap<'_>,
+    span: Span,
+) -> Vec<(String, Json)> {
+    let (line_start, column_start) = source_map.line_col(span.start());
+    let (line_end, column_end) = source_map.line_col(span.end());
+    let number = |n: usize| Json::Number(n as f64);
+    vec![
+        ("file_name".to_string(), Json::String(file_name.to_string())),
+        ("byte_start".to_string(), number(span.start())),
+        ("byte_end".to_string(), number(span.end())),
+        ("line_start".to_string(), number(line_start)),
+        ("line_end".to_string(), number(line_end)),
+        ("column_start".to_string(), number(column_start)),
+        ("column_end".to_string(), number(column_end)),
+    ]
+}
+
+/// Solves the facts and returns the diagnostics for their errors, in node order.
+pub fn diagnose(facts: &Facts) -> Vec<Diagnostic> {
+    diagnose_with_limits(facts, &Limits::default()).expect("solving without limits can't fail")
+}
+
+/// Solves the facts and returns the diagnostics for their errors like `diagnose`, or fails if
+/// solving exceeds the limits.
+#[tracing::instrument(skip_all)]
+pub fn diagnose_with_limits(facts: &Facts, limits: &Limits) -> eyre::Result<Vec<Diagnostic>> {
+    let budget = Budget::start(limits);
+    diagnose_with_solver(facts, &mut |facts| solve_within(facts, &budget))
+}
+
+/// Returns the diagnostics for the errors of the facts like `diagnose`, with the given solver,
+/// e.g. Soufflé. The solver is called once on the facts, and once more for each invalidation.
+pub(crate) fn diagnose_with_solver(
+    facts: &Facts,
+    solve: &mut dyn FnMut(&Facts) -> eyre::Result<SolverOutput>,
+) -> eyre::Result<Vec<Diagnostic>> {
+    let diagnostics = Diagnostics { facts };
+    let output = solve(facts)?;
+
+    let mut diagnostics_per_node = Vec::new();
+
+    // Invalidations are independent from each other: the errors caused by an invalidation are
+    // found by solving the facts with only that invalidation. Conflicting actions invalidating
+    // multiple loans are only reported once, like rustc does.
+    if !output.invalidated_origin_accessed.is_empty() {
+        let mut reported_nodes = Vec::new();
+        for (idx, (_, node)) in facts.invalidate_origin.iter().enumerate() {
+            if reported_nodes.contains(&node) {
+                continue;
+            }
+
+            let mut isolated = facts.clone();
+            isolated.invalidate_origin = vec![facts.invalidate_origin[idx].clone()];
+            let first_access = solve(&isolated)?
+                .invalidated_origin_accessed
+                .into_iter()
+                .min_by_key(|(_, node)| diagnostics.node_order(node));
+            if let Some((origin, access_node)) = first_access {
+                reported_nodes.push(node);
+                diagnostics_per_node.push(diagnostics.invalidation(
+                    idx,
+                    &origin,
+                    &access_node,
+                    &output,
+                ));
+            }
+        }
+    }
+
+    // Subsets involving placeholders hold from where they're required until the end of the
+    // function: report them once, where they're first required.
+    let mut reported_loans = Vec::new();
+    for (loan, placeholder, node) in
+        diagnostics.in_node_order(&output.loan_escapes_into_placeholder)
+    {
+        if reported_loans.contains(&loan) {
+            continue;
+        }
+        reported_loans.push(loan);
+        if let Some(diagnostic) = diagnostics.escaping_loan(loan, placeholder, node) {
+            diagnostics_per_node.push(diagnostic);
+        }
+    }
+
+    let mut reported_subsets = Vec::new();
+    for (origin1, origin2, node) in diagnostics.in_node_order(&output.placeholder_subset_error) {
+        if reported_subsets.contains(&(origin1, origin2)) {
+            continue;
+        }
+        reported_subsets.push((origin1, origin2));
+        diagnostics_per_node.push(diagnostics.placeholder_subset(origin1, origin2, node));
+    }
+
+    for error in move_errors(facts) {
+        diagnostics_per_node.push(diagnostics.use_after_move(&error));
+    }
+
+    diagnostics_per_node.sort_by_key(|diagnostic| diagnostics.node_order(&diagnostic.node));
+    Ok(diagnostics_per_node)
+}
+
+/// Attaches the derivation tree of each error to the diagnostics reporting it, as a note
+/// explaining it in terms of the program's statements, see `Derivation::to_source_string`. The
+/// derivations are e.g. the in-crate solver's, from `explain`, or Soufflé's, from
+/// `explain_with_souffle`.
+///
+/// An error is reported by the diagnostics with a label at its node: an invalidated origin
+/// accessed later is labeled where it's accessed.
+pub fn explain_diagnostics(
+    diagnostics: &mut [Diagnostic],
+    derivations: &[Derivation],
+    facts: &Facts,
+) {
+    for derivation in derivations {
+        let node = match derivation.node() {
+            Some(node) => node,
+            None => continue,
+        };
+        for diagnostic in diagnostics.iter_mut() {
+            if diagnostic.labels.iter().any(|label| label.node.0 == node) {
+                diagnostic.notes.push(derivation.to_source_string(facts));
+            }
+        }
+    }
+}
+
+struct Diagnostics<'a> {
+    facts: &'a Facts,
+}
+
+impl Diagnostics<'_> {
+    // The position of the node in the program, for nodes to be reported in the order they
+    // appear.
+    fn node_order(&self, node: &Node) -> usize {
+        self.facts
+            .node_text
+            .iter()
+            .position(|(_, candidate)| candidate == node)
+            .unwrap_or(usize::MAX)
+    }
+
+    fn in_node_order<'e>(
+        &self,
+        errors: &'e [(Origin, Origin, Node)],
+    ) -> Vec<(&'e Origin, &'e Origin, &'e Node)> {
+        let mut errors: Vec<_> = errors
+            .iter()
+            .map(|(origin1, origin2, node)| (origin1, origin2, node))
+            .collect();
+        errors.sort_by_key(|(_, _, node)| self.node_order(node));
+        errors
+    }
+
+    fn label(&self, node: &Node, message: String) -> Label {
+        let text = self
+            .facts
+            .node_text
+            .iter()
+            .find(|(_, candidate)| candidate == node)
+            .map_or("(pass)", |(text, _)| text.as_str());
+        Label {
+            node: node.clone(),
+            text: text.to_string(),
+            location: self.source_location(node),
+            message,
+        }
+    }
+
+    fn source_location(&self, node: &Node) -> Option<String> {
+        self.facts
+            .source_location
+            .iter()
+            .find(|(candidate, _)| candidate == node)
+            .map(|(_, location)| location.clone())
+    }
+
+    fn diagnostic(
+        &self,
+        code: Option<&'static str>,
+        message: String,
+        node: &Node,
+        mut labels: Vec<Label>,
+    ) -> Diagnostic {
+        labels.sort_by_key(|label| self.node_order(&label.node));
+        Diagnostic {
+            code,
+            message,
+            node: node.clone(),
+            location: self.source_location(node),
+            labels,
+            help: None,
+            notes: Vec::new(),
+        }
+    }
+
+    // The error for the invalidation at index `idx`, of a loan flowing into `origin` which is
+    // accessed at `access_node`. The path to the error only goes through the nodes where the loan
+    // is still live, contained in an origin per the solver's output, and then where `origin` stays
+    // invalidated, not cleared.
+    fn invalidation(
+        &self,
+        idx: usize,
+        origin: &Origin,
+        access_node: &Node,
+        output: &SolverOutput,
+    ) -> Diagnostic {
+        let (loan_origin, node) = &self.facts.invalidate_origin[idx];
+        let mut diagnostic = self.invalidation_error(idx, origin, access_node);
+        let path = self
+            .facts
+            .loan_issued_at
+            .iter()
+            .filter(|(candidate, ..)| candidate == loan_origin)
+            .filter_map(|(_, loan, loan_node)| {
+                let mut containing: HashMap<&Node, Vec<&Origin>> = HashMap::new();
+                for (origin, _, node) in output
+                    .origin_contains_loan_at
+                    .iter()
+                    .filter(|(_, candidate, _)| candidate == loan)
+                {
+                    containing.entry(node).or_default().push(origin);
+                }
+                let mut path = self.shortest_path(loan_node, node, |node, successor| {
+                    self.loan_flows(loan_origin, &containing, node, successor)
+                })?;
+                let rest =
+                    self.shortest_path(node, access_node, |node, _| !self.cleared(origin, node))?;
+                path.extend(rest.into_iter().skip(1));
+                Some(path)
+            })
+            .min_by_key(Vec::len);
+        if let Some(path) = path {
+            let mut note =
+                "the shortest path through the borrow, its invalidation, and the later use:"
+                    .to_string();
+            for node in path {
+                let label = self.label(node, String::new());
+                note.push_str(&format!("\n{} `{}`", node.0, label.text));
+            }
+            diagnostic.notes.push(note);
+        }
+        diagnostic
+    }
+
+    // Whether the loan, issued with the origin `loan_origin`, flows along the CFG edge from `node`
+    // to `successor`, in another origin than its own. The origins containing the loan on exit
+    // from the node are those containing it on entry which are not cleared, or its own where it's
+    // issued, and those they flow into at the node: one of them must still contain it on entry to
+    // the successor. `containing` are the origins containing the loan on entry to each node.
+    fn loan_flows(
+        &self,
+        loan_origin: &Origin,
+        containing: &HashMap<&Node, Vec<&Origin>>,
+        node: &Node,
+        successor: &Node,
+    ) -> bool {
+        let mut carrying: Vec<&Origin> = containing
+            .get(node)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|origin| !self.cleared(origin, node))
+            .collect();
+        let issued_here = self
+            .facts
+            .loan_issued_at
+            .iter()
+            .any(|(origin, _, n)| origin == loan_origin && n == node);
+        if issued_here {
+            carrying.push(loan_origin);
+        }
+        loop {
+            let len = carrying.len();
+            for (origin1, origin2, n) in &self.facts.introduce_subset {
+                if n == node && carrying.contains(&origin1) && !carrying.contains(&origin2) {
+                    carrying.push(origin2);
+                }
+            }
+            if carrying.len() == len {
+                break;
+            }
+        }
+        let contained_after = containing.get(successor).into_iter().flatten();
+        carrying
+            .into_iter()
+            .any(|origin| origin != loan_origin && contained_after.clone().any(|o| *o == origin))
+    }
+
+    fn cleared(&self, origin: &Origin, node: &Node) -> bool {
+        self.facts
+            .clear_origin
+            .iter()
+            .any(|(candidate, n)| candidate == origin && n == node)
+    }
+
+    // The shortest path along the CFG edges from a node to another, both included, if there's
+    // one, only following the edges for which `follows` holds.
+    fn shortest_path<'n>(
+        &'n self,
+        from: &'n Node,
+        to: &'n Node,
+        follows: impl Fn(&Node, &Node) -> bool,
+    ) -> Option<Vec<&'n Node>> {
+        let mut successors: HashMap<&Node, Vec<&Node>> = HashMap::new();
+        for (node1, node2) in &self.facts.cfg_edge {
+            successors.entry(node1).or_default().push(node2);
+        }
+
+        // A breadth-first search, remembering the node each one was first reached from
+        let mut reached_from: HashMap<&Node, &Node> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                let mut path = vec![to];
+                while let Some(&previous) = path.last().and_then(|node| reached_from.get(node)) {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &successor in successors.get(node).into_iter().flatten() {
+                if successor != from
+                    && !reached_from.contains_key(successor)
+                    && follows(node, successor)
+                {
+                    reached_from.insert(successor, node);
+                    queue.push_back(successor);
+                }
+            }
+        }
+        None
+    }
+
+    // The error for the invalidation at index `idx`, like `invalidation`, without its path.
+    fn invalidation_error(&self, idx: usize, origin: &Origin, access_node: &Node) -> Diagnostic {
+        let (loan, node) = &self.facts.invalidate_origin[idx];
+        let issued_loan = self.facts.issued_loan(loan);
+        let is_assignment = self
+            .facts
+            .provenance(Relation::InvalidateOrigin, idx)
+            .map(|provenance| provenance.path.0.first() == Some(&ExprPathElem::Lhs));
+
+        match (issued_loan, is_assignment) {
+            (Some((_, place, loan_node)), Some(true)) => {
+                // The place assigned can be a prefix of the borrowed place, or one of its fields,
+                // like rustc, the error is about the place assigned
+                let assigned = self
+                    .facts
+                    .place_assigned_at
+                    .iter()
+                    .find(|(_, candidate)| candidate == node)
+                    .map_or(place, |(assigned, _)| assigned.as_str());
+                self.diagnostic(
+                    Some("E0506"),
+                    format!("cannot assign to `{}` because it is borrowed", assigned),
+                    node,
+                    vec![
+                        self.label(loan_node, format!("`{}` is borrowed here", place)),
+                        self.label(
+                            node,
+                            format!(
+                                "`{}` is assigned to here but it was already borrowed",
+                                assigned
+                            ),
+                        ),
+                        self.label(access_node, "borrow later used here".to_string()),
+                    ],
+                )
+            }
+
+            (Some((LoanKind::Shared, place, loan_node)), Some(false)) => self.diagnostic(
+                Some("E0502"),
+                format!(
+                    "cannot borrow `{}` as mutable because it is also borrowed as immutable",
+                    place
+                ),
+                node,
+                vec![
+                    self.label(loan_node, "immutable borrow occurs here".to_string()),
+                    self.label(node, "mutable borrow occurs here".to_string()),
+                    self.label(access_node, "immutable borrow later used here".to_string()),
+                ],
+            ),
+
+            (Some((LoanKind::Mutable, place, loan_node)), Some(false)) => self.diagnostic(
+                Some("E0499"),
+                format!(
+                    "cannot borrow `{}` as mutable more than once at a time",
+                    place
+                ),
+                node,
+                vec![
+                    self.label(loan_node, "first mutable borrow occurs here".to_string()),
+                    self.label(node, "second mutable borrow occurs here".to_string()),
+                    self.label(access_node, "first borrow later used here".to_string()),
+                ],
+            ),
+
+            _ => self.diagnostic(
+                None,
+                format!("loan `{}` is invalidated while it's still in use", loan.0),
+                node,
+                vec![
+                    self.label(node, format!("`{}` is invalidated here", loan.0)),
+                    self.label(
+                        access_node,
+                        format!("`{}` is later accessed here", origin.0),
+                    ),
+                ],
+            ),
+        }
+    }
+
+    // The error for a loan flowing into a placeholder. Reborrows through references (`&*x`) can
+    // rightfully flow into placeholders, so only loans of locals are errors: loans whose place
+    // is unknown are not reported.
+    fn escaping_loan(
+        &self,
+        loan: &Origin,
+        placeholder: &Origin,
+        node: &Node,
+    ) -> Option<Diagnostic> {
+        let (_, place, loan_node) = self.facts.issued_loan(loan)?;
+        if place.contains('*') {
+            return None;
+        }
+
+        let local = place.split('.').next().unwrap_or(place);
+        Some(self.diagnostic(
+            Some("E0597"),
+            format!("`{}` does not live long enough", local),
+            loan_node,
+            vec![
+                self.label(
+                    loan_node,
+                    "borrowed value does not live long enough".to_string(),
+                ),
+                self.label(
+                    node,
+                    format!(
+                        "this usage requires that `{}` is borrowed for `{}`",
+                        place, placeholder.0
+                    ),
+                ),
+            ],
+        ))
+    }
+
+    fn use_after_move(&self, error: &MoveError) -> Diagnostic {
+        let (message, moved, used) = if error.is_partial() {
+            (
+                format!("use of partially moved value: `{}`", error.accessed_path),
+                format!("`{}` moved here", error.moved_path),
+                "value used here after partial move",
+            )
+        } else {
+            (
+                format!("use of moved value: `{}`", error.moved_path),
+                "value moved here".to_string(),
+                "value used here after move",
+            )
+        };
+        self.diagnostic(
+            Some("E0382"),
+            message,
+            &error.accessed_at,
+            vec![
+                self.label(&error.moved_at, moved),
+                self.label(&error.accessed_at, used.to_string()),
+            ],
+        )
+    }
+
+    fn placeholder_subset(&self, origin1: &Origin, origin2: &Origin, node: &Node) -> Diagnostic {
+        let mut diagnostic = self.diagnostic(
+            None,
+            "lifetime may not live long enough".to_string(),
+            node,
+            vec![self.label(
+                node,
+                format!("requires that `{}` must outlive `{}`", origin1.0, origin2.0),
+            )],
+        );
+        diagnostic.help = Some(format!(
+            "consider adding the following bound: `{}: {}`",
+            origin1.0, origin2.0
+        ));
+        diagnostic
+    }
+}
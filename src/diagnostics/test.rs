@@ -0,0 +1,548 @@
+use super::*;
+use crate::fact_emitter::test::expect_facts;
+use insta::assert_display_snapshot;
+
+// Renders the diagnostics of the program, with the readable node names of the tests.
+fn expect_diagnostics(program: &str) -> String {
+    diagnose(&expect_facts(program))
+        .iter()
+        .map(|diagnostic| diagnostic.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn assign_to_borrowed() {
+    let program = "
+        let p: i32;
+        let x: &'x i32;
+
+        bb0: {
+            p = 22;
+            x = &'L_p p;
+            p = 33;
+            use(move x);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0506]: cannot assign to `p` because it is borrowed
+      --> c
+       | b `x = &'L_p p`: `p` is borrowed here
+       | c `p = 33`: `p` is assigned to here but it was already borrowed
+       | d `use(move x)`: borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               b `x = &'L_p p`
+               c `p = 33`
+               d `use(move x)`
+    "###);
+}
+
+#[test]
+fn assign_to_other_index_of_borrowed_array() {
+    // The indices of an array are not told apart, even constant ones, like in rustc
+    let program = "
+        let x: [i32; 3];
+        let r: &'r i32;
+
+        bb0: {
+            r = &'L x[1];
+            x[2] = 1;
+            use(copy r);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0506]: cannot assign to `x[2]` because it is borrowed
+      --> b
+       | a `r = &'L x[1]`: `x[1]` is borrowed here
+       | b `x[2] = 1`: `x[2]` is assigned to here but it was already borrowed
+       | c `use(copy r)`: borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               a `r = &'L x[1]`
+               b `x[2] = 1`
+               c `use(copy r)`
+    "###);
+}
+
+#[test]
+fn assign_to_field_of_borrowed() {
+    // The place assigned is reported, rather than the borrowed place it overlaps
+    let program = "
+        struct Pair { first: i32, second: i32 }
+        let p: Pair;
+        let x: &'x Pair;
+
+        bb0: {
+            x = &'L_p p;
+            p.first = 1;
+            use(copy x);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0506]: cannot assign to `p.first` because it is borrowed
+      --> b
+       | a `x = &'L_p p`: `p` is borrowed here
+       | b `p.first = 1`: `p.first` is assigned to here but it was already borrowed
+       | c `use(copy x)`: borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               a `x = &'L_p p`
+               b `p.first = 1`
+               c `use(copy x)`
+    "###);
+}
+
+#[test]
+fn conflicting_borrows() {
+    let program = "
+        let p: i32;
+        let x: &'x i32;
+        let y: &'y mut i32;
+
+        bb0: {
+            x = &'L_p p;
+            y = &'L_p_mut mut p;
+            use(move x);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0502]: cannot borrow `p` as mutable because it is also borrowed as immutable
+      --> b
+       | a `x = &'L_p p`: immutable borrow occurs here
+       | b `y = &'L_p_mut mut p`: mutable borrow occurs here
+       | c `use(move x)`: immutable borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               a `x = &'L_p p`
+               b `y = &'L_p_mut mut p`
+               c `use(move x)`
+    "###);
+
+    let program = "
+        let p: i32;
+        let x: &'x mut i32;
+        let y: &'y mut i32;
+
+        bb0: {
+            x = &'L_p mut p;
+            y = &'L_p_mut mut p;
+            use(move x);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0499]: cannot borrow `p` as mutable more than once at a time
+      --> b
+       | a `x = &'L_p mut p`: first mutable borrow occurs here
+       | b `y = &'L_p_mut mut p`: second mutable borrow occurs here
+       | c `use(move x)`: first borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               a `x = &'L_p mut p`
+               b `y = &'L_p_mut mut p`
+               c `use(move x)`
+    "###);
+}
+
+#[test]
+fn conflicting_action_reported_once() {
+    // Both loans are in use when `p` is assigned, which is a single error
+    let program = "
+        let p: i32;
+        let x: &'x i32;
+        let y: &'y i32;
+
+        bb0: {
+            x = &'L_p1 p;
+            y = &'L_p2 p;
+            p = 33;
+            use(move x, move y);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0506]: cannot assign to `p` because it is borrowed
+      --> c
+       | a `x = &'L_p1 p`: `p` is borrowed here
+       | c `p = 33`: `p` is assigned to here but it was already borrowed
+       | d `use(move x, move y)`: borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               a `x = &'L_p1 p`
+               b `y = &'L_p2 p`
+               c `p = 33`
+               d `use(move x, move y)`
+    "###);
+}
+
+#[test]
+fn borrowed_value_does_not_live_long_enough() {
+    let program = "
+        placeholder 'a;
+
+        let p: i32;
+        let x: &'a i32;
+
+        bb0: {
+            x = &'L_p p;
+            use(copy x);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0597]: `p` does not live long enough
+      --> a
+       | a `x = &'L_p p`: borrowed value does not live long enough
+       | a `x = &'L_p p`: this usage requires that `p` is borrowed for `'a`
+    "###);
+
+    // Reborrowing data the placeholder already outlives is fine
+    let program = "
+        placeholder 'a;
+
+        let r: &'a i32;
+        let x: &'a i32;
+
+        bb0: {
+            x = &'L_r *r;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @"");
+}
+
+#[test]
+fn placeholder_outlives_requirement() {
+    let program = "
+        placeholder 'a, 'b;
+
+        let x: &'a i32;
+        let y: &'b i32;
+
+        bb0: {
+            y = copy x;
+            y = copy x;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: lifetime may not live long enough
+      --> a
+       | a `y = copy x`: requires that `'a` must outlive `'b`
+       = help: consider adding the following bound: `'a: 'b`
+    "###);
+}
+
+#[test]
+fn facts_without_loan_details() {
+    // Fact files don't know what the loans borrow, nor what invalidates them
+    let program = "
+        mark_as_loan_origin('L_p)
+
+        a: \"x = &'L_p p\" {
+            clear_origin('L_p)
+            introduce_subset('L_p, 'x)
+            goto b
+        }
+        b: \"p = 33\" {
+            invalidate_origin('L_p)
+            goto c
+        }
+        c: \"use(move x)\" {
+            access_origin('x)
+            goto
+        }
+    ";
+    let facts = crate::fact_parser::read_facts(program).unwrap();
+    let diagnostics: Vec<_> = diagnose(&facts).iter().map(|d| d.to_string()).collect();
+    assert_display_snapshot!(diagnostics.join("\n"), @r###"
+    error: loan `'L_p` is invalidated while it's still in use
+      --> b
+       | b `p = 33`: `'L_p` is invalidated here
+       | c `use(move x)`: `'x` is later accessed here
+    "###);
+}
+
+#[test]
+fn shortest_path_to_the_error() {
+    // The path to the invalidation takes the shorter branch, and leaves the loop without going
+    // around it
+    let program = "
+        let x: i32;
+        let y: i32;
+        let r: &'r i32;
+
+        bb0: {
+            r = &'L_x x;
+            goto bb1, bb2;
+        }
+        bb1: {
+            y = 0;
+            y = 1;
+            goto bb3;
+        }
+        bb2: {
+            y = 2;
+            goto bb3;
+        }
+        bb3: {
+            x = 1;
+            goto bb4, bb5;
+        }
+        bb4: {
+            goto bb3;
+        }
+        bb5: {
+            use(copy r);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0506]: cannot assign to `x` because it is borrowed
+      --> e
+       | a `r = &'L_x x`: `x` is borrowed here
+       | e `x = 1`: `x` is assigned to here but it was already borrowed
+       | g `use(copy r)`: borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               a `r = &'L_x x`
+               d `y = 2`
+               e `x = 1`
+               g `use(copy r)`
+    "###);
+}
+
+#[test]
+fn shortest_path_where_the_loan_is_live() {
+    // The shorter branches reassign `r`, which no longer contains the loan there: the path goes
+    // through the longer ones, before and after the invalidation
+    let program = "
+        let x: i32;
+        let y: i32;
+        let z: i32;
+        let r: &'r i32;
+
+        bb0: {
+            r = &'L_x x;
+            goto bb1, bb2;
+        }
+        bb1: {
+            z = 0;
+            z = 1;
+            goto bb3;
+        }
+        bb2: {
+            r = &'M y;
+            goto bb3;
+        }
+        bb3: {
+            x = 1;
+            goto bb4, bb5;
+        }
+        bb4: {
+            r = &'N y;
+            goto bb6;
+        }
+        bb5: {
+            z = 2;
+            z = 3;
+            goto bb6;
+        }
+        bb6: {
+            use(copy r);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0506]: cannot assign to `x` because it is borrowed
+      --> e
+       | a `r = &'L_x x`: `x` is borrowed here
+       | e `x = 1`: `x` is assigned to here but it was already borrowed
+       | i `use(copy r)`: borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               a `r = &'L_x x`
+               b `z = 0`
+               c `z = 1`
+               e `x = 1`
+               g `z = 2`
+               h `z = 3`
+               i `use(copy r)`
+    "###);
+}
+
+#[test]
+fn use_after_move() {
+    let program = "
+        struct Pair { first: Vec, second: Vec }
+
+        let pair: Pair;
+        let v: Vec;
+
+        bb0: {
+            v = move pair.first;
+            use(move pair.second);
+            use(move pair);
+            use(move v);
+            use(move v);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0382]: use of partially moved value: `pair`
+      --> c
+       | a `v = move pair.first`: `pair.first` moved here
+       | c `use(move pair)`: value used here after partial move
+
+    error[E0382]: use of moved value: `v`
+      --> e
+       | d `use(move v)`: value moved here
+       | e `use(move v)`: value used here after move
+    "###);
+}
+
+#[test]
+fn method_call_invalidates_reference() {
+    // The classic example of pushing into a vector while a reference to its contents is live,
+    // with the receivers borrowed by the method calls
+    let program = "
+        struct Vec<T> { item: T }
+        impl<T> Vec<T> {
+            fn new() -> Self;
+            fn push(&mut self, value: T) -> ();
+            fn first(&self) -> &T;
+        }
+
+        let v: Vec<i32>;
+        let r: &i32;
+
+        bb0: {
+            v = Vec::new();
+            r = v.first();
+            v.push(1);
+            use(copy r);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0502]: cannot borrow `v` as mutable because it is also borrowed as immutable
+      --> d
+       | b `v.first()`: immutable borrow occurs here
+       | d `v.push(1)`: mutable borrow occurs here
+       | f `use(copy r)`: immutable borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               b `v.first()`
+               c `r = v.first()`
+               d `v.push(1)`
+               e `v.push(1)`
+               f `use(copy r)`
+    "###);
+}
+
+#[test]
+fn errors_at_source_locations() {
+    // The statements transcribed from Rust are located in its source
+    let program = "
+        let p: i32;
+        let x: &'x i32;
+
+        bb0: {
+            x = &'L_p p; @ main.rs:3
+            p = 33; @ main.rs:4
+            use(move x);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error[E0506]: cannot assign to `p` because it is borrowed
+      --> b (main.rs:4)
+       | a `x = &'L_p p` (main.rs:3): `p` is borrowed here
+       | b `p = 33` (main.rs:4): `p` is assigned to here but it was already borrowed
+       | c `use(move x)`: borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               a `x = &'L_p p`
+               b `p = 33`
+               c `use(move x)`
+    "###);
+}
+
+#[test]
+fn json_diagnostics() {
+    // The labels are spans of the statements, at byte offsets of the input, and the help is a
+    // child diagnostic
+    let program = "let p: i32;
+let x: &'x i32;
+bb0: {
+    p = 22;
+    x = &'L_p p;
+    p = 33;
+    use(move x);
+}
+";
+    let facts = expect_facts(program);
+    let diagnostics = diagnose(&facts);
+    assert_eq!(diagnostics.len(), 1);
+    assert_display_snapshot!(diagnostics[0].to_json(&facts, "main.txt", program), @r###"
+    {"message":"cannot assign to `p` because it is borrowed","code":{"code":"E0506","explanation":null},"level":"error","spans":[{"file_name":"main.txt","byte_start":51,"byte_end":62,"line_start":5,"line_end":5,"column_start":5,"column_end":16,"is_primary":false,"label":"`p` is borrowed here"},{"file_name":"main.txt","byte_start":68,"byte_end":74,"line_start":6,"line_end":6,"column_start":5,"column_end":11,"is_primary":true,"label":"`p` is assigned to here but it was already borrowed"},{"file_name":"main.txt","byte_start":80,"byte_end":91,"line_start":7,"line_end":7,"column_start":5,"column_end":16,"is_primary":false,"label":"borrow later used here"}],"children":[{"message":"the shortest path through the borrow, its invalidation, and the later use:\nb `x = &'L_p p`\nc `p = 33`\nd `use(move x)`","code":null,"level":"note","spans":[],"children":[],"rendered":null}],"rendered":"error[E0506]: cannot assign to `p` because it is borrowed\n --> main.txt:6:5\n  |\n5 |     x = &'L_p p;\n  |     ----------- `p` is borrowed here\n6 |     p = 33;\n  |     ^^^^^^ `p` is assigned to here but it was already borrowed\n7 |     use(move x);\n  |     ----------- borrow later used here\n  |\n  = note: the shortest path through the borrow, its invalidation, and the later use:\n          b `x = &'L_p p`\n          c `p = 33`\n          d `use(move x)`\n"}
+    "###);
+
+    // Fact files have no spans
+    let mut facts = expect_facts(program);
+    facts.node_span.clear();
+    let diagnostics = diagnose(&facts);
+    assert_display_snapshot!(diagnostics[0].to_json(&facts, "main.facts", ""), @r###"
+    {"message":"cannot assign to `p` because it is borrowed","code":{"code":"E0506","explanation":null},"level":"error","spans":[],"children":[{"message":"the shortest path through the borrow, its invalidation, and the later use:\nb `x = &'L_p p`\nc `p = 33`\nd `use(move x)`","code":null,"level":"note","spans":[],"children":[],"rendered":null}],"rendered":"error[E0506]: cannot assign to `p` because it is borrowed\n  --> c\n   | b `x = &'L_p p`: `p` is borrowed here\n   | c `p = 33`: `p` is assigned to here but it was already borrowed\n   | d `use(move x)`: borrow later used here\n   = note: the shortest path through the borrow, its invalidation, and the later use:\n           b `x = &'L_p p`\n           c `p = 33`\n           d `use(move x)`\n"}
+    "###);
+}
+
+#[test]
+fn rendered_diagnostics() {
+    // The labels underline the statements, with `^` at the reported node
+    let program = "let p: i32;
+let x: &'x i32;
+bb0: {
+    p = 22;
+    x = &'L_p p;
+    p = 33; @ main.rs:4
+    use(move x);
+}
+";
+    let facts = expect_facts(program);
+    let diagnostics = diagnose(&facts);
+    assert_display_snapshot!(diagnostics[0].render(&facts, "main.txt", program), @r###"
+    error[E0506]: cannot assign to `p` because it is borrowed
+     --> main.txt:6:5 (main.rs:4)
+      |
+    5 |     x = &'L_p p;
+      |     ----------- `p` is borrowed here
+    6 |     p = 33; @ main.rs:4
+      |     ^^^^^^ `p` is assigned to here but it was already borrowed (main.rs:4)
+    7 |     use(move x);
+      |     ----------- borrow later used here
+      |
+      = note: the shortest path through the borrow, its invalidation, and the later use:
+              b `x = &'L_p p`
+              c `p = 33`
+              d `use(move x)`
+    "###);
+
+    // Fact files have no spans
+    let mut facts = expect_facts(program);
+    facts.node_span.clear();
+    let diagnostics = diagnose(&facts);
+    assert_eq!(
+        diagnostics[0].render(&facts, "main.facts", ""),
+        diagnostics[0].to_string()
+    );
+}
+
+#[test]
+fn diagnostics_explained_by_derivations() {
+    let program = "
+        placeholder 'a, 'b;
+        let x: &'a i32;
+        let y: &'b i32;
+
+        bb0: {
+            y = copy x;
+        }
+    ";
+    let facts = expect_facts(program);
+    let mut diagnostics = diagnose(&facts);
+    explain_diagnostics(&mut diagnostics, &crate::solver::explain(&facts), &facts);
+    assert_display_snapshot!(diagnostics[0], @r###"
+    error: lifetime may not live long enough
+      --> a
+       | a `y = copy x`: requires that `'a` must outlive `'b`
+       = help: consider adding the following bound: `'a: 'b`
+       = note: placeholder_subset_error('a, 'b, a) at `y = copy x` [unknown subset between placeholders]
+               ├── subset_on_exit('a, 'b, a) at `y = copy x` [introduced]
+               │   └── introduce_subset('a, 'b, a) at `y = copy x`
+               ├── placeholder_origin('a)
+               ├── placeholder_origin('b)
+               └── !known_placeholder_subset_closure('a, 'b)
+    "###);
+}
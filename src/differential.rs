@@ -0,0 +1,248 @@
+//! Differential testing against rustc: a program is translated to equivalent Rust source, which
+//! is checked with `rustc --error-format=json`, and the statements where rustc reports borrow
+//! errors are compared with the ones where this crate's analysis does.
+//!
+//! The errors rustc reports which are not borrow errors, like the mutability errors which
+//! aren't modeled here, or type errors in the translation, are listed separately.
+
+mod rust_source;
+
+#[cfg(test)]
+mod test;
+
+use crate::ast_parser::parse_ast;
+use crate::diagnostics::diagnose;
+use crate::fact_emitter::{emit_facts, infer_access_kinds, Facts, Node};
+use crate::json::{parse_json, Json};
+use eyre::WrapErr;
+use rust_source::rust_source;
+use std::fmt;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The error codes of the borrow errors rustc can report for a program, including the errors
+/// about moves. Some borrow errors have no code, and are recognized by their message instead.
+const BORROW_ERROR_CODES: &[&str] = &[
+    "E0382", "E0499", "E0502", "E0503", "E0505", "E0506", "E0521", "E0597", "E0713", "E0716",
+];
+const UNCODED_BORROW_ERRORS: &[&str] = &["lifetime may not live long enough"];
+
+/// The errors rustc and this crate's analysis report for a program, per statement.
+#[derive(Debug, Default)]
+pub struct Comparison {
+    pub entries: Vec<ComparisonEntry>,
+
+    /// The errors reported by rustc which aren't borrow errors.
+    pub other_rustc_errors: Vec<String>,
+}
+
+/// The errors reported for a statement: they agree when both rustc and the analysis report
+/// errors there.
+#[derive(Debug)]
+pub struct ComparisonEntry {
+    pub node: Node,
+    pub text: String,
+    pub rustc: Vec<String>,
+    pub polonius: Vec<String>,
+}
+
+impl ComparisonEntry {
+    pub fn agrees(&self) -> bool {
+        !self.rustc.is_empty() && !self.polonius.is_empty()
+    }
+}
+
+impl Comparison {
+    pub fn agreement_count(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.agrees()).count()
+    }
+
+    pub fn divergence_count(&self) -> usize {
+        self.entries.len() - self.agreement_count()
+    }
+
+    // Returns the entry of the given node, creating it when it has no errors yet.
+    fn entry(&mut self, node: &Node, facts: &Facts) -> &mut ComparisonEntry {
+        if let Some(idx) = self.entries.iter().position(|entry| &entry.node == node) {
+            return &mut self.entries[idx];
+        }
+
+        let text = facts
+            .node_text
+            .iter()
+            .find(|(_, candidate)| candidate == node)
+            .map_or("(pass)", |(text, _)| text.as_str());
+        self.entries.push(ComparisonEntry {
+            node: node.clone(),
+            text: text.to_string(),
+            rustc: Vec::new(),
+            polonius: Vec::new(),
+        });
+        self.entries.last_mut().unwrap()
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let status = if entry.agrees() { "agree" } else { "diverge" };
+            writeln!(f, "{:<8}{}: `{}`", status, entry.node.0, entry.text)?;
+            for (tool, errors) in [("rustc", &entry.rustc), ("polonius", &entry.polonius)] {
+                if errors.is_empty() {
+                    writeln!(f, "    {:<9} no error", format!("{}:", tool))?;
+                }
+                for error in errors {
+                    writeln!(f, "    {:<9} {}", format!("{}:", tool), error)?;
+                }
+            }
+        }
+        for error in &self.other_rustc_errors {
+            writeln!(f, "rustc also reported: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the Rust source the program is translated to, to be checked by rustc.
+pub fn translate_to_rust(input: &str) -> eyre::Result<String> {
+    let mut program = parse_ast(input)?;
+    infer_access_kinds(&mut program);
+    Ok(rust_source(&program).text)
+}
+
+/// Compares the borrow errors rustc reports on the program's translation, with the errors of
+/// this crate's analysis.
+pub fn compare_with_rustc(input: &str) -> eyre::Result<Comparison> {
+    let mut program = parse_ast(input)?;
+    infer_access_kinds(&mut program);
+    let source = rust_source(&program);
+    let facts = emit_facts(input)?;
+
+    let mut comparison = Comparison::default();
+    let mut other_rustc_errors = Vec::new();
+    for error in run_rustc(&source.text)? {
+        let is_borrow_error = match &error.code {
+            Some(code) => BORROW_ERROR_CODES.contains(&code.as_str()),
+            None => UNCODED_BORROW_ERRORS.contains(&error.message.as_str()),
+        };
+        let node = error.line.and_then(|line| source.node_at_line(line));
+        match node {
+            Some(node) if is_borrow_error => {
+                comparison.entry(node, &facts).rustc.push(error.to_string())
+            }
+            _ => other_rustc_errors.push(error.to_string()),
+        }
+    }
+
+    for diagnostic in diagnose(&facts) {
+        let summary = match diagnostic.code {
+            Some(code) => format!("error[{}]: {}", code, diagnostic.message),
+            None => format!("error: {}", diagnostic.message),
+        };
+        comparison
+            .entry(&diagnostic.node, &facts)
+            .polonius
+            .push(summary);
+    }
+
+    // Report the statements in program order
+    let node_order = |node: &Node| {
+        facts
+            .node_text
+            .iter()
+            .position(|(_, candidate)| candidate == node)
+    };
+    comparison
+        .entries
+        .sort_by_key(|entry| node_order(&entry.node));
+    comparison.other_rustc_errors = other_rustc_errors;
+    Ok(comparison)
+}
+
+// An error reported by rustc, with the line of its primary span.
+struct RustcError {
+    code: Option<String>,
+    message: String,
+    line: Option<usize>,
+}
+
+impl fmt::Display for RustcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "error[{}]: {}", code, self.message),
+            None => write!(f, "error: {}", self.message),
+        }
+    }
+}
+
+// Checks the source with rustc, which is `$RUSTC` if set, and returns the errors it reports.
+fn run_rustc(source: &str) -> eyre::Result<Vec<RustcError>> {
+    // Programs can be compared in parallel, each in its own directory
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "polonius-rustc-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir)
+        .wrap_err_with(|| format!("failed to create `{}`", dir.display()))?;
+    let source_path = dir.join("program.rs");
+    std::fs::write(&source_path, source)
+        .wrap_err_with(|| format!("failed to write `{}`", source_path.display()))?;
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let output = Command::new(&rustc)
+        .args([
+            "--edition",
+            "2021",
+            "--crate-type",
+            "lib",
+            "--crate-name",
+            "program",
+        ])
+        .args(["--emit", "metadata", "--error-format", "json", "--out-dir"])
+        .arg(&dir)
+        .arg(&source_path)
+        .output()
+        .wrap_err_with(|| format!("failed to run `{}`", rustc));
+    std::fs::remove_dir_all(&dir).ok();
+    let output = output?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut errors = Vec::new();
+    for line in stderr.lines().filter(|line| line.starts_with('{')) {
+        let diagnostic = parse_json(line).wrap_err("failed to parse rustc's diagnostics")?;
+        if diagnostic.get("level").and_then(Json::as_str) != Some("error") {
+            continue;
+        }
+
+        let spans = diagnostic.get("spans").map_or(&[][..], Json::as_array);
+        // The summary at the end (`aborting due to previous error`) has no spans
+        if spans.is_empty() {
+            continue;
+        }
+        let line = spans
+            .iter()
+            .find(|span| span.get("is_primary") == Some(&Json::Bool(true)))
+            .and_then(|span| match span.get("line_start") {
+                Some(Json::Number(line)) => Some(*line as usize),
+                _ => None,
+            });
+        let code = diagnostic
+            .get("code")
+            .and_then(|code| code.get("code"))
+            .and_then(Json::as_str)
+            .map(str::to_string);
+        let message = diagnostic
+            .get("message")
+            .and_then(Json::as_str)
+            .unwrap_or_default()
+            .to_string();
+        errors.push(RustcError {
+            code,
+            message,
+            line,
+        });
+    }
+    Ok(errors)
+}
@@ -0,0 +1,515 @@
+//! Translation of a program to equivalent Rust source, whose body is the analyzed function.
+//!
+//! - the placeholders are the lifetime parameters of that function, and the other origins are
+//!   left for rustc to infer,
+//! - the variables are locals, initialized with an opaque value so that rustc doesn't report
+//!   uses of uninitialized variables,
+//! - calls to functions without a prototype are calls to an opaque function taking all the
+//!   arguments, which rustc can't relate to its result,
+//! - the CFG is encoded with labeled blocks when all its edges go forward in the program: each
+//!   block is entered by breaking out of the labeled block preceding it. Other CFGs are encoded
+//!   with a loop over a state machine, where rustc also sees the infeasible paths between
+//!   blocks, and can report more errors.
+//!
+//! Each statement is on its own line, to map rustc's errors back to the program's nodes.
+
+use crate::ast::*;
+use crate::fact_emitter::Node;
+use std::fmt::Write;
+
+/// The Rust source of a program, with the lines of its statements.
+pub struct RustSource {
+    pub text: String,
+
+    /// The 1-based line of each statement, with the node of that statement.
+    pub lines: Vec<(usize, Node)>,
+}
+
+impl RustSource {
+    /// Returns the node of the statement on the given line, if any.
+    pub fn node_at_line(&self, line: usize) -> Option<&Node> {
+        self.lines
+            .iter()
+            .find(|(candidate, _)| *candidate == line)
+            .map(|(_, node)| node)
+    }
+}
+
+// Helpers for the translated function: opaque values, opaque calls, and opaque conditions.
+const PRELUDE: &str = "#![allow(warnings)]
+
+fn any<T>() -> T { unimplemented!() }
+fn opaque<T, R>(_: T) -> R { unimplemented!() }
+fn cond() -> bool { unimplemented!() }
+";
+
+pub fn rust_source(program: &Program) -> RustSource {
+    let mut translation = Translation {
+        program,
+        text: String::new(),
+        lines: Vec::new(),
+    };
+    translation.translate();
+    RustSource {
+        text: translation.text,
+        lines: translation.lines,
+    }
+}
+
+struct Translation<'a> {
+    program: &'a Program,
+    text: String,
+    lines: Vec<(usize, Node)>,
+}
+
+impl Translation<'_> {
+    fn line(&mut self, indent: usize, line: &str) {
+        writeln!(self.text, "{:indent$}{}", "", line, indent = indent * 4).unwrap();
+    }
+
+    fn current_line(&self) -> usize {
+        self.text.lines().count() + 1
+    }
+
+    fn translate(&mut self) {
+        self.text.push_str(PRELUDE);
+
+        for decl in &self.program.struct_decls {
+            self.struct_decl(decl);
+        }
+        for (name, parameters) in self.undeclared_structs() {
+            let generics: Vec<_> = parameters
+                .iter()
+                .enumerate()
+                .map(|(idx, p)| match p {
+                    Parameter::Origin(_) => format!("'o{}", idx),
+                    Parameter::Ty(_) => format!("T{}", idx),
+                })
+                .collect();
+            let marker = marker(&generics);
+            self.line(0, "");
+            self.line(
+                0,
+                &format!("struct {}{}({});", ident(&name), list(&generics), marker),
+            );
+        }
+        for prototype in &self.program.fn_prototypes {
+            // Lifetimes are declared before types in Rust, and the origins of the signatures of
+            // `impl` blocks' functions are declared after the block's types
+            let mut generic_decls: Vec<_> = prototype.generic_decls.iter().collect();
+            generic_decls.sort_by_key(|decl| matches!(decl, GenericDecl::Ty(_)));
+            let generics: Vec<_> = generic_decls.into_iter().map(generic_decl).collect();
+            let args: Vec<_> = prototype
+                .arg_tys
+                .iter()
+                .enumerate()
+                .map(|(idx, ty)| format!("arg{}: {}", idx, rust_ty(ty, &|_| true)))
+                .collect();
+            self.line(0, "");
+            self.line(
+                0,
+                &format!(
+                    "fn {}{}({}) -> {} {{ any() }}",
+                    fn_ident(&prototype.name),
+                    list(&generics),
+                    args.join(", "),
+                    rust_ty(&prototype.ret_ty, &|_| true)
+                ),
+            );
+        }
+
+        let placeholders: Vec<_> = self
+            .program
+            .placeholders
+            .iter()
+            .filter(|p| **p != STATIC_ORIGIN)
+            .map(|p| p.to_string())
+            .collect();
+        let bounds: Vec<_> = self
+            .program
+            .known_placeholder_subsets
+            .iter()
+            .map(|(o1, o2)| format!("{}: {}", o1, o2))
+            .collect();
+        let where_clause = if bounds.is_empty() {
+            String::new()
+        } else {
+            format!(" where {}", bounds.join(", "))
+        };
+        // Dereferencing raw pointers is only allowed in an unsafe fn
+        let uses_raw_ptrs = self
+            .program
+            .variables
+            .iter()
+            .any(|v| contains_raw_ptr(&v.ty));
+        let qualifier = if uses_raw_ptrs { "unsafe " } else { "" };
+        self.line(0, "");
+        self.line(
+            0,
+            &format!(
+                "{}fn program{}(){} {{",
+                qualifier,
+                list(&placeholders),
+                where_clause
+            ),
+        );
+
+        let program = self.program;
+        let is_placeholder = |origin: &str| {
+            origin == STATIC_ORIGIN || program.placeholders.iter().any(|p| p == origin)
+        };
+        for v in &self.program.variables {
+            let line = match &v.ty {
+                Ty::Infer => format!("let mut {} = any();", ident(&v.name)),
+                ty => format!(
+                    "let mut {}: {} = any();",
+                    ident(&v.name),
+                    rust_ty(ty, &is_placeholder)
+                ),
+            };
+            self.line(1, &line);
+        }
+
+        let blocks = &self.program.basic_blocks;
+        let is_forward = blocks.iter().enumerate().all(|(idx, bb)| {
+            bb.terminator
+                .successors()
+                .iter()
+                .all(|successor| self.block_idx(successor) > idx)
+        });
+        if is_forward {
+            self.labeled_blocks();
+        } else {
+            self.state_machine();
+        }
+        self.line(0, "}");
+    }
+
+    fn struct_decl(&mut self, decl: &StructDecl) {
+        let generics: Vec<_> = decl.generic_decls.iter().map(generic_decl).collect();
+        self.line(0, "");
+        if decl.is_copy {
+            self.line(0, "#[derive(Clone, Copy)]");
+        }
+        self.line(
+            0,
+            &format!("struct {}{} {{", ident(&decl.name), list(&generics)),
+        );
+        for field in &decl.field_decls {
+            self.line(
+                1,
+                &format!("{}: {},", ident(&field.name), rust_ty(&field.ty, &|_| true)),
+            );
+        }
+        if !generics.is_empty() {
+            self.line(1, &format!("_marker: {},", marker(&generics)));
+        }
+        self.line(0, "}");
+    }
+
+    // The structs used in the variables' types without being declared, with the parameters
+    // they're first used with.
+    fn undeclared_structs(&self) -> Vec<(Name, Vec<Parameter>)> {
+        fn visit(ty: &Ty, structs: &mut Vec<(Name, Vec<Parameter>)>) {
+            match ty {
+                Ty::Ref { ty, .. }
+                | Ty::RefMut { ty, .. }
+                | Ty::RawPtr { ty }
+                | Ty::RawPtrMut { ty }
+                | Ty::Array { ty, .. } => visit(ty, structs),
+                Ty::Struct { name, parameters } => {
+                    if !structs.iter().any(|(candidate, _)| candidate == name) {
+                        structs.push((*name, parameters.clone()));
+                    }
+                    for p in parameters {
+                        if let Parameter::Ty(ty) = p {
+                            visit(ty, structs);
+                        }
+                    }
+                }
+                Ty::I32 | Ty::Unit | Ty::Infer => {}
+            }
+        }
+
+        let mut structs = Vec::new();
+        for v in &self.program.variables {
+            visit(&v.ty, &mut structs);
+        }
+        structs.retain(|(name, _)| !self.program.struct_decls.iter().any(|s| &s.name == name));
+        structs
+    }
+
+    fn block_idx(&self, name: &str) -> usize {
+        self.program
+            .basic_blocks
+            .iter()
+            .position(|bb| bb.name == name)
+            .unwrap_or_else(|| panic!("Unknown successor block {}", name))
+    }
+
+    // Each block `i` follows the labeled block `'block_i`, nested inside the labeled blocks of
+    // the blocks following it: breaking out of `'block_i` enters block `i`.
+    fn labeled_blocks(&mut self) {
+        let blocks = &self.program.basic_blocks;
+        self.line(1, "'exit: {");
+        for (idx, bb) in blocks.iter().enumerate().skip(1).rev() {
+            self.line(1 + blocks.len() - idx, &format!("{}: {{", label(&bb.name)));
+        }
+        for (idx, bb) in blocks.iter().enumerate() {
+            let indent = 1 + blocks.len() - idx;
+            self.statements(bb, indent);
+            let jumps: Vec<_> = bb
+                .terminator
+                .successors()
+                .iter()
+                .map(|successor| format!("break {};", label(successor)))
+                .collect();
+            self.jump(indent, jumps, "break 'exit;");
+            self.line(indent - 1, "}");
+        }
+    }
+
+    fn state_machine(&mut self) {
+        let blocks = &self.program.basic_blocks;
+        self.line(1, "let mut state = 0;");
+        self.line(1, "loop {");
+        self.line(2, "match state {");
+        for (idx, bb) in blocks.iter().enumerate() {
+            self.line(3, &format!("{} => {{", idx));
+            self.statements(bb, 4);
+            let jumps: Vec<_> = bb
+                .terminator
+                .successors()
+                .iter()
+                .map(|successor| format!("state = {};", self.block_idx(successor)))
+                .collect();
+            self.jump(4, jumps, "break;");
+            self.line(3, "}");
+        }
+        self.line(3, "_ => unreachable!(),");
+        self.line(2, "}");
+        self.line(1, "}");
+    }
+
+    // Ends a block with one of its jumps, chosen on opaque conditions, or with the given exit
+    // when it has no successors.
+    fn jump(&mut self, indent: usize, jumps: Vec<String>, exit: &str) {
+        let (last, rest) = match jumps.split_last() {
+            Some(split) => split,
+            None => return self.line(indent, exit),
+        };
+        if rest.is_empty() {
+            return self.line(indent, last);
+        }
+        for (idx, jump) in rest.iter().enumerate() {
+            let keyword = if idx == 0 { "if" } else { "} else if" };
+            self.line(indent, &format!("{} cond() {{", keyword));
+            self.line(indent + 1, jump);
+        }
+        self.line(indent, "} else {");
+        self.line(indent + 1, last);
+        self.line(indent, "}");
+    }
+
+    fn statements(&mut self, bb: &BasicBlock, indent: usize) {
+        for (idx, s) in bb.statements.iter().enumerate() {
+            let line = match &**s {
+                Statement::Assign(place, expr) => {
+                    format!("{} = {};", rust_place(place), self.expr(expr))
+                }
+                Statement::Expr(Expr::Call {
+                    name,
+                    generic_args,
+                    arguments,
+                }) if self.is_declared(name) => {
+                    format!("{};", self.call(name, generic_args, arguments))
+                }
+                Statement::Expr(Expr::Call { arguments, .. }) => {
+                    format!("opaque::<_, ()>({});", self.tuple(arguments))
+                }
+                Statement::Expr(expr) => format!("opaque::<_, ()>({});", self.expr(expr)),
+
+                // Rust has no equivalent of forcing an origin or a loan to end
+                Statement::End(origin) => format!("// end({});", origin),
+                Statement::Kill(place) => format!("// kill({});", rust_place(place)),
+            };
+            let node = Node::from(format!("{}[{}]", bb.name, idx));
+            self.lines.push((self.current_line(), node));
+            self.line(indent, &line);
+        }
+    }
+
+    fn is_declared(&self, name: &str) -> bool {
+        self.program.fn_prototypes.iter().any(|f| f.name == name)
+    }
+
+    fn expr(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::Access { kind, place } => match kind {
+                AccessKind::Copy | AccessKind::Move | AccessKind::Infer => rust_place(place),
+                AccessKind::Borrow(_) => format!("&{}", rust_place(place)),
+                AccessKind::BorrowMut(_) => format!("&mut {}", rust_place(place)),
+            },
+            Expr::Number { value } => value.to_string(),
+            Expr::Call {
+                name,
+                generic_args,
+                arguments,
+            } => self.call(name, generic_args, arguments),
+            Expr::Cast { operand, ty } => {
+                format!("{} as {}", self.expr(operand), rust_ty(ty, &|_| false))
+            }
+            Expr::Unit => "()".to_string(),
+        }
+    }
+
+    // A call to a declared function, with the types of its turbofish: the origins are left to
+    // inference, as they can't be given to late-bound lifetimes.
+    fn call(&self, name: &str, generic_args: &[Parameter], arguments: &[Expr]) -> String {
+        if self.is_declared(name) {
+            let program = self.program;
+            let is_placeholder = |origin: &str| {
+                origin == STATIC_ORIGIN || program.placeholders.iter().any(|p| p == origin)
+            };
+            let tys: Vec<_> = generic_args
+                .iter()
+                .filter_map(|arg| match arg {
+                    Parameter::Ty(ty) => Some(rust_ty(ty, &is_placeholder)),
+                    Parameter::Origin(_) => None,
+                })
+                .collect();
+            let turbofish = if tys.is_empty() {
+                String::new()
+            } else {
+                format!("::<{}>", tys.join(", "))
+            };
+            let arguments: Vec<_> = arguments.iter().map(|arg| self.expr(arg)).collect();
+            format!("{}{}({})", fn_ident(name), turbofish, arguments.join(", "))
+        } else {
+            format!("opaque({})", self.tuple(arguments))
+        }
+    }
+
+    // The arguments of an opaque call, passed as a tuple, with a trailing comma to make single
+    // arguments tuples as well.
+    fn tuple(&self, arguments: &[Expr]) -> String {
+        let arguments: Vec<_> = arguments.iter().map(|arg| self.expr(arg)).collect();
+        let trailing_comma = if arguments.is_empty() { "" } else { "," };
+        format!("({}{})", arguments.join(", "), trailing_comma)
+    }
+}
+
+// The Rust type of `ty`, where the origins not satisfying `is_named` are left to inference.
+fn rust_ty(ty: &Ty, is_named: &dyn Fn(&str) -> bool) -> String {
+    let origin = |origin: &str| {
+        if is_named(origin) && origin != ANONYMOUS_ORIGIN {
+            origin.to_string()
+        } else {
+            "'_".to_string()
+        }
+    };
+    match ty {
+        Ty::Ref { origin: o, ty } => format!("&{} {}", origin(o), rust_ty(ty, is_named)),
+        Ty::RefMut { origin: o, ty } => format!("&{} mut {}", origin(o), rust_ty(ty, is_named)),
+        Ty::RawPtr { ty } => format!("*const {}", rust_ty(ty, is_named)),
+        Ty::RawPtrMut { ty } => format!("*mut {}", rust_ty(ty, is_named)),
+        Ty::Array { ty, len } => format!("[{}; {}]", rust_ty(ty, is_named), len),
+        Ty::I32 => "i32".to_string(),
+        Ty::Unit => "()".to_string(),
+        Ty::Infer => "_".to_string(),
+        Ty::Struct { name, parameters } => {
+            let parameters: Vec<_> = parameters
+                .iter()
+                .map(|p| match p {
+                    Parameter::Origin(o) => origin(o),
+                    Parameter::Ty(ty) => rust_ty(ty, is_named),
+                })
+                .collect();
+            format!("{}{}", ident(name), list(&parameters))
+        }
+    }
+}
+
+fn contains_raw_ptr(ty: &Ty) -> bool {
+    match ty {
+        Ty::RawPtr { .. } | Ty::RawPtrMut { .. } => true,
+        Ty::Ref { ty, .. } | Ty::RefMut { ty, .. } | Ty::Array { ty, .. } => contains_raw_ptr(ty),
+        Ty::Struct { parameters, .. } => parameters
+            .iter()
+            .any(|p| matches!(p, Parameter::Ty(ty) if contains_raw_ptr(ty))),
+        Ty::I32 | Ty::Unit | Ty::Infer => false,
+    }
+}
+
+fn rust_place(place: &Place) -> String {
+    let mut text = ident(&place.base);
+    let mut is_deref = false;
+    for projection in &place.projections {
+        if is_deref && *projection != Projection::Deref {
+            text = format!("({})", text);
+        }
+        text = match projection {
+            Projection::Deref => format!("*{}", text),
+            Projection::Field(field) => format!("{}.{}", text, ident(field)),
+            Projection::Index(index) if index.bytes().all(|b| b.is_ascii_digit()) => {
+                format!("{}[{}]", text, index)
+            }
+            // Index variables are `i32`s, and Rust indexes with `usize`s
+            Projection::Index(index) => format!("{}[{} as usize]", text, ident(index)),
+        };
+        is_deref = *projection == Projection::Deref;
+    }
+    text
+}
+
+fn generic_decl(decl: &GenericDecl) -> String {
+    match decl {
+        GenericDecl::Origin(name) | GenericDecl::Ty(name) => name.to_string(),
+    }
+}
+
+// A marker type using all the generic parameters, which rustc requires.
+fn marker(generics: &[String]) -> String {
+    let uses: Vec<_> = generics
+        .iter()
+        .map(|g| match g.strip_prefix('\'') {
+            Some(_) => format!("&{} ()", g),
+            None => g.clone(),
+        })
+        .collect();
+    format!("std::marker::PhantomData<({},)>", uses.join(", "))
+}
+
+fn list(items: &[String]) -> String {
+    if items.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", items.join(", "))
+    }
+}
+
+fn label(block: &str) -> String {
+    format!("'block_{}", block)
+}
+
+// Escapes the names which are keywords in Rust, like the `use` function of many programs.
+// The name of a function, where the functions of `impl` blocks are free functions named after
+// their type, e.g. `Vec_push` for `Vec::push`, as the impl's generic parameters are not known.
+fn fn_ident(name: &str) -> String {
+    ident(&name.replace("::", "_"))
+}
+
+fn ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match",
+        "mod", "move", "mut", "priv", "pub", "ref", "return", "static", "struct", "trait", "true",
+        "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+        "abstract", "become", "final", "override",
+    ];
+    match name {
+        "self" | "super" | "crate" | "Self" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
@@ -0,0 +1,302 @@
+use super::*;
+use crate::json::parse_json;
+use insta::assert_display_snapshot;
+
+#[test]
+fn forward_cfg_source() {
+    let program = "
+        struct Pair<'a> { first: &'a i32 }
+        fn identity<'r>(x: &'r i32) -> &'r i32;
+        placeholder 'a, 'b where 'a: 'b;
+
+        let p: i32;
+        let x: &'a i32;
+        let v: Vec<&i32>;
+        let pair: Pair<'b>;
+
+        bb0: {
+            x = &p;
+            goto bb1, bb2;
+        }
+        bb1: {
+            use(move x);
+            goto bb3;
+        }
+        bb2: {
+            x = identity(move x);
+            goto bb3;
+        }
+        bb3: {
+            pair.first = copy x;
+        }
+    ";
+    assert_display_snapshot!(translate_to_rust(program).unwrap(), @r###"
+    #![allow(warnings)]
+
+    fn any<T>() -> T { unimplemented!() }
+    fn opaque<T, R>(_: T) -> R { unimplemented!() }
+    fn cond() -> bool { unimplemented!() }
+
+    struct Pair<'a> {
+        first: &'a i32,
+        _marker: std::marker::PhantomData<(&'a (),)>,
+    }
+
+    struct Vec<T0>(std::marker::PhantomData<(T0,)>);
+
+    fn identity<'r>(arg0: &'r i32) -> &'r i32 { any() }
+
+    fn program<'a, 'b>() where 'a: 'b {
+        let mut p: i32 = any();
+        let mut x: &'a i32 = any();
+        let mut v: Vec<&'_ i32> = any();
+        let mut pair: Pair<'b> = any();
+        'exit: {
+            'block_bb3: {
+                'block_bb2: {
+                    'block_bb1: {
+                        x = &p;
+                        if cond() {
+                            break 'block_bb1;
+                        } else {
+                            break 'block_bb2;
+                        }
+                    }
+                    opaque::<_, ()>((x,));
+                    break 'block_bb3;
+                }
+                x = identity(x);
+                break 'block_bb3;
+            }
+            pair.first = x;
+            break 'exit;
+        }
+    }
+    "###);
+}
+
+#[test]
+fn cyclic_cfg_source() {
+    let program = "
+        let p: i32;
+        let x: &i32;
+
+        bb0: {
+            x = &p;
+            goto bb1;
+        }
+        bb1: {
+            p = 1;
+            goto bb0, bb2;
+        }
+        bb2: {
+        }
+    ";
+    assert_display_snapshot!(translate_to_rust(program).unwrap(), @r###"
+    #![allow(warnings)]
+
+    fn any<T>() -> T { unimplemented!() }
+    fn opaque<T, R>(_: T) -> R { unimplemented!() }
+    fn cond() -> bool { unimplemented!() }
+
+    fn program() {
+        let mut p: i32 = any();
+        let mut x: &'_ i32 = any();
+        let mut state = 0;
+        loop {
+            match state {
+                0 => {
+                    x = &p;
+                    state = 1;
+                }
+                1 => {
+                    p = 1;
+                    if cond() {
+                        state = 0;
+                    } else {
+                        state = 2;
+                    }
+                }
+                2 => {
+                    break;
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+    "###);
+}
+
+#[test]
+fn parse_rustc_json() {
+    let json = parse_json(
+        r#"{"message":"cannot assign","code":{"code":"E0506"},"spans":[{"line_start":12,"is_primary":true}],"rendered":"a \"b\"\nA"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        json.get("message").and_then(Json::as_str),
+        Some("cannot assign")
+    );
+    assert_eq!(
+        json.get("code").and_then(|c| c.get("code")),
+        Some(&Json::String("E0506".to_string()))
+    );
+    assert_eq!(
+        json.get("spans").map(Json::as_array).unwrap()[0].get("line_start"),
+        Some(&Json::Number(12.0))
+    );
+    assert_eq!(
+        json.get("rendered").and_then(Json::as_str),
+        Some("a \"b\"\nA")
+    );
+}
+
+#[test]
+fn agreement_with_rustc() {
+    let program = "
+        let p: i32;
+        let x: &i32;
+
+        bb0: {
+            p = 22;
+            x = &p;
+            p = 33;
+            use(move x);
+        }
+    ";
+    assert_display_snapshot!(compare_with_rustc(program).unwrap(), @r###"
+    agree   bb0[2]: `p = 33`
+        rustc:    error[E0506]: cannot assign to `p` because it is borrowed
+        polonius: error[E0506]: cannot assign to `p` because it is borrowed
+    "###);
+}
+
+#[test]
+fn agreement_through_fn_signatures() {
+    // The loan of `p` flows into the call's result through the signature of `identity`
+    let program = "
+        fn identity<'r>(x: &'r i32) -> &'r i32;
+
+        let p: i32;
+        let x: &i32;
+        let y: &i32;
+
+        bb0: {
+            x = &p;
+            y = identity(move x);
+            p = 33;
+            use(move y);
+        }
+    ";
+    assert_display_snapshot!(compare_with_rustc(program).unwrap(), @r###"
+    agree   bb0[2]: `p = 33`
+        rustc:    error[E0506]: cannot assign to `p` because it is borrowed
+        polonius: error[E0506]: cannot assign to `p` because it is borrowed
+    "###);
+}
+
+#[test]
+fn agreement_on_method_calls() {
+    // Methods are translated to free functions, called with their borrowed receivers
+    let program = "
+        struct Vec<T> { item: T }
+        impl<T> Vec<T> {
+            fn push(&mut self, value: T) -> ();
+            fn first(&self) -> &T;
+        }
+
+        let v: Vec<i32>;
+        let r: &i32;
+
+        bb0: {
+            r = v.first();
+            v.push(1);
+            use(copy r);
+        }
+    ";
+    assert_display_snapshot!(compare_with_rustc(program).unwrap(), @r###"
+    agree   bb0[2]: `v.push(1)`
+        rustc:    error[E0502]: cannot borrow `v` as mutable because it is also borrowed as immutable
+        polonius: error[E0502]: cannot borrow `v` as mutable because it is also borrowed as immutable
+    "###);
+}
+
+#[test]
+fn agreement_on_moves() {
+    // Moving out of a borrowed value, and using it after it's moved, are borrow errors too
+    let program = "
+        struct S { value: i32 }
+
+        let s: S;
+        let t: S;
+        let r: &'r S;
+
+        bb0: {
+            r = &'L_t t;
+            s = move t;
+            use(copy r);
+            use(move t);
+        }
+    ";
+    assert_display_snapshot!(compare_with_rustc(program).unwrap(), @r###"
+    agree   bb0[1]: `s = move t`
+        rustc:    error[E0505]: cannot move out of `t` because it is borrowed
+        polonius: error[E0502]: cannot borrow `t` as mutable because it is also borrowed as immutable
+    agree   bb0[3]: `use(move t)`
+        rustc:    error[E0382]: use of moved value: `t`
+        polonius: error[E0382]: use of moved value: `t`
+    "###);
+}
+
+#[test]
+fn agreement_on_array_indices() {
+    // All the indices of an array can overlap, even different constants, like in rustc
+    let program = "
+        let array: [i32; 2];
+        let x: &i32;
+
+        bb0: {
+            x = &array[0];
+            array[1] = 33;
+            use(move x);
+        }
+    ";
+    assert_display_snapshot!(compare_with_rustc(program).unwrap(), @r###"
+    agree   bb0[1]: `array[1] = 33`
+        rustc:    error[E0506]: cannot assign to `array[_]` because it is borrowed
+        polonius: error[E0506]: cannot assign to `array[1]` because it is borrowed
+    "###);
+}
+
+#[test]
+fn divergence_from_rustc() {
+    // The loan returned on one path doesn't flow into the placeholder on the other path, where
+    // the analysis accepts borrowing again, but rustc's NLL doesn't
+    let program = "
+        placeholder 'a;
+
+        let map: &'a mut i32;
+        let r: &'r mut i32;
+        let out: &'a mut i32;
+
+        bb0: {
+            r = &'L0 mut *map;
+            goto bb1, bb2;
+        }
+
+        bb1: {
+            out = move r;
+            return;
+        }
+
+        bb2: {
+            out = &'L1 mut *map;
+            use(move out);
+        }
+    ";
+    assert_display_snapshot!(compare_with_rustc(program).unwrap(), @r###"
+    diverge bb2[0]: `out = &'L1 mut *map`
+        rustc:    error[E0499]: cannot borrow `*map` as mutable more than once at a time
+        polonius: no error
+    "###);
+}
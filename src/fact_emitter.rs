@@ -1,29 +1,54 @@
 // Tests dedicated to specific relations
 #[cfg(test)]
-mod test;
+pub(crate) mod test;
 
 // Tests porting the existing examples using the manual fact format, to the new frontend format
 #[cfg(test)]
 mod examples;
 
+mod filter;
+mod liveness;
+mod namespace;
+mod slice;
+pub use filter::FactFilter;
+pub use slice::FactSlice;
+
 use crate::ast::*;
 use crate::ast_parser::parse_ast;
-use std::collections::{BTreeMap, HashMap};
+use crate::convert::RELATIONS;
+use crate::fact_parser::facts_from_rows;
+use crate::graph::{BlockGraph, Dominators};
+use crate::limits::{Budget, Limits};
+use crate::reaching::ReachingDefinitions;
+use crate::source_map::SourceMap;
+use crate::span::{Span, Spanned};
+use crate::symbol::Symbol;
+use crate::validation::{self, visit_exprs};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::ops::ControlFlow;
+use std::rc::Rc;
+use std::time::Instant;
+
+#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize)]
+pub struct Origin(pub Symbol);
 
-#[derive(Default, PartialEq, Eq, Clone)]
-struct Origin(String);
+#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize)]
+pub struct Node(pub Symbol);
 
-#[derive(Default, PartialEq, Eq, Clone)]
-struct Node(String);
+/// A loan, issued by a single borrow expression: borrows can share an origin, but each of them
+/// issues its own loan.
+#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize)]
+pub struct Loan(pub Symbol);
 
 impl<S> From<S> for Origin
 where
-    S: AsRef<str> + ToString,
+    S: AsRef<str>,
 {
     fn from(s: S) -> Self {
-        Self(s.to_string())
+        Self(Symbol::intern(s.as_ref()))
     }
 }
 
@@ -35,10 +60,10 @@ impl fmt::Debug for Origin {
 
 impl<S> From<S> for Node
 where
-    S: AsRef<str> + ToString,
+    S: AsRef<str>,
 {
     fn from(s: S) -> Self {
-        Self(s.to_string())
+        Self(Symbol::intern(s.as_ref()))
     }
 }
 
@@ -48,25 +73,477 @@ impl fmt::Debug for Node {
     }
 }
 
-#[derive(Default, Debug)]
-pub(crate) struct Facts {
-    access_origin: Vec<(Origin, Node)>,
-    cfg_edge: Vec<(Node, Node)>,
-    clear_origin: Vec<(Origin, Node)>,
-    introduce_subset: Vec<(Origin, Origin, Node)>,
-    invalidate_origin: Vec<(Origin, Node)>,
-    node_text: Vec<(String, Node)>,
+impl<S> From<S> for Loan
+where
+    S: AsRef<str>,
+{
+    fn from(s: S) -> Self {
+        Self(Symbol::intern(s.as_ref()))
+    }
 }
 
-#[allow(dead_code)]
-fn emit_facts(input: &str) -> eyre::Result<Facts> {
-    let program = parse_ast(input)?;
-    let emitter = FactEmitter::new(program, input, false);
+impl fmt::Debug for Loan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Facts {
+    pub access_origin: Vec<(Origin, Node)>,
+    pub cfg_edge: Vec<(Node, Node)>,
+
+    // The kind of each CFG edge, for the rules treating unwinding or loops differently. Only
+    // emitted facts have them: the edges of the fact file format, or of rustc's facts, are
+    // normal edges.
+    pub cfg_edge_kind: Vec<(Node, Node, CfgEdgeKind)>,
+
+    pub clear_origin: Vec<(Origin, Node)>,
+    pub introduce_subset: Vec<(Origin, Origin, Node)>,
+    pub invalidate_origin: Vec<(Origin, Node)>,
+    pub node_text: Vec<(String, Node)>,
+    pub node_span: Vec<(Node, usize, usize)>,
+
+    // The line and column, from 1, where the statement of each node starts in the input, for
+    // the frontends placing markers in an editor from the facts alone.
+    pub node_location: Vec<(Node, usize, usize)>,
+
+    // The locations in the Rust source of the statements which have one, like `main.rs:42`.
+    pub source_location: Vec<(Node, String)>,
+
+    // The origins of the program's loans.
+    pub mark_as_loan_origin: Vec<Origin>,
+
+    // The universal origins of the analyzed function, as opposed to the origins local to it.
+    pub placeholder_origin: Vec<Origin>,
+
+    // The subsets known to hold between placeholders: `(o1, o2)` when `o1: o2` is declared.
+    pub known_placeholder_subset: Vec<(Origin, Origin)>,
+
+    // The fresh origins given to anonymous borrows, with the borrow expression they name.
+    pub anonymous_origin: Vec<(Origin, String, Node)>,
+
+    // The loans issued by borrow expressions: their origin, the loan, and where it's issued.
+    pub loan_issued_at: Vec<(Origin, Loan, Node)>,
+
+    // The kind of each loan, and the place it borrows.
+    pub loan_place: Vec<(Loan, LoanKind, String)>,
+
+    // The place assigned at each node, as written, like `x.f` or `*r`.
+    pub place_assigned_at: Vec<(String, Node)>,
+
+    // The move paths, a variable and its fields like `x.f`, that are moved out of, assigned to,
+    // and accessed at each node, for the move analysis.
+    pub path_moved_at: Vec<(String, Node)>,
+    pub path_assigned_at: Vec<(String, Node)>,
+    pub path_accessed_at: Vec<(String, Node)>,
+
+    // The variables used, defined (assigned as a whole), and dropped at each node, for the
+    // liveness of variables.
+    pub var_used_at: Vec<(Name, Node)>,
+    pub var_defined_at: Vec<(Name, Node)>,
+    pub var_dropped_at: Vec<(Name, Node)>,
+
+    // The origins in the types of the variables, which are live when the variables are used,
+    // and the ones which are live when they're dropped.
+    pub use_of_var_derefs_origin: Vec<(Name, Origin)>,
+    pub drop_of_var_derefs_origin: Vec<(Name, Origin)>,
+
+    // Where the facts of each relation came from, at the same index as the fact itself.
+    #[serde(skip)]
+    provenance: BTreeMap<Relation, Vec<Provenance>>,
+}
+
+/// Whether a loan is a shared (`&x`) or a mutable (`&mut x`) borrow.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoanKind {
+    Shared,
+    Mutable,
+}
+
+/// How control flows along a CFG edge.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CfgEdgeKind {
+    /// From a statement to the next one, or from a block to one of its successors.
+    Normal,
+
+    /// From a call or an assert to the block it unwinds to.
+    Unwind,
+
+    /// From a block to one of its dominators, e.g. to the head of a loop.
+    Back,
+}
+
+impl CfgEdgeKind {
+    pub const ALL: &'static [CfgEdgeKind] =
+        &[CfgEdgeKind::Normal, CfgEdgeKind::Unwind, CfgEdgeKind::Back];
+
+    pub fn from_name(name: &str) -> Option<CfgEdgeKind> {
+        CfgEdgeKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CfgEdgeKind::Normal => "normal",
+            CfgEdgeKind::Unwind => "unwind",
+            CfgEdgeKind::Back => "back",
+        }
+    }
+}
+
+/// The relations emitted from a program's statements.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Relation {
+    AccessOrigin,
+    ClearOrigin,
+    IntroduceSubset,
+    InvalidateOrigin,
+    LoanIssuedAt,
+}
+
+impl Relation {
+    pub const ALL: &'static [Relation] = &[
+        Relation::AccessOrigin,
+        Relation::ClearOrigin,
+        Relation::IntroduceSubset,
+        Relation::InvalidateOrigin,
+        Relation::LoanIssuedAt,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Relation> {
+        Relation::ALL
+            .iter()
+            .copied()
+            .find(|relation| relation.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Relation::AccessOrigin => "access_origin",
+            Relation::ClearOrigin => "clear_origin",
+            Relation::IntroduceSubset => "introduce_subset",
+            Relation::InvalidateOrigin => "invalidate_origin",
+            Relation::LoanIssuedAt => "loan_issued_at",
+        }
+    }
+}
+
+/// The piece of syntax that produced a fact: the statement it was emitted for, and the path to
+/// the sub-expression within that statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    pub span: Span,
+    pub path: ExprPath,
+}
+
+impl Provenance {
+    fn new(span: Span) -> Self {
+        Self {
+            span,
+            path: ExprPath::default(),
+        }
+    }
+
+    fn with(&self, elem: ExprPathElem) -> Self {
+        let mut path = self.path.clone();
+        path.0.push(elem);
+        Self {
+            span: self.span,
+            path,
+        }
+    }
+}
+
+/// A path from a statement to one of its sub-expressions, e.g. `rhs.args[1]`. The empty path
+/// refers to the statement as a whole.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExprPath(pub Vec<ExprPathElem>);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExprPathElem {
+    /// The place assigned to, on the left-hand side of an assignment.
+    Lhs,
+
+    /// The expression evaluated on the right-hand side of an assignment.
+    Rhs,
+
+    /// The argument at the given index, of a function call.
+    Arg(usize),
+
+    /// The operand of a cast.
+    Operand,
+}
+
+impl fmt::Display for ExprPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, elem) in self.0.iter().enumerate() {
+            if idx != 0 {
+                write!(f, ".")?;
+            }
+            match elem {
+                ExprPathElem::Lhs => write!(f, "lhs")?,
+                ExprPathElem::Rhs => write!(f, "rhs")?,
+                ExprPathElem::Arg(arg_idx) => write!(f, "args[{}]", arg_idx)?,
+                ExprPathElem::Operand => write!(f, "operand")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Facts {
+    /// Returns where the fact at index `idx` of the given relation came from.
+    pub fn provenance(&self, relation: Relation, idx: usize) -> Option<&Provenance> {
+        self.provenance.get(&relation)?.get(idx)
+    }
+
+    /// Returns the first loan issued with the given origin: its kind, the place it borrows, and
+    /// where it's issued.
+    pub fn issued_loan(&self, origin: &Origin) -> Option<(LoanKind, &str, &Node)> {
+        let (_, loan, node) = self
+            .loan_issued_at
+            .iter()
+            .find(|(candidate, ..)| candidate == origin)?;
+        let (_, kind, place) = self
+            .loan_place
+            .iter()
+            .find(|(candidate, ..)| candidate == loan)?;
+        Some((*kind, place, node))
+    }
+
+    /// The nodes of the statements, in their order, then the other nodes of the CFG edges.
+    pub(crate) fn nodes(&self) -> Vec<&Node> {
+        let mut nodes: Vec<&Node> = Vec::new();
+        let edge_nodes = self.cfg_edge.iter().flat_map(|(from, to)| [from, to]);
+        for node in self
+            .node_text
+            .iter()
+            .map(|(_, node)| node)
+            .chain(edge_nodes)
+        {
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+        nodes
+    }
+
+    /// The text of the node's statement, if it has one.
+    pub(crate) fn node_text(&self, node: &Node) -> Option<&str> {
+        self.node_text
+            .iter()
+            .find(|(_, candidate)| candidate == node)
+            .map(|(text, _)| text.as_str())
+    }
+
+    /// The span of the node's statement in the input, if it has one: facts read from fact files
+    /// have no spans.
+    pub(crate) fn node_span(&self, node: &Node) -> Option<Span> {
+        self.node_span
+            .iter()
+            .find(|(candidate, ..)| candidate == node)
+            .map(|&(_, start, end)| Span::new(start, end))
+    }
+
+    /// The number of facts, of all the relations.
+    pub fn fact_count(&self) -> usize {
+        self.access_origin.len()
+            + self.cfg_edge.len()
+            + self.cfg_edge_kind.len()
+            + self.clear_origin.len()
+            + self.introduce_subset.len()
+            + self.invalidate_origin.len()
+            + self.node_text.len()
+            + self.node_span.len()
+            + self.node_location.len()
+            + self.source_location.len()
+            + self.mark_as_loan_origin.len()
+            + self.placeholder_origin.len()
+            + self.known_placeholder_subset.len()
+            + self.anonymous_origin.len()
+            + self.loan_issued_at.len()
+            + self.loan_place.len()
+            + self.place_assigned_at.len()
+            + self.path_moved_at.len()
+            + self.path_assigned_at.len()
+            + self.path_accessed_at.len()
+            + self.var_used_at.len()
+            + self.var_defined_at.len()
+            + self.var_dropped_at.len()
+            + self.use_of_var_derefs_origin.len()
+            + self.drop_of_var_derefs_origin.len()
+    }
+
+    fn relation_len(&self, relation: Relation) -> usize {
+        match relation {
+            Relation::AccessOrigin => self.access_origin.len(),
+            Relation::ClearOrigin => self.clear_origin.len(),
+            Relation::IntroduceSubset => self.introduce_subset.len(),
+            Relation::InvalidateOrigin => self.invalidate_origin.len(),
+            Relation::LoanIssuedAt => self.loan_issued_at.len(),
+        }
+    }
+
+    // Appends the facts of the relations of the fact file format.
+    fn merge(&mut self, other: Facts) {
+        self.mark_as_loan_origin.extend(other.mark_as_loan_origin);
+        self.placeholder_origin.extend(other.placeholder_origin);
+        self.known_placeholder_subset
+            .extend(other.known_placeholder_subset);
+        self.node_text.extend(other.node_text);
+        self.source_location.extend(other.source_location);
+        self.cfg_edge.extend(other.cfg_edge);
+        self.access_origin.extend(other.access_origin);
+        self.clear_origin.extend(other.clear_origin);
+        self.introduce_subset.extend(other.introduce_subset);
+        self.invalidate_origin.extend(other.invalidate_origin);
+        self.loan_issued_at.extend(other.loan_issued_at);
+    }
+
+    // Attributes all the facts emitted since the last call, to the given provenance. Facts are
+    // attributed from the innermost expressions outwards, so the most precise path is recorded.
+    fn attribute_to(&mut self, provenance: &Provenance) {
+        for &relation in Relation::ALL {
+            let len = self.relation_len(relation);
+            let provenances = self.provenance.entry(relation).or_default();
+            provenances.resize(len, provenance.clone());
+        }
+    }
+}
+
+/// The origin that the loans escaping through raw pointers flow into, when they're kept live.
+pub const ESCAPED_ORIGIN: &str = "'escaped";
+
+/// Options for the constructs whose facts can be modeled in more than one way.
+#[derive(Clone, Debug, Default)]
+pub struct EmitOptions {
+    pub raw_pointer_casts: RawPointerCasts,
+
+    /// Whether the blocks which are not reachable from the entry block are left out, instead of
+    /// having their facts emitted like the others.
+    pub exclude_unreachable_blocks: bool,
+
+    /// Whether the invalidations of dead loans are kept, instead of being pruned: the loans
+    /// which can't flow into an origin live after the invalidating node.
+    pub keep_dead_loans: bool,
+
+    /// The limits on the number of nodes and facts of the program.
+    pub limits: Limits,
+
+    pub node_names: NodeNames,
+}
+
+/// How the nodes are named.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NodeNames {
+    /// By the index of their statement in their block, `bb0[1]`.
+    #[default]
+    Indexed,
+
+    /// By a short hash of their block's name and their statement's text, `bb0[5d41402a]`, so
+    /// that adding a statement doesn't rename the nodes of the statements after it. Statements
+    /// with the same text in the same block are told apart by their occurrence,
+    /// `bb0[5d41402a_1]`.
+    Hashed,
+}
+
+impl std::str::FromStr for NodeNames {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s {
+            "indexed" => Ok(Self::Indexed),
+            "hashed" => Ok(Self::Hashed),
+            _ => eyre::bail!(
+                "unknown node naming `{}`, expected `indexed` or `hashed`",
+                s
+            ),
+        }
+    }
+}
+
+/// How casting a reference to a raw pointer is modeled. The cast itself is always a read of the
+/// reference.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RawPointerCasts {
+    /// The loans of the reference are no longer tracked through the pointer: they can be
+    /// invalidated while the pointer is still used.
+    #[default]
+    EndTracking,
+
+    /// The loans of the reference escape: they flow into the `'escaped` origin, which is live
+    /// until the end of the program, so invalidating them after the cast is an error.
+    KeepLive,
+}
+
+impl std::str::FromStr for RawPointerCasts {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s {
+            "end-tracking" => Ok(Self::EndTracking),
+            "keep-live" => Ok(Self::KeepLive),
+            _ => eyre::bail!(
+                "unknown raw pointer cast model `{}`, expected `end-tracking` or `keep-live`",
+                s
+            ),
+        }
+    }
+}
+
+/// Parses the input program and emits its facts.
+pub fn emit_facts(input: &str) -> eyre::Result<Facts> {
+    emit_facts_with_options(input, &EmitOptions::default())
+}
+
+/// Parses the input program and emits its facts, modeled according to the given options. The
+/// program must be valid, see `validate`.
+#[tracing::instrument(skip_all)]
+pub fn emit_facts_with_options(input: &str, options: &EmitOptions) -> eyre::Result<Facts> {
+    // The phases of the emission share the clock of the timeout
+    let mut options = options.clone();
+    options.limits.started.get_or_insert_with(Instant::now);
+
+    let mut program = parse_valid_program(input)?;
+    Budget::start(&options.limits).check_time("validating the program")?;
+    if options.exclude_unreachable_blocks {
+        remove_unreachable_blocks(&mut program);
+    }
+    let node_count = program
+        .basic_blocks
+        .iter()
+        .map(|bb| bb.statements.len())
+        .sum();
+    options.limits.check_nodes(node_count)?;
+
+    let emitter = FactEmitter::new(program, input, options, false)?;
     let mut facts = Default::default();
-    emitter.emit_facts(&mut facts);
+    emitter.emit_facts(&mut facts)?;
+    emitter.options.limits.check_facts(&facts)?;
     Ok(facts)
 }
 
+/// Parses the input program and returns, at each of its nodes, the origins of the loans whose
+/// borrows reach the node's entry, in the order of the borrows. The program must be valid, see
+/// `validate`.
+pub fn reaching_loans(input: &str) -> eyre::Result<Vec<(Node, Vec<Origin>)>> {
+    let program = parse_valid_program(input)?;
+    Ok(FactEmitter::new(program, input, EmitOptions::default(), false)?.loans_reaching_nodes())
+}
+
+fn parse_valid_program(input: &str) -> eyre::Result<Program> {
+    let program = parse_ast(input)?;
+    let errors = validation::errors(&program, input);
+    if !errors.is_empty() {
+        let errors: String = errors.iter().map(ToString::to_string).collect();
+        eyre::bail!("the program is invalid:\n{}", errors);
+    }
+    Ok(program)
+}
+
 // An internal representation of a `Node`, a location in the CFG: the block within the program,
 // and the statement within that block. Used to analyze locations (e.g. reachability), whereas
 // `Node`s are user-readable representations for facts.
@@ -87,53 +564,326 @@ impl From<(usize, usize)> for Location {
 
 struct FactEmitter<'a> {
     input: &'a str,
+    source_map: SourceMap<'a>,
     program: Program,
-    loans: HashMap<Place, Vec<(Origin, Location)>>,
+    // The loans of each place, ordered by place so that the facts are emitted in the same order
+    // on every run.
+    loans: BTreeMap<Place, Vec<(Origin, Location)>>,
+    anonymous_origins: Vec<(Origin, String, Location)>,
+    declarations: DeclarationIndices,
+
+    // The index of each node in the CFG, and the nodes issuing loans whose borrows reach each
+    // node: a loan is only invalidated where its borrow can reach.
+    node_indices: HashMap<Node, usize>,
+    first_nodes: Vec<usize>,
+    reaching_loans: ReachingDefinitions,
+
+    // The dominators of the blocks, whose incoming edges from the blocks they dominate are back
+    // edges.
+    dominators: Dominators,
+
+    // The places whose types were resolved, with their type and the origins of the types walked
+    // to reach it: the same places are resolved at each of their accesses.
+    place_tys: RefCell<HashMap<Place, PlaceTy>>,
     simple_node_names: bool,
+    options: EmitOptions,
+}
+
+// The type of a place, and the origins in the types walked to reach it.
+type PlaceTy = (Rc<Ty>, Vec<Origin>);
+
+// The positions of a program's variables and structs, by name, so that looking up the
+// declarations of a place doesn't scan them all. When a name is declared more than once, which
+// validation rejects, the first declaration is used, like a scan would.
+#[derive(Default)]
+pub(crate) struct DeclarationIndices {
+    variables: HashMap<Name, usize>,
+    structs: HashMap<Name, usize>,
+}
+
+impl DeclarationIndices {
+    pub(crate) fn new(program: &Program) -> Self {
+        let mut indices = Self::default();
+        for (idx, v) in program.variables.iter().enumerate() {
+            indices.variables.entry(v.name).or_insert(idx);
+        }
+        for (idx, decl) in program.struct_decls.iter().enumerate() {
+            indices.structs.entry(decl.name).or_insert(idx);
+        }
+        indices
+    }
+
+    fn variable<'p>(&self, program: &'p Program, name: &str) -> Option<&'p VariableDecl> {
+        self.variables.get(name).map(|&idx| &program.variables[idx])
+    }
+
+    fn struct_decl<'p>(&self, program: &'p Program, name: &str) -> Option<&'p StructDecl> {
+        self.structs
+            .get(name)
+            .map(|&idx| &program.struct_decls[idx])
+    }
 }
 
 impl<'a> FactEmitter<'a> {
-    fn new(program: Program, input: &'a str, simple_node_names: bool) -> Self {
+    fn new(
+        mut program: Program,
+        input: &'a str,
+        options: EmitOptions,
+        simple_node_names: bool,
+    ) -> eyre::Result<Self> {
+        let budget = Budget::start(&options.limits);
+        infer_access_kinds(&mut program);
+        infer_variable_tys(&mut program);
+        infer_elided_origins(&mut program);
+        instantiate_calls(&mut program);
+
+        // Name the origins of anonymous borrows, in program order
+        let mut anonymous_origins = AnonymousOrigins::default();
+        let program = anonymous_origins.fold_program(program);
+        let anonymous_origins = anonymous_origins.origins;
+        budget.check_time("inferring the types of the program")?;
+
         // Collect loans from borrow expressions present in the program
-        let mut loans: HashMap<Place, Vec<(Origin, Location)>> = HashMap::new();
+        let mut loans: BTreeMap<Place, Vec<(Origin, Location)>> = BTreeMap::new();
 
         for (block_idx, bb) in program.basic_blocks.iter().enumerate() {
             for (statement_idx, s) in bb.statements.iter().enumerate() {
-                let (Statement::Assign(_, expr) | Statement::Expr(expr)) = &**s;
+                let expr = match s.expr() {
+                    Some(expr) => expr,
+                    None => continue,
+                };
+
+                // Borrows can also be nested in the statement's expression, e.g. as arguments
+                // of a call
+                visit_exprs(expr, &mut |expr| {
+                    if let Expr::Access {
+                        kind: AccessKind::Borrow(origin) | AccessKind::BorrowMut(origin),
+                        place,
+                    } = expr
+                    {
+                        // TODO: we probably also need to track the loan's mode, if we want to
+                        // emit errors when mutably borrowing through a shared ref and the likes ?
+                        loans
+                            .entry(place.clone())
+                            .or_default()
+                            .push((origin.into(), (block_idx, statement_idx).into()));
+                    }
+                });
+            }
+        }
 
+        // The nodes issuing loans are the definitions reaching the nodes, and are never killed
+        let graph = program.cfg();
+        let mut first_nodes = Vec::with_capacity(program.basic_blocks.len());
+        let mut node_count = 0;
+        for bb in &program.basic_blocks {
+            first_nodes.push(node_count);
+            node_count += bb.statements.len().max(1);
+        }
+        let mut loan_nodes: Vec<_> = loans
+            .values()
+            .flatten()
+            .map(|(_, location)| first_nodes[location.block_idx] + location.statement_idx)
+            .collect();
+        loan_nodes.sort_unstable();
+        loan_nodes.dedup();
+        let reaching_loans = ReachingDefinitions::within(&graph, loan_nodes, |_| None, &budget)?;
+
+        let dominators = BlockGraph::from_program(&program).dominators();
+        let declarations = DeclarationIndices::new(&program);
+        let mut emitter = Self {
+            input,
+            source_map: SourceMap::new(input),
+            program,
+            loans,
+            anonymous_origins,
+            declarations,
+            node_indices: HashMap::new(),
+            first_nodes,
+            reaching_loans,
+            dominators,
+            place_tys: Default::default(),
+            simple_node_names,
+            options,
+        };
+        for (block_idx, bb) in emitter.program.basic_blocks.iter().enumerate() {
+            for idx in 0..bb.statements.len().max(1) {
+                let node = emitter.node_at(&bb.name, idx);
+                emitter
+                    .node_indices
+                    .insert(node, emitter.first_nodes[block_idx] + idx);
+            }
+        }
+        Ok(emitter)
+    }
+
+    fn emit_facts(&self, facts: &mut Facts) -> eyre::Result<()> {
+        let budget = Budget::start(&self.options.limits);
+
+        // The origins of borrow expressions are the origins of loans
+        struct LoanOrigins<'f>(&'f mut Vec<Origin>);
+        impl<'ast> Visitor<'ast> for LoanOrigins<'_> {
+            fn visit_expr(&mut self, expr: &'ast Expr) {
                 if let Expr::Access {
                     kind: AccessKind::Borrow(origin) | AccessKind::BorrowMut(origin),
-                    place,
+                    ..
                 } = expr
                 {
-                    // TODO: handle fields and loans taken on subsets of their paths.
-                    // Until then: only support borrowing from complete places.
-                    //
-                    // TODO: we probably also need to track the loan's mode, if we want to emit
-                    // errors when mutably borrowing through a shared ref and the likes ?
-                    loans
-                        .entry(place.clone())
-                        .or_default()
-                        .push((origin.into(), (block_idx, statement_idx).into()));
+                    let origin = Origin::from(origin);
+                    if !self.0.contains(&origin) {
+                        self.0.push(origin);
+                    }
                 }
+                walk_expr(self, expr);
             }
         }
+        LoanOrigins(&mut facts.mark_as_loan_origin).visit_program(&self.program);
 
-        Self {
-            input,
-            program,
-            loans,
-            simple_node_names,
+        for placeholder in &self.program.placeholders {
+            facts.placeholder_origin.push(placeholder.into());
+        }
+        for (origin1, origin2) in &self.program.known_placeholder_subsets {
+            facts
+                .known_placeholder_subset
+                .push((origin1.into(), origin2.into()));
+        }
+
+        // `'static` is a placeholder, when it's used, and is known to outlive all the others
+        let static_origin = Origin::from(STATIC_ORIGIN);
+        let mut origins = Vec::new();
+        for v in &self.program.variables {
+            v.ty.collect_origins_into(&mut origins);
+        }
+        if origins.contains(&static_origin) {
+            if !facts.placeholder_origin.contains(&static_origin) {
+                facts.placeholder_origin.push(static_origin.clone());
+            }
+            for placeholder in &facts.placeholder_origin {
+                if *placeholder != static_origin {
+                    facts
+                        .known_placeholder_subset
+                        .push((static_origin.clone(), placeholder.clone()));
+                }
+            }
+        }
+
+        // Each placeholder contains a loan of the caller, issued at the entry node, like the
+        // placeholder loans of upstream Polonius: the origins this loan flows into are the ones
+        // the caller's data can reach. They're named `ph0`, `ph1`, ... in the placeholders' order.
+        if let Some(entry_block) = self.program.basic_blocks.first() {
+            let entry_node = self.node_at(&entry_block.name, 0);
+            let placeholder_loans = facts
+                .placeholder_origin
+                .iter()
+                .enumerate()
+                .map(|(idx, placeholder)| {
+                    let loan = Loan::from(format!("ph{}", idx));
+                    (placeholder.clone(), loan, entry_node.clone())
+                })
+                .collect::<Vec<_>>();
+            facts.loan_issued_at.extend(placeholder_loans);
         }
-    }
 
-    fn emit_facts(&self, facts: &mut Facts) {
+        // The limits are checked as the facts of each block are emitted, rather than once they
+        // all are
         for bb in &self.program.basic_blocks {
             self.emit_block_facts(bb, facts);
+            budget.check_time(format_args!("emitting the facts of `{}`", bb.name))?;
+            self.options.limits.check_facts(facts)?;
+        }
+
+        // The function exits at the last node of the blocks which return
+        let exit_nodes: Vec<_> = self
+            .program
+            .basic_blocks
+            .iter()
+            .filter(|bb| bb.terminator.is_return())
+            .map(|bb| self.node_at(&bb.name, bb.statements.len().saturating_sub(1)))
+            .collect();
+
+        // Loans escaping through raw pointers are kept live until the end of the program, by
+        // accessing their origin everywhere. These accesses come from where the function exits,
+        // or from its last node when it doesn't.
+        let escaped_origin = Origin::from(ESCAPED_ORIGIN);
+        if facts
+            .introduce_subset
+            .iter()
+            .any(|(_, o, _)| *o == escaped_origin)
+        {
+            let nodes: Vec<_> = facts.node_text.iter().map(|(_, n)| n.clone()).collect();
+            let exit_span = exit_nodes
+                .iter()
+                .chain(nodes.last())
+                .find_map(|node| facts.node_span(node));
+            for node in nodes {
+                facts.access_origin.push((escaped_origin.clone(), node));
+            }
+            if let Some(span) = exit_span {
+                facts.attribute_to(&Provenance::new(span));
+            }
         }
+
+        // The variables whose values need to be dropped are dropped when the function exits.
+        //
+        // TODO: variables are dropped even when they're moved out, and the previous values of
+        // reassigned variables are not dropped.
+        for v in &self.program.variables {
+            if self.program.needs_drop(&v.ty) {
+                for node in &exit_nodes {
+                    facts.var_dropped_at.push((v.name, node.clone()));
+                }
+            }
+        }
+
+        // Using a variable can dereference all the origins in its type, and so can dropping it,
+        // when its drop isn't known to do nothing
+        for v in &self.program.variables {
+            let mut origins = Vec::new();
+            v.ty.collect_origins_into(&mut origins);
+            for origin in origins {
+                if self.program.needs_drop(&v.ty) {
+                    facts
+                        .drop_of_var_derefs_origin
+                        .push((v.name, origin.clone()));
+                }
+                facts.use_of_var_derefs_origin.push((v.name, origin));
+            }
+        }
+
+        // The loans which can't flow into a live origin are dead, and their invalidations can't
+        // lead to errors
+        if !self.options.keep_dead_loans {
+            facts.prune_dead_loans();
+            budget.check_time("pruning the dead loans")?;
+        }
+
+        // The facts written in the program are merged verbatim, after the emitted ones
+        for fact in &self.program.raw_facts {
+            let mut rows: HashMap<String, Vec<Vec<String>>> = RELATIONS
+                .iter()
+                .map(|(name, _)| (name.to_string(), Vec::new()))
+                .collect();
+            let atoms = fact.arguments.iter().chain(&fact.node);
+            rows.get_mut(fact.relation.as_str())
+                .expect("the relations of raw facts are validated")
+                .push(atoms.map(|atom| atom.to_string()).collect());
+            facts.merge(facts_from_rows(&rows));
+            facts.attribute_to(&Provenance::new(fact.span()));
+        }
+
+        for (origin, borrow_text, location) in &self.anonymous_origins {
+            let block = &self.program.basic_blocks[location.block_idx];
+            let node = self.node_at(&block.name, location.statement_idx);
+            facts
+                .anonymous_origin
+                .push((origin.clone(), borrow_text.clone(), node));
+        }
+        Ok(())
     }
 
     fn emit_block_facts(&self, bb: &BasicBlock, facts: &mut Facts) {
+        let _block = tracing::debug_span!("block", name = %bb.name).entered();
+
         // Emit CFG facts for the block
         self.emit_cfg_edges(&bb, facts);
 
@@ -141,13 +891,24 @@ impl<'a> FactEmitter<'a> {
             let node = self.node_at(&bb.name, idx);
 
             // Emit `node_text` for this statement: the line from where it was parsed
-            // in the original input program.
-            let statement_text = {
-                let span = s.span();
-                self.input[span.start()..span.end() - 1].to_string()
-            };
-            facts.node_text.push((statement_text, node.clone()));
+            // in the original input program, `node_span` for the byte range of that text, and
+            // `node_location` for the line and column it starts at.
+            // Statements lowered from nested expressions span that expression, without the `;`.
+            let span = s.span();
+            let text = self.statement_text(s);
+            let (text_start, text_end) = (span.start(), span.start() + text.len());
+            facts.node_text.push((text.to_string(), node.clone()));
+            facts.node_span.push((node.clone(), text_start, text_end));
+            let (line, column) = self.source_map.line_col(text_start);
+            facts.node_location.push((node.clone(), line, column));
+            if let Some(location) = self.program.source_location(span) {
+                facts
+                    .source_location
+                    .push((node.clone(), location.to_string()));
+            }
+            tracing::trace!(node = %node.0, statement = text, "emitting facts");
 
+            let provenance = Provenance::new(s.span());
             match &**s {
                 Statement::Assign(place, expr) => {
                     // Emit facts about the assignment LHS
@@ -159,28 +920,44 @@ impl<'a> FactEmitter<'a> {
                         facts.clear_origin.push((origin.clone(), node.clone()));
                     }
 
-                    // TODO: the following is wrong and simplistic, see 
+                    // TODO: the following is wrong and simplistic, see
                     // https://github.com/nikomatsakis/polonius.next/pull/4#discussion_r739325010
                     // but will be fixed by https://github.com/nikomatsakis/polonius.next/pull/10
                     if !lhs_ty.is_ref() {
-                        // Assignments to non-references invalidate loans borrowing from them.
-                        //
-                        // TODO: handle assignments to fields and loans taken on subsets of
-                        // their paths. Until then: only support invalidations on assignments
-                        // to complete places.
-                        //
-                        if let Some(loans) = self.loans.get(place) {
-                            for (origin, _location) in loans {
-                                // TODO: if the `location` where the loan was issued can't
-                                // reach the current location, there is no need to emit
-                                // the invalidation
-                                facts.invalidate_origin.push((origin.clone(), node.clone()));
-                            }
-                        }
+                        // Assignments to non-references invalidate loans borrowing from them,
+                        // when the location where the loan was issued can reach them
+                        self.emit_invalidations(&node, place, s.span(), facts);
                     }
 
+                    // Assignments define their variable, and assignments to its fields or
+                    // through a reference use it
+                    if place.projections.is_empty() {
+                        facts.var_defined_at.push((place.base, node.clone()));
+                    } else {
+                        facts.var_used_at.push((place.base, node.clone()));
+                    }
+                    self.emit_index_facts(&node, place, facts);
+
+                    facts
+                        .place_assigned_at
+                        .push((place.to_string(), node.clone()));
+
+                    // Assignments initialize their move path, unless they assign through a
+                    // reference, which is an access of that reference
+                    if place.has_deref() {
+                        facts
+                            .path_accessed_at
+                            .push((place.move_path(), node.clone()));
+                    } else {
+                        facts
+                            .path_assigned_at
+                            .push((place.move_path(), node.clone()));
+                    }
+
+                    facts.attribute_to(&provenance.with(ExprPathElem::Lhs));
+
                     // Emit facts about the assignment RHS: evaluate the `expr`
-                    self.emit_expr_facts(&node, expr, facts);
+                    self.emit_expr_facts(&node, expr, &provenance.with(ExprPathElem::Rhs), facts);
 
                     // Relate the LHS and RHS tys
                     self.emit_subset_facts(&node, &lhs_ty, expr, facts);
@@ -188,18 +965,80 @@ impl<'a> FactEmitter<'a> {
 
                 Statement::Expr(expr) => {
                     // Evaluate the `expr`
-                    self.emit_expr_facts(&node, expr, facts);
+                    self.emit_expr_facts(&node, expr, &provenance, facts);
+                }
+
+                Statement::End(origin) => {
+                    // The origin is cleared, as if all the references with that origin were
+                    // overwritten
+                    facts.clear_origin.push((origin.into(), node.clone()));
+                }
+
+                Statement::Kill(place) => {
+                    // The loans that writing to the place would invalidate are killed instead:
+                    // their origins are cleared
+                    for origin in self.loans_invalidated_by(place, &node) {
+                        facts.clear_origin.push((origin.clone(), node.clone()));
+                    }
                 }
             }
+
+            // The remaining facts are about the statement as a whole
+            facts.attribute_to(&provenance);
+        }
+    }
+
+    // Invalidates the loans of the place, unless the statement at the span has
+    // `#[no_invalidate]`.
+    fn emit_invalidations(&self, node: &Node, place: &Place, span: Span, facts: &mut Facts) {
+        if self.program.has_attribute(span, Attribute::NoInvalidate) {
+            return;
+        }
+        for origin in self.loans_invalidated_by(place, node) {
+            facts.invalidate_origin.push((origin.clone(), node.clone()));
         }
     }
 
-    fn emit_expr_facts(&self, node: &Node, expr: &Expr, facts: &mut Facts) {
+    // The variables indexing arrays in a place are read, and copied, when the place is used.
+    fn emit_index_facts(&self, node: &Node, place: &Place, facts: &mut Facts) {
+        for index in place.index_variables() {
+            facts.var_used_at.push((*index, node.clone()));
+            facts
+                .path_accessed_at
+                .push((index.to_string(), node.clone()));
+        }
+    }
+
+    fn emit_expr_facts(
+        &self,
+        node: &Node,
+        expr: &Expr,
+        provenance: &Provenance,
+        facts: &mut Facts,
+    ) {
         match expr {
             Expr::Access { kind, place } => {
+                facts.var_used_at.push((place.base, node.clone()));
+                facts
+                    .path_accessed_at
+                    .push((place.move_path(), node.clone()));
+                self.emit_index_facts(node, place, facts);
+
                 match kind {
                     // Borrowing clears its origin: it's issuing a fresh origin of the same name
                     AccessKind::Borrow(origin) | AccessKind::BorrowMut(origin) => {
+                        // Each borrow issues its own loan, named `bw0`, `bw1`, ... in program
+                        // order
+                        let loan = Loan::from(format!("bw{}", facts.loan_place.len()));
+                        let loan_kind = match kind {
+                            AccessKind::BorrowMut(_) => LoanKind::Mutable,
+                            _ => LoanKind::Shared,
+                        };
+                        facts
+                            .loan_issued_at
+                            .push((origin.into(), loan.clone(), node.clone()));
+                        facts.loan_place.push((loan, loan_kind, place.to_string()));
+
                         facts.clear_origin.push((origin.into(), node.clone()));
 
                         if matches!(kind, AccessKind::BorrowMut(_)) {
@@ -211,50 +1050,78 @@ impl<'a> FactEmitter<'a> {
                                 facts.access_origin.push((origin.clone(), node.clone()));
                             }
 
-                            // 2) and invalidates existing loans of that place
-                            //
-                            // TODO: handle assignments to fields and loans taken on subsets of
-                            // their paths. Until then: only support invalidations on assignments
-                            // to complete places.
-                            //
-                            // TODO: here as well, there is a question of: can the loans we're
-                            // invalidating, reach the current node ?
-                            //
-                            if let Some(loans) = self.loans.get(place) {
-                                for (origin, _) in loans {
-                                    facts.invalidate_origin.push((origin.clone(), node.clone()));
-                                }
+                            // 2) and invalidates existing loans of that place, unless it's a
+                            // two-phase borrow, which is only reserved here
+                            let is_two_phase = self
+                                .program
+                                .has_attribute(provenance.span, Attribute::TwoPhase);
+                            if !is_two_phase {
+                                self.emit_invalidations(node, place, provenance.span, facts);
                             }
                         }
                     }
 
-                    AccessKind::Copy | AccessKind::Move => {
-                        // FIXME: currently function call parameters are not parsed without access
-                        // kinds, check if there's some special behaviour needed for copy/moves,
-                        // instead of just being "reads" (e.g. maybe moves also need clearing
-                        // or invalidations)
+                    AccessKind::Infer => unreachable!("Access kinds are inferred before emitting"),
 
+                    AccessKind::Copy | AccessKind::Move => {
                         // Reads access all the origins in their type
                         let origins = self.origins_of_place(place);
                         for origin in origins {
                             facts.access_origin.push((origin.into(), node.clone()));
                         }
+
+                        // Reads of `Copy` places are copies, and reads of other places are moves,
+                        // whichever access kind they're written with. Moving out of a place
+                        // invalidates its loans, like assigning to it.
+                        //
+                        // Moves don't need to clear the origins of the moved place: it can't be
+                        // used until it's assigned again, which clears them.
+                        if !self.program.is_copy(&self.ty_of_place(place)) {
+                            self.emit_invalidations(node, place, provenance.span, facts);
+
+                            // Moving out of a reference is not possible, only out of move paths
+                            if !place.has_deref() {
+                                facts.path_moved_at.push((place.move_path(), node.clone()));
+                            }
+                        }
                     }
                 }
             }
 
-            Expr::Call { arguments, .. } => {
+            Expr::Call {
+                name,
+                generic_args,
+                arguments,
+            } => {
                 // Calls evaluate their arguments
-                arguments
-                    .iter()
-                    .for_each(|expr| self.emit_expr_facts(&node, expr, facts));
+                for (idx, expr) in arguments.iter().enumerate() {
+                    let provenance = provenance.with(ExprPathElem::Arg(idx));
+                    self.emit_expr_facts(node, expr, &provenance, facts);
+                }
+
+                // The arguments flow into the parameters of the function's signature, instantiated
+                // at this call, which can introduce subsets between the arguments themselves.
+                // Calls to undeclared functions are opaque.
+                if let Some(prototype) = self.fn_prototype(name) {
+                    let (arg_tys, _) = instantiate_signature(prototype, generic_args);
+                    for (param_ty, argument) in arg_tys.iter().zip(arguments) {
+                        if let Some(arg_ty) = operand_ty(&self.program, argument) {
+                            self.relate_values(node, param_ty, &arg_ty, facts);
+                        }
+                    }
+                }
+            }
 
-                // TODO: Depending on the signature of the function, some subsets can be introduced
-                // between the arguments to the call
+            Expr::Cast { operand, .. } => {
+                // Casts evaluate their operand
+                let provenance = provenance.with(ExprPathElem::Operand);
+                self.emit_expr_facts(node, operand, &provenance, facts);
             }
 
             _ => {}
         }
+
+        facts.attribute_to(provenance);
     }
 
     // Introduce subsets: `expr` flows into `place`
@@ -295,7 +1162,7 @@ impl<'a> FactEmitter<'a> {
                     node.clone(),
                 ));
                 let rhs_ty = self.ty_of_place(place);
-                self.relate_tys(node, lhs_ty, rhs_ty, Variance::Covariant, facts);
+                self.relate_tys(node, lhs_ty, &rhs_ty, Variance::Covariant, facts);
             }
 
             // `lhs = copy or move rhs`, where lhs and rhs are shared reference types
@@ -310,7 +1177,7 @@ impl<'a> FactEmitter<'a> {
                 },
             ) => {
                 let rhs_ty = self.ty_of_place(place);
-                match rhs_ty {
+                match &*rhs_ty {
                     Ty::Ref {
                         origin: source_origin,
                         ty: rhs_ty,
@@ -349,7 +1216,7 @@ impl<'a> FactEmitter<'a> {
                     node.clone(),
                 ));
                 let rhs_ty = self.ty_of_place(place);
-                self.relate_tys(node, lhs_ty, rhs_ty, Variance::Invariant, facts);
+                self.relate_tys(node, lhs_ty, &rhs_ty, Variance::Invariant, facts);
             }
 
             // `lhs = copy or move rhs`, where lhs and rhs are unique reference types
@@ -364,7 +1231,7 @@ impl<'a> FactEmitter<'a> {
                 },
             ) => {
                 let rhs_ty = self.ty_of_place(place);
-                match rhs_ty {
+                match &*rhs_ty {
                     Ty::RefMut {
                         origin: source_origin,
                         ty: rhs_ty,
@@ -386,22 +1253,51 @@ impl<'a> FactEmitter<'a> {
                 }
             }
 
-            // `lhs = rhs`, where lhs and rhs are structs, and may have generic parameters which
-            // will need subsets.
+            // `lhs = rhs`, where lhs and rhs are structs or arrays, and may have generic
+            // parameters which will need subsets.
             (
-                Ty::Struct { .. },
+                Ty::Struct { .. } | Ty::Array { .. },
                 Expr::Access {
                     kind: AccessKind::Copy | AccessKind::Move,
                     place,
                 },
             ) => {
                 let rhs_ty = self.ty_of_place(place);
-                self.relate_tys(node, lhs_ty, rhs_ty, Variance::Covariant, facts);
+                self.relate_tys(node, lhs_ty, &rhs_ty, Variance::Covariant, facts);
             }
 
-            (_, Expr::Call { .. }) => {
-                // TODO: When possible, check if the function signature requires that the RHS inputs
-                // flow into the LHS output.
+            // `lhs = f(..)`: the return value of the function's signature, instantiated at this
+            // call, flows into the LHS. The arguments flowing into the signature are related when
+            // evaluating the call.
+            (
+                _,
+                Expr::Call {
+                    name, generic_args, ..
+                },
+            ) => {
+                if let Some(prototype) = self.fn_prototype(name) {
+                    let (_, ret_ty) = instantiate_signature(prototype, generic_args);
+                    self.relate_values(node, lhs_ty, &ret_ty, facts);
+                }
+            }
+
+            // `lhs = rhs as ty`: raw pointers have no origins, so the loans of a reference cast to
+            // a raw pointer either stop being tracked, or escape into an origin kept live.
+            //
+            // TODO: relate the origins in the pointee types, e.g. when casting `&&'a i32` to
+            // `*const &'b i32`.
+            (_, Expr::Cast { operand, ty }) => {
+                let escapes = self.options.raw_pointer_casts == RawPointerCasts::KeepLive;
+                if let (true, Expr::Access { place, .. }) = (escapes && ty.is_raw_ptr(), &**operand)
+                {
+                    let mut origins = Vec::new();
+                    self.ty_of_place(place).collect_origins_into(&mut origins);
+                    for origin in origins {
+                        facts
+                            .introduce_subset
+                            .push((origin, ESCAPED_ORIGIN.into(), node.clone()));
+                    }
+                }
             }
 
             _ => {
@@ -413,6 +1309,50 @@ impl<'a> FactEmitter<'a> {
         }
     }
 
+    // Emit subset relationships for a value of type `rhs_ty` flowing into a place of type
+    // `lhs_ty`: between the origins of the references themselves, and between their parameters.
+    fn relate_values(&self, node: &Node, lhs_ty: &Ty, rhs_ty: &Ty, facts: &mut Facts) {
+        match (lhs_ty, rhs_ty) {
+            (
+                Ty::Ref {
+                    origin: target_origin,
+                    ty: lhs_ty,
+                },
+                Ty::Ref {
+                    origin: source_origin,
+                    ty: rhs_ty,
+                },
+            ) => {
+                facts.introduce_subset.push((
+                    source_origin.into(),
+                    target_origin.into(),
+                    node.clone(),
+                ));
+                self.relate_tys(node, lhs_ty, rhs_ty, Variance::Covariant, facts);
+            }
+
+            (
+                Ty::RefMut {
+                    origin: target_origin,
+                    ty: lhs_ty,
+                },
+                Ty::RefMut {
+                    origin: source_origin,
+                    ty: rhs_ty,
+                },
+            ) => {
+                facts.introduce_subset.push((
+                    source_origin.into(),
+                    target_origin.into(),
+                    node.clone(),
+                ));
+                self.relate_tys(node, lhs_ty, rhs_ty, Variance::Invariant, facts);
+            }
+
+            _ => self.relate_tys(node, lhs_ty, rhs_ty, Variance::Covariant, facts),
+        }
+    }
+
     // Emit subset relationships between the two types' parameters, according to the
     // variance rules, recursively.
     fn relate_tys(
@@ -436,189 +1376,262 @@ impl<'a> FactEmitter<'a> {
             ) => {
                 // Relate the arguments to the generic structs pair-wise, according to variance
                 for (lhs_arg, rhs_arg) in lhs_args.iter().zip(rhs_args.iter()) {
-                    match (lhs_arg, rhs_arg) {
-                        (
-                            Parameter::Ty(
-                                param @ Ty::Ref {
-                                    origin: target_origin,
-                                    ty: lhs_ty,
-                                },
-                            ),
-                            Parameter::Ty(Ty::Ref {
-                                origin: source_origin,
-                                ty: rhs_ty,
-                            }),
-                        )
-                        | (
-                            Parameter::Ty(
-                                param @ Ty::RefMut {
-                                    origin: target_origin,
-                                    ty: lhs_ty,
-                                },
-                            ),
-                            Parameter::Ty(Ty::RefMut {
-                                origin: source_origin,
-                                ty: rhs_ty,
-                            }),
-                        ) => {
-                            if let Variance::Covariant | Variance::Invariant = variance {
-                                facts.introduce_subset.push((
-                                    source_origin.into(),
-                                    target_origin.into(),
-                                    node.clone(),
-                                ));
-                            }
+                    self.relate_parameters(node, lhs_arg, rhs_arg, variance, facts);
+                }
+            }
 
-                            if let Variance::Contravariant | Variance::Invariant = variance {
-                                facts.introduce_subset.push((
-                                    target_origin.into(),
-                                    source_origin.into(),
-                                    node.clone(),
-                                ));
-                            }
+            // The elements of arrays are related like a type parameter
+            (Ty::Array { ty: lhs_ty, .. }, Ty::Array { ty: rhs_ty, .. }) => {
+                let lhs_arg = Parameter::Ty((**lhs_ty).clone());
+                let rhs_arg = Parameter::Ty((**rhs_ty).clone());
+                self.relate_parameters(node, &lhs_arg, &rhs_arg, variance, facts);
+            }
+
+            _ => {}
+        }
+    }
 
-                            // Unique references change the relationships of their children
-                            // parameter pairs: they must be invariant.
-                            let variance = if matches!(param, Ty::RefMut { .. }) {
-                                Variance::Invariant
-                            } else {
-                                variance
-                            };
+    // Emit subset relationships between a pair of arguments to generic types, according to the
+    // variance rules, recursively.
+    fn relate_parameters(
+        &self,
+        node: &Node,
+        lhs_arg: &Parameter,
+        rhs_arg: &Parameter,
+        variance: Variance,
+        facts: &mut Facts,
+    ) {
+        match (lhs_arg, rhs_arg) {
+            (
+                Parameter::Ty(
+                    param @ Ty::Ref {
+                        origin: target_origin,
+                        ty: lhs_ty,
+                    },
+                ),
+                Parameter::Ty(Ty::Ref {
+                    origin: source_origin,
+                    ty: rhs_ty,
+                }),
+            )
+            | (
+                Parameter::Ty(
+                    param @ Ty::RefMut {
+                        origin: target_origin,
+                        ty: lhs_ty,
+                    },
+                ),
+                Parameter::Ty(Ty::RefMut {
+                    origin: source_origin,
+                    ty: rhs_ty,
+                }),
+            ) => {
+                if let Variance::Covariant | Variance::Invariant = variance {
+                    facts.introduce_subset.push((
+                        source_origin.into(),
+                        target_origin.into(),
+                        node.clone(),
+                    ));
+                }
 
-                            self.relate_tys(node, &lhs_ty, &rhs_ty, variance, facts);
-                        }
+                if let Variance::Contravariant | Variance::Invariant = variance {
+                    facts.introduce_subset.push((
+                        target_origin.into(),
+                        source_origin.into(),
+                        node.clone(),
+                    ));
+                }
 
-                        (Parameter::Ty(lhs_ty), Parameter::Ty(rhs_ty)) => {
-                            // TODO: variance can also change if the type is special here:
-                            // e.g. UnsafeCell
-                            self.relate_tys(node, &lhs_ty, &rhs_ty, variance, facts);
-                        }
+                // Unique references change the relationships of their children
+                // parameter pairs: they must be invariant.
+                let variance = if matches!(param, Ty::RefMut { .. }) {
+                    Variance::Invariant
+                } else {
+                    variance
+                };
+
+                self.relate_tys(node, lhs_ty, rhs_ty, variance, facts);
+            }
+
+            (Parameter::Ty(lhs_ty), Parameter::Ty(rhs_ty)) => {
+                // TODO: variance can also change if the type is special here:
+                // e.g. UnsafeCell
+                self.relate_tys(node, lhs_ty, rhs_ty, variance, facts);
+            }
+
+            // TODO: the variance of structs with respect to their origin parameters
+            // is not computed from their fields yet, they're assumed covariant.
+            (Parameter::Origin(target_origin), Parameter::Origin(source_origin)) => {
+                if let Variance::Covariant | Variance::Invariant = variance {
+                    facts.introduce_subset.push((
+                        source_origin.into(),
+                        target_origin.into(),
+                        node.clone(),
+                    ));
+                }
 
-                        _ => todo!(),
-                    }
+                if let Variance::Contravariant | Variance::Invariant = variance {
+                    facts.introduce_subset.push((
+                        target_origin.into(),
+                        source_origin.into(),
+                        node.clone(),
+                    ));
                 }
             }
 
-            _ => {}
+            _ => todo!(),
         }
     }
 
     fn emit_cfg_edges(&self, bb: &BasicBlock, facts: &mut Facts) {
         let statement_count = bb.statements.len();
+        let mut emit_edge = |from: Node, to: Node, kind: CfgEdgeKind| {
+            facts.cfg_edge.push((from.clone(), to.clone()));
+            facts.cfg_edge_kind.push((from, to, kind));
+        };
 
         // Emit intra-block CFG edges between statements
         for idx in 1..statement_count {
-            facts
-                .cfg_edge
-                .push((self.node_at(&bb.name, idx - 1), self.node_at(&bb.name, idx)));
+            emit_edge(
+                self.node_at(&bb.name, idx - 1),
+                self.node_at(&bb.name, idx),
+                CfgEdgeKind::Normal,
+            );
         }
 
         // Emit inter-block CFG edges between a block and its successors
-        for succ in &bb.successors {
+        let block_idx = self.block_idx(&bb.name);
+        for succ in &bb.terminator.successors() {
+            let kind = if bb.terminator.unwind() == Some(*succ) {
+                CfgEdgeKind::Unwind
+            } else if self.dominators.dominates(self.block_idx(succ), block_idx) {
+                CfgEdgeKind::Back
+            } else {
+                CfgEdgeKind::Normal
+            };
+
             // Note: `goto`s are not statements, so a block with a single goto
             // has no statements but still needs a node index in the CFG.
-            facts.cfg_edge.push((
+            emit_edge(
                 self.node_at(&bb.name, statement_count.saturating_sub(1)),
                 self.node_at(succ, 0),
-            ));
+                kind,
+            );
         }
     }
 
-    fn ty_of_place(&self, place: &Place) -> &Ty {
-        self.walk_place_tys(place, |_| ())
+    // The origins of the loans invalidated by writing to, or moving out of, the place: the loans
+    // of the places overlapping it, except the ones reached through a reference stored in the
+    // place, which stay valid as the reference is only overwritten, and the ones whose borrow
+    // can't reach the node. They're ordered by the location of their borrow.
+    fn loans_invalidated_by(&self, place: &Place, node: &Node) -> Vec<&Origin> {
+        let node_idx = self.node_indices[node];
+        let reaches_node = |location: &Location| {
+            let definition = self.loan_definition(location);
+            self.reaching_loans.definitions()[definition] == node_idx
+                || self.reaching_loans.reaches(definition, node_idx)
+        };
+        let mut loans: Vec<_> = self
+            .loans
+            .iter()
+            .filter(|(loan_place, _)| {
+                let is_behind_reference = place.is_prefix_of(loan_place)
+                    && loan_place.projections[place.projections.len()..]
+                        .contains(&Projection::Deref);
+                loan_place.overlaps(place) && !is_behind_reference
+            })
+            .flat_map(|(_, loans)| loans)
+            .filter(|(_, location)| reaches_node(location))
+            .collect();
+        loans.sort_by_key(|(_, location)| (location.block_idx, location.statement_idx));
+        loans.into_iter().map(|(origin, _)| origin).collect()
     }
 
-    fn origins_of_place(&self, place: &Place) -> Vec<Origin> {
-        let mut origins = Vec::new();
-        self.walk_place_tys(place, |ty| {
-            ty.collect_origins_into(&mut origins);
-        });
-        origins
+    // The definition of the node issuing the loans of the location, among the reaching loans.
+    fn loan_definition(&self, location: &Location) -> usize {
+        let loan_node = self.first_nodes[location.block_idx] + location.statement_idx;
+        self.reaching_loans
+            .definitions()
+            .binary_search(&loan_node)
+            .expect("the nodes issuing loans are sorted definitions")
     }
 
-    fn walk_place_tys<F>(&self, place: &Place, mut ty_walked_callback: F) -> &Ty
-    where
-        F: FnMut(&Ty),
-    {
-        let v = self
-            .program
-            .variables
-            .iter()
-            .find(|v| v.name == place.base)
-            .unwrap_or_else(|| panic!("Can't find variable {}", place.base));
+    // The origins of the loans whose borrows reach the entry of each node, in the order of the
+    // nodes, and of the borrows.
+    fn loans_reaching_nodes(&self) -> Vec<(Node, Vec<Origin>)> {
+        let mut loans: Vec<_> = self.loans.values().flatten().collect();
+        loans.sort_by_key(|(_, location)| (location.block_idx, location.statement_idx));
+        let mut nodes: Vec<_> = self.node_indices.iter().collect();
+        nodes.sort_by_key(|(_, &node_idx)| node_idx);
+        nodes
+            .into_iter()
+            .map(|(node, &node_idx)| {
+                let origins = loans
+                    .iter()
+                    .filter(|(_, location)| {
+                        let definition = self.loan_definition(location);
+                        self.reaching_loans.reaches(definition, node_idx)
+                    })
+                    .map(|(origin, _)| origin.clone())
+                    .collect();
+                (node.clone(), origins)
+            })
+            .collect()
+    }
 
-        let mut ty = &v.ty;
+    fn fn_prototype(&self, name: &str) -> Option<&FnPrototype> {
+        self.program.fn_prototypes.iter().find(|f| f.name == name)
+    }
 
-        for proj in &place.projections {
-            // Notify a traversal step was taken for the current field parent's ty
-            ty_walked_callback(ty);
+    fn ty_of_place(&self, place: &Place) -> Rc<Ty> {
+        self.ty_and_origins_of_place(place).0
+    }
 
-            match proj {
-                Projection::Deref => ty = ty.target().expect("Deref of non-reference type"),
+    fn origins_of_place(&self, place: &Place) -> Vec<Origin> {
+        self.ty_and_origins_of_place(place).1
+    }
 
-                Projection::Field(field_name) => {
-                    let (struct_name, struct_substs) = match ty {
-                        Ty::Struct { name, parameters } => (name, parameters),
-                        _ => panic!("Ty {:?} must be a struct to access its fields", ty),
-                    };
-                    let decl = self
-                        .program
-                        .struct_decls
-                        .iter()
-                        .find(|s| &s.name == struct_name)
-                        .unwrap_or_else(|| {
-                            panic!("Can't find struct {} at field {}", struct_name, field_name,)
-                        });
+    // The type of the place, and the origins in the types walked to reach it, resolved once per
+    // place.
+    fn ty_and_origins_of_place(&self, place: &Place) -> PlaceTy {
+        if let Some(resolved) = self.place_tys.borrow().get(place) {
+            return resolved.clone();
+        }
 
-                    // Find the expected named field inside the struct decl
-                    let field = decl
-                        .field_decls
-                        .iter()
-                        .find(|v| &v.name == field_name)
-                        .unwrap_or_else(|| {
-                            panic!("Can't find field {} in struct {}", field_name, struct_name)
-                        });
+        let mut origins = Vec::new();
+        let ty = try_walk_place_tys_indexed(&self.program, &self.declarations, place, |ty| {
+            ty.collect_origins_into(&mut origins);
+        })
+        .unwrap_or_else(|e| panic!("{}", e));
+        let resolved = (Rc::new(ty.clone()), origins);
+        self.place_tys
+            .borrow_mut()
+            .insert(place.clone(), resolved.clone());
+        resolved
+    }
 
-                    // It's possible that the field has a generic type, which we need to substitute
-                    // with the matching type from the struct's arguments
-                    ty = match &field.ty {
-                        Ty::Struct {
-                            name: field_ty_name,
-                            ..
-                        } => {
-                            if let Some(idx) = decl.generic_decls.iter().position(|d| match d {
-                                GenericDecl::Ty(param_ty_name) => param_ty_name == field_ty_name,
-                                _ => false,
-                            }) {
-                                // We found the field ty in the generic decls, so return the subst
-                                // at the same index
-                                match &struct_substs[idx] {
-                                    Parameter::Ty(subst_ty) => subst_ty,
-
-                                    // TODO: handle generic origins
-                                    _ => panic!("The parameter at idx {} should be a Ty", idx),
-                                }
-                            } else {
-                                // Otherwise, the field ty is a regular type
-                                &field.ty
-                            }
-                        }
-                        _ => &field.ty,
-                    }
-                }
-            }
-        }
+    // The text of the statement in the input, without its `;`.
+    fn statement_text(&self, s: &Spanned<Statement>) -> &'a str {
+        let span = s.span();
+        let text = &self.input[span.start()..span.end()];
+        text.strip_suffix(';').unwrap_or(text)
+    }
 
-        // Notify a step in the walk was taken, either:
-        // - the `base` ty, when there are no fields
-        // - the last field's ty, from the place's `fields` list. The callbacks for the previous
-        // fields in the list have already been processed in the loop just above.
-        ty_walked_callback(ty);
-        ty
+    // The index of the block in the program. The blocks the program goes to are validated to
+    // exist.
+    fn block_idx(&self, block: &str) -> usize {
+        self.program
+            .basic_blocks
+            .iter()
+            .position(|bb| bb.name == block)
+            .expect("unknown block")
     }
 
     fn node_at(&self, block: &str, statement_idx: usize) -> Node {
-        let mut node = format!("{}[{}]", block, statement_idx);
+        let mut node = match self.options.node_names {
+            NodeNames::Indexed => format!("{}[{}]", block, statement_idx),
+            NodeNames::Hashed => self.hashed_node_name(block, statement_idx),
+        };
 
         // Hack: if we temporarily need simpler node names, while comparing to the manual facts:
         // use single-letter names.
@@ -633,7 +1646,7 @@ impl<'a> FactEmitter<'a> {
                 .program
                 .basic_blocks
                 .iter()
-                .take_while(|bb| block != bb.name)
+                .take_while(|bb| bb.name != block)
                 .fold(0, |acc, bb| acc + bb.statements.len().max(1));
             let node_idx = 'a' as u32 + (bb_statement_start_idx + statement_idx) as u32;
             let node_as_letter = char::from_u32(node_idx).unwrap_or_else(|| {
@@ -648,6 +1661,37 @@ impl<'a> FactEmitter<'a> {
         node.into()
     }
 
+    // The name of a node from the hash of its block's name and its statement's text, see
+    // `NodeNames::Hashed`. The node of an empty block hashes its name only.
+    fn hashed_node_name(&self, block: &str, statement_idx: usize) -> String {
+        let statements = self
+            .program
+            .basic_blocks
+            .iter()
+            .find(|bb| bb.name == block)
+            .map_or(&[][..], |bb| &bb.statements[..]);
+        let text = statements
+            .get(statement_idx)
+            .map_or("", |s| self.statement_text(s));
+        let occurrence = statements[..statement_idx.min(statements.len())]
+            .iter()
+            .filter(|s| self.statement_text(s) == text)
+            .count();
+
+        // The 32-bit FNV-1a hash, which is stable across platforms and compiler versions
+        let hash = block
+            .bytes()
+            .chain([0])
+            .chain(text.bytes())
+            .fold(0x811c9dc5u32, |hash, byte| {
+                (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+            });
+        match occurrence {
+            0 => format!("{}[{:08x}]", block, hash),
+            _ => format!("{}[{:08x}_{}]", block, hash, occurrence),
+        }
+    }
+
     // Sanity check that no origins are present
     // - in the LHS ty
     // - in borrow expressions on the RHS
@@ -681,7 +1725,7 @@ impl<'a> FactEmitter<'a> {
                     );
                 }
 
-                AccessKind::Copy | AccessKind::Move => {
+                AccessKind::Copy | AccessKind::Move | AccessKind::Infer => {
                     let rhs_ty = self.ty_of_place(place);
                     assert_eq!(
                         rhs_ty.has_origins(),
@@ -696,6 +1740,663 @@ impl<'a> FactEmitter<'a> {
     }
 }
 
+// Walks the types of the successive projections of a place, and returns the type of the place
+// itself.
+fn walk_place_tys<'p, F>(program: &'p Program, place: &Place, ty_walked_callback: F) -> &'p Ty
+where
+    F: FnMut(&Ty),
+{
+    try_walk_place_tys(program, place, ty_walked_callback).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// Walks the types of the successive projections of a place like `walk_place_tys`, or returns why
+// the place is invalid: its variable is not declared, or one of its projections doesn't apply to
+// the type it's projected from.
+pub(crate) fn try_walk_place_tys<'p, F>(
+    program: &'p Program,
+    place: &Place,
+    ty_walked_callback: F,
+) -> Result<&'p Ty, String>
+where
+    F: FnMut(&Ty),
+{
+    let declarations = DeclarationIndices::new(program);
+    try_walk_place_tys_indexed(program, &declarations, place, ty_walked_callback)
+}
+
+// Walks the types of the successive projections of a place like `try_walk_place_tys`, looking
+// its variable and structs up in the indices of the program's declarations.
+fn try_walk_place_tys_indexed<'p, F>(
+    program: &'p Program,
+    declarations: &DeclarationIndices,
+    place: &Place,
+    mut ty_walked_callback: F,
+) -> Result<&'p Ty, String>
+where
+    F: FnMut(&Ty),
+{
+    let v = declarations
+        .variable(program, &place.base)
+        .ok_or_else(|| format!("unknown variable `{}`", place.base))?;
+
+    let mut ty = &v.ty;
+
+    for (idx, proj) in place.projections.iter().enumerate() {
+        let projected = || Place {
+            base: place.base,
+            projections: place.projections[..idx].to_vec(),
+        };
+        // Notify a traversal step was taken for the current field parent's ty
+        ty_walked_callback(ty);
+
+        match proj {
+            Projection::Index(index) => {
+                let is_variable = !index.bytes().all(|b| b.is_ascii_digit());
+                if is_variable && declarations.variable(program, index).is_none() {
+                    return Err(format!("unknown variable `{}`", index));
+                }
+                ty = match ty {
+                    Ty::Array { ty, .. } => ty,
+                    _ => {
+                        return Err(format!(
+                            "can't index `{}`, which is not an array",
+                            projected()
+                        ))
+                    }
+                };
+            }
+
+            Projection::Deref => {
+                ty = ty.target().ok_or_else(|| {
+                    format!(
+                        "can't dereference `{}`, which is not a reference",
+                        projected()
+                    )
+                })?
+            }
+
+            Projection::Field(field_name) => {
+                let (struct_name, struct_substs) = match ty {
+                    Ty::Struct { name, parameters } => (name, parameters),
+                    _ => {
+                        return Err(format!(
+                            "can't access field `{}` of `{}`, which is not a struct",
+                            field_name,
+                            projected()
+                        ))
+                    }
+                };
+                let decl = declarations
+                    .struct_decl(program, struct_name)
+                    .ok_or_else(|| format!("unknown struct `{}`", struct_name))?;
+
+                // Find the expected named field inside the struct decl
+                let field = decl
+                    .field_decls
+                    .iter()
+                    .find(|v| &v.name == field_name)
+                    .ok_or_else(|| {
+                        format!("struct `{}` has no field `{}`", struct_name, field_name)
+                    })?;
+
+                // It's possible that the field has a generic type, which we need to substitute
+                // with the matching type from the struct's arguments
+                ty = match &field.ty {
+                    Ty::Struct {
+                        name: field_ty_name,
+                        ..
+                    } => {
+                        if let Some(param_idx) = decl.generic_decls.iter().position(|d| match d {
+                            GenericDecl::Ty(param_ty_name) => param_ty_name == field_ty_name,
+                            _ => false,
+                        }) {
+                            // We found the field ty in the generic decls, so return the subst
+                            // at the same index
+                            match struct_substs.get(param_idx) {
+                                Some(Parameter::Ty(subst_ty)) => subst_ty,
+
+                                // TODO: handle generic origins
+                                _ => {
+                                    return Err(format!(
+                                        "the parameter at index {} of struct `{}` should be a type",
+                                        param_idx, struct_name
+                                    ))
+                                }
+                            }
+                        } else {
+                            // Otherwise, the field ty is a regular type
+                            &field.ty
+                        }
+                    }
+                    _ => &field.ty,
+                }
+            }
+        }
+    }
+
+    // Notify a step in the walk was taken, either:
+    // - the `base` ty, when there are no fields
+    // - the last field's ty, from the place's `fields` list. The callbacks for the previous
+    // fields in the list have already been processed in the loop just above.
+    ty_walked_callback(ty);
+    Ok(ty)
+}
+
+// Calls `f` with the origin of each borrow in the given expression, and the text of that borrow
+// expression.
+fn visit_borrows_mut(expr: &mut Expr, f: &mut impl FnMut(&mut Name, String)) {
+    match expr {
+        Expr::Access { kind, place } => match kind {
+            AccessKind::Borrow(origin) => f(origin, format!("&{}", place)),
+            AccessKind::BorrowMut(origin) => f(origin, format!("&mut {}", place)),
+            AccessKind::Copy | AccessKind::Move | AccessKind::Infer => {}
+        },
+
+        Expr::Call { arguments, .. } => {
+            for argument in arguments {
+                visit_borrows_mut(argument, f);
+            }
+        }
+
+        Expr::Cast { operand, .. } => visit_borrows_mut(operand, f),
+
+        Expr::Number { .. } | Expr::Unit => {}
+    }
+}
+
+// Names the origins of anonymous borrows `'anon0`, `'anon1`, etc. in program order, and records
+// them with the text of their borrow, and their location.
+#[derive(Default)]
+struct AnonymousOrigins {
+    origins: Vec<(Origin, String, Location)>,
+    block_idx: usize,
+    statement_idx: usize,
+}
+
+impl Folder for AnonymousOrigins {
+    fn fold_basic_block(&mut self, bb: BasicBlock) -> BasicBlock {
+        self.statement_idx = 0;
+        let bb = noop_fold_basic_block(self, bb);
+        self.block_idx += 1;
+        bb
+    }
+
+    fn fold_statement(&mut self, statement: Spanned<Statement>) -> Vec<Spanned<Statement>> {
+        let statements = noop_fold_statement(self, statement);
+        self.statement_idx += 1;
+        statements
+    }
+
+    // The operands of terminators are named at the statements evaluating them.
+    fn fold_terminator(&mut self, terminator: Terminator) -> Terminator {
+        terminator
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let mut fresh_origin = |borrow_text: String| {
+            let origin = Name::from(format!("'anon{}", self.origins.len()));
+            let location = (self.block_idx, self.statement_idx).into();
+            self.origins.push((origin.into(), borrow_text, location));
+            origin
+        };
+        let expr = match expr {
+            Expr::Access {
+                kind: AccessKind::Borrow(origin),
+                place,
+            } if origin == ANONYMOUS_ORIGIN => Expr::Access {
+                kind: AccessKind::Borrow(fresh_origin(format!("&{}", place))),
+                place,
+            },
+            Expr::Access {
+                kind: AccessKind::BorrowMut(origin),
+                place,
+            } if origin == ANONYMOUS_ORIGIN => Expr::Access {
+                kind: AccessKind::BorrowMut(fresh_origin(format!("&mut {}", place))),
+                place,
+            },
+            expr => expr,
+        };
+        noop_fold_expr(self, expr)
+    }
+}
+
+// Removes the blocks which are not reachable from the entry block: they can only have edges to
+// each other, or to reachable blocks, so the rest of the CFG is unchanged.
+fn remove_unreachable_blocks(program: &mut Program) {
+    let unreachable_blocks = BlockGraph::from_program(program).unreachable_blocks();
+    let mut block_idx = 0;
+    program.basic_blocks.retain(|_| {
+        block_idx += 1;
+        !unreachable_blocks.contains(&(block_idx - 1))
+    });
+}
+
+// Infers the types of the variables declared without one, e.g. `let p;`, from their first
+// assignment in the program.
+//
+// Their origins are not the ones of the assigned value, but fresh origins the value flows into:
+// they are elided here, and inferred with the other elided origins.
+// Infers how the call arguments written without an access kind are passed, from the types of the
+// function's parameters:
+// - non-references passed to a reference parameter are borrowed, mutably for a mutable
+//   reference. Mutable references are reborrowed instead of being moved into the call, like
+//   rustc does.
+// - the other arguments are read, which copies them or moves them depending on their type.
+//
+// Only the types of the declared variables are known at this point: arguments of an inferred
+// type are always read.
+pub(crate) fn infer_access_kinds(program: &mut Program) {
+    for block_idx in 0..program.basic_blocks.len() {
+        for statement_idx in 0..program.basic_blocks[block_idx].statements.len() {
+            let expr = match program.basic_blocks[block_idx].statements[statement_idx].expr() {
+                Some(expr) => expr,
+                None => continue,
+            };
+            let (name, arguments) = match expr {
+                Expr::Call {
+                    name, arguments, ..
+                } => (name, arguments),
+                _ => continue,
+            };
+            let arg_tys = program
+                .fn_prototypes
+                .iter()
+                .find(|f| &f.name == name)
+                .map(|prototype| &prototype.arg_tys[..])
+                .unwrap_or_default();
+
+            let kinds: Vec<_> = arguments
+                .iter()
+                .enumerate()
+                .map(|(idx, argument)| {
+                    let place = match argument {
+                        Expr::Access {
+                            kind: AccessKind::Infer,
+                            place,
+                        } => place,
+                        _ => return None,
+                    };
+                    let ty = try_walk_place_tys(program, place, |_| ()).ok();
+                    let anonymous = Name::from(ANONYMOUS_ORIGIN);
+                    Some(match (arg_tys.get(idx), ty) {
+                        (Some(Ty::Ref { .. }), Some(Ty::RefMut { .. })) => {
+                            (AccessKind::Borrow(anonymous), true)
+                        }
+                        (Some(Ty::RefMut { .. }), Some(Ty::RefMut { .. })) => {
+                            (AccessKind::BorrowMut(anonymous), true)
+                        }
+                        (_, None | Some(Ty::Infer | Ty::Ref { .. })) => (AccessKind::Move, false),
+                        (Some(Ty::Ref { .. }), _) => (AccessKind::Borrow(anonymous), false),
+                        (Some(Ty::RefMut { .. }), _) => (AccessKind::BorrowMut(anonymous), false),
+                        _ => (AccessKind::Move, false),
+                    })
+                })
+                .collect();
+
+            let expr = match program.basic_blocks[block_idx].statements[statement_idx].expr_mut() {
+                Some(expr) => expr,
+                None => continue,
+            };
+            if let Expr::Call { arguments, .. } = expr {
+                for (argument, inferred) in arguments.iter_mut().zip(kinds) {
+                    if let (Expr::Access { kind, place }, Some((inferred, is_reborrow))) =
+                        (argument, inferred)
+                    {
+                        *kind = inferred;
+                        if is_reborrow {
+                            place.projections.push(Projection::Deref);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn infer_variable_tys(program: &mut Program) {
+    for block_idx in 0..program.basic_blocks.len() {
+        for statement_idx in 0..program.basic_blocks[block_idx].statements.len() {
+            let (place, expr) = match &*program.basic_blocks[block_idx].statements[statement_idx] {
+                Statement::Assign(place, expr) if place.projections.is_empty() => (place, expr),
+                _ => continue,
+            };
+
+            let v = program
+                .variables
+                .iter()
+                .position(|v| v.name == place.base)
+                .unwrap_or_else(|| panic!("Can't find variable {}", place.base));
+            if program.variables[v].ty != Ty::Infer {
+                continue;
+            }
+
+            let mut ty = match expr {
+                Expr::Access { kind, place } => {
+                    let ty = Box::new(walk_place_tys(program, place, |_| ()).clone());
+                    let origin = Name::from(ANONYMOUS_ORIGIN);
+                    match kind {
+                        AccessKind::Borrow(_) => Ty::Ref { origin, ty },
+                        AccessKind::BorrowMut(_) => Ty::RefMut { origin, ty },
+                        AccessKind::Copy | AccessKind::Move | AccessKind::Infer => *ty,
+                    }
+                }
+                Expr::Number { .. } => Ty::I32,
+                Expr::Unit => Ty::Unit,
+                Expr::Cast { ty, .. } => ty.clone(),
+                Expr::Call {
+                    name,
+                    generic_args,
+                    arguments,
+                } => {
+                    let prototype = program
+                        .fn_prototypes
+                        .iter()
+                        .find(|f| &f.name == name)
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "Can't infer the type of {}: missing prototype for fn {}",
+                                place.base, name
+                            )
+                        });
+                    let generic_args =
+                        instantiate_generic_args(program, prototype, generic_args, arguments);
+                    instantiate_signature(prototype, &generic_args).1
+                }
+            };
+            if ty == Ty::Infer {
+                panic!(
+                    "Can't infer the type of {}, from a variable whose type is not inferred yet",
+                    place.base
+                );
+            }
+
+            ty.visit_origins_mut(&mut |origin| *origin = ANONYMOUS_ORIGIN.into());
+            program.variables[v].ty = ty;
+        }
+    }
+
+    if let Some(v) = program.variables.iter().find(|v| v.ty == Ty::Infer) {
+        panic!("Can't infer the type of {}, it's never assigned", v.name);
+    }
+}
+
+// Infers the origins elided from the types of variables, e.g. `let p: &i32;`.
+//
+// These are local origins, like all the origins in variable types: the loans they contain are
+// inferred by the analysis itself, from the subsets introduced where the variables are assigned.
+// Solving for them before emitting facts thus only needs them to be distinct from all the other
+// origins: each elided origin is given a fresh name, after its variable (`'p`, then `'p1`, `'p2`,
+// etc. for the other elided origins in the same type).
+fn infer_elided_origins(program: &mut Program) {
+    let mut used_origins = HashSet::new();
+    for v in &mut program.variables {
+        v.ty.visit_origins_mut(&mut |origin| {
+            used_origins.insert(*origin);
+        });
+    }
+    for bb in &mut program.basic_blocks {
+        for s in &mut bb.statements {
+            let expr = match s.expr_mut() {
+                Some(expr) => expr,
+                None => continue,
+            };
+            visit_borrows_mut(expr, &mut |origin, _| {
+                used_origins.insert(*origin);
+            });
+        }
+    }
+
+    for v in &mut program.variables {
+        let name = &v.name;
+        let mut candidates = (0..).map(|idx| match idx {
+            0 => Name::from(format!("'{}", name)),
+            _ => Name::from(format!("'{}{}", name, idx)),
+        });
+        v.ty.visit_origins_mut(&mut |origin| {
+            if origin == ANONYMOUS_ORIGIN {
+                let fresh_origin = candidates
+                    .by_ref()
+                    .find(|candidate| !used_origins.contains(candidate))
+                    .unwrap();
+                used_origins.insert(fresh_origin);
+                *origin = fresh_origin;
+            }
+        });
+    }
+}
+
+// Instantiates the generic parameters of the declared functions at each of their calls, with
+// the generic arguments of `instantiate_generic_args`. The origins left out of the generic
+// arguments are fresh at each call, and named after it and the origin they instantiate (`'call0_a`
+// for the origin `'a` at the first call), and so are the origins elided in, or inferred with, its
+// types (`'call0_1`, etc.).
+fn instantiate_calls(program: &mut Program) {
+    let mut call_idx = 0;
+    for block_idx in 0..program.basic_blocks.len() {
+        for statement_idx in 0..program.basic_blocks[block_idx].statements.len() {
+            let expr = match program.basic_blocks[block_idx].statements[statement_idx].expr() {
+                Some(expr) => expr,
+                None => continue,
+            };
+            let (prototype, generic_args, arguments) = match expr {
+                Expr::Call {
+                    name,
+                    generic_args,
+                    arguments,
+                } => match program.fn_prototypes.iter().find(|f| &f.name == name) {
+                    Some(prototype) => (prototype, generic_args, arguments),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let mut instance =
+                instantiate_generic_args(program, prototype, generic_args, arguments);
+            let mut fresh_origin_count = 0;
+            for (decl, arg) in prototype.generic_decls.iter().zip(&mut instance) {
+                match (decl, arg) {
+                    (GenericDecl::Origin(param), Parameter::Origin(origin)) => {
+                        if origin == ANONYMOUS_ORIGIN {
+                            *origin = format!("'call{}_{}", call_idx, &param[1..]).into();
+                        }
+                    }
+                    (_, Parameter::Ty(ty)) => ty.visit_origins_mut(&mut |origin| {
+                        if origin == ANONYMOUS_ORIGIN {
+                            fresh_origin_count += 1;
+                            *origin = format!("'call{}_{}", call_idx, fresh_origin_count).into();
+                        }
+                    }),
+                    _ => {
+                        unreachable!("Generic arguments are instantiated in the order of the decls")
+                    }
+                }
+            }
+            call_idx += 1;
+
+            let expr = match program.basic_blocks[block_idx].statements[statement_idx].expr_mut() {
+                Some(expr) => expr,
+                None => continue,
+            };
+            if let Expr::Call { generic_args, .. } = expr {
+                *generic_args = instance;
+            }
+        }
+    }
+}
+
+// The generic arguments of a call to the function, one for each of its generic parameters, in
+// order. They are the ones given with a turbofish, or for the ones left out: the anonymous origin,
+// and the types inferred from the arguments, with anonymous origins. The types which can't be
+// inferred are `Ty::Infer`.
+fn instantiate_generic_args(
+    program: &Program,
+    prototype: &FnPrototype,
+    generic_args: &[Parameter],
+    arguments: &[Expr],
+) -> Vec<Parameter> {
+    let mut origins = generic_args.iter().filter_map(|arg| match arg {
+        Parameter::Origin(origin) => Some(*origin),
+        Parameter::Ty(_) => None,
+    });
+    let mut tys = generic_args.iter().filter_map(|arg| match arg {
+        Parameter::Origin(_) => None,
+        Parameter::Ty(ty) => Some(ty.clone()),
+    });
+    let mut instance: Vec<_> = prototype
+        .generic_decls
+        .iter()
+        .map(|decl| match decl {
+            GenericDecl::Origin(_) => {
+                Parameter::Origin(origins.next().unwrap_or_else(|| ANONYMOUS_ORIGIN.into()))
+            }
+            GenericDecl::Ty(_) => Parameter::Ty(tys.next().unwrap_or(Ty::Infer)),
+        })
+        .collect();
+
+    for (param_ty, argument) in prototype.arg_tys.iter().zip(arguments) {
+        if let Some(arg_ty) = operand_ty(program, argument) {
+            infer_generic_tys(param_ty, &arg_ty, &prototype.generic_decls, &mut instance);
+        }
+    }
+    instance
+}
+
+// Infers the type parameters left out of the generic arguments, by matching the type of a
+// parameter of the function with the type of the argument given to it.
+fn infer_generic_tys(
+    param_ty: &Ty,
+    arg_ty: &Ty,
+    generic_decls: &[GenericDecl],
+    instance: &mut [Parameter],
+) {
+    if let Ty::Struct { name, parameters } = param_ty {
+        let idx = generic_decls
+            .iter()
+            .position(|decl| matches!(decl, GenericDecl::Ty(param) if param == name));
+        if let (true, Some(idx)) = (parameters.is_empty(), idx) {
+            if instance[idx] == Parameter::Ty(Ty::Infer) {
+                let mut ty = arg_ty.clone();
+                ty.visit_origins_mut(&mut |origin| *origin = ANONYMOUS_ORIGIN.into());
+                instance[idx] = Parameter::Ty(ty);
+            }
+            return;
+        }
+    }
+
+    match (param_ty, arg_ty) {
+        (Ty::Ref { ty: param_ty, .. }, Ty::Ref { ty: arg_ty, .. })
+        | (Ty::RefMut { ty: param_ty, .. }, Ty::RefMut { ty: arg_ty, .. })
+        | (Ty::RawPtr { ty: param_ty }, Ty::RawPtr { ty: arg_ty })
+        | (Ty::RawPtrMut { ty: param_ty }, Ty::RawPtrMut { ty: arg_ty })
+        | (Ty::Array { ty: param_ty, .. }, Ty::Array { ty: arg_ty, .. }) => {
+            infer_generic_tys(param_ty, arg_ty, generic_decls, instance)
+        }
+
+        (
+            Ty::Struct {
+                name: param_name,
+                parameters: param_parameters,
+            },
+            Ty::Struct {
+                name: arg_name,
+                parameters: arg_parameters,
+            },
+        ) if param_name == arg_name => {
+            for pair in param_parameters.iter().zip(arg_parameters) {
+                if let (Parameter::Ty(param_ty), Parameter::Ty(arg_ty)) = pair {
+                    infer_generic_tys(param_ty, arg_ty, generic_decls, instance);
+                }
+            }
+        }
+
+        _ => {}
+    }
+}
+
+// The types of the arguments and of the return value of the function, with its generic parameters
+// substituted by the generic arguments of a call.
+fn instantiate_signature(prototype: &FnPrototype, generic_args: &[Parameter]) -> (Vec<Ty>, Ty) {
+    let substitute = |ty| substitute_generics(ty, &prototype.generic_decls, generic_args);
+    let arg_tys = prototype.arg_tys.iter().map(substitute).collect();
+    (arg_tys, substitute(&prototype.ret_ty))
+}
+
+fn substitute_generics(ty: &Ty, generic_decls: &[GenericDecl], generic_args: &[Parameter]) -> Ty {
+    // Origin parameters start with a `'`, so they can't have the name of a type parameter
+    let arg_of = |name: &Name| {
+        generic_decls
+            .iter()
+            .zip(generic_args)
+            .find_map(|(decl, arg)| match decl {
+                GenericDecl::Origin(param) | GenericDecl::Ty(param) if param == name => Some(arg),
+                _ => None,
+            })
+    };
+    let substitute_origin = |origin: &Name| match arg_of(origin) {
+        Some(Parameter::Origin(arg)) => *arg,
+        _ => *origin,
+    };
+    let substitute = |ty: &Ty| Box::new(substitute_generics(ty, generic_decls, generic_args));
+
+    match ty {
+        Ty::Ref { origin, ty } => Ty::Ref {
+            origin: substitute_origin(origin),
+            ty: substitute(ty),
+        },
+        Ty::RefMut { origin, ty } => Ty::RefMut {
+            origin: substitute_origin(origin),
+            ty: substitute(ty),
+        },
+        Ty::RawPtr { ty } => Ty::RawPtr { ty: substitute(ty) },
+        Ty::RawPtrMut { ty } => Ty::RawPtrMut { ty: substitute(ty) },
+        Ty::Array { ty, len } => Ty::Array {
+            ty: substitute(ty),
+            len: *len,
+        },
+        Ty::Struct { name, parameters } => {
+            if let (true, Some(Parameter::Ty(arg))) = (parameters.is_empty(), arg_of(name)) {
+                return arg.clone();
+            }
+            let parameters = parameters
+                .iter()
+                .map(|param| match param {
+                    Parameter::Origin(origin) => Parameter::Origin(substitute_origin(origin)),
+                    Parameter::Ty(ty) => Parameter::Ty(*substitute(ty)),
+                })
+                .collect();
+            Ty::Struct {
+                name: *name,
+                parameters,
+            }
+        }
+        Ty::I32 | Ty::Unit | Ty::Infer => ty.clone(),
+    }
+}
+
+// The type of an operand, e.g. an argument of a call, which is not itself a call.
+fn operand_ty(program: &Program, operand: &Expr) -> Option<Ty> {
+    match operand {
+        Expr::Access { kind, place } => {
+            let ty = Box::new(walk_place_tys(program, place, |_| ()).clone());
+            Some(match kind {
+                AccessKind::Borrow(origin) => Ty::Ref {
+                    origin: *origin,
+                    ty,
+                },
+                AccessKind::BorrowMut(origin) => Ty::RefMut {
+                    origin: *origin,
+                    ty,
+                },
+                AccessKind::Copy | AccessKind::Move | AccessKind::Infer => *ty,
+            })
+        }
+        Expr::Number { .. } => Some(Ty::I32),
+        Expr::Cast { ty, .. } => Some(ty.clone()),
+        Expr::Unit => Some(Ty::Unit),
+        Expr::Call { .. } => None,
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum Variance {
     Covariant,
@@ -741,6 +2442,10 @@ impl Ty {
                 return ty.visit_origins(visitor);
             }
 
+            Ty::RawPtr { ty } | Ty::RawPtrMut { ty } | Ty::Array { ty, .. } => {
+                return ty.visit_origins(visitor);
+            }
+
             Ty::Struct { parameters, .. } => {
                 for param in parameters {
                     match param {
@@ -758,13 +2463,39 @@ impl Ty {
 
             Ty::I32 => {}
             Ty::Unit => {}
+            Ty::Infer => {}
         }
 
         None
     }
 
+    // Calls `f` with each of the origins present in this type, recursively.
+    pub(crate) fn visit_origins_mut(&mut self, f: &mut impl FnMut(&mut Name)) {
+        match self {
+            Ty::Ref { origin, ty } | Ty::RefMut { origin, ty } => {
+                f(origin);
+                ty.visit_origins_mut(f);
+            }
+
+            Ty::RawPtr { ty } | Ty::RawPtrMut { ty } | Ty::Array { ty, .. } => {
+                ty.visit_origins_mut(f)
+            }
+
+            Ty::Struct { parameters, .. } => {
+                for param in parameters {
+                    match param {
+                        Parameter::Origin(origin) => f(origin),
+                        Parameter::Ty(ty) => ty.visit_origins_mut(f),
+                    }
+                }
+            }
+
+            Ty::I32 | Ty::Unit | Ty::Infer => {}
+        }
+    }
+
     // Collects all the origins present in this type, recursively.
-    fn collect_origins_into(&self, origins: &mut Vec<Origin>) {
+    pub(crate) fn collect_origins_into(&self, origins: &mut Vec<Origin>) {
         struct OriginCollector<'a> {
             origins: &'a mut Vec<Origin>,
         }
@@ -822,6 +2553,13 @@ impl fmt::Display for Facts {
                 .push(format!("clear_origin({})", origin.0));
         }
 
+        for (origin, loan, node) in &self.loan_issued_at {
+            facts_per_node
+                .entry(&node.0)
+                .or_default()
+                .push(format!("loan_issued_at({}, {})", origin.0, loan.0));
+        }
+
         for (origin1, origin2, node) in &self.introduce_subset {
             facts_per_node
                 .entry(&node.0)
@@ -829,25 +2567,56 @@ impl fmt::Display for Facts {
                 .push(format!("introduce_subset({}, {})", origin1.0, origin2.0));
         }
 
-        // Display the indexed data in the frontend format
+        // Display the global facts first, then the indexed data in the frontend format
+        let mut global_facts = Vec::new();
+        for origin in &self.mark_as_loan_origin {
+            global_facts.push(format!("mark_as_loan_origin({})", origin.0));
+        }
+        for origin in &self.placeholder_origin {
+            global_facts.push(format!("placeholder_origin({})", origin.0));
+        }
+        for (origin1, origin2) in &self.known_placeholder_subset {
+            global_facts.push(format!(
+                "known_placeholder_subset({}, {})",
+                origin1.0, origin2.0
+            ));
+        }
+        for fact in &global_facts {
+            writeln!(f, "{}", fact)?;
+        }
+        if !global_facts.is_empty() && !facts_per_node.is_empty() {
+            writeln!(f)?;
+        }
+
+        // Index the text and successors of each node, instead of looking them up for each node.
+        // The first text of a node is its text, like the successors are in the CFG order.
+        let mut node_texts: BTreeMap<&str, &str> = BTreeMap::new();
+        for (text, node) in &self.node_text {
+            node_texts.entry(&node.0).or_insert(text);
+        }
+        let mut successors: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (from, to) in &self.cfg_edge {
+            successors.entry(&from.0).or_default().push(&to.0);
+        }
+        let source_locations: BTreeMap<&str, &str> = self
+            .source_location
+            .iter()
+            .map(|(node, location)| (node.0.as_ref(), location.as_str()))
+            .collect();
+
         for (node_idx, (node, facts)) in facts_per_node.into_iter().enumerate() {
             if node_idx != 0 {
                 write!(f, "\n")?;
             }
 
-            // Emit node start, with the statement's `node_text` representation
-            let node_text = self
-                .node_text
-                .iter()
-                .find_map(|(node_text, candidate_node)| {
-                    if candidate_node.0 == node {
-                        Some(node_text.as_ref())
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or("(pass)");
-            writeln!(f, "{}: {:?} {{", node, node_text)?;
+            // Emit node start, with the statement's `node_text` representation,
+            // followed by its location in the Rust source, if it has one
+            let node_text = node_texts.get(node).copied().unwrap_or("(pass)");
+            write!(f, "{}: {:?} ", node, node_text)?;
+            if let Some(location) = source_locations.get(node) {
+                write!(f, "@ {} ", location)?;
+            }
+            writeln!(f, "{{")?;
 
             // Emit all facts first
             for fact in facts {
@@ -857,8 +2626,8 @@ impl fmt::Display for Facts {
             // And `goto` facts last, with their special syntax. A `goto` is always required,
             // even for the function's exit node (but will have no successors in that case).
             write!(f, "\tgoto")?;
-            for (_, succ) in self.cfg_edge.iter().filter(|(from, _)| from.0 == node) {
-                write!(f, " {}", succ.0)?;
+            for succ in successors.get(node).into_iter().flatten() {
+                write!(f, " {}", succ)?;
             }
 
             writeln!(f, "\n}}")?;
@@ -19,19 +19,21 @@ fn canonical_liveness() {
         }
     ";
     assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_p)
+    mark_as_loan_origin('L_q)
+
     a: "p = 22" {
-    	invalidate_origin('L_p)
     	goto b
     }
 
     b: "q = 44" {
-    	invalidate_origin('L_q)
     	goto c
     }
 
     c: "x = &'L_p p" {
     	clear_origin('x)
     	clear_origin('L_p)
+    	loan_issued_at('L_p, bw0)
     	introduce_subset('L_p, 'x)
     	goto d
     }
@@ -39,6 +41,7 @@ fn canonical_liveness() {
     d: "x = &'L_q q" {
     	clear_origin('x)
     	clear_origin('L_q)
+    	loan_issued_at('L_q, bw1)
     	introduce_subset('L_q, 'x)
     	goto e
     }
@@ -70,14 +73,16 @@ fn canonical_liveness_err() {
         }
     ";
     assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_p)
+
     a: "p = 22" {
-    	invalidate_origin('L_p)
     	goto b
     }
 
     b: "x = &'L_p p" {
     	clear_origin('x)
     	clear_origin('L_p)
+    	loan_issued_at('L_p, bw0)
     	introduce_subset('L_p, 'x)
     	goto c
     }
@@ -41,10 +41,14 @@ fn issue_47680() {
     // - node d: missing clear origin of a loan of the deref
 
     assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_Thing)
+    mark_as_loan_origin('L_*temp)
+
     a: "temp = &'L_Thing mut thing" {
     	invalidate_origin('L_Thing)
     	clear_origin('temp)
     	clear_origin('L_Thing)
+    	loan_issued_at('L_Thing, bw0)
     	introduce_subset('L_Thing, 'temp)
     	goto b
     }
@@ -54,6 +58,7 @@ fn issue_47680() {
     	invalidate_origin('L_*temp)
     	clear_origin('t0)
     	clear_origin('L_*temp)
+    	loan_issued_at('L_*temp, bw1)
     	introduce_subset('L_*temp, 't0)
     	goto c
     }
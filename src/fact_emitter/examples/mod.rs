@@ -23,14 +23,16 @@ fn example_a() {
     ";
 
     assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_x)
+
     a: "x = 3" {
-    	invalidate_origin('L_x)
     	goto b
     }
 
     b: "y = &'L_x x" {
     	clear_origin('y)
     	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
     	introduce_subset('L_x, 'y)
     	goto c
     }
@@ -30,13 +30,14 @@ fn vec_temp() {
     // - node e: missing subset between the call's arguments, the fn signatures lack lifetime bounds
 
     assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_x)
+    mark_as_loan_origin('L_v)
+
     a: "x = 22" {
-    	invalidate_origin('L_x)
     	goto b
     }
 
     b: "v = Vec_new()" {
-    	invalidate_origin('L_v)
     	clear_origin('v)
     	goto c
     }
@@ -44,6 +45,7 @@ fn vec_temp() {
     c: "p = &'L_x x" {
     	clear_origin('p)
     	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
     	introduce_subset('L_x, 'p)
     	goto d
     }
@@ -54,6 +56,7 @@ fn vec_temp() {
     	clear_origin('tmp0)
     	clear_origin('tmp1)
     	clear_origin('L_v)
+    	loan_issued_at('L_v, bw1)
     	introduce_subset('L_v, 'tmp0)
     	introduce_subset('v, 'tmp1)
     	introduce_subset('tmp1, 'v)
@@ -74,6 +77,7 @@ fn vec_temp() {
 
     g: "Vec_len(move v)" {
     	access_origin('v)
+    	invalidate_origin('L_v)
     	goto
     }
     "###);
@@ -0,0 +1,162 @@
+//! Filtering of emitted facts, to narrow large outputs down to the facts relevant to a specific
+//! investigation.
+
+use super::{Facts, Node, Origin, Provenance, Relation};
+use std::collections::BTreeMap;
+
+/// Criteria selecting a subset of the emitted facts.
+///
+/// Criteria of the same kind are alternatives (e.g. facts about either of two origins), while
+/// criteria of different kinds must all match (e.g. facts about an origin, at a given node).
+/// An empty filter selects all the facts.
+#[derive(Clone, Debug, Default)]
+pub struct FactFilter {
+    pub relations: Vec<Relation>,
+    pub origins: Vec<Origin>,
+    pub nodes: Vec<Node>,
+}
+
+impl FactFilter {
+    /// Adds a criterion in its textual form: `relation=<name>`, `origin=<origin>`, or
+    /// `node=<node>`, e.g. `origin='a`.
+    pub fn add_criterion(&mut self, criterion: &str) -> eyre::Result<()> {
+        let (kind, value) = criterion.split_once('=').ok_or_else(|| {
+            eyre::eyre!(
+                "invalid filter `{}`, expected `relation=..`, `origin=..` or `node=..`",
+                criterion
+            )
+        })?;
+        match kind {
+            "relation" => {
+                let relation = Relation::from_name(value)
+                    .ok_or_else(|| eyre::eyre!("unknown relation `{}`", value))?;
+                self.relations.push(relation);
+            }
+            "origin" => self.origins.push(value.into()),
+            "node" => self.nodes.push(value.into()),
+            _ => eyre::bail!(
+                "unknown filter kind `{}`, expected `relation`, `origin` or `node`",
+                kind
+            ),
+        }
+        Ok(())
+    }
+
+    fn matches_relation(&self, relation: Relation) -> bool {
+        self.relations.is_empty() || self.relations.contains(&relation)
+    }
+
+    fn matches_node(&self, node: &Node) -> bool {
+        self.nodes.is_empty() || self.nodes.contains(node)
+    }
+
+    fn matches_origins(&self, origins: &[&Origin]) -> bool {
+        self.origins.is_empty() || origins.iter().any(|&origin| self.origins.contains(origin))
+    }
+}
+
+impl Facts {
+    /// Returns the facts selected by the given filter.
+    ///
+    /// The CFG, the node texts, spans and source locations, the names of anonymous origins, the places of loans,
+    /// and the global facts, are the structure the other facts are displayed against: the relation
+    /// and origin criteria don't apply to them, and the node criteria keep the edges leaving the selected nodes.
+    pub fn filter(&self, filter: &FactFilter) -> Facts {
+        let mut filtered = Facts {
+            cfg_edge: self
+                .cfg_edge
+                .iter()
+                .filter(|(from, _)| filter.matches_node(from))
+                .cloned()
+                .collect(),
+            cfg_edge_kind: self
+                .cfg_edge_kind
+                .iter()
+                .filter(|(from, ..)| filter.matches_node(from))
+                .cloned()
+                .collect(),
+            node_text: self.node_text.clone(),
+            node_span: self.node_span.clone(),
+            node_location: self.node_location.clone(),
+            source_location: self.source_location.clone(),
+            anonymous_origin: self.anonymous_origin.clone(),
+            loan_place: self.loan_place.clone(),
+            place_assigned_at: self.place_assigned_at.clone(),
+            mark_as_loan_origin: self.mark_as_loan_origin.clone(),
+            placeholder_origin: self.placeholder_origin.clone(),
+            known_placeholder_subset: self.known_placeholder_subset.clone(),
+            ..Facts::default()
+        };
+
+        let keep_origin_fact = |relation, (origin, node): &(Origin, Node)| {
+            filter.matches_relation(relation)
+                && filter.matches_origins(&[origin])
+                && filter.matches_node(node)
+        };
+
+        filtered.access_origin = self.retain_into(
+            &mut filtered.provenance,
+            Relation::AccessOrigin,
+            &self.access_origin,
+            |f| keep_origin_fact(Relation::AccessOrigin, f),
+        );
+        filtered.clear_origin = self.retain_into(
+            &mut filtered.provenance,
+            Relation::ClearOrigin,
+            &self.clear_origin,
+            |f| keep_origin_fact(Relation::ClearOrigin, f),
+        );
+        filtered.invalidate_origin = self.retain_into(
+            &mut filtered.provenance,
+            Relation::InvalidateOrigin,
+            &self.invalidate_origin,
+            |f| keep_origin_fact(Relation::InvalidateOrigin, f),
+        );
+        filtered.loan_issued_at = self.retain_into(
+            &mut filtered.provenance,
+            Relation::LoanIssuedAt,
+            &self.loan_issued_at,
+            |(origin, _, node)| {
+                filter.matches_relation(Relation::LoanIssuedAt)
+                    && filter.matches_origins(&[origin])
+                    && filter.matches_node(node)
+            },
+        );
+        filtered.introduce_subset = self.retain_into(
+            &mut filtered.provenance,
+            Relation::IntroduceSubset,
+            &self.introduce_subset,
+            |(origin1, origin2, node)| {
+                filter.matches_relation(Relation::IntroduceSubset)
+                    && filter.matches_origins(&[origin1, origin2])
+                    && filter.matches_node(node)
+            },
+        );
+
+        filtered
+    }
+
+    // Returns the facts of a relation matching the given predicate, and records their
+    // provenance into `provenance`.
+    fn retain_into<T: Clone>(
+        &self,
+        provenance: &mut BTreeMap<Relation, Vec<Provenance>>,
+        relation: Relation,
+        facts: &[T],
+        mut keep: impl FnMut(&T) -> bool,
+    ) -> Vec<T> {
+        let mut kept = Vec::new();
+        for (idx, fact) in facts.iter().enumerate() {
+            if keep(fact) {
+                kept.push(fact.clone());
+                if let Some(fact_provenance) = self.provenance(relation, idx) {
+                    provenance
+                        .entry(relation)
+                        .or_default()
+                        .push(fact_provenance.clone());
+                }
+            }
+        }
+        kept
+    }
+}
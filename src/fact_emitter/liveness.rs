@@ -0,0 +1,128 @@
+//! The liveness of origins, computed over the emitted facts like `origin_live_on_entry` in the
+//! rules, to prune the invalidations of dead loans.
+//!
+//! Invalidating a loan can only lead to an error if an origin it may flow into is accessed
+//! afterwards, before being cleared: that is, if one of these origins is live on exit from the
+//! invalidating node. The origins a loan may flow into are approximated by the subsets
+//! introduced anywhere in the program, so the invalidations which are kept are a superset of
+//! the ones leading to errors.
+
+use super::{Facts, Node, Origin, Relation};
+use std::collections::{HashMap, HashSet};
+
+impl Facts {
+    /// The origins live on entry to each node: the ones accessed at the node, or live on entry to
+    /// one of its successors and not cleared at the node.
+    pub(crate) fn origin_live_on_entry(&self) -> HashMap<&Node, HashSet<&Origin>> {
+        let mut predecessors: HashMap<&Node, Vec<&Node>> = HashMap::new();
+        for (from, to) in &self.cfg_edge {
+            predecessors.entry(to).or_default().push(from);
+        }
+        let cleared: HashSet<(&Origin, &Node)> =
+            self.clear_origin.iter().map(|(o, n)| (o, n)).collect();
+
+        let mut live: HashMap<&Node, HashSet<&Origin>> = HashMap::new();
+        let mut worklist = Vec::new();
+        for (origin, node) in &self.access_origin {
+            if live.entry(node).or_default().insert(origin) {
+                worklist.push((origin, node));
+            }
+        }
+        while let Some((origin, node)) = worklist.pop() {
+            for &predecessor in predecessors.get(node).into_iter().flatten() {
+                if cleared.contains(&(origin, predecessor)) {
+                    continue;
+                }
+                if live.entry(predecessor).or_default().insert(origin) {
+                    worklist.push((origin, predecessor));
+                }
+            }
+        }
+        live
+    }
+
+    /// Returns whether a loan can flow into an origin live on exit from a node: when it's
+    /// invalidated there, the invalidation can lead to an error, and when it's issued there, the
+    /// borrow is used. Placeholders are live everywhere, as they outlive the function: so are the
+    /// loans flowing into them.
+    pub(crate) fn loan_live_on_exit(&self) -> impl Fn(&Origin, &Node) -> bool + '_ {
+        let live = self.origin_live_on_entry();
+        let mut successors: HashMap<&Node, Vec<&Node>> = HashMap::new();
+        for (from, to) in &self.cfg_edge {
+            successors.entry(from).or_default().push(to);
+        }
+        let subsets = self.subsets();
+        let mut flows_into: HashMap<&Origin, HashSet<&Origin>> = HashMap::new();
+        let loans = self.invalidate_origin.iter().map(|(loan, _)| loan);
+        for loan in loans.chain(self.loan_issued_at.iter().map(|(loan, ..)| loan)) {
+            flows_into
+                .entry(loan)
+                .or_insert_with(|| origins_flowed_into(&subsets, loan));
+        }
+
+        let placeholders: HashSet<&Origin> = self.placeholder_origin.iter().collect();
+        let escaping_loans: HashSet<&Origin> = flows_into
+            .iter()
+            .filter(|(_, origins)| origins.iter().any(|origin| placeholders.contains(origin)))
+            .map(|(&loan, _)| loan)
+            .collect();
+
+        move |loan, node| {
+            escaping_loans.contains(loan)
+                || successors
+                    .get(node)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|successor| live.get(successor))
+                    .any(|live| flows_into[loan].iter().any(|origin| live.contains(origin)))
+        }
+    }
+
+    /// Removes the invalidations of the loans which can't flow into an origin live on exit
+    /// from the invalidating node.
+    pub(crate) fn prune_dead_loans(&mut self) {
+        let kept: Vec<bool> = {
+            let is_live = self.loan_live_on_exit();
+            self.invalidate_origin
+                .iter()
+                .map(|(loan, node)| is_live(loan, node))
+                .collect()
+        };
+
+        retain_kept(&mut self.invalidate_origin, &kept);
+        if let Some(provenances) = self.provenance.get_mut(&Relation::InvalidateOrigin) {
+            retain_kept(provenances, &kept);
+        }
+    }
+
+    /// The origins each origin flows into directly, through the subsets introduced anywhere in
+    /// the program.
+    pub(super) fn subsets(&self) -> HashMap<&Origin, Vec<&Origin>> {
+        let mut subsets: HashMap<&Origin, Vec<&Origin>> = HashMap::new();
+        for (origin1, origin2, _) in &self.introduce_subset {
+            subsets.entry(origin1).or_default().push(origin2);
+        }
+        subsets
+    }
+}
+
+/// The origins a loan may flow into along the subsets, including its own.
+pub(super) fn origins_flowed_into<'a>(
+    subsets: &HashMap<&'a Origin, Vec<&'a Origin>>,
+    loan: &'a Origin,
+) -> HashSet<&'a Origin> {
+    let mut origins = HashSet::new();
+    let mut stack = vec![loan];
+    while let Some(origin) = stack.pop() {
+        if origins.insert(origin) {
+            stack.extend(subsets.get(origin).into_iter().flatten());
+        }
+    }
+    origins
+}
+
+// Keeps the elements whose flag, at the same index, is set.
+fn retain_kept<T>(elements: &mut Vec<T>, kept: &[bool]) {
+    let mut flags = kept.iter();
+    elements.retain(|_| flags.next().copied().unwrap_or(true));
+}
@@ -0,0 +1,190 @@
+//! Scoping of the facts of a function by its name, so that the facts of several functions can
+//! be merged in one output: the names of the nodes, origins, loans, variables and move paths
+//! of a function are prefixed with its name, e.g. `main::bb0[1]` and `main::'x`.
+
+use super::{Facts, Loan, Node, Origin, Relation};
+use crate::ast::Name;
+
+impl Facts {
+    /// The facts, with the names of their nodes, origins, loans, variables and move paths
+    /// scoped by the name of the function they're the facts of. The places of the loans are
+    /// kept as written, they're what the loans are reported by.
+    pub fn namespaced(&self, function: &str) -> Facts {
+        let node = |node: &Node| Node::from(format!("{}::{}", function, node.0));
+        let origin = |origin: &Origin| Origin::from(format!("{}::{}", function, origin.0));
+        let loan = |loan: &Loan| Loan::from(format!("{}::{}", function, loan.0));
+        let name = |name: &Name| Name::intern(&format!("{}::{}", function, name));
+        let path = |path: &String| format!("{}::{}", function, path);
+
+        Facts {
+            access_origin: self
+                .access_origin
+                .iter()
+                .map(|(o, n)| (origin(o), node(n)))
+                .collect(),
+            cfg_edge: self
+                .cfg_edge
+                .iter()
+                .map(|(n1, n2)| (node(n1), node(n2)))
+                .collect(),
+            cfg_edge_kind: self
+                .cfg_edge_kind
+                .iter()
+                .map(|(n1, n2, kind)| (node(n1), node(n2), *kind))
+                .collect(),
+            clear_origin: self
+                .clear_origin
+                .iter()
+                .map(|(o, n)| (origin(o), node(n)))
+                .collect(),
+            introduce_subset: self
+                .introduce_subset
+                .iter()
+                .map(|(o1, o2, n)| (origin(o1), origin(o2), node(n)))
+                .collect(),
+            invalidate_origin: self
+                .invalidate_origin
+                .iter()
+                .map(|(o, n)| (origin(o), node(n)))
+                .collect(),
+            node_text: self
+                .node_text
+                .iter()
+                .map(|(text, n)| (text.clone(), node(n)))
+                .collect(),
+            node_span: self
+                .node_span
+                .iter()
+                .map(|(n, start, end)| (node(n), *start, *end))
+                .collect(),
+            node_location: self
+                .node_location
+                .iter()
+                .map(|(n, line, column)| (node(n), *line, *column))
+                .collect(),
+            source_location: self
+                .source_location
+                .iter()
+                .map(|(n, location)| (node(n), location.clone()))
+                .collect(),
+            mark_as_loan_origin: self.mark_as_loan_origin.iter().map(origin).collect(),
+            placeholder_origin: self.placeholder_origin.iter().map(origin).collect(),
+            known_placeholder_subset: self
+                .known_placeholder_subset
+                .iter()
+                .map(|(o1, o2)| (origin(o1), origin(o2)))
+                .collect(),
+            anonymous_origin: self
+                .anonymous_origin
+                .iter()
+                .map(|(o, expr, n)| (origin(o), expr.clone(), node(n)))
+                .collect(),
+            loan_issued_at: self
+                .loan_issued_at
+                .iter()
+                .map(|(o, l, n)| (origin(o), loan(l), node(n)))
+                .collect(),
+            loan_place: self
+                .loan_place
+                .iter()
+                .map(|(l, kind, place)| (loan(l), *kind, place.clone()))
+                .collect(),
+            place_assigned_at: self
+                .place_assigned_at
+                .iter()
+                .map(|(place, n)| (place.clone(), node(n)))
+                .collect(),
+            path_moved_at: self
+                .path_moved_at
+                .iter()
+                .map(|(p, n)| (path(p), node(n)))
+                .collect(),
+            path_assigned_at: self
+                .path_assigned_at
+                .iter()
+                .map(|(p, n)| (path(p), node(n)))
+                .collect(),
+            path_accessed_at: self
+                .path_accessed_at
+                .iter()
+                .map(|(p, n)| (path(p), node(n)))
+                .collect(),
+            var_used_at: self
+                .var_used_at
+                .iter()
+                .map(|(v, n)| (name(v), node(n)))
+                .collect(),
+            var_defined_at: self
+                .var_defined_at
+                .iter()
+                .map(|(v, n)| (name(v), node(n)))
+                .collect(),
+            var_dropped_at: self
+                .var_dropped_at
+                .iter()
+                .map(|(v, n)| (name(v), node(n)))
+                .collect(),
+            use_of_var_derefs_origin: self
+                .use_of_var_derefs_origin
+                .iter()
+                .map(|(v, o)| (name(v), origin(o)))
+                .collect(),
+            drop_of_var_derefs_origin: self
+                .drop_of_var_derefs_origin
+                .iter()
+                .map(|(v, o)| (name(v), origin(o)))
+                .collect(),
+            provenance: self.provenance.clone(),
+        }
+    }
+
+    /// Appends the facts of another function, e.g. scoped with `namespaced` so that their names
+    /// don't collide with these facts'.
+    pub fn append(&mut self, mut other: Facts) {
+        // The provenances are at the same index as their facts: they're kept when both facts
+        // have them all
+        for &relation in Relation::ALL {
+            let is_attributed = |facts: &Facts| {
+                facts.provenance.get(&relation).map_or(0, Vec::len) == facts.relation_len(relation)
+            };
+            if is_attributed(self) && is_attributed(&other) {
+                let provenances = other.provenance.remove(&relation).unwrap_or_default();
+                self.provenance
+                    .entry(relation)
+                    .or_default()
+                    .extend(provenances);
+            } else {
+                self.provenance.remove(&relation);
+            }
+        }
+
+        self.access_origin.extend(other.access_origin);
+        self.cfg_edge.extend(other.cfg_edge);
+        self.cfg_edge_kind.extend(other.cfg_edge_kind);
+        self.clear_origin.extend(other.clear_origin);
+        self.introduce_subset.extend(other.introduce_subset);
+        self.invalidate_origin.extend(other.invalidate_origin);
+        self.node_text.extend(other.node_text);
+        self.node_span.extend(other.node_span);
+        self.node_location.extend(other.node_location);
+        self.source_location.extend(other.source_location);
+        self.mark_as_loan_origin.extend(other.mark_as_loan_origin);
+        self.placeholder_origin.extend(other.placeholder_origin);
+        self.known_placeholder_subset
+            .extend(other.known_placeholder_subset);
+        self.anonymous_origin.extend(other.anonymous_origin);
+        self.loan_issued_at.extend(other.loan_issued_at);
+        self.loan_place.extend(other.loan_place);
+        self.place_assigned_at.extend(other.place_assigned_at);
+        self.path_moved_at.extend(other.path_moved_at);
+        self.path_assigned_at.extend(other.path_assigned_at);
+        self.path_accessed_at.extend(other.path_accessed_at);
+        self.var_used_at.extend(other.var_used_at);
+        self.var_defined_at.extend(other.var_defined_at);
+        self.var_dropped_at.extend(other.var_dropped_at);
+        self.use_of_var_derefs_origin
+            .extend(other.use_of_var_derefs_origin);
+        self.drop_of_var_derefs_origin
+            .extend(other.drop_of_var_derefs_origin);
+    }
+}
@@ -0,0 +1,321 @@
+//! Slicing of emitted facts down to the ones relevant to a single loan, or a single error, to
+//! review the minimal set of facts involved in a rule interaction.
+//!
+//! The slice of a loan is the part of the CFG where it can matter: the nodes reachable from
+//! where it's issued, from which a node accessing an origin it may flow into, or invalidating
+//! it, can be reached. Its origins are the loan's origin and the origins it may flow into,
+//! through the subsets introduced anywhere in the program. The slice of an error is the union of
+//! the slices of the loans invalidated in the origins accessed at its node, with the CFG
+//! restricted to the nodes reaching it.
+//!
+//! The slice of a variable follows the data flow of the variable: its nodes are the ones using,
+//! defining, or dropping it or the places derived from it, borrowing it, and the ones where the
+//! origins of its type, and of its loans, or the origins these may flow into, are accessed or
+//! flow elsewhere. As these nodes needn't be contiguous, the CFG is contracted to the paths
+//! between them.
+
+use super::liveness::origins_flowed_into;
+use super::{FactFilter, Facts, Name, Node, Origin};
+use crate::solver::solve;
+use std::collections::{HashMap, HashSet};
+
+/// What to slice the facts down to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FactSlice {
+    /// The loan with the given origin.
+    Loan(Origin),
+
+    /// The errors reported at the given node.
+    Error(Node),
+
+    /// The variable with the given name.
+    Variable(Name),
+}
+
+impl std::str::FromStr for FactSlice {
+    type Err = eyre::Report;
+
+    /// Parses a slice in its textual form: `loan=<origin>`, `error=<node>`, or
+    /// `variable=<name>`.
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s.split_once('=') {
+            Some(("loan", origin)) => Ok(Self::Loan(origin.into())),
+            Some(("error", node)) => Ok(Self::Error(node.into())),
+            Some(("variable", name)) => Ok(Self::Variable(name.into())),
+            _ => eyre::bail!(
+                "invalid slice `{}`, expected `loan=<origin>`, `error=<node>` or `variable=<name>`",
+                s
+            ),
+        }
+    }
+}
+
+impl Facts {
+    /// Returns the facts relevant to the loan, error, or variable: the facts of its origins, at
+    /// the nodes of its part of the CFG, which are the only nodes left.
+    pub fn slice(&self, slice: &FactSlice) -> eyre::Result<Facts> {
+        let (origins, nodes) = match slice {
+            FactSlice::Loan(loan) => self.loan_slice(loan, None)?,
+            FactSlice::Error(node) => {
+                let output = solve(self);
+                let accessed: HashSet<_> = output
+                    .invalidated_origin_accessed
+                    .iter()
+                    .filter(|(_, n)| n == node)
+                    .map(|(origin, _)| origin)
+                    .collect();
+                let subsets = self.subsets();
+                let loans: Vec<_> = self
+                    .invalidate_origin
+                    .iter()
+                    .map(|(loan, _)| loan)
+                    .filter(|loan| {
+                        origins_flowed_into(&subsets, loan)
+                            .iter()
+                            .any(|origin| accessed.contains(origin))
+                    })
+                    .collect();
+                if loans.is_empty() {
+                    eyre::bail!("no error is reported at node `{}`", node.0);
+                }
+                let mut origins = HashSet::new();
+                let mut nodes = HashSet::new();
+                for loan in loans {
+                    let (loan_origins, loan_nodes) = self.loan_slice(loan, Some(node))?;
+                    origins.extend(loan_origins);
+                    nodes.extend(loan_nodes);
+                }
+                (origins, nodes)
+            }
+            FactSlice::Variable(name) => self.variable_slice(name)?,
+        };
+
+        let filter = FactFilter {
+            origins: origins.iter().cloned().collect(),
+            nodes: nodes.iter().cloned().collect(),
+            ..FactFilter::default()
+        };
+        let mut sliced = self.filter(&filter);
+        sliced.cfg_edge = self.contracted_cfg(&nodes);
+
+        // The edges contracted through other nodes have no kind: their paths can go through
+        // edges of different kinds
+        let cfg_edge = &sliced.cfg_edge;
+        sliced
+            .cfg_edge_kind
+            .retain(|(from, to, _)| cfg_edge.contains(&(from.clone(), to.clone())));
+        sliced
+            .mark_as_loan_origin
+            .retain(|origin| origins.contains(origin));
+        sliced.node_text.retain(|(_, node)| nodes.contains(node));
+        sliced.node_span.retain(|(node, ..)| nodes.contains(node));
+        sliced
+            .node_location
+            .retain(|(node, ..)| nodes.contains(node));
+        sliced
+            .source_location
+            .retain(|(node, _)| nodes.contains(node));
+        sliced
+            .place_assigned_at
+            .retain(|(_, node)| nodes.contains(node));
+
+        // The facts about the variable and its places, which no other slice keeps
+        if let FactSlice::Variable(name) = slice {
+            let of_variable = |var: &Name, node: &Node| var == name && nodes.contains(node);
+            let of_path =
+                |path: &String, node: &Node| is_rooted_at(path, name) && of_variable(name, node);
+            sliced.var_used_at = kept(&self.var_used_at, |(v, n)| of_variable(v, n));
+            sliced.var_defined_at = kept(&self.var_defined_at, |(v, n)| of_variable(v, n));
+            sliced.var_dropped_at = kept(&self.var_dropped_at, |(v, n)| of_variable(v, n));
+            sliced.path_moved_at = kept(&self.path_moved_at, |(p, n)| of_path(p, n));
+            sliced.path_assigned_at = kept(&self.path_assigned_at, |(p, n)| of_path(p, n));
+            sliced.path_accessed_at = kept(&self.path_accessed_at, |(p, n)| of_path(p, n));
+            sliced.use_of_var_derefs_origin =
+                kept(&self.use_of_var_derefs_origin, |(v, _)| v == name);
+            sliced.drop_of_var_derefs_origin =
+                kept(&self.drop_of_var_derefs_origin, |(v, _)| v == name);
+        }
+        Ok(sliced)
+    }
+
+    // The origins and nodes of the slice of a variable.
+    fn variable_slice(&self, name: &Name) -> eyre::Result<(HashSet<Origin>, HashSet<Node>)> {
+        let mut nodes: HashSet<&Node> = self
+            .var_used_at
+            .iter()
+            .chain(&self.var_defined_at)
+            .chain(&self.var_dropped_at)
+            .filter(|(var, _)| var == name)
+            .map(|(_, node)| node)
+            .chain(
+                self.path_moved_at
+                    .iter()
+                    .chain(&self.path_assigned_at)
+                    .chain(&self.path_accessed_at)
+                    .filter(|(path, _)| is_rooted_at(path, name))
+                    .map(|(_, node)| node),
+            )
+            .collect();
+        if nodes.is_empty() {
+            eyre::bail!("the variable `{}` is never used", name);
+        }
+
+        // The origins of the variable's type, and of the loans of its places
+        let borrowed: HashSet<_> = self
+            .loan_place
+            .iter()
+            .filter(|(_, _, place)| is_rooted_at(place, name))
+            .map(|(loan, ..)| loan)
+            .collect();
+        let mut roots: Vec<&Origin> = self
+            .use_of_var_derefs_origin
+            .iter()
+            .chain(&self.drop_of_var_derefs_origin)
+            .filter(|(var, _)| var == name)
+            .map(|(_, origin)| origin)
+            .collect();
+        for (origin, loan, node) in &self.loan_issued_at {
+            if borrowed.contains(loan) {
+                roots.push(origin);
+                nodes.insert(node);
+            }
+        }
+        let subsets = self.subsets();
+        let origins: HashSet<&Origin> = roots
+            .into_iter()
+            .flat_map(|origin| origins_flowed_into(&subsets, origin))
+            .collect();
+
+        // Where these origins are accessed, invalidated, or flow into other origins
+        nodes.extend(
+            self.access_origin
+                .iter()
+                .chain(&self.invalidate_origin)
+                .filter(|(origin, _)| origins.contains(origin))
+                .map(|(_, node)| node),
+        );
+        nodes.extend(
+            self.introduce_subset
+                .iter()
+                .filter(|(origin, ..)| origins.contains(origin))
+                .map(|(.., node)| node),
+        );
+
+        Ok((
+            origins.into_iter().cloned().collect(),
+            nodes.into_iter().cloned().collect(),
+        ))
+    }
+
+    // The edges of the CFG contracted to the given nodes: an edge between two of them when the
+    // second can be reached from the first, through nodes which aren't part of the slice.
+    fn contracted_cfg(&self, nodes: &HashSet<Node>) -> Vec<(Node, Node)> {
+        let mut successors: HashMap<&Node, Vec<&Node>> = HashMap::new();
+        for (from, to) in &self.cfg_edge {
+            successors.entry(from).or_default().push(to);
+        }
+
+        let mut edges = Vec::new();
+        let mut contracted = HashSet::new();
+        for (from, _) in self
+            .cfg_edge
+            .iter()
+            .filter(|(from, _)| nodes.contains(from))
+        {
+            if !contracted.insert(from) {
+                continue;
+            }
+            let mut visited = HashSet::new();
+            let mut stack: Vec<&Node> = successors[from].iter().rev().copied().collect();
+            while let Some(node) = stack.pop() {
+                if !visited.insert(node) {
+                    continue;
+                }
+                if nodes.contains(node) {
+                    edges.push((from.clone(), node.clone()));
+                } else {
+                    stack.extend(successors.get(node).into_iter().flatten().rev());
+                }
+            }
+        }
+        edges
+    }
+
+    // The origins and nodes of the slice of a loan, optionally restricted to the nodes reaching
+    // the given target.
+    fn loan_slice(
+        &self,
+        loan: &Origin,
+        target: Option<&Node>,
+    ) -> eyre::Result<(HashSet<Origin>, HashSet<Node>)> {
+        let issued: Vec<_> = self
+            .loan_issued_at
+            .iter()
+            .filter(|(origin, ..)| origin == loan)
+            .map(|(.., node)| node)
+            .collect();
+        if issued.is_empty() {
+            eyre::bail!("no loan is issued with the origin `{}`", loan.0);
+        }
+        let origins = origins_flowed_into(&self.subsets(), loan);
+
+        let targets: Vec<_> = match target {
+            Some(target) => vec![target],
+            None => self
+                .access_origin
+                .iter()
+                .filter(|(origin, _)| origins.contains(origin))
+                .chain(
+                    self.invalidate_origin
+                        .iter()
+                        .filter(|(origin, _)| origin == loan),
+                )
+                .map(|(_, node)| node)
+                .chain(issued.iter().copied())
+                .collect(),
+        };
+
+        let mut successors: HashMap<&Node, Vec<&Node>> = HashMap::new();
+        let mut predecessors: HashMap<&Node, Vec<&Node>> = HashMap::new();
+        for (from, to) in &self.cfg_edge {
+            successors.entry(from).or_default().push(to);
+            predecessors.entry(to).or_default().push(from);
+        }
+        let reachable = reachable_from(issued, &successors);
+        let reaching = reachable_from(targets, &predecessors);
+        let nodes = reachable
+            .intersection(&reaching)
+            .map(|&node| node.clone())
+            .collect();
+        Ok((origins.into_iter().cloned().collect(), nodes))
+    }
+}
+
+// The facts matching the predicate.
+fn kept<T: Clone>(facts: &[T], keep: impl Fn(&T) -> bool) -> Vec<T> {
+    facts.iter().filter(|&fact| keep(fact)).cloned().collect()
+}
+
+// Whether the move path or place is the variable, or one of its places, like `x.f` or `*x`.
+fn is_rooted_at(place: &str, name: &Name) -> bool {
+    let place = place.trim_start_matches(['*', '(']);
+    match place.strip_prefix(name.as_ref() as &str) {
+        Some(rest) => !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'),
+        None => false,
+    }
+}
+
+// The nodes reachable from the given ones, themselves included, along the given edges.
+fn reachable_from<'a>(
+    nodes: Vec<&'a Node>,
+    edges: &HashMap<&'a Node, Vec<&'a Node>>,
+) -> HashSet<&'a Node> {
+    let mut reachable = HashSet::new();
+    let mut stack = nodes;
+    while let Some(node) = stack.pop() {
+        if reachable.insert(node) {
+            stack.extend(edges.get(node).into_iter().flatten().copied());
+        }
+    }
+    reachable
+}
@@ -1,33 +1,68 @@
 //! Tests dedicated to specific relations
 mod access_origin;
+mod anonymous_origin;
+mod call_arguments;
 mod cfg_edge;
 mod clear_origin;
+mod control_flow;
+mod dead_loans;
+mod elided_origins;
+mod filter;
+mod generic_calls;
+mod inferred_tys;
 mod introduce_subset;
 mod invalidate_origin;
+mod namespace;
+mod nested_exprs;
+mod node_location;
+mod node_names;
+mod node_span;
+mod placeholder_origin;
+mod provenance;
+mod raw_facts;
+mod raw_pointers;
+mod slice;
+mod source_location;
+mod statement_attributes;
+mod unreachable_blocks;
+mod var_liveness;
 
 use super::*;
-use crate::ast_parser::test::expect_parse;
 use crate::ast_parser as parse;
+use crate::ast_parser::test::expect_parse;
 use insta::assert_debug_snapshot;
 
+// The facts of the program, with the invalidations of dead loans kept: the tests of each
+// relation are about the facts a statement emits, not about the liveness of loans.
 pub(crate) fn expect_facts(input: &str) -> Facts {
-    let program = expect_parse(input);
-    let emitter = FactEmitter::new(program, input, true);
+    let options = EmitOptions {
+        keep_dead_loans: true,
+        ..EmitOptions::default()
+    };
+    expect_facts_with_options(input, &options)
+}
+
+fn expect_facts_with_options(input: &str, options: &EmitOptions) -> Facts {
+    let mut program = expect_parse(input);
+    if options.exclude_unreachable_blocks {
+        remove_unreachable_blocks(&mut program);
+    }
+    let emitter = FactEmitter::new(program, input, options.clone(), true).unwrap();
     let mut facts = Default::default();
-    emitter.emit_facts(&mut facts);
+    emitter.emit_facts(&mut facts).unwrap();
     facts
 }
 
 fn create_emitter(input: &str) -> FactEmitter {
     let program = expect_parse(input);
-    FactEmitter::new(program, input, true)
+    FactEmitter::new(program, input, EmitOptions::default(), true).unwrap()
 }
 
 // Returns the type of the given place's path in the given program.
 fn find_ty(program: &str, path: &str) -> Ty {
     let emitter = create_emitter(program);
     let place = parse::place(path).expect("Invalid place");
-    emitter.ty_of_place(&place).clone()
+    (*emitter.ty_of_place(&place)).clone()
 }
 
 // Returns the origins present in the type of the given place's path in the given program.
@@ -162,3 +197,75 @@ fn origins_in_ty() {
         [Origin::from("'f"), Origin::from("'e"), Origin::from("'d")]
     );
 }
+
+#[test]
+fn places_are_resolved_once() {
+    let program = program! {
+        struct Pair { first: i32, second: i32 }
+        let p: &'p Pair;
+    };
+    let emitter = FactEmitter::new(program, "", EmitOptions::default(), true).unwrap();
+    let place = parse::place("(*p).first").unwrap();
+    let (ty, origins) = emitter.ty_and_origins_of_place(&place);
+    assert_eq!(*ty, Ty::I32);
+    assert_eq!(origins, [Origin::from("'p")]);
+
+    // The second resolution is the cached one
+    assert!(Rc::ptr_eq(&ty, &emitter.ty_of_place(&place)));
+    assert_eq!(emitter.origins_of_place(&place), origins);
+}
+
+#[test]
+fn loans_of_borrows_sharing_an_origin() {
+    // Each borrow issues its own loan, even when it uses the origin of another borrow
+    let program = "
+        let x: i32;
+        let p: &'p i32;
+        let q: &'q mut i32;
+
+        bb0: {
+            p = &'L x;
+            p = &'L x;
+            q = &mut x;
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_debug_snapshot!((facts.loan_issued_at, facts.loan_place), @r###"
+    (
+        [
+            (
+                "'L",
+                "bw0",
+                "a",
+            ),
+            (
+                "'L",
+                "bw1",
+                "b",
+            ),
+            (
+                "'anon0",
+                "bw2",
+                "c",
+            ),
+        ],
+        [
+            (
+                "bw0",
+                Shared,
+                "x",
+            ),
+            (
+                "bw1",
+                Shared,
+                "x",
+            ),
+            (
+                "bw2",
+                Mutable,
+                "x",
+            ),
+        ],
+    )
+    "###);
+}
@@ -0,0 +1,117 @@
+use super::*;
+use insta::{assert_debug_snapshot, assert_display_snapshot};
+
+#[test]
+fn fresh_origins_for_anonymous_borrows() {
+    let program = "
+        let x: i32;
+        let v: Vec<&'v i32>;
+        let p: &'p i32;
+        let tmp: &'tmp0 mut Vec<&'tmp1 i32>;
+
+        bb0: {
+            p = &x;
+            tmp = &mut v;
+            Vec_push(move tmp, &x);
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_debug_snapshot!(facts.anonymous_origin, @r###"
+    [
+        (
+            "'anon0",
+            "&x",
+            "a",
+        ),
+        (
+            "'anon1",
+            "&mut v",
+            "b",
+        ),
+        (
+            "'anon2",
+            "&x",
+            "c",
+        ),
+    ]
+    "###);
+    assert_display_snapshot!(facts, @r###"
+    mark_as_loan_origin('anon0)
+    mark_as_loan_origin('anon1)
+    mark_as_loan_origin('anon2)
+
+    a: "p = &x" {
+    	clear_origin('p)
+    	clear_origin('anon0)
+    	loan_issued_at('anon0, bw0)
+    	introduce_subset('anon0, 'p)
+    	goto b
+    }
+
+    b: "tmp = &mut v" {
+    	access_origin('v)
+    	invalidate_origin('anon1)
+    	clear_origin('tmp0)
+    	clear_origin('tmp1)
+    	clear_origin('anon1)
+    	loan_issued_at('anon1, bw1)
+    	introduce_subset('anon1, 'tmp0)
+    	introduce_subset('v, 'tmp1)
+    	introduce_subset('tmp1, 'v)
+    	goto c
+    }
+
+    c: "&x" {
+    	clear_origin('_tmp0)
+    	clear_origin('anon2)
+    	loan_issued_at('anon2, bw2)
+    	introduce_subset('anon2, '_tmp0)
+    	goto d
+    }
+
+    d: "Vec_push(move tmp, &x)" {
+    	access_origin('tmp0)
+    	access_origin('tmp1)
+    	access_origin('_tmp0)
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn named_and_explicitly_anonymous_origins() {
+    let program = "
+        let x: i32;
+        let p: &'p i32;
+        let q: &'q i32;
+
+        bb0: {
+            p = &'L_x x;
+            q = &'_ x;
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_debug_snapshot!(facts.anonymous_origin, @r###"
+    [
+        (
+            "'anon0",
+            "&x",
+            "b",
+        ),
+    ]
+    "###);
+    assert_debug_snapshot!(facts.introduce_subset, @r###"
+    [
+        (
+            "'L_x",
+            "'p",
+            "a",
+        ),
+        (
+            "'anon0",
+            "'q",
+            "b",
+        ),
+    ]
+    "###);
+}
@@ -0,0 +1,49 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+#[test]
+fn inferred_access_kinds() {
+    // Arguments without an access kind are borrowed when the parameter is a reference, reborrowed
+    // when they're mutable references, and read otherwise: `x` is copied, and `v` is moved.
+    let program = "
+        struct Vec { len: i32 }
+        fn inspect(v: &Vec, x: i32) -> ();
+        fn modify(v: &mut Vec) -> ();
+        fn consume(v: Vec) -> ();
+
+        let v: Vec;
+        let r: &'r mut Vec;
+        let x: i32;
+
+        bb0: {
+            inspect(v, x);
+            modify(r);
+            consume(v);
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('anon0)
+    mark_as_loan_origin('anon1)
+
+    a: "inspect(v, x)" {
+    	clear_origin('anon0)
+    	loan_issued_at('anon0, bw0)
+    	introduce_subset('anon0, 'call0__0)
+    	goto b
+    }
+
+    b: "modify(r)" {
+    	access_origin('r)
+    	invalidate_origin('anon1)
+    	clear_origin('anon1)
+    	loan_issued_at('anon1, bw1)
+    	introduce_subset('anon1, 'call1__0)
+    	goto c
+    }
+
+    c: "consume(v)" {
+    	invalidate_origin('anon0)
+    	goto
+    }
+    "###);
+}
@@ -77,3 +77,41 @@ fn multiple_successor_blocks() {
     ]
     "###);
 }
+
+#[test]
+fn edge_kinds() {
+    let program = "
+        fn f() -> ();
+        let x: i32;
+        bb0: {
+            x = 1;
+            goto bb1;
+        }
+        bb1: {
+            f() -> [return: bb2, unwind: bb3];
+        }
+        bb2: {
+            goto bb1, bb4;
+        }
+        bb3: {
+            unreachable;
+        }
+        bb4: {
+            return;
+        }
+    ";
+    let kinds: Vec<_> = expect_facts(program)
+        .cfg_edge_kind
+        .iter()
+        .map(|(from, to, kind)| format!("{} -> {}: {}", from.0, to.0, kind.name()))
+        .collect();
+    assert_debug_snapshot!(kinds, @r###"
+    [
+        "a -> b: normal",
+        "b -> c: normal",
+        "b -> d: unwind",
+        "c -> b: back",
+        "c -> e: normal",
+    ]
+    "###);
+}
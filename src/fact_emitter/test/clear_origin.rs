@@ -116,3 +116,51 @@ fn mut_borrows_clear_their_origin() {
         Some(("'L_i".into(), "b".into()))
     );
 }
+
+#[test]
+fn end_and_kill_clear_origins() {
+    // `end` clears the origin, and `kill` the origins of the place's loans, at their node
+    let facts = expect_facts(
+        "
+        let x: i32;
+        let y: i32;
+        let r: &'r i32;
+        let s: &'s i32;
+
+        bb0: {
+            r = &'L_x x;
+            s = &'L_y y;
+            end('r);
+            kill(x);
+        }
+    ",
+    );
+    assert_debug_snapshot!(facts.clear_origin, @r###"
+    [
+        (
+            "'r",
+            "a",
+        ),
+        (
+            "'L_x",
+            "a",
+        ),
+        (
+            "'s",
+            "b",
+        ),
+        (
+            "'L_y",
+            "b",
+        ),
+        (
+            "'r",
+            "c",
+        ),
+        (
+            "'L_x",
+            "d",
+        ),
+    ]
+    "###);
+}
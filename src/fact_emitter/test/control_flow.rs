@@ -0,0 +1,239 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+#[test]
+fn loans_invalidated_in_loops() {
+    let program = "
+        let x: i32;
+        let p: &'p i32;
+
+        bb0: {
+            p = &'L_x x;
+            while copy x {
+                use(copy p);
+                if copy x {
+                    x = 1;
+                }
+            }
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "p = &'L_x x" {
+    	clear_origin('p)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'p)
+    	goto b
+    }
+
+    b: "copy x" {
+    	goto c e
+    }
+
+    c: "use(copy p)" {
+    	access_origin('p)
+    	goto d
+    }
+
+    d: "copy x" {
+    	goto f g
+    }
+
+    e: "(pass)" {
+    	goto
+    }
+
+    f: "x = 1" {
+    	invalidate_origin('L_x)
+    	goto g
+    }
+
+    g: "(pass)" {
+    	goto b
+    }
+    "###);
+}
+
+#[test]
+fn terminator_operands_are_read() {
+    let program = "
+        fn f<'a>(x: &'a i32) -> &'a i32;
+        let x: i32;
+        let p: &'p i32;
+        let q: &'q i32;
+
+        bb0: {
+            p = &'L_x x;
+            switch(copy p) -> [bb1, bb2];
+        }
+
+        bb1: {
+            q = f(copy p) -> [return: bb2, unwind: bb3];
+        }
+
+        bb2: {
+            return;
+        }
+
+        bb3: {
+            unreachable;
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "p = &'L_x x" {
+    	clear_origin('p)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'p)
+    	goto b
+    }
+
+    b: "copy p" {
+    	access_origin('p)
+    	goto c d
+    }
+
+    c: "q = f(copy p)" {
+    	access_origin('p)
+    	clear_origin('q)
+    	introduce_subset('p, 'call0_a)
+    	introduce_subset('call0_a, 'q)
+    	goto d e
+    }
+
+    d: "(pass)" {
+    	goto
+    }
+
+    e: "(pass)" {
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn assert_goes_to_both_targets() {
+    let program = "
+        let x: i32;
+        let p: &'p i32;
+        let b: [i32; 2];
+
+        bb0: {
+            p = &'L_x x;
+            x = 1;
+            assert(copy p) -> [success: bb1, unwind: bb2];
+        }
+
+        bb1: {
+            use(copy b[x]);
+        }
+
+        bb2: {
+            unreachable;
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "p = &'L_x x" {
+    	clear_origin('p)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'p)
+    	goto b
+    }
+
+    b: "x = 1" {
+    	invalidate_origin('L_x)
+    	goto c
+    }
+
+    c: "copy p" {
+    	access_origin('p)
+    	goto d e
+    }
+
+    d: "use(copy b[x])" {
+    	goto
+    }
+
+    e: "(pass)" {
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn loans_live_across_iterations() {
+    // The loan of the previous iteration is invalidated before being used, without numbering
+    // the blocks of the loop
+    let program = "
+        let x: i32;
+        let p: &'p i32;
+
+        bb0: {
+            loop {
+                use(copy p);
+                x = 1;
+                p = &'L_x x;
+                if copy x {
+                    continue;
+                }
+                break;
+            }
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "(pass)" {
+    	goto b
+    }
+
+    b: "use(copy p)" {
+    	access_origin('p)
+    	goto c
+    }
+
+    c: "x = 1" {
+    	invalidate_origin('L_x)
+    	goto d
+    }
+
+    d: "p = &'L_x x" {
+    	clear_origin('p)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'p)
+    	goto e
+    }
+
+    e: "copy x" {
+    	goto g h
+    }
+
+    f: "(pass)" {
+    	goto
+    }
+
+    g: "(pass)" {
+    	goto b
+    }
+
+    h: "(pass)" {
+    	goto f
+    }
+
+    i: "(pass)" {
+    	goto h
+    }
+
+    j: "(pass)" {
+    	goto b
+    }
+    "###);
+}
@@ -0,0 +1,82 @@
+use super::*;
+use insta::assert_debug_snapshot;
+
+fn invalidations(program: &str) -> Vec<(Origin, Node)> {
+    expect_facts_with_options(program, &EmitOptions::default()).invalidate_origin
+}
+
+#[test]
+fn dead_loans_are_not_invalidated() {
+    // The reference is not used after the assignment: the loan is dead
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+        bb0: {
+            y = &'L_x x;
+            x = 22;
+        }
+    ";
+    assert_debug_snapshot!(invalidations(program), @"[]");
+
+    // It's live when the reference is used after it, even on a single path
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+        bb0: {
+            y = &'L_x x;
+            x = 22;
+            goto bb1, bb2;
+        }
+        bb1: {
+            use(copy y);
+        }
+        bb2: {
+        }
+    ";
+    assert_debug_snapshot!(invalidations(program), @r###"
+    [
+        (
+            "'L_x",
+            "b",
+        ),
+    ]
+    "###);
+
+    // And dead again when the reference is overwritten before it's used
+    let program = "
+        let x: i32;
+        let z: i32;
+        let y: &'y i32;
+        bb0: {
+            y = &'L_x x;
+            x = 22;
+            y = &'L_z z;
+            use(copy y);
+        }
+    ";
+    assert_debug_snapshot!(invalidations(program), @"[]");
+}
+
+#[test]
+fn loans_live_through_subsets() {
+    // The loan flows into `'z` through `'y`, and `z` is used after the invalidation
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+        let z: &'z i32;
+        bb0: {
+            y = &'L_x x;
+            z = copy y;
+            x = 22;
+            use(copy z);
+        }
+    ";
+    assert_debug_snapshot!(invalidations(program), @r###"
+    [
+        (
+            "'L_x",
+            "c",
+        ),
+    ]
+    "###);
+}
@@ -0,0 +1,82 @@
+use super::*;
+use insta::{assert_debug_snapshot, assert_display_snapshot};
+
+#[test]
+fn elided_origins_are_named_after_their_variable() {
+    assert_eq!(find_origins("let p: &i32;", "p"), [Origin::from("'p")]);
+    assert_eq!(find_origins("let p: &mut i32;", "p"), [Origin::from("'p")]);
+    assert_eq!(
+        find_origins("let v: Vec<&&i32>;", "v"),
+        [Origin::from("'v"), Origin::from("'v1")]
+    );
+
+    // Explicit origins are kept, and fresh names don't clash with existing origins
+    assert_eq!(
+        find_origins("let p: &'p &i32;", "p"),
+        [Origin::from("'p"), Origin::from("'p1")]
+    );
+    let program = "
+        let p: &i32;
+        let q: &'p i32;
+    ";
+    assert_eq!(find_origins(program, "p"), [Origin::from("'p1")]);
+}
+
+#[test]
+fn elided_origins_in_facts() {
+    let program = "
+        let x: i32;
+        let p: &i32;
+        let q: &mut &i32;
+
+        bb0: {
+            p = &'L_x x;
+            q = &'L_p mut p;
+            use(move q);
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_debug_snapshot!(facts.introduce_subset, @r###"
+    [
+        (
+            "'L_x",
+            "'p",
+            "a",
+        ),
+        (
+            "'L_p",
+            "'q",
+            "b",
+        ),
+    ]
+    "###);
+    assert_display_snapshot!(facts, @r###"
+    mark_as_loan_origin('L_x)
+    mark_as_loan_origin('L_p)
+
+    a: "p = &'L_x x" {
+    	clear_origin('p)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'p)
+    	goto b
+    }
+
+    b: "q = &'L_p mut p" {
+    	access_origin('p)
+    	invalidate_origin('L_p)
+    	clear_origin('q)
+    	clear_origin('q1)
+    	clear_origin('L_p)
+    	loan_issued_at('L_p, bw1)
+    	introduce_subset('L_p, 'q)
+    	goto c
+    }
+
+    c: "use(move q)" {
+    	access_origin('q)
+    	access_origin('q1)
+    	goto
+    }
+    "###);
+}
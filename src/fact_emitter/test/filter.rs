@@ -0,0 +1,105 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+const PROGRAM: &str = "
+    let x: i32;
+    let y: &'y i32;
+    let z: &'z i32;
+
+    bb0: {
+        y = &'L_x x;
+        z = copy y;
+        goto bb1;
+    }
+
+    bb1: {
+        use(move z);
+    }
+";
+
+fn filtered_facts(criteria: &[&str]) -> Facts {
+    let mut filter = FactFilter::default();
+    for criterion in criteria {
+        filter.add_criterion(criterion).expect("invalid criterion");
+    }
+    expect_facts(PROGRAM).filter(&filter)
+}
+
+#[test]
+fn filter_by_relation() {
+    assert_display_snapshot!(filtered_facts(&["relation=introduce_subset"]), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "y = &'L_x x" {
+    	introduce_subset('L_x, 'y)
+    	goto b
+    }
+
+    b: "z = copy y" {
+    	introduce_subset('y, 'z)
+    	goto c
+    }
+
+    c: "use(move z)" {
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn filter_by_origin() {
+    assert_display_snapshot!(filtered_facts(&["origin='z"]), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "y = &'L_x x" {
+    	goto b
+    }
+
+    b: "z = copy y" {
+    	clear_origin('z)
+    	introduce_subset('y, 'z)
+    	goto c
+    }
+
+    c: "use(move z)" {
+    	access_origin('z)
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn filter_by_node_and_origin() {
+    assert_display_snapshot!(filtered_facts(&["node=b", "origin='y"]), @r###"
+    mark_as_loan_origin('L_x)
+
+    b: "z = copy y" {
+    	access_origin('y)
+    	introduce_subset('y, 'z)
+    	goto c
+    }
+
+    c: "use(move z)" {
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn filtering_keeps_provenance() {
+    let facts = filtered_facts(&["relation=access_origin"]);
+    let provenance = facts
+        .provenance(Relation::AccessOrigin, 1)
+        .expect("missing provenance");
+    let span = provenance.span;
+    assert_eq!(&PROGRAM[span.start()..span.end()], "use(move z);");
+    assert_eq!(provenance.path.to_string(), "args[0]");
+}
+
+#[test]
+fn invalid_criteria() {
+    let mut filter = FactFilter::default();
+    assert!(filter.add_criterion("origin").is_err());
+    assert!(filter.add_criterion("relation=unknown").is_err());
+    assert!(filter.add_criterion("block=bb0").is_err());
+}
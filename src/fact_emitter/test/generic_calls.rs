@@ -0,0 +1,119 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+#[test]
+fn explicit_generic_args() {
+    // The type given to `T` relates the argument to the result, through `'a`
+    let program = "
+        fn id<T>(x: T) -> T;
+        placeholder 'a;
+
+        let x: &'x i32;
+        let y: &'y i32;
+
+        bb0: {
+            y = id::<&'a i32>(copy x);
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    placeholder_origin('a)
+
+    a: "y = id::<&'a i32>(copy x)" {
+    	access_origin('x)
+    	clear_origin('y)
+    	loan_issued_at('a, ph0)
+    	introduce_subset('x, 'a)
+    	introduce_subset('a, 'y)
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn fresh_origins_at_each_call() {
+    // The origins of the signature are instantiated with fresh origins at each call, and so are the
+    // origins of the types inferred from the arguments
+    let program = "
+        struct Option<T> { value: T }
+        fn identity<'r>(x: &'r i32) -> &'r i32;
+        fn wrap<T>(x: T) -> Option<T>;
+
+        let x: &'x i32;
+        let y: &'y i32;
+        let z: Option<&'z i32>;
+
+        bb0: {
+            y = identity(copy x);
+            y = identity(copy y);
+            z = wrap(copy x);
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    a: "y = identity(copy x)" {
+    	access_origin('x)
+    	clear_origin('y)
+    	introduce_subset('x, 'call0_r)
+    	introduce_subset('call0_r, 'y)
+    	goto b
+    }
+
+    b: "y = identity(copy y)" {
+    	access_origin('y)
+    	clear_origin('y)
+    	introduce_subset('y, 'call1_r)
+    	introduce_subset('call1_r, 'y)
+    	goto c
+    }
+
+    c: "z = wrap(copy x)" {
+    	access_origin('x)
+    	clear_origin('z)
+    	introduce_subset('x, 'call2_1)
+    	introduce_subset('call2_1, 'z)
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn subsets_between_arguments() {
+    // Pushing a reference into a vector makes it flow into the vector's parameter
+    let program = "
+        struct Vec<T> { item: T }
+        fn push<'a, T>(v: &'a mut Vec<T>, value: T) -> ();
+
+        let v: Vec<&'v i32>;
+        let r: &'r i32;
+
+        bb0: {
+            push(&'L mut v, copy r);
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L)
+
+    a: "&'L mut v" {
+    	access_origin('v)
+    	invalidate_origin('L)
+    	clear_origin('_tmp0)
+    	clear_origin('_tmp01)
+    	clear_origin('L)
+    	loan_issued_at('L, bw0)
+    	introduce_subset('L, '_tmp0)
+    	introduce_subset('v, '_tmp01)
+    	introduce_subset('_tmp01, 'v)
+    	goto b
+    }
+
+    b: "push(&'L mut v, copy r)" {
+    	access_origin('_tmp0)
+    	access_origin('_tmp01)
+    	access_origin('r)
+    	introduce_subset('_tmp0, 'call0_a)
+    	introduce_subset('_tmp01, 'call0_1)
+    	introduce_subset('call0_1, '_tmp01)
+    	introduce_subset('r, 'call0_1)
+    	goto
+    }
+    "###);
+}
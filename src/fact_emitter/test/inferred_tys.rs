@@ -0,0 +1,125 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+#[test]
+fn tys_inferred_from_first_assignment() {
+    let program = "
+        struct Vec<T> { e: T }
+        fn Vec_new() -> Vec<&'static i32>;
+
+        let x: i32;
+        let r: &'r i32;
+        let n;
+        let u;
+        let p;
+        let q;
+        let m;
+        let v;
+
+        bb0: {
+            n = 3;
+            u = ();
+            p = &'L_x x;
+            q = copy r;
+            m = &'L_n mut n;
+            v = Vec_new();
+        }
+    ";
+    assert_eq!(find_ty(program, "n"), Ty::I32);
+    assert_eq!(find_ty(program, "u"), Ty::Unit);
+
+    // Inferred types have fresh origins
+    assert_eq!(find_origins(program, "p"), [Origin::from("'p")]);
+    assert_eq!(find_origins(program, "q"), [Origin::from("'q")]);
+    assert_eq!(find_origins(program, "v"), [Origin::from("'v")]);
+    assert!(matches!(find_ty(program, "m"), Ty::RefMut { .. }));
+    assert_eq!(find_ty(program, "*p"), Ty::I32);
+}
+
+#[test]
+fn tys_inferred_from_inferred_variables() {
+    let program = "
+        let x: i32;
+        let p;
+        let q;
+
+        bb0: {
+            p = &'L_x x;
+            q = copy p;
+            use(move q);
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "p = &'L_x x" {
+    	clear_origin('p)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'p)
+    	goto b
+    }
+
+    b: "q = copy p" {
+    	access_origin('p)
+    	clear_origin('q)
+    	introduce_subset('p, 'q)
+    	goto c
+    }
+
+    c: "use(move q)" {
+    	access_origin('q)
+    	goto
+    }
+    "###);
+}
+
+#[test]
+#[should_panic(expected = "Can't infer the type of p, it's never assigned")]
+fn unassigned_variable() {
+    find_ty("let p;", "p");
+}
+
+#[test]
+fn tys_inferred_from_initializers() {
+    let program = "
+        let x: i32;
+
+        bb0: {
+            let p = &'L_x x;
+            let q: &i32 = copy p;
+            x = 1;
+            use(copy q);
+        }
+    ";
+    assert_eq!(find_origins(program, "p"), [Origin::from("'p")]);
+    assert_eq!(find_origins(program, "q"), [Origin::from("'q")]);
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "let p = &'L_x x" {
+    	clear_origin('p)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'p)
+    	goto b
+    }
+
+    b: "let q: &i32 = copy p" {
+    	access_origin('p)
+    	clear_origin('q)
+    	introduce_subset('p, 'q)
+    	goto c
+    }
+
+    c: "x = 1" {
+    	invalidate_origin('L_x)
+    	goto d
+    }
+
+    d: "use(copy q)" {
+    	access_origin('q)
+    	goto
+    }
+    "###);
+}
@@ -9,15 +9,15 @@ fn assignment_to_non_references() {
         let y: &'y i32;
 
         bb0: {
-            x = 22;
             y = &'L_x x;
+            x = 22;
         }
     ";
     assert_debug_snapshot!(expect_facts(program).invalidate_origin, @r###"
     [
         (
             "'L_x",
-            "a",
+            "b",
         ),
     ]
     "###);
@@ -27,15 +27,15 @@ fn assignment_to_non_references() {
         let v: Vec;
         let ref: &'ref Vec;
         bb0: {
-            v = Vec_new();
             ref = &'L_v v;
+            v = Vec_new();
         }
     ";
     assert_debug_snapshot!(expect_facts(program).invalidate_origin, @r###"
     [
         (
             "'L_v",
-            "a",
+            "b",
         ),
     ]
     "###);
@@ -50,8 +50,8 @@ fn mut_borrows_invalidate_loans() {
         let y: &'y mut i32;
 
         bb0: {
-            x = 22;
             y = &'L_x mut x;
+            x = 22;
         }
     ";
     assert_debug_snapshot!(expect_facts(program).invalidate_origin, @r###"
@@ -67,3 +67,142 @@ fn mut_borrows_invalidate_loans() {
     ]
     "###);
 }
+
+#[test]
+fn moves_invalidate_loans() {
+    // Reading a non-`Copy` struct moves it, even when written as a copy, and invalidates its
+    // loans. Reading a `Copy` struct doesn't, even when written as a move.
+    let program = "
+        #[derive(Copy)]
+        struct Point { x: i32 }
+        struct Buffer { len: i32 }
+
+        let point: Point;
+        let buffer: Buffer;
+        let p: &'p Point;
+        let b: &'b Buffer;
+
+        bb0: {
+            p = &'L_point point;
+            b = &'L_buffer buffer;
+            use(move point);
+            use(copy buffer);
+        }
+    ";
+    assert_debug_snapshot!(expect_facts(program).invalidate_origin, @r###"
+    [
+        (
+            "'L_buffer",
+            "d",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn overlapping_places_invalidate_loans() {
+    // Writes invalidate the loans of their fields and of the structs containing them, and of all
+    // the indices of the arrays they index, but not the loans of other fields, or behind the
+    // references they overwrite
+    let program = "
+        struct Pair { first: i32, second: i32 }
+        struct Ref<'a> { value: &'a mut i32 }
+
+        let pair: Pair;
+        let array: [i32; 2];
+        let r: Ref<'r>;
+        let i: i32;
+        let x: &'x i32;
+
+        bb0: {
+            x = &'L_pair pair;
+            x = &'L_first pair.first;
+            x = &'L_second pair.second;
+            x = &'L_array0 array[0];
+            x = &'L_arrayi array[i];
+            x = &'L_value *r.value;
+            pair.first = 1;
+            array[1] = 2;
+            r = move r;
+        }
+    ";
+    assert_debug_snapshot!(expect_facts(program).invalidate_origin, @r###"
+    [
+        (
+            "'L_pair",
+            "g",
+        ),
+        (
+            "'L_first",
+            "g",
+        ),
+        (
+            "'L_array0",
+            "h",
+        ),
+        (
+            "'L_arrayi",
+            "h",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn loans_of_nested_borrows() {
+    // Borrows inferred for call arguments are loans, like the ones assigned to a variable
+    let program = "
+        fn inspect<'a>(x: &'a i32) -> &'a i32;
+
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            y = inspect(x);
+            x = 1;
+        }
+    ";
+    assert_debug_snapshot!(expect_facts(program).invalidate_origin, @r###"
+    [
+        (
+            "'anon0",
+            "b",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn loans_issued_in_the_same_statement() {
+    // Loans issued at the same location are invalidated in the order of their places, the same
+    // on every run
+    let program = "
+        struct Pair { first: i32, second: i32 }
+
+        let pair: Pair;
+
+        bb0: {
+            use(&'L_second pair.second, &'L_pair pair, &'L_first pair.first);
+            pair.first = 1;
+        }
+    ";
+    let facts = expect_facts(program);
+    for _ in 0..10 {
+        assert_eq!(
+            facts.invalidate_origin,
+            expect_facts(program).invalidate_origin
+        );
+    }
+    assert_debug_snapshot!(facts.invalidate_origin, @r###"
+    [
+        (
+            "'L_pair",
+            "e",
+        ),
+        (
+            "'L_first",
+            "e",
+        ),
+    ]
+    "###);
+}
@@ -0,0 +1,57 @@
+use super::*;
+use crate::diagnostics::diagnose;
+use crate::fact_parser::read_facts;
+use insta::assert_display_snapshot;
+
+const ASSIGN_TO_BORROWED: &str = "
+    let x: i32;
+    let r: &'r i32;
+    bb0: {
+        r = &'L_x x;
+        x = 4;
+        use(copy r);
+    }
+";
+
+#[test]
+fn namespaced_facts() {
+    let facts = emit_facts(ASSIGN_TO_BORROWED).unwrap().namespaced("main");
+    assert_display_snapshot!(facts, @r###"
+    mark_as_loan_origin(main::'L_x)
+
+    main::bb0[0]: "r = &'L_x x" {
+    	clear_origin(main::'r)
+    	clear_origin(main::'L_x)
+    	loan_issued_at(main::'L_x, main::bw0)
+    	introduce_subset(main::'L_x, main::'r)
+    	goto main::bb0[1]
+    }
+
+    main::bb0[1]: "x = 4" {
+    	invalidate_origin(main::'L_x)
+    	goto main::bb0[2]
+    }
+
+    main::bb0[2]: "use(copy r)" {
+    	access_origin(main::'r)
+    	goto
+    }
+    "###);
+
+    // Scoped names can be read back from the fact file format
+    let read = read_facts(&facts.to_string()).unwrap();
+    assert_eq!(read.to_string(), facts.to_string());
+}
+
+#[test]
+fn functions_do_not_collide() {
+    // The same body in two functions has an error in each of them
+    let facts = emit_facts(ASSIGN_TO_BORROWED).unwrap();
+    let mut functions = facts.namespaced("f");
+    functions.append(facts.namespaced("g"));
+    let nodes: Vec<_> = diagnose(&functions)
+        .iter()
+        .map(|diagnostic| diagnostic.node.0.to_string())
+        .collect();
+    assert_eq!(nodes, ["f::bb0[1]", "g::bb0[1]"]);
+}
@@ -0,0 +1,92 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+#[test]
+fn nested_exprs_are_lowered_to_temporaries() {
+    let program = "
+        struct Pair<'p> { first: &'p i32, second: i32 }
+        fn make_pair<'a>(x: &'a i32) -> Pair<'a>;
+
+        let y: i32;
+        let x: i32;
+
+        bb0: {
+            x = make_pair(&'L_y y).second;
+            y = 1;
+            use(copy x);
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_y)
+
+    a: "&'L_y y" {
+    	clear_origin('_tmp0)
+    	clear_origin('L_y)
+    	loan_issued_at('L_y, bw0)
+    	introduce_subset('L_y, '_tmp0)
+    	goto b
+    }
+
+    b: "make_pair(&'L_y y)" {
+    	access_origin('_tmp0)
+    	clear_origin('_tmp1)
+    	introduce_subset('_tmp0, 'call0_a)
+    	introduce_subset('call0_a, '_tmp1)
+    	goto c
+    }
+
+    c: "x = make_pair(&'L_y y).second" {
+    	access_origin('_tmp1)
+    	goto d
+    }
+
+    d: "y = 1" {
+    	invalidate_origin('L_y)
+    	goto e
+    }
+
+    e: "use(copy x)" {
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn nested_calls_are_lowered_to_temporaries() {
+    let program = "
+        fn f<'a>(x: &'a i32) -> &'a i32;
+        fn g<'b>(x: &'b i32) -> i32;
+
+        let y: i32;
+        let z: i32;
+
+        bb0: {
+            z = g(f(&'L_y y));
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_y)
+
+    a: "&'L_y y" {
+    	clear_origin('_tmp0)
+    	clear_origin('L_y)
+    	loan_issued_at('L_y, bw0)
+    	introduce_subset('L_y, '_tmp0)
+    	goto b
+    }
+
+    b: "f(&'L_y y)" {
+    	access_origin('_tmp0)
+    	clear_origin('_tmp1)
+    	introduce_subset('_tmp0, 'call0_a)
+    	introduce_subset('call0_a, '_tmp1)
+    	goto c
+    }
+
+    c: "z = g(f(&'L_y y))" {
+    	access_origin('_tmp1)
+    	introduce_subset('_tmp1, 'call1_b)
+    	goto
+    }
+    "###);
+}
@@ -0,0 +1,49 @@
+use super::*;
+use crate::source_map::SourceMap;
+use insta::assert_debug_snapshot;
+
+#[test]
+fn lines_and_columns_of_statements() {
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            x = 1;
+            goto bb1;
+        }
+
+        bb1: {
+            y = &'L_x x; x = 2;
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_debug_snapshot!(facts.node_location, @r###"
+    [
+        (
+            "a",
+            6,
+            13,
+        ),
+        (
+            "b",
+            11,
+            13,
+        ),
+        (
+            "c",
+            11,
+            26,
+        ),
+    ]
+    "###);
+
+    // The location is where the node's span starts
+    let source_map = SourceMap::new(program);
+    for ((node, line, column), (span_node, start, _)) in
+        facts.node_location.iter().zip(&facts.node_span)
+    {
+        assert_eq!(node, span_node);
+        assert_eq!((*line, *column), source_map.line_col(*start));
+    }
+}
@@ -0,0 +1,62 @@
+use super::*;
+use insta::assert_debug_snapshot;
+
+// The nodes of the program named after their statements, with their text.
+fn hashed_nodes(program: &str) -> Vec<(String, Node)> {
+    let options = EmitOptions {
+        node_names: NodeNames::Hashed,
+        ..EmitOptions::default()
+    };
+    emit_facts_with_options(program, &options)
+        .unwrap()
+        .node_text
+}
+
+#[test]
+fn hashed_node_names() {
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+        bb0: {
+            x = 1;
+            y = &'L_x x;
+            goto bb1;
+        }
+        bb1: {
+            x = 1;
+            x = 1;
+        }
+        bb2: {
+            goto bb1;
+        }
+    ";
+    assert_debug_snapshot!(hashed_nodes(program), @r###"
+    [
+        (
+            "x = 1",
+            "bb0[93efa013]",
+        ),
+        (
+            "y = &'L_x x",
+            "bb0[90631421]",
+        ),
+        (
+            "x = 1",
+            "bb1[5ca930f8]",
+        ),
+        (
+            "x = 1",
+            "bb1[5ca930f8_1]",
+        ),
+    ]
+    "###);
+
+    // Adding a statement doesn't rename the nodes of the other ones
+    let edited = program.replace("y = &'L_x x;", "x = 2;\n            y = &'L_x x;");
+    let nodes = hashed_nodes(program);
+    let edited_nodes = hashed_nodes(&edited);
+    assert_eq!(edited_nodes.len(), nodes.len() + 1);
+    for node in &nodes {
+        assert!(edited_nodes.contains(node));
+    }
+}
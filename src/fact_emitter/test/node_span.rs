@@ -0,0 +1,40 @@
+use super::*;
+use insta::assert_debug_snapshot;
+
+#[test]
+fn spans_of_statements() {
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            x = 1;
+            goto bb1;
+        }
+
+        bb1: {
+            y = &'L_x x;
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_debug_snapshot!(facts.node_span, @r###"
+    [
+        (
+            "a",
+            73,
+            78,
+        ),
+        (
+            "b",
+            140,
+            151,
+        ),
+    ]
+    "###);
+
+    // The span covers the same text as `node_text`
+    for ((node, start, end), (text, text_node)) in facts.node_span.iter().zip(&facts.node_text) {
+        assert_eq!(node, text_node);
+        assert_eq!(&program[*start..*end], text);
+    }
+}
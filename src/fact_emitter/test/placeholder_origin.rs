@@ -0,0 +1,37 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+#[test]
+fn placeholders_are_global_facts() {
+    let program = "
+        placeholder 'a, 'b;
+        placeholder 'c;
+
+        let x: &'a i32;
+        let y: &'b i32;
+
+        bb0: {
+            y = copy x;
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_eq!(
+        facts.placeholder_origin,
+        [Origin::from("'a"), Origin::from("'b"), Origin::from("'c")]
+    );
+    assert_display_snapshot!(facts, @r###"
+    placeholder_origin('a)
+    placeholder_origin('b)
+    placeholder_origin('c)
+
+    a: "y = copy x" {
+    	access_origin('a)
+    	clear_origin('b)
+    	loan_issued_at('a, ph0)
+    	loan_issued_at('b, ph1)
+    	loan_issued_at('c, ph2)
+    	introduce_subset('a, 'b)
+    	goto
+    }
+    "###);
+}
@@ -0,0 +1,119 @@
+use super::*;
+use insta::assert_debug_snapshot;
+
+// Renders each fact of the program's facts with the syntax that produced it: the statement's
+// text, and the path to the sub-expression.
+fn describe_provenance(program: &str, facts: &Facts) -> Vec<String> {
+    let describe = |relation: Relation, idx: usize, fact: String| {
+        let provenance = facts
+            .provenance(relation, idx)
+            .expect("missing provenance for fact");
+        let span = provenance.span;
+        format!(
+            "{}({}) from {:?} at `{}`",
+            relation.name(),
+            fact,
+            &program[span.start()..span.end()],
+            provenance.path,
+        )
+    };
+
+    let mut descriptions = Vec::new();
+    for (idx, (origin, node)) in facts.access_origin.iter().enumerate() {
+        let fact = format!("{}, {}", origin.0, node.0);
+        descriptions.push(describe(Relation::AccessOrigin, idx, fact));
+    }
+    for (idx, (origin, node)) in facts.clear_origin.iter().enumerate() {
+        let fact = format!("{}, {}", origin.0, node.0);
+        descriptions.push(describe(Relation::ClearOrigin, idx, fact));
+    }
+    for (idx, (origin1, origin2, node)) in facts.introduce_subset.iter().enumerate() {
+        let fact = format!("{}, {}, {}", origin1.0, origin2.0, node.0);
+        descriptions.push(describe(Relation::IntroduceSubset, idx, fact));
+    }
+    for (idx, (origin, node)) in facts.invalidate_origin.iter().enumerate() {
+        let fact = format!("{}, {}", origin.0, node.0);
+        descriptions.push(describe(Relation::InvalidateOrigin, idx, fact));
+    }
+    descriptions
+}
+
+#[test]
+fn assignment_provenance() {
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            y = &'L_x x;
+        }
+    ";
+    assert_debug_snapshot!(describe_provenance(program, &expect_facts(program)), @r###"
+    [
+        "clear_origin('y, a) from \"y = &'L_x x;\" at `lhs`",
+        "clear_origin('L_x, a) from \"y = &'L_x x;\" at `rhs`",
+        "introduce_subset('L_x, 'y, a) from \"y = &'L_x x;\" at ``",
+    ]
+    "###);
+}
+
+#[test]
+fn call_argument_provenance() {
+    let program = "
+        let a: &'a i32;
+        let b: &'b i32;
+        let c: &'c i32;
+
+        bb0: {
+            c = call(move a, move b);
+            use(move c);
+        }
+    ";
+    assert_debug_snapshot!(describe_provenance(program, &expect_facts(program)), @r###"
+    [
+        "access_origin('a, a) from \"c = call(move a, move b);\" at `rhs.args[0]`",
+        "access_origin('b, a) from \"c = call(move a, move b);\" at `rhs.args[1]`",
+        "access_origin('c, b) from \"use(move c);\" at `args[0]`",
+        "clear_origin('c, a) from \"c = call(move a, move b);\" at `lhs`",
+    ]
+    "###);
+}
+
+#[test]
+fn escaped_and_raw_fact_provenance() {
+    // The accesses keeping the escaped loans live come from the node where the function exits,
+    // and the facts written in the program from their own text
+    let program = "
+        let x: i32;
+        let p: *const i32;
+        let y: i32;
+
+        bb0: {
+            p = &'L_x x as *const i32;
+            y = copy *p;
+        }
+        facts {
+            b: access_origin('L_x)
+            b: invalidate_origin('L_x)
+        }
+    ";
+    let options = EmitOptions {
+        raw_pointer_casts: RawPointerCasts::KeepLive,
+        ..Default::default()
+    };
+    let facts = expect_facts_with_options(program, &options);
+    assert_debug_snapshot!(describe_provenance(program, &facts), @r###"
+    [
+        "access_origin('_tmp0, b) from \"p = &'L_x x as *const i32;\" at `rhs.operand`",
+        "access_origin('escaped, a) from \"y = copy *p\" at ``",
+        "access_origin('escaped, b) from \"y = copy *p\" at ``",
+        "access_origin('escaped, c) from \"y = copy *p\" at ``",
+        "access_origin('L_x, b) from \"b: access_origin('L_x)\" at ``",
+        "clear_origin('_tmp0, a) from \"&'L_x x\" at `lhs`",
+        "clear_origin('L_x, a) from \"&'L_x x\" at `rhs`",
+        "introduce_subset('L_x, '_tmp0, a) from \"&'L_x x\" at ``",
+        "introduce_subset('_tmp0, 'escaped, b) from \"p = &'L_x x as *const i32;\" at ``",
+        "invalidate_origin('L_x, b) from \"b: invalidate_origin('L_x)\" at ``",
+    ]
+    "###);
+}
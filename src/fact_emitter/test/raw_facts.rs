@@ -0,0 +1,29 @@
+use super::*;
+
+#[test]
+fn raw_facts_are_merged() {
+    // The facts are added after the emitted ones, with the node of local facts as their last
+    // atom, and the span of the fact as their provenance
+    let program = "
+        let x: i32;
+        bb0: {
+            x = 1;
+        }
+        facts {
+            placeholder_origin('p)
+            a: invalidate_origin('a)
+            a: cfg_edge(a)
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_eq!(facts.placeholder_origin, ["'p".into()]);
+    assert_eq!(facts.invalidate_origin, [("'a".into(), "a".into())]);
+    assert_eq!(facts.cfg_edge, [("a".into(), "a".into())]);
+
+    let provenance = facts.provenance(Relation::InvalidateOrigin, 0).unwrap();
+    let span = provenance.span;
+    assert_eq!(
+        &program[span.start()..span.end()],
+        "a: invalidate_origin('a)"
+    );
+}
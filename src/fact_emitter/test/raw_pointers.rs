@@ -0,0 +1,113 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+// Casting a reference to a raw pointer, and invalidating the loan while the pointer is still
+// used: the loan can't be tracked through the pointer.
+const RAW_POINTER_ESCAPE: &str = "
+    let x: i32;
+    let p: *const i32;
+    let y: i32;
+
+    bb0: {
+        p = &'L_x x as *const i32;
+        x = 1;
+        y = copy *p;
+    }
+";
+
+#[test]
+fn casts_end_tracking_loans() {
+    assert_display_snapshot!(expect_facts(RAW_POINTER_ESCAPE), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "&'L_x x" {
+    	clear_origin('_tmp0)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, '_tmp0)
+    	goto b
+    }
+
+    b: "p = &'L_x x as *const i32" {
+    	access_origin('_tmp0)
+    	goto c
+    }
+
+    c: "x = 1" {
+    	invalidate_origin('L_x)
+    	goto d
+    }
+
+    d: "y = copy *p" {
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn casts_keep_loans_live() {
+    let options = EmitOptions {
+        raw_pointer_casts: RawPointerCasts::KeepLive,
+        ..Default::default()
+    };
+    let facts = expect_facts_with_options(RAW_POINTER_ESCAPE, &options);
+    assert_display_snapshot!(facts, @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "&'L_x x" {
+    	access_origin('escaped)
+    	clear_origin('_tmp0)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, '_tmp0)
+    	goto b
+    }
+
+    b: "p = &'L_x x as *const i32" {
+    	access_origin('_tmp0)
+    	access_origin('escaped)
+    	introduce_subset('_tmp0, 'escaped)
+    	goto c
+    }
+
+    c: "x = 1" {
+    	access_origin('escaped)
+    	invalidate_origin('L_x)
+    	goto d
+    }
+
+    d: "y = copy *p" {
+    	access_origin('escaped)
+    	goto
+    }
+    "###);
+
+    // The loan escaped, so invalidating it is an error
+    let errors = crate::solver::solve(&facts).invalidated_origin_accessed;
+    assert_debug_snapshot!(errors, @r###"
+    [
+        (
+            "'escaped",
+            "d",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn non_pointer_casts_read_their_operand() {
+    let program = "
+        let x: &i32;
+        let y: i32;
+
+        bb0: {
+            y = copy *x as i32;
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    a: "y = copy *x as i32" {
+    	access_origin('x)
+    	goto
+    }
+    "###);
+}
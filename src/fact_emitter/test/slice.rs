@@ -0,0 +1,192 @@
+use super::*;
+use insta::{assert_debug_snapshot, assert_display_snapshot};
+
+// Two independent loans: one invalidated while live, in `bb1`, and one unrelated to the error.
+const PROGRAM: &str = "
+    let x: i32;
+    let w: i32;
+    let y: &'y i32;
+    let z: &'z i32;
+    let v: &'v i32;
+
+    bb0: {
+        y = &'L_x x;
+        v = &'L_w w;
+        goto bb1, bb2;
+    }
+
+    bb1: {
+        x = 1;
+        goto bb3;
+    }
+
+    bb2: {
+        use(copy v);
+        goto bb3;
+    }
+
+    bb3: {
+        z = copy y;
+        use(copy z);
+    }
+";
+
+fn sliced_facts(slice: &str) -> eyre::Result<Facts> {
+    expect_facts(PROGRAM).slice(&slice.parse()?)
+}
+
+#[test]
+fn slice_by_loan() {
+    assert_display_snapshot!(sliced_facts("loan='L_x").unwrap(), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "y = &'L_x x" {
+    	clear_origin('y)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'y)
+    	goto b
+    }
+
+    b: "v = &'L_w w" {
+    	goto c d
+    }
+
+    c: "x = 1" {
+    	invalidate_origin('L_x)
+    	goto e
+    }
+
+    d: "use(copy v)" {
+    	goto e
+    }
+
+    e: "z = copy y" {
+    	access_origin('y)
+    	clear_origin('z)
+    	introduce_subset('y, 'z)
+    	goto f
+    }
+
+    f: "use(copy z)" {
+    	access_origin('z)
+    	goto
+    }
+    "###);
+    assert_display_snapshot!(sliced_facts("loan='L_w").unwrap(), @r###"
+    mark_as_loan_origin('L_w)
+
+    b: "v = &'L_w w" {
+    	clear_origin('v)
+    	clear_origin('L_w)
+    	loan_issued_at('L_w, bw1)
+    	introduce_subset('L_w, 'v)
+    	goto d
+    }
+
+    d: "use(copy v)" {
+    	access_origin('v)
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn slice_by_error() {
+    // The error is reported where `'y` is accessed, after the invalidation in `bb1`
+    assert_display_snapshot!(sliced_facts("error=e").unwrap(), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "y = &'L_x x" {
+    	clear_origin('y)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'y)
+    	goto b
+    }
+
+    b: "v = &'L_w w" {
+    	goto c d
+    }
+
+    c: "x = 1" {
+    	invalidate_origin('L_x)
+    	goto e
+    }
+
+    d: "use(copy v)" {
+    	goto e
+    }
+
+    e: "z = copy y" {
+    	access_origin('y)
+    	clear_origin('z)
+    	introduce_subset('y, 'z)
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn invalid_slices() {
+    assert_display_snapshot!(sliced_facts("loan='L_y").unwrap_err(), @"no loan is issued with the origin `'L_y`");
+    assert_display_snapshot!(sliced_facts("error=a").unwrap_err(), @"no error is reported at node `a`");
+    assert_display_snapshot!(sliced_facts("variable=u").unwrap_err(), @"the variable `u` is never used");
+    assert_display_snapshot!(sliced_facts("origin='y").unwrap_err(), @"invalid slice `origin='y`, expected `loan=<origin>`, `error=<node>` or `variable=<name>`");
+}
+
+#[test]
+fn slice_by_variable() {
+    // The data flow of `x`: its loan, and the references it flows into
+    assert_display_snapshot!(sliced_facts("variable=x").unwrap(), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "y = &'L_x x" {
+    	clear_origin('y)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'y)
+    	goto c e
+    }
+
+    c: "x = 1" {
+    	invalidate_origin('L_x)
+    	goto e
+    }
+
+    e: "z = copy y" {
+    	access_origin('y)
+    	clear_origin('z)
+    	introduce_subset('y, 'z)
+    	goto f
+    }
+
+    f: "use(copy z)" {
+    	access_origin('z)
+    	goto
+    }
+    "###);
+
+    // The data flow of `v`, whose origin is only accessed in `bb2`
+    let facts = sliced_facts("variable=v").unwrap();
+    assert_display_snapshot!(facts, @r###"
+    b: "v = &'L_w w" {
+    	clear_origin('v)
+    	introduce_subset('L_w, 'v)
+    	goto d
+    }
+
+    d: "use(copy v)" {
+    	access_origin('v)
+    	goto
+    }
+    "###);
+    assert_debug_snapshot!(facts.use_of_var_derefs_origin, @r###"
+    [
+        (
+            "v",
+            "'v",
+        ),
+    ]
+    "###);
+}
@@ -0,0 +1,42 @@
+use super::*;
+use crate::fact_parser::read_facts;
+use insta::assert_display_snapshot;
+
+#[test]
+fn locations_of_statements() {
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            x = 1; @ main.rs:2
+            y = &'L_x x; @ main.rs:3
+            use(copy y);
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_display_snapshot!(facts, @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "x = 1" @ main.rs:2 {
+    	goto b
+    }
+
+    b: "y = &'L_x x" @ main.rs:3 {
+    	clear_origin('y)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'y)
+    	goto c
+    }
+
+    c: "use(copy y)" {
+    	access_origin('y)
+    	goto
+    }
+    "###);
+
+    // The locations are read back from the fact file
+    let read = read_facts(&facts.to_string()).unwrap();
+    assert_eq!(read.source_location, facts.source_location);
+}
@@ -0,0 +1,119 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+#[test]
+fn no_invalidate() {
+    // The assignment and the move invalidate nothing, but the second assignment still does
+    let program = "
+        struct Vec { len: i32 }
+
+        let x: i32;
+        let v: Vec;
+        let y: &'y i32;
+        let w: &'w Vec;
+
+        bb0: {
+            y = &'L_x x;
+            w = &'L_v v;
+            #[no_invalidate] x = 1;
+            #[no_invalidate] consume(move v);
+            x = 2;
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_x)
+    mark_as_loan_origin('L_v)
+
+    a: "y = &'L_x x" {
+    	clear_origin('y)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'y)
+    	goto b
+    }
+
+    b: "w = &'L_v v" {
+    	clear_origin('w)
+    	clear_origin('L_v)
+    	loan_issued_at('L_v, bw1)
+    	introduce_subset('L_v, 'w)
+    	goto c
+    }
+
+    c: "x = 1" {
+    	goto d
+    }
+
+    d: "consume(move v)" {
+    	goto e
+    }
+
+    e: "x = 2" {
+    	invalidate_origin('L_x)
+    	goto
+    }
+    "###);
+}
+
+#[test]
+fn two_phase_borrows() {
+    // The mutable borrow is only reserved, and doesn't invalidate the shared loan, nor does the
+    // temporary borrowing `v` for the method call lowered from the statement
+    let program = "
+        struct Vec { len: i32 }
+        impl Vec {
+            fn push(&mut self, value: i32) -> ();
+        }
+
+        let v: Vec;
+        let r: &'r Vec;
+        let m: &'m mut Vec;
+
+        bb0: {
+            r = &'L_v v;
+            #[two_phase] m = &'L_mut_v mut v;
+            #[two_phase] v.push(0);
+            use(copy r);
+        }
+    ";
+    assert_display_snapshot!(expect_facts(program), @r###"
+    mark_as_loan_origin('L_v)
+    mark_as_loan_origin('L_mut_v)
+    mark_as_loan_origin('anon0)
+
+    a: "r = &'L_v v" {
+    	clear_origin('r)
+    	clear_origin('L_v)
+    	loan_issued_at('L_v, bw0)
+    	introduce_subset('L_v, 'r)
+    	goto b
+    }
+
+    b: "m = &'L_mut_v mut v" {
+    	clear_origin('m)
+    	clear_origin('L_mut_v)
+    	loan_issued_at('L_mut_v, bw1)
+    	introduce_subset('L_mut_v, 'm)
+    	goto c
+    }
+
+    c: "v.push(0)" {
+    	clear_origin('_tmp0)
+    	clear_origin('anon0)
+    	loan_issued_at('anon0, bw2)
+    	introduce_subset('anon0, '_tmp0)
+    	goto d
+    }
+
+    d: "v.push(0)" {
+    	access_origin('_tmp0)
+    	introduce_subset('_tmp0, 'call0__0)
+    	goto e
+    }
+
+    e: "use(copy r)" {
+    	access_origin('r)
+    	goto
+    }
+    "###);
+}
@@ -0,0 +1,63 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+// `bb2` is only reachable from itself, and its loan would otherwise be invalidated in `bb1`.
+const UNREACHABLE_LOOP: &str = "
+    let x: i32;
+    let y: &'y i32;
+
+    bb0: {
+        x = 0;
+        goto bb1;
+    }
+
+    bb1: {
+        x = 1;
+    }
+
+    bb2: {
+        y = &'L_x x;
+        goto bb2;
+    }
+";
+
+#[test]
+fn unreachable_blocks_are_emitted_by_default() {
+    assert_display_snapshot!(expect_facts(UNREACHABLE_LOOP), @r###"
+    mark_as_loan_origin('L_x)
+
+    a: "x = 0" {
+    	goto b
+    }
+
+    b: "x = 1" {
+    	goto
+    }
+
+    c: "y = &'L_x x" {
+    	clear_origin('y)
+    	clear_origin('L_x)
+    	loan_issued_at('L_x, bw0)
+    	introduce_subset('L_x, 'y)
+    	goto c
+    }
+    "###);
+}
+
+#[test]
+fn excluded_unreachable_blocks() {
+    let options = EmitOptions {
+        exclude_unreachable_blocks: true,
+        ..Default::default()
+    };
+    let facts = expect_facts_with_options(UNREACHABLE_LOOP, &options);
+    assert_display_snapshot!(facts, @r###"
+    a: "x = 0" {
+    	goto b
+    }
+
+    b: "x = 1" {
+    	goto
+    }
+    "###);
+}
@@ -0,0 +1,167 @@
+use super::*;
+
+#[test]
+fn var_uses_and_definitions() {
+    // Assignments to a whole variable define it, other accesses use it
+    let program = "
+        struct Pair { first: i32, second: i32 }
+
+        let x: i32;
+        let pair: Pair;
+        let r: &'r mut i32;
+
+        bb0: {
+            x = 1;
+            pair.first = copy x;
+            r = &'L_x mut x;
+            *r = 2;
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_debug_snapshot!((&facts.var_used_at, &facts.var_defined_at), @r###"
+    (
+        [
+            (
+                "pair",
+                "b",
+            ),
+            (
+                "x",
+                "b",
+            ),
+            (
+                "x",
+                "c",
+            ),
+            (
+                "r",
+                "d",
+            ),
+        ],
+        [
+            (
+                "x",
+                "a",
+            ),
+            (
+                "r",
+                "c",
+            ),
+        ],
+    )
+    "###);
+}
+
+#[test]
+fn var_drops() {
+    // Structs which are not `Copy` are dropped at the exits of the function
+    let program = "
+        #[derive(Copy)]
+        struct Point { x: i32 }
+
+        let v: Vec;
+        let point: Point;
+        let x: i32;
+
+        bb0: {
+            v = Vec_new();
+            goto bb1, bb2;
+        }
+        bb1: {
+            x = 1;
+        }
+        bb2: {
+        }
+    ";
+    assert_debug_snapshot!(expect_facts(program).var_dropped_at, @r###"
+    [
+        (
+            "v",
+            "b",
+        ),
+        (
+            "v",
+            "c",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn var_origins() {
+    // All the origins of a variable are dereferenced by its uses, and by its drops when it needs
+    // to be dropped
+    let program = "
+        struct Wrapper<'w> { value: &'w i32 }
+
+        let r: &'r &'r1 i32;
+        let wrapper: Wrapper<'a>;
+
+        bb0: {
+            use(copy r);
+        }
+    ";
+    let facts = expect_facts(program);
+    assert_debug_snapshot!(
+        (&facts.use_of_var_derefs_origin, &facts.drop_of_var_derefs_origin),
+        @r###"
+    (
+        [
+            (
+                "r",
+                "'r",
+            ),
+            (
+                "r",
+                "'r1",
+            ),
+            (
+                "wrapper",
+                "'a",
+            ),
+        ],
+        [
+            (
+                "wrapper",
+                "'a",
+            ),
+        ],
+    )
+    "###
+    );
+}
+
+#[test]
+fn index_variables_are_used() {
+    let program = "
+        let array: [i32; 2];
+        let i: i32;
+        let j: i32;
+        let x: i32;
+
+        bb0: {
+            x = copy array[i];
+            array[j] = 0;
+        }
+    ";
+    assert_debug_snapshot!(expect_facts(program).var_used_at, @r###"
+    [
+        (
+            "array",
+            "a",
+        ),
+        (
+            "i",
+            "a",
+        ),
+        (
+            "array",
+            "b",
+        ),
+        (
+            "j",
+            "b",
+        ),
+    ]
+    "###);
+}
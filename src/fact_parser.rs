@@ -2,12 +2,14 @@
 //!
 //! ```notrust
 //! Program    := Fact* Statement,
-//! Statement  := Ident: String { Fact* goto Ident* }
+//! Statement  := Ident: String Location? { Fact* goto Ident* }
 //! Fact       := Ident ( Symbol, )*
-//! Ident      := [a-zA-Z_][a-zA-Z_0-9]*    /* regular expression */
+//! Ident      := [a-zA-Z_][a-zA-Z_0-9\[\]]*    /* regular expression, e.g. bb0[1] */
 //! Symbol     := Ident | 'Ident
 //! String     := "[^"]*"   /* regular expression */
+//! Location   := @ [^ \t\n{]+   /* regular expression, e.g. main.rs:42 */
 //! ```
+use crate::fact_emitter::{CfgEdgeKind, Facts, Node, Origin};
 use eyre::WrapErr;
 use itertools::Itertools;
 use std::collections::HashMap;
@@ -21,6 +23,9 @@ pub struct Program {
 pub struct Statement {
     pub name: String,
     pub text: String,
+
+    /// The location in the Rust source of the statement, e.g. `main.rs:42`.
+    pub location: Option<String>,
     pub facts: Vec<Fact>,
     pub successors: Vec<String>,
 }
@@ -39,13 +44,17 @@ peg::parser! {
             }
         }
 
-        rule _ = quiet!{[' ' | '\n']*}
-        rule __ = quiet!{[' ' | '\n']+}
+        rule _ = quiet!{[' ' | '\t' | '\n']*}
+        rule __ = quiet!{[' ' | '\t' | '\n']+}
 
         rule comment() -> () = _ "//" [^'\n']* "\n" { () }
 
-        rule statement() -> Statement = name:ident() _ ":" _ text:string() _ "{" _ facts:fact()**__ _ "goto" _ successors:ident()**__ _ "}" {
-            Statement { name, text, facts, successors }
+        rule statement() -> Statement = name:ident() _ ":" _ text:string() _ location:location()? _ "{" _ facts:fact()**__ _ "goto" _ successors:ident()**__ _ "}" {
+            Statement { name, text, location, facts, successors }
+        }
+
+        rule location() -> String = "@" _ t:$([^' ' | '\t' | '\n' | '{']+) {
+            t.to_string()
         }
 
         rule fact() -> Fact = comment()* _ name:ident() _ "(" _ arguments:symbol()**comma() _ ")" {
@@ -56,10 +65,13 @@ peg::parser! {
 
         rule symbol() -> String = ident() / string()
 
-        rule ident() -> String = t:$("'"?['a'..='z' | 'A'..='Z' | '_' | '0' ..= '9' | '*' ]+) {
+        // Names can be scoped by the function they're from, like `main::bb0[1]` or `main::'x`
+        rule ident() -> String = t:$(ident_segment() ++ "::") {
             t.to_string()
         }
 
+        rule ident_segment() = "'"?['a'..='z' | 'A'..='Z' | '_' | '0' ..= '9' | '*' | '[' | ']' ]+
+
         rule string() -> String = ['"'] t:$([^'"']*) ['"'] {
             t.to_string()
         }
@@ -87,13 +99,109 @@ pub fn generate_facts(input: &str, output_path: &Path) -> eyre::Result<()> {
     Ok(())
 }
 
-const EXPECTED_GLOBAL_FACT_NAMES: &[&str] = &["mark_as_loan_origin"];
+/// Parses a fact file into `Facts`, e.g. to analyze it with the in-crate solver.
+pub fn read_facts(input: &str) -> eyre::Result<Facts> {
+    let program = parse_facts(input).wrap_err("failed to parse input")?;
+    let rows = collect_facts(&program)?;
+    Ok(facts_from_rows(&rows))
+}
+
+/// The facts of the rows of each relation of the fact file format, with the node of the local
+/// facts as their last atom, like in the Soufflé `.facts` files.
+pub(crate) fn facts_from_rows(rows: &HashMap<String, Vec<Vec<String>>>) -> Facts {
+    let origins = |name: &str| -> Vec<Origin> {
+        rows[name]
+            .iter()
+            .map(|row| row[0].as_str().into())
+            .collect()
+    };
+    let origin_nodes = |name: &str| -> Vec<(Origin, Node)> {
+        rows[name]
+            .iter()
+            .map(|row| (row[0].as_str().into(), row[1].as_str().into()))
+            .collect()
+    };
+
+    let mut facts = Facts::default();
+    facts.access_origin = origin_nodes("access_origin");
+    facts.cfg_edge = rows["cfg_edge"]
+        .iter()
+        .map(|row| (row[0].as_str().into(), row[1].as_str().into()))
+        .collect();
+
+    // The fact file format has no node locations, the other formats do. Rows whose line or
+    // column are not numbers are ignored.
+    facts.node_location = rows
+        .get("node_location")
+        .into_iter()
+        .flatten()
+        .filter_map(|row| {
+            let (line, column) = (row[0].parse().ok()?, row[1].parse().ok()?);
+            Some((row[2].as_str().into(), line, column))
+        })
+        .collect();
+
+    // The fact file format has no edge kinds, the other formats do. Unknown kinds are ignored.
+    facts.cfg_edge_kind = rows
+        .get("cfg_edge_kind")
+        .into_iter()
+        .flatten()
+        .filter_map(|row| {
+            let kind = CfgEdgeKind::from_name(&row[2])?;
+            Some((row[0].as_str().into(), row[1].as_str().into(), kind))
+        })
+        .collect();
+    facts.clear_origin = origin_nodes("clear_origin");
+    facts.introduce_subset = rows["introduce_subset"]
+        .iter()
+        .map(|row| {
+            (
+                row[0].as_str().into(),
+                row[1].as_str().into(),
+                row[2].as_str().into(),
+            )
+        })
+        .collect();
+    facts.invalidate_origin = origin_nodes("invalidate_origin");
+    facts.loan_issued_at = rows["loan_issued_at"]
+        .iter()
+        .map(|row| {
+            (
+                row[0].as_str().into(),
+                row[1].as_str().into(),
+                row[2].as_str().into(),
+            )
+        })
+        .collect();
+    facts.node_text = rows["node_text"]
+        .iter()
+        .map(|row| (row[0].clone(), row[1].as_str().into()))
+        .collect();
+    facts.source_location = rows["source_location"]
+        .iter()
+        .map(|row| (row[1].as_str().into(), row[0].clone()))
+        .collect();
+    facts.mark_as_loan_origin = origins("mark_as_loan_origin");
+    facts.placeholder_origin = origins("placeholder_origin");
+    facts.known_placeholder_subset = rows["known_placeholder_subset"]
+        .iter()
+        .map(|row| (row[0].as_str().into(), row[1].as_str().into()))
+        .collect();
+    facts
+}
+
+pub(crate) const EXPECTED_GLOBAL_FACT_NAMES: &[&str] = &[
+    "mark_as_loan_origin",
+    "placeholder_origin",
+    "known_placeholder_subset",
+];
 const EXPECTED_LOCAL_FACT_NAMES: &[&str] = &[
     "access_origin",
     "cfg_edge",
     "clear_origin",
     "introduce_subset",
     "invalidate_origin",
+    "loan_issued_at",
 ];
 
 /// Maps a program into a set of facts:
@@ -107,6 +215,7 @@ fn collect_facts(program: &Program) -> eyre::Result<HashMap<String, Vec<Vec<Stri
         facts.insert(expected.to_string(), vec![]);
     }
     facts.insert("node_text".to_string(), vec![]);
+    facts.insert("source_location".to_string(), vec![]);
     facts.insert("cfg_edge".to_string(), vec![]);
 
     for global_fact in &program.global_facts {
@@ -132,6 +241,12 @@ fn collect_facts(program: &Program) -> eyre::Result<HashMap<String, Vec<Vec<Stri
             .get_mut("node_text")
             .unwrap()
             .push(vec![statement.text.clone(), statement.name.clone()]);
+        if let Some(location) = &statement.location {
+            facts
+                .get_mut("source_location")
+                .unwrap()
+                .push(vec![location.clone(), statement.name.clone()]);
+        }
 
         for successor in &statement.successors {
             facts
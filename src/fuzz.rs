@@ -0,0 +1,164 @@
+//! Randomized differential testing of the solvers: programs are generated from consecutive
+//! seeds, see `generate_stress_program`, and the errors the optimized and naive solvers compute
+//! over their facts must be the same.
+//!
+//! A program on which they diverge is shrunk before being reported: the generator prints one
+//! statement or declaration per line, and lines are removed one at a time, as long as the
+//! program stays valid and the solvers still diverge on it, until no line can be removed.
+
+use crate::fact_emitter::{emit_facts, Facts};
+use crate::solver::{solve, solve_naive, SolverOutput};
+use crate::stress::{generate_stress_program, StressOptions};
+use crate::validation::validate;
+use std::fmt;
+
+#[cfg(test)]
+mod test;
+
+/// The programs generated to test the solvers.
+#[derive(Clone, Debug)]
+pub struct FuzzOptions {
+    /// The seed of the first program, the next ones use the following seeds.
+    pub seed: u64,
+
+    /// The number of programs.
+    pub runs: usize,
+
+    /// The shape of the programs. Its seed is replaced by each program's.
+    pub program: StressOptions,
+}
+
+impl Default for FuzzOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            runs: 100,
+            program: StressOptions {
+                seed: 0,
+                blocks: 4,
+                struct_depth: 2,
+                statements_per_block: 3,
+                variables: 2,
+            },
+        }
+    }
+}
+
+/// A program on which the solvers compute different errors.
+#[derive(Debug)]
+pub struct Divergence {
+    /// The seed the program was generated from.
+    pub seed: u64,
+
+    /// The shrunk program.
+    pub program: String,
+    pub optimized: SolverOutput,
+    pub naive: SolverOutput,
+}
+
+/// Generates the programs, and returns the first one the solvers diverge on, shrunk.
+pub fn fuzz_solvers(options: &FuzzOptions) -> Option<Divergence> {
+    find_divergence(options, &|facts| {
+        !same_errors(&solve(facts), &solve_naive(facts))
+    })
+}
+
+// Generates the programs, and returns the first one whose facts diverge, shrunk.
+fn find_divergence(options: &FuzzOptions, diverges: &dyn Fn(&Facts) -> bool) -> Option<Divergence> {
+    let program_diverges = |program: &str| {
+        let is_valid = validate(program)
+            .map(|diagnostics| !diagnostics.iter().any(|d| d.is_error()))
+            .unwrap_or(false);
+        is_valid && emit_facts(program).is_ok_and(|facts| diverges(&facts))
+    };
+
+    (0..options.runs as u64)
+        .map(|run| options.seed.wrapping_add(run))
+        .find_map(|seed| {
+            let shape = StressOptions {
+                seed,
+                ..options.program.clone()
+            };
+            let program = generate_stress_program(&shape);
+            if !program_diverges(&program) {
+                return None;
+            }
+
+            let program = shrink_program(&program, &program_diverges);
+            let facts = emit_facts(&program).expect("shrunk programs are valid");
+            Some(Divergence {
+                seed,
+                program,
+                optimized: solve(&facts),
+                naive: solve_naive(&facts),
+            })
+        })
+}
+
+/// Removes the lines of the program one at a time, while it keeps the property, until none
+/// can be removed. Block headers, gotos, and closing braces are kept, so that the CFG stays the
+/// same. The program is dedented first, without its leading and trailing blank lines, like the
+/// pretty-printer's output.
+pub fn shrink_program(program: &str, keeps_property: &dyn Fn(&str) -> bool) -> String {
+    let indent = program
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut lines: Vec<&str> = program
+        .lines()
+        .map(|line| line.get(indent..).unwrap_or_else(|| line.trim_start()))
+        .skip_while(|line| line.is_empty())
+        .collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    let is_removable = |line: &str| {
+        let line = line.trim();
+        !line.is_empty() && !line.ends_with('{') && line != "}" && !line.starts_with("goto ")
+    };
+
+    let mut shrunk = true;
+    while shrunk {
+        shrunk = false;
+        let mut idx = 0;
+        while idx < lines.len() {
+            if is_removable(lines[idx]) {
+                let mut candidate = lines.clone();
+                candidate.remove(idx);
+                if keeps_property(&to_program(&candidate)) {
+                    lines = candidate;
+                    shrunk = true;
+                    continue;
+                }
+            }
+            idx += 1;
+        }
+    }
+    to_program(&lines)
+}
+
+fn to_program(lines: &[&str]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Whether the solvers' outputs have the same errors, whatever the loans of the origins.
+pub(crate) fn same_errors(optimized: &SolverOutput, naive: &SolverOutput) -> bool {
+    optimized.invalidated_origin_accessed == naive.invalidated_origin_accessed
+        && optimized.placeholder_subset_error == naive.placeholder_subset_error
+        && optimized.loan_escapes_into_placeholder == naive.loan_escapes_into_placeholder
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "the solvers diverge on the program of seed {}:",
+            self.seed
+        )?;
+        writeln!(f, "{}", self.program)?;
+        writeln!(f, "optimized: {:#?}", self.optimized)?;
+        writeln!(f, "naive: {:#?}", self.naive)
+    }
+}
@@ -0,0 +1,76 @@
+use super::*;
+use insta::assert_display_snapshot;
+
+fn small(runs: usize) -> FuzzOptions {
+    FuzzOptions {
+        runs,
+        ..FuzzOptions::default()
+    }
+}
+
+#[test]
+fn solvers_agree() {
+    assert!(fuzz_solvers(&small(20)).is_none());
+}
+
+#[test]
+fn shrink_divergence() {
+    // Programs with an error stand for the ones the solvers diverge on: they're shrunk to the
+    // statements the error needs
+    let divergence = find_divergence(&small(20), &|facts| {
+        !solve(facts).invalidated_origin_accessed.is_empty()
+    })
+    .expect("one of the programs has an error");
+    assert_display_snapshot!(divergence.program, @r###"
+    struct S0 { value: i32, count: i32 }
+    struct S1 { left: S0, right: S0 }
+    struct S2 { left: S1, right: S1 }
+
+    let x1: S2;
+    let r1: &'r1 i32;
+    let y: i32;
+
+    bb0: {
+        r1 = &'L1 x1.left.right.count;
+        goto bb1, bb0;
+    }
+
+    bb1: {
+        goto bb2;
+    }
+
+    bb2: {
+        x1.left.right.count = 38;
+        goto bb3, bb0;
+    }
+
+    bb3: {
+        y = copy *r1;
+    }
+    "###);
+}
+
+#[test]
+fn shrink_lines() {
+    let program = "
+        let x: i32;
+        let y: i32;
+        bb0: {
+            x = 1;
+            y = 2;
+            goto bb1;
+        }
+        bb1: {
+            use(copy y);
+        }
+    ";
+    let shrunk = shrink_program(program, &|program| program.contains("y ="));
+    assert_display_snapshot!(shrunk, @r###"
+    bb0: {
+        y = 2;
+        goto bb1;
+    }
+    bb1: {
+    }
+    "###);
+}
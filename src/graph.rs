@@ -0,0 +1,350 @@
+//! Graph algorithms over the basic blocks of a program: reverse post-order, dominators, and
+//! post-dominators.
+//!
+//! The blocks are indexed in program order, and the first block is the entry. Dominators are
+//! computed with the iterative algorithm of Cooper, Harvey and Kennedy, in "A Simple, Fast
+//! Dominance Algorithm". Post-dominators are the dominators of the reversed graph, entered from
+//! a virtual exit following all the blocks without successors.
+//!
+//! The CFG between the nodes of a program, its statements, is also available, with the kinds of
+//! its edges, for the analyses and visualizations which don't need to re-derive it from the
+//! `cfg_edge` facts.
+
+use crate::ast::{Name, Program};
+use crate::ast_parser::parse_ast;
+use crate::fact_emitter::Node;
+
+#[cfg(test)]
+mod test;
+
+/// The CFG of a program, between its basic blocks.
+#[derive(Debug)]
+pub struct BlockGraph {
+    names: Vec<Name>,
+    successors: Vec<Vec<usize>>,
+}
+
+/// The immediate dominator of each block, if it has one: the entry block, and the blocks which
+/// are not reachable from it, don't.
+#[derive(Debug)]
+pub struct Dominators {
+    immediate_dominators: Vec<Option<usize>>,
+}
+
+/// The CFG of a program, between its nodes: each statement is a node, and a block without
+/// statements is a single node, so that its successors are reachable through it. The nodes are
+/// named like in the facts, e.g. `bb0[1]`, and are indexed in program order.
+#[derive(Debug)]
+pub struct NodeGraph {
+    nodes: Vec<Node>,
+
+    /// The edges `(from, to, kind)`, in the order of the `cfg_edge` facts.
+    edges: Vec<(usize, usize, EdgeKind)>,
+}
+
+/// How control flows along an edge between two nodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// From a statement to the next one in its block.
+    Statement,
+
+    /// From the last node of a block to the first node of one of its successors.
+    Goto,
+}
+
+/// Parses the input program and returns the graph of its basic blocks.
+pub fn block_graph(input: &str) -> eyre::Result<BlockGraph> {
+    Ok(BlockGraph::from_program(&parse_ast(input)?))
+}
+
+impl BlockGraph {
+    /// The graph between the program's blocks. Successors which are not blocks of the program
+    /// are ignored.
+    pub(crate) fn from_program(program: &Program) -> Self {
+        let names: Vec<_> = program.basic_blocks.iter().map(|bb| bb.name).collect();
+        let successors = program
+            .basic_blocks
+            .iter()
+            .map(|bb| {
+                bb.terminator
+                    .successors()
+                    .iter()
+                    .filter_map(|successor| names.iter().position(|name| name == successor))
+                    .collect()
+            })
+            .collect();
+        Self { names, successors }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn name(&self, block: usize) -> &str {
+        &self.names[block]
+    }
+
+    pub fn block(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|candidate| candidate == name)
+    }
+
+    pub fn successors(&self, block: usize) -> &[usize] {
+        &self.successors[block]
+    }
+
+    /// The blocks reachable from the entry, in reverse post-order: each block comes before its
+    /// successors, except along back edges.
+    pub fn reverse_post_order(&self) -> Vec<usize> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        reverse_post_order(&self.successors, 0)
+    }
+
+    /// The blocks which are not reachable from the entry, in program order.
+    pub fn unreachable_blocks(&self) -> Vec<usize> {
+        let reachable = self.reverse_post_order();
+        (0..self.len())
+            .filter(|block| !reachable.contains(block))
+            .collect()
+    }
+
+    pub fn dominators(&self) -> Dominators {
+        if self.is_empty() {
+            return Dominators {
+                immediate_dominators: Vec::new(),
+            };
+        }
+        Dominators {
+            immediate_dominators: immediate_dominators(&self.successors, 0),
+        }
+    }
+
+    /// The post-dominators of the blocks: a block's immediate post-dominator is `None` when it's
+    /// only post-dominated by the exit of the function, or when it can't reach the exit.
+    pub fn post_dominators(&self) -> Dominators {
+        // The reversed graph, where the virtual exit is the last node
+        let exit = self.len();
+        let mut predecessors = vec![Vec::new(); exit + 1];
+        for (block, successors) in self.successors.iter().enumerate() {
+            for &successor in successors {
+                predecessors[successor].push(block);
+            }
+            if successors.is_empty() {
+                predecessors[exit].push(block);
+            }
+        }
+
+        let mut immediate_dominators = immediate_dominators(&predecessors, exit);
+        immediate_dominators.pop();
+        for dominator in &mut immediate_dominators {
+            if *dominator == Some(exit) {
+                *dominator = None;
+            }
+        }
+        Dominators {
+            immediate_dominators,
+        }
+    }
+}
+
+/// Parses the input program and returns the graph of its nodes.
+pub fn node_graph(input: &str) -> eyre::Result<NodeGraph> {
+    Ok(parse_ast(input)?.cfg())
+}
+
+impl Program {
+    /// The CFG between the nodes of the program. Successors which are not blocks of the program
+    /// are ignored.
+    pub fn cfg(&self) -> NodeGraph {
+        // The index of the first node of each block
+        let mut first_nodes = Vec::with_capacity(self.basic_blocks.len());
+        let mut nodes = Vec::new();
+        for bb in &self.basic_blocks {
+            first_nodes.push(nodes.len());
+            for idx in 0..bb.statements.len().max(1) {
+                nodes.push(Node::from(format!("{}[{}]", bb.name, idx)));
+            }
+        }
+
+        let mut edges = Vec::new();
+        for (block, bb) in self.basic_blocks.iter().enumerate() {
+            let first = first_nodes[block];
+            let last = first + bb.statements.len().max(1) - 1;
+            for node in first..last {
+                edges.push((node, node + 1, EdgeKind::Statement));
+            }
+            for successor in &bb.terminator.successors() {
+                let target = self
+                    .basic_blocks
+                    .iter()
+                    .position(|bb| bb.name == *successor);
+                if let Some(target) = target {
+                    edges.push((last, first_nodes[target], EdgeKind::Goto));
+                }
+            }
+        }
+        NodeGraph { nodes, edges }
+    }
+}
+
+impl NodeGraph {
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn node(&self, idx: usize) -> &Node {
+        &self.nodes[idx]
+    }
+
+    pub fn index(&self, node: &Node) -> Option<usize> {
+        self.nodes.iter().position(|candidate| candidate == node)
+    }
+
+    /// The edges `(from, to, kind)` between the indices of the nodes.
+    pub fn edges(&self) -> &[(usize, usize, EdgeKind)] {
+        &self.edges
+    }
+
+    /// The successors of the node, with the kind of the edge to them.
+    pub fn successors(&self, node: usize) -> impl Iterator<Item = (usize, EdgeKind)> + '_ {
+        self.edges
+            .iter()
+            .filter(move |&&(from, _, _)| from == node)
+            .map(|&(_, to, kind)| (to, kind))
+    }
+
+    /// The predecessors of the node, with the kind of the edge from them.
+    pub fn predecessors(&self, node: usize) -> impl Iterator<Item = (usize, EdgeKind)> + '_ {
+        self.edges
+            .iter()
+            .filter(move |&&(_, to, _)| to == node)
+            .map(|&(from, _, kind)| (from, kind))
+    }
+
+    /// The nodes reachable from the entry, in reverse post-order.
+    pub fn reverse_post_order(&self) -> Vec<usize> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let mut successors = vec![Vec::new(); self.len()];
+        for &(from, to, _) in &self.edges {
+            successors[from].push(to);
+        }
+        reverse_post_order(&successors, 0)
+    }
+}
+
+impl Dominators {
+    pub fn immediate_dominator(&self, block: usize) -> Option<usize> {
+        self.immediate_dominators[block]
+    }
+
+    /// Whether `dominator` dominates `block`. Every block dominates itself.
+    pub fn dominates(&self, dominator: usize, block: usize) -> bool {
+        let mut current = Some(block);
+        while let Some(candidate) = current {
+            if candidate == dominator {
+                return true;
+            }
+            current = self.immediate_dominators[candidate];
+        }
+        false
+    }
+}
+
+fn reverse_post_order(successors: &[Vec<usize>], entry: usize) -> Vec<usize> {
+    let mut visited = vec![false; successors.len()];
+    let mut post_order = Vec::new();
+
+    // Each node on the stack, with the index of its next successor to visit
+    let mut stack = vec![(entry, 0)];
+    visited[entry] = true;
+    while let Some((node, idx)) = stack.pop() {
+        match successors[node].get(idx) {
+            Some(&successor) => {
+                stack.push((node, idx + 1));
+                if !visited[successor] {
+                    visited[successor] = true;
+                    stack.push((successor, 0));
+                }
+            }
+            None => post_order.push(node),
+        }
+    }
+    post_order.reverse();
+    post_order
+}
+
+// The immediate dominators of the nodes reachable from the entry, in the graph of the given
+// successors.
+fn immediate_dominators(successors: &[Vec<usize>], entry: usize) -> Vec<Option<usize>> {
+    let order = reverse_post_order(successors, entry);
+    let mut rpo_index = vec![usize::MAX; successors.len()];
+    for (idx, &node) in order.iter().enumerate() {
+        rpo_index[node] = idx;
+    }
+    let mut predecessors = vec![Vec::new(); successors.len()];
+    for (node, node_successors) in successors.iter().enumerate() {
+        for &successor in node_successors {
+            predecessors[successor].push(node);
+        }
+    }
+
+    let mut dominators = vec![None; successors.len()];
+    dominators[entry] = Some(entry);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in order.iter().skip(1) {
+            let mut processed = predecessors[node]
+                .iter()
+                .copied()
+                .filter(|&predecessor| dominators[predecessor].is_some());
+            let first = processed
+                .next()
+                .expect("reachable nodes have a processed predecessor");
+            let dominator = processed.fold(first, |dominator, predecessor| {
+                intersect(&dominators, &rpo_index, dominator, predecessor)
+            });
+            if dominators[node] != Some(dominator) {
+                dominators[node] = Some(dominator);
+                changed = true;
+            }
+        }
+    }
+
+    // The entry has no immediate dominator
+    dominators[entry] = None;
+    dominators
+}
+
+// The nearest common dominator of the two nodes, walking up the dominator tree being built.
+fn intersect(
+    dominators: &[Option<usize>],
+    rpo_index: &[usize],
+    mut node1: usize,
+    mut node2: usize,
+) -> usize {
+    while node1 != node2 {
+        while rpo_index[node1] > rpo_index[node2] {
+            node1 = dominators[node1].expect("processed nodes have a dominator");
+        }
+        while rpo_index[node2] > rpo_index[node1] {
+            node2 = dominators[node2].expect("processed nodes have a dominator");
+        }
+    }
+    node1
+}
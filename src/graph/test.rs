@@ -0,0 +1,253 @@
+use super::*;
+use insta::assert_debug_snapshot;
+
+// The names of the blocks' immediate dominators, for more readable snapshots.
+fn named(graph: &BlockGraph, dominators: &Dominators) -> Vec<(String, Option<String>)> {
+    (0..graph.len())
+        .map(|block| {
+            let dominator = dominators.immediate_dominator(block);
+            (
+                graph.name(block).to_string(),
+                dominator.map(|dominator| graph.name(dominator).to_string()),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn diamond_with_loop() {
+    let program = "
+        bb0: { goto bb1, bb2; }
+        bb1: { goto bb3; }
+        bb2: { goto bb3; }
+        bb3: { goto bb0, bb4; }
+        bb4: { }
+    ";
+    let graph = block_graph(program).unwrap();
+    let order: Vec<_> = graph
+        .reverse_post_order()
+        .into_iter()
+        .map(|block| graph.name(block))
+        .collect();
+    assert_debug_snapshot!(order, @r###"
+    [
+        "bb0",
+        "bb2",
+        "bb1",
+        "bb3",
+        "bb4",
+    ]
+    "###);
+    assert_debug_snapshot!(named(&graph, &graph.dominators()), @r###"
+    [
+        (
+            "bb0",
+            None,
+        ),
+        (
+            "bb1",
+            Some(
+                "bb0",
+            ),
+        ),
+        (
+            "bb2",
+            Some(
+                "bb0",
+            ),
+        ),
+        (
+            "bb3",
+            Some(
+                "bb0",
+            ),
+        ),
+        (
+            "bb4",
+            Some(
+                "bb3",
+            ),
+        ),
+    ]
+    "###);
+    assert_debug_snapshot!(named(&graph, &graph.post_dominators()), @r###"
+    [
+        (
+            "bb0",
+            Some(
+                "bb3",
+            ),
+        ),
+        (
+            "bb1",
+            Some(
+                "bb3",
+            ),
+        ),
+        (
+            "bb2",
+            Some(
+                "bb3",
+            ),
+        ),
+        (
+            "bb3",
+            Some(
+                "bb4",
+            ),
+        ),
+        (
+            "bb4",
+            None,
+        ),
+    ]
+    "###);
+
+    let dominators = graph.dominators();
+    let (bb0, bb1, bb3) = (0, 1, 3);
+    assert!(dominators.dominates(bb0, bb3));
+    assert!(dominators.dominates(bb3, bb3));
+    assert!(!dominators.dominates(bb1, bb3));
+}
+
+#[test]
+fn multiple_exits() {
+    // Blocks leaving the function separately are only post-dominated by its exit
+    let program = "
+        bb0: { goto bb1; }
+        bb1: { goto bb2, bb3; }
+        bb2: { }
+        bb3: { }
+    ";
+    let graph = block_graph(program).unwrap();
+    assert_debug_snapshot!(named(&graph, &graph.post_dominators()), @r###"
+    [
+        (
+            "bb0",
+            Some(
+                "bb1",
+            ),
+        ),
+        (
+            "bb1",
+            None,
+        ),
+        (
+            "bb2",
+            None,
+        ),
+        (
+            "bb3",
+            None,
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn unreachable_blocks() {
+    // Blocks unreachable from the entry have no dominator, and blocks which can't reach the exit
+    // have no post-dominator
+    let program = "
+        bb0: { goto bb1; }
+        bb1: { goto bb1; }
+        bb2: { goto bb0; }
+    ";
+    let graph = block_graph(program).unwrap();
+    assert_debug_snapshot!(graph.reverse_post_order(), @r###"
+    [
+        0,
+        1,
+    ]
+    "###);
+    assert_debug_snapshot!(named(&graph, &graph.dominators()), @r###"
+    [
+        (
+            "bb0",
+            None,
+        ),
+        (
+            "bb1",
+            Some(
+                "bb0",
+            ),
+        ),
+        (
+            "bb2",
+            None,
+        ),
+    ]
+    "###);
+    assert_debug_snapshot!(named(&graph, &graph.post_dominators()), @r###"
+    [
+        (
+            "bb0",
+            None,
+        ),
+        (
+            "bb1",
+            None,
+        ),
+        (
+            "bb2",
+            None,
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn node_graph_edges() {
+    let program = "
+        let x: i32;
+        bb0: { x = 1; x = 2; goto bb1, bb2; }
+        bb1: { goto bb2; }
+        bb2: { x = 3; goto bb0, bb3; }
+        bb3: { }
+    ";
+    let graph = node_graph(program).unwrap();
+    let edges: Vec<_> = graph
+        .edges()
+        .iter()
+        .map(|&(from, to, kind)| {
+            format!("{} -> {}: {:?}", graph.node(from).0, graph.node(to).0, kind)
+        })
+        .collect();
+    assert_debug_snapshot!(edges, @r###"
+    [
+        "bb0[0] -> bb0[1]: Statement",
+        "bb0[1] -> bb1[0]: Goto",
+        "bb0[1] -> bb2[0]: Goto",
+        "bb1[0] -> bb2[0]: Goto",
+        "bb2[0] -> bb0[0]: Goto",
+        "bb2[0] -> bb3[0]: Goto",
+    ]
+    "###);
+
+    // The same edges as the facts
+    let facts = crate::emit_facts(program).unwrap();
+    let fact_edges: Vec<_> = facts
+        .cfg_edge
+        .iter()
+        .map(|(from, to)| (graph.index(from).unwrap(), graph.index(to).unwrap()))
+        .collect();
+    let graph_edges: Vec<_> = graph
+        .edges()
+        .iter()
+        .map(|&(from, to, _)| (from, to))
+        .collect();
+    assert_eq!(fact_edges, graph_edges);
+
+    let order: Vec<_> = graph
+        .reverse_post_order()
+        .into_iter()
+        .map(|node| graph.node(node).0.as_str())
+        .collect();
+    assert_eq!(order, ["bb0[0]", "bb0[1]", "bb1[0]", "bb2[0]", "bb3[0]"]);
+    assert_eq!(
+        graph
+            .predecessors(graph.index(&"bb2[0]".into()).unwrap())
+            .count(),
+        2
+    );
+}
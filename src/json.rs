@@ -0,0 +1,138 @@
+//! Minimal JSON support: parsing the diagnostics rustc emits with `--error-format=json`, and
+//! printing the outputs of this crate as JSON.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Returns the value of the given key, if this is an object containing it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(candidate, _)| candidate == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(values) => values,
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (idx, value) in values.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (idx, (key, value)) in members.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+peg::parser! {
+    grammar json_parser() for str {
+        pub rule value() -> Json = _ v:(
+            "null" { Json::Null } /
+            "true" { Json::Bool(true) } /
+            "false" { Json::Bool(false) } /
+            n:number() { Json::Number(n) } /
+            s:string() { Json::String(s) } /
+            "[" _ values:value()**"," _ "]" { Json::Array(values) } /
+            "{" _ members:member()**"," _ "}" { Json::Object(members) }
+        ) _ { v }
+
+        rule member() -> (String, Json) = _ key:string() _ ":" value:value() { (key, value) }
+
+        rule number() -> f64 = n:$("-"? ['0'..='9']+ ("." ['0'..='9']+)? (['e' | 'E'] ['+' | '-']? ['0'..='9']+)?) {?
+            n.parse().or(Err("a number"))
+        }
+
+        rule string() -> String = "\"" chars:character()* "\"" {
+            chars.into_iter().collect()
+        }
+
+        rule character() -> char = (
+            c:$([^ '"' | '\\']) { c.chars().next().unwrap() } /
+            "\\u" hex:$(['0'..='9' | 'a'..='f' | 'A'..='F']*<4>) {?
+                // Surrogate pairs are not decoded, rustc's diagnostics don't need them
+                let code = u32::from_str_radix(hex, 16).or(Err("a unicode escape"))?;
+                Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+            } /
+            "\\" c:$(['"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't']) {
+                match c {
+                    "b" => '\u{8}',
+                    "f" => '\u{c}',
+                    "n" => '\n',
+                    "r" => '\r',
+                    "t" => '\t',
+                    c => c.chars().next().unwrap(),
+                }
+            }
+        )
+
+        rule _ = quiet!{[' ' | '\n' | '\r' | '\t']*}
+    }
+}
+
+pub fn parse_json(input: &str) -> eyre::Result<Json> {
+    Ok(json_parser::value(input)?)
+}
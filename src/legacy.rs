@@ -0,0 +1,621 @@
+//! Import of the fact sets of the upstream Polonius test corpus (the `nll-facts` directories
+//! dumped by rustc), translated to the relations of the new rules.
+//!
+//! Each relation is read from its tab-separated `.facts` file, and missing files are empty
+//! relations: older fact sets don't have all of them, and some were renamed over time.
+//!
+//! The translation maps the legacy relations onto the new ones:
+//! - the points of the legacy CFG (`Start(bb0[1])`, `Mid(bb0[1])`) are nodes, named
+//!   `bb0_1_start` and `bb0_1_mid`,
+//! - loans are origins, named after the loan (`'L_bw0`), and issuing a loan into an origin
+//!   clears the loan and introduces a subset into that origin,
+//! - `subset_base` is `introduce_subset`, `loan_invalidated_at` is `invalidate_origin`, and
+//!   `loan_killed_at` clears the loan,
+//! - variables are accessed where they're used or dropped, and their origins are cleared
+//!   where they're defined,
+//! - universal regions are placeholders, and so are the origins of placeholder loans.
+//!
+//! The legacy rules are also available, to compare the loan errors of both formulations on the
+//! same fact sets.
+//!
+//! In the other direction, the facts emitted for a program can be exported as a legacy fact set,
+//! for upstream Polonius to analyze: the CFG, whose points are the nodes, the move paths moved
+//! out of, assigned to, and accessed at each node, which its move errors are computed from, and
+//! the variables used, defined and dropped at each node, with the origins in their types, which
+//! its liveness is computed from. The loans issued into placeholders are exported as their
+//! placeholder loans, rather than as borrows.
+
+mod rules;
+
+#[cfg(test)]
+mod test;
+
+use crate::ast::Name;
+use crate::fact_emitter::{Facts, Loan, Node, Origin};
+use crate::solver::solve;
+use eyre::WrapErr;
+pub(crate) use rules::legacy_relations;
+pub use rules::{legacy_errors, legacy_move_errors};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// The relations of a legacy fact set that the translation uses, with their atoms as they
+/// appear in the `.facts` files.
+#[derive(Default, Debug)]
+pub struct LegacyFacts {
+    /// `(origin, loan, point)`
+    pub loan_issued_at: Vec<(String, String, String)>,
+    pub cfg_edge: Vec<(String, String)>,
+
+    /// `(loan, point)`
+    pub loan_killed_at: Vec<(String, String)>,
+
+    /// `(origin1, origin2, point)`
+    pub subset_base: Vec<(String, String, String)>,
+
+    /// `(point, loan)`
+    pub loan_invalidated_at: Vec<(String, String)>,
+
+    /// `(variable, point)`
+    pub var_used_at: Vec<(String, String)>,
+    pub var_defined_at: Vec<(String, String)>,
+    pub var_dropped_at: Vec<(String, String)>,
+
+    /// `(variable, origin)`
+    pub use_of_var_derefs_origin: Vec<(String, String)>,
+    pub drop_of_var_derefs_origin: Vec<(String, String)>,
+
+    pub universal_region: Vec<String>,
+
+    /// `(origin, loan)`: the placeholder loan of a universal region
+    pub placeholder: Vec<(String, String)>,
+
+    /// `(origin1, origin2)`
+    pub known_placeholder_subset: Vec<(String, String)>,
+
+    /// `(path, variable)`
+    pub path_is_var: Vec<(String, String)>,
+
+    /// `(child, parent)`
+    pub child_path: Vec<(String, String)>,
+
+    /// `(path, point)`
+    pub path_assigned_at_base: Vec<(String, String)>,
+    pub path_moved_at_base: Vec<(String, String)>,
+    pub path_accessed_at_base: Vec<(String, String)>,
+}
+
+impl LegacyFacts {
+    /// Reads the fact set in the given directory.
+    pub fn read(dir: &Path) -> eyre::Result<Self> {
+        // Each relation's file, and the older names of that file
+        let read = |names: &[&str], arity: usize| read_relation(dir, names, arity);
+
+        let pairs = |rows: Vec<Vec<String>>| -> Vec<(String, String)> {
+            rows.into_iter()
+                .map(|mut row| (row.remove(0), row.remove(0)))
+                .collect()
+        };
+        let triples = |rows: Vec<Vec<String>>| -> Vec<(String, String, String)> {
+            rows.into_iter()
+                .map(|mut row| (row.remove(0), row.remove(0), row.remove(0)))
+                .collect()
+        };
+
+        Ok(Self {
+            loan_issued_at: triples(read(&["loan_issued_at", "borrow_region"], 3)?),
+            cfg_edge: pairs(read(&["cfg_edge"], 2)?),
+            loan_killed_at: pairs(read(&["loan_killed_at", "killed"], 2)?),
+            subset_base: triples(read(&["subset_base", "outlives"], 3)?),
+            loan_invalidated_at: pairs(read(&["loan_invalidated_at", "invalidates"], 2)?),
+            var_used_at: pairs(read(&["var_used_at", "var_used"], 2)?),
+            var_defined_at: pairs(read(&["var_defined_at", "var_defined"], 2)?),
+            var_dropped_at: pairs(read(&["var_dropped_at", "var_drop_used"], 2)?),
+            use_of_var_derefs_origin: pairs(read(
+                &["use_of_var_derefs_origin", "var_uses_region"],
+                2,
+            )?),
+            drop_of_var_derefs_origin: pairs(read(
+                &["drop_of_var_derefs_origin", "var_drops_region"],
+                2,
+            )?),
+            universal_region: read(&["universal_region"], 1)?
+                .into_iter()
+                .map(|mut row| row.remove(0))
+                .collect(),
+            // Newer fact sets have the placeholder loans of universal regions instead
+            placeholder: pairs(read(&["placeholder"], 2)?),
+            known_placeholder_subset: pairs(read(
+                &["known_placeholder_subset", "known_subset"],
+                2,
+            )?),
+            path_is_var: pairs(read(&["path_is_var", "path_belongs_to_var"], 2)?),
+            child_path: pairs(read(&["child_path", "child"], 2)?),
+            path_assigned_at_base: pairs(read(&["path_assigned_at_base", "initialized_at"], 2)?),
+            path_moved_at_base: pairs(read(&["path_moved_at_base", "moved_out_at"], 2)?),
+            path_accessed_at_base: pairs(read(&["path_accessed_at_base", "path_accessed_at"], 2)?),
+        })
+    }
+
+    /// Exports the facts emitted for a program as a legacy fact set: the CFG, whose points are
+    /// the nodes, the loans, the move paths, and the variables' uses, definitions and drops.
+    /// Each move path is named after its index (`mp0`), and the variables keep their names.
+    ///
+    /// Loans are issued into the origin of their borrow, which flows into the other origins
+    /// through `subset_base`, like `introduce_subset`. Clearing a loan's origin kills the loan,
+    /// except where it's issued, and invalidating it invalidates the loan. The loans issued into
+    /// placeholders are their `placeholder` loans instead.
+    ///
+    /// The nodes are single points, so the moves of a path which is assigned again at the same
+    /// node, e.g. in `x = f(move x)`, are not exported: upstream, they would be at distinct
+    /// points, the move before the assignment.
+    pub fn from_facts(facts: &Facts) -> Self {
+        let mut legacy = Self::default();
+        for (node1, node2) in &facts.cfg_edge {
+            legacy
+                .cfg_edge
+                .push((node1.0.to_string(), node2.0.to_string()));
+        }
+
+        let variables = |relation: &[(Name, Node)]| {
+            relation
+                .iter()
+                .map(|(variable, node)| (variable.to_string(), node.0.to_string()))
+                .collect()
+        };
+        legacy.var_used_at = variables(&facts.var_used_at);
+        legacy.var_defined_at = variables(&facts.var_defined_at);
+        legacy.var_dropped_at = variables(&facts.var_dropped_at);
+
+        let origins = |relation: &[(Name, Origin)]| {
+            relation
+                .iter()
+                .map(|(variable, origin)| (variable.to_string(), origin.0.to_string()))
+                .collect()
+        };
+        legacy.use_of_var_derefs_origin = origins(&facts.use_of_var_derefs_origin);
+        legacy.drop_of_var_derefs_origin = origins(&facts.drop_of_var_derefs_origin);
+
+        // The loans issued into placeholders are their placeholder loans, which they contain
+        // everywhere
+        let (placeholder_loans, loans): (Vec<_>, Vec<_>) = facts
+            .loan_issued_at
+            .iter()
+            .partition(|(origin, ..)| facts.placeholder_origin.contains(origin));
+        for (origin, loan, _) in placeholder_loans {
+            legacy
+                .placeholder
+                .push((origin.0.to_string(), loan.0.to_string()));
+        }
+        for (origin, loan, node) in &loans {
+            legacy.loan_issued_at.push((
+                origin.0.to_string(),
+                loan.0.to_string(),
+                node.0.to_string(),
+            ));
+        }
+        for (origin1, origin2, node) in &facts.introduce_subset {
+            legacy.subset_base.push((
+                origin1.0.to_string(),
+                origin2.0.to_string(),
+                node.0.to_string(),
+            ));
+        }
+        let mut loans_of: HashMap<&Origin, Vec<(&Loan, &Node)>> = HashMap::new();
+        for (origin, loan, node) in loans {
+            loans_of.entry(origin).or_default().push((loan, node));
+        }
+        for (origin, node) in &facts.clear_origin {
+            let loans = loans_of.get(origin).map_or(&[][..], Vec::as_slice);
+            if loans.iter().any(|&(_, issued_at)| issued_at == node) {
+                continue;
+            }
+            for (loan, _) in loans {
+                legacy
+                    .loan_killed_at
+                    .push((loan.0.to_string(), node.0.to_string()));
+            }
+        }
+        for (origin, node) in &facts.invalidate_origin {
+            for (loan, _) in loans_of.get(origin).into_iter().flatten() {
+                legacy
+                    .loan_invalidated_at
+                    .push((node.0.to_string(), loan.0.to_string()));
+            }
+        }
+        for placeholder in &facts.placeholder_origin {
+            legacy.universal_region.push(placeholder.0.to_string());
+        }
+        for (origin1, origin2) in &facts.known_placeholder_subset {
+            legacy
+                .known_placeholder_subset
+                .push((origin1.0.to_string(), origin2.0.to_string()));
+        }
+
+        // The move paths, with their ancestors, parents first
+        let mut paths: Vec<&str> = Vec::new();
+        let all_paths = facts
+            .path_assigned_at
+            .iter()
+            .chain(&facts.path_moved_at)
+            .chain(&facts.path_accessed_at);
+        for (path, _) in all_paths {
+            let ancestors = path.match_indices('.').map(|(idx, _)| &path[..idx]);
+            for ancestor in ancestors.chain(std::iter::once(path.as_str())) {
+                if !paths.contains(&ancestor) {
+                    paths.push(ancestor);
+                }
+            }
+        }
+        let path_name = |path: &str| {
+            let idx = paths.iter().position(|&candidate| candidate == path);
+            format!("mp{}", idx.expect("all move paths are named"))
+        };
+        for &path in &paths {
+            match path.rsplit_once('.') {
+                Some((parent, _)) => legacy.child_path.push((path_name(path), path_name(parent))),
+                None => legacy.path_is_var.push((path_name(path), path.to_string())),
+            }
+        }
+
+        let is_assigned_at = |path: &str, node: &Node| {
+            facts.path_assigned_at.iter().any(|(assigned, candidate)| {
+                candidate == node
+                    && path
+                        .strip_prefix(assigned.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            })
+        };
+        let relation = |relation: &[(String, Node)]| {
+            relation
+                .iter()
+                .map(|(path, node)| (path_name(path), node.0.to_string()))
+                .collect()
+        };
+        legacy.path_assigned_at_base = relation(&facts.path_assigned_at);
+        legacy.path_accessed_at_base = relation(&facts.path_accessed_at);
+        let moves: Vec<_> = facts
+            .path_moved_at
+            .iter()
+            .filter(|(path, node)| !is_assigned_at(path, node))
+            .cloned()
+            .collect();
+        legacy.path_moved_at_base = relation(&moves);
+        legacy
+    }
+
+    /// Writes the relations to their `.facts` files in the given directory, with quoted atoms
+    /// like recent versions of rustc.
+    pub fn write(&self, dir: &Path) -> eyre::Result<()> {
+        std::fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("failed to create `{}`", dir.display()))?;
+
+        let pairs = |relation: &[(String, String)]| -> Vec<Vec<String>> {
+            relation
+                .iter()
+                .map(|(atom1, atom2)| vec![atom1.clone(), atom2.clone()])
+                .collect()
+        };
+        let triples = |relation: &[(String, String, String)]| -> Vec<Vec<String>> {
+            relation
+                .iter()
+                .map(|(atom1, atom2, atom3)| vec![atom1.clone(), atom2.clone(), atom3.clone()])
+                .collect()
+        };
+        let universal_region = self
+            .universal_region
+            .iter()
+            .map(|origin| vec![origin.clone()])
+            .collect();
+
+        let relations = [
+            ("loan_issued_at", triples(&self.loan_issued_at)),
+            ("cfg_edge", pairs(&self.cfg_edge)),
+            ("loan_killed_at", pairs(&self.loan_killed_at)),
+            ("subset_base", triples(&self.subset_base)),
+            ("loan_invalidated_at", pairs(&self.loan_invalidated_at)),
+            ("var_used_at", pairs(&self.var_used_at)),
+            ("var_defined_at", pairs(&self.var_defined_at)),
+            ("var_dropped_at", pairs(&self.var_dropped_at)),
+            (
+                "use_of_var_derefs_origin",
+                pairs(&self.use_of_var_derefs_origin),
+            ),
+            (
+                "drop_of_var_derefs_origin",
+                pairs(&self.drop_of_var_derefs_origin),
+            ),
+            ("universal_region", universal_region),
+            ("placeholder", pairs(&self.placeholder)),
+            (
+                "known_placeholder_subset",
+                pairs(&self.known_placeholder_subset),
+            ),
+            ("path_is_var", pairs(&self.path_is_var)),
+            ("child_path", pairs(&self.child_path)),
+            ("path_assigned_at_base", pairs(&self.path_assigned_at_base)),
+            ("path_moved_at_base", pairs(&self.path_moved_at_base)),
+            ("path_accessed_at_base", pairs(&self.path_accessed_at_base)),
+        ];
+        for (name, rows) in relations {
+            let contents: String = rows
+                .iter()
+                .map(|row| {
+                    let atoms: Vec<_> = row.iter().map(|atom| format!("{:?}", atom)).collect();
+                    format!("{}\n", atoms.join("\t"))
+                })
+                .collect();
+            let path = dir.join(name).with_extension("facts");
+            std::fs::write(&path, contents)
+                .wrap_err_with(|| format!("failed to write `{}`", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Translates the legacy relations to the facts of the new rules.
+    pub fn to_facts(&self) -> Facts {
+        let mut facts = Facts::default();
+
+        // The nodes are the points of the CFG, and the points only mentioned by other facts
+        let mut points = Vec::new();
+        for (point1, point2) in &self.cfg_edge {
+            for point in [point1, point2] {
+                if !points.contains(point) {
+                    points.push(point.clone());
+                }
+            }
+        }
+        let other_points = self
+            .loan_issued_at
+            .iter()
+            .map(|(_, _, point)| point)
+            .chain(self.subset_base.iter().map(|(_, _, point)| point))
+            .chain(self.loan_killed_at.iter().map(|(_, point)| point))
+            .chain(self.loan_invalidated_at.iter().map(|(point, _)| point))
+            .chain(self.var_used_at.iter().map(|(_, point)| point))
+            .chain(self.var_defined_at.iter().map(|(_, point)| point))
+            .chain(self.var_dropped_at.iter().map(|(_, point)| point));
+        for point in other_points {
+            if !points.contains(point) {
+                points.push(point.clone());
+            }
+        }
+        for point in &points {
+            facts.node_text.push((point.clone(), node(point)));
+        }
+
+        for (point1, point2) in &self.cfg_edge {
+            facts.cfg_edge.push((node(point1), node(point2)));
+        }
+
+        for (origin, loan, point) in &self.loan_issued_at {
+            let loan = loan_origin(loan);
+            if !facts.mark_as_loan_origin.contains(&loan) {
+                facts.mark_as_loan_origin.push(loan.clone());
+            }
+            facts.clear_origin.push((loan.clone(), node(point)));
+            facts
+                .introduce_subset
+                .push((loan, self::origin(origin), node(point)));
+        }
+        for (origin1, origin2, point) in &self.subset_base {
+            facts
+                .introduce_subset
+                .push((origin(origin1), origin(origin2), node(point)));
+        }
+        for (loan, point) in &self.loan_killed_at {
+            facts.clear_origin.push((loan_origin(loan), node(point)));
+        }
+        for (point, loan) in &self.loan_invalidated_at {
+            facts
+                .invalidate_origin
+                .push((loan_origin(loan), node(point)));
+        }
+
+        // The origins in the types of the variables, accessed by their uses and drops
+        let use_origins = origins_of(&self.use_of_var_derefs_origin);
+        let drop_origins = origins_of(&self.drop_of_var_derefs_origin);
+        let accesses = self
+            .var_used_at
+            .iter()
+            .map(|access| (access, &use_origins))
+            .chain(
+                self.var_dropped_at
+                    .iter()
+                    .map(|access| (access, &drop_origins)),
+            );
+        for ((variable, point), origins) in accesses {
+            for origin in origins.get(variable.as_str()).into_iter().flatten() {
+                facts.access_origin.push((origin.clone(), node(point)));
+            }
+        }
+        for (variable, point) in &self.var_defined_at {
+            for origin in use_origins.get(variable.as_str()).into_iter().flatten() {
+                facts.clear_origin.push((origin.clone(), node(point)));
+            }
+        }
+
+        let placeholders = self
+            .universal_region
+            .iter()
+            .chain(self.placeholder.iter().map(|(origin, _)| origin));
+        for placeholder in placeholders {
+            let placeholder = origin(placeholder);
+            if !facts.placeholder_origin.contains(&placeholder) {
+                facts.placeholder_origin.push(placeholder);
+            }
+        }
+        for (origin1, origin2) in &self.known_placeholder_subset {
+            facts
+                .known_placeholder_subset
+                .push((origin(origin1), origin(origin2)));
+        }
+
+        facts
+    }
+
+    /// Computes the loan errors of both formulations: the legacy rules over these facts, and
+    /// the new rules over their translation.
+    pub fn compare_formulations(&self) -> FormulationComparison {
+        let facts = self.to_facts();
+
+        // The new rules report errors where the invalidated loans are accessed: an invalidation
+        // is an error when it's accessed, when solving with only that invalidation. The
+        // translation keeps the invalidations in order.
+        let mut new = Vec::new();
+        for (idx, (point, loan)) in self.loan_invalidated_at.iter().enumerate() {
+            let mut isolated = facts.clone();
+            isolated.invalidate_origin = vec![facts.invalidate_origin[idx].clone()];
+            if !solve(&isolated).invalidated_origin_accessed.is_empty() {
+                new.push((loan.clone(), point.clone()));
+            }
+        }
+        new.sort();
+        new.dedup();
+
+        FormulationComparison {
+            legacy: legacy_errors(self),
+            new,
+        }
+    }
+}
+
+/// The loan errors of the legacy rules and of the new rules over the same legacy fact set, as
+/// `(loan, point)` pairs, where the point is the one invalidating the loan.
+#[derive(Debug)]
+pub struct FormulationComparison {
+    pub legacy: Vec<(String, String)>,
+    pub new: Vec<(String, String)>,
+}
+
+impl FormulationComparison {
+    pub fn agrees(&self) -> bool {
+        self.legacy == self.new
+    }
+}
+
+impl fmt::Display for FormulationComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut errors: Vec<_> = self.legacy.iter().chain(&self.new).collect();
+        errors.sort();
+        errors.dedup();
+        for error in errors {
+            let status = match (self.legacy.contains(error), self.new.contains(error)) {
+                (true, true) => "both",
+                (true, false) => "legacy",
+                _ => "new",
+            };
+            writeln!(f, "{:<8}`{}` invalidated at `{}`", status, error.0, error.1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Imports the fact sets found in the corpus directory, e.g. the `inputs` of the upstream
+/// repository, and writes each of them to a `program.txt` fact file, at the same relative path
+/// in the output directory. Returns the number of imported fact sets.
+pub fn import_corpus(corpus: &Path, output: &Path) -> eyre::Result<usize> {
+    let pattern = corpus.join("**").join("cfg_edge.facts");
+    let pattern = pattern
+        .to_str()
+        .ok_or_else(|| eyre::eyre!("invalid corpus path `{}`", corpus.display()))?;
+
+    let mut imported = 0;
+    for cfg_edge_path in glob::glob(pattern)? {
+        let dir = cfg_edge_path?
+            .parent()
+            .expect("fact files are in a directory")
+            .to_path_buf();
+        let facts = LegacyFacts::read(&dir)?.to_facts();
+
+        let output_dir = output.join(dir.strip_prefix(corpus)?);
+        std::fs::create_dir_all(&output_dir)
+            .wrap_err_with(|| format!("failed to create `{}`", output_dir.display()))?;
+        let program_path = output_dir.join("program.txt");
+        std::fs::write(&program_path, facts.to_string())
+            .wrap_err_with(|| format!("failed to write `{}`", program_path.display()))?;
+        imported += 1;
+    }
+    Ok(imported)
+}
+
+// Reads the rows of a relation, from the first of its file names which exists.
+fn read_relation(dir: &Path, names: &[&str], arity: usize) -> eyre::Result<Vec<Vec<String>>> {
+    let path = match names
+        .iter()
+        .map(|name| dir.join(name).with_extension("facts"))
+        .find(|path| path.exists())
+    {
+        Some(path) => path,
+        None => return Ok(Vec::new()),
+    };
+
+    let contents = std::fs::read_to_string(&path)
+        .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+    let mut rows = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+
+        // Atoms can be quoted, depending on the version of rustc which dumped them
+        let row: Vec<String> = line
+            .split('\t')
+            .map(|atom| atom.trim_matches('"').to_string())
+            .collect();
+        if row.len() != arity {
+            eyre::bail!(
+                "expected {} columns at line {} of `{}`, found {}",
+                arity,
+                idx + 1,
+                path.display(),
+                row.len()
+            );
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+// Groups the origins of each variable, from a relation of `(variable, origin)` pairs.
+fn origins_of(relation: &[(String, String)]) -> HashMap<&str, Vec<Origin>> {
+    let mut origins: HashMap<&str, Vec<Origin>> = HashMap::new();
+    for (variable, origin_name) in relation {
+        origins
+            .entry(variable.as_str())
+            .or_default()
+            .push(origin(origin_name));
+    }
+    origins
+}
+
+// Names a point as a node: `Mid(bb0[1])` is `bb0_1_mid`.
+fn node(point: &str) -> Node {
+    let name = point
+        .strip_suffix("])")
+        .and_then(|point| point.split_once('('))
+        .and_then(|(kind, location)| {
+            let (block, statement) = location.split_once('[')?;
+            Some(format!("{}_{}_{}", block, statement, kind.to_lowercase()))
+        })
+        .unwrap_or_else(|| sanitize(point));
+    name.into()
+}
+
+// Names a legacy origin, e.g. `'_#2r` is `'_2r`.
+fn origin(origin: &str) -> Origin {
+    format!("'{}", sanitize(origin.trim_start_matches('\''))).into()
+}
+
+// Names the origin of a loan, e.g. `bw0` is `'L_bw0`.
+fn loan_origin(loan: &str) -> Origin {
+    format!("'L_{}", sanitize(loan)).into()
+}
+
+// Keeps only the characters which are valid in the fact format's identifiers.
+fn sanitize(atom: &str) -> String {
+    atom.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect()
+}
@@ -0,0 +1,349 @@
+//! The legacy rules, as in upstream Polonius' naive variant, computing the loan errors of a
+//! legacy fact set: a loan invalidated at a point where it's live. And the move errors of its
+//! initialization analysis: a path accessed at a point where it may be uninitialized.
+//!
+//! Initialization is not tracked by the loan errors, so variables are drop-live until they're
+//! defined, even when they're moved out. The placeholder errors of the legacy rules are not
+//! computed.
+
+use super::LegacyFacts;
+use crate::solver::Interner;
+use datafrog::{Iteration, Relation, RelationLeaper, ValueFilter};
+use std::collections::BTreeSet;
+
+type Idx = u32;
+
+/// The relations computed by the legacy rules, with their atoms as in the facts.
+pub(crate) struct LegacyRelations {
+    /// `(origin, point)`
+    pub(crate) origin_live_on_entry: Vec<(String, String)>,
+
+    /// `(origin, loan, point)`
+    pub(crate) origin_contains_loan_on_entry: Vec<(String, String, String)>,
+
+    /// `(loan, point)`
+    pub(crate) loan_live_at: Vec<(String, String)>,
+    pub(crate) errors: Vec<(String, String)>,
+}
+
+/// Computes the `errors(loan, point)` of the legacy rules over the given facts.
+pub fn legacy_errors(facts: &LegacyFacts) -> Vec<(String, String)> {
+    legacy_relations(facts).errors
+}
+
+/// Computes the relations of the legacy rules over the given facts, up to their errors.
+pub(crate) fn legacy_relations(facts: &LegacyFacts) -> LegacyRelations {
+    let mut atoms = Interner::<String>::default();
+
+    let mut pairs = |relation: &[(String, String)]| -> Relation<(Idx, Idx)> {
+        relation
+            .iter()
+            .map(|(atom1, atom2)| (atoms.intern(atom1), atoms.intern(atom2)))
+            .collect()
+    };
+    let cfg_edge = pairs(&facts.cfg_edge);
+    let loan_killed_at = pairs(&facts.loan_killed_at);
+    let var_used_at = pairs(&facts.var_used_at);
+    let var_defined_at = pairs(&facts.var_defined_at);
+    let var_dropped_at = pairs(&facts.var_dropped_at);
+    let use_of_var_derefs_origin = pairs(&facts.use_of_var_derefs_origin);
+    let drop_of_var_derefs_origin = pairs(&facts.drop_of_var_derefs_origin);
+    let loan_invalidated_at = pairs(&facts.loan_invalidated_at);
+    let cfg_edge_reverse = Relation::from_map(&cfg_edge, |&(point1, point2)| (point2, point1));
+
+    let mut triples = |relation: &[(String, String, String)]| -> Relation<(Idx, Idx, Idx)> {
+        relation
+            .iter()
+            .map(|(atom1, atom2, atom3)| {
+                (
+                    atoms.intern(atom1),
+                    atoms.intern(atom2),
+                    atoms.intern(atom3),
+                )
+            })
+            .collect()
+    };
+    let loan_issued_at = triples(&facts.loan_issued_at);
+    let subset_base = triples(&facts.subset_base);
+
+    let points: Vec<Idx> = facts
+        .cfg_edge
+        .iter()
+        .flat_map(|(point1, point2)| [point1, point2])
+        .map(|point| atoms.intern(point))
+        .collect();
+    let universal_region: Vec<Idx> = facts
+        .universal_region
+        .iter()
+        .map(|origin| atoms.intern(origin))
+        .collect();
+
+    // var_live_on_entry(V, P) :- var_used_at(V, P).
+    // var_live_on_entry(V, P) :-
+    //   var_live_on_entry(V, Q),
+    //   cfg_edge(P, Q),
+    //   !var_defined_at(V, P).
+    //
+    // And similarly for `var_drop_live_on_entry`, from `var_dropped_at`.
+    let var_live_on_entry = |accessed_at: &Relation<(Idx, Idx)>| {
+        let mut iteration = Iteration::new();
+        let live = iteration.variable::<(Idx, Idx)>("var_live_on_entry");
+        live.insert(accessed_at.clone());
+        while iteration.changed() {
+            live.from_leapjoin(
+                &live,
+                (
+                    cfg_edge_reverse.extend_with(|&(_var, point2)| point2),
+                    var_defined_at.extend_anti(|&(var, _point2)| var),
+                ),
+                |&(var, _point2), &point1| (var, point1),
+            );
+        }
+        live.complete()
+    };
+
+    // origin_live_on_entry(O, P) :-
+    //   var_live_on_entry(V, P),
+    //   use_of_var_derefs_origin(V, O).
+    // origin_live_on_entry(O, P) :-
+    //   var_drop_live_on_entry(V, P),
+    //   drop_of_var_derefs_origin(V, O).
+    // origin_live_on_entry(O, P) :- cfg_node(P), universal_region(O).
+    let origin_live_on_entry: Relation<(Idx, Idx)> = {
+        let derefs = |live: Relation<(Idx, Idx)>, derefs_origin: &Relation<(Idx, Idx)>| {
+            Relation::from_join(&live, derefs_origin, |&_var, &point, &origin| {
+                (origin, point)
+            })
+        };
+        let used = derefs(var_live_on_entry(&var_used_at), &use_of_var_derefs_origin);
+        let dropped = derefs(
+            var_live_on_entry(&var_dropped_at),
+            &drop_of_var_derefs_origin,
+        );
+        used.iter()
+            .chain(dropped.iter())
+            .copied()
+            .chain(
+                universal_region
+                    .iter()
+                    .flat_map(|&origin| points.iter().map(move |&point| (origin, point))),
+            )
+            .collect()
+    };
+
+    let origin_contains_loan_on_entry = {
+        let mut iteration = Iteration::new();
+        let subset = iteration.variable::<(Idx, Idx, Idx)>("subset");
+        let contains = iteration.variable::<(Idx, Idx, Idx)>("origin_contains_loan_on_entry");
+
+        // Indices of the above, for joins
+        let subset_o1 = iteration.variable_indistinct::<((Idx, Idx), Idx)>("subset_o1");
+        let subset_o2 = iteration.variable_indistinct::<((Idx, Idx), Idx)>("subset_o2");
+        let contains_o = iteration.variable_indistinct::<((Idx, Idx), Idx)>("contains_o");
+
+        // subset(O1, O2, P) :- subset_base(O1, O2, P).
+        subset.insert(subset_base);
+
+        // origin_contains_loan_on_entry(O, L, P) :- loan_issued_at(O, L, P).
+        contains.insert(loan_issued_at);
+
+        while iteration.changed() {
+            subset_o1.from_map(&subset, |&(o1, o2, point)| ((o1, point), o2));
+            subset_o2.from_map(&subset, |&(o1, o2, point)| ((o2, point), o1));
+            contains_o.from_map(&contains, |&(origin, loan, point)| ((origin, point), loan));
+
+            // subset(O1, O3, P) :-
+            //   subset(O1, O2, P),
+            //   subset(O2, O3, P).
+            subset.from_join(&subset_o2, &subset_o1, |&(_o2, point), &o1, &o3| {
+                (o1, o3, point)
+            });
+
+            // subset(O1, O2, Q) :-
+            //   subset(O1, O2, P),
+            //   cfg_edge(P, Q),
+            //   origin_live_on_entry(O1, Q),
+            //   origin_live_on_entry(O2, Q).
+            subset.from_leapjoin(
+                &subset,
+                (
+                    cfg_edge.extend_with(|&(_o1, _o2, point1)| point1),
+                    ValueFilter::from(|&(o1, o2, _point1), &point2| {
+                        origin_live_on_entry.binary_search(&(o1, point2)).is_ok()
+                            && origin_live_on_entry.binary_search(&(o2, point2)).is_ok()
+                    }),
+                ),
+                |&(o1, o2, _point1), &point2| (o1, o2, point2),
+            );
+
+            // origin_contains_loan_on_entry(O2, L, P) :-
+            //   origin_contains_loan_on_entry(O1, L, P),
+            //   subset(O1, O2, P).
+            contains.from_join(&contains_o, &subset_o1, |&(_o1, point), &loan, &o2| {
+                (o2, loan, point)
+            });
+
+            // origin_contains_loan_on_entry(O, L, Q) :-
+            //   origin_contains_loan_on_entry(O, L, P),
+            //   !loan_killed_at(L, P),
+            //   cfg_edge(P, Q),
+            //   origin_live_on_entry(O, Q).
+            contains.from_leapjoin(
+                &contains,
+                (
+                    cfg_edge.extend_with(|&(_origin, _loan, point1)| point1),
+                    ValueFilter::from(|&(origin, loan, point1), &point2| {
+                        loan_killed_at.binary_search(&(loan, point1)).is_err()
+                            && origin_live_on_entry
+                                .binary_search(&(origin, point2))
+                                .is_ok()
+                    }),
+                ),
+                |&(origin, loan, _point1), &point2| (origin, loan, point2),
+            );
+        }
+        contains.complete()
+    };
+
+    // loan_live_at(L, P) :-
+    //   origin_contains_loan_on_entry(O, L, P),
+    //   origin_live_on_entry(O, P).
+    let loan_live_at: Relation<(Idx, Idx)> = origin_contains_loan_on_entry
+        .iter()
+        .filter(|&&(origin, _loan, point)| {
+            origin_live_on_entry.binary_search(&(origin, point)).is_ok()
+        })
+        .map(|&(_origin, loan, point)| (loan, point))
+        .collect();
+
+    // errors(L, P) :-
+    //   loan_invalidated_at(L, P),
+    //   loan_live_at(L, P).
+    let mut errors: Vec<_> = loan_invalidated_at
+        .iter()
+        .filter(|&&(point, loan)| loan_live_at.binary_search(&(loan, point)).is_ok())
+        .map(|&(point, loan)| (atoms.value(loan).clone(), atoms.value(point).clone()))
+        .collect();
+    errors.sort();
+
+    let pair =
+        |&(atom1, atom2): &(Idx, Idx)| (atoms.value(atom1).clone(), atoms.value(atom2).clone());
+    LegacyRelations {
+        origin_live_on_entry: origin_live_on_entry.iter().map(pair).collect(),
+        origin_contains_loan_on_entry: origin_contains_loan_on_entry
+            .iter()
+            .map(|&(origin, loan, point)| {
+                (
+                    atoms.value(origin).clone(),
+                    atoms.value(loan).clone(),
+                    atoms.value(point).clone(),
+                )
+            })
+            .collect(),
+        loan_live_at: loan_live_at.iter().map(pair).collect(),
+        errors,
+    }
+}
+
+/// Computes the `move_error(path, point)` of the legacy rules over the given facts.
+pub fn legacy_move_errors(facts: &LegacyFacts) -> Vec<(String, String)> {
+    let mut atoms = Interner::<String>::default();
+    let mut pairs = |relation: &[(String, String)]| -> Vec<(Idx, Idx)> {
+        relation
+            .iter()
+            .map(|(atom1, atom2)| (atoms.intern(atom1), atoms.intern(atom2)))
+            .collect()
+    };
+    let cfg_edge: Relation<(Idx, Idx)> = pairs(&facts.cfg_edge).into();
+    let child_path = pairs(&facts.child_path);
+    let path_assigned_at_base = pairs(&facts.path_assigned_at_base);
+    let path_moved_at_base = pairs(&facts.path_moved_at_base);
+    let path_accessed_at_base = pairs(&facts.path_accessed_at_base);
+
+    // ancestor_path(Parent, Child) :- child_path(Child, Parent).
+    // ancestor_path(Grandparent, Child) :-
+    //   ancestor_path(Parent, Child),
+    //   child_path(Parent, Grandparent).
+    let mut ancestor_path: BTreeSet<(Idx, Idx)> = child_path
+        .iter()
+        .map(|&(child, parent)| (parent, child))
+        .collect();
+    loop {
+        let derived: Vec<_> = ancestor_path
+            .iter()
+            .flat_map(|&(parent, child)| {
+                child_path
+                    .iter()
+                    .filter(move |&&(candidate, _)| candidate == parent)
+                    .map(move |&(_, grandparent)| (grandparent, child))
+            })
+            .collect();
+        let len = ancestor_path.len();
+        ancestor_path.extend(derived);
+        if ancestor_path.len() == len {
+            break;
+        }
+    }
+
+    // path_moved_at(Path, P) :- path_moved_at_base(Path, P).
+    // path_moved_at(Child, P) :-
+    //   path_moved_at(Parent, P),
+    //   ancestor_path(Parent, Child).
+    //
+    // And similarly for `path_assigned_at` and `path_accessed_at`.
+    let with_children = |base: &[(Idx, Idx)]| -> Relation<(Idx, Idx)> {
+        base.iter()
+            .copied()
+            .chain(base.iter().flat_map(|&(parent, point)| {
+                ancestor_path
+                    .iter()
+                    .filter(move |&&(ancestor, _)| ancestor == parent)
+                    .map(move |&(_, child)| (child, point))
+            }))
+            .collect()
+    };
+    let path_assigned_at = with_children(&path_assigned_at_base);
+    let path_moved_at = with_children(&path_moved_at_base);
+    let path_accessed_at = with_children(&path_accessed_at_base);
+
+    // path_maybe_uninitialized_on_exit(Path, P) :- path_moved_at(Path, P).
+    // path_maybe_uninitialized_on_exit(Path, Q) :-
+    //   path_maybe_uninitialized_on_exit(Path, P),
+    //   cfg_edge(P, Q),
+    //   !path_assigned_at(Path, Q).
+    let path_maybe_uninitialized_on_exit = {
+        let mut iteration = Iteration::new();
+        let uninitialized = iteration.variable::<(Idx, Idx)>("path_maybe_uninitialized_on_exit");
+        uninitialized.insert(path_moved_at);
+        while iteration.changed() {
+            uninitialized.from_leapjoin(
+                &uninitialized,
+                (
+                    cfg_edge.extend_with(|&(_path, point1)| point1),
+                    path_assigned_at.extend_anti(|&(path, _point1)| path),
+                ),
+                |&(path, _point1), &point2| (path, point2),
+            );
+        }
+        uninitialized.complete()
+    };
+
+    // move_error(Path, Q) :-
+    //   path_maybe_uninitialized_on_exit(Path, P),
+    //   cfg_edge(P, Q),
+    //   path_accessed_at(Path, Q).
+    let mut errors: Vec<_> = path_maybe_uninitialized_on_exit
+        .iter()
+        .flat_map(|&(path, point1)| {
+            cfg_edge
+                .iter()
+                .filter(move |&&(candidate, _)| candidate == point1)
+                .map(move |&(_, point2)| (path, point2))
+        })
+        .filter(|tuple| path_accessed_at.binary_search(tuple).is_ok())
+        .map(|(path, point)| (atoms.value(path).clone(), atoms.value(point).clone()))
+        .collect();
+    errors.sort();
+    errors.dedup();
+    errors
+}
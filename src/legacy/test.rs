@@ -0,0 +1,311 @@
+use super::*;
+use crate::solver::solve;
+use crate::test_util::{write_fact_set, write_files, ASSIGN_TO_BORROWED};
+use insta::{assert_debug_snapshot, assert_display_snapshot};
+
+#[test]
+fn translate_legacy_facts() {
+    let dir = write_files("legacy-translate", &[]);
+    write_fact_set(&dir, ASSIGN_TO_BORROWED);
+    let facts = LegacyFacts::read(&dir).unwrap().to_facts();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_display_snapshot!(facts, @r###"
+    mark_as_loan_origin('L_bw0)
+    placeholder_origin('_0r)
+
+    bb0_0_mid: "Mid(bb0[0])" {
+    	goto bb0_1_start
+    }
+
+    bb0_0_start: "Start(bb0[0])" {
+    	goto bb0_0_mid
+    }
+
+    bb0_1_mid: "Mid(bb0[1])" {
+    	clear_origin('L_bw0)
+    	clear_origin('_2r)
+    	introduce_subset('L_bw0, '_1r)
+    	introduce_subset('_1r, '_2r)
+    	goto bb0_2_start
+    }
+
+    bb0_1_start: "Start(bb0[1])" {
+    	goto bb0_1_mid
+    }
+
+    bb0_2_mid: "Mid(bb0[2])" {
+    	invalidate_origin('L_bw0)
+    	clear_origin('L_bw0)
+    	goto bb0_3_start
+    }
+
+    bb0_2_start: "Start(bb0[2])" {
+    	goto bb0_2_mid
+    }
+
+    bb0_3_mid: "Mid(bb0[3])" {
+    	access_origin('_2r)
+    	goto
+    }
+
+    bb0_3_start: "Start(bb0[3])" {
+    	goto bb0_3_mid
+    }
+    "###);
+    assert_eq!(
+        solve(&facts).invalidated_origin_accessed,
+        [("'_2r".into(), "bb0_3_mid".into())]
+    );
+}
+
+#[test]
+fn import_fact_sets() {
+    // The corpus has a directory per test, with the fact sets of each function
+    let dir = write_files("legacy-import", &[]);
+    let corpus = dir.join("inputs");
+    write_fact_set(
+        &corpus.join("assign-to-borrowed/nll-facts/main"),
+        ASSIGN_TO_BORROWED,
+    );
+
+    let output = dir.join("output");
+    assert_eq!(import_corpus(&corpus, &output).unwrap(), 1);
+    let program_path = output.join("assign-to-borrowed/nll-facts/main/program.txt");
+    let program = std::fs::read_to_string(program_path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // The imported fact file can be read back as the same facts
+    let facts = crate::fact_parser::read_facts(&program).unwrap();
+    assert_eq!(
+        solve(&facts).invalidated_origin_accessed,
+        [("'_2r".into(), "bb0_3_mid".into())]
+    );
+}
+
+#[test]
+fn compare_rule_formulations() {
+    let dir = write_files("legacy-compare", &[]);
+    write_fact_set(&dir, ASSIGN_TO_BORROWED);
+    let facts = LegacyFacts::read(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let comparison = facts.compare_formulations();
+    assert!(comparison.agrees());
+    assert_display_snapshot!(comparison, @"both    `bw0` invalidated at `Mid(bb0[2])`");
+
+    // Without the later use of `y`, the loan is dead when `x` is assigned to
+    let mut facts = facts;
+    facts.var_used_at.clear();
+    let comparison = facts.compare_formulations();
+    assert!(comparison.agrees());
+    assert!(comparison.legacy.is_empty());
+}
+
+#[test]
+fn export_move_paths() {
+    let program = "
+        struct Pair { first: Vec, second: Vec }
+
+        let pair: Pair;
+        let v: Vec;
+
+        bb0: {
+            v = move pair.first;
+            use(move pair.second);
+            goto bb1, bb2;
+        }
+        bb1: {
+            pair = Pair_new();
+            goto bb2;
+        }
+        bb2: {
+            use(move pair);
+        }
+    ";
+    let facts = crate::fact_emitter::test::expect_facts(program);
+    let exported = LegacyFacts::from_facts(&facts);
+    assert_debug_snapshot!((
+        &exported.path_is_var,
+        &exported.child_path,
+        &exported.path_assigned_at_base,
+        &exported.path_moved_at_base,
+        &exported.path_accessed_at_base,
+    ), @r###"
+    (
+        [
+            (
+                "mp0",
+                "v",
+            ),
+            (
+                "mp1",
+                "pair",
+            ),
+        ],
+        [
+            (
+                "mp2",
+                "mp1",
+            ),
+            (
+                "mp3",
+                "mp1",
+            ),
+        ],
+        [
+            (
+                "mp0",
+                "a",
+            ),
+            (
+                "mp1",
+                "c",
+            ),
+        ],
+        [
+            (
+                "mp2",
+                "a",
+            ),
+            (
+                "mp3",
+                "b",
+            ),
+            (
+                "mp1",
+                "d",
+            ),
+        ],
+        [
+            (
+                "mp2",
+                "a",
+            ),
+            (
+                "mp3",
+                "b",
+            ),
+            (
+                "mp1",
+                "d",
+            ),
+        ],
+    )
+    "###);
+
+    // The fact set can be read back, and its move errors are the ones of the move analysis
+    let dir = write_files("legacy-export", &[]);
+    exported.write(&dir).unwrap();
+    let read = LegacyFacts::read(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let errors = legacy_move_errors(&read);
+    assert_debug_snapshot!(errors, @r###"
+    [
+        (
+            "mp2",
+            "d",
+        ),
+        (
+            "mp3",
+            "d",
+        ),
+    ]
+    "###);
+
+    let mut error_points: Vec<_> = errors.into_iter().map(|(_, point)| point).collect();
+    error_points.dedup();
+    let accesses: Vec<_> = crate::moves::move_errors(&facts)
+        .into_iter()
+        .map(|error| error.accessed_at.0)
+        .collect();
+    assert_eq!(error_points, accesses);
+}
+
+#[test]
+fn export_loans() {
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            y = &'L_x x;
+            kill(x);
+            x = 4;
+            use(move y);
+        }
+    ";
+    let facts = crate::fact_emitter::test::expect_facts(program);
+    let exported = LegacyFacts::from_facts(&facts);
+    assert_debug_snapshot!((
+        &exported.loan_issued_at,
+        &exported.subset_base,
+        &exported.loan_killed_at,
+        &exported.loan_invalidated_at,
+    ), @r###"
+    (
+        [
+            (
+                "'L_x",
+                "bw0",
+                "a",
+            ),
+        ],
+        [
+            (
+                "'L_x",
+                "'y",
+                "a",
+            ),
+        ],
+        [
+            (
+                "bw0",
+                "b",
+            ),
+        ],
+        [
+            (
+                "c",
+                "bw0",
+            ),
+        ],
+    )
+    "###);
+    assert_eq!(legacy_errors(&exported), vec![]);
+}
+
+#[test]
+fn export_placeholder_loans() {
+    let program = "
+        placeholder 'a;
+        let x: &'a i32;
+        let y: &'y i32;
+
+        bb0: {
+            y = copy x;
+            use(copy y);
+        }
+    ";
+    let facts = crate::fact_emitter::test::expect_facts(program);
+    let exported = LegacyFacts::from_facts(&facts);
+    assert_debug_snapshot!((
+        &exported.loan_issued_at,
+        &exported.universal_region,
+        &exported.placeholder,
+    ), @r###"
+    (
+        [],
+        [
+            "'a",
+        ],
+        [
+            (
+                "'a",
+                "ph0",
+            ),
+        ],
+    )
+    "###);
+    assert_eq!(legacy_errors(&exported), vec![]);
+}
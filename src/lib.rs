@@ -1,14 +1,101 @@
+mod annotations;
 mod ast;
 mod ast_parser;
+mod batch;
+mod cargo;
+mod color;
+mod config;
+mod convert;
+mod diagnostics;
+mod differential;
 mod fact_emitter;
 mod fact_parser;
+mod fuzz;
+mod graph;
 mod graphviz;
+mod json;
+mod legacy;
+mod limits;
+mod lints;
+mod lowering;
+mod models;
+mod moves;
+mod query;
+mod reaching;
+mod regression;
+mod sarif;
+mod solver;
+mod souffle;
+mod source_map;
+mod sources;
 mod span;
+mod stress;
+mod subset_graph;
+mod surface;
+mod symbol;
+#[cfg(test)]
+mod test_util;
+mod timings;
+mod tui;
+mod validation;
 
 use std::{path::PathBuf, process::Command};
 
+pub use annotations::{check_annotations, AnnotationMismatches};
+pub use batch::{
+    analyze_fact_sets, analyze_programs, collect_programs, BatchOptions, ProgramOutcome,
+    ProgramReport,
+};
+pub use cargo::{collect_fact_sets, dump_nll_facts};
+pub use color::{colorize_diagnostics, colorize_facts};
+pub use config::{Config, CONFIG_FILE_NAME};
+pub use convert::{
+    facts_from_binary, facts_from_json, facts_to_binary, facts_to_json, read_facts_in,
+    write_facts_in, FactFormat,
+};
+pub use diagnostics::{diagnose, diagnose_with_limits, explain_diagnostics, Diagnostic, Label};
+pub use differential::{compare_with_rustc, translate_to_rust, Comparison, ComparisonEntry};
 use eyre::Context;
-pub use fact_parser::generate_facts;
+pub use fact_emitter::{
+    emit_facts, emit_facts_with_options, reaching_loans, CfgEdgeKind, EmitOptions, ExprPath,
+    ExprPathElem, FactFilter, FactSlice, Facts, Loan, LoanKind, Node, NodeNames, Origin,
+    Provenance, RawPointerCasts, Relation,
+};
+pub use fact_parser::{generate_facts, read_facts};
+pub use fuzz::{fuzz_solvers, shrink_program, Divergence, FuzzOptions};
+pub use graph::{block_graph, node_graph, BlockGraph, Dominators, EdgeKind, NodeGraph};
+pub use json::Json;
+pub use legacy::{
+    import_corpus, legacy_errors, legacy_move_errors, FormulationComparison, LegacyFacts,
+};
+pub use limits::Limits;
+pub use lints::{lint, lint_with_limits};
+pub use models::{
+    models, BorrowCheckModel, LegacyPolonius, ModelComparison, ModelOutput, NewRules, Nll,
+};
+pub use moves::{move_errors, MoveError};
+pub use query::{run_repl, Query, RelationIndex};
+pub use reaching::ReachingDefinitions;
+pub use regression::{
+    compare_to_baseline, output_snapshot, record_baseline, OutputChange, Regression,
+};
+pub use sarif::sarif_log;
+pub use solver::{
+    explain, rule_coverage, solve, solve_naive, solve_with_limits, CoverageMatrix, Derivation,
+    SolverOutput, RULES,
+};
+pub use souffle::{diagnose_with_souffle, explain_with_souffle, solve_with_souffle};
+pub use source_map::SourceMap;
+pub use sources::Sources;
+pub use span::Span;
+pub use stress::{
+    benchmark_solvers, generate_stress_program, SolverBenchmark, SolverTiming, StressOptions,
+};
+pub use subset_graph::subset_graphs;
+pub use symbol::Symbol;
+pub use timings::{CountingAllocator, PhaseTiming, Timings, TimingsLayer};
+pub use tui::{run_viewer, NodeViewer};
+pub use validation::{validate, Severity, SourceDiagnostic};
 
 pub fn test_harness(dir_name: &str) -> eyre::Result<()> {
     // let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
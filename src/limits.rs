@@ -0,0 +1,105 @@
+//! Limits on the resources the analysis of a program can use, so that adversarial or generated
+//! inputs fail with an error, instead of exhausting the memory or running for hours.
+//!
+//! The size of the program is limited by its number of nodes, checked before emitting its facts,
+//! and by its number of facts, checked as they're emitted. The solving is limited by the number
+//! of rounds of each of its fixpoint computations. The time the analysis takes is limited
+//! overall: it's checked while emitting the facts, linting them, and solving them. All the limits
+//! are off by default.
+
+use crate::fact_emitter::Facts;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod test;
+
+/// The limits of an analysis. A missing limit is not checked.
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    /// The maximum number of nodes, i.e. statements, of the program.
+    pub max_nodes: Option<usize>,
+
+    /// The maximum number of facts emitted for the program, of all the relations.
+    pub max_facts: Option<usize>,
+
+    /// The maximum number of rounds of each fixpoint computation of the solver.
+    pub max_iterations: Option<usize>,
+
+    /// The maximum duration of the analysis: of the emission of the facts, their lints, and the
+    /// solvings computing the diagnostics.
+    pub timeout: Option<Duration>,
+
+    /// When the analysis started, for the timeout to include the phases before the current one,
+    /// e.g. the validation of the program. When missing, each phase starts the clock anew.
+    pub started: Option<Instant>,
+}
+
+impl Limits {
+    pub(crate) fn check_nodes(&self, node_count: usize) -> eyre::Result<()> {
+        match self.max_nodes {
+            Some(max) if node_count > max => eyre::bail!(
+                "the program has {} nodes, more than the limit of {}",
+                node_count,
+                max
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_facts(&self, facts: &Facts) -> eyre::Result<()> {
+        match self.max_facts {
+            Some(max) if facts.fact_count() > max => eyre::bail!(
+                "the program has {} facts, more than the limit of {}",
+                facts.fact_count(),
+                max
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The limits of an analysis in progress: its rounds are checked against the limits, and the time
+/// it has taken since it started.
+pub(crate) struct Budget<'a> {
+    limits: &'a Limits,
+    start: Instant,
+}
+
+impl<'a> Budget<'a> {
+    /// Starts the clock of the limits, unless the analysis has already started.
+    pub(crate) fn start(limits: &'a Limits) -> Self {
+        Self {
+            limits,
+            start: limits.started.unwrap_or_else(Instant::now),
+        }
+    }
+
+    /// Checks the analysis hasn't taken more than the timeout so far, while doing the given task.
+    pub(crate) fn check_time(&self, task: impl fmt::Display) -> eyre::Result<()> {
+        if let Some(timeout) = self.limits.timeout {
+            if self.start.elapsed() >= timeout {
+                eyre::bail!(
+                    "the analysis takes more than the limit of {:?}, while {}",
+                    timeout,
+                    task
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the round of the fixpoint computation of the relation is within the limits.
+    pub(crate) fn check_round(&self, relation: &str, round: usize) -> eyre::Result<()> {
+        if let Some(max) = self.limits.max_iterations {
+            if round > max {
+                eyre::bail!(
+                    "computing `{}` takes more than the limit of {} iterations",
+                    relation,
+                    max
+                );
+            }
+        }
+        self.check_time(format_args!("computing `{}`", relation))
+    }
+}
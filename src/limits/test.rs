@@ -0,0 +1,107 @@
+use super::*;
+use crate::diagnostics::diagnose_with_limits;
+use crate::fact_emitter::{emit_facts, emit_facts_with_options, EmitOptions};
+use crate::lints::lint_with_limits;
+use crate::solver::{solve, solve_with_limits};
+use insta::assert_display_snapshot;
+
+const PROGRAM: &str = "
+    let x: i32;
+    let r: &'r i32;
+
+    bb0: {
+        r = &'L_x x;
+        goto bb1;
+    }
+
+    bb1: {
+        x = 1;
+        goto bb2;
+    }
+
+    bb2: {
+        use(copy r);
+    }
+";
+
+fn emit_with_limits(limits: Limits) -> eyre::Result<Facts> {
+    let options = EmitOptions {
+        limits,
+        ..EmitOptions::default()
+    };
+    emit_facts_with_options(PROGRAM, &options)
+}
+
+#[test]
+fn program_size() {
+    // The program has 3 nodes, and 32 facts
+    let facts = emit_with_limits(Limits {
+        max_nodes: Some(3),
+        max_facts: Some(32),
+        ..Limits::default()
+    })
+    .unwrap();
+    assert_eq!(facts.fact_count(), 32);
+
+    let error = emit_with_limits(Limits {
+        max_nodes: Some(2),
+        ..Limits::default()
+    })
+    .unwrap_err();
+    assert_display_snapshot!(error, @"the program has 3 nodes, more than the limit of 2");
+
+    // The facts are counted as they're emitted, block by block: the emission stops at the first
+    // block exceeding the limit
+    let error = emit_with_limits(Limits {
+        max_facts: Some(10),
+        ..Limits::default()
+    })
+    .unwrap_err();
+    assert_display_snapshot!(error, @"the program has 16 facts, more than the limit of 10");
+}
+
+#[test]
+fn solver_iterations() {
+    let facts = emit_facts(PROGRAM).unwrap();
+    let limits = |max_iterations| Limits {
+        max_iterations: Some(max_iterations),
+        ..Limits::default()
+    };
+
+    // Solving within the limits is the same as without them
+    assert_eq!(
+        solve_with_limits(&facts, &limits(100)).unwrap(),
+        solve(&facts)
+    );
+
+    let error = solve_with_limits(&facts, &limits(1)).unwrap_err();
+    assert_display_snapshot!(error, @"computing `origin_live_on_entry` takes more than the limit of 1 iterations");
+    let error = diagnose_with_limits(&facts, &limits(1)).unwrap_err();
+    assert_display_snapshot!(error, @"computing `origin_live_on_entry` takes more than the limit of 1 iterations");
+}
+
+#[test]
+fn timeout() {
+    let facts = emit_facts(PROGRAM).unwrap();
+    let limits = Limits {
+        timeout: Some(Duration::from_secs(0)),
+        ..Limits::default()
+    };
+    let error = solve_with_limits(&facts, &limits).unwrap_err();
+    assert_display_snapshot!(error, @"the analysis takes more than the limit of 0ns, while preparing the facts");
+
+    // The timeout also covers the emission of the facts, and their lints
+    let error = emit_with_limits(limits.clone()).unwrap_err();
+    assert_display_snapshot!(error, @"the analysis takes more than the limit of 0ns, while validating the program");
+    let error = lint_with_limits(PROGRAM, &facts, &limits).unwrap_err();
+    assert_display_snapshot!(error, @"the analysis takes more than the limit of 0ns, while linting the borrows");
+
+    // The clock of an analysis which has already started is shared by its phases
+    let limits = Limits {
+        timeout: Some(Duration::from_secs(60)),
+        started: Some(Instant::now() - Duration::from_secs(61)),
+        ..Limits::default()
+    };
+    let error = emit_with_limits(limits).unwrap_err();
+    assert_display_snapshot!(error, @"the analysis takes more than the limit of 60s, while validating the program");
+}
@@ -0,0 +1,113 @@
+//! Lints of the emitted facts, reported as warnings at the declarations of the program, to keep
+//! handwritten programs honest: the parts of a program which play no role in its facts are
+//! usually mistakes, e.g. a reference assigned but never used, where an error was expected.
+//!
+//! A variable is unused when it's never read, borrowed, or otherwise used, e.g. only assigned.
+//! Variables whose name starts with an underscore are not reported, like in Rust. An origin of
+//! the type of a variable is unused when it's never the target of a subset, nor accessed:
+//! no loan can flow into it, and it's never live. Placeholders are not reported, as they are
+//! related to the caller's origins.
+//!
+//! A borrow is unused when its loan can't flow into an origin live after it's issued: the
+//! reference it creates is never accessed, like the dead loans whose invalidations are pruned by
+//! the emitter, see `EmitOptions::keep_dead_loans`. Placeholders are live everywhere, and so are
+//! the loans flowing into them.
+
+use crate::ast::ANONYMOUS_ORIGIN;
+use crate::ast_parser::parse_ast;
+use crate::fact_emitter::{Facts, Origin};
+use crate::limits::{Budget, Limits};
+use crate::validation::{Severity, SourceDiagnostic};
+use std::collections::HashSet;
+
+#[cfg(test)]
+mod test;
+
+/// Parses the input program, whose facts have been emitted, and returns the warnings about its
+/// unused variables, origins, and borrows, in program order.
+pub fn lint(input: &str, facts: &Facts) -> eyre::Result<Vec<SourceDiagnostic>> {
+    lint_with_limits(input, facts, &Limits::default())
+}
+
+/// Returns the warnings about the program like `lint`, or fails if that exceeds the timeout of
+/// the analysis.
+#[tracing::instrument(skip_all)]
+pub fn lint_with_limits(
+    input: &str,
+    facts: &Facts,
+    limits: &Limits,
+) -> eyre::Result<Vec<SourceDiagnostic>> {
+    let budget = Budget::start(limits);
+    let program = parse_ast(input)?;
+    let used_variables: HashSet<_> = facts.var_used_at.iter().map(|(var, _)| var).collect();
+    let used_origins: HashSet<_> = facts
+        .introduce_subset
+        .iter()
+        .map(|(_, origin, _)| origin)
+        .chain(facts.access_origin.iter().map(|(origin, _)| origin))
+        .collect();
+    let placeholders: Vec<Origin> = program.placeholders.iter().map(Origin::from).collect();
+
+    let mut warnings = Vec::new();
+    let mut reported_origins = HashSet::new();
+    for decl in &program.variables {
+        if !decl.name.starts_with('_') && !used_variables.contains(&decl.name) {
+            warnings.push(SourceDiagnostic::new(
+                Severity::Warning,
+                format!("variable `{}` is never used", decl.name),
+                decl.span,
+                input,
+            ));
+        }
+
+        // Each origin is reported once, at the first variable whose type contains it
+        let mut origins = Vec::new();
+        decl.ty.collect_origins_into(&mut origins);
+        for origin in origins {
+            if &*origin.0 == ANONYMOUS_ORIGIN
+                || placeholders.contains(&origin)
+                || used_origins.contains(&origin)
+                || !reported_origins.insert(origin.clone())
+            {
+                continue;
+            }
+            warnings.push(SourceDiagnostic::new(
+                Severity::Warning,
+                format!(
+                    "origin `{}` of variable `{}` is never accessed, nor the target of a subset",
+                    origin.0, decl.name
+                ),
+                decl.span,
+                input,
+            ));
+        }
+    }
+
+    // The borrows are located by the spans of their nodes
+    let is_live = facts.loan_live_on_exit();
+    for (origin, loan, node) in &facts.loan_issued_at {
+        budget.check_time("linting the borrows")?;
+        if is_live(origin, node) {
+            continue;
+        }
+        let span = facts.node_span(node);
+        let place = facts
+            .loan_place
+            .iter()
+            .find(|(place_loan, ..)| place_loan == loan)
+            .map(|(_, _, place)| place);
+        if let (Some(span), Some(place)) = (span, place) {
+            warnings.push(SourceDiagnostic::new(
+                Severity::Warning,
+                format!(
+                    "borrow of `{}` with origin `{}` is never used",
+                    place, origin.0
+                ),
+                span,
+                input,
+            ));
+        }
+    }
+    warnings.sort_by_key(|warning| warning.span.start());
+    Ok(warnings)
+}
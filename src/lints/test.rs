@@ -0,0 +1,122 @@
+use super::*;
+use crate::diagnostics::diagnose;
+use crate::fact_emitter::emit_facts;
+use insta::assert_display_snapshot;
+
+// The warnings about the program, one after the other.
+fn expect_warnings(input: &str) -> String {
+    let facts = emit_facts(input).unwrap();
+    lint(input, &facts)
+        .unwrap()
+        .iter()
+        .map(SourceDiagnostic::to_string)
+        .collect()
+}
+
+#[test]
+fn unused_variables() {
+    // `z` is only assigned, and `_w` is never used on purpose
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+        let z: i32;
+        let _w: i32;
+        bb0: {
+            y = &'L_x x;
+            z = 1;
+            use(copy y);
+        }
+    ";
+    assert_display_snapshot!(expect_warnings(program), @r###"
+    warning: variable `z` is never used
+      --> 57..58 `z`
+    "###);
+}
+
+#[test]
+fn unused_origins() {
+    // No loan flows into `'z`, which is never accessed either, and `'a` is a placeholder: only
+    // the variable `p` is unused
+    let program = "
+        placeholder 'a;
+        let x: i32;
+        let y: &'y i32;
+        let z: &'z i32;
+        let p: &'a i32;
+        bb0: {
+            y = &'L_x x;
+            use(copy y);
+        }
+    ";
+    assert_display_snapshot!(expect_warnings(program), @r###"
+    warning: variable `z` is never used
+      --> 81..82 `z`
+    warning: origin `'z` of variable `z` is never accessed, nor the target of a subset
+      --> 81..82 `z`
+    warning: variable `p` is never used
+      --> 105..106 `p`
+    "###);
+
+    // An origin is only reported at the first variable it's in
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+        let z: &'y i32;
+        bb0: {
+            x = 1;
+            use(copy x);
+        }
+    ";
+    assert_display_snapshot!(expect_warnings(program), @r###"
+    warning: variable `y` is never used
+      --> 33..34 `y`
+    warning: origin `'y` of variable `y` is never accessed, nor the target of a subset
+      --> 33..34 `y`
+    warning: variable `z` is never used
+      --> 57..58 `z`
+    "###);
+}
+
+#[test]
+fn unused_borrows() {
+    // The first borrow is overwritten before `y` is used, and the last one is never used
+    let program = "
+        let x: i32;
+        let y: &'y i32;
+        let z: &'z i32;
+        bb0: {
+            y = &'L_x x;
+            y = &'L_x2 x;
+            use(copy y);
+            z = &'L_x3 x;
+        }
+    ";
+    assert_display_snapshot!(expect_warnings(program), @r###"
+    warning: variable `z` is never used
+      --> 57..58 `z`
+    warning: borrow of `x` with origin `'L_x` is never used
+      --> 96..107 `y = &'L_x x`
+    warning: borrow of `x` with origin `'L_x3` is never used
+      --> 172..184 `z = &'L_x3 x`
+    "###);
+}
+
+#[test]
+fn borrows_flowing_into_placeholders() {
+    // The loan flows into the placeholder, which outlives the function: the borrow is used,
+    // even though `x` never is, and it's an error for `z` not to live long enough
+    let program = "
+        placeholder 'a;
+        let z: i32;
+        let x: &'a i32;
+        bb0: {
+            x = &'L z;
+        }
+    ";
+    assert_display_snapshot!(expect_warnings(program), @r###"
+    warning: variable `x` is never used
+      --> 57..58 `x`
+    "###);
+    let errors = diagnose(&emit_facts(program).unwrap());
+    assert!(errors.iter().any(|error| error.code == Some("E0597")));
+}
@@ -0,0 +1,540 @@
+//! Lowering of the statements as they are written in the input, where expressions can be
+//! nested, to the AST, where each statement is a single simple operation.
+//!
+//! The parser builds the program as it's written, see `surface::Program`, and lowering it is a
+//! separate pass run after parsing: the AST is the intermediate representation the facts are
+//! emitted from, structured like MIR with locals, basic blocks of simple statements, terminators,
+//! and places with projections, so that the surface syntax can grow without the emitter knowing
+//! about it. The locals are the declared variables and the temporaries, named like in the input.
+//!
+//! Nested expressions are evaluated into compiler-generated temporaries, declared without a
+//! type, by extra statements inserted before the statement that uses them. For example,
+//! `x = foo(&'a y.f).g;` is lowered to:
+//!
+//! ```notrust
+//! _tmp0 = &'a y.f;
+//! _tmp1 = foo(move _tmp0);
+//! x = move _tmp1.g;
+//! ```
+//!
+//! The operands of casts are lowered like call arguments, so that `p = &x as *const i32;` is
+//! `_tmp0 = &x; p = move _tmp0 as *const i32;`.
+//!
+//! Method calls are calls to the method of the `impl` block declaring it, whose first argument
+//! is the receiver, borrowed as the method's `self` requires: `v.push(x)` is lowered to
+//! `_tmp0 = &mut v; Vec::push(move _tmp0, x)`, where the borrow spans the method call. Methods
+//! are found by their name, so they can only be called when it's unique among the `impl` blocks.
+//!
+//! The attributes of a statement are recorded with its span, and apply to all the statements
+//! lowered from it.
+//!
+//! Blocks containing structured control flow (`if`, `while`, `loop`) are split into multiple
+//! basic blocks, named after the block they're in (`bb0_1`, `bb0_2`, ...), with the
+//! successors the control flow requires. Conditions are evaluated in their own statement,
+//! spanning the condition. Loops go back to their head at the end of their body, or at a
+//! `continue`: the block evaluating the condition of a `while`, or the body of a `loop`.
+//!
+//! The operands of terminators are evaluated at the end of their block, in their own statement
+//! like conditions: `switch(copy x) -> [bb1, bb2];` ends the block with a `copy x;` statement,
+//! the call of `y = f(move x) -> [return: bb1];` is the block's last statement, and so is the
+//! condition of `assert(copy c) -> [success: bb1, unwind: bb2];`.
+
+use crate::ast::{self, AccessKind, Name};
+use crate::span::{Span, Spanned as Sp, WithSpan};
+use crate::surface::{BasicBlock, Expr, Place, PlaceBase, Program, Statement, Terminator};
+use std::fmt;
+
+#[cfg(test)]
+mod test;
+
+/// An error in a program which parses but can't be lowered, e.g. a `break` outside of a loop, at
+/// the span of the statement or expression.
+#[derive(Debug)]
+pub(crate) struct LoweringError {
+    pub(crate) span: Span,
+    message: String,
+}
+
+impl fmt::Display for LoweringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for LoweringError {}
+
+fn error(message: impl Into<String>, span: Span) -> LoweringError {
+    LoweringError {
+        span,
+        message: message.into(),
+    }
+}
+
+/// Lowers the blocks of the parsed program, to the AST the facts are emitted from.
+#[tracing::instrument(name = "lowering", skip_all)]
+pub(crate) fn lower_program(program: Program<'_>) -> Result<ast::Program, LoweringError> {
+    let mut lowering = Lowering::new(program.variables, &program.fn_prototypes);
+    let mut basic_blocks = Vec::new();
+    for bb in &program.basic_blocks {
+        basic_blocks.extend(lowering.lower_block(bb)?);
+    }
+    Ok(ast::Program {
+        struct_decls: program.struct_decls,
+        fn_prototypes: program.fn_prototypes,
+        placeholders: program.placeholders,
+        known_placeholder_subsets: program.known_placeholder_subsets,
+        variables: lowering.variables,
+        basic_blocks,
+        statement_attributes: lowering.statement_attributes,
+        source_locations: lowering.source_locations,
+        raw_facts: program.raw_facts,
+    })
+}
+
+struct Lowering {
+    variables: Vec<ast::VariableDecl>,
+    statement_attributes: Vec<Sp<ast::Attribute>>,
+    source_locations: Vec<Sp<ast::SourceLocation>>,
+    temp_count: usize,
+
+    // The methods which can be called, by name: the function declaring them, and how their
+    // receiver is accessed.
+    methods: Vec<(Name, Name, AccessKind)>,
+}
+
+impl Lowering {
+    fn new(variables: Vec<ast::VariableDecl>, fn_prototypes: &[ast::FnPrototype]) -> Self {
+        let methods = fn_prototypes
+            .iter()
+            .filter(|prototype| prototype.is_method)
+            .map(|prototype| {
+                let method = match prototype.name.rsplit_once("::") {
+                    Some((_, method)) => method,
+                    None => &prototype.name,
+                };
+                let receiver_kind = match &prototype.arg_tys[0] {
+                    ast::Ty::Ref { .. } => AccessKind::Borrow(ast::ANONYMOUS_ORIGIN.into()),
+                    ast::Ty::RefMut { .. } => AccessKind::BorrowMut(ast::ANONYMOUS_ORIGIN.into()),
+                    _ => AccessKind::Move,
+                };
+                (Name::from(method), prototype.name, receiver_kind)
+            })
+            .collect();
+        Self {
+            variables,
+            statement_attributes: Vec::new(),
+            source_locations: Vec::new(),
+            temp_count: 0,
+            methods,
+        }
+    }
+
+    fn lower_block(&mut self, bb: &BasicBlock<'_>) -> Result<Vec<ast::BasicBlock>, LoweringError> {
+        let mut blocks = Blocks::new(bb.name, bb.span);
+        self.lower_statements(&bb.statements, &mut blocks)?;
+        let terminator = self.lower_terminator(&bb.terminator, &mut blocks)?;
+        Ok(blocks.finish(terminator))
+    }
+
+    fn lower_terminator(
+        &mut self,
+        terminator: &Terminator<'_>,
+        blocks: &mut Blocks,
+    ) -> Result<ast::Terminator, LoweringError> {
+        Ok(match terminator {
+            Terminator::Goto(targets) => ast::Terminator::Goto(targets.clone()),
+            Terminator::Return => ast::Terminator::Return,
+            Terminator::Switch {
+                discriminant,
+                targets,
+            } => ast::Terminator::Switch {
+                discriminant: self.lower_evaluated(None, discriminant, blocks)?,
+                targets: targets.clone(),
+            },
+            Terminator::Call {
+                destination,
+                call,
+                target,
+                unwind,
+            } => ast::Terminator::Call {
+                call: self.lower_evaluated(destination.clone(), call, blocks)?,
+                destination: destination.clone(),
+                target: *target,
+                unwind: *unwind,
+            },
+            Terminator::Assert {
+                condition,
+                success,
+                unwind,
+            } => ast::Terminator::Assert {
+                condition: self.lower_evaluated(None, condition, blocks)?,
+                success: *success,
+                unwind: *unwind,
+            },
+            Terminator::Unreachable => ast::Terminator::Unreachable,
+        })
+    }
+
+    fn lower_statements(
+        &mut self,
+        statements: &[Sp<Statement<'_>>],
+        blocks: &mut Blocks,
+    ) -> Result<(), LoweringError> {
+        for s in statements {
+            let span = s.span();
+            let statement = match &**s {
+                Statement::Let(variable, expr) => {
+                    let place = ast::Place {
+                        base: variable.name,
+                        projections: vec![],
+                    };
+                    self.variables.push(variable.clone());
+                    ast::Statement::Assign(place, self.lower_expr(expr, &mut blocks.statements)?)
+                }
+                Statement::Assign(place, expr) => ast::Statement::Assign(
+                    place.clone(),
+                    self.lower_expr(expr, &mut blocks.statements)?,
+                ),
+                Statement::Expr(expr) => {
+                    ast::Statement::Expr(self.lower_expr(expr, &mut blocks.statements)?)
+                }
+                Statement::End(origin) => ast::Statement::End(*origin),
+                Statement::Kill(place) => ast::Statement::Kill(place.clone()),
+
+                Statement::If {
+                    condition,
+                    then_block,
+                    else_block,
+                } => {
+                    self.lower_condition(condition, blocks)?;
+                    let then_name = blocks.fresh_name();
+                    let else_name = else_block.as_ref().map(|_| blocks.fresh_name());
+                    let join_name = blocks.fresh_name();
+
+                    let else_successor = else_name.unwrap_or(join_name);
+                    blocks.switch_to(vec![then_name, else_successor], then_name);
+                    self.lower_statements(then_block, blocks)?;
+                    if let (Some(else_name), Some(else_block)) = (else_name, else_block.as_ref()) {
+                        blocks.switch_to(vec![join_name], else_name);
+                        self.lower_statements(else_block, blocks)?;
+                    }
+                    blocks.switch_to(vec![join_name], join_name);
+                    continue;
+                }
+
+                Statement::While { condition, body } => {
+                    let head_name = blocks.fresh_name();
+                    let body_name = blocks.fresh_name();
+                    let exit_name = blocks.fresh_name();
+
+                    blocks.switch_to(vec![head_name], head_name);
+                    self.lower_condition(condition, blocks)?;
+                    blocks.switch_to(vec![body_name, exit_name], body_name);
+                    self.lower_loop_body(body, head_name, exit_name, blocks)?;
+                    blocks.switch_to(vec![head_name], exit_name);
+                    continue;
+                }
+
+                Statement::Loop { body } => {
+                    let body_name = blocks.fresh_name();
+                    let exit_name = blocks.fresh_name();
+
+                    blocks.switch_to(vec![body_name], body_name);
+                    self.lower_loop_body(body, body_name, exit_name, blocks)?;
+                    blocks.switch_to(vec![body_name], exit_name);
+                    continue;
+                }
+
+                Statement::Break => {
+                    let (_, exit_name) = blocks
+                        .loops
+                        .last()
+                        .cloned()
+                        .ok_or_else(|| error("`break` outside of a loop", span))?;
+
+                    // Any statements following the `break` are in an unreachable block
+                    let unreachable_name = blocks.fresh_name();
+                    blocks.switch_to(vec![exit_name], unreachable_name);
+                    continue;
+                }
+
+                Statement::Continue => {
+                    let (head_name, _) = blocks
+                        .loops
+                        .last()
+                        .cloned()
+                        .ok_or_else(|| error("`continue` outside of a loop", span))?;
+
+                    // Like after a `break`, the following statements are unreachable
+                    let unreachable_name = blocks.fresh_name();
+                    blocks.switch_to(vec![head_name], unreachable_name);
+                    continue;
+                }
+
+                Statement::Attributed(attributes, statement) => {
+                    let span = statement.span();
+                    self.statement_attributes
+                        .extend(attributes.iter().map(|&attribute| attribute.at(span)));
+                    self.lower_statements(std::slice::from_ref(&**statement), blocks)?;
+                    continue;
+                }
+
+                Statement::Located(location, statement) => {
+                    let span = statement.span();
+                    self.source_locations.push(location.clone().at(span));
+                    self.lower_statements(std::slice::from_ref(&**statement), blocks)?;
+                    continue;
+                }
+            };
+            blocks.statements.push(statement.at(span));
+        }
+        Ok(())
+    }
+
+    // Evaluates the condition of an `if` or `while`, in its own statement.
+    fn lower_condition(
+        &mut self,
+        condition: &Sp<Expr<'_>>,
+        blocks: &mut Blocks,
+    ) -> Result<(), LoweringError> {
+        self.lower_evaluated(None, condition, blocks)?;
+        Ok(())
+    }
+
+    // Evaluates the expression in its own statement spanning it, assigning it to the
+    // destination if there's one, and returns the lowered expression.
+    fn lower_evaluated(
+        &mut self,
+        destination: Option<ast::Place>,
+        expr: &Sp<Expr<'_>>,
+        blocks: &mut Blocks,
+    ) -> Result<ast::Expr, LoweringError> {
+        let span = expr.span();
+        let expr = self.lower_expr(expr, &mut blocks.statements)?;
+        let statement = match destination {
+            Some(place) => ast::Statement::Assign(place, expr.clone()),
+            None => ast::Statement::Expr(expr.clone()),
+        };
+        blocks.statements.push(statement.at(span));
+        Ok(expr)
+    }
+
+    fn lower_loop_body(
+        &mut self,
+        body: &[Sp<Statement<'_>>],
+        head_name: Name,
+        exit_name: Name,
+        blocks: &mut Blocks,
+    ) -> Result<(), LoweringError> {
+        blocks.loops.push((head_name, exit_name));
+        self.lower_statements(body, blocks)?;
+        blocks.loops.pop();
+        Ok(())
+    }
+
+    fn lower_expr(
+        &mut self,
+        expr: &Expr<'_>,
+        statements: &mut Vec<Sp<ast::Statement>>,
+    ) -> Result<ast::Expr, LoweringError> {
+        Ok(match expr {
+            Expr::Access { kind, place } => ast::Expr::Access {
+                kind: kind.clone(),
+                place: self.lower_place(place, statements)?,
+            },
+            Expr::Number { value } => ast::Expr::Number { value: *value },
+            Expr::Call {
+                name,
+                generic_args,
+                arguments,
+            } => ast::Expr::Call {
+                name: *name,
+                generic_args: generic_args.clone(),
+                arguments: arguments
+                    .iter()
+                    .map(|arg| self.lower_operand(arg, statements))
+                    .collect::<Result<_, _>>()?,
+            },
+            Expr::MethodCall {
+                receiver,
+                method,
+                generic_args,
+                arguments,
+                span,
+            } => {
+                let mut candidates = self.methods.iter().filter(|(name, ..)| name == method);
+                let (_, name, receiver_kind) = candidates
+                    .next()
+                    .ok_or_else(|| error(format!("unknown method `{}`", method), *span))?;
+                if candidates.next().is_some() {
+                    return Err(error(
+                        format!("method `{}` is declared in multiple `impl` blocks", method),
+                        *span,
+                    ));
+                }
+                let (name, receiver_kind) = (*name, receiver_kind.clone());
+
+                // The call's first argument is the receiver, accessed like the method declares
+                let receiver = ast::Expr::Access {
+                    kind: receiver_kind,
+                    place: self.lower_place(receiver, statements)?,
+                };
+                let mut lowered_arguments = vec![self.make_operand(receiver, *span, statements)];
+                for arg in arguments {
+                    lowered_arguments.push(self.lower_operand(arg, statements)?);
+                }
+                ast::Expr::Call {
+                    name,
+                    generic_args: generic_args.clone(),
+                    arguments: lowered_arguments,
+                }
+            }
+            Expr::Cast { operand, ty } => ast::Expr::Cast {
+                operand: Box::new(self.lower_operand(operand, statements)?),
+                ty: ty.clone(),
+            },
+            Expr::Unit => ast::Expr::Unit,
+        })
+    }
+
+    // Lowers a call argument, or the operand of a cast.
+    fn lower_operand(
+        &mut self,
+        expr: &Sp<Expr<'_>>,
+        statements: &mut Vec<Sp<ast::Statement>>,
+    ) -> Result<ast::Expr, LoweringError> {
+        let lowered = self.lower_expr(expr, statements)?;
+        Ok(self.make_operand(lowered, expr.span(), statements))
+    }
+
+    // Borrows, calls and casts are evaluated into a temporary, in a new statement at the span,
+    // which is moved into the call or cast.
+    fn make_operand(
+        &mut self,
+        expr: ast::Expr,
+        span: Span,
+        statements: &mut Vec<Sp<ast::Statement>>,
+    ) -> ast::Expr {
+        match expr {
+            ast::Expr::Access {
+                kind: AccessKind::Borrow(_) | AccessKind::BorrowMut(_),
+                ..
+            }
+            | ast::Expr::Call { .. }
+            | ast::Expr::Cast { .. } => {
+                let temp = self.assign_to_temp(expr, span, statements);
+                ast::Expr::Access {
+                    kind: AccessKind::Move,
+                    place: ast::Place {
+                        base: temp,
+                        projections: vec![],
+                    },
+                }
+            }
+            _ => expr,
+        }
+    }
+
+    fn lower_place(
+        &mut self,
+        place: &Place<'_>,
+        statements: &mut Vec<Sp<ast::Statement>>,
+    ) -> Result<ast::Place, LoweringError> {
+        let base = match &place.base {
+            PlaceBase::Variable(name) => *name,
+            PlaceBase::Temp(expr) => {
+                let lowered = self.lower_expr(expr, statements)?;
+                self.assign_to_temp(lowered, expr.span(), statements)
+            }
+        };
+        Ok(ast::Place {
+            base,
+            projections: place.projections.clone(),
+        })
+    }
+
+    // Assigns the lowered expression to a fresh temporary, in a new statement at the span, and
+    // returns the temporary's name.
+    fn assign_to_temp(
+        &mut self,
+        expr: ast::Expr,
+        span: Span,
+        statements: &mut Vec<Sp<ast::Statement>>,
+    ) -> Name {
+        let name = Name::from(format!("_tmp{}", self.temp_count));
+        self.temp_count += 1;
+        self.variables.push(ast::VariableDecl {
+            name,
+            ty: ast::Ty::Infer,
+            span,
+        });
+
+        let place = ast::Place {
+            base: name,
+            projections: vec![],
+        };
+        statements.push(ast::Statement::Assign(place, expr).at(span));
+        name
+    }
+}
+
+// The basic blocks lowered from a block of the input: the block itself, and the blocks split
+// from it by control flow, in the order they were created.
+struct Blocks {
+    blocks: Vec<ast::BasicBlock>,
+
+    // The block currently being lowered, and its statements so far
+    current_idx: usize,
+    statements: Vec<Sp<ast::Statement>>,
+
+    // The blocks to go to when continuing and breaking out of the enclosing loops, innermost
+    // last
+    loops: Vec<(Name, Name)>,
+}
+
+impl Blocks {
+    fn new(name: Name, span: Span) -> Self {
+        Self {
+            blocks: vec![ast::BasicBlock {
+                name,
+                span,
+                statements: Vec::new(),
+                terminator: ast::Terminator::Return,
+            }],
+            current_idx: 0,
+            statements: Vec::new(),
+            loops: Vec::new(),
+        }
+    }
+
+    fn fresh_name(&mut self) -> Name {
+        let name = Name::from(format!("{}_{}", self.blocks[0].name, self.blocks.len()));
+        self.blocks.push(ast::BasicBlock {
+            name,
+            span: self.blocks[0].span,
+            statements: Vec::new(),
+            terminator: ast::Terminator::Return,
+        });
+        name
+    }
+
+    // Ends the current block with a `goto` to the given successors, and starts lowering the
+    // block `name`.
+    fn switch_to(&mut self, successors: Vec<Name>, name: Name) {
+        let current = &mut self.blocks[self.current_idx];
+        current.statements = std::mem::take(&mut self.statements);
+        current.terminator = ast::Terminator::Goto(successors);
+        self.current_idx = self
+            .blocks
+            .iter()
+            .position(|bb| bb.name == name)
+            .expect("switching to an unknown block");
+    }
+
+    fn finish(mut self, terminator: ast::Terminator) -> Vec<ast::BasicBlock> {
+        let current = &mut self.blocks[self.current_idx];
+        current.statements = std::mem::take(&mut self.statements);
+        current.terminator = terminator;
+        self.blocks
+    }
+}
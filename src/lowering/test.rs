@@ -0,0 +1,77 @@
+use bumpalo::Bump;
+
+use super::*;
+use crate::ast_parser::parse_surface;
+use crate::validation::validate;
+
+#[test]
+fn lowering_after_parsing() {
+    // The parsed program is as written, with the borrow nested in the call
+    let input = "
+        fn f<'a>(x: &'a i32) -> ();
+        let y: i32;
+        bb0: {
+            f(&'a y);
+        }
+    ";
+    let arena = Bump::new();
+    let program = parse_surface(input, &arena).unwrap();
+    assert_eq!(program.variables.len(), 1);
+    assert_eq!(program.basic_blocks[0].statements.len(), 1);
+
+    // Lowering evaluates it into a temporary, declared without a type
+    let program = lower_program(program).unwrap();
+    let variables: Vec<_> = program
+        .variables
+        .iter()
+        .map(|variable| variable.name.to_string())
+        .collect();
+    assert_eq!(variables, ["y", "_tmp0"]);
+    let statements: Vec<_> = program.basic_blocks[0]
+        .statements
+        .iter()
+        .map(|statement| statement.to_string())
+        .collect();
+    assert_eq!(statements, ["_tmp0 = &'a y", "f(move _tmp0)"]);
+}
+
+#[test]
+fn errors_at_their_span() {
+    fn lowering_error(input: &str) -> (String, &str) {
+        let error = lower_program(parse_surface(input, &Bump::new()).unwrap()).unwrap_err();
+        (
+            error.to_string(),
+            &input[error.span.start()..error.span.end()],
+        )
+    }
+    assert_eq!(
+        lowering_error("bb0: { loop { break; } continue; }"),
+        ("`continue` outside of a loop".to_string(), "continue;")
+    );
+    assert_eq!(
+        lowering_error("let v: i32; bb0: { v.push(1); }"),
+        ("unknown method `push`".to_string(), "v.push(1)")
+    );
+
+    // The validation recovers from them like from syntax errors
+    let input = "
+        let x: i32;
+        bb0: {
+            break;
+            x = 1;
+            x.f();
+        }
+    ";
+    let errors: Vec<_> = validate(input)
+        .unwrap()
+        .iter()
+        .map(|error| error.to_string())
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            "error: `break` outside of a loop\n  --> 48..54 `break;`\n",
+            "error: unknown method `f`\n  --> 86..91 `x.f()`\n"
+        ]
+    );
+}
@@ -1,6 +1,1184 @@
+use eyre::WrapErr;
+use std::path::Path;
+use tracing_subscriber::prelude::*;
+
+// The allocations are counted for `--timings`
+#[global_allocator]
+static ALLOCATOR: polonius::CountingAllocator = polonius::CountingAllocator;
+
+// Any command takes `--timings`, or `--timings=json`, to print the time and allocations of each
+// phase of the analysis to stderr when it's done, as a table or as JSON, see `polonius::Timings`.
 fn main() -> eyre::Result<()> {
-    for arg in std::env::args().skip(1) {
-        polonius::test_harness(&arg)?;
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let timings_format = args
+        .iter()
+        .position(|arg| arg.starts_with("--timings"))
+        .map(|idx| args.remove(idx));
+    if let Some(format) = &timings_format {
+        if format != "--timings" && format != "--timings=json" {
+            eyre::bail!(
+                "unexpected argument `{}`, expected `--timings[=json]`",
+                format
+            );
+        }
     }
+
+    // Logs of the parser, emitter, and solver go to stderr, selected with `RUST_LOG`, e.g.
+    // `RUST_LOG=polonius::solver=trace`. Nothing is logged by default. The spans are all
+    // recorded for the timings, whatever the logs' level.
+    let timings = polonius::TimingsLayer::default();
+    let timings_layer = timings_format.as_ref().map(|_| {
+        timings
+            .clone()
+            .with_filter(tracing_subscriber::filter::filter_fn(|metadata| {
+                metadata.is_span()
+            }))
+    });
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(tracing_subscriber::EnvFilter::from_default_env()),
+        )
+        .with(timings_layer)
+        .init();
+
+    let result = tracing::info_span!("main").in_scope(|| run(&args));
+    match timings_format.as_deref() {
+        Some("--timings=json") => eprintln!("{}", timings.timings().to_json()),
+        Some(_) => eprint!("{}", timings.timings()),
+        None => {}
+    }
+    match result {
+        Ok(0) => Ok(()),
+        Ok(exit_code) => std::process::exit(exit_code),
+        Err(e) => Err(e),
+    }
+}
+
+// Runs the command, and returns its exit code.
+fn run(args: &[String]) -> eyre::Result<i32> {
+    match args.first().map(String::as_str) {
+        Some("check") => {
+            // Scripts tell the programs with errors from the ones which couldn't be analyzed
+            match check(&args[1..]) {
+                Ok(false) => Ok(0),
+                Ok(true) => Ok(1),
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    Ok(2)
+                }
+            }
+        }
+        Some("cargo") => match cargo(&args[1..]) {
+            Ok(false) => Ok(0),
+            Ok(true) => Ok(1),
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                Ok(2)
+            }
+        },
+        _ => run_command(args).map(|_| 0),
+    }
+}
+
+// Runs one of the commands which fail with an error, rather than an exit code.
+fn run_command(args: &[String]) -> eyre::Result<()> {
+    match args.first().map(String::as_str) {
+        Some("emit") => emit(&args[1..]),
+        Some("explain") => explain(&args[1..]),
+        Some("view") => view(&args[1..]),
+        Some("repl") => repl(&args[1..]),
+        Some("query") => query(&args[1..]),
+        Some("subset-graph") => subset_graph(&args[1..]),
+        Some("slice") => slice(&args[1..]),
+        Some("compare-rustc") => compare_rustc(&args[1..]),
+        Some("import") => import(&args[1..]),
+        Some("export") => export(&args[1..]),
+        Some("convert") => convert(&args[1..]),
+        Some("compare-legacy") => compare_legacy(&args[1..]),
+        Some("compare-models") => compare_models(&args[1..]),
+        Some("check-annotations") => check_annotations(&args[1..]),
+        Some("stress") => stress(&args[1..]),
+        Some("baseline") => baseline(&args[1..]),
+        Some("coverage") => coverage(&args[1..]),
+        Some("fuzz") => fuzz(&args[1..]),
+        _ => {
+            for arg in args {
+                polonius::test_harness(arg)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// `emit <program>* [--function <name>] [--legacy] [--only <kind>=<value>]* [--only-node <node>]*
+// [--slice loan=<origin> | --slice error=<node>] [--raw-pointer-casts <model>]
+// [--exclude-unreachable] [--keep-dead-loans] [--node-names <naming>] [--color] [--format <format>
+// [--output <path>]] [--config <path>]`: emits the facts of a program, or of a legacy fact set
+// directory with `--legacy`, in the frontend format, optionally keeping only the facts matching the
+// given filters. With `--format`, they're written in another format of `convert` instead, e.g.
+// `--format csv --output <dir>` for a CSV file per relation: the `text` and `json` formats are
+// printed without `--output`, and the others need it. With `--slice`, only the facts relevant to a
+// loan, or to the errors at a node, are kept, see `Facts::slice`. The facts of unreachable blocks
+// are left out with `--exclude-unreachable`. The invalidations of dead loans are pruned, unless
+// `--keep-dead-loans` is given. The nodes are named after the index of their statement, or with
+// `--node-names hashed`, after its text. The unused variables, origins and borrows of the program
+// are warned about, see `lint`. Given several programs, the bodies of as many functions, their
+// facts are scoped by the name of their file, without its extension, like `main::bb0[1]`, and
+// emitted together, see `Facts::namespaced`. A single program's facts are scoped with
+// `--function <name>`. The output is colored with `--color`. The options of the configuration file
+// are applied first, see `load_config`.
+fn emit(args: &[String]) -> eyre::Result<()> {
+    let (config, args) = load_config(args)?;
+    let mut program_paths = Vec::new();
+    let mut function = None;
+    let mut is_legacy = false;
+    let mut color = config.color;
+    let mut filter = config.filter;
+    let mut options = config.emit;
+    let mut slice: Option<polonius::FactSlice> = None;
+    let mut format = polonius::FactFormat::Text;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--only" => {
+                let criterion = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--only` expects a filter, e.g. `origin='a`"))?;
+                filter.add_criterion(criterion)?;
+            }
+            "--only-node" => {
+                let node = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--only-node` expects a node, e.g. `bb1[0]`"))?;
+                filter.nodes.push(node.into());
+            }
+            "--slice" => {
+                let value = args.next().ok_or_else(|| {
+                    eyre::eyre!("`--slice` expects a loan or an error, e.g. `loan='L_x`")
+                })?;
+                slice = Some(value.parse()?);
+            }
+            "--raw-pointer-casts" => options.raw_pointer_casts = raw_pointer_casts(args.next())?,
+            "--exclude-unreachable" => options.exclude_unreachable_blocks = true,
+            "--keep-dead-loans" => options.keep_dead_loans = true,
+            "--node-names" => options.node_names = node_names(args.next())?,
+            "--function" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--function` expects the name of the function"))?;
+                function = Some(name.as_str());
+            }
+            "--legacy" => is_legacy = true,
+            "--color" => color = true,
+            "--format" => {
+                format = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--format` expects a fact format, e.g. `csv`"))?
+                    .parse()?;
+            }
+            "--output" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--output` expects a path"))?;
+                output = Some(Path::new(path));
+            }
+            _ if arg.starts_with("--") => eyre::bail!("unexpected argument `{}`", arg),
+            _ => program_paths.push(arg),
+        }
+    }
+
+    let emit_program = |program_path: &String| -> eyre::Result<polonius::Facts> {
+        if is_legacy {
+            return Ok(polonius::LegacyFacts::read(Path::new(program_path))?.to_facts());
+        }
+        let sources = polonius::Sources::load(Path::new(program_path))?;
+        validate(&sources, color, false)?;
+        let facts = polonius::emit_facts_with_options(sources.text(), &options)?;
+        lint(&sources, &facts, &options.limits, color, false)?;
+        Ok(facts)
+    };
+    let facts = match program_paths.as_slice() {
+        [] => eyre::bail!("missing program to emit"),
+        [program_path] => {
+            let facts = emit_program(program_path)?;
+            match function {
+                Some(function) => facts.namespaced(function),
+                None => facts,
+            }
+        }
+        program_paths => {
+            if function.is_some() {
+                eyre::bail!("`--function` names the function of a single program");
+            }
+            let mut facts = polonius::Facts::default();
+            for program_path in program_paths {
+                let function = Path::new(program_path)
+                    .file_stem()
+                    .map_or(program_path.to_string(), |stem| {
+                        stem.to_string_lossy().into_owned()
+                    });
+                facts.append(emit_program(program_path)?.namespaced(&function));
+            }
+            facts
+        }
+    };
+    let facts = match &slice {
+        Some(slice) => facts.slice(slice)?,
+        None => facts,
+    };
+    let facts = facts.filter(&filter);
+    match (format, output) {
+        (_, Some(output)) => polonius::write_facts_in(&facts, format, output)?,
+        (polonius::FactFormat::Text, None) => {
+            let listing = facts.to_string();
+            if color {
+                print!("{}", polonius::colorize_facts(&listing));
+            } else {
+                print!("{}", listing);
+            }
+        }
+        (polonius::FactFormat::Json, None) => println!("{}", polonius::facts_to_json(&facts)),
+        (_, None) => eyre::bail!("`--format {}` needs an `--output <path>`", format.name()),
+    }
+    Ok(())
+}
+
+// `check <program> [--facts | --legacy] [--compare] [--loans] [--raw-pointer-casts <model>]
+// [--exclude-unreachable] [--keep-dead-loans] [--node-names <naming>] [--color] [--souffle]
+// [--explain] [--error-format=<human | json>] [--config <path>] [--max-nodes <n>] [--max-facts <n>]
+// [--max-iterations <n>] [--timeout <seconds>]`: analyzes a program, a fact file with
+// `--facts`, or a legacy fact set directory with `--legacy`, with the in-crate solver and reports
+// its errors, in the style of rustc's diagnostics, after the warnings about the unused variables,
+// origins and borrows of a program, see `lint`. With `--compare`, the naive solver also runs,
+// and its errors must match the optimized solver's. With `--loans`, the loans each origin may
+// contain at each node are printed first, as `origin_contains_loan_at` tuples. The output is
+// colored with `--color`. The analysis fails when it exceeds one of the `--max-*` limits, or the
+// timeout. The options of the configuration file are applied first, see `load_config`.
+//
+// With `--souffle`, the errors are computed by a locally installed Soufflé evaluating the
+// rules of `polonius.dl`, instead of the in-crate solver, see `polonius::diagnose_with_souffle`.
+// With `--explain`, the derivation tree of each error is attached to the diagnostics reporting
+// it, as a note, see `polonius::explain_diagnostics`: from Soufflé's provenance with
+// `--souffle`, or from the in-crate solver's derivations otherwise.
+//
+// With `--error-format=json`, the warnings and errors are printed to stdout instead, one JSON
+// object per line, in the structure of rustc's JSON diagnostics, see `Diagnostic::to_json`.
+//
+// Given several programs, or a directory of programs, they're analyzed in parallel, on
+// `--jobs <n>` threads, see `check_batch`. With `--sarif <path>`, the programs are analyzed as
+// a batch, even a single one, and their results are also written to a SARIF log.
+//
+// Returns whether errors were found: the exit code is 0 when there are none, 1 when there are,
+// and 2 when the analysis fails, e.g. on a program which doesn't parse, or exceeds the limits.
+fn check(args: &[String]) -> eyre::Result<bool> {
+    let (config, args) = load_config(args)?;
+    let mut program_paths = Vec::new();
+    let mut is_fact_file = false;
+    let mut is_legacy = false;
+    let mut compare = config.compare_solvers;
+    let mut print_loans = config.print_loans;
+    let mut color = config.color;
+    let mut options = config.emit;
+    let mut jobs = None;
+    let mut emit_only = false;
+    let mut json = false;
+    let mut sarif_path = None;
+    let mut souffle = false;
+    let mut explain = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--raw-pointer-casts" => options.raw_pointer_casts = raw_pointer_casts(args.next())?,
+            "--exclude-unreachable" => options.exclude_unreachable_blocks = true,
+            "--keep-dead-loans" => options.keep_dead_loans = true,
+            "--node-names" => options.node_names = node_names(args.next())?,
+            "--facts" => is_fact_file = true,
+            "--legacy" => is_legacy = true,
+            "--compare" => compare = true,
+            "--souffle" => souffle = true,
+            "--explain" => explain = true,
+            "--loans" => print_loans = true,
+            "--color" => color = true,
+            "--max-nodes" => options.limits.max_nodes = Some(limit(arg, args.next())?),
+            "--max-facts" => options.limits.max_facts = Some(limit(arg, args.next())?),
+            "--max-iterations" => options.limits.max_iterations = Some(limit(arg, args.next())?),
+            "--timeout" => {
+                let seconds = limit(arg, args.next())? as u64;
+                options.limits.timeout = Some(std::time::Duration::from_secs(seconds));
+            }
+            "--jobs" => jobs = Some(limit(arg, args.next())?),
+            "--emit-only" => emit_only = true,
+            "--sarif" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--sarif` expects the path of the log"))?;
+                sarif_path = Some(Path::new(path));
+            }
+            "--error-format=human" => json = false,
+            "--error-format=json" => json = true,
+            _ if arg.starts_with("--error-format") => eyre::bail!(
+                "unexpected argument `{}`, expected `--error-format=human` or `--error-format=json`",
+                arg
+            ),
+            _ if arg.starts_with("--") => eyre::bail!("unexpected argument `{}`", arg),
+            _ => program_paths.push(arg),
+        }
+    }
+
+    let is_batch = sarif_path.is_some()
+        || program_paths.len() > 1
+        || (!is_legacy && program_paths.iter().any(|path| Path::new(path).is_dir()));
+    if is_batch {
+        if is_fact_file || is_legacy || print_loans || compare || json || souffle || explain {
+            eyre::bail!(
+                "`--facts`, `--legacy`, `--loans`, `--compare`, `--souffle`, `--explain` and \
+                `--error-format=json` check a single program"
+            );
+        }
+        let mut batch = polonius::BatchOptions {
+            emit: options,
+            solve: !emit_only,
+            ..polonius::BatchOptions::default()
+        };
+        if let Some(jobs) = jobs {
+            batch.jobs = jobs;
+        }
+        return check_batch(&program_paths, &batch, sarif_path, color);
+    }
+
+    let program_path = match program_paths.as_slice() {
+        [program_path] => program_path,
+        _ => eyre::bail!("missing program to check"),
+    };
+
+    // The timeout covers the whole analysis, from the validation of the program
+    options.limits.started = Some(std::time::Instant::now());
+    let mut sources = None;
+    let facts = if is_legacy {
+        polonius::LegacyFacts::read(Path::new(program_path))?.to_facts()
+    } else {
+        if is_fact_file {
+            let input = std::fs::read_to_string(program_path)
+                .wrap_err_with(|| format!("failed to read `{}`", program_path))?;
+            polonius::read_facts(&input)?
+        } else {
+            let program = polonius::Sources::load(Path::new(program_path))?;
+            validate(&program, color, json)?;
+            let facts = polonius::emit_facts_with_options(program.text(), &options)?;
+            lint(&program, &facts, &options.limits, color, json)?;
+            sources = Some(program);
+            facts
+        }
+    };
+
+    if compare {
+        let optimized = polonius::solve(&facts);
+        let naive = polonius::solve_naive(&facts);
+        if optimized != naive {
+            eyre::bail!(
+                "the naive and optimized solvers disagree\noptimized: {:#?}\nnaive: {:#?}",
+                optimized,
+                naive
+            );
+        }
+    }
+
+    if print_loans {
+        let output = polonius::solve_with_limits(&facts, &options.limits)?;
+        for (origin, loan, node) in &output.origin_contains_loan_at {
+            let fact = format!(
+                "origin_contains_loan_at({}, {}, {})\n",
+                origin.0, loan.0, node.0
+            );
+            print!(
+                "{}",
+                if color {
+                    polonius::colorize_facts(&fact)
+                } else {
+                    fact
+                }
+            );
+        }
+    }
+
+    let mut diagnostics = if souffle {
+        let work_dir =
+            std::env::temp_dir().join(format!("polonius-souffle-{}", std::process::id()));
+        let diagnostics =
+            polonius::diagnose_with_souffle(&facts, &work_dir).and_then(|mut diagnostics| {
+                if explain {
+                    let derivations = polonius::explain_with_souffle(&facts, &work_dir)?;
+                    polonius::explain_diagnostics(&mut diagnostics, &derivations, &facts);
+                }
+                Ok(diagnostics)
+            });
+        let _ = std::fs::remove_dir_all(&work_dir);
+        diagnostics?
+    } else {
+        polonius::diagnose_with_limits(&facts, &options.limits)?
+    };
+    if explain && !souffle {
+        polonius::explain_diagnostics(&mut diagnostics, &polonius::explain(&facts), &facts);
+    }
+    for diagnostic in &diagnostics {
+        if json {
+            // Facts read from files have no spans, and their diagnostics no file name
+            match &sources {
+                Some(sources) => println!("{}", sources.diagnostic_to_json(diagnostic, &facts)),
+                None => println!("{}", diagnostic.to_json(&facts, program_path, "")),
+            }
+            continue;
+        }
+        // Facts read from files have no spans, and their diagnostics no snippets
+        let diagnostic = match &sources {
+            Some(sources) => format!("{}\n", sources.render_diagnostic(diagnostic, &facts)),
+            None => format!("{}\n", diagnostic),
+        };
+        print!(
+            "{}",
+            if color {
+                polonius::colorize_diagnostics(&diagnostic)
+            } else {
+                diagnostic
+            }
+        );
+    }
+
+    if !diagnostics.is_empty() {
+        eprintln!("found {} error(s)", diagnostics.len());
+    }
+    Ok(!diagnostics.is_empty())
+}
+
+// `check <program or directory>* [--jobs <n>] [--emit-only] [--sarif <path>]`: analyzes the
+// programs, and the `.txt` files of the directories, in parallel, and reports each one when
+// they're all done, in order: its warnings and errors, and its number of facts and the time it
+// took. With `--emit-only`, the facts are only emitted, not solved. With `--sarif`, the warnings
+// and errors are also written to a SARIF 2.1.0 log, see `polonius::sarif_log`. Fails if any
+// program couldn't be analyzed, and otherwise returns whether errors were found.
+fn check_batch(
+    paths: &[&String],
+    options: &polonius::BatchOptions,
+    sarif_path: Option<&Path>,
+    color: bool,
+) -> eyre::Result<bool> {
+    let paths: Vec<_> = paths.iter().map(std::path::PathBuf::from).collect();
+    let programs = polonius::collect_programs(&paths)?;
+    let start = std::time::Instant::now();
+    let reports = polonius::analyze_programs(&programs, options);
+    report_batch(&reports, "program(s)", start, sarif_path, color)
+}
+
+// Writes the reports of a batch to the SARIF log, if any, prints them and how many of the
+// analyzed `items` failed. Fails if some couldn't be analyzed, and returns whether errors were
+// found otherwise, like `check`.
+fn report_batch(
+    reports: &[polonius::ProgramReport],
+    items: &str,
+    start: std::time::Instant,
+    sarif_path: Option<&Path>,
+    color: bool,
+) -> eyre::Result<bool> {
+    if let Some(sarif_path) = sarif_path {
+        write_sarif_log(reports, sarif_path)?;
+    }
+    print_reports(reports, color);
+    println!(
+        "{} {}, {} failed, in {:.2?}",
+        reports.len(),
+        items,
+        reports.iter().filter(|report| !report.is_ok()).count(),
+        start.elapsed()
+    );
+    let analysis_failures = reports
+        .iter()
+        .filter(|report| report.outcome.is_err())
+        .count();
+    if analysis_failures > 0 {
+        eyre::bail!("{} {} couldn't be analyzed", analysis_failures, items);
+    }
+    Ok(reports.iter().any(|report| !report.is_ok()))
+}
+
+fn write_sarif_log(reports: &[polonius::ProgramReport], path: &Path) -> eyre::Result<()> {
+    let log = format!("{}\n", polonius::sarif_log(reports));
+    std::fs::write(path, log).wrap_err_with(|| format!("failed to write `{}`", path.display()))
+}
+
+// Prints each report of a batch: its warnings to stderr, its status, number of facts and the
+// time it took, and its errors.
+fn print_reports(reports: &[polonius::ProgramReport], color: bool) {
+    let colorize = |diagnostics: String| {
+        if color {
+            polonius::colorize_diagnostics(&diagnostics)
+        } else {
+            diagnostics
+        }
+    };
+    for report in reports {
+        let path = report.path.display();
+        match &report.outcome {
+            Ok(outcome) => {
+                for warning in &outcome.warnings {
+                    eprint!("{}", colorize(warning.clone()));
+                }
+                let status = if report.is_ok() { "ok" } else { "FAILED" };
+                println!(
+                    "{}: {} ({} facts, {:.2?})",
+                    status, path, outcome.fact_count, report.elapsed
+                );
+                for error in &outcome.rendered_errors {
+                    print!("{}", colorize(format!("{}\n", error)));
+                }
+            }
+            Err(e) => {
+                println!("FAILED: {} ({:.2?})", path, report.elapsed);
+                println!("{}", colorize(format!("{:#}", e)));
+            }
+        }
+    }
+}
+
+// Prints the errors and warnings of the program to stderr, so that they're not mixed with the
+// facts, optionally colored, or to stdout as JSON, and fails if there are errors.
+fn validate(sources: &polonius::Sources, color: bool, json: bool) -> eyre::Result<()> {
+    let diagnostics = sources.validate()?;
+    print_diagnostics(sources, &diagnostics, color, json);
+
+    let error_count = diagnostics.iter().filter(|d| d.is_error()).count();
+    if error_count > 0 {
+        eyre::bail!("found {} error(s)", error_count);
+    }
+    Ok(())
+}
+
+// Prints the warnings about the unused parts of the program to stderr, once its facts are
+// emitted, see `polonius::lint`.
+fn lint(
+    sources: &polonius::Sources,
+    facts: &polonius::Facts,
+    limits: &polonius::Limits,
+    color: bool,
+    json: bool,
+) -> eyre::Result<()> {
+    print_diagnostics(sources, &sources.lint(facts, limits)?, color, json);
+    Ok(())
+}
+
+fn print_diagnostics(
+    sources: &polonius::Sources,
+    diagnostics: &[polonius::SourceDiagnostic],
+    color: bool,
+    json: bool,
+) {
+    for diagnostic in diagnostics {
+        if json {
+            println!("{}", sources.to_json(diagnostic));
+            continue;
+        }
+        let rendered = sources.render(diagnostic);
+        if color {
+            eprint!("{}", polonius::colorize_diagnostics(&rendered));
+        } else {
+            eprint!("{}", rendered);
+        }
+    }
+}
+
+// The configuration of a command: the file given with `--config <path>`, or the
+// `polonius-next.toml` file of the current directory, if there is one. Returns the configuration,
+// and the other arguments of the command.
+fn load_config(args: &[String]) -> eyre::Result<(polonius::Config, Vec<String>)> {
+    let mut config_path = None;
+    let mut remaining = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--config` expects a configuration file"))?;
+                config_path = Some(path);
+            }
+            _ => remaining.push(arg.clone()),
+        }
+    }
+
+    let config = match config_path {
+        Some(path) => polonius::Config::load(Path::new(path))?,
+        None => polonius::Config::discover(Path::new("."))?,
+    };
+    Ok((config, remaining))
+}
+
+// The value of `--raw-pointer-casts`: how the loans of references cast to raw pointers are
+// modeled, either `end-tracking` (the default) or `keep-live`.
+fn raw_pointer_casts(value: Option<&String>) -> eyre::Result<polonius::RawPointerCasts> {
+    value
+        .ok_or_else(|| eyre::eyre!("`--raw-pointer-casts` expects `end-tracking` or `keep-live`"))?
+        .parse()
+}
+
+// The value of `--node-names`: how the nodes are named, either `indexed` (the default), or
+// `hashed` from the text of their statement.
+fn node_names(value: Option<&String>) -> eyre::Result<polonius::NodeNames> {
+    value
+        .ok_or_else(|| eyre::eyre!("`--node-names` expects `indexed` or `hashed`"))?
+        .parse()
+}
+
+// The value of one of the `--max-*` limits, or of `--timeout`.
+fn limit(flag: &str, value: Option<&String>) -> eyre::Result<usize> {
+    let value = value.ok_or_else(|| eyre::eyre!("`{}` expects a number", flag))?;
+    value
+        .parse()
+        .wrap_err_with(|| format!("invalid number `{}` for `{}`", value, flag))
+}
+
+// `explain <program> [--facts] [--json]`: prints the derivation tree of each error of a program,
+// or of a fact file with `--facts`, as text or as JSON.
+fn explain(args: &[String]) -> eyre::Result<()> {
+    let mut program_path = None;
+    let mut is_fact_file = false;
+    let mut json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--facts" => is_fact_file = true,
+            "--json" => json = true,
+            _ if program_path.is_none() => program_path = Some(arg),
+            _ => eyre::bail!("unexpected argument `{}`", arg),
+        }
+    }
+
+    let program_path = program_path.ok_or_else(|| eyre::eyre!("missing program to explain"))?;
+    let (_, facts) = query_facts(program_path, is_fact_file, false)?;
+
+    let derivations = polonius::explain(&facts);
+    if json {
+        let derivations = derivations.iter().map(polonius::Derivation::to_json);
+        println!("{}", polonius::Json::Array(derivations.collect()));
+    } else {
+        for derivation in &derivations {
+            println!("{}", derivation);
+        }
+    }
+    Ok(())
+}
+
+// `view <program> [--facts]`: steps through the nodes of a program, or of a fact file with
+// `--facts`, in the terminal, showing the source, the CFG, and the facts at each node.
+fn view(args: &[String]) -> eyre::Result<()> {
+    let mut program_path = None;
+    let mut is_fact_file = false;
+    for arg in args {
+        match arg.as_str() {
+            "--facts" => is_fact_file = true,
+            _ if program_path.is_none() => program_path = Some(arg),
+            _ => eyre::bail!("unexpected argument `{}`", arg),
+        }
+    }
+
+    let program_path = program_path.ok_or_else(|| eyre::eyre!("missing program to view"))?;
+    let (input, facts) = query_facts(program_path, is_fact_file, false)?;
+    polonius::run_viewer(&input, facts)
+}
+
+// `repl <program> [--facts]`: analyzes a program, or a fact file with `--facts`, and answers the
+// queries typed afterwards, like `subsets at bb2[1]` or `loans of x`, see `Query`.
+fn repl(args: &[String]) -> eyre::Result<()> {
+    let mut program_path = None;
+    let mut is_fact_file = false;
+    for arg in args {
+        match arg.as_str() {
+            "--facts" => is_fact_file = true,
+            _ if program_path.is_none() => program_path = Some(arg),
+            _ => eyre::bail!("unexpected argument `{}`", arg),
+        }
+    }
+
+    let program_path = program_path.ok_or_else(|| eyre::eyre!("missing program to query"))?;
+    let (_, facts) = query_facts(program_path, is_fact_file, false)?;
+    let index = polonius::RelationIndex::new(facts);
+    polonius::run_repl(&index, std::io::stdin().lock(), std::io::stdout())
+}
+
+// `query <program> [--facts] <query>`: analyzes a program, or a fact file with `--facts`, and
+// prints the answer to a query of the `repl`, e.g. `query <program> contains --node bb3[0]` for
+// the loans each origin may contain on entry to the node.
+fn query(args: &[String]) -> eyre::Result<()> {
+    let mut program_path = None;
+    let mut is_fact_file = false;
+    let mut words = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--facts" => is_fact_file = true,
+            _ if program_path.is_none() => program_path = Some(arg),
+            _ => words.push(arg.as_str()),
+        }
+    }
+
+    let program_path = program_path.ok_or_else(|| eyre::eyre!("missing program to query"))?;
+    let query: polonius::Query = words.join(" ").parse()?;
+    let (_, facts) = query_facts(program_path, is_fact_file, false)?;
+    let index = polonius::RelationIndex::new(facts);
+    print!("{}", index.answer(&query)?);
+    Ok(())
+}
+
+// `subset-graph <program> [--facts] [--node <node>]*`: prints the subset graphs between the
+// origins of a program, or of a fact file with `--facts`, on entry to each node, or only to the
+// given nodes, in DOT, see `subset_graphs`.
+fn subset_graph(args: &[String]) -> eyre::Result<()> {
+    let mut program_path = None;
+    let mut is_fact_file = false;
+    let mut nodes = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--facts" => is_fact_file = true,
+            "--node" => {
+                let node = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--node` expects a node, e.g. `bb1[0]`"))?;
+                nodes.push(polonius::Node::from(node));
+            }
+            _ if program_path.is_none() => program_path = Some(arg),
+            _ => eyre::bail!("unexpected argument `{}`", arg),
+        }
+    }
+
+    let program_path = program_path.ok_or_else(|| eyre::eyre!("missing program to graph"))?;
+    let (input, facts) = query_facts(program_path, is_fact_file, false)?;
+    print!("{}", polonius::subset_graphs(&facts, &nodes, &input)?);
+    Ok(())
+}
+
+// The text of a program to query, or of a fact file, and its facts. The errors and warnings of
+// the program are printed first, see `validate`.
+fn query_facts(
+    program_path: &str,
+    is_fact_file: bool,
+    color: bool,
+) -> eyre::Result<(String, polonius::Facts)> {
+    if is_fact_file {
+        let input = std::fs::read_to_string(program_path)
+            .wrap_err_with(|| format!("failed to read `{}`", program_path))?;
+        let facts = polonius::read_facts(&input)?;
+        Ok((input, facts))
+    } else {
+        let sources = polonius::Sources::load(Path::new(program_path))?;
+        validate(&sources, color, false)?;
+        let facts = polonius::emit_facts(sources.text())?;
+        Ok((sources.text().to_string(), facts))
+    }
+}
+
+// `slice <program> <variable> [--facts] [--color]`: prints the statements of a program, or of a
+// fact file with `--facts`, involving the variable or its places, and their facts about the
+// variable's origins and loans, to follow its data flow, see `Facts::slice`.
+fn slice(args: &[String]) -> eyre::Result<()> {
+    let mut paths = Vec::new();
+    let mut is_fact_file = false;
+    let mut color = false;
+    for arg in args {
+        match arg.as_str() {
+            "--facts" => is_fact_file = true,
+            "--color" => color = true,
+            _ => paths.push(arg),
+        }
+    }
+    let (program_path, variable) = match paths[..] {
+        [program_path, variable] => (program_path, variable),
+        _ => eyre::bail!("expected a program and a variable to slice it by"),
+    };
+
+    let (_, facts) = query_facts(program_path, is_fact_file, color)?;
+    let listing = facts
+        .slice(&polonius::FactSlice::Variable(variable.as_str().into()))?
+        .to_string();
+    if color {
+        print!("{}", polonius::colorize_facts(&listing));
+    } else {
+        print!("{}", listing);
+    }
+    Ok(())
+}
+
+// `compare-rustc [--print-source] <program>*`: compares the borrow errors rustc reports on the
+// programs translated to Rust, with the errors of the analysis.
+fn compare_rustc(args: &[String]) -> eyre::Result<()> {
+    let print_source = args.iter().any(|arg| arg == "--print-source");
+    let (mut agreements, mut divergences) = (0, 0);
+    for program_path in args.iter().filter(|arg| *arg != "--print-source") {
+        let sources = polonius::Sources::load(Path::new(program_path))?;
+        let input = sources.text();
+
+        println!("== {}", program_path);
+        if print_source {
+            println!("{}", polonius::translate_to_rust(input)?);
+        }
+        let comparison = polonius::compare_with_rustc(input)?;
+        print!("{}", comparison);
+        agreements += comparison.agreement_count();
+        divergences += comparison.divergence_count();
+    }
+    println!("{} agreement(s), {} divergence(s)", agreements, divergences);
+    Ok(())
+}
+
+// `import <corpus> <output>`: imports the fact sets of the upstream Polonius test corpus, e.g.
+// its `inputs` directory, as fact files in the output directory.
+fn import(args: &[String]) -> eyre::Result<()> {
+    let (corpus, output) = match args {
+        [corpus, output] => (Path::new(corpus), Path::new(output)),
+        _ => eyre::bail!("expected a corpus directory, and an output directory"),
+    };
+    let imported = polonius::import_corpus(corpus, output)?;
+    println!(
+        "imported {} fact set(s) into `{}`",
+        imported,
+        output.display()
+    );
+    Ok(())
+}
+
+// `export <program> <output>`: exports the facts of a program as a legacy fact set, in the
+// output directory, for upstream Polonius to compute its move errors.
+fn export(args: &[String]) -> eyre::Result<()> {
+    let (program_path, output) = match args {
+        [program_path, output] => (program_path, Path::new(output)),
+        _ => eyre::bail!("expected a program, and an output directory"),
+    };
+    let sources = polonius::Sources::load(Path::new(program_path))?;
+    let facts = polonius::emit_facts(sources.text()).map_err(|e| sources.locate_parse_error(e))?;
+    polonius::LegacyFacts::from_facts(&facts).write(output)?;
+    println!("exported `{}` into `{}`", program_path, output.display());
+    Ok(())
+}
+
+// `convert <input> <output> --from <format> --to <format>`: converts facts between the `text`,
+// `souffle`, `json`, `legacy`, `csv`, `sqlite` and `binary` formats. The output of the `text` and
+// `json` formats is printed when it's `-`.
+fn convert(args: &[String]) -> eyre::Result<()> {
+    let mut paths = Vec::new();
+    let (mut from, mut to) = (None, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" | "--to" => {
+                let format: polonius::FactFormat = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`{}` expects a fact format", arg))?
+                    .parse()?;
+                if arg == "--from" {
+                    from = Some(format);
+                } else {
+                    to = Some(format);
+                }
+            }
+            _ if paths.len() < 2 => paths.push(Path::new(arg)),
+            _ => eyre::bail!("unexpected argument `{}`", arg),
+        }
+    }
+
+    let (input, output) = match paths.as_slice() {
+        [input, output] => (input, output),
+        _ => eyre::bail!("expected an input, and an output"),
+    };
+    let from = from.ok_or_else(|| eyre::eyre!("missing `--from <format>`"))?;
+    let to = to.ok_or_else(|| eyre::eyre!("missing `--to <format>`"))?;
+    let facts = polonius::read_facts_in(from, input)?;
+    match to {
+        polonius::FactFormat::Text if *output == Path::new("-") => print!("{}", facts),
+        polonius::FactFormat::Json if *output == Path::new("-") => {
+            println!("{}", polonius::facts_to_json(&facts))
+        }
+        _ => polonius::write_facts_in(&facts, to, output)?,
+    }
+    Ok(())
+}
+
+// `compare-legacy <fact set>*`: compares the loan errors of the legacy rules on the fact set
+// directories, with the errors of the new rules on their translation.
+fn compare_legacy(args: &[String]) -> eyre::Result<()> {
+    let (mut agreements, mut divergences) = (0, 0);
+    for dir in args {
+        let facts = polonius::LegacyFacts::read(Path::new(dir))?;
+        let comparison = facts.compare_formulations();
+
+        println!("== {}", dir);
+        print!("{}", comparison);
+        if comparison.agrees() {
+            agreements += 1;
+        } else {
+            divergences += 1;
+        }
+    }
+    println!("{} agreement(s), {} divergence(s)", agreements, divergences);
+    Ok(())
+}
+
+// `compare-models <program or directory>* [--facts] [--relations]`: compares the loan errors of
+// the borrow checking models on the programs, and the `.txt` files of the directories, or fact
+// files with `--facts`. The relations of each model are printed with `--relations`.
+fn compare_models(args: &[String]) -> eyre::Result<()> {
+    let mut paths = Vec::new();
+    let mut is_fact_file = false;
+    let mut print_relations = false;
+    for arg in args {
+        match arg.as_str() {
+            "--facts" => is_fact_file = true,
+            "--relations" => print_relations = true,
+            _ if arg.starts_with("--") => eyre::bail!("unexpected argument `{}`", arg),
+            _ => paths.push(std::path::PathBuf::from(arg)),
+        }
+    }
+
+    let models = polonius::models();
+    let (mut agreements, mut divergences) = (0, 0);
+    for path in polonius::collect_programs(&paths)? {
+        let facts = if is_fact_file {
+            let input = std::fs::read_to_string(&path)
+                .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+            polonius::read_facts(&input)?
+        } else {
+            let sources = polonius::Sources::load(&path)?;
+            polonius::emit_facts(sources.text()).map_err(|e| sources.locate_parse_error(e))?
+        };
+
+        println!("== {}", path.display());
+        if print_relations {
+            for model in &models {
+                println!("-- {}", model.name());
+                for (name, rows) in model.check(&facts).relations {
+                    for row in rows {
+                        println!("{}({})", name, row.join(", "));
+                    }
+                }
+            }
+        }
+        let comparison = polonius::ModelComparison::compare(&facts, &models);
+        print!("{}", comparison);
+        if comparison.agrees() {
+            agreements += 1;
+        } else {
+            divergences += 1;
+        }
+    }
+    println!("{} agreement(s), {} divergence(s)", agreements, divergences);
+    Ok(())
+}
+
+// `check-annotations <program>*`: checks the programs have exactly the errors their
+// `// ERROR: <text>` annotations expect, and fails otherwise.
+fn check_annotations(args: &[String]) -> eyre::Result<()> {
+    let mut failures = 0;
+    for program_path in args {
+        let sources = polonius::Sources::load(Path::new(program_path))?;
+        let mismatches = polonius::check_annotations(sources.text())
+            .map_err(|e| sources.locate_parse_error(e))?;
+        if mismatches.is_empty() {
+            println!("ok: {}", program_path);
+        } else {
+            println!("FAILED: {}", program_path);
+            print!("{}", mismatches);
+            failures += 1;
+        }
+    }
+    if failures > 0 {
+        eyre::bail!("{} program(s) don't have the errors they expect", failures);
+    }
+    Ok(())
+}
+
+// `stress [--seed <n>] [--blocks <n>] [--depth <n>] [--statements <n>] [--variables <n>]
+// [--bench]`: prints a large synthetic program, generated from the seed, with the given number of
+// blocks, depth of struct nesting, statements per block, and variables. With `--bench`, the
+// optimized and naive solvers are timed on programs of this shape, of increasing sizes up to the
+// given number of blocks, see `polonius::benchmark_solvers`. The naive solver takes minutes on
+// programs of a hundred blocks, so the default number of blocks is too large to benchmark it.
+fn stress(args: &[String]) -> eyre::Result<()> {
+    let mut options = polonius::StressOptions::default();
+    let mut is_benchmark = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--bench" => {
+                is_benchmark = true;
+                continue;
+            }
+            "--seed" | "--blocks" | "--depth" | "--statements" | "--variables" => args
+                .next()
+                .ok_or_else(|| eyre::eyre!("`{}` expects a number", arg))?,
+            _ => eyre::bail!("unexpected argument `{}`", arg),
+        };
+        let number: usize = value
+            .parse()
+            .wrap_err_with(|| format!("invalid number `{}` for `{}`", value, arg))?;
+        match arg.as_str() {
+            "--seed" => options.seed = number as u64,
+            "--blocks" => options.blocks = number,
+            "--depth" => options.struct_depth = number,
+            "--statements" => options.statements_per_block = number,
+            _ => options.variables = number,
+        }
+    }
+    if is_benchmark {
+        print!("{}", polonius::benchmark_solvers(&options)?);
+    } else {
+        print!("{}", polonius::generate_stress_program(&options));
+    }
+    Ok(())
+}
+
+// `fuzz [--seed <n>] [--runs <n>] [--blocks <n>] [--depth <n>] [--statements <n>]
+// [--variables <n>]`: generates programs of the given shape, like `stress`, from consecutive
+// seeds, and fails on the first one the optimized and naive solvers compute different errors
+// for, printing it shrunk, see `polonius::fuzz_solvers`.
+fn fuzz(args: &[String]) -> eyre::Result<()> {
+    let mut options = polonius::FuzzOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let number = limit(arg, args.next())?;
+        match arg.as_str() {
+            "--seed" => options.seed = number as u64,
+            "--runs" => options.runs = number,
+            "--blocks" => options.program.blocks = number,
+            "--depth" => options.program.struct_depth = number,
+            "--statements" => options.program.statements_per_block = number,
+            "--variables" => options.program.variables = number,
+            _ => eyre::bail!("unexpected argument `{}`", arg),
+        }
+    }
+    if let Some(divergence) = polonius::fuzz_solvers(&options) {
+        print!("{}", divergence);
+        eyre::bail!("the solvers diverge");
+    }
+    println!("the solvers agree on {} program(s)", options.runs);
+    Ok(())
+}
+
+// `cargo [--facts-dir <dir>] [--jobs <n>] [--emit-only] [--sarif <path>] [--color]
+// [--max-iterations <n>] [--timeout <seconds>] [-- <cargo rustc arguments>]`: builds the crate of
+// the current directory with `cargo rustc`, dumping the fact set of each of its functions with
+// `-Znll-facts` in the directory, `target/polonius-next` by default, and analyzes them in
+// parallel, reporting each function like a batch of programs, see `check_batch`. The build
+// needs a nightly toolchain. The arguments after `--` are passed to `cargo rustc`, e.g. `--lib`,
+// or `-- <rustc arguments>`. Returns whether errors were found, like `check`.
+//
+// The `cargo-polonius-next` binary runs this command as the `cargo polonius-next` subcommand.
+fn cargo(args: &[String]) -> eyre::Result<bool> {
+    let (config, args) = load_config(args)?;
+    let mut facts_dir = Path::new("target").join("polonius-next");
+    let mut batch = polonius::BatchOptions {
+        emit: config.emit,
+        ..polonius::BatchOptions::default()
+    };
+    let mut sarif_path = None;
+    let mut color = config.color;
+    let mut cargo_args = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--facts-dir" => {
+                let dir = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--facts-dir` expects a directory"))?;
+                facts_dir = dir.into();
+            }
+            "--jobs" => batch.jobs = limit(arg, args.next())?,
+            "--emit-only" => batch.solve = false,
+            "--sarif" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("`--sarif` expects the path of the log"))?;
+                sarif_path = Some(Path::new(path));
+            }
+            "--color" => color = true,
+            "--max-iterations" => batch.emit.limits.max_iterations = Some(limit(arg, args.next())?),
+            "--timeout" => {
+                let seconds = limit(arg, args.next())? as u64;
+                batch.emit.limits.timeout = Some(std::time::Duration::from_secs(seconds));
+            }
+            "--" => cargo_args.extend(args.by_ref().cloned()),
+            _ => eyre::bail!("unexpected argument `{}`", arg),
+        }
+    }
+
+    polonius::dump_nll_facts(&cargo_args, &facts_dir)?;
+    let fact_sets = polonius::collect_fact_sets(&facts_dir)?;
+    let start = std::time::Instant::now();
+    let reports = polonius::analyze_fact_sets(&fact_sets, &batch);
+    report_batch(&reports, "function(s)", start, sarif_path, color)
+}
+
+// `baseline (record | compare) <baseline> <program or directory>* [--config <path>]`: records
+// the snapshots of the outputs of the programs, and of the `.txt` files of the directories, in
+// the baseline directory, or compares them with the recorded ones, and fails if any changed, see
+// `polonius::record_baseline`. The facts are emitted with the options of the configuration file,
+// see `load_config`.
+fn baseline(args: &[String]) -> eyre::Result<()> {
+    let (config, args) = load_config(args)?;
+    let (mode, baseline, paths) = match args.as_slice() {
+        [mode, baseline, paths @ ..] => (mode, Path::new(baseline), paths),
+        _ => eyre::bail!("expected `record` or `compare`, and the baseline directory"),
+    };
+    let paths: Vec<_> = paths.iter().map(std::path::PathBuf::from).collect();
+    let programs = polonius::collect_programs(&paths)?;
+    match mode.as_str() {
+        "record" => {
+            polonius::record_baseline(&programs, baseline, &config.emit)?;
+            println!("recorded {} program(s)", programs.len());
+        }
+        "compare" => {
+            let regressions = polonius::compare_to_baseline(&programs, baseline, &config.emit)?;
+            for regression in &regressions {
+                print!("{}", regression);
+            }
+            if !regressions.is_empty() {
+                eyre::bail!(
+                    "{} of {} program(s) changed",
+                    regressions.len(),
+                    programs.len()
+                );
+            }
+            println!("{} program(s) unchanged", programs.len());
+        }
+        _ => eyre::bail!("unexpected mode `{}`, expected `record` or `compare`", mode),
+    }
+    Ok(())
+}
+
+// `coverage <program or directory>* [--facts]`: solves the programs, and the `.txt` files of the
+// directories, or fact files with `--facts`, and prints the matrix of the rules each of them
+// fired, and the rules none of them did, see `polonius::rule_coverage`.
+fn coverage(args: &[String]) -> eyre::Result<()> {
+    let mut paths = Vec::new();
+    let mut is_fact_file = false;
+    for arg in args {
+        match arg.as_str() {
+            "--facts" => is_fact_file = true,
+            _ if arg.starts_with("--") => eyre::bail!("unexpected argument `{}`", arg),
+            _ => paths.push(std::path::PathBuf::from(arg)),
+        }
+    }
+
+    let mut matrix = polonius::CoverageMatrix::default();
+    for path in polonius::collect_programs(&paths)? {
+        let facts = if is_fact_file {
+            let input = std::fs::read_to_string(&path)
+                .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+            polonius::read_facts(&input)?
+        } else {
+            let sources = polonius::Sources::load(&path)?;
+            polonius::emit_facts(sources.text()).map_err(|e| sources.locate_parse_error(e))?
+        };
+        matrix.add(path.display().to_string(), polonius::rule_coverage(&facts));
+    }
+    print!("{}", matrix);
     Ok(())
 }
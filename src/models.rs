@@ -0,0 +1,349 @@
+//! Borrow checking models: the formulations of the borrow check the crate can compare on the
+//! same facts, to study where they differ on identical programs.
+//!
+//! - `Nll`: origins are sets of nodes, where they or the origins they outlive are live, and
+//!   subsets hold at every node. A loan is in scope from where it's issued, while the node is
+//!   in its origin, and until it's killed.
+//! - `LegacyPolonius`: the rules of upstream Polonius, over the legacy fact set exported from the
+//!   facts, see `LegacyFacts::from_facts`. Subsets flow through the CFG between live origins, and
+//!   a loan is live where a live origin contains it.
+//! - `NewRules`: the rules of `polonius.dl`, where an invalidated origin is an error when it's
+//!   accessed.
+//!
+//! The models report their errors as the loans invalidated while in use, and the node
+//! invalidating them. The new rules report errors where an invalidated origin is accessed
+//! instead: an invalidation is one of their errors when solving with only that invalidation
+//! reports an error, like in `LegacyFacts::compare_formulations`.
+
+use crate::fact_emitter::{Facts, Loan, Node, Origin};
+use crate::legacy::{legacy_relations, LegacyFacts};
+use crate::solver::{naive, solve};
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[cfg(test)]
+mod test;
+
+/// A formulation of the borrow check, computing the loan errors of a program's facts.
+pub trait BorrowCheckModel {
+    /// The name of the model, e.g. `nll`.
+    fn name(&self) -> &'static str;
+
+    /// Computes the loan errors of the facts, and the relations they're derived from.
+    fn check(&self, facts: &Facts) -> ModelOutput;
+}
+
+/// The loan errors computed by a model, and its intermediate relations.
+#[derive(Default, Debug)]
+pub struct ModelOutput {
+    /// `(loan, node)`: the loan is invalidated at the node while it's in use, in order.
+    pub errors: Vec<(Loan, Node)>,
+
+    /// The relations the errors are derived from, by name, with the atoms of their tuples.
+    pub relations: Vec<(&'static str, Vec<Vec<String>>)>,
+}
+
+/// The models of the crate, in the order they were formulated.
+pub fn models() -> Vec<Box<dyn BorrowCheckModel>> {
+    vec![Box::new(Nll), Box::new(LegacyPolonius), Box::new(NewRules)]
+}
+
+/// The NLL-style model, with location-insensitive subsets.
+pub struct Nll;
+
+impl BorrowCheckModel for Nll {
+    fn name(&self) -> &'static str {
+        "nll"
+    }
+
+    fn check(&self, facts: &Facts) -> ModelOutput {
+        let origin_live_on_entry: BTreeSet<(&Origin, &Node)> = naive::compute(facts)
+            .origin_live_on_entry
+            .into_keys()
+            .collect();
+
+        // outlives(O1, O2) :- introduce_subset(O1, O2, _).
+        // outlives(O1, O3) :- outlives(O1, O2), outlives(O2, O3).
+        let mut outlives: BTreeSet<(&Origin, &Origin)> = facts
+            .introduce_subset
+            .iter()
+            .map(|(o1, o2, _)| (o1, o2))
+            .collect();
+        loop {
+            let mut transitive = Vec::new();
+            for &(o1, o2) in &outlives {
+                for &(_, o3) in outlives.iter().filter(|(o, _)| *o == o2) {
+                    if !outlives.contains(&(o1, o3)) {
+                        transitive.push((o1, o3));
+                    }
+                }
+            }
+            if transitive.is_empty() {
+                break;
+            }
+            outlives.extend(transitive);
+        }
+
+        // loan_region(L, N) :- loan_issued_at(O, L, _), origin_live_on_entry(O, N).
+        // loan_region(L, N) :-
+        //   loan_issued_at(O1, L, _),
+        //   outlives(O1, O2),
+        //   origin_live_on_entry(O2, N).
+        let mut loan_region: BTreeSet<(&Loan, &Node)> = BTreeSet::new();
+        for (origin, loan, _) in &facts.loan_issued_at {
+            for &(live, node) in &origin_live_on_entry {
+                if live == origin || outlives.contains(&(origin, live)) {
+                    loan_region.insert((loan, node));
+                }
+            }
+        }
+
+        // loan_in_scope(L, N2) :-
+        //   loan_issued_at(_, L, N1),
+        //   cfg_edge(N1, N2),
+        //   loan_region(L, N2).
+        // loan_in_scope(L, N2) :-
+        //   loan_in_scope(L, N1),
+        //   !loan_killed_at(L, N1),
+        //   cfg_edge(N1, N2),
+        //   loan_region(L, N2).
+        let mut loan_in_scope: BTreeSet<(&Loan, &Node)> = BTreeSet::new();
+        let mut pending: Vec<_> = facts
+            .loan_issued_at
+            .iter()
+            .map(|(_, loan, node)| (loan, node))
+            .collect();
+        while let Some((loan, node1)) = pending.pop() {
+            for (_, node2) in facts.cfg_edge.iter().filter(|(node, _)| node == node1) {
+                if loan_region.contains(&(loan, node2))
+                    && loan_in_scope.insert((loan, node2))
+                    && !is_loan_killed_at(facts, loan, node2)
+                {
+                    pending.push((loan, node2));
+                }
+            }
+        }
+
+        // errors(L, N) :-
+        //   invalidate_origin(O, N),
+        //   loan_issued_at(O, L, _),
+        //   loan_in_scope(L, N).
+        let mut errors = Vec::new();
+        for (origin, node) in &facts.invalidate_origin {
+            for (_, loan, _) in loans_of(facts, origin) {
+                if loan_in_scope.contains(&(loan, node)) {
+                    errors.push((loan.clone(), node.clone()));
+                }
+            }
+        }
+        errors.sort();
+        errors.dedup();
+
+        let pairs = |relation: &BTreeSet<(&Loan, &Node)>| {
+            relation
+                .iter()
+                .map(|(loan, node)| vec![loan.0.to_string(), node.0.to_string()])
+                .collect()
+        };
+        ModelOutput {
+            errors,
+            relations: vec![
+                (
+                    "origin_live_on_entry",
+                    origin_live_on_entry
+                        .iter()
+                        .map(|(origin, node)| vec![origin.0.to_string(), node.0.to_string()])
+                        .collect(),
+                ),
+                (
+                    "outlives",
+                    outlives
+                        .iter()
+                        .map(|(o1, o2)| vec![o1.0.to_string(), o2.0.to_string()])
+                        .collect(),
+                ),
+                ("loan_region", pairs(&loan_region)),
+                ("loan_in_scope", pairs(&loan_in_scope)),
+            ],
+        }
+    }
+}
+
+/// The model of upstream Polonius' rules.
+pub struct LegacyPolonius;
+
+impl BorrowCheckModel for LegacyPolonius {
+    fn name(&self) -> &'static str {
+        "legacy"
+    }
+
+    fn check(&self, facts: &Facts) -> ModelOutput {
+        let relations = legacy_relations(&LegacyFacts::from_facts(facts));
+        let pairs = |relation: &[(String, String)]| {
+            relation
+                .iter()
+                .map(|(atom1, atom2)| vec![atom1.clone(), atom2.clone()])
+                .collect()
+        };
+        ModelOutput {
+            errors: relations
+                .errors
+                .iter()
+                .map(|(loan, point)| (Loan::from(loan), Node::from(point)))
+                .collect(),
+            relations: vec![
+                (
+                    "origin_live_on_entry",
+                    pairs(&relations.origin_live_on_entry),
+                ),
+                (
+                    "origin_contains_loan_on_entry",
+                    relations
+                        .origin_contains_loan_on_entry
+                        .iter()
+                        .map(|(origin, loan, point)| {
+                            vec![origin.clone(), loan.clone(), point.clone()]
+                        })
+                        .collect(),
+                ),
+                ("loan_live_at", pairs(&relations.loan_live_at)),
+            ],
+        }
+    }
+}
+
+/// The model of the rules in `polonius.dl`.
+pub struct NewRules;
+
+impl BorrowCheckModel for NewRules {
+    fn name(&self) -> &'static str {
+        "new"
+    }
+
+    fn check(&self, facts: &Facts) -> ModelOutput {
+        let mut errors = Vec::new();
+        for (origin, node) in &facts.invalidate_origin {
+            let mut isolated = facts.clone();
+            isolated.invalidate_origin = vec![(origin.clone(), node.clone())];
+            if !solve(&isolated).invalidated_origin_accessed.is_empty() {
+                for (_, loan, _) in loans_of(facts, origin) {
+                    errors.push((loan.clone(), node.clone()));
+                }
+            }
+        }
+        errors.sort();
+        errors.dedup();
+
+        let relations = naive::compute(facts);
+        let origin_node_rows = |tuples: Vec<&(&Origin, &Node)>| {
+            tuples
+                .into_iter()
+                .map(|(origin, node)| vec![origin.0.to_string(), node.0.to_string()])
+                .collect()
+        };
+        let subset_rows = |tuples: Vec<&(&Origin, &Origin, &Node)>| {
+            tuples
+                .into_iter()
+                .map(|(o1, o2, node)| vec![o1.0.to_string(), o2.0.to_string(), node.0.to_string()])
+                .collect()
+        };
+        ModelOutput {
+            errors,
+            relations: vec![
+                (
+                    "origin_live_on_entry",
+                    origin_node_rows(relations.origin_live_on_entry.keys().collect()),
+                ),
+                (
+                    "subset_on_entry",
+                    subset_rows(relations.subset_on_entry.keys().collect()),
+                ),
+                (
+                    "origin_invalidated",
+                    origin_node_rows(relations.origin_invalidated.keys().collect()),
+                ),
+                (
+                    "origin_contains_loan_at",
+                    relations
+                        .origin_contains_loan_at
+                        .keys()
+                        .map(|(origin, loan, node)| {
+                            vec![origin.0.to_string(), loan.0.to_string(), node.0.to_string()]
+                        })
+                        .collect(),
+                ),
+            ],
+        }
+    }
+}
+
+// The loans issued into the origin.
+fn loans_of<'a>(
+    facts: &'a Facts,
+    origin: &'a Origin,
+) -> impl Iterator<Item = &'a (Origin, Loan, Node)> {
+    facts
+        .loan_issued_at
+        .iter()
+        .filter(move |(candidate, _, _)| candidate == origin)
+}
+
+// Whether the loan is killed at the node: its origin is cleared there, other than by issuing a
+// loan into it.
+fn is_loan_killed_at(facts: &Facts, loan: &Loan, node: &Node) -> bool {
+    facts
+        .loan_issued_at
+        .iter()
+        .filter(|(_, candidate, _)| candidate == loan)
+        .any(|(origin, _, _)| {
+            facts.clear_origin.contains(&(origin.clone(), node.clone()))
+                && !loans_of(facts, origin).any(|(_, _, issued_at)| issued_at == node)
+        })
+}
+
+/// The loan errors of each model over the same facts.
+#[derive(Debug)]
+pub struct ModelComparison {
+    pub errors: Vec<(&'static str, Vec<(Loan, Node)>)>,
+}
+
+impl ModelComparison {
+    /// Checks the facts with each of the models.
+    pub fn compare(facts: &Facts, models: &[Box<dyn BorrowCheckModel>]) -> Self {
+        let errors = models
+            .iter()
+            .map(|model| (model.name(), model.check(facts).errors))
+            .collect();
+        Self { errors }
+    }
+
+    /// Whether all the models report the same errors.
+    pub fn agrees(&self) -> bool {
+        self.errors.windows(2).all(|pair| pair[0].1 == pair[1].1)
+    }
+}
+
+impl fmt::Display for ModelComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The models reporting each error are aligned to the width of all of them
+        let width = self.errors.iter().map(|(name, _)| name.len() + 2).sum();
+        let errors: BTreeSet<_> = self.errors.iter().flat_map(|(_, errors)| errors).collect();
+        for (loan, node) in errors {
+            let models: Vec<_> = self
+                .errors
+                .iter()
+                .filter(|(_, errors)| errors.contains(&(loan.clone(), node.clone())))
+                .map(|(name, _)| *name)
+                .collect();
+            writeln!(
+                f,
+                "{:<width$}`{}` invalidated at `{}`",
+                models.join(", "),
+                loan.0,
+                node.0,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use super::*;
+use crate::fact_emitter::test::expect_facts;
+use insta::assert_display_snapshot;
+
+// The errors of each model over the program.
+fn compare(program: &str) -> ModelComparison {
+    ModelComparison::compare(&expect_facts(program), &models())
+}
+
+#[test]
+fn models_agree_on_a_borrow_conflict() {
+    let comparison = compare(
+        "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            x = 3;
+            y = &'L_x x;
+            x = 4;
+            use(move y);
+        }
+    ",
+    );
+    assert!(comparison.agrees());
+    assert_display_snapshot!(comparison, @"nll, legacy, new  `bw0` invalidated at `c`");
+}
+
+// The loan of `x` only flows into `q` in `bb1`, but NLL's subsets hold everywhere: `q` is live
+// where `x` is assigned in `bb2`, so the loan is still in scope there.
+#[test]
+fn models_diverge_on_location_insensitive_subsets() {
+    let comparison = compare(
+        "
+        let x: i32;
+        let y: i32;
+        let p: &'p i32;
+        let q: &'q i32;
+
+        bb0: {
+            q = &'L_y y;
+            p = &'L_x x;
+            use(copy q);
+            goto bb1, bb2;
+        }
+
+        bb1: {
+            q = copy p;
+            goto bb3;
+        }
+
+        bb2: {
+            x = 1;
+            use(copy q);
+            goto bb3;
+        }
+
+        bb3: {
+            use(copy q);
+        }
+    ",
+    );
+    assert!(!comparison.agrees());
+    assert_display_snapshot!(comparison, @"nll               `bw1` invalidated at `e`");
+}
+
+#[test]
+fn relations_of_the_models() {
+    let facts = expect_facts(
+        "
+        let x: i32;
+        let y: &'y i32;
+
+        bb0: {
+            y = &'L_x x;
+            x = 4;
+            use(move y);
+        }
+    ",
+    );
+    let relations: Vec<_> = models()
+        .iter()
+        .map(|model| {
+            let names: Vec<_> = model
+                .check(&facts)
+                .relations
+                .into_iter()
+                .map(|(name, rows)| format!("{}: {}", name, rows.len()))
+                .collect();
+            format!("{}: {}", model.name(), names.join(", "))
+        })
+        .collect();
+    assert_display_snapshot!(relations.join("\n"), @r###"
+    nll: origin_live_on_entry: 2, outlives: 1, loan_region: 2, loan_in_scope: 2
+    legacy: origin_live_on_entry: 2, origin_contains_loan_on_entry: 4, loan_live_at: 2
+    new: origin_live_on_entry: 2, subset_on_entry: 2, origin_invalidated: 2, origin_contains_loan_at: 4
+    "###);
+}
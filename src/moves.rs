@@ -0,0 +1,109 @@
+//! Move analysis: the accesses of move paths which may have been moved out of, on some path
+//! through the CFG, without being assigned again since.
+//!
+//! Initialization is tracked per move path: moving `x.f` out of `x` leaves `x.g` initialized,
+//! so only accessing `x.f`, its fields, or `x` as a whole is an error. Assigning to a path
+//! initializes it and all its fields again.
+
+use crate::fact_emitter::{Facts, Node};
+use std::collections::{BTreeSet, HashMap};
+
+#[cfg(test)]
+mod test;
+
+/// An access of a move path, which overlaps a path that may have been moved out of.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MoveError {
+    /// The path moved out of, and where.
+    pub moved_path: String,
+    pub moved_at: Node,
+
+    /// The path accessed after the move, either the moved path itself, one of its fields, or
+    /// a path containing it, and where.
+    pub accessed_path: String,
+    pub accessed_at: Node,
+}
+
+impl MoveError {
+    /// Whether the accessed path contains the moved path, e.g. `x` when `x.f` was moved out of.
+    pub fn is_partial(&self) -> bool {
+        is_prefix(&self.accessed_path, &self.moved_path)
+    }
+}
+
+/// Computes the accesses of the paths which may have been moved out of, in the order of the
+/// accesses. Each access is only reported once, with the first of the moves it conflicts with.
+pub fn move_errors(facts: &Facts) -> Vec<MoveError> {
+    // The paths which may be moved out of on entry to each node, with where they were moved
+    let mut moved_on_entry: HashMap<&Node, BTreeSet<(&str, &Node)>> = HashMap::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (node1, node2) in &facts.cfg_edge {
+            let moved_on_exit = moved_on_exit(facts, node1, moved_on_entry.get(node1));
+            let on_entry = moved_on_entry.entry(node2).or_default();
+            for moved in moved_on_exit {
+                changed |= on_entry.insert(moved);
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+    for (accessed_path, accessed_at) in &facts.path_accessed_at {
+        let conflicting_move = moved_on_entry
+            .get(accessed_at)
+            .into_iter()
+            .flatten()
+            .find(|(moved_path, _)| overlaps(moved_path, accessed_path));
+        if let Some((moved_path, moved_at)) = conflicting_move {
+            let error = MoveError {
+                moved_path: moved_path.to_string(),
+                moved_at: (*moved_at).clone(),
+                accessed_path: accessed_path.clone(),
+                accessed_at: accessed_at.clone(),
+            };
+            if !errors.contains(&error) {
+                errors.push(error);
+            }
+        }
+    }
+    errors
+}
+
+// The paths which may be moved out of on exit from the node: the statement's moves happen before
+// its assignment, which initializes the assigned path and its fields again.
+fn moved_on_exit<'a>(
+    facts: &'a Facts,
+    node: &'a Node,
+    on_entry: Option<&BTreeSet<(&'a str, &'a Node)>>,
+) -> Vec<(&'a str, &'a Node)> {
+    let moved_here = facts
+        .path_moved_at
+        .iter()
+        .filter(|(_, candidate)| candidate == node)
+        .map(|(path, _)| (path.as_str(), node));
+    let is_assigned = |path: &str| {
+        facts.path_assigned_at.iter().any(|(assigned, candidate)| {
+            candidate == node && (assigned == path || is_prefix(assigned, path))
+        })
+    };
+    on_entry
+        .into_iter()
+        .flatten()
+        .copied()
+        .chain(moved_here)
+        .filter(|(path, _)| !is_assigned(path))
+        .collect()
+}
+
+// Whether one of the paths contains the other.
+fn overlaps(path1: &str, path2: &str) -> bool {
+    path1 == path2 || is_prefix(path1, path2) || is_prefix(path2, path1)
+}
+
+// Whether `path` strictly contains `other`, e.g. `x` contains `x.f`, but not `xy`.
+fn is_prefix(path: &str, other: &str) -> bool {
+    other
+        .strip_prefix(path)
+        .is_some_and(|rest| rest.starts_with('.'))
+}
@@ -0,0 +1,108 @@
+use super::*;
+use crate::fact_emitter::test::expect_facts;
+use insta::assert_debug_snapshot;
+
+#[test]
+fn partial_moves() {
+    // Moving a field leaves its siblings usable, but not the struct as a whole
+    let program = "
+        struct Pair { first: Vec, second: Vec }
+
+        let pair: Pair;
+        let first: Vec;
+        let second: &'s Vec;
+
+        bb0: {
+            first = move pair.first;
+            second = &'L_second pair.second;
+            use(move pair);
+        }
+    ";
+    assert_debug_snapshot!(move_errors(&expect_facts(program)), @r###"
+    [
+        MoveError {
+            moved_path: "pair.first",
+            moved_at: "a",
+            accessed_path: "pair",
+            accessed_at: "c",
+        },
+    ]
+    "###);
+}
+
+#[test]
+fn use_of_moved_field() {
+    let program = "
+        struct Pair { first: Vec, second: Vec }
+
+        let pair: Pair;
+        let first: Vec;
+
+        bb0: {
+            use(move pair);
+            first = move pair.first;
+        }
+    ";
+    assert_debug_snapshot!(move_errors(&expect_facts(program)), @r###"
+    [
+        MoveError {
+            moved_path: "pair",
+            moved_at: "a",
+            accessed_path: "pair.first",
+            accessed_at: "b",
+        },
+    ]
+    "###);
+}
+
+#[test]
+fn assignments_initialize_moved_paths() {
+    // The move in the loop is only an error on the path which doesn't assign the vector again
+    let program = "
+        let v: Vec;
+        let w: Vec;
+
+        bb0: {
+            v = Vec_new();
+            goto bb1;
+        }
+        bb1: {
+            w = move v;
+            goto bb2, bb3;
+        }
+        bb2: {
+            v = Vec_new();
+            goto bb1;
+        }
+        bb3: {
+            use(move v);
+        }
+    ";
+    assert_debug_snapshot!(move_errors(&expect_facts(program)), @r###"
+    [
+        MoveError {
+            moved_path: "v",
+            moved_at: "b",
+            accessed_path: "v",
+            accessed_at: "d",
+        },
+    ]
+    "###);
+}
+
+#[test]
+fn copies_are_not_moves() {
+    let program = "
+        #[derive(Copy)]
+        struct Point { x: i32 }
+
+        let point: Point;
+        let x: i32;
+
+        bb0: {
+            use(move point);
+            x = copy point.x;
+        }
+    ";
+    assert_debug_snapshot!(move_errors(&expect_facts(program)), @"[]");
+}
@@ -0,0 +1,221 @@
+//! Queries over the analysis of a program, to inspect its intermediate state rather than only its
+//! errors, e.g. from the `repl`:
+//! - `subsets at <node>`: the subsets between origins on entry to the node,
+//! - `live at <node>`: the origins live on entry to the node,
+//! - `invalidated at <node>`: the origins invalidated on entry to the node,
+//! - `contains at <node>`: the loans each origin may contain on entry to the node,
+//! - `loans of <place>`: the loans borrowing the place, or one of its fields,
+//! - `nodes`: the nodes, with their statements,
+//! - `errors`: the errors of the program.
+//!
+//! On the command line, the node of a query can also follow `--node`, like
+//! `contains --node bb3[0]`.
+//!
+//! The `repl` reads the queries a line at a time, until `quit` or the end of its input, and
+//! answers each of them, or explains why it can't.
+//!
+//! The relations are solved once, by the naive solver which keeps the intermediate ones, and
+//! indexed by node in a `RelationIndex`, so that each query is answered without solving again.
+
+use crate::diagnostics::{diagnose, Diagnostic};
+use crate::fact_emitter::{Facts, Loan, LoanKind, Node, Origin};
+use crate::solver::naive;
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::{self, BufRead};
+
+#[cfg(test)]
+mod test;
+
+/// A query over the analysis of a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query {
+    Subsets(Node),
+    Live(Node),
+    Invalidated(Node),
+    Contains(Node),
+    LoansOf(String),
+    Nodes,
+    Errors,
+}
+
+impl std::str::FromStr for Query {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        let words: Vec<_> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["subsets", "at" | "--node", node] => Ok(Self::Subsets(Node::from(node))),
+            ["live", "at" | "--node", node] => Ok(Self::Live(Node::from(node))),
+            ["invalidated", "at" | "--node", node] => Ok(Self::Invalidated(Node::from(node))),
+            ["contains", "at" | "--node", node] => Ok(Self::Contains(Node::from(node))),
+            ["loans", "of", place] => Ok(Self::LoansOf(place.to_string())),
+            ["nodes"] => Ok(Self::Nodes),
+            ["errors"] => Ok(Self::Errors),
+            _ => eyre::bail!(
+                "unknown query `{}`, expected `subsets at <node>`, `live at <node>`, \
+                `invalidated at <node>`, `contains at <node>`, `loans of <place>`, `nodes` or \
+                `errors`",
+                s.trim()
+            ),
+        }
+    }
+}
+
+/// The relations of a program's analysis, indexed by node.
+pub struct RelationIndex {
+    facts: Facts,
+    diagnostics: Vec<Diagnostic>,
+
+    /// The nodes, in the order of their statements.
+    nodes: Vec<Node>,
+    subsets: HashMap<Node, Vec<(Origin, Origin)>>,
+    live: HashMap<Node, Vec<Origin>>,
+    invalidated: HashMap<Node, Vec<Origin>>,
+    contents: HashMap<Node, Vec<(Origin, Loan)>>,
+}
+
+impl RelationIndex {
+    /// Solves the facts, and indexes their relations.
+    pub fn new(facts: Facts) -> Self {
+        let mut subsets: HashMap<Node, Vec<(Origin, Origin)>> = HashMap::new();
+        let mut live: HashMap<Node, Vec<Origin>> = HashMap::new();
+        let mut invalidated: HashMap<Node, Vec<Origin>> = HashMap::new();
+        let mut contents: HashMap<Node, Vec<(Origin, Loan)>> = HashMap::new();
+        let relations = naive::compute(&facts);
+        for &(origin1, origin2, node) in relations.subset_on_entry.keys() {
+            let subset = (origin1.clone(), origin2.clone());
+            subsets.entry(node.clone()).or_default().push(subset);
+        }
+        for &(origin, node) in relations.origin_live_on_entry.keys() {
+            live.entry(node.clone()).or_default().push(origin.clone());
+        }
+        for &(origin, node) in relations.origin_invalidated.keys() {
+            invalidated
+                .entry(node.clone())
+                .or_default()
+                .push(origin.clone());
+        }
+        for &(origin, loan, node) in relations.origin_contains_loan_at.keys() {
+            let content = (origin.clone(), loan.clone());
+            contents.entry(node.clone()).or_default().push(content);
+        }
+
+        let nodes = facts.nodes().into_iter().cloned().collect();
+
+        Self {
+            diagnostics: diagnose(&facts),
+            facts,
+            nodes,
+            subsets,
+            live,
+            invalidated,
+            contents,
+        }
+    }
+
+    /// The answer to the query, a line per result, or an error if it's about an unknown node.
+    pub fn answer(&self, query: &Query) -> eyre::Result<String> {
+        let mut answer = String::new();
+        match query {
+            Query::Subsets(node) => {
+                for (origin1, origin2) in self.at(&self.subsets, node)? {
+                    writeln!(answer, "{} ⊆ {}", origin1.0, origin2.0)?;
+                }
+            }
+            Query::Live(node) => {
+                for origin in self.at(&self.live, node)? {
+                    writeln!(answer, "{}", origin.0)?;
+                }
+            }
+            Query::Invalidated(node) => {
+                for origin in self.at(&self.invalidated, node)? {
+                    writeln!(answer, "{}", origin.0)?;
+                }
+            }
+            Query::Contains(node) => {
+                let contents = self.at(&self.contents, node)?;
+                for (origin, loans) in &contents.iter().group_by(|(origin, _)| origin) {
+                    let loans: Vec<_> = loans.map(|(_, loan)| loan.0.as_str()).collect();
+                    writeln!(answer, "{} contains {}", origin.0, loans.join(", "))?;
+                }
+            }
+            Query::LoansOf(place) => {
+                for (loan, kind, borrowed) in &self.facts.loan_place {
+                    let is_field = borrowed
+                        .strip_prefix(place.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'));
+                    if !is_field {
+                        continue;
+                    }
+                    let borrow = match kind {
+                        LoanKind::Shared => "&",
+                        LoanKind::Mutable => "&mut ",
+                    };
+                    write!(answer, "{}: {}{}", loan.0, borrow, borrowed)?;
+                    for (origin, node) in self.issued(loan) {
+                        write!(answer, ", into {} at {}", origin.0, node.0)?;
+                    }
+                    writeln!(answer)?;
+                }
+            }
+            Query::Nodes => {
+                for node in &self.nodes {
+                    match self.facts.node_text(node) {
+                        Some(text) => writeln!(answer, "{}: {}", node.0, text)?,
+                        None => writeln!(answer, "{}", node.0)?,
+                    }
+                }
+            }
+            Query::Errors => {
+                for diagnostic in &self.diagnostics {
+                    write!(answer, "{}", diagnostic)?;
+                }
+            }
+        }
+        Ok(answer)
+    }
+
+    // The tuples of a relation indexed at the node, which is empty if none hold there.
+    fn at<'a, T>(&self, relation: &'a HashMap<Node, Vec<T>>, node: &Node) -> eyre::Result<&'a [T]> {
+        if !self.nodes.contains(node) {
+            eyre::bail!("unknown node `{}`", node.0);
+        }
+        Ok(relation.get(node).map_or(&[], Vec::as_slice))
+    }
+
+    // The origins the loan is issued into, and where.
+    fn issued<'a>(&'a self, loan: &'a Loan) -> impl Iterator<Item = (&'a Origin, &'a Node)> {
+        self.facts
+            .loan_issued_at
+            .iter()
+            .filter(move |(_, candidate, _)| candidate == loan)
+            .map(|(origin, _, node)| (origin, node))
+    }
+}
+
+/// Answers the queries of each line of the input, until `quit` or the end of the input, prompting
+/// for each of them on the output.
+pub fn run_repl(
+    index: &RelationIndex,
+    input: impl BufRead,
+    mut output: impl io::Write,
+) -> eyre::Result<()> {
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        match line?.trim() {
+            "" => {}
+            "quit" => return Ok(()),
+            query => match query.parse().and_then(|query| index.answer(&query)) {
+                Ok(answer) => write!(output, "{}", answer)?,
+                Err(e) => writeln!(output, "error: {}", e)?,
+            },
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    writeln!(output)?;
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use super::*;
+use crate::fact_emitter::emit_facts;
+use insta::assert_display_snapshot;
+
+const PROGRAM: &str = "
+struct S { f: i32, g: i32 }
+let s: S;
+let x: i32;
+let r: &'r i32;
+let m: &'m mut i32;
+bb0: {
+    r = &'L_f s.f;
+    m = &'L_g mut s.g;
+    goto bb1, bb2;
+}
+bb1: {
+    s.f = 4;
+    goto bb3;
+}
+bb2: {
+    r = &'L_x x;
+    goto bb3;
+}
+bb3: {
+    use(copy r);
+}
+";
+
+fn answer(index: &RelationIndex, query: &str) -> String {
+    match query.parse().and_then(|query| index.answer(&query)) {
+        Ok(answer) => answer,
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+#[test]
+fn queries() {
+    let index = RelationIndex::new(emit_facts(PROGRAM).unwrap());
+    assert_display_snapshot!(answer(&index, "subsets at bb3[0]"), @r###"
+    'L_f ⊆ 'r
+    'L_x ⊆ 'r
+    "###);
+    assert_display_snapshot!(answer(&index, "live at bb1[0]"), @"'r");
+    assert_display_snapshot!(answer(&index, "invalidated at bb3[0]"), @r###"
+    'L_f
+    'r
+    "###);
+    assert_display_snapshot!(answer(&index, "contains at bb3[0]"), @r###"
+    'L_f contains bw0
+    'L_g contains bw1
+    'L_x contains bw2
+    'r contains bw0, bw2
+    "###);
+    assert_display_snapshot!(answer(&index, "contains --node bb1[0]"), @r###"
+    'L_f contains bw0
+    'L_g contains bw1
+    'r contains bw0
+    "###);
+    assert_display_snapshot!(answer(&index, "loans of s"), @r###"
+    bw0: &s.f, into 'L_f at bb0[0]
+    bw1: &mut s.g, into 'L_g at bb0[1]
+    "###);
+    assert_display_snapshot!(answer(&index, "loans of s.g"), @"bw1: &mut s.g, into 'L_g at bb0[1]");
+    assert_display_snapshot!(answer(&index, "nodes"), @r###"
+    bb0[0]: r = &'L_f s.f
+    bb0[1]: m = &'L_g mut s.g
+    bb1[0]: s.f = 4
+    bb2[0]: r = &'L_x x
+    bb3[0]: use(copy r)
+    "###);
+    assert_display_snapshot!(answer(&index, "errors"), @r###"
+    error[E0506]: cannot assign to `s.f` because it is borrowed
+      --> bb1[0]
+       | bb0[0] `r = &'L_f s.f`: `s.f` is borrowed here
+       | bb1[0] `s.f = 4`: `s.f` is assigned to here but it was already borrowed
+       | bb3[0] `use(copy r)`: borrow later used here
+       = note: the shortest path through the borrow, its invalidation, and the later use:
+               bb0[0] `r = &'L_f s.f`
+               bb0[1] `m = &'L_g mut s.g`
+               bb1[0] `s.f = 4`
+               bb3[0] `use(copy r)`
+    "###);
+
+    assert_display_snapshot!(answer(&index, "subsets at bb9[0]"), @"error: unknown node `bb9[0]`");
+    assert_display_snapshot!(answer(&index, "subsets of 'r"), @"error: unknown query `subsets of 'r`, expected `subsets at <node>`, `live at <node>`, `invalidated at <node>`, `contains at <node>`, `loans of <place>`, `nodes` or `errors`");
+}
+
+#[test]
+fn repl() {
+    // The answers follow the prompts, as the queries are typed in the terminal
+    let index = RelationIndex::new(emit_facts(PROGRAM).unwrap());
+    let mut output = Vec::new();
+    let input = "live at bb1[0]\n\nloans at bb2[0]\nquit\nnodes\n";
+    run_repl(&index, input.as_bytes(), &mut output).unwrap();
+    assert_display_snapshot!(String::from_utf8(output).unwrap(), @r###"
+    > 'r
+    > > error: unknown query `loans at bb2[0]`, expected `subsets at <node>`, `live at <node>`, `invalidated at <node>`, `contains at <node>`, `loans of <place>`, `nodes` or `errors`
+    >
+    "###);
+}
+
+#[test]
+fn placeholder_loans() {
+    // The caller's data, in the placeholder loan `ph0`, reaches the origins `'p` flows into,
+    // and stays in `'p` when `p` is overwritten, along with the local loan flowing into it
+    let program = "
+        placeholder 'p;
+        let x: i32;
+        let p: &'p i32;
+        let r: &'r i32;
+        bb0: {
+            r = copy p;
+            use(copy r);
+            r = &'L_x x;
+            p = copy r;
+            use(copy p);
+        }
+    ";
+    let index = RelationIndex::new(emit_facts(program).unwrap());
+    assert_display_snapshot!(answer(&index, "contains at bb0[1]"), @r###"
+    'p contains ph0
+    'r contains ph0
+    "###);
+    assert_display_snapshot!(answer(&index, "contains at bb0[4]"), @r###"
+    'L_x contains bw0
+    'p contains bw0, ph0
+    "###);
+}
@@ -0,0 +1,182 @@
+//! Reaching definitions: the classic forward dataflow analysis computing, at each node of a
+//! program's CFG, the definitions which may reach it, along a path from the node making them
+//! where they're not killed.
+//!
+//! The emitter uses it for the loans issued by borrows: a write or a move can only invalidate
+//! the loans whose borrow reaches its node, so no invalidations are emitted for the others,
+//! e.g. for a borrow after the write in straight-line code. Loans are never killed: borrowing
+//! again with the same origin doesn't remove the earlier loan from the origins it flowed into.
+
+use crate::graph::NodeGraph;
+use crate::limits::{Budget, Limits};
+use std::collections::VecDeque;
+
+#[cfg(test)]
+mod test;
+
+/// The definitions reaching the entry of each node of a graph.
+#[derive(Debug)]
+pub struct ReachingDefinitions {
+    /// The node making each definition.
+    definitions: Vec<usize>,
+
+    /// The definitions reaching the entry of each node, a bit per definition.
+    on_entry: BitMatrix,
+}
+
+impl ReachingDefinitions {
+    /// Computes the definitions reaching each node of the graph, given the node making each
+    /// definition, and the definitions each node kills. A node making a definition doesn't
+    /// kill it.
+    pub fn new<K: IntoIterator<Item = usize>>(
+        graph: &NodeGraph,
+        definitions: Vec<usize>,
+        kills: impl Fn(usize) -> K,
+    ) -> Self {
+        Self::within(
+            graph,
+            definitions,
+            kills,
+            &Budget::start(&Limits::default()),
+        )
+        .expect("computing without limits can't fail")
+    }
+
+    // Computes the definitions like `new`, or fails if that exceeds the timeout of the analysis.
+    pub(crate) fn within<K: IntoIterator<Item = usize>>(
+        graph: &NodeGraph,
+        definitions: Vec<usize>,
+        kills: impl Fn(usize) -> K,
+        budget: &Budget<'_>,
+    ) -> eyre::Result<Self> {
+        let mut predecessors = vec![Vec::new(); graph.len()];
+        let mut successors = vec![Vec::new(); graph.len()];
+        for &(from, to, _) in graph.edges() {
+            predecessors[to].push(from);
+            successors[from].push(to);
+        }
+
+        // The definitions each node makes, and the ones it kills, without the ones it makes
+        let mut gen = BitMatrix::new(graph.len(), definitions.len());
+        for (definition, &node) in definitions.iter().enumerate() {
+            gen.insert(node, definition);
+        }
+        let mut kill = BitMatrix::new(graph.len(), definitions.len());
+        for node in 0..graph.len() {
+            for definition in kills(node) {
+                if definitions[definition] != node {
+                    kill.insert(node, definition);
+                }
+            }
+        }
+
+        let mut on_entry = BitMatrix::new(graph.len(), definitions.len());
+        let mut on_exit = gen.clone();
+
+        // The nodes whose predecessors' exits changed, starting with all of them in reverse
+        // post-order, then the unreachable ones: each node is visited after its predecessors,
+        // except along back edges, so that few of them are visited again.
+        let mut worklist: VecDeque<usize> = graph.reverse_post_order().into();
+        let mut is_queued = vec![false; graph.len()];
+        for &node in &worklist {
+            is_queued[node] = true;
+        }
+        worklist.extend((0..graph.len()).filter(|&node| !is_queued[node]));
+        is_queued.iter_mut().for_each(|queued| *queued = true);
+
+        let mut entry = vec![0; on_entry.words_per_row];
+        let mut exit = vec![0; on_entry.words_per_row];
+        while let Some(node) = worklist.pop_front() {
+            budget.check_time("computing the reaching definitions")?;
+            is_queued[node] = false;
+
+            entry.iter_mut().for_each(|word| *word = 0);
+            for &predecessor in &predecessors[node] {
+                for (word, &exit_word) in entry.iter_mut().zip(on_exit.row(predecessor)) {
+                    *word |= exit_word;
+                }
+            }
+            for (((word, &entry_word), &gen_word), &kill_word) in exit
+                .iter_mut()
+                .zip(&entry)
+                .zip(gen.row(node))
+                .zip(kill.row(node))
+            {
+                *word = gen_word | (entry_word & !kill_word);
+            }
+            on_entry.row_mut(node).copy_from_slice(&entry);
+
+            if on_exit.row(node) != exit.as_slice() {
+                on_exit.row_mut(node).copy_from_slice(&exit);
+                for &successor in &successors[node] {
+                    if !is_queued[successor] {
+                        is_queued[successor] = true;
+                        worklist.push_back(successor);
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            definitions,
+            on_entry,
+        })
+    }
+
+    /// The number of definitions.
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// The node making each definition.
+    pub fn definitions(&self) -> &[usize] {
+        &self.definitions
+    }
+
+    /// Whether the definition reaches the entry of the node.
+    pub fn reaches(&self, definition: usize, node: usize) -> bool {
+        self.on_entry.contains(node, definition)
+    }
+
+    /// The definitions reaching the entry of the node, in order.
+    pub fn on_entry(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.definitions.len()).filter(move |&definition| self.reaches(definition, node))
+    }
+}
+
+// A set of columns per row, as bits packed in words.
+#[derive(Clone, Debug)]
+struct BitMatrix {
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitMatrix {
+    fn new(rows: usize, columns: usize) -> Self {
+        let words_per_row = columns.div_ceil(64);
+        Self {
+            words_per_row,
+            words: vec![0; rows * words_per_row],
+        }
+    }
+
+    fn row(&self, row: usize) -> &[u64] {
+        &self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    fn row_mut(&mut self, row: usize) -> &mut [u64] {
+        &mut self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    fn insert(&mut self, row: usize, column: usize) {
+        self.row_mut(row)[column / 64] |= 1 << (column % 64);
+    }
+
+    fn contains(&self, row: usize, column: usize) -> bool {
+        self.row(row)[column / 64] & (1 << (column % 64)) != 0
+    }
+}
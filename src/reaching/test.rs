@@ -0,0 +1,88 @@
+use super::*;
+use crate::fact_emitter::{emit_facts, reaching_loans};
+use crate::graph::node_graph;
+use insta::assert_debug_snapshot;
+
+// The loans reaching each node, one node per line.
+fn loans_at_nodes(program: &str) -> Vec<String> {
+    reaching_loans(program)
+        .unwrap()
+        .into_iter()
+        .map(|(node, origins)| {
+            let origins: Vec<_> = origins.iter().map(|origin| origin.0.as_str()).collect();
+            format!("{}: {}", node.0, origins.join(", "))
+        })
+        .collect()
+}
+
+#[test]
+fn definitions_reach_through_loops() {
+    // A definition in the loop reaches its head, and a definition after it doesn't
+    let program = "
+        let x: i32;
+        let r: &'r i32;
+        bb0: { goto bb1; }
+        bb1: { r = &'L_x x; goto bb2, bb3; }
+        bb2: { goto bb1; }
+        bb3: { r = &'L_x2 x; }
+    ";
+    assert_debug_snapshot!(loans_at_nodes(program), @r###"
+    [
+        "bb0[0]: ",
+        "bb1[0]: 'L_x",
+        "bb2[0]: 'L_x",
+        "bb3[0]: 'L_x",
+    ]
+    "###);
+}
+
+#[test]
+fn killed_definitions() {
+    // Definitions of the same variable kill each other, like assignments
+    let graph = node_graph(
+        "
+        let x: i32;
+        bb0: { x = 1; goto bb1, bb2; }
+        bb1: { x = 2; goto bb3; }
+        bb2: { goto bb3; }
+        bb3: { }
+        ",
+    )
+    .unwrap();
+    let definitions = vec![0, 1];
+    let reaching = ReachingDefinitions::new(&graph, definitions.clone(), |node| {
+        if definitions.contains(&node) {
+            definitions.clone()
+        } else {
+            Vec::new()
+        }
+    });
+    let on_entry = |node: &str| -> Vec<_> {
+        reaching
+            .on_entry(graph.index(&node.into()).unwrap())
+            .collect()
+    };
+    assert_eq!(on_entry("bb0[0]"), Vec::<usize>::new());
+    assert_eq!(on_entry("bb1[0]"), [0]);
+    assert_eq!(on_entry("bb2[0]"), [0]);
+    assert_eq!(on_entry("bb3[0]"), [0, 1]);
+}
+
+#[test]
+fn invalidations_of_unreached_loans() {
+    // Writing to `x` before it's borrowed doesn't invalidate the loan
+    let facts = emit_facts(
+        "
+        let x: i32;
+        let r: &'r i32;
+        bb0: { x = 1; r = &'L_x x; x = 2; use(copy r); }
+        ",
+    )
+    .unwrap();
+    let invalidations: Vec<_> = facts
+        .invalidate_origin
+        .iter()
+        .map(|(origin, node)| format!("{} at {}", origin.0, node.0))
+        .collect();
+    assert_eq!(invalidations, ["'L_x at bb0[2]"]);
+}
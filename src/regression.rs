@@ -0,0 +1,182 @@
+//! Regression checks of the outputs of programs across versions of this crate: a baseline of
+//! the programs' outputs is recorded once, and later runs report the programs whose outputs
+//! changed, with the lines that changed.
+//!
+//! The output of a program is a snapshot of its facts, as they're displayed, followed by its
+//! errors, or by why it couldn't be analyzed. The snapshot of each program is a file of the
+//! baseline directory, at the program's path with a `.snap` extension appended, so that
+//! baselines can be reviewed and versioned with the programs.
+
+use crate::diagnostics::diagnose_with_limits;
+use crate::fact_emitter::{emit_facts_with_options, EmitOptions};
+use crate::sources::Sources;
+use eyre::WrapErr;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+/// A program whose output is not the one of the baseline.
+#[derive(Debug)]
+pub struct Regression {
+    pub path: PathBuf,
+    pub change: OutputChange,
+}
+
+#[derive(Debug)]
+pub enum OutputChange {
+    /// The program has no snapshot in the baseline.
+    New,
+
+    /// The lines of the snapshot which changed: the baseline's lines prefixed with `-`, and the
+    /// new ones with `+`, each group after the number of its first line in the baseline.
+    Changed(String),
+}
+
+/// The snapshot of the outputs of the program at the path: its facts, and its errors.
+pub fn output_snapshot(path: &Path, options: &EmitOptions) -> String {
+    let analyze = || -> eyre::Result<String> {
+        let sources = Sources::load(path)?;
+        let facts = emit_facts_with_options(sources.text(), options)
+            .map_err(|e| sources.locate_parse_error(e))?;
+        let mut snapshot = format!("{}\n== errors\n", facts);
+        for error in diagnose_with_limits(&facts, &options.limits)? {
+            snapshot += &format!("{}\n", error);
+        }
+        Ok(snapshot)
+    };
+    analyze().unwrap_or_else(|e| format!("== failed\n{:#}\n", e))
+}
+
+/// Records the snapshots of the programs in the baseline directory, replacing their previous
+/// snapshots.
+pub fn record_baseline(
+    programs: &[PathBuf],
+    baseline: &Path,
+    options: &EmitOptions,
+) -> eyre::Result<()> {
+    for program in programs {
+        let snapshot_path = snapshot_path(baseline, program);
+        if let Some(dir) = snapshot_path.parent() {
+            std::fs::create_dir_all(dir)
+                .wrap_err_with(|| format!("failed to create `{}`", dir.display()))?;
+        }
+        std::fs::write(&snapshot_path, output_snapshot(program, options))
+            .wrap_err_with(|| format!("failed to write `{}`", snapshot_path.display()))?;
+    }
+    Ok(())
+}
+
+/// Compares the snapshots of the programs with the ones recorded in the baseline directory, and
+/// returns the programs whose snapshots changed, in program order.
+pub fn compare_to_baseline(
+    programs: &[PathBuf],
+    baseline: &Path,
+    options: &EmitOptions,
+) -> eyre::Result<Vec<Regression>> {
+    let mut regressions = Vec::new();
+    for program in programs {
+        let snapshot_path = snapshot_path(baseline, program);
+        let change = if snapshot_path.exists() {
+            let expected = std::fs::read_to_string(&snapshot_path)
+                .wrap_err_with(|| format!("failed to read `{}`", snapshot_path.display()))?;
+            let actual = output_snapshot(program, options);
+            if expected == actual {
+                continue;
+            }
+            OutputChange::Changed(diff_lines(&expected, &actual))
+        } else {
+            OutputChange::New
+        };
+        regressions.push(Regression {
+            path: program.clone(),
+            change,
+        });
+    }
+    Ok(regressions)
+}
+
+// The path of the program's snapshot in the baseline: the program's path, without its root or
+// parent directory components, with a `.snap` extension appended.
+fn snapshot_path(baseline: &Path, program: &Path) -> PathBuf {
+    let mut path = baseline.to_path_buf();
+    for component in program.components() {
+        if let Component::Normal(component) = component {
+            path.push(component);
+        }
+    }
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".snap");
+    path.set_file_name(file_name);
+    path
+}
+
+// The changed lines between the two texts, see `OutputChange::Changed`. The lines common to
+// both texts are the longest common subsequence of the lines between their common prefix and
+// suffix.
+fn diff_lines(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+    let prefix = expected
+        .iter()
+        .zip(&actual)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(actual[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &expected[prefix..expected.len() - suffix];
+    let new = &actual[prefix..actual.len() - suffix];
+
+    // The length of the longest common subsequence of the suffixes of the lines
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    let mut in_group = false;
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            in_group = false;
+            continue;
+        }
+        if !in_group {
+            diff += &format!("@@ line {}\n", prefix + i + 1);
+            in_group = true;
+        }
+        if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff += &format!("-{}\n", old[i]);
+            i += 1;
+        } else {
+            diff += &format!("+{}\n", new[j]);
+            j += 1;
+        }
+    }
+    diff
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.change {
+            OutputChange::New => writeln!(f, "new: {}", self.path.display()),
+            OutputChange::Changed(diff) => {
+                writeln!(f, "changed: {}", self.path.display())?;
+                write!(f, "{}", diff)
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use super::*;
+use crate::test_util::write_files;
+use insta::assert_display_snapshot;
+
+const VALID: &str = "
+let x: i32;
+let r: &'r i32;
+bb0: {
+    r = &'L_x x;
+    use(copy r);
+    x = 4;
+}
+";
+
+const INVALID: &str = "
+let x: i32;
+let r: &'r i32;
+bb0: {
+    r = &'L_x x;
+    x = 4;
+    use(copy r);
+}
+";
+
+#[test]
+fn compare_with_baseline() {
+    let dir = write_files(
+        "regression",
+        &[("programs/a.txt", VALID), ("programs/b.txt", VALID)],
+    );
+    let programs_dir = dir.join("programs");
+    let programs: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+        .iter()
+        .map(|name| programs_dir.join(name))
+        .collect();
+
+    let baseline = dir.join("baseline");
+    let options = EmitOptions::default();
+    record_baseline(&programs[..2], &baseline, &options).unwrap();
+    assert!(compare_to_baseline(&programs[..2], &baseline, &options)
+        .unwrap()
+        .is_empty());
+
+    // The changed lines are reported, and the programs without snapshots
+    std::fs::write(&programs[1], INVALID).unwrap();
+    std::fs::write(&programs[2], VALID).unwrap();
+    let regressions = compare_to_baseline(&programs, &baseline, &options).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let report: String = regressions.iter().map(|r| r.to_string()).collect();
+    let report = report.replace(&programs_dir.display().to_string(), "<dir>");
+    assert_display_snapshot!(report, @r###"
+    changed: <dir>/b.txt
+    @@ line 11
+    -bb0[1]: "use(copy r)" {
+    -	access_origin('r)
+    +bb0[1]: "x = 4" {
+    +	invalidate_origin('L_x)
+    @@ line 16
+    -bb0[2]: "x = 4" {
+    +bb0[2]: "use(copy r)" {
+    +	access_origin('r)
+    @@ line 21
+    +error[E0506]: cannot assign to `x` because it is borrowed
+    +  --> bb0[1]
+    +   | bb0[0] `r = &'L_x x`: `x` is borrowed here
+    +   | bb0[1] `x = 4`: `x` is assigned to here but it was already borrowed
+    +   | bb0[2] `use(copy r)`: borrow later used here
+    +   = note: the shortest path through the borrow, its invalidation, and the later use:
+    +           bb0[0] `r = &'L_x x`
+    +           bb0[1] `x = 4`
+    +           bb0[2] `use(copy r)`
+    +
+    new: <dir>/c.txt
+    "###);
+}
+
+#[test]
+fn snapshot_paths() {
+    let baseline = Path::new("baseline");
+    assert_eq!(
+        snapshot_path(baseline, Path::new("examples/a.txt")),
+        Path::new("baseline/examples/a.txt.snap")
+    );
+    assert_eq!(
+        snapshot_path(baseline, Path::new("/tmp/../b.txt")),
+        Path::new("baseline/tmp/b.txt.snap")
+    );
+}
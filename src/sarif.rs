@@ -0,0 +1,165 @@
+//! Exports the results of a batch of programs as a SARIF 2.1.0 log, the format of static
+//! analysis results read by result viewers and code review tools.
+//!
+//! The log has a single run, whose results are the warnings and errors of the programs, built
+//! from their JSON diagnostics, see `Diagnostic::to_json`: a diagnostic's primary spans are the
+//! result's locations, and its other labeled spans are related locations. The rustc error codes
+//! are the rules of the run. A program which couldn't be analyzed is an error result located at
+//! its file.
+
+use crate::batch::ProgramReport;
+use crate::json::Json;
+
+#[cfg(test)]
+mod test;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The SARIF log of the reports of a batch, in the order of the reports.
+pub fn sarif_log(reports: &[ProgramReport]) -> Json {
+    let mut results = Vec::new();
+    for report in reports {
+        match &report.outcome {
+            Ok(outcome) => results.extend(outcome.json.iter().map(result)),
+            Err(e) => {
+                let uri = artifact_uri(&report.path.display().to_string());
+                results.push(object(vec![
+                    ("level", string("error")),
+                    ("message", message(&format!("{:#}", e))),
+                    (
+                        "locations",
+                        Json::Array(vec![object(vec![(
+                            "physicalLocation",
+                            object(vec![("artifactLocation", artifact_location(&uri))]),
+                        )])]),
+                    ),
+                ]));
+            }
+        }
+    }
+
+    let mut rule_ids: Vec<_> = results
+        .iter()
+        .filter_map(|result| result.get("ruleId").and_then(Json::as_str))
+        .collect();
+    rule_ids.sort_unstable();
+    rule_ids.dedup();
+    let rules = rule_ids
+        .into_iter()
+        .map(|id| object(vec![("id", string(id))]))
+        .collect();
+
+    let driver = object(vec![
+        ("name", string(env!("CARGO_PKG_NAME"))),
+        ("version", string(env!("CARGO_PKG_VERSION"))),
+        ("rules", Json::Array(rules)),
+    ]);
+    let run = object(vec![
+        ("tool", object(vec![("driver", driver)])),
+        ("results", Json::Array(results)),
+    ]);
+    object(vec![
+        ("$schema", string(SCHEMA)),
+        ("version", string("2.1.0")),
+        ("runs", Json::Array(vec![run])),
+    ])
+}
+
+// The SARIF result of a JSON diagnostic.
+fn result(diagnostic: &Json) -> Json {
+    let text = |key: &str| diagnostic.get(key).and_then(Json::as_str).unwrap_or("");
+    let level = match text("level") {
+        "error" => "error",
+        "warning" => "warning",
+        _ => "note",
+    };
+    let spans = diagnostic.get("spans").map_or(&[][..], Json::as_array);
+    let is_primary = |span: &&Json| span.get("is_primary") == Some(&Json::Bool(true));
+    let locations = spans.iter().filter(is_primary).map(location).collect();
+    let related_locations = spans
+        .iter()
+        .filter(|span| !is_primary(span))
+        .enumerate()
+        .map(|(idx, span)| {
+            let mut location = location(span);
+            if let Json::Object(members) = &mut location {
+                members.insert(0, ("id".to_string(), Json::Number(idx as f64)));
+            }
+            location
+        })
+        .collect();
+
+    let mut members = Vec::new();
+    let rule_id = diagnostic
+        .get("code")
+        .and_then(|code| code.get("code"))
+        .and_then(Json::as_str);
+    if let Some(rule_id) = rule_id {
+        members.push(("ruleId", string(rule_id)));
+    }
+    members.push(("level", string(level)));
+    members.push(("message", message(text("message"))));
+    members.push(("locations", Json::Array(locations)));
+    members.push(("relatedLocations", Json::Array(related_locations)));
+    object(members)
+}
+
+// The SARIF location of a JSON diagnostic's span, with its label as message.
+fn location(span: &Json) -> Json {
+    let number = |key: &str| match span.get(key) {
+        Some(Json::Number(n)) => *n,
+        _ => 0.0,
+    };
+    let file_name = span.get("file_name").and_then(Json::as_str).unwrap_or("");
+    let region = object(vec![
+        ("startLine", Json::Number(number("line_start"))),
+        ("startColumn", Json::Number(number("column_start"))),
+        ("endLine", Json::Number(number("line_end"))),
+        ("endColumn", Json::Number(number("column_end"))),
+        ("byteOffset", Json::Number(number("byte_start"))),
+        (
+            "byteLength",
+            Json::Number(number("byte_end") - number("byte_start")),
+        ),
+    ]);
+    let mut members = vec![(
+        "physicalLocation",
+        object(vec![
+            (
+                "artifactLocation",
+                artifact_location(&artifact_uri(file_name)),
+            ),
+            ("region", region),
+        ]),
+    )];
+    if let Some(label) = span.get("label").and_then(Json::as_str) {
+        members.push(("message", message(label)));
+    }
+    object(members)
+}
+
+// The URI of a file path: SARIF URIs use forward slashes.
+fn artifact_uri(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+fn artifact_location(uri: &str) -> Json {
+    object(vec![("uri", string(uri))])
+}
+
+fn message(text: &str) -> Json {
+    object(vec![("text", string(text))])
+}
+
+fn object(members: Vec<(&str, Json)>) -> Json {
+    Json::Object(
+        members
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+fn string(s: &str) -> Json {
+    Json::String(s.to_string())
+}
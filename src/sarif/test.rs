@@ -0,0 +1,41 @@
+use super::*;
+use crate::batch::{analyze_programs, BatchOptions};
+use crate::test_util::write_files;
+use insta::assert_display_snapshot;
+
+#[test]
+fn results_of_a_batch() {
+    let files = [
+        (
+            "invalid.txt",
+            "let x: i32;\nlet r: &'r i32;\nbb0: {\n    r = &'L_x x;\n    x = 4;\n    use(copy r);\n}\n",
+        ),
+        ("unused.txt", "let x: i32;\nbb0: {\n}\n"),
+        ("unparsable.txt", "bb0: { x = ; }\n"),
+    ];
+    let dir = write_files("sarif-results", &files);
+    let programs: Vec<_> = files.iter().map(|(name, _)| dir.join(name)).collect();
+    let reports = analyze_programs(&programs, &BatchOptions::default());
+    let log = sarif_log(&reports);
+    let log = log.to_string().replace(&dir.display().to_string(), "<dir>");
+    let log = crate::json::parse_json(&log).unwrap();
+    assert_eq!(log.get("version"), Some(&string("2.1.0")));
+
+    let run = &log.get("runs").unwrap().as_array()[0];
+    let driver = run.get("tool").and_then(|tool| tool.get("driver")).unwrap();
+    assert_display_snapshot!(driver.get("rules").unwrap(), @r###"
+    [{"id":"E0506"}]
+    "###);
+    let results: Vec<_> = run
+        .get("results")
+        .unwrap()
+        .as_array()
+        .iter()
+        .map(|result| result.to_string())
+        .collect();
+    assert_display_snapshot!(results.join("\n"), @r###"
+    {"ruleId":"E0506","level":"error","message":{"text":"cannot assign to `x` because it is borrowed"},"locations":[{"physicalLocation":{"artifactLocation":{"uri":"<dir>/invalid.txt"},"region":{"startLine":5,"startColumn":5,"endLine":5,"endColumn":10,"byteOffset":56,"byteLength":5}},"message":{"text":"`x` is assigned to here but it was already borrowed"}}],"relatedLocations":[{"id":0,"physicalLocation":{"artifactLocation":{"uri":"<dir>/invalid.txt"},"region":{"startLine":4,"startColumn":5,"endLine":4,"endColumn":16,"byteOffset":39,"byteLength":11}},"message":{"text":"`x` is borrowed here"}},{"id":1,"physicalLocation":{"artifactLocation":{"uri":"<dir>/invalid.txt"},"region":{"startLine":6,"startColumn":5,"endLine":6,"endColumn":16,"byteOffset":67,"byteLength":11}},"message":{"text":"borrow later used here"}}]}
+    {"level":"warning","message":{"text":"variable `x` is never used"},"locations":[{"physicalLocation":{"artifactLocation":{"uri":"<dir>/unused.txt"},"region":{"startLine":1,"startColumn":5,"endLine":1,"endColumn":6,"byteOffset":4,"byteLength":1}}}],"relatedLocations":[]}
+    {"level":"error","message":{"text":"error: syntax error, expected one of \"&\", \"(\", \"*\", \"copy\", \"move\", a number, an identifier\n --> <dir>/unparsable.txt:1:12\n  |\n1 | bb0: { x = ; }\n  |            ^\nfound 1 error(s)"},"locations":[{"physicalLocation":{"artifactLocation":{"uri":"<dir>/unparsable.txt"}}}]}
+    "###);
+}
@@ -0,0 +1,567 @@
+//! An in-crate solver for the rules in `polonius.dl`, using datafrog, to analyze the facts
+//! emitted for a program without needing Soufflé.
+//!
+//! The rules are computed in the same strata as their datalog counterparts: origin liveness,
+//! then the subsets flowing through the CFG, then the invalidations propagated through them, and
+//! finally the errors.
+//!
+//! The `naive` solver evaluates the same rules literally, as a reference to check this one
+//! against. Its relations are also what the derivation trees of the errors are built from.
+
+use crate::fact_emitter::{Facts, Loan, Node, Origin};
+use crate::limits::{Budget, Limits};
+use datafrog::{Iteration, Relation, RelationLeaper, ValueFilter};
+use std::collections::HashMap;
+
+mod coverage;
+mod derivation;
+pub(crate) mod naive;
+
+#[cfg(test)]
+mod test;
+
+pub use coverage::{rule_coverage, CoverageMatrix, RULES};
+pub use derivation::{explain, Derivation};
+pub use naive::solve_naive;
+
+type OriginIdx = u32;
+type NodeIdx = u32;
+type LoanIdx = u32;
+
+/// The errors computed from a program's facts, and the loans each origin may contain.
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct SolverOutput {
+    /// `invalidated_origin_accessed(o, n)`: the origin `o`, invalidated by a conflicting access
+    /// to one of its loans, is accessed at node `n`.
+    pub invalidated_origin_accessed: Vec<(Origin, Node)>,
+
+    /// `placeholder_subset_error(o1, o2, n)`: `o1: o2` is required between two placeholders at
+    /// node `n`, without being known to hold.
+    pub placeholder_subset_error: Vec<(Origin, Origin, Node)>,
+
+    /// `loan_escapes_into_placeholder(l, o, n)`: the loan `l` flows into the placeholder `o` at
+    /// node `n`, so the borrowed value would need to outlive the function.
+    pub loan_escapes_into_placeholder: Vec<(Origin, Origin, Node)>,
+
+    /// `origin_contains_loan_at(o, l, n)`: the origin `o` may contain the loan `l` on entry to
+    /// node `n`. This is not an error, but the state of the analysis at each node.
+    pub origin_contains_loan_at: Vec<(Origin, Loan, Node)>,
+}
+
+impl SolverOutput {
+    /// The loans the origin may contain on entry to the node.
+    pub fn loans_in(&self, origin: &Origin, node: &Node) -> Vec<&Loan> {
+        self.origin_contains_loan_at
+            .iter()
+            .filter(|(o, _, n)| o == origin && n == node)
+            .map(|(_, loan, _)| loan)
+            .collect()
+    }
+}
+
+// Maps origins and nodes to the dense indices datafrog computes with.
+#[derive(Default)]
+pub(crate) struct Interner<T> {
+    indices: HashMap<T, u32>,
+    values: Vec<T>,
+}
+
+impl<T: Clone + Eq + std::hash::Hash> Interner<T> {
+    pub(crate) fn intern(&mut self, value: &T) -> u32 {
+        if let Some(&idx) = self.indices.get(value) {
+            return idx;
+        }
+        let idx = self.values.len() as u32;
+        self.indices.insert(value.clone(), idx);
+        self.values.push(value.clone());
+        idx
+    }
+
+    pub(crate) fn value(&self, idx: u32) -> &T {
+        &self.values[idx as usize]
+    }
+}
+
+/// Computes the errors of the rules in `polonius.dl` over the given facts.
+pub fn solve(facts: &Facts) -> SolverOutput {
+    solve_with_limits(facts, &Limits::default()).expect("solving without limits can't fail")
+}
+
+/// Computes the errors of the rules in `polonius.dl` over the given facts, or fails if that
+/// exceeds the limits.
+pub fn solve_with_limits(facts: &Facts, limits: &Limits) -> eyre::Result<SolverOutput> {
+    solve_within(facts, &Budget::start(limits))
+}
+
+// Computes the errors like `solve_with_limits`, within the limits of a solving which has
+// already started, like the multiple solvings of the diagnostics.
+#[tracing::instrument(skip_all)]
+pub(crate) fn solve_within(facts: &Facts, budget: &Budget<'_>) -> eyre::Result<SolverOutput> {
+    let mut origins = Interner::<Origin>::default();
+    let mut nodes = Interner::<Node>::default();
+
+    let origin_node =
+        |origins: &mut Interner<Origin>,
+         nodes: &mut Interner<Node>,
+         (origin, node): &(Origin, Node)| { (origins.intern(origin), nodes.intern(node)) };
+
+    let access_origin: Relation<(OriginIdx, NodeIdx)> = facts
+        .access_origin
+        .iter()
+        .map(|fact| origin_node(&mut origins, &mut nodes, fact))
+        .collect();
+    let invalidate_origin: Relation<(OriginIdx, NodeIdx)> = facts
+        .invalidate_origin
+        .iter()
+        .map(|fact| origin_node(&mut origins, &mut nodes, fact))
+        .collect();
+    let clear_origin: Relation<(OriginIdx, NodeIdx)> = facts
+        .clear_origin
+        .iter()
+        .map(|fact| origin_node(&mut origins, &mut nodes, fact))
+        .collect();
+    let introduce_subset: Relation<(OriginIdx, OriginIdx, NodeIdx)> = facts
+        .introduce_subset
+        .iter()
+        .map(|(origin1, origin2, node)| {
+            (
+                origins.intern(origin1),
+                origins.intern(origin2),
+                nodes.intern(node),
+            )
+        })
+        .collect();
+    let cfg_edge: Relation<(NodeIdx, NodeIdx)> = facts
+        .cfg_edge
+        .iter()
+        .map(|(node1, node2)| (nodes.intern(node1), nodes.intern(node2)))
+        .collect();
+    let cfg_edge_reverse = Relation::from_map(&cfg_edge, |&(node1, node2)| (node2, node1));
+    let placeholder_origin: Relation<OriginIdx> = facts
+        .placeholder_origin
+        .iter()
+        .map(|origin| origins.intern(origin))
+        .collect();
+    let known_placeholder_subset: Relation<(OriginIdx, OriginIdx)> = facts
+        .known_placeholder_subset
+        .iter()
+        .map(|(origin1, origin2)| (origins.intern(origin1), origins.intern(origin2)))
+        .collect();
+
+    // Loans and placeholders are considered live everywhere, when carrying subsets over
+    // CFG edges: they're flagged by index, instead of being paired with every node.
+    let mut is_global = Vec::new();
+    for origin in facts
+        .mark_as_loan_origin
+        .iter()
+        .chain(&facts.placeholder_origin)
+    {
+        let origin = origins.intern(origin) as usize;
+        if is_global.len() <= origin {
+            is_global.resize(origin + 1, false);
+        }
+        is_global[origin] = true;
+    }
+    for (_, node) in &facts.node_text {
+        nodes.intern(node);
+    }
+    budget.check_time("preparing the facts")?;
+
+    // origin_live_on_entry
+    let origin_live_on_entry = {
+        let _stratum = tracing::debug_span!("stratum", relation = "origin_live_on_entry").entered();
+        let mut iteration = Iteration::new();
+        let origin_live_on_entry =
+            iteration.variable::<(OriginIdx, NodeIdx)>("origin_live_on_entry");
+        origin_live_on_entry.insert(access_origin.clone());
+
+        let mut round = 0;
+        while iteration.changed() {
+            round += 1;
+            tracing::trace!(round, "iteration");
+            budget.check_round("origin_live_on_entry", round)?;
+
+            // origin_live_on_entry(O, N1) :-
+            //   cfg_edge(N1, N2),
+            //   !clear_origin(O, N1),
+            //   origin_live_on_entry(O, N2).
+            origin_live_on_entry.from_leapjoin(
+                &origin_live_on_entry,
+                (
+                    cfg_edge_reverse.extend_with(|&(_origin, node2)| node2),
+                    clear_origin.extend_anti(|&(origin, _node2)| origin),
+                ),
+                |&(origin, _node2), &node1| (origin, node1),
+            );
+        }
+        tracing::debug!(rounds = round, "reached fixpoint");
+        origin_live_on_entry.complete()
+    };
+
+    let is_live_or_global = |origin: OriginIdx, node: NodeIdx| {
+        is_global.get(origin as usize).copied().unwrap_or(false)
+            || origin_live_on_entry.binary_search(&(origin, node)).is_ok()
+    };
+
+    // subset_on_entry, subset_on_exit
+    let (subset_on_entry, subset_on_exit) = {
+        let _stratum = tracing::debug_span!("stratum", relation = "subset").entered();
+        let mut iteration = Iteration::new();
+        let subset_on_exit =
+            iteration.variable::<(OriginIdx, OriginIdx, NodeIdx)>("subset_on_exit");
+        let subset_on_entry =
+            iteration.variable::<(OriginIdx, OriginIdx, NodeIdx)>("subset_on_entry");
+
+        // Indices of the above, for joins
+        let subset_on_exit_o1 =
+            iteration.variable_indistinct::<((OriginIdx, NodeIdx), OriginIdx)>("subset_on_exit_o1");
+        let subset_on_exit_o2 =
+            iteration.variable_indistinct::<((OriginIdx, NodeIdx), OriginIdx)>("subset_on_exit_o2");
+        let subset_on_entry_o1 = iteration
+            .variable_indistinct::<((OriginIdx, NodeIdx), OriginIdx)>("subset_on_entry_o1");
+        let subset_on_entry_uncleared_o1 = iteration
+            .variable_indistinct::<((OriginIdx, NodeIdx), OriginIdx)>(
+                "subset_on_entry_uncleared_o1",
+            );
+
+        // subset_on_exit(O1, O2, N) :- introduce_subset(O1, O2, N).
+        subset_on_exit.insert(introduce_subset);
+
+        let mut round = 0;
+        while iteration.changed() {
+            round += 1;
+            tracing::trace!(round, "iteration");
+            budget.check_round("subset", round)?;
+
+            subset_on_exit_o1.from_map(&subset_on_exit, |&(o1, o2, node)| ((o1, node), o2));
+            subset_on_exit_o2.from_map(&subset_on_exit, |&(o1, o2, node)| ((o2, node), o1));
+            subset_on_entry_o1.from_map(&subset_on_entry, |&(o1, o2, node)| ((o1, node), o2));
+
+            // subset_on_exit(O1, O2, N) :-
+            //   subset_on_entry(O1, O2, N),
+            //   !clear_origin(O1, N),
+            //   !clear_origin(O2, N).
+            subset_on_entry_uncleared_o1.from_antijoin(
+                &subset_on_entry_o1,
+                &clear_origin,
+                |&(o1, node), &o2| ((o2, node), o1),
+            );
+            subset_on_exit.from_antijoin(
+                &subset_on_entry_uncleared_o1,
+                &clear_origin,
+                |&(o2, node), &o1| (o1, o2, node),
+            );
+
+            // subset_on_exit(O1, O3, N1) :-
+            //   subset_on_exit(O1, O2, N1),
+            //   subset_on_exit(O2, O3, N1).
+            subset_on_exit.from_join(
+                &subset_on_exit_o2,
+                &subset_on_exit_o1,
+                |&(_o2, node), &o1, &o3| (o1, o3, node),
+            );
+
+            // subset_on_entry(O1, O2, N2) :-
+            //   cfg_edge(N1, N2),
+            //   (origin_live_on_entry(O1, N2); mark_as_loan_origin(O1); placeholder_origin(O1)),
+            //   (origin_live_on_entry(O2, N2); mark_as_loan_origin(O2); placeholder_origin(O2)),
+            //   subset_on_exit(O1, O2, N1).
+            subset_on_entry.from_leapjoin(
+                &subset_on_exit,
+                (
+                    cfg_edge.extend_with(|&(_o1, _o2, node1)| node1),
+                    ValueFilter::from(|&(o1, o2, _node1), &node2| {
+                        is_live_or_global(o1, node2) && is_live_or_global(o2, node2)
+                    }),
+                ),
+                |&(o1, o2, _node1), &node2| (o1, o2, node2),
+            );
+        }
+
+        tracing::debug!(rounds = round, "reached fixpoint");
+        (subset_on_entry.complete(), subset_on_exit.complete())
+    };
+
+    // origin_invalidated
+    let origin_invalidated = {
+        // The origins invalidated at a node, directly or through the subsets on entry to
+        // that node:
+        //
+        // origin_invalidated(O2, N2) :-
+        //   cfg_edge(N1, N2),
+        //   !clear_origin(O2, N1),
+        //   subset_on_entry(O1, O2, N1),
+        //   invalidate_origin(O1, N1).
+        let subset_on_entry_o1 =
+            Relation::from_map(&subset_on_entry, |&(o1, o2, node)| ((o1, node), o2));
+        let invalidated_through_subsets = Relation::from_join(
+            &subset_on_entry_o1,
+            &Relation::from_map(&invalidate_origin, |&(origin, node)| ((origin, node), ())),
+            |&(_o1, node), &o2, &()| (o2, node),
+        );
+
+        let _stratum = tracing::debug_span!("stratum", relation = "origin_invalidated").entered();
+        let mut iteration = Iteration::new();
+        let origin_invalidated = iteration.variable::<(OriginIdx, NodeIdx)>("origin_invalidated");
+        let invalidated_on_exit =
+            iteration.variable::<(OriginIdx, NodeIdx)>("origin_invalidated_on_exit");
+        invalidated_on_exit.insert(invalidate_origin.clone());
+        invalidated_on_exit.insert(invalidated_through_subsets);
+
+        let mut round = 0;
+        while iteration.changed() {
+            round += 1;
+            tracing::trace!(round, "iteration");
+            budget.check_round("origin_invalidated", round)?;
+
+            invalidated_on_exit.from_map(&origin_invalidated, |&fact| fact);
+
+            // origin_invalidated(O, N2) :-
+            //   cfg_edge(N1, N2),
+            //   !clear_origin(O, N1),
+            //   (invalidate_origin(O, N1); origin_invalidated(O, N1)).
+            origin_invalidated.from_leapjoin(
+                &invalidated_on_exit,
+                (
+                    cfg_edge.extend_with(|&(_origin, node1)| node1),
+                    ValueFilter::from(|&(origin, node1), _node2| {
+                        clear_origin.binary_search(&(origin, node1)).is_err()
+                    }),
+                ),
+                |&(origin, _node1), &node2| (origin, node2),
+            );
+        }
+        tracing::debug!(rounds = round, "reached fixpoint");
+        origin_invalidated.complete()
+    };
+
+    // origin_contains_loan_at
+    let mut loans = Interner::<Loan>::default();
+    let loan_issued_at: Relation<(OriginIdx, LoanIdx, NodeIdx)> = facts
+        .loan_issued_at
+        .iter()
+        .map(|(origin, loan, node)| {
+            (
+                origins.intern(origin),
+                loans.intern(loan),
+                nodes.intern(node),
+            )
+        })
+        .collect();
+    let loan_in_own_origin = {
+        let _stratum =
+            tracing::debug_span!("stratum", relation = "origin_contains_loan_at").entered();
+        let mut iteration = Iteration::new();
+        let loan_in_own_origin =
+            iteration.variable::<(OriginIdx, LoanIdx, NodeIdx)>("loan_in_own_origin");
+        let issued = iteration.variable::<(OriginIdx, LoanIdx, NodeIdx)>("loan_issued_at");
+        issued.insert(loan_issued_at.clone());
+
+        let mut round = 0;
+        while iteration.changed() {
+            round += 1;
+            tracing::trace!(round, "iteration");
+            budget.check_round("origin_contains_loan_at", round)?;
+
+            // origin_contains_loan_at(O, L, N2) :-
+            //   cfg_edge(N1, N2),
+            //   loan_issued_at(O, L, N1).
+            loan_in_own_origin.from_leapjoin(
+                &issued,
+                cfg_edge.extend_with(|&(_origin, _loan, node1)| node1),
+                |&(origin, loan, _node1), &node2| (origin, loan, node2),
+            );
+
+            // origin_contains_loan_at(O, L, N2) :-
+            //   cfg_edge(N1, N2),
+            //   !clear_origin(O, N1),
+            //   origin_contains_loan_at(O, L, N1),
+            //   loan_issued_at(O, L, _).
+            loan_in_own_origin.from_leapjoin(
+                &loan_in_own_origin,
+                (
+                    cfg_edge.extend_with(|&(_origin, _loan, node1)| node1),
+                    ValueFilter::from(|&(origin, _loan, node1), _node2| {
+                        clear_origin.binary_search(&(origin, node1)).is_err()
+                    }),
+                ),
+                |&(origin, loan, _node1), &node2| (origin, loan, node2),
+            );
+
+            // origin_contains_loan_at(O, L, N2) :-
+            //   cfg_edge(N1, N2),
+            //   origin_contains_loan_at(O, L, N1),
+            //   loan_issued_at(O, L, _),
+            //   placeholder_origin(O).
+            loan_in_own_origin.from_leapjoin(
+                &loan_in_own_origin,
+                (
+                    cfg_edge.extend_with(|&(_origin, _loan, node1)| node1),
+                    ValueFilter::from(|&(origin, _loan, _node1), _node2| {
+                        placeholder_origin.binary_search(&origin).is_ok()
+                    }),
+                ),
+                |&(origin, loan, _node1), &node2| (origin, loan, node2),
+            );
+        }
+        tracing::debug!(rounds = round, "reached fixpoint");
+        loan_in_own_origin.complete()
+    };
+
+    // origin_contains_loan_at(O2, L, N) :-
+    //   origin_contains_loan_at(O1, L, N),
+    //   loan_issued_at(O1, L, _),
+    //   subset_on_entry(O1, O2, N).
+    let loan_in_subsets = Relation::from_join(
+        &Relation::from_map(&loan_in_own_origin, |&(origin, loan, node)| {
+            ((origin, node), loan)
+        }),
+        &Relation::from_map(&subset_on_entry, |&(o1, o2, node)| ((o1, node), o2)),
+        |&(_o1, node), &loan, &o2| (o2, loan, node),
+    );
+    let contains_loan: Relation<(OriginIdx, LoanIdx, NodeIdx)> = loan_in_own_origin
+        .iter()
+        .chain(loan_in_subsets.iter())
+        .copied()
+        .collect();
+    let origin_contains_loan_at: Relation<_> = contains_loan
+        .iter()
+        .map(|&(origin, loan, node)| {
+            (
+                origins.value(origin).clone(),
+                loans.value(loan).clone(),
+                nodes.value(node).clone(),
+            )
+        })
+        .collect();
+
+    // invalidated_origin_accessed(O, N) :-
+    //   access_origin(O, N),
+    //   origin_invalidated(O, N).
+    let mut invalidated_origin_accessed: Vec<_> = access_origin
+        .iter()
+        .filter(|fact| origin_invalidated.binary_search(fact).is_ok())
+        .map(|&(origin, node)| (origins.value(origin).clone(), nodes.value(node).clone()))
+        .collect();
+
+    // known_placeholder_subset_closure(O1, O3) :-
+    //   known_placeholder_subset_closure(O1, O2),
+    //   known_placeholder_subset(O2, O3).
+    let known_placeholder_subset_closure = {
+        let _stratum =
+            tracing::debug_span!("stratum", relation = "known_placeholder_subset_closure")
+                .entered();
+        let mut iteration = Iteration::new();
+        let closure = iteration.variable::<(OriginIdx, OriginIdx)>("known_placeholder_subset");
+        closure.insert(known_placeholder_subset.clone());
+        let mut round = 0;
+        while iteration.changed() {
+            round += 1;
+            tracing::trace!(round, "iteration");
+            budget.check_round("known_placeholder_subset_closure", round)?;
+
+            closure.from_leapjoin(
+                &closure,
+                known_placeholder_subset.extend_with(|&(_o1, o2)| o2),
+                |&(o1, _o2), &o3| (o1, o3),
+            );
+        }
+        tracing::debug!(rounds = round, "reached fixpoint");
+        closure.complete()
+    };
+
+    // placeholder_subset_error(O1, O2, N) :-
+    //   subset_on_exit(O1, O2, N),
+    //   placeholder_origin(O1),
+    //   placeholder_origin(O2),
+    //   O1 != O2,
+    //   !known_placeholder_subset_closure(O1, O2).
+    let mut placeholder_subset_error: Vec<_> = subset_on_exit
+        .iter()
+        .filter(|&&(o1, o2, _node)| {
+            o1 != o2
+                && placeholder_origin.binary_search(&o1).is_ok()
+                && placeholder_origin.binary_search(&o2).is_ok()
+                && known_placeholder_subset_closure
+                    .binary_search(&(o1, o2))
+                    .is_err()
+        })
+        .map(|&(o1, o2, node)| {
+            (
+                origins.value(o1).clone(),
+                origins.value(o2).clone(),
+                nodes.value(node).clone(),
+            )
+        })
+        .collect();
+
+    // placeholder_subset_error(O1, O2, N) :-
+    //   origin_contains_loan_at(O2, L, N),
+    //   loan_issued_at(O1, L, _),
+    //   placeholder_origin(O1),
+    //   placeholder_origin(O2),
+    //   O1 != O2,
+    //   !known_placeholder_subset_closure(O1, O2).
+    let placeholder_loans: Relation<(LoanIdx, OriginIdx)> = loan_issued_at
+        .iter()
+        .filter(|&&(origin, _loan, _node)| placeholder_origin.binary_search(&origin).is_ok())
+        .map(|&(origin, loan, _node)| (loan, origin))
+        .collect();
+    let placeholder_loan_errors = Relation::from_join(
+        &Relation::from_map(&contains_loan, |&(o2, loan, node)| (loan, (o2, node))),
+        &placeholder_loans,
+        |_loan, &(o2, node), &o1| (o1, o2, node),
+    );
+    placeholder_subset_error.extend(
+        placeholder_loan_errors
+            .iter()
+            .filter(|&&(o1, o2, _node)| {
+                o1 != o2
+                    && placeholder_origin.binary_search(&o2).is_ok()
+                    && known_placeholder_subset_closure
+                        .binary_search(&(o1, o2))
+                        .is_err()
+            })
+            .map(|&(o1, o2, node)| {
+                (
+                    origins.value(o1).clone(),
+                    origins.value(o2).clone(),
+                    nodes.value(node).clone(),
+                )
+            }),
+    );
+
+    // loan_escapes_into_placeholder(L, O, N) :-
+    //   subset_on_exit(L, O, N),
+    //   mark_as_loan_origin(L),
+    //   placeholder_origin(O).
+    let loan_origin: Relation<OriginIdx> = facts
+        .mark_as_loan_origin
+        .iter()
+        .map(|origin| origins.intern(origin))
+        .collect();
+    let mut loan_escapes_into_placeholder: Vec<_> = subset_on_exit
+        .iter()
+        .filter(|&&(loan, o, _node)| {
+            loan_origin.binary_search(&loan).is_ok() && placeholder_origin.binary_search(&o).is_ok()
+        })
+        .map(|&(loan, o, node)| {
+            (
+                origins.value(loan).clone(),
+                origins.value(o).clone(),
+                nodes.value(node).clone(),
+            )
+        })
+        .collect();
+
+    invalidated_origin_accessed.sort();
+    placeholder_subset_error.sort();
+    placeholder_subset_error.dedup();
+    loan_escapes_into_placeholder.sort();
+    Ok(SolverOutput {
+        invalidated_origin_accessed,
+        placeholder_subset_error,
+        loan_escapes_into_placeholder,
+        origin_contains_loan_at: origin_contains_loan_at.elements,
+    })
+}
@@ -0,0 +1,147 @@
+//! Coverage of the rules in `polonius.dl`: which of them fired, i.e. derived at least one tuple,
+//! when solving a program's facts, and across a corpus of programs, to know which rules are not
+//! exercised by any of them.
+//!
+//! The rules are named after the relation they derive, and what they derive it from, like in
+//! the derivation trees of the errors. The two sides of the disjunction of `origin_invalidated`
+//! are rules of their own.
+
+use super::naive::{compute, errors};
+use crate::fact_emitter::{Facts, Node, Origin};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// The rules, in the order of `polonius.dl`.
+pub const RULES: &[&str] = &[
+    "origin_live_on_entry: accessed",
+    "origin_live_on_entry: live in a successor, and not cleared",
+    "subset_on_exit: on entry, and not cleared",
+    "subset_on_exit: introduced",
+    "subset_on_exit: transitive",
+    "subset_on_entry: carried over an edge, between live origins",
+    "origin_invalidated: invalidated in a predecessor",
+    "origin_invalidated: carried over an edge, and not cleared",
+    "origin_invalidated: invalidated through a subset in a predecessor",
+    "origin_contains_loan_at: issued in a predecessor",
+    "origin_contains_loan_at: carried over an edge, and not cleared",
+    "origin_contains_loan_at: carried over an edge, in its placeholder",
+    "origin_contains_loan_at: flowing through a subset",
+    "invalidated_origin_accessed: accessed while invalidated",
+    "known_placeholder_subset_closure: known",
+    "known_placeholder_subset_closure: transitive",
+    "placeholder_subset_error: unknown subset between placeholders",
+    "placeholder_subset_error: placeholder loan flowing into another placeholder",
+    "loan_escapes_into_placeholder: loan flowing into a placeholder",
+];
+
+/// Solves the facts with the naive solver, and returns the rules which fired, out of `RULES`.
+pub fn rule_coverage(facts: &Facts) -> BTreeSet<&'static str> {
+    let relations = compute(facts);
+    let output = errors(facts, &relations);
+
+    // The errors between placeholders are derived by either rule, or both
+    let from_subsets =
+        |(o1, o2, n): &(Origin, Origin, Node)| relations.subset_on_exit.contains_key(&(o1, o2, n));
+    let from_placeholder_loan = |(o1, o2, n): &(Origin, Origin, Node)| {
+        facts.loan_issued_at.iter().any(|(issuer, loan, _)| {
+            issuer == o1
+                && relations
+                    .origin_contains_loan_at
+                    .contains_key(&(o2, loan, n))
+        })
+    };
+    let errors = [
+        (
+            "invalidated_origin_accessed: accessed while invalidated",
+            output.invalidated_origin_accessed.is_empty(),
+        ),
+        (
+            "placeholder_subset_error: unknown subset between placeholders",
+            !output.placeholder_subset_error.iter().any(from_subsets),
+        ),
+        (
+            "placeholder_subset_error: placeholder loan flowing into another placeholder",
+            !output
+                .placeholder_subset_error
+                .iter()
+                .any(from_placeholder_loan),
+        ),
+        (
+            "loan_escapes_into_placeholder: loan flowing into a placeholder",
+            output.loan_escapes_into_placeholder.is_empty(),
+        ),
+    ];
+    let mut fired = relations.fired;
+    for (rule, is_empty) in errors {
+        if !is_empty {
+            fired.insert(rule);
+        }
+    }
+    fired
+}
+
+/// The rules fired by each program of a corpus. It's displayed as a matrix with a row per rule,
+/// and a column per program, marking the programs firing the rule.
+#[derive(Default, Debug)]
+pub struct CoverageMatrix {
+    /// The programs, e.g. their paths, and the rules they fired.
+    pub programs: Vec<(String, BTreeSet<&'static str>)>,
+}
+
+impl CoverageMatrix {
+    pub fn add(&mut self, program: impl Into<String>, fired: BTreeSet<&'static str>) {
+        self.programs.push((program.into(), fired));
+    }
+
+    /// The number of programs firing the rule.
+    pub fn count(&self, rule: &str) -> usize {
+        self.programs
+            .iter()
+            .filter(|(_, fired)| fired.contains(rule))
+            .count()
+    }
+
+    /// The rules fired by none of the programs, in the order of `RULES`.
+    pub fn untested(&self) -> Vec<&'static str> {
+        RULES
+            .iter()
+            .copied()
+            .filter(|rule| self.count(rule) == 0)
+            .collect()
+    }
+}
+
+impl fmt::Display for CoverageMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The programs are numbered, from 1, in the columns of the matrix
+        for (idx, (program, _)) in self.programs.iter().enumerate() {
+            writeln!(f, "{:>3}: {}", idx + 1, program)?;
+        }
+        writeln!(f)?;
+        for rule in RULES {
+            let marks: String = self
+                .programs
+                .iter()
+                .map(|(_, fired)| if fired.contains(rule) { 'x' } else { '.' })
+                .collect();
+            writeln!(
+                f,
+                "{} {:>3}/{} {}",
+                marks,
+                self.count(rule),
+                self.programs.len(),
+                rule
+            )?;
+        }
+
+        let untested = self.untested();
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{}/{} rule(s) fired, {} untested",
+            RULES.len() - untested.len(),
+            RULES.len(),
+            untested.len()
+        )
+    }
+}
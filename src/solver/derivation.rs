@@ -0,0 +1,561 @@
+//! Derivation trees of the errors: the rule applications deriving each error, down to the input
+//! facts, to explain why the rules fire.
+//!
+//! The trees are rebuilt from the relations of the naive solver: a derived tuple's premises are
+//! looked for among the tuples derived in earlier rounds, so that every tree is finite.
+
+use super::naive::{compute, errors, Relations};
+use crate::fact_emitter::{Facts, Loan, Node, Origin};
+use crate::json::Json;
+use std::fmt;
+
+/// A fact, and how it was derived: the rule which derived it from its premises, or none for
+/// input facts. Negated premises are the facts which don't hold, e.g. `!clear_origin('a, b)`.
+#[derive(Debug)]
+pub struct Derivation {
+    pub fact: String,
+    pub rule: Option<&'static str>,
+    pub premises: Vec<Derivation>,
+}
+
+impl Derivation {
+    pub(crate) fn input(fact: String) -> Self {
+        Self {
+            fact,
+            rule: None,
+            premises: Vec::new(),
+        }
+    }
+
+    pub(crate) fn derived(fact: String, rule: &'static str, premises: Vec<Derivation>) -> Self {
+        Self {
+            fact,
+            rule: Some(rule),
+            premises,
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("fact".to_string(), Json::String(self.fact.clone())),
+            (
+                "rule".to_string(),
+                self.rule
+                    .map_or(Json::Null, |rule| Json::String(rule.to_string())),
+            ),
+            (
+                "premises".to_string(),
+                Json::Array(self.premises.iter().map(Derivation::to_json).collect()),
+            ),
+        ])
+    }
+
+    /// The node of the fact, its last argument, e.g. `b` in `cfg_edge(a, b)`.
+    pub fn node(&self) -> Option<&str> {
+        let (_, arguments) = self.fact.strip_suffix(')')?.split_once('(')?;
+        arguments.rsplit(", ").next()
+    }
+
+    /// The tree like it's displayed, with the text of the statement of each fact's node, and
+    /// its location in the Rust source, to explain the error in terms of the program.
+    pub fn to_source_string(&self, facts: &Facts) -> String {
+        struct Located<'a>(&'a Derivation, &'a Facts);
+
+        impl fmt::Display for Located<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt_tree(f, "", Some(self.1))
+            }
+        }
+
+        Located(self, facts).to_string()
+    }
+
+    fn fmt_tree(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        prefix: &str,
+        facts: Option<&Facts>,
+    ) -> fmt::Result {
+        write!(f, "{}", self.fact)?;
+        if let (Some(facts), Some(node)) = (facts, self.node()) {
+            let text = facts.node_text.iter().find(|(_, n)| n.0 == node);
+            if let Some((text, _)) = text {
+                write!(f, " at `{}`", text)?;
+            }
+            let location = facts.source_location.iter().find(|(n, _)| n.0 == node);
+            if let Some((_, location)) = location {
+                write!(f, " ({})", location)?;
+            }
+        }
+        match self.rule {
+            Some(rule) => writeln!(f, " [{}]", rule)?,
+            None => writeln!(f)?,
+        }
+        for (idx, premise) in self.premises.iter().enumerate() {
+            let is_last = idx + 1 == self.premises.len();
+            let (branch, indent) = if is_last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            write!(f, "{}{}", prefix, branch)?;
+            premise.fmt_tree(f, &format!("{}{}", prefix, indent), facts)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Derivation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_tree(f, "", None)
+    }
+}
+
+/// Solves the facts, and returns the derivation tree of each error.
+pub fn explain(facts: &Facts) -> Vec<Derivation> {
+    let relations = compute(facts);
+    let output = errors(facts, &relations);
+    let explainer = Explainer { facts, relations };
+
+    let mut derivations = Vec::new();
+    for (origin, node) in &output.invalidated_origin_accessed {
+        derivations.push(Derivation::derived(
+            format!("invalidated_origin_accessed({}, {})", origin.0, node.0),
+            "accessed while invalidated",
+            vec![
+                Derivation::input(format!("access_origin({}, {})", origin.0, node.0)),
+                explainer.origin_invalidated(origin, node),
+            ],
+        ));
+    }
+    for (origin1, origin2, node) in &output.placeholder_subset_error {
+        if !explainer
+            .relations
+            .subset_on_exit
+            .contains_key(&(origin1, origin2, node))
+        {
+            derivations.push(explainer.placeholder_loan_error(origin1, origin2, node));
+            continue;
+        }
+        derivations.push(Derivation::derived(
+            format!(
+                "placeholder_subset_error({}, {}, {})",
+                origin1.0, origin2.0, node.0
+            ),
+            "unknown subset between placeholders",
+            vec![
+                explainer.subset_on_exit(origin1, origin2, node),
+                Derivation::input(format!("placeholder_origin({})", origin1.0)),
+                Derivation::input(format!("placeholder_origin({})", origin2.0)),
+                Derivation::input(format!(
+                    "!known_placeholder_subset_closure({}, {})",
+                    origin1.0, origin2.0
+                )),
+            ],
+        ));
+    }
+    for (loan, placeholder, node) in &output.loan_escapes_into_placeholder {
+        derivations.push(Derivation::derived(
+            format!(
+                "loan_escapes_into_placeholder({}, {}, {})",
+                loan.0, placeholder.0, node.0
+            ),
+            "loan flowing into a placeholder",
+            vec![
+                explainer.subset_on_exit(loan, placeholder, node),
+                Derivation::input(format!("mark_as_loan_origin({})", loan.0)),
+                Derivation::input(format!("placeholder_origin({})", placeholder.0)),
+            ],
+        ));
+    }
+    derivations
+}
+
+struct Explainer<'a> {
+    facts: &'a Facts,
+    relations: Relations<'a>,
+}
+
+impl Explainer<'_> {
+    fn cleared(&self, origin: &Origin, node: &Node) -> bool {
+        self.facts
+            .clear_origin
+            .iter()
+            .any(|(o, n)| o == origin && n == node)
+    }
+
+    fn invalidated(&self, origin: &Origin, node: &Node) -> bool {
+        self.facts
+            .invalidate_origin
+            .iter()
+            .any(|(o, n)| o == origin && n == node)
+    }
+
+    fn cfg_edge(node1: &Node, node2: &Node) -> Derivation {
+        Derivation::input(format!("cfg_edge({}, {})", node1.0, node2.0))
+    }
+
+    fn not_cleared(origin: &Origin, node: &Node) -> Derivation {
+        Derivation::input(format!("!clear_origin({}, {})", origin.0, node.0))
+    }
+
+    fn origin_live_on_entry(&self, origin: &Origin, node: &Node) -> Derivation {
+        let fact = format!("origin_live_on_entry({}, {})", origin.0, node.0);
+        let round = self.relations.origin_live_on_entry[&(origin, node)];
+        if self
+            .facts
+            .access_origin
+            .iter()
+            .any(|(o, n)| o == origin && n == node)
+        {
+            return Derivation::derived(
+                fact,
+                "accessed",
+                vec![Derivation::input(format!(
+                    "access_origin({}, {})",
+                    origin.0, node.0
+                ))],
+            );
+        }
+
+        let (_, successor) = self
+            .facts
+            .cfg_edge
+            .iter()
+            .find(|(n1, n2)| {
+                n1 == node
+                    && !self.cleared(origin, node)
+                    && self
+                        .relations
+                        .origin_live_on_entry
+                        .get(&(origin, n2))
+                        .is_some_and(|&premise_round| premise_round < round)
+            })
+            .expect("live origins are accessed, or live in a successor");
+        Derivation::derived(
+            fact,
+            "live in a successor, and not cleared",
+            vec![
+                Self::cfg_edge(node, successor),
+                Self::not_cleared(origin, node),
+                self.origin_live_on_entry(origin, successor),
+            ],
+        )
+    }
+
+    // Why the origin is live, or considered live, on entry to the node.
+    fn live_or_global(&self, origin: &Origin, node: &Node) -> Derivation {
+        if self
+            .relations
+            .origin_live_on_entry
+            .contains_key(&(origin, node))
+        {
+            self.origin_live_on_entry(origin, node)
+        } else if self.facts.mark_as_loan_origin.contains(origin) {
+            Derivation::input(format!("mark_as_loan_origin({})", origin.0))
+        } else {
+            Derivation::input(format!("placeholder_origin({})", origin.0))
+        }
+    }
+
+    fn subset_on_exit(&self, origin1: &Origin, origin2: &Origin, node: &Node) -> Derivation {
+        let fact = format!("subset_on_exit({}, {}, {})", origin1.0, origin2.0, node.0);
+        let round = self.relations.subset_on_exit[&(origin1, origin2, node)];
+        if self
+            .facts
+            .introduce_subset
+            .iter()
+            .any(|(o1, o2, n)| o1 == origin1 && o2 == origin2 && n == node)
+        {
+            return Derivation::derived(
+                fact,
+                "introduced",
+                vec![Derivation::input(format!(
+                    "introduce_subset({}, {}, {})",
+                    origin1.0, origin2.0, node.0
+                ))],
+            );
+        }
+
+        let on_entry = self
+            .relations
+            .subset_on_entry
+            .get(&(origin1, origin2, node));
+        if on_entry.is_some_and(|&premise_round| premise_round < round)
+            && !self.cleared(origin1, node)
+            && !self.cleared(origin2, node)
+        {
+            return Derivation::derived(
+                fact,
+                "on entry, and not cleared",
+                vec![
+                    self.subset_on_entry(origin1, origin2, node),
+                    Self::not_cleared(origin1, node),
+                    Self::not_cleared(origin2, node),
+                ],
+            );
+        }
+
+        let earlier = |o1, o2| {
+            self.relations
+                .subset_on_exit
+                .get(&(o1, o2, node))
+                .is_some_and(|&premise_round| premise_round < round)
+        };
+        let &(_, middle, _) = self
+            .relations
+            .subset_on_exit
+            .keys()
+            .find(|&&(o1, o2, n)| {
+                n == node && o1 == origin1 && earlier(o1, o2) && earlier(o2, origin2)
+            })
+            .expect("subsets are introduced, carried from entry, or transitive");
+        Derivation::derived(
+            fact,
+            "transitive",
+            vec![
+                self.subset_on_exit(origin1, middle, node),
+                self.subset_on_exit(middle, origin2, node),
+            ],
+        )
+    }
+
+    fn subset_on_entry(&self, origin1: &Origin, origin2: &Origin, node: &Node) -> Derivation {
+        let round = self.relations.subset_on_entry[&(origin1, origin2, node)];
+        let (predecessor, _) = self
+            .facts
+            .cfg_edge
+            .iter()
+            .find(|(n1, n2)| {
+                n2 == node
+                    && self
+                        .relations
+                        .subset_on_exit
+                        .get(&(origin1, origin2, n1))
+                        .is_some_and(|&premise_round| premise_round < round)
+            })
+            .expect("subsets on entry are carried from a predecessor");
+        Derivation::derived(
+            format!("subset_on_entry({}, {}, {})", origin1.0, origin2.0, node.0),
+            "carried over an edge, between live origins",
+            vec![
+                Self::cfg_edge(predecessor, node),
+                self.live_or_global(origin1, node),
+                self.live_or_global(origin2, node),
+                self.subset_on_exit(origin1, origin2, predecessor),
+            ],
+        )
+    }
+
+    fn origin_invalidated(&self, origin: &Origin, node: &Node) -> Derivation {
+        let fact = format!("origin_invalidated({}, {})", origin.0, node.0);
+        let round = self.relations.origin_invalidated[&(origin, node)];
+        let predecessors = self
+            .facts
+            .cfg_edge
+            .iter()
+            .filter(|(_, n2)| n2 == node)
+            .map(|(n1, _)| n1);
+
+        for predecessor in predecessors.clone() {
+            if self.cleared(origin, predecessor) {
+                continue;
+            }
+            if self.invalidated(origin, predecessor) {
+                return Derivation::derived(
+                    fact,
+                    "invalidated in a predecessor",
+                    vec![
+                        Self::cfg_edge(predecessor, node),
+                        Derivation::input(format!(
+                            "invalidate_origin({}, {})",
+                            origin.0, predecessor.0
+                        )),
+                        Self::not_cleared(origin, predecessor),
+                    ],
+                );
+            }
+            let is_earlier = self
+                .relations
+                .origin_invalidated
+                .get(&(origin, predecessor))
+                .is_some_and(|&premise_round| premise_round < round);
+            if is_earlier {
+                return Derivation::derived(
+                    fact,
+                    "carried over an edge, and not cleared",
+                    vec![
+                        Self::cfg_edge(predecessor, node),
+                        self.origin_invalidated(origin, predecessor),
+                        Self::not_cleared(origin, predecessor),
+                    ],
+                );
+            }
+        }
+
+        for predecessor in predecessors {
+            if self.cleared(origin, predecessor) {
+                continue;
+            }
+            let subset = self.relations.subset_on_entry.keys().find(|&&(o1, o2, n)| {
+                o2 == origin && n == predecessor && self.invalidated(o1, predecessor)
+            });
+            if let Some(&(invalidated, _, _)) = subset {
+                return Derivation::derived(
+                    fact,
+                    "invalidated through a subset in a predecessor",
+                    vec![
+                        Self::cfg_edge(predecessor, node),
+                        Derivation::input(format!(
+                            "invalidate_origin({}, {})",
+                            invalidated.0, predecessor.0
+                        )),
+                        self.subset_on_entry(invalidated, origin, predecessor),
+                        Self::not_cleared(origin, predecessor),
+                    ],
+                );
+            }
+        }
+        unreachable!("invalidated origins are invalidated in a predecessor")
+    }
+
+    // The error between placeholders derived from the placeholder loan of `origin1` flowing into
+    // `origin2`, where they're not subsets, e.g. where `origin2` is cleared.
+    fn placeholder_loan_error(
+        &self,
+        origin1: &Origin,
+        origin2: &Origin,
+        node: &Node,
+    ) -> Derivation {
+        let (_, loan, issued_at) = self
+            .facts
+            .loan_issued_at
+            .iter()
+            .find(|(o, l, _)| {
+                o == origin1
+                    && self
+                        .relations
+                        .origin_contains_loan_at
+                        .contains_key(&(origin2, l, node))
+            })
+            .expect("placeholder subset errors are subsets, or placeholder loans flowing");
+        Derivation::derived(
+            format!(
+                "placeholder_subset_error({}, {}, {})",
+                origin1.0, origin2.0, node.0
+            ),
+            "placeholder loan flowing into another placeholder",
+            vec![
+                self.origin_contains_loan_at(origin2, loan, node),
+                Self::loan_issued_at(origin1, loan, issued_at),
+                Derivation::input(format!("placeholder_origin({})", origin1.0)),
+                Derivation::input(format!("placeholder_origin({})", origin2.0)),
+                Derivation::input(format!(
+                    "!known_placeholder_subset_closure({}, {})",
+                    origin1.0, origin2.0
+                )),
+            ],
+        )
+    }
+
+    fn loan_issued_at(origin: &Origin, loan: &Loan, node: &Node) -> Derivation {
+        Derivation::input(format!(
+            "loan_issued_at({}, {}, {})",
+            origin.0, loan.0, node.0
+        ))
+    }
+
+    fn origin_contains_loan_at(&self, origin: &Origin, loan: &Loan, node: &Node) -> Derivation {
+        let fact = format!(
+            "origin_contains_loan_at({}, {}, {})",
+            origin.0, loan.0, node.0
+        );
+        let round = self.relations.origin_contains_loan_at[&(origin, loan, node)];
+        let is_earlier = |origin, node| {
+            self.relations
+                .origin_contains_loan_at
+                .get(&(origin, loan, node))
+                .is_some_and(|&premise_round| premise_round < round)
+        };
+        let issued_by = |origin: &Origin| {
+            self.facts
+                .loan_issued_at
+                .iter()
+                .find(|(o, l, _)| o == origin && l == loan)
+                .map(|(_, _, issued_at)| issued_at)
+        };
+
+        let predecessors = self
+            .facts
+            .cfg_edge
+            .iter()
+            .filter(|(_, n2)| n2 == node)
+            .map(|(n1, _)| n1);
+        for predecessor in predecessors {
+            let issued_at = match issued_by(origin) {
+                Some(issued_at) => issued_at,
+                None => break,
+            };
+            if issued_at == predecessor {
+                return Derivation::derived(
+                    fact,
+                    "issued in a predecessor",
+                    vec![
+                        Self::cfg_edge(predecessor, node),
+                        Self::loan_issued_at(origin, loan, predecessor),
+                    ],
+                );
+            }
+            if !is_earlier(origin, predecessor) {
+                continue;
+            }
+            if !self.cleared(origin, predecessor) {
+                return Derivation::derived(
+                    fact,
+                    "carried over an edge, and not cleared",
+                    vec![
+                        Self::cfg_edge(predecessor, node),
+                        Self::not_cleared(origin, predecessor),
+                        self.origin_contains_loan_at(origin, loan, predecessor),
+                        Self::loan_issued_at(origin, loan, issued_at),
+                    ],
+                );
+            }
+            if self.facts.placeholder_origin.contains(origin) {
+                return Derivation::derived(
+                    fact,
+                    "carried over an edge, in its placeholder",
+                    vec![
+                        Self::cfg_edge(predecessor, node),
+                        self.origin_contains_loan_at(origin, loan, predecessor),
+                        Self::loan_issued_at(origin, loan, issued_at),
+                        Derivation::input(format!("placeholder_origin({})", origin.0)),
+                    ],
+                );
+            }
+        }
+
+        let (issuer, _, issued_at) = self
+            .facts
+            .loan_issued_at
+            .iter()
+            .find(|(o, l, _)| {
+                l == loan
+                    && is_earlier(o, node)
+                    && self
+                        .relations
+                        .subset_on_entry
+                        .contains_key(&(o, origin, node))
+            })
+            .expect("loans are issued, carried over an edge, or flowing through a subset");
+        Derivation::derived(
+            fact,
+            "flowing through a subset",
+            vec![
+                self.origin_contains_loan_at(issuer, loan, node),
+                Self::loan_issued_at(issuer, loan, issued_at),
+                self.subset_on_entry(issuer, origin, node),
+            ],
+        )
+    }
+}
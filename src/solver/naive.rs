@@ -0,0 +1,485 @@
+//! A naive solver, evaluating each rule of `polonius.dl` literally, over sets of tuples, until
+//! none derives new tuples. It's slow, but simple enough to be checked against the datalog
+//! rules, and used as a reference for the optimized solver.
+//!
+//! The recursive relations remember the round in which each of their tuples was first derived:
+//! the premises of a tuple are from earlier rounds, which is what the derivation trees of the
+//! errors are built from.
+//!
+//! The rules which derived at least one tuple, whether new or not, are recorded, for the
+//! coverage of the rules by a program, see `rule_coverage`.
+
+use super::SolverOutput;
+use crate::fact_emitter::{Facts, Loan, Node, Origin};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The relations computed from the facts, with the round each tuple was derived in. Input facts
+/// are derived in round 0.
+pub(crate) struct Relations<'a> {
+    pub(crate) origin_live_on_entry: BTreeMap<(&'a Origin, &'a Node), usize>,
+    pub(crate) subset_on_exit: BTreeMap<(&'a Origin, &'a Origin, &'a Node), usize>,
+    pub(crate) subset_on_entry: BTreeMap<(&'a Origin, &'a Origin, &'a Node), usize>,
+    pub(crate) origin_invalidated: BTreeMap<(&'a Origin, &'a Node), usize>,
+    pub(crate) origin_contains_loan_at: BTreeMap<(&'a Origin, &'a Loan, &'a Node), usize>,
+    pub(crate) known_placeholder_subset_closure: BTreeSet<(&'a Origin, &'a Origin)>,
+
+    /// The rules which fired, named like in `RULES`.
+    pub(crate) fired: BTreeSet<&'static str>,
+}
+
+/// Computes the errors of the rules in `polonius.dl` over the given facts, naively.
+pub fn solve_naive(facts: &Facts) -> SolverOutput {
+    errors(facts, &compute(facts))
+}
+
+/// Computes the errors from the relations computed from the facts.
+pub(crate) fn errors(facts: &Facts, relations: &Relations<'_>) -> SolverOutput {
+    // invalidated_origin_accessed(O, N) :-
+    //   access_origin(O, N),
+    //   origin_invalidated(O, N).
+    let invalidated_origin_accessed: BTreeSet<_> = facts
+        .access_origin
+        .iter()
+        .filter(|(o, n)| relations.origin_invalidated.contains_key(&(o, n)))
+        .cloned()
+        .collect();
+
+    // placeholder_subset_error(O1, O2, N) :-
+    //   subset_on_exit(O1, O2, N),
+    //   placeholder_origin(O1),
+    //   placeholder_origin(O2),
+    //   O1 != O2,
+    //   !known_placeholder_subset_closure(O1, O2).
+    let is_error = |o1: &Origin, o2: &Origin| {
+        facts.placeholder_origin.contains(o1)
+            && facts.placeholder_origin.contains(o2)
+            && o1 != o2
+            && !relations
+                .known_placeholder_subset_closure
+                .contains(&(o1, o2))
+    };
+    let mut placeholder_subset_error: BTreeSet<_> = relations
+        .subset_on_exit
+        .keys()
+        .filter(|&&(o1, o2, _)| is_error(o1, o2))
+        .map(|&(o1, o2, n)| (o1.clone(), o2.clone(), n.clone()))
+        .collect();
+
+    // placeholder_subset_error(O1, O2, N) :-
+    //   origin_contains_loan_at(O2, L, N),
+    //   loan_issued_at(O1, L, _),
+    //   placeholder_origin(O1),
+    //   placeholder_origin(O2),
+    //   O1 != O2,
+    //   !known_placeholder_subset_closure(O1, O2).
+    for &(o2, l, n) in relations.origin_contains_loan_at.keys() {
+        for (o1, loan, _) in &facts.loan_issued_at {
+            if loan == l && is_error(o1, o2) {
+                placeholder_subset_error.insert((o1.clone(), o2.clone(), n.clone()));
+            }
+        }
+    }
+
+    // loan_escapes_into_placeholder(L, O, N) :-
+    //   subset_on_exit(L, O, N),
+    //   mark_as_loan_origin(L),
+    //   placeholder_origin(O).
+    let loan_escapes_into_placeholder = relations
+        .subset_on_exit
+        .keys()
+        .filter(|&&(l, o, _)| {
+            facts.mark_as_loan_origin.contains(l) && facts.placeholder_origin.contains(o)
+        })
+        .map(|&(l, o, n)| (l.clone(), o.clone(), n.clone()))
+        .collect();
+
+    let origin_contains_loan_at = relations
+        .origin_contains_loan_at
+        .keys()
+        .map(|&(o, l, n)| (o.clone(), l.clone(), n.clone()))
+        .collect();
+
+    SolverOutput {
+        invalidated_origin_accessed: invalidated_origin_accessed.into_iter().collect(),
+        placeholder_subset_error: placeholder_subset_error.into_iter().collect(),
+        loan_escapes_into_placeholder,
+        origin_contains_loan_at,
+    }
+}
+
+/// Computes the relations the errors are derived from.
+#[tracing::instrument(skip_all)]
+pub(crate) fn compute(facts: &Facts) -> Relations<'_> {
+    let invalidate_origin: BTreeSet<_> = facts
+        .invalidate_origin
+        .iter()
+        .map(|(o, n)| (o, n))
+        .collect();
+    let clear_origin: BTreeSet<_> = facts.clear_origin.iter().map(|(o, n)| (o, n)).collect();
+    let cleared = |origin: &Origin, node: &Node| clear_origin.contains(&(origin, node));
+    let is_loan_or_placeholder = |origin: &Origin| {
+        facts.mark_as_loan_origin.contains(origin) || facts.placeholder_origin.contains(origin)
+    };
+    let mut fired = BTreeSet::new();
+
+    // origin_live_on_entry(O, N) :-
+    //   access_origin(O, N).
+    let mut origin_live_on_entry: BTreeMap<_, _> = facts
+        .access_origin
+        .iter()
+        .map(|(o, n)| ((o, n), 0))
+        .collect();
+    record(
+        &mut fired,
+        "origin_live_on_entry: accessed",
+        &facts.access_origin,
+        0,
+    );
+    for round in 1.. {
+        // origin_live_on_entry(O, N1) :-
+        //   cfg_edge(N1, N2),
+        //   !clear_origin(O, N1),
+        //   origin_live_on_entry(O, N2).
+        let mut derived = Vec::new();
+        for (n1, n2) in &facts.cfg_edge {
+            for &(o, n) in origin_live_on_entry.keys() {
+                if n == n2 && !cleared(o, n1) {
+                    derived.push((o, n1));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "origin_live_on_entry: live in a successor, and not cleared",
+            &derived,
+            0,
+        );
+        tracing::trace!(
+            relation = "origin_live_on_entry",
+            round,
+            derived = derived.len(),
+            "iteration"
+        );
+        if !extend(&mut origin_live_on_entry, derived, round) {
+            break;
+        }
+    }
+
+    // subset_on_exit(O1, O2, N) :-
+    //   introduce_subset(O1, O2, N).
+    let mut subset_on_exit: BTreeMap<_, _> = facts
+        .introduce_subset
+        .iter()
+        .map(|(o1, o2, n)| ((o1, o2, n), 0))
+        .collect();
+    record(
+        &mut fired,
+        "subset_on_exit: introduced",
+        &facts.introduce_subset,
+        0,
+    );
+    let mut subset_on_entry = BTreeMap::new();
+    for round in 1.. {
+        let mut derived_on_exit = Vec::new();
+        let mut derived_on_entry = Vec::new();
+
+        // subset_on_exit(O1, O2, N) :-
+        //   subset_on_entry(O1, O2, N),
+        //   !clear_origin(O1, N),
+        //   !clear_origin(O2, N).
+        for &(o1, o2, n) in subset_on_entry.keys() {
+            if !cleared(o1, n) && !cleared(o2, n) {
+                derived_on_exit.push((o1, o2, n));
+            }
+        }
+        record(
+            &mut fired,
+            "subset_on_exit: on entry, and not cleared",
+            &derived_on_exit,
+            0,
+        );
+
+        // subset_on_exit(O1, O3, N1) :-
+        //   subset_on_exit(O1, O2, N1),
+        //   subset_on_exit(O2, O3, N1).
+        let len = derived_on_exit.len();
+        for &(o1, o2, n1) in subset_on_exit.keys() {
+            for &(candidate, o3, n) in subset_on_exit.keys() {
+                if candidate == o2 && n == n1 {
+                    derived_on_exit.push((o1, o3, n1));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "subset_on_exit: transitive",
+            &derived_on_exit,
+            len,
+        );
+
+        // subset_on_entry(O1, O2, N2) :-
+        //   cfg_edge(N1, N2),
+        //   (origin_live_on_entry(O1, N2); mark_as_loan_origin(O1); placeholder_origin(O1)),
+        //   (origin_live_on_entry(O2, N2); mark_as_loan_origin(O2); placeholder_origin(O2)),
+        //   subset_on_exit(O1, O2, N1).
+        let live = |origin, node| {
+            origin_live_on_entry.contains_key(&(origin, node)) || is_loan_or_placeholder(origin)
+        };
+        for (n1, n2) in &facts.cfg_edge {
+            for &(o1, o2, n) in subset_on_exit.keys() {
+                if n == n1 && live(o1, n2) && live(o2, n2) {
+                    derived_on_entry.push((o1, o2, n2));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "subset_on_entry: carried over an edge, between live origins",
+            &derived_on_entry,
+            0,
+        );
+
+        tracing::trace!(
+            relation = "subset",
+            round,
+            derived = derived_on_exit.len() + derived_on_entry.len(),
+            "iteration"
+        );
+        let exit_changed = extend(&mut subset_on_exit, derived_on_exit, round);
+        let entry_changed = extend(&mut subset_on_entry, derived_on_entry, round);
+        if !exit_changed && !entry_changed {
+            break;
+        }
+    }
+
+    let mut origin_invalidated = BTreeMap::new();
+    for round in 1.. {
+        // origin_invalidated(O, N2) :-
+        //   cfg_edge(N1, N2),
+        //   !clear_origin(O, N1),
+        //   (invalidate_origin(O, N1); origin_invalidated(O, N1)).
+        //
+        // Each side of the disjunction is recorded as a rule of its own.
+        let mut derived = Vec::new();
+        for (n1, n2) in &facts.cfg_edge {
+            for &(o, n) in &invalidate_origin {
+                if n == n1 && !cleared(o, n1) {
+                    derived.push((o, n2));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "origin_invalidated: invalidated in a predecessor",
+            &derived,
+            0,
+        );
+        let len = derived.len();
+        for (n1, n2) in &facts.cfg_edge {
+            for &(o, n) in origin_invalidated.keys() {
+                if n == n1 && !cleared(o, n1) {
+                    derived.push((o, n2));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "origin_invalidated: carried over an edge, and not cleared",
+            &derived,
+            len,
+        );
+
+        // origin_invalidated(O2, N2) :-
+        //   cfg_edge(N1, N2),
+        //   !clear_origin(O2, N1),
+        //   subset_on_entry(O1, O2, N1),
+        //   invalidate_origin(O1, N1).
+        let len = derived.len();
+        for (n1, n2) in &facts.cfg_edge {
+            for &(o1, o2, n) in subset_on_entry.keys() {
+                if n == n1 && !cleared(o2, n1) && invalidate_origin.contains(&(o1, n1)) {
+                    derived.push((o2, n2));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "origin_invalidated: invalidated through a subset in a predecessor",
+            &derived,
+            len,
+        );
+        tracing::trace!(
+            relation = "origin_invalidated",
+            round,
+            derived = derived.len(),
+            "iteration"
+        );
+        if !extend(&mut origin_invalidated, derived, round) {
+            break;
+        }
+    }
+
+    let mut origin_contains_loan_at = BTreeMap::new();
+    for round in 1.. {
+        // origin_contains_loan_at(O, L, N2) :-
+        //   cfg_edge(N1, N2),
+        //   loan_issued_at(O, L, N1).
+        let mut derived = Vec::new();
+        for (n1, n2) in &facts.cfg_edge {
+            for (o, l, n) in &facts.loan_issued_at {
+                if n == n1 {
+                    derived.push((o, l, n2));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "origin_contains_loan_at: issued in a predecessor",
+            &derived,
+            0,
+        );
+
+        // origin_contains_loan_at(O, L, N2) :-
+        //   cfg_edge(N1, N2),
+        //   !clear_origin(O, N1),
+        //   origin_contains_loan_at(O, L, N1),
+        //   loan_issued_at(O, L, _).
+        let len = derived.len();
+        for (n1, n2) in &facts.cfg_edge {
+            for &(o, l, n) in origin_contains_loan_at.keys() {
+                let is_issued = facts
+                    .loan_issued_at
+                    .iter()
+                    .any(|(issuer, loan, _)| issuer == o && loan == l);
+                if n == n1 && !cleared(o, n1) && is_issued {
+                    derived.push((o, l, n2));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "origin_contains_loan_at: carried over an edge, and not cleared",
+            &derived,
+            len,
+        );
+
+        // origin_contains_loan_at(O, L, N2) :-
+        //   cfg_edge(N1, N2),
+        //   origin_contains_loan_at(O, L, N1),
+        //   loan_issued_at(O, L, _),
+        //   placeholder_origin(O).
+        let len = derived.len();
+        for (n1, n2) in &facts.cfg_edge {
+            for &(o, l, n) in origin_contains_loan_at.keys() {
+                let is_issued = facts
+                    .loan_issued_at
+                    .iter()
+                    .any(|(issuer, loan, _)| issuer == o && loan == l);
+                if n == n1 && is_issued && facts.placeholder_origin.contains(o) {
+                    derived.push((o, l, n2));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "origin_contains_loan_at: carried over an edge, in its placeholder",
+            &derived,
+            len,
+        );
+
+        // origin_contains_loan_at(O2, L, N) :-
+        //   origin_contains_loan_at(O1, L, N),
+        //   loan_issued_at(O1, L, _),
+        //   subset_on_entry(O1, O2, N).
+        let len = derived.len();
+        for (o1, l, _) in &facts.loan_issued_at {
+            for &(candidate, o2, n) in subset_on_entry.keys() {
+                if candidate == o1 && origin_contains_loan_at.contains_key(&(o1, l, n)) {
+                    derived.push((o2, l, n));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "origin_contains_loan_at: flowing through a subset",
+            &derived,
+            len,
+        );
+
+        tracing::trace!(
+            relation = "origin_contains_loan_at",
+            round,
+            derived = derived.len(),
+            "iteration"
+        );
+        if !extend(&mut origin_contains_loan_at, derived, round) {
+            break;
+        }
+    }
+
+    // known_placeholder_subset_closure(O1, O2) :-
+    //   known_placeholder_subset(O1, O2).
+    let mut known_placeholder_subset_closure: BTreeSet<_> = facts
+        .known_placeholder_subset
+        .iter()
+        .map(|(o1, o2)| (o1, o2))
+        .collect();
+    record(
+        &mut fired,
+        "known_placeholder_subset_closure: known",
+        &facts.known_placeholder_subset,
+        0,
+    );
+    loop {
+        // known_placeholder_subset_closure(O1, O3) :-
+        //   known_placeholder_subset_closure(O1, O2),
+        //   known_placeholder_subset(O2, O3).
+        let mut derived = Vec::new();
+        for &(o1, o2) in &known_placeholder_subset_closure {
+            for (candidate, o3) in &facts.known_placeholder_subset {
+                if candidate == o2 {
+                    derived.push((o1, o3));
+                }
+            }
+        }
+        record(
+            &mut fired,
+            "known_placeholder_subset_closure: transitive",
+            &derived,
+            0,
+        );
+        let len = known_placeholder_subset_closure.len();
+        known_placeholder_subset_closure.extend(derived);
+        if known_placeholder_subset_closure.len() == len {
+            break;
+        }
+    }
+
+    Relations {
+        origin_live_on_entry,
+        subset_on_exit,
+        subset_on_entry,
+        origin_invalidated,
+        origin_contains_loan_at,
+        known_placeholder_subset_closure,
+        fired,
+    }
+}
+
+// Records the rule as fired when it derived tuples, i.e. when there are derived tuples past the
+// `len` there were before evaluating it.
+fn record<T>(fired: &mut BTreeSet<&'static str>, rule: &'static str, derived: &[T], len: usize) {
+    if derived.len() > len {
+        fired.insert(rule);
+    }
+}
+
+// Adds the tuples derived in the given round to the relation, and returns whether any of them
+// is new.
+fn extend<T: Ord>(relation: &mut BTreeMap<T, usize>, derived: Vec<T>, round: usize) -> bool {
+    let len = relation.len();
+    for tuple in derived {
+        relation.entry(tuple).or_insert(round);
+    }
+    relation.len() != len
+}
@@ -0,0 +1,432 @@
+use super::*;
+use crate::fact_emitter::test::expect_facts;
+use crate::fact_parser::read_facts;
+use insta::{assert_debug_snapshot, assert_display_snapshot};
+
+// Solves the facts emitted for the program, with the readable node names of the tests, and
+// checks the naive solver agrees.
+fn expect_solved(program: &str) -> SolverOutput {
+    let facts = expect_facts(program);
+    let output = solve(&facts);
+    assert_eq!(solve_naive(&facts), output);
+    output
+}
+
+#[test]
+fn errors_match_souffle_on_test_suite() {
+    // The errors computed by Soufflé are blessed in each test's directory
+    for entry in std::fs::read_dir("tests").unwrap() {
+        let path = entry.unwrap().path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let program = std::fs::read_to_string(path.join("program.txt")).unwrap();
+        let facts = read_facts(&program).unwrap();
+        let expected =
+            std::fs::read_to_string(path.join("invalidated_origin_accessed.csv")).unwrap();
+
+        let errors: String = solve(&facts)
+            .invalidated_origin_accessed
+            .iter()
+            .map(|(origin, node)| format!("{}\t{}\n", origin.0, node.0))
+            .collect();
+        assert_eq!(errors, expected, "unexpected errors in {}", path.display());
+    }
+}
+
+#[test]
+fn naive_solver_matches_on_test_suite() {
+    for entry in std::fs::read_dir("tests").unwrap() {
+        let path = entry.unwrap().path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let program = std::fs::read_to_string(path.join("program.txt")).unwrap();
+        let facts = read_facts(&program).unwrap();
+        assert_eq!(
+            solve_naive(&facts),
+            solve(&facts),
+            "the solvers disagree on {}",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn invalidated_loan_accessed() {
+    let program = "
+        let p: i32;
+        let x: &'x i32;
+
+        bb0: {
+            p = 22;
+            x = &'L_p p;
+            p = 33;
+            use(move x);
+        }
+    ";
+    let output = expect_solved(program);
+    assert_debug_snapshot!(output, @r###"
+    SolverOutput {
+        invalidated_origin_accessed: [
+            (
+                "'x",
+                "d",
+            ),
+        ],
+        placeholder_subset_error: [],
+        loan_escapes_into_placeholder: [],
+        origin_contains_loan_at: [
+            (
+                "'L_p",
+                "bw0",
+                "c",
+            ),
+            (
+                "'L_p",
+                "bw0",
+                "d",
+            ),
+            (
+                "'x",
+                "bw0",
+                "c",
+            ),
+            (
+                "'x",
+                "bw0",
+                "d",
+            ),
+        ],
+    }
+    "###);
+}
+
+#[test]
+fn undeclared_placeholder_subset() {
+    let program = "
+        placeholder 'a, 'b;
+
+        let x: &'a i32;
+        let y: &'b i32;
+
+        bb0: {
+            y = copy x;
+        }
+    ";
+    let output = expect_solved(program);
+    assert_debug_snapshot!(output.placeholder_subset_error, @r###"
+    [
+        (
+            "'a",
+            "'b",
+            "a",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn declared_placeholder_subsets() {
+    // Declared subsets, and their transitive closure, hold
+    let program = "
+        placeholder 'a, 'b, 'c where 'a: 'b, 'b: 'c;
+
+        let x: &'a i32;
+        let y: &'c i32;
+
+        bb0: {
+            y = copy x;
+        }
+    ";
+    assert!(expect_solved(program).placeholder_subset_error.is_empty());
+
+    // But not their reverse
+    let program = "
+        placeholder 'a, 'b where 'a: 'b;
+
+        let x: &'a i32;
+        let y: &'b i32;
+
+        bb0: {
+            x = copy y;
+        }
+    ";
+    assert_debug_snapshot!(expect_solved(program).placeholder_subset_error, @r###"
+    [
+        (
+            "'b",
+            "'a",
+            "a",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn static_outlives_placeholders() {
+    let program = "
+        placeholder 'a;
+
+        let s: &'static i32;
+        let x: &'a i32;
+
+        bb0: {
+            x = copy s;
+            s = copy x;
+        }
+    ";
+    assert_debug_snapshot!(expect_solved(program).placeholder_subset_error, @r###"
+    [
+        (
+            "'a",
+            "'static",
+            "b",
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn explain_invalidated_loan_accessed() {
+    let program = "
+        let p: i32;
+        let x: &'x i32;
+
+        bb0: {
+            p = 22;
+            x = &'L_p p;
+            p = 33;
+            use(move x);
+        }
+    ";
+    let derivations = explain(&expect_facts(program));
+    assert_eq!(derivations.len(), 1);
+    assert_display_snapshot!(derivations[0], @r###"
+    invalidated_origin_accessed('x, d) [accessed while invalidated]
+    ├── access_origin('x, d)
+    └── origin_invalidated('x, d) [invalidated through a subset in a predecessor]
+        ├── cfg_edge(c, d)
+        ├── invalidate_origin('L_p, c)
+        ├── subset_on_entry('L_p, 'x, c) [carried over an edge, between live origins]
+        │   ├── cfg_edge(b, c)
+        │   ├── mark_as_loan_origin('L_p)
+        │   ├── origin_live_on_entry('x, c) [live in a successor, and not cleared]
+        │   │   ├── cfg_edge(c, d)
+        │   │   ├── !clear_origin('x, c)
+        │   │   └── origin_live_on_entry('x, d) [accessed]
+        │   │       └── access_origin('x, d)
+        │   └── subset_on_exit('L_p, 'x, b) [introduced]
+        │       └── introduce_subset('L_p, 'x, b)
+        └── !clear_origin('x, c)
+    "###);
+}
+
+#[test]
+fn explain_as_json() {
+    let program = "
+        placeholder 'a, 'b;
+        let x: &'a i32;
+        let y: &'b i32;
+
+        bb0: {
+            y = copy x;
+        }
+    ";
+    let derivations = explain(&expect_facts(program));
+    assert_display_snapshot!(derivations[0].to_json(), @r###"
+    {"fact":"placeholder_subset_error('a, 'b, a)","rule":"unknown subset between placeholders","premises":[{"fact":"subset_on_exit('a, 'b, a)","rule":"introduced","premises":[{"fact":"introduce_subset('a, 'b, a)","rule":null,"premises":[]}]},{"fact":"placeholder_origin('a)","rule":null,"premises":[]},{"fact":"placeholder_origin('b)","rule":null,"premises":[]},{"fact":"!known_placeholder_subset_closure('a, 'b)","rule":null,"premises":[]}]}
+    "###);
+}
+
+#[test]
+fn placeholder_loan_flowing_into_another_placeholder() {
+    // The data of the caller `'a` refers to flows into `'b`, in the placeholder loan of `'a`:
+    // once `y` is overwritten, `'b` is cleared and no longer a superset of `'a`, but it still
+    // contains the loan on entry
+    let program = "
+        placeholder 'a, 'b;
+        let x: &'a i32;
+        let y: &'b i32;
+        let z: &'b i32;
+
+        bb0: {
+            y = copy x;
+            y = copy z;
+        }
+    ";
+    let output = expect_solved(program);
+    assert_debug_snapshot!(output.placeholder_subset_error, @r###"
+    [
+        (
+            "'a",
+            "'b",
+            "a",
+        ),
+        (
+            "'a",
+            "'b",
+            "b",
+        ),
+    ]
+    "###);
+
+    let derivations = explain(&expect_facts(program));
+    assert_eq!(derivations.len(), 2);
+    assert_display_snapshot!(derivations[1], @r###"
+    placeholder_subset_error('a, 'b, b) [placeholder loan flowing into another placeholder]
+    ├── origin_contains_loan_at('b, ph0, b) [flowing through a subset]
+    │   ├── origin_contains_loan_at('a, ph0, b) [issued in a predecessor]
+    │   │   ├── cfg_edge(a, b)
+    │   │   └── loan_issued_at('a, ph0, a)
+    │   ├── loan_issued_at('a, ph0, a)
+    │   └── subset_on_entry('a, 'b, b) [carried over an edge, between live origins]
+    │       ├── cfg_edge(a, b)
+    │       ├── placeholder_origin('a)
+    │       ├── origin_live_on_entry('b, b) [accessed]
+    │       │   └── access_origin('b, b)
+    │       └── subset_on_exit('a, 'b, a) [introduced]
+    │           └── introduce_subset('a, 'b, a)
+    ├── loan_issued_at('a, ph0, a)
+    ├── placeholder_origin('a)
+    ├── placeholder_origin('b)
+    └── !known_placeholder_subset_closure('a, 'b)
+    "###);
+}
+
+#[test]
+fn loans_contained_in_origins() {
+    // The loan of `p` flows into `'x`, and is in `'L_p` until `'L_p` borrows `q` instead
+    let program = "
+        let p: i32;
+        let q: i32;
+        let x: &'x i32;
+
+        bb0: {
+            x = &'L_p p;
+            use(copy x);
+            x = &'L_p q;
+            use(copy x);
+        }
+    ";
+    let output = expect_solved(program);
+    assert_debug_snapshot!(output.origin_contains_loan_at, @r###"
+    [
+        (
+            "'L_p",
+            "bw0",
+            "b",
+        ),
+        (
+            "'L_p",
+            "bw0",
+            "c",
+        ),
+        (
+            "'L_p",
+            "bw1",
+            "d",
+        ),
+        (
+            "'x",
+            "bw0",
+            "b",
+        ),
+        (
+            "'x",
+            "bw1",
+            "d",
+        ),
+    ]
+    "###);
+    assert_debug_snapshot!(output.loans_in(&"'x".into(), &"d".into()), @r###"
+    [
+        "bw1",
+    ]
+    "###);
+}
+
+#[test]
+fn rules_fired_by_a_program() {
+    let program = "
+        let p: i32;
+        let x: &'x i32;
+
+        bb0: {
+            p = 22;
+            x = &'L_p p;
+            p = 33;
+            use(move x);
+        }
+    ";
+    let fired = rule_coverage(&expect_facts(program));
+    assert!(fired.iter().all(|rule| RULES.contains(rule)));
+    assert_debug_snapshot!(fired, @r###"
+    {
+        "invalidated_origin_accessed: accessed while invalidated",
+        "origin_contains_loan_at: carried over an edge, and not cleared",
+        "origin_contains_loan_at: flowing through a subset",
+        "origin_contains_loan_at: issued in a predecessor",
+        "origin_invalidated: invalidated in a predecessor",
+        "origin_invalidated: invalidated through a subset in a predecessor",
+        "origin_live_on_entry: accessed",
+        "origin_live_on_entry: live in a successor, and not cleared",
+        "subset_on_entry: carried over an edge, between live origins",
+        "subset_on_exit: introduced",
+        "subset_on_exit: on entry, and not cleared",
+    }
+    "###);
+}
+
+#[test]
+fn coverage_of_the_test_suite() {
+    let mut matrix = CoverageMatrix::default();
+    let mut entries: Vec<_> = std::fs::read_dir("tests")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let program = std::fs::read_to_string(path.join("program.txt")).unwrap();
+        let facts = read_facts(&program).unwrap();
+        let name = path.file_name().unwrap().to_string_lossy();
+        matrix.add(name, rule_coverage(&facts));
+    }
+    assert_display_snapshot!(matrix, @r###"
+  1: canonical-liveness
+  2: canonical-liveness-err
+  3: diamond-ref-mod
+  4: example-a
+  5: issue-47680
+  6: killing-and-murder
+  7: killing-and-murder-err
+  8: self-invalidation-loop
+  9: self-invalidation-loop-shared
+ 10: vec-temp
+
+xxxxxxxxxx  10/10 origin_live_on_entry: accessed
+xxxxxxxxxx  10/10 origin_live_on_entry: live in a successor, and not cleared
+xxxxxxxxxx  10/10 subset_on_exit: on entry, and not cleared
+xxxxxxxxxx  10/10 subset_on_exit: introduced
+....xxxxxx   6/10 subset_on_exit: transitive
+xxxxxxxxxx  10/10 subset_on_entry: carried over an edge, between live origins
+xxxx.xxxxx   9/10 origin_invalidated: invalidated in a predecessor
+x.x..x.xxx   6/10 origin_invalidated: carried over an edge, and not cleared
+.x.x..xx.x   5/10 origin_invalidated: invalidated through a subset in a predecessor
+..........   0/10 origin_contains_loan_at: issued in a predecessor
+..........   0/10 origin_contains_loan_at: carried over an edge, and not cleared
+..........   0/10 origin_contains_loan_at: carried over an edge, in its placeholder
+..........   0/10 origin_contains_loan_at: flowing through a subset
+.x.x..xx.x   5/10 invalidated_origin_accessed: accessed while invalidated
+..........   0/10 known_placeholder_subset_closure: known
+..........   0/10 known_placeholder_subset_closure: transitive
+..........   0/10 placeholder_subset_error: unknown subset between placeholders
+..........   0/10 placeholder_subset_error: placeholder loan flowing into another placeholder
+..........   0/10 loan_escapes_into_placeholder: loan flowing into a placeholder
+
+10/19 rule(s) fired, 9 untested
+"###);
+}
@@ -0,0 +1,275 @@
+//! Solving facts with Soufflé, the reference implementation of the rules in `polonius.dl`,
+//! instead of the in-crate solver: the facts are written as Soufflé inputs, a locally
+//! installed `souffle` evaluates the bundled rules on them, and the relations it outputs are
+//! read back, so that its errors are reported as diagnostics like the in-crate solver's.
+//!
+//! The rules are bundled in the crate, so that they don't have to be found next to the binary.
+//! Reporting the errors of each invalidation solves the facts again, see `diagnose`, so
+//! Soufflé runs once more for each invalidation.
+//!
+//! Soufflé's provenance explains the errors with proof trees, which are read back as the
+//! derivation trees of the in-crate solver's `explain`, see `explain_with_souffle`.
+
+use crate::convert::{write_facts_in, FactFormat};
+use crate::diagnostics::{diagnose_with_solver, Diagnostic};
+use crate::fact_emitter::Facts;
+use crate::json::{parse_json, Json};
+use crate::solver::{Derivation, SolverOutput};
+use eyre::WrapErr;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[cfg(test)]
+mod test;
+
+const RULES: &str = include_str!("polonius.dl");
+
+/// Solves the facts with Soufflé, writing its inputs and outputs in the work directory.
+pub fn solve_with_souffle(facts: &Facts, work_dir: &Path) -> eyre::Result<SolverOutput> {
+    let output_dir = prepare_work_dir(facts, work_dir)?;
+    let output = souffle(work_dir, &[])
+        .output()
+        .wrap_err("failed to run soufflé, is it installed?")?;
+    if !output.status.success() {
+        eyre::bail!(
+            "soufflé failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    read_souffle_output(&output_dir)
+}
+
+/// Solves the facts with Soufflé, and returns the derivation tree of each error like `explain`,
+/// translated from the proof trees Soufflé's provenance (`-t explain`) gives for them.
+///
+/// Soufflé numbers the rules of each relation in the order of `polonius.dl`, the disjunctions
+/// being split into a rule per side, which is how they're named like the in-crate solver's.
+pub fn explain_with_souffle(facts: &Facts, work_dir: &Path) -> eyre::Result<Vec<Derivation>> {
+    let errors = solve_with_souffle(facts, work_dir)?;
+
+    // The proofs are queried on the explain shell's input, as JSON, deep enough to reach the
+    // input facts rather than stopping at `subproof` leaves
+    let mut commands = String::from("setdepth 10000\nformat json\n");
+    let quoted = |values: &[&str]| {
+        let values: Vec<_> = values
+            .iter()
+            .map(|value| format!("\"{}\"", value))
+            .collect();
+        values.join(", ")
+    };
+    for (origin, node) in &errors.invalidated_origin_accessed {
+        commands += &format!(
+            "explain invalidated_origin_accessed({})\n",
+            quoted(&[&origin.0, &node.0])
+        );
+    }
+    for (origin1, origin2, node) in &errors.placeholder_subset_error {
+        commands += &format!(
+            "explain placeholder_subset_error({})\n",
+            quoted(&[&origin1.0, &origin2.0, &node.0])
+        );
+    }
+    for (loan, placeholder, node) in &errors.loan_escapes_into_placeholder {
+        commands += &format!(
+            "explain loan_escapes_into_placeholder({})\n",
+            quoted(&[&loan.0, &placeholder.0, &node.0])
+        );
+    }
+    commands += "exit\n";
+
+    let mut child = souffle(work_dir, &["-t", "explain"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .wrap_err("failed to run soufflé, is it installed?")?;
+    child
+        .stdin
+        .take()
+        .expect("the input is piped")
+        .write_all(commands.as_bytes())
+        .wrap_err("failed to query soufflé's proofs")?;
+    let output = child.wait_with_output().wrap_err("failed to run soufflé")?;
+    if !output.status.success() {
+        eyre::bail!(
+            "soufflé failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    read_proofs(&String::from_utf8_lossy(&output.stdout))
+}
+
+// Writes the facts and the rules in the work directory, for Soufflé to solve, and returns the
+// directory of its outputs.
+fn prepare_work_dir(facts: &Facts, work_dir: &Path) -> eyre::Result<PathBuf> {
+    let (facts_dir, output_dir) = (work_dir.join("facts"), work_dir.join("output"));
+    for dir in &[&facts_dir, &output_dir] {
+        if dir.exists() {
+            std::fs::remove_dir_all(dir)
+                .wrap_err_with(|| format!("failed to remove `{}`", dir.display()))?;
+        }
+    }
+    write_facts_in(facts, FactFormat::Souffle, &facts_dir)?;
+    std::fs::create_dir_all(&output_dir)
+        .wrap_err_with(|| format!("failed to create `{}`", output_dir.display()))?;
+    let rules_path = work_dir.join("polonius.dl");
+    std::fs::write(&rules_path, RULES)
+        .wrap_err_with(|| format!("failed to write `{}`", rules_path.display()))?;
+    Ok(output_dir)
+}
+
+// The Soufflé command solving the work directory prepared by `prepare_work_dir`.
+fn souffle(work_dir: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new("souffle");
+    command
+        .args(args)
+        .arg(work_dir.join("polonius.dl"))
+        .arg("-F")
+        .arg(work_dir.join("facts"))
+        .arg("-D")
+        .arg(work_dir.join("output"));
+    command
+}
+
+/// Returns the diagnostics for the errors of the facts like `diagnose`, with the errors computed
+/// by Soufflé, see `solve_with_souffle`.
+pub fn diagnose_with_souffle(facts: &Facts, work_dir: &Path) -> eyre::Result<Vec<Diagnostic>> {
+    diagnose_with_solver(facts, &mut |facts| solve_with_souffle(facts, work_dir))
+}
+
+// Reads the output relations Soufflé wrote in the directory, as tab-separated `.csv` files.
+fn read_souffle_output(dir: &Path) -> eyre::Result<SolverOutput> {
+    let rows = |name: &str, arity: usize| -> eyre::Result<Vec<Vec<String>>> {
+        let path = dir.join(name).with_extension("csv");
+        let contents = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+        let mut rows = Vec::new();
+        for (idx, line) in contents.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let row: Vec<String> = line.split('\t').map(str::to_string).collect();
+            if row.len() != arity {
+                eyre::bail!(
+                    "expected {} columns at line {} of `{}`, found {}",
+                    arity,
+                    idx + 1,
+                    path.display(),
+                    row.len()
+                );
+            }
+            rows.push(row);
+        }
+        rows.sort();
+        Ok(rows)
+    };
+
+    let mut output = SolverOutput::default();
+    for row in rows("invalidated_origin_accessed", 2)? {
+        output
+            .invalidated_origin_accessed
+            .push((row[0].as_str().into(), row[1].as_str().into()));
+    }
+    for row in rows("placeholder_subset_error", 3)? {
+        output.placeholder_subset_error.push((
+            row[0].as_str().into(),
+            row[1].as_str().into(),
+            row[2].as_str().into(),
+        ));
+    }
+    for row in rows("loan_escapes_into_placeholder", 3)? {
+        output.loan_escapes_into_placeholder.push((
+            row[0].as_str().into(),
+            row[1].as_str().into(),
+            row[2].as_str().into(),
+        ));
+    }
+    for row in rows("origin_contains_loan_at", 3)? {
+        output.origin_contains_loan_at.push((
+            row[0].as_str().into(),
+            row[1].as_str().into(),
+            row[2].as_str().into(),
+        ));
+    }
+    Ok(output)
+}
+
+// Reads the proof trees printed in JSON by Soufflé's explain shell, between its prompts, as
+// derivation trees.
+fn read_proofs(output: &str) -> eyre::Result<Vec<Derivation>> {
+    let mut derivations = Vec::new();
+    let mut rest = output;
+    while let Some(start) = rest.find("{ \"proof\"") {
+        let len = json_object_len(&rest[start..])
+            .ok_or_else(|| eyre::eyre!("unterminated proof in soufflé's output"))?;
+        let proof = parse_json(&rest[start..start + len])?;
+        let tree = proof
+            .get("proof")
+            .ok_or_else(|| eyre::eyre!("expected a proof in soufflé's output"))?;
+        derivations.push(proof_derivation(tree)?);
+        rest = &rest[start + len..];
+    }
+    Ok(derivations)
+}
+
+// The length of the JSON object the text starts with, up to its closing brace.
+fn json_object_len(text: &str) -> Option<usize> {
+    let (mut depth, mut in_string, mut escaped) = (0, false, false);
+    for (idx, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// A node of a proof tree: an axiom, i.e. an input fact, or a fact derived by the numbered rule
+// of its relation from the facts of its children.
+fn proof_derivation(proof: &Json) -> eyre::Result<Derivation> {
+    if let Some(axiom) = proof.get("axiom").and_then(Json::as_str) {
+        return Ok(Derivation::input(fact_text(axiom)));
+    }
+    let (premises, label) = match (proof.get("premises"), proof.get("rule-number")) {
+        (Some(Json::String(premises)), Some(Json::String(label))) => (premises, label),
+        _ => eyre::bail!("unexpected proof in soufflé's output: {}", proof),
+    };
+    let relation = premises.split('(').next().unwrap_or(premises).trim();
+    let number: usize = label
+        .trim_matches(|c| c == '(' || c == ')')
+        .trim_start_matches('R')
+        .parse()
+        .wrap_err_with(|| format!("unexpected rule number `{}` in soufflé's output", label))?;
+    let mut rules = crate::solver::RULES
+        .iter()
+        .filter_map(|rule| rule.strip_prefix(relation)?.strip_prefix(": "));
+    let rule = number
+        .checked_sub(1)
+        .and_then(|idx| rules.nth(idx))
+        .ok_or_else(|| eyre::eyre!("unknown rule {} of `{}`", label, relation))?;
+    let children = match proof.get("children") {
+        Some(children) => children
+            .as_array()
+            .iter()
+            .map(proof_derivation)
+            .collect::<eyre::Result<_>>()?,
+        None => Vec::new(),
+    };
+    Ok(Derivation::derived(fact_text(premises), rule, children))
+}
+
+// A fact as printed by Soufflé, with its symbols quoted, e.g. `cfg_edge("a", "b")`, in the
+// syntax of the derivation trees, `cfg_edge(a, b)`.
+fn fact_text(fact: &str) -> String {
+    fact.trim().replace('"', "")
+}
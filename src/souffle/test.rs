@@ -0,0 +1,127 @@
+use super::*;
+use crate::diagnostics::diagnose;
+use crate::fact_emitter::test::expect_facts;
+use crate::solver::solve;
+use crate::test_util::write_files;
+
+// Writes the output relations like Soufflé does, as tab-separated `.csv` files.
+fn write_output(dir: &Path, output: &SolverOutput) {
+    let write = |name: &str, rows: Vec<String>| {
+        let contents: String = rows.iter().map(|row| format!("{}\n", row)).collect();
+        std::fs::write(dir.join(name).with_extension("csv"), contents).unwrap();
+    };
+    write(
+        "invalidated_origin_accessed",
+        output
+            .invalidated_origin_accessed
+            .iter()
+            .map(|(o, n)| format!("{}\t{}", o.0, n.0))
+            .collect(),
+    );
+    write(
+        "placeholder_subset_error",
+        output
+            .placeholder_subset_error
+            .iter()
+            .map(|(o1, o2, n)| format!("{}\t{}\t{}", o1.0, o2.0, n.0))
+            .collect(),
+    );
+    write(
+        "loan_escapes_into_placeholder",
+        output
+            .loan_escapes_into_placeholder
+            .iter()
+            .map(|(l, o, n)| format!("{}\t{}\t{}", l.0, o.0, n.0))
+            .collect(),
+    );
+    write(
+        "origin_contains_loan_at",
+        output
+            .origin_contains_loan_at
+            .iter()
+            .map(|(o, l, n)| format!("{}\t{}\t{}", o.0, l.0, n.0))
+            .collect(),
+    );
+}
+
+#[test]
+fn diagnostics_from_souffle_output() {
+    // The outputs are read back into the solver's output, and reported like the in-crate
+    // solver's errors
+    let program = "
+        let p: i32;
+        let x: &'x i32;
+        let y: &'y mut i32;
+
+        bb0: {
+            x = &'L_p p;
+            p = 33;
+            y = &'L_p_mut mut p;
+            use(move x);
+        }
+    ";
+    let facts = expect_facts(program);
+    let dir = write_files("souffle-output", &[]);
+    let diagnostics = diagnose_with_solver(&facts, &mut |facts| {
+        write_output(&dir, &solve(facts));
+        read_souffle_output(&dir)
+    })
+    .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let expected: Vec<_> = diagnose(&facts).iter().map(|d| d.to_string()).collect();
+    let actual: Vec<_> = diagnostics.iter().map(|d| d.to_string()).collect();
+    assert_eq!(actual.len(), 2);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn missing_outputs() {
+    let dir = write_files("souffle-missing", &[]);
+    let error = read_souffle_output(&dir).unwrap_err();
+    assert!(error.to_string().starts_with("failed to read"));
+}
+
+#[test]
+fn derivations_from_souffle_proofs() {
+    // The proof trees are printed between the prompts of the explain shell, with quoted symbols
+    let output = r#"Enter command > Enter command > Enter command > { "proof":
+{ "premises": "placeholder_subset_error(\"'a\", \"'b\", \"a\")",
+  "rule-number": "(R1)",
+  "children": [
+  { "premises": "subset_on_exit(\"'a\", \"'b\", \"a\")",
+    "rule-number": "(R2)",
+    "children": [
+    { "axiom": "introduce_subset(\"'a\", \"'b\", \"a\")"}
+  ]},
+  { "axiom": "placeholder_origin(\"'a\")"},
+  { "axiom": "placeholder_origin(\"'b\")"},
+  { "axiom": "!known_placeholder_subset_closure(\"'a\", \"'b\")"}
+]}
+}
+Enter command > "#;
+    let derivations = read_proofs(output).unwrap();
+    assert_eq!(derivations.len(), 1);
+
+    // They're the same as the in-crate solver's
+    let program = "
+        placeholder 'a, 'b;
+        let x: &'a i32;
+        let y: &'b i32;
+
+        bb0: {
+            y = copy x;
+        }
+    ";
+    let expected = crate::solver::explain(&expect_facts(program));
+    assert_eq!(derivations[0].to_string(), expected[0].to_string());
+}
+
+#[test]
+fn unknown_rule_in_proof() {
+    let output = r#"{ "proof":
+{ "premises": "subset_on_exit(\"'a\", \"'b\", \"a\")", "rule-number": "(R4)", "children": [] }
+}"#;
+    let error = read_proofs(output).unwrap_err();
+    assert_eq!(error.to_string(), "unknown rule (R4) of `subset_on_exit`");
+}
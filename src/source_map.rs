@@ -0,0 +1,80 @@
+//! Conversions from the byte offsets of spans to lines and columns in the text they're from, for
+//! diagnostics and the other outputs pointing into the input.
+//!
+//! Lines and columns start at 1, like in editors, and columns count chars, not bytes.
+
+use crate::span::Span;
+
+#[cfg(test)]
+mod test;
+
+/// The lines of a text, to locate offsets in it.
+#[derive(Debug)]
+pub struct SourceMap<'a> {
+    text: &'a str,
+
+    /// The offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    /// The line and column of the offset. Offsets past the end of the text are at its end.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.text.len());
+        let line_idx = match self.line_starts.binary_search(&offset) {
+            Ok(idx) => idx,
+            Err(idx) => idx - 1,
+        };
+        let column = self.text[self.line_starts[line_idx]..offset]
+            .chars()
+            .count()
+            + 1;
+        (line_idx + 1, column)
+    }
+
+    /// The text of the line, without its newline.
+    pub fn line(&self, line: usize) -> &'a str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.text.len(), |&next| next - 1);
+        self.text[start..end].trim_end_matches('\r')
+    }
+
+    /// The lines the span is on, with their number: the snippet to show for the span.
+    pub fn snippet(&self, span: Span) -> Vec<(usize, &'a str)> {
+        let (first, _) = self.line_col(span.start());
+
+        // The span's last line is the one of its last char, which can be multi-byte
+        let end = span.end().min(self.text.len());
+        let last_char = self.text[..end]
+            .char_indices()
+            .last()
+            .map_or(0, |(idx, _)| idx);
+        let (last, _) = self.line_col(last_char.max(span.start()));
+        (first..=last.max(first))
+            .map(|line| (line, self.line(line)))
+            .collect()
+    }
+
+    /// The column where the span starts, and the number of columns it covers on its first line,
+    /// at least one: where to underline the span in its snippet.
+    pub fn underline(&self, span: Span) -> (usize, usize) {
+        let (line, column) = self.line_col(span.start());
+        let (end_line, end_column) = self.line_col(span.end());
+        let end = if end_line == line {
+            end_column
+        } else {
+            self.line(line).chars().count() + 1
+        };
+        (column, end.saturating_sub(column).max(1))
+    }
+}
@@ -0,0 +1,51 @@
+use super::*;
+
+#[test]
+fn lines_and_columns() {
+    let source_map = SourceMap::new("let x: i32;\nbb0: {\n    x = 'é';\n}");
+    assert_eq!(source_map.line_col(0), (1, 1));
+    assert_eq!(source_map.line_col(4), (1, 5));
+
+    // The newline is at the end of its line, and the next offset starts the next line
+    assert_eq!(source_map.line_col(11), (1, 12));
+    assert_eq!(source_map.line_col(12), (2, 1));
+
+    // Columns count chars: the `;` follows the 2-byte `é`
+    assert_eq!(source_map.line_col(31), (3, 12));
+    assert_eq!(source_map.line_col(100), (4, 2));
+}
+
+#[test]
+fn snippets() {
+    let text = "let x: i32;\nbb0: {\n    x = 1;\n}\n";
+    let source_map = SourceMap::new(text);
+    assert_eq!(source_map.line(3), "    x = 1;");
+    assert_eq!(source_map.line(5), "");
+
+    let start = text.find("bb0").unwrap();
+    let end = text.find('}').unwrap() + 1;
+    assert_eq!(
+        source_map.snippet(Span::new(start, end)),
+        vec![(2, "bb0: {"), (3, "    x = 1;"), (4, "}")]
+    );
+
+    // Empty spans are on the line they point to
+    assert_eq!(
+        source_map.snippet(Span::new(4, 4)),
+        vec![(1, "let x: i32;")]
+    );
+}
+
+#[test]
+fn underlines() {
+    let text = "bb0: {\n    x = 'é';\n}\n";
+    let source_map = SourceMap::new(text);
+    let start = text.find('x').unwrap();
+    let end = text.find(';').unwrap() + 1;
+    assert_eq!(source_map.underline(Span::new(start, end)), (5, 8));
+
+    // Spans over multiple lines are underlined until the end of their first line, and empty
+    // spans on one column
+    assert_eq!(source_map.underline(Span::new(0, text.len())), (1, 6));
+    assert_eq!(source_map.underline(Span::new(start, start)), (5, 1));
+}
@@ -0,0 +1,240 @@
+//! Programs split across multiple files, so that struct declarations and function signatures can
+//! be shared by many programs.
+//!
+//! A line of the form `include "structs.txt";` is replaced by the contents of the included file,
+//! whose path is relative to the including file. Included files can include other files, but not
+//! themselves. The program is then parsed from the text of all the files together, and the spans
+//! in that text are mapped back to the file they come from, for diagnostics.
+
+use crate::diagnostics::Diagnostic;
+use crate::fact_emitter::Facts;
+use crate::json::Json;
+use crate::limits::Limits;
+use crate::lints::lint_with_limits;
+use crate::lowering::LoweringError;
+use crate::source_map::SourceMap;
+use crate::span::Span;
+use crate::validation::{validate, SourceDiagnostic};
+use eyre::WrapErr;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+/// The text of a program, with the files it was included from.
+#[derive(Debug)]
+pub struct Sources {
+    text: String,
+    files: Vec<SourceFile>,
+
+    /// The parts of the text, in text order.
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug)]
+struct SourceFile {
+    path: PathBuf,
+    text: String,
+}
+
+// A part of the text, copied from a part of a file.
+#[derive(Debug)]
+struct Segment {
+    start: usize,
+    len: usize,
+    file_idx: usize,
+    file_start: usize,
+}
+
+impl Sources {
+    /// Reads the program at the given path, and the files it includes.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let mut sources = Self {
+            text: String::new(),
+            files: Vec::new(),
+            segments: Vec::new(),
+        };
+        sources.include(path, &mut Vec::new())?;
+        Ok(sources)
+    }
+
+    /// The text of the program, with the includes replaced by the included files.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The file containing the start of the span of the text, and the span in that file. Spans
+    /// crossing into another file are cut at the end of the first one.
+    pub fn locate(&self, span: Span) -> (&Path, Span) {
+        let (file_idx, span) = self.locate_in_file(span);
+        (&self.files[file_idx].path, span)
+    }
+
+    fn locate_in_file(&self, span: Span) -> (usize, Span) {
+        let segment = match self
+            .segments
+            .iter()
+            .rev()
+            .find(|segment| segment.start <= span.start())
+        {
+            Some(segment) => segment,
+
+            // The text is empty
+            None => return (0, Span::new(0, 0)),
+        };
+        let offset = span.start() - segment.start;
+        let len = (span.end() - span.start()).min(segment.len.saturating_sub(offset));
+        let start = segment.file_start + offset;
+        (segment.file_idx, Span::new(start, start + len))
+    }
+
+    /// Parses the program and returns its errors and warnings, like `validate`, located in the
+    /// files they're from.
+    pub fn validate(&self) -> eyre::Result<Vec<SourceDiagnostic>> {
+        let diagnostics = validate(&self.text).map_err(|e| self.locate_parse_error(e))?;
+        Ok(self.locate_diagnostics(diagnostics))
+    }
+
+    /// Returns the warnings about the unused parts of the program, whose facts have been
+    /// emitted, like `lint_with_limits`, located in the files they're from.
+    pub fn lint(&self, facts: &Facts, limits: &Limits) -> eyre::Result<Vec<SourceDiagnostic>> {
+        let warnings =
+            lint_with_limits(&self.text, facts, limits).map_err(|e| self.locate_parse_error(e))?;
+        Ok(self.locate_diagnostics(warnings))
+    }
+
+    fn locate_diagnostics(&self, diagnostics: Vec<SourceDiagnostic>) -> Vec<SourceDiagnostic> {
+        diagnostics
+            .into_iter()
+            .map(|mut diagnostic| {
+                let (path, span) = self.locate(diagnostic.span);
+                diagnostic.path = Some(path.to_path_buf());
+                diagnostic.span = span;
+                diagnostic
+            })
+            .collect()
+    }
+
+    /// Adds the file the parse error is in to the error, with the line and column in that file.
+    /// The errors of lowering the parsed program are located at their span.
+    pub fn locate_parse_error(&self, error: eyre::Report) -> eyre::Report {
+        let offset = if let Some(parse_error) =
+            error.downcast_ref::<peg::error::ParseError<peg::str::LineCol>>()
+        {
+            parse_error.location.offset
+        } else if let Some(lowering_error) = error.downcast_ref::<LoweringError>() {
+            lowering_error.span.start()
+        } else {
+            return error;
+        };
+        let (file_idx, span) = self.locate_in_file(Span::new(offset, offset));
+        let file = &self.files[file_idx];
+        let (line, column) = SourceMap::new(&file.text).line_col(span.start());
+        error.wrap_err(format!(
+            "failed to parse `{}` at {}:{}",
+            file.path.display(),
+            line,
+            column
+        ))
+    }
+
+    /// Renders the diagnostic, returned by `validate`, with the lines of the file it's in.
+    pub fn render(&self, diagnostic: &SourceDiagnostic) -> String {
+        diagnostic.render(&SourceMap::new(self.file_text(diagnostic)))
+    }
+
+    /// The diagnostic, returned by `validate`, as JSON, see `SourceDiagnostic::to_json`.
+    pub fn to_json(&self, diagnostic: &SourceDiagnostic) -> Json {
+        diagnostic.to_json(&SourceMap::new(self.file_text(diagnostic)))
+    }
+
+    /// Renders the diagnostic of the program's facts, see `Diagnostic::render`, with its spans
+    /// located in the files they're from.
+    pub fn render_diagnostic(&self, diagnostic: &Diagnostic, facts: &Facts) -> String {
+        diagnostic.render_located(facts, &|span| self.locate_text(span))
+    }
+
+    /// The diagnostic of the program's facts as JSON, see `Diagnostic::to_json`, with its spans
+    /// located in the files they're from.
+    pub fn diagnostic_to_json(&self, diagnostic: &Diagnostic, facts: &Facts) -> Json {
+        diagnostic.to_json_located(facts, &|span| self.locate_text(span))
+    }
+
+    // The name and text of the file containing the span, and the span in that file.
+    fn locate_text(&self, span: Span) -> (String, &str, Span) {
+        let (file_idx, span) = self.locate_in_file(span);
+        let file = &self.files[file_idx];
+        (file.path.display().to_string(), &file.text, span)
+    }
+
+    // The text of the file the diagnostic is in.
+    fn file_text(&self, diagnostic: &SourceDiagnostic) -> &str {
+        match &diagnostic.path {
+            Some(path) => self
+                .files
+                .iter()
+                .find(|file| &file.path == path)
+                .map_or(self.text.as_str(), |file| file.text.as_str()),
+            None => &self.text,
+        }
+    }
+
+    // Appends the file to the text, replacing its includes by the included files. The files being
+    // included are on the stack, to detect include cycles.
+    fn include(&mut self, path: &Path, stack: &mut Vec<PathBuf>) -> eyre::Result<()> {
+        let canonical_path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if stack.contains(&canonical_path) {
+            eyre::bail!("`{}` includes itself", path.display());
+        }
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+        let file_idx = self.files.len();
+        self.files.push(SourceFile {
+            path: path.to_path_buf(),
+            text: text.clone(),
+        });
+
+        stack.push(canonical_path);
+        let mut copied_until = 0;
+        let mut line_start = 0;
+        for line in text.split_inclusive('\n') {
+            let content = line.trim_end_matches('\n');
+            if let Some(included) = include_directive(content) {
+                self.copy(file_idx, &text, copied_until, line_start);
+                let included = path.parent().unwrap_or(Path::new("")).join(included);
+                self.include(&included, stack)
+                    .wrap_err_with(|| format!("included from `{}`", path.display()))?;
+                copied_until = line_start + content.len();
+            }
+            line_start += line.len();
+        }
+        self.copy(file_idx, &text, copied_until, text.len());
+        stack.pop();
+        Ok(())
+    }
+
+    // Appends the part of the file between the given offsets to the text.
+    fn copy(&mut self, file_idx: usize, file_text: &str, start: usize, end: usize) {
+        if start == end {
+            return;
+        }
+        self.segments.push(Segment {
+            start: self.text.len(),
+            len: end - start,
+            file_idx,
+            file_start: start,
+        });
+        self.text.push_str(&file_text[start..end]);
+    }
+}
+
+// The path included by the line, if it's an include directive: `include "path";`.
+fn include_directive(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("include")?
+        .trim_start()
+        .strip_suffix(';')?
+        .trim_end()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+}
@@ -0,0 +1,178 @@
+use super::*;
+use crate::test_util::write_files;
+use insta::assert_display_snapshot;
+
+// The diagnostics, with the paths relative to the directory, one after the other.
+fn expect_diagnostics(sources: &Sources, dir: &Path) -> String {
+    sources
+        .validate()
+        .unwrap()
+        .into_iter()
+        .map(|mut diagnostic| {
+            diagnostic.path = diagnostic
+                .path
+                .map(|path| path.strip_prefix(dir).unwrap().to_path_buf());
+            diagnostic.to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn nested_includes() {
+    // Included paths are relative to the including file
+    let dir = write_files(
+        "sources-nested",
+        &[
+            (
+                "program.txt",
+                "include \"shared/structs.txt\";\nlet pair: Pair;\nbb0: { use(copy pair.third); }\n",
+            ),
+            (
+                "shared/structs.txt",
+                "struct Pair { first: i32, second: i32 }\ninclude \"fns.txt\";\n",
+            ),
+            ("shared/fns.txt", "fn use(x: i32) -> ();"),
+        ],
+    );
+    let sources = Sources::load(&dir.join("program.txt")).unwrap();
+    assert_display_snapshot!(sources.text(), @r###"
+    struct Pair { first: i32, second: i32 }
+    fn use(x: i32) -> ();
+
+    let pair: Pair;
+    bb0: { use(copy pair.third); }
+    "###);
+    assert_display_snapshot!(expect_diagnostics(&sources, &dir), @r###"
+    error: struct `Pair` has no field `third`
+      --> program.txt:53..74 `use(copy pair.third);`
+    "###);
+}
+
+#[test]
+fn diagnostics_in_included_files() {
+    let dir = write_files(
+        "sources-diagnostics",
+        &[
+            (
+                "program.txt",
+                "include \"structs.txt\";\nlet pair: Pair;\nbb0: { }\n",
+            ),
+            ("structs.txt", "struct Pair { first: Vec }\n"),
+        ],
+    );
+    let sources = Sources::load(&dir.join("program.txt")).unwrap();
+    assert_display_snapshot!(expect_diagnostics(&sources, &dir), @r###"
+    error: unknown struct `Vec` in the type of field `Pair.first`
+      --> structs.txt:14..19 `first`
+    "###);
+}
+
+#[test]
+fn rendered_diagnostics() {
+    // Diagnostics are rendered with the lines of the file they're in
+    let dir = write_files(
+        "sources-rendered",
+        &[
+            (
+                "program.txt",
+                "include \"structs.txt\";\nlet pair: Pair;\nbb0: {\n    use(copy pair.first);\n}\n",
+            ),
+            (
+                "structs.txt",
+                "struct Pair { first: i32,\n    second: Vec }\n",
+            ),
+        ],
+    );
+    let sources = Sources::load(&dir.join("program.txt")).unwrap();
+    let rendered: String = sources
+        .validate()
+        .unwrap()
+        .iter()
+        .map(|diagnostic| sources.render(diagnostic))
+        .collect();
+    assert_display_snapshot!(rendered.replace(&dir.display().to_string(), "<dir>"), @r###"
+    error: unknown struct `Vec` in the type of field `Pair.second`
+     --> <dir>/structs.txt:2:5
+      |
+    2 |     second: Vec }
+      |     ^^^^^^
+    "###);
+}
+
+#[test]
+fn rendered_errors_in_included_files() {
+    // The labels in another file than the error's start with their location
+    let dir = write_files(
+        "sources-rendered-errors",
+        &[
+            (
+                "program.txt",
+                "let p: i32;\nlet x: &'x i32;\nbb0: {\n    include \"borrow.txt\";\n    p = 33;\n    use(move x);\n}\n",
+            ),
+            ("borrow.txt", "x = &'L_p p;\n"),
+        ],
+    );
+    let sources = Sources::load(&dir.join("program.txt")).unwrap();
+    let facts = crate::emit_facts(sources.text()).unwrap();
+    let rendered: String = crate::diagnose(&facts)
+        .iter()
+        .map(|diagnostic| sources.render_diagnostic(diagnostic, &facts))
+        .collect();
+    assert_display_snapshot!(rendered.replace(&dir.display().to_string(), "<dir>"), @r###"
+    error[E0506]: cannot assign to `p` because it is borrowed
+     --> <dir>/program.txt:5:5
+      |
+     ::: <dir>/borrow.txt:1:1
+    1 | x = &'L_p p;
+      | ----------- `p` is borrowed here
+     ::: <dir>/program.txt:5:5
+    5 |     p = 33;
+      |     ^^^^^^ `p` is assigned to here but it was already borrowed
+    6 |     use(move x);
+      |     ----------- borrow later used here
+      |
+      = note: the shortest path through the borrow, its invalidation, and the later use:
+              bb0[0] `x = &'L_p p`
+              bb0[1] `p = 33`
+              bb0[2] `use(move x)`
+    "###);
+}
+
+#[test]
+fn parse_errors_in_included_files() {
+    let dir = write_files(
+        "sources-parse-errors",
+        &[
+            ("program.txt", "include \"structs.txt\";\nbb0: { }\n"),
+            ("structs.txt", "struct Pair {\n    first: i32;\n}\n"),
+        ],
+    );
+    let sources = Sources::load(&dir.join("program.txt")).unwrap();
+    assert_display_snapshot!(expect_diagnostics(&sources, &dir), @r###"
+    error: syntax error, expected one of ",", "}"
+      --> structs.txt:28..29 `;`
+    "###);
+}
+
+#[test]
+fn include_cycles() {
+    let dir = write_files(
+        "sources-cycles",
+        &[
+            ("program.txt", "include \"a.txt\";\n"),
+            ("a.txt", "include \"b.txt\";\n"),
+            ("b.txt", "include \"a.txt\";\n"),
+        ],
+    );
+    let error = Sources::load(&dir.join("program.txt")).unwrap_err();
+    let messages: Vec<_> = error
+        .chain()
+        .map(|e| e.to_string().replace(&dir.display().to_string(), "<dir>"))
+        .collect();
+    assert_display_snapshot!(messages.join("\n"), @r###"
+    included from `<dir>/program.txt`
+    included from `<dir>/a.txt`
+    included from `<dir>/b.txt`
+    `<dir>/a.txt` includes itself
+    "###);
+}
@@ -52,6 +52,10 @@ impl<T> Spanned<T> {
         }
     }
 
+    pub fn into_inner(this: Self) -> T {
+        this.inner
+    }
+
     pub fn map<U>(this: Self, f: impl FnOnce(T) -> U) -> Spanned<U> {
         Spanned {
             inner: f(this.inner),
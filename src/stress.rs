@@ -0,0 +1,230 @@
+//! Generation of large synthetic programs, to benchmark the emitter and solvers, and to find the
+//! constructs they don't scale to.
+//!
+//! The programs are valid, and generated from a seed, so that a program which is slow to analyze
+//! can be generated again to investigate it. Their shape is controlled by `StressOptions`: the
+//! number of blocks, the depth of the struct nesting, and the number of statements per block.
+//! Most statements borrow, mutate, or read deeply nested fields, so that loans overlap and
+//! are invalidated across the CFG.
+//!
+//! The solvers are benchmarked on programs of increasing sizes: the optimized solver, whose
+//! rules are leapfrog joins over sorted and indexed relations, and the naive one, evaluating
+//! each rule over sets of tuples, see `benchmark_solvers`.
+
+use crate::fact_emitter::emit_facts;
+use crate::fuzz::same_errors;
+use crate::solver::{solve, solve_naive};
+use std::fmt::{self, Write};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod test;
+
+/// The shape of a generated program.
+#[derive(Clone, Debug)]
+pub struct StressOptions {
+    /// The seed of the generator: the same options generate the same program.
+    pub seed: u64,
+
+    /// The number of blocks of the CFG, at least 1.
+    pub blocks: usize,
+
+    /// The depth of the struct nesting: the fields of the variables are paths of this length.
+    pub struct_depth: usize,
+
+    /// The number of statements of each block.
+    pub statements_per_block: usize,
+
+    /// The number of struct variables, and of references to their fields, at least 1.
+    pub variables: usize,
+}
+
+impl Default for StressOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            blocks: 1000,
+            struct_depth: 4,
+            statements_per_block: 4,
+            variables: 8,
+        }
+    }
+}
+
+/// Generates a program of the given shape.
+pub fn generate_stress_program(options: &StressOptions) -> String {
+    let mut rng = SplitMix64(options.seed);
+    let (blocks, variables) = (options.blocks.max(1), options.variables.max(1));
+    let mut program = String::new();
+
+    // `S0` has the scalar fields, and each struct nests two of the previous one.
+    writeln!(program, "struct S0 {{ value: i32, count: i32 }}").unwrap();
+    for depth in 1..=options.struct_depth {
+        writeln!(
+            program,
+            "struct S{} {{ left: S{}, right: S{} }}",
+            depth,
+            depth - 1,
+            depth - 1
+        )
+        .unwrap();
+    }
+    writeln!(program).unwrap();
+
+    for idx in 0..variables {
+        writeln!(program, "let x{}: S{};", idx, options.struct_depth).unwrap();
+        writeln!(program, "let r{}: &'r{} i32;", idx, idx).unwrap();
+        writeln!(program, "let m{}: &'m{} mut i32;", idx, idx).unwrap();
+    }
+    writeln!(program, "let y: i32;").unwrap();
+
+    let mut loan_count = 0;
+    for block in 0..blocks {
+        writeln!(program, "\nbb{}: {{", block).unwrap();
+        for _ in 0..options.statements_per_block {
+            let variable = rng.below(variables);
+            let field = field_path(&mut rng, options.struct_depth);
+            let statement = match rng.below(5) {
+                0 => {
+                    loan_count += 1;
+                    format!("r{} = &'L{} x{}.{};", variable, loan_count, variable, field)
+                }
+                1 => {
+                    loan_count += 1;
+                    format!(
+                        "m{} = &'L{} mut x{}.{};",
+                        variable, loan_count, variable, field
+                    )
+                }
+                2 => format!("x{}.{} = {};", variable, field, rng.below(100)),
+                3 => format!("y = copy *r{};", variable),
+                _ => format!("use(copy m{});", variable),
+            };
+            writeln!(program, "    {}", statement).unwrap();
+        }
+
+        // Each block goes to the next one, so that they're all reachable, and sometimes to
+        // another one, forming loops when it's an earlier one.
+        if block + 1 < blocks {
+            let mut successors = vec![block + 1];
+            if rng.below(3) == 0 {
+                let other = rng.below(blocks);
+                if !successors.contains(&other) {
+                    successors.push(other);
+                }
+            }
+            let successors: Vec<_> = successors.iter().map(|b| format!("bb{}", b)).collect();
+            writeln!(program, "    goto {};", successors.join(", ")).unwrap();
+        }
+        writeln!(program, "}}").unwrap();
+    }
+    program
+}
+
+// A path of fields from a variable down to one of the scalar fields of `S0`.
+fn field_path(rng: &mut SplitMix64, depth: usize) -> String {
+    let mut path: Vec<_> = (0..depth)
+        .map(|_| if rng.below(2) == 0 { "left" } else { "right" })
+        .collect();
+    path.push(if rng.below(2) == 0 { "value" } else { "count" });
+    path.join(".")
+}
+
+// The SplitMix64 generator: small, and good enough to pick the shapes of statements.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // A number in `0..bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// The time each solver takes on a generated program.
+#[derive(Clone, Debug)]
+pub struct SolverTiming {
+    /// The number of blocks of the program.
+    pub blocks: usize,
+
+    /// The number of facts emitted for the program.
+    pub facts: usize,
+    pub optimized: Duration,
+    pub naive: Duration,
+}
+
+/// The timings of the solvers on programs of increasing sizes.
+#[derive(Default, Debug)]
+pub struct SolverBenchmark {
+    pub timings: Vec<SolverTiming>,
+}
+
+/// Solves the programs of the given shape, with an eighth, a quarter, half, and all of its
+/// blocks, with both solvers, and times them. Fails if the solvers compute different errors.
+pub fn benchmark_solvers(options: &StressOptions) -> eyre::Result<SolverBenchmark> {
+    let mut sizes: Vec<_> = (0..4).rev().map(|shift| options.blocks >> shift).collect();
+    sizes.retain(|&blocks| blocks > 0);
+    sizes.dedup();
+
+    let mut benchmark = SolverBenchmark::default();
+    for blocks in sizes {
+        let program = generate_stress_program(&StressOptions {
+            blocks,
+            ..options.clone()
+        });
+        let facts = emit_facts(&program)?;
+
+        let start = Instant::now();
+        let optimized = solve(&facts);
+        let optimized_time = start.elapsed();
+
+        let start = Instant::now();
+        let naive = solve_naive(&facts);
+        let naive_time = start.elapsed();
+
+        if !same_errors(&optimized, &naive) {
+            eyre::bail!(
+                "the solvers diverge on the program of {} blocks, of seed {}",
+                blocks,
+                options.seed
+            );
+        }
+        benchmark.timings.push(SolverTiming {
+            blocks,
+            facts: facts.fact_count(),
+            optimized: optimized_time,
+            naive: naive_time,
+        });
+    }
+    Ok(benchmark)
+}
+
+impl fmt::Display for SolverBenchmark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>8} {:>10} {:>12} {:>12} {:>8}",
+            "blocks", "facts", "optimized", "naive", "speedup"
+        )?;
+        for timing in &self.timings {
+            let speedup = timing.naive.as_secs_f64() / timing.optimized.as_secs_f64().max(1e-9);
+            writeln!(
+                f,
+                "{:>8} {:>10} {:>10.1}ms {:>10.1}ms {:>7.1}x",
+                timing.blocks,
+                timing.facts,
+                timing.optimized.as_secs_f64() * 1000.0,
+                timing.naive.as_secs_f64() * 1000.0,
+                speedup
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use super::*;
+use crate::fact_emitter::emit_facts;
+use crate::solver::{solve, solve_naive};
+use crate::validation::validate;
+use insta::assert_display_snapshot;
+
+fn small(seed: u64) -> StressOptions {
+    StressOptions {
+        seed,
+        blocks: 3,
+        struct_depth: 2,
+        statements_per_block: 3,
+        variables: 2,
+    }
+}
+
+#[test]
+fn small_program() {
+    assert_display_snapshot!(generate_stress_program(&small(0)), @r###"
+    struct S0 { value: i32, count: i32 }
+    struct S1 { left: S0, right: S0 }
+    struct S2 { left: S1, right: S1 }
+
+    let x0: S2;
+    let r0: &'r0 i32;
+    let m0: &'m0 mut i32;
+    let x1: S2;
+    let r1: &'r1 i32;
+    let m1: &'m1 mut i32;
+    let y: i32;
+
+    bb0: {
+        x1.left.right.value = 90;
+        m1 = &'L1 mut x1.left.right.value;
+        x0.right.right.count = 25;
+        goto bb1;
+    }
+
+    bb1: {
+        y = copy *r0;
+        x0.right.right.value = 78;
+        use(copy m0);
+        goto bb2;
+    }
+
+    bb2: {
+        m1 = &'L2 mut x1.right.right.count;
+        use(copy m0);
+        use(copy m1);
+    }
+    "###);
+}
+
+#[test]
+fn same_seed_same_program() {
+    let options = StressOptions {
+        blocks: 50,
+        ..StressOptions::default()
+    };
+    assert_eq!(
+        generate_stress_program(&options),
+        generate_stress_program(&options)
+    );
+    assert_ne!(
+        generate_stress_program(&options),
+        generate_stress_program(&StressOptions { seed: 1, ..options })
+    );
+}
+
+#[test]
+fn generated_programs_are_valid() {
+    for seed in 0..10 {
+        let program = generate_stress_program(&StressOptions {
+            blocks: 8,
+            ..small(seed)
+        });
+        assert!(validate(&program).unwrap().is_empty(), "{}", program);
+
+        // The solvers agree on them
+        let facts = emit_facts(&program).unwrap();
+        assert_eq!(solve(&facts), solve_naive(&facts), "{}", program);
+    }
+}
+
+#[test]
+fn benchmark_of_the_solvers() {
+    let options = StressOptions {
+        blocks: 16,
+        ..small(0)
+    };
+    let benchmark = benchmark_solvers(&options).unwrap();
+    let sizes: Vec<_> = benchmark
+        .timings
+        .iter()
+        .map(|timing| timing.blocks)
+        .collect();
+    assert_eq!(sizes, [2, 4, 8, 16]);
+    assert!(benchmark
+        .timings
+        .windows(2)
+        .all(|pair| pair[0].facts < pair[1].facts));
+    assert!(benchmark.to_string().starts_with("  blocks      facts"));
+}
@@ -0,0 +1,113 @@
+//! The subset graphs between origins, in effect on entry to each node, rendered in DOT to see how
+//! the subsets evolve along the CFG.
+//!
+//! Each node is a cluster of the graph, labelled with its statement, and the line and column of
+//! the statement in the program when the facts have spans, where an edge `'a -> 'b`
+//! is the subset `'a ⊆ 'b`: the loans of `'a` flow into `'b`. The subsets which don't hold on
+//! entry to any predecessor of the node, the ones it starts with, are in bold. The origins of
+//! loans are boxes, and the placeholders double circles.
+
+use crate::fact_emitter::{Facts, Node, Origin};
+use crate::solver::naive;
+use crate::source_map::SourceMap;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+#[cfg(test)]
+mod test;
+
+/// The subset graphs on entry to the nodes, or to all the nodes of the facts if there are none, in
+/// DOT. The spans of the nodes are located in the input the facts were emitted from.
+pub fn subset_graphs(facts: &Facts, nodes: &[Node], input: &str) -> eyre::Result<String> {
+    let all_nodes = facts.nodes();
+    if let Some(node) = nodes.iter().find(|node| !all_nodes.contains(node)) {
+        eyre::bail!("unknown node `{}`", node.0);
+    }
+    let nodes: Vec<&Node> = if nodes.is_empty() {
+        all_nodes
+    } else {
+        nodes.iter().collect()
+    };
+
+    let relations = naive::compute(facts);
+    let subsets_on_entry = |node: &Node| -> BTreeSet<(&Origin, &Origin)> {
+        relations
+            .subset_on_entry
+            .keys()
+            .filter(|(_, _, candidate)| *candidate == node)
+            .map(|&(origin1, origin2, _)| (origin1, origin2))
+            .collect()
+    };
+
+    let source_map = SourceMap::new(input);
+    let mut dot = String::new();
+    writeln!(dot, "digraph subsets {{")?;
+    for (idx, node) in nodes.into_iter().enumerate() {
+        let subsets = subsets_on_entry(node);
+        let predecessor_subsets: BTreeSet<_> = facts
+            .cfg_edge
+            .iter()
+            .filter(|(_, to)| to == node)
+            .flat_map(|(from, _)| subsets_on_entry(from))
+            .collect();
+
+        let mut label = node.0.to_string();
+        if let Some(span) = facts.node_span(node) {
+            let (line, column) = source_map.line_col(span.start());
+            label += &format!(" ({}:{})", line, column);
+        }
+        if let Some(text) = facts.node_text(node) {
+            label += &format!(": {}", text);
+        }
+        writeln!(dot, "    subgraph cluster_{} {{", idx)?;
+        writeln!(dot, "        label = \"{}\"", escape(&label))?;
+
+        // The clusters without subsets are shown too, around an invisible node
+        writeln!(dot, "        \"{}\" [shape = point, style = invis]", idx)?;
+        let origins: BTreeSet<&Origin> = subsets
+            .iter()
+            .flat_map(|&(origin1, origin2)| [origin1, origin2])
+            .collect();
+        for origin in origins {
+            let shape = if facts.mark_as_loan_origin.contains(origin) {
+                "box"
+            } else if facts.placeholder_origin.contains(origin) {
+                "doublecircle"
+            } else {
+                "ellipse"
+            };
+            writeln!(
+                dot,
+                "        \"{}:{}\" [label = \"{}\", shape = {}]",
+                idx,
+                escape(&origin.0),
+                escape(&origin.0),
+                shape
+            )?;
+        }
+        for (origin1, origin2) in &subsets {
+            let style = if predecessor_subsets.contains(&(*origin1, *origin2)) {
+                ""
+            } else {
+                " [style = bold]"
+            };
+            writeln!(
+                dot,
+                "        \"{}:{}\" -> \"{}:{}\"{}",
+                idx,
+                escape(&origin1.0),
+                idx,
+                escape(&origin2.0),
+                style
+            )?;
+        }
+        writeln!(dot, "    }}")?;
+    }
+    writeln!(dot, "}}")?;
+    Ok(dot)
+}
+
+// The text escaped for a DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
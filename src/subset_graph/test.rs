@@ -0,0 +1,58 @@
+use super::*;
+use crate::fact_emitter::emit_facts;
+use insta::assert_display_snapshot;
+
+#[test]
+fn subsets_along_the_cfg() {
+    // The subset introduced by the call holds from its successor on, and is only new there
+    let program = "
+        fn f<'a>(x: &'a i32) -> &'a i32;
+        placeholder 'p;
+        let x: i32;
+        let r: &'r i32;
+        let p: &'p i32;
+        bb0: {
+            r = &'L_x x;
+            p = f(copy r);
+            use(copy r);
+        }
+    ";
+    let facts = emit_facts(program).unwrap();
+    assert_display_snapshot!(subset_graphs(&facts, &[], program).unwrap(), @r###"
+    digraph subsets {
+        subgraph cluster_0 {
+            label = "bb0[0] (8:13): r = &'L_x x"
+            "0" [shape = point, style = invis]
+        }
+        subgraph cluster_1 {
+            label = "bb0[1] (9:13): p = f(copy r)"
+            "1" [shape = point, style = invis]
+            "1:'L_x" [label = "'L_x", shape = box]
+            "1:'r" [label = "'r", shape = ellipse]
+            "1:'L_x" -> "1:'r" [style = bold]
+        }
+        subgraph cluster_2 {
+            label = "bb0[2] (10:13): use(copy r)"
+            "2" [shape = point, style = invis]
+            "2:'L_x" [label = "'L_x", shape = box]
+            "2:'p" [label = "'p", shape = doublecircle]
+            "2:'r" [label = "'r", shape = ellipse]
+            "2:'L_x" -> "2:'p" [style = bold]
+            "2:'L_x" -> "2:'r"
+            "2:'r" -> "2:'p" [style = bold]
+        }
+    }
+    "###);
+    assert_display_snapshot!(subset_graphs(&facts, &[Node::from("bb0[1]")], program).unwrap(), @r###"
+    digraph subsets {
+        subgraph cluster_0 {
+            label = "bb0[1] (9:13): p = f(copy r)"
+            "0" [shape = point, style = invis]
+            "0:'L_x" [label = "'L_x", shape = box]
+            "0:'r" [label = "'r", shape = ellipse]
+            "0:'L_x" -> "0:'r" [style = bold]
+        }
+    }
+    "###);
+    assert_display_snapshot!(subset_graphs(&facts, &[Node::from("bb1[0]")], program).unwrap_err(), @"unknown node `bb1[0]`");
+}
@@ -0,0 +1,150 @@
+//! The syntax tree of programs as they are written in the input, before lowering: statements can
+//! nest expressions and structured control flow, and places can be based on the result of an
+//! expression. Lowering it, see `lowering`, gives the AST the facts are emitted from.
+//!
+//! The tree only lives until it's lowered, and its statements and expressions are allocated in
+//! an arena, which is freed all at once after lowering.
+
+use bumpalo::boxed::Box as ArenaBox;
+use bumpalo::collections::Vec as ArenaVec;
+
+use crate::ast::{self, AccessKind, Name, Projection};
+use crate::span::{Span, Spanned as Sp};
+
+/// A program as it is written in the input: its declarations are already the AST's, but its
+/// blocks are not lowered yet.
+#[derive(Debug)]
+pub(crate) struct Program<'a> {
+    pub(crate) struct_decls: Vec<ast::StructDecl>,
+    pub(crate) fn_prototypes: Vec<ast::FnPrototype>,
+    pub(crate) placeholders: Vec<Name>,
+    pub(crate) known_placeholder_subsets: Vec<(Name, Name)>,
+    pub(crate) variables: Vec<ast::VariableDecl>,
+    pub(crate) basic_blocks: Vec<BasicBlock<'a>>,
+    pub(crate) raw_facts: Vec<Sp<ast::RawFact>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct BasicBlock<'a> {
+    pub(crate) name: Name,
+    pub(crate) span: Span,
+    pub(crate) statements: ArenaVec<'a, Sp<Statement<'a>>>,
+    pub(crate) terminator: Terminator<'a>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Terminator<'a> {
+    Goto(Vec<Name>),
+    Return,
+    Switch {
+        discriminant: Sp<Expr<'a>>,
+        targets: Vec<Name>,
+    },
+    Call {
+        destination: Option<ast::Place>,
+        call: Sp<Expr<'a>>,
+        target: Name,
+        unwind: Option<Name>,
+    },
+    Assert {
+        condition: Sp<Expr<'a>>,
+        success: Name,
+        unwind: Option<Name>,
+    },
+    Unreachable,
+}
+
+#[derive(Debug)]
+pub(crate) enum Statement<'a> {
+    /// A declaration with an initializer (`let x: ty = expr;`), lowered to the variable
+    /// declaration and an assignment.
+    Let(ast::VariableDecl, Expr<'a>),
+
+    Assign(ast::Place, Expr<'a>),
+
+    Expr(Expr<'a>),
+
+    /// `end('a);` and `kill(place);`, which are already simple.
+    End(Name),
+    Kill(ast::Place),
+
+    /// `if condition { .. } else { .. }`: the condition is evaluated, and either block can
+    /// be executed next.
+    If {
+        condition: Sp<Expr<'a>>,
+        then_block: ArenaVec<'a, Sp<Statement<'a>>>,
+        else_block: Option<ArenaVec<'a, Sp<Statement<'a>>>>,
+    },
+
+    /// `while condition { .. }`: the condition is evaluated before each iteration.
+    While {
+        condition: Sp<Expr<'a>>,
+        body: ArenaVec<'a, Sp<Statement<'a>>>,
+    },
+
+    /// `loop { .. }`, which is only exited by a `break`.
+    Loop {
+        body: ArenaVec<'a, Sp<Statement<'a>>>,
+    },
+
+    Break,
+
+    /// `continue;`, which goes back to the head of the innermost loop: the evaluation of the
+    /// condition of a `while`, or the start of the body of a `loop`.
+    Continue,
+
+    /// A statement with attributes (`#[two_phase] x = ..;`).
+    Attributed(Vec<ast::Attribute>, ArenaBox<'a, Sp<Statement<'a>>>),
+
+    /// A statement with its location in the Rust source (`x = ..; @ main.rs:42`).
+    Located(ast::SourceLocation, ArenaBox<'a, Sp<Statement<'a>>>),
+}
+
+#[derive(Debug)]
+pub(crate) enum Expr<'a> {
+    Access {
+        kind: AccessKind,
+        place: Place<'a>,
+    },
+    Number {
+        value: i32,
+    },
+    Call {
+        name: Name,
+        generic_args: Vec<ast::Parameter>,
+        arguments: ArenaVec<'a, Sp<Expr<'a>>>,
+    },
+    MethodCall {
+        receiver: Place<'a>,
+        method: Name,
+        generic_args: Vec<ast::Parameter>,
+        arguments: ArenaVec<'a, Sp<Expr<'a>>>,
+        span: Span,
+    },
+    Cast {
+        operand: ArenaBox<'a, Sp<Expr<'a>>>,
+        ty: ast::Ty,
+    },
+    Unit,
+}
+
+/// A place whose base can be the result of an expression, like `foo().f`.
+#[derive(Debug)]
+pub(crate) struct Place<'a> {
+    pub(crate) base: PlaceBase<'a>,
+    pub(crate) projections: Vec<Projection>,
+}
+
+#[derive(Debug)]
+pub(crate) enum PlaceBase<'a> {
+    Variable(Name),
+    Temp(ArenaBox<'a, Sp<Expr<'a>>>),
+}
+
+impl Place<'_> {
+    /// Whether this place is a projection of the result of an expression, which can be used
+    /// as an expression itself, e.g. `foo().f` is a move out of the call's result.
+    pub(crate) fn is_projected_temp(&self) -> bool {
+        matches!(self.base, PlaceBase::Temp(_)) && !self.projections.is_empty()
+    }
+}
@@ -0,0 +1,23 @@
+use super::*;
+
+#[test]
+fn interning() {
+    let a = Symbol::intern("'a");
+    assert_eq!(a, Symbol::intern(&String::from("'a")));
+    assert!(std::ptr::eq(a.as_str(), Symbol::from("'a").as_str()));
+    assert_ne!(a, Symbol::intern("'b"));
+    assert_eq!(a, "'a");
+    assert_eq!(a.to_string(), "'a");
+    assert_eq!(format!("{:?}", a), "\"'a\"");
+}
+
+#[test]
+fn symbols_are_ordered_like_strings() {
+    // Whatever the order they're interned in
+    let mut symbols: Vec<_> = ["bb10[0]", "'z", "bb1[2]", "'a"]
+        .iter()
+        .map(|s| Symbol::intern(s))
+        .collect();
+    symbols.sort();
+    assert_eq!(symbols, ["'a", "'z", "bb10[0]", "bb1[2]"]);
+}
@@ -0,0 +1,60 @@
+//! Helpers shared by the tests of several modules, writing their inputs to files.
+
+use std::path::{Path, PathBuf};
+
+// Writes the given files, at their relative paths, in a fresh directory named after the test,
+// and returns that directory. Without files, it's an empty directory for the test's outputs.
+pub(crate) fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("polonius-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (path, contents) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+// Writes the given relations in the directory, as the `.facts` files of a fact set.
+pub(crate) fn write_fact_set(dir: &Path, relations: &[(&str, &str)]) {
+    std::fs::create_dir_all(dir).unwrap();
+    for (relation, rows) in relations {
+        let rows: String = rows
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .map(|row| format!("{}\n", row.replace(' ', "\t")))
+            .collect();
+        std::fs::write(dir.join(relation).with_extension("facts"), rows).unwrap();
+    }
+}
+
+// The fact set rustc dumps for this function:
+//
+// let mut x = 22;
+// let y = &x;
+// x = 44;
+// use(y);
+pub(crate) const ASSIGN_TO_BORROWED: &[(&str, &str)] = &[
+    (
+        "cfg_edge",
+        r#"
+        "Start(bb0[0])" "Mid(bb0[0])"
+        "Mid(bb0[0])" "Start(bb0[1])"
+        "Start(bb0[1])" "Mid(bb0[1])"
+        "Mid(bb0[1])" "Start(bb0[2])"
+        "Start(bb0[2])" "Mid(bb0[2])"
+        "Mid(bb0[2])" "Start(bb0[3])"
+        "Start(bb0[3])" "Mid(bb0[3])"
+        "#,
+    ),
+    ("loan_issued_at", r#""'_#1r" "bw0" "Mid(bb0[1])""#),
+    ("subset_base", r#""'_#1r" "'_#2r" "Mid(bb0[1])""#),
+    ("var_defined_at", r#""y" "Mid(bb0[1])""#),
+    ("loan_invalidated_at", r#""Mid(bb0[2])" "bw0""#),
+    ("loan_killed_at", r#""bw0" "Mid(bb0[2])""#),
+    ("var_used_at", r#""y" "Mid(bb0[3])""#),
+    ("use_of_var_derefs_origin", r#""y" "'_#2r""#),
+    ("universal_region", r#""'_#0r""#),
+];
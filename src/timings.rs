@@ -0,0 +1,243 @@
+//! Timings of the phases of an analysis: parsing, lowering, emitting the facts, and each
+//! stratum of the solver, to know where the time goes on large programs.
+//!
+//! The phases are the `tracing` spans of the analysis, recorded by a `TimingsLayer` whatever the
+//! level of the logs. Each phase is identified by its path from the outermost span, e.g.
+//! `main > solve_within > subset`, and the spans of the same path are added up, like the
+//! multiple solvings of the diagnostics. The strata of the solver are named after the relations
+//! they compute.
+//!
+//! The allocations are counted by the `CountingAllocator`, when it's the global allocator, like
+//! in the `polonius` binary. Its counts are global, so a phase also counts the allocations of
+//! other threads during its spans, and there are none without it.
+
+use crate::json::Json;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::span;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+#[cfg(test)]
+mod test;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting the allocations and the bytes allocated.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+// The allocations and bytes allocated so far.
+fn allocation_counts() -> (u64, u64) {
+    (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        ALLOCATED_BYTES.load(Ordering::Relaxed),
+    )
+}
+
+/// The time and allocations of a phase, over all its spans.
+#[derive(Clone, Debug, Default)]
+pub struct PhaseTiming {
+    /// The names of the phase and of the phases containing it, outermost first.
+    pub path: Vec<String>,
+
+    /// The number of spans of the phase.
+    pub calls: usize,
+    pub duration: Duration,
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+}
+
+impl PhaseTiming {
+    /// The name of the phase, the last of its path.
+    pub fn name(&self) -> &str {
+        self.path.last().map_or("", String::as_str)
+    }
+}
+
+/// The timings of the phases, in the order they started, each after the phase containing it.
+#[derive(Clone, Debug, Default)]
+pub struct Timings {
+    pub phases: Vec<PhaseTiming>,
+}
+
+impl Timings {
+    /// The phase of the given path, e.g. `["main", "validate"]`.
+    pub fn phase(&self, path: &[&str]) -> Option<&PhaseTiming> {
+        self.phases.iter().find(|phase| phase.path == path)
+    }
+
+    /// The timings as a JSON array, of an object per phase, with its duration in milliseconds.
+    pub fn to_json(&self) -> Json {
+        let phases = self.phases.iter().map(|phase| {
+            Json::Object(vec![
+                (
+                    "phase".to_string(),
+                    Json::Array(phase.path.iter().cloned().map(Json::String).collect()),
+                ),
+                ("calls".to_string(), Json::Number(phase.calls as f64)),
+                (
+                    "duration_ms".to_string(),
+                    Json::Number(phase.duration.as_secs_f64() * 1000.0),
+                ),
+                (
+                    "allocations".to_string(),
+                    Json::Number(phase.allocations as f64),
+                ),
+                (
+                    "allocated_bytes".to_string(),
+                    Json::Number(phase.allocated_bytes as f64),
+                ),
+            ])
+        });
+        Json::Array(phases.collect())
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<44} {:>6} {:>12} {:>12} {:>14}",
+            "phase", "calls", "time", "allocations", "bytes"
+        )?;
+        for phase in &self.phases {
+            let indent = "  ".repeat(phase.path.len() - 1);
+            writeln!(
+                f,
+                "{:<44} {:>6} {:>10.2}ms {:>12} {:>14}",
+                format!("{}{}", indent, phase.name()),
+                phase.calls,
+                phase.duration.as_secs_f64() * 1000.0,
+                phase.allocations,
+                phase.allocated_bytes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A `tracing` layer recording the timings of the spans, into timings shared with its clones.
+#[derive(Clone, Default)]
+pub struct TimingsLayer {
+    timings: Arc<Mutex<Timings>>,
+}
+
+impl TimingsLayer {
+    /// The timings of the spans closed so far.
+    pub fn timings(&self) -> Timings {
+        self.timings.lock().unwrap().clone()
+    }
+}
+
+// The state of a span, in its extensions: the index of its phase, and the time and allocation
+// counts when it was entered.
+struct SpanTiming {
+    phase: usize,
+    entered: Option<(Instant, u64, u64)>,
+}
+
+// Records the name of a stratum of the solver, the relation it computes.
+struct RelationVisitor(Option<String>);
+
+impl tracing::field::Visit for RelationVisitor {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        if field.name() == "relation" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+        if field.name() == "relation" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+impl<S> Layer<S> for TimingsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("the span was just created");
+        let mut visitor = RelationVisitor(None);
+        attrs.record(&mut visitor);
+        let name = visitor
+            .0
+            .unwrap_or_else(|| span.metadata().name().to_string());
+
+        let mut timings = self.timings.lock().unwrap();
+        let parent_phase = span.parent().and_then(|parent| {
+            let extensions = parent.extensions();
+            extensions.get::<SpanTiming>().map(|timing| timing.phase)
+        });
+        let mut path = match parent_phase {
+            Some(parent_phase) => timings.phases[parent_phase].path.clone(),
+            None => Vec::new(),
+        };
+        path.push(name);
+        let phase = match timings.phases.iter().position(|phase| phase.path == path) {
+            Some(phase) => phase,
+            None => {
+                timings.phases.push(PhaseTiming {
+                    path,
+                    ..PhaseTiming::default()
+                });
+                timings.phases.len() - 1
+            }
+        };
+        timings.phases[phase].calls += 1;
+        span.extensions_mut().insert(SpanTiming {
+            phase,
+            entered: None,
+        });
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("the span is entered");
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<SpanTiming>() {
+            let (allocations, allocated_bytes) = allocation_counts();
+            timing.entered = Some((Instant::now(), allocations, allocated_bytes));
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("the span is exited");
+        let entered = match span.extensions_mut().get_mut::<SpanTiming>() {
+            Some(timing) => timing.entered.take().map(|entered| (timing.phase, entered)),
+            None => None,
+        };
+        if let Some((phase, (start, start_allocations, start_bytes))) = entered {
+            let (allocations, allocated_bytes) = allocation_counts();
+            let mut timings = self.timings.lock().unwrap();
+            let phase = &mut timings.phases[phase];
+            phase.duration += start.elapsed();
+            phase.allocations += allocations - start_allocations;
+            phase.allocated_bytes += allocated_bytes - start_bytes;
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use super::*;
+use crate::fact_emitter::emit_facts;
+use crate::solver::solve;
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn phases_of_an_analysis() {
+    let layer = TimingsLayer::default();
+    let subscriber = tracing_subscriber::registry().with(layer.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        let _main = tracing::info_span!("main").entered();
+        let facts = emit_facts(
+            "
+            let x: i32;
+            let y: &'y i32;
+
+            bb0: {
+                y = &'L_x x;
+                x = 4;
+                use(move y);
+            }
+            ",
+        )
+        .unwrap();
+        solve(&facts);
+        solve(&facts);
+    });
+
+    let timings = layer.timings();
+    let phases: Vec<_> = timings
+        .phases
+        .iter()
+        .map(|phase| format!("{} x{}", phase.path.join(" > "), phase.calls))
+        .collect();
+    insta::assert_display_snapshot!(phases.join("\n"), @r###"
+    main x1
+    main > emit_facts_with_options x1
+    main > emit_facts_with_options > parse_ast x1
+    main > emit_facts_with_options > parse_ast > lowering x1
+    main > emit_facts_with_options > block x1
+    main > solve_within x2
+    main > solve_within > origin_live_on_entry x2
+    main > solve_within > subset x2
+    main > solve_within > origin_invalidated x2
+    main > solve_within > origin_contains_loan_at x2
+    main > solve_within > known_placeholder_subset_closure x2
+    "###);
+
+    let main = timings.phase(&["main"]).unwrap();
+    let emit = timings.phase(&["main", "emit_facts_with_options"]).unwrap();
+    assert!(main.duration >= emit.duration);
+    assert!(timings.to_string().starts_with("phase"));
+    assert_eq!(timings.to_json().as_array().len(), timings.phases.len());
+}
@@ -0,0 +1,342 @@
+//! A terminal viewer stepping through the nodes of a program, to explore why an error arises:
+//! it shows the source with the statement of the current node highlighted, the CFG edges
+//! around the node, and the facts emitted at the node, with the loans each origin may contain
+//! on entry to it, and its errors.
+//!
+//! The CFG is walked along its edges, forward to one of the successors of the current node, or
+//! backward to one of its predecessors, and back through the nodes visited so far:
+//! - `→` or `l` follows the selected successor, `Tab` selects the next one,
+//! - `←` or `h` follows the selected predecessor, `Shift-Tab` selects the next one,
+//! - `Backspace` or `b` goes back to the previously visited node,
+//! - `q` or `Esc` quits.
+//!
+//! The state of the walk is kept in `NodeViewer`, independent of the terminal.
+
+use crate::diagnostics::{diagnose, Diagnostic};
+use crate::fact_emitter::{Facts, Node};
+use crate::solver::{solve, SolverOutput};
+use crate::source_map::SourceMap;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+#[cfg(test)]
+mod test;
+
+/// The nodes of a program, the current one, and the edges selected to leave it.
+pub struct NodeViewer {
+    facts: Facts,
+    output: SolverOutput,
+    diagnostics: Vec<Diagnostic>,
+
+    /// The nodes, in the order of their texts.
+    nodes: Vec<Node>,
+    current: usize,
+    selected_successor: usize,
+    selected_predecessor: usize,
+
+    /// The nodes visited before the current one, the last one most recently.
+    history: Vec<usize>,
+}
+
+impl NodeViewer {
+    /// Solves the facts, and starts at their first node.
+    pub fn new(facts: Facts) -> Self {
+        let mut nodes: Vec<Node> = Vec::new();
+        let edge_nodes = facts.cfg_edge.iter().flat_map(|(from, to)| [from, to]);
+        for node in facts
+            .node_text
+            .iter()
+            .map(|(_, node)| node)
+            .chain(edge_nodes)
+        {
+            if !nodes.contains(node) {
+                nodes.push(node.clone());
+            }
+        }
+        Self {
+            output: solve(&facts),
+            diagnostics: diagnose(&facts),
+            facts,
+            nodes,
+            current: 0,
+            selected_successor: 0,
+            selected_predecessor: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// The current node, if the program has any.
+    pub fn current(&self) -> Option<&Node> {
+        self.nodes.get(self.current)
+    }
+
+    /// The text of the current node.
+    pub fn current_text(&self) -> &str {
+        self.current()
+            .and_then(|node| self.facts.node_text.iter().find(|(_, n)| n == node))
+            .map_or("", |(text, _)| text)
+    }
+
+    /// The successors of the current node, in the CFG order.
+    pub fn successors(&self) -> Vec<&Node> {
+        self.facts
+            .cfg_edge
+            .iter()
+            .filter(|(from, _)| Some(from) == self.current())
+            .map(|(_, to)| to)
+            .collect()
+    }
+
+    /// The predecessors of the current node, in the CFG order.
+    pub fn predecessors(&self) -> Vec<&Node> {
+        self.facts
+            .cfg_edge
+            .iter()
+            .filter(|(_, to)| Some(to) == self.current())
+            .map(|(from, _)| from)
+            .collect()
+    }
+
+    pub fn selected_successor(&self) -> Option<&Node> {
+        self.successors().get(self.selected_successor).copied()
+    }
+
+    pub fn selected_predecessor(&self) -> Option<&Node> {
+        self.predecessors().get(self.selected_predecessor).copied()
+    }
+
+    pub fn select_next_successor(&mut self) {
+        let count = self.successors().len();
+        if count > 0 {
+            self.selected_successor = (self.selected_successor + 1) % count;
+        }
+    }
+
+    pub fn select_next_predecessor(&mut self) {
+        let count = self.predecessors().len();
+        if count > 0 {
+            self.selected_predecessor = (self.selected_predecessor + 1) % count;
+        }
+    }
+
+    /// Follows the edge to the selected successor, if there is one.
+    pub fn forward(&mut self) {
+        if let Some(node) = self.selected_successor().cloned() {
+            self.go_to(&node);
+        }
+    }
+
+    /// Follows the edge from the selected predecessor, if there is one.
+    pub fn backward(&mut self) {
+        if let Some(node) = self.selected_predecessor().cloned() {
+            self.go_to(&node);
+        }
+    }
+
+    /// Goes back to the previously visited node, if there is one.
+    pub fn back(&mut self) {
+        if let Some(previous) = self.history.pop() {
+            self.current = previous;
+            self.reset_selection();
+        }
+    }
+
+    fn go_to(&mut self, node: &Node) {
+        if let Some(idx) = self.nodes.iter().position(|n| n == node) {
+            self.history.push(self.current);
+            self.current = idx;
+            self.reset_selection();
+        }
+    }
+
+    fn reset_selection(&mut self) {
+        self.selected_successor = 0;
+        self.selected_predecessor = 0;
+    }
+
+    /// The facts emitted at the current node, in the fact file format.
+    pub fn facts_at_current(&self) -> Vec<String> {
+        let current = match self.current() {
+            Some(current) => current,
+            None => return Vec::new(),
+        };
+        let facts = &self.facts;
+        let at_current = |node: &Node| node == current;
+
+        // In the order of the facts' `Display`
+        let mut lines = Vec::new();
+        for (origin, _) in facts.access_origin.iter().filter(|(_, n)| at_current(n)) {
+            lines.push(format!("access_origin({})", origin.0));
+        }
+        for (origin, _) in facts
+            .invalidate_origin
+            .iter()
+            .filter(|(_, n)| at_current(n))
+        {
+            lines.push(format!("invalidate_origin({})", origin.0));
+        }
+        for (origin, _) in facts.clear_origin.iter().filter(|(_, n)| at_current(n)) {
+            lines.push(format!("clear_origin({})", origin.0));
+        }
+        for (origin, loan, _) in facts
+            .loan_issued_at
+            .iter()
+            .filter(|(_, _, n)| at_current(n))
+        {
+            lines.push(format!("loan_issued_at({}, {})", origin.0, loan.0));
+        }
+        for (origin1, origin2, _) in facts
+            .introduce_subset
+            .iter()
+            .filter(|(_, _, n)| at_current(n))
+        {
+            lines.push(format!("introduce_subset({}, {})", origin1.0, origin2.0));
+        }
+        lines
+    }
+
+    /// The loans each origin may contain on entry to the current node.
+    pub fn loans_at_current(&self) -> Vec<String> {
+        self.output
+            .origin_contains_loan_at
+            .iter()
+            .filter(|(_, _, node)| Some(node) == self.current())
+            .map(|(origin, loan, _)| format!("{} contains {}", origin.0, loan.0))
+            .collect()
+    }
+
+    /// The errors reported at the current node.
+    pub fn errors_at_current(&self) -> Vec<&Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| Some(&diagnostic.node) == self.current())
+            .collect()
+    }
+
+    /// The lines of the current node's statement in the source, if it has a span.
+    pub fn current_lines(&self, source_map: &SourceMap<'_>) -> Vec<usize> {
+        self.current()
+            .and_then(|node| self.facts.node_span(node))
+            .map_or(Vec::new(), |span| {
+                source_map
+                    .snippet(span)
+                    .into_iter()
+                    .map(|(line, _)| line)
+                    .collect()
+            })
+    }
+}
+
+/// Shows the viewer in the terminal until it's quit. The source is the text the facts were
+/// emitted from, whose spans are highlighted.
+pub fn run_viewer(source: &str, facts: Facts) -> eyre::Result<()> {
+    let mut viewer = NodeViewer::new(facts);
+    let mut terminal = ratatui::init();
+    let result = view(&mut terminal, source, &mut viewer);
+    ratatui::restore();
+    result
+}
+
+fn view(terminal: &mut DefaultTerminal, source: &str, viewer: &mut NodeViewer) -> eyre::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, source, viewer))?;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        match key.code {
+            KeyCode::Right | KeyCode::Char('l') => viewer.forward(),
+            KeyCode::Left | KeyCode::Char('h') => viewer.backward(),
+            KeyCode::Tab => viewer.select_next_successor(),
+            KeyCode::BackTab => viewer.select_next_predecessor(),
+            KeyCode::Backspace | KeyCode::Char('b') => viewer.back(),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame<'_>, source: &str, viewer: &NodeViewer) {
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(frame.area());
+    let [cfg_area, facts_area] =
+        Layout::vertical([Constraint::Length(9), Constraint::Min(0)]).areas(right);
+
+    // The source, with the lines of the current statement highlighted, and scrolled to them
+    let source_map = SourceMap::new(source);
+    let highlighted = viewer.current_lines(&source_map);
+    let lines: Vec<Line<'_>> = source
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            if highlighted.contains(&(idx + 1)) {
+                Line::styled(line, Style::new().reversed())
+            } else {
+                Line::raw(line)
+            }
+        })
+        .collect();
+    let height = left.height.saturating_sub(2) as usize;
+    let first_highlighted = highlighted.first().map_or(0, |line| line - 1);
+    let scroll = first_highlighted.saturating_sub(height / 2);
+    let source = Paragraph::new(Text::from(lines))
+        .block(Block::bordered().title("Source"))
+        .scroll((scroll as u16, 0));
+    frame.render_widget(source, left);
+
+    // The edges around the current node, with the selected ones marked
+    let edge = |node: &Node, selected: Option<&Node>| {
+        let marker = if Some(node) == selected { "> " } else { "  " };
+        Line::raw(format!("{}{}", marker, node.0))
+    };
+    let mut cfg = vec![Line::raw("predecessors:").bold()];
+    for node in viewer.predecessors() {
+        cfg.push(edge(node, viewer.selected_predecessor()));
+    }
+    let current = viewer.current().map_or("", |node| node.0.as_str());
+    let location = highlighted
+        .first()
+        .map_or(String::new(), |line| format!(" (line {})", line));
+    cfg.push(
+        Line::raw(format!(
+            "{}{}: \"{}\"",
+            current,
+            location,
+            viewer.current_text()
+        ))
+        .reversed(),
+    );
+    cfg.push(Line::raw("successors:").bold());
+    for node in viewer.successors() {
+        cfg.push(edge(node, viewer.selected_successor()));
+    }
+    let cfg = Paragraph::new(Text::from(cfg)).block(Block::bordered().title("CFG"));
+    frame.render_widget(cfg, cfg_area);
+
+    // The facts at the node, the loans on entry to it, and its errors
+    let mut lines: Vec<Line<'_>> = viewer
+        .facts_at_current()
+        .into_iter()
+        .map(Line::raw)
+        .collect();
+    let loans = viewer.loans_at_current();
+    if !loans.is_empty() {
+        lines.push(Line::raw(""));
+        lines.push(Line::raw("loans on entry:").bold());
+        lines.extend(loans.into_iter().map(Line::raw));
+    }
+    for diagnostic in viewer.errors_at_current() {
+        lines.push(Line::raw(""));
+        for line in diagnostic.to_string().lines() {
+            lines.push(Line::raw(line.to_string()).red());
+        }
+    }
+    let facts = Paragraph::new(Text::from(lines))
+        .block(Block::bordered().title("Facts — ←/→ follow edges, Tab select, b back, q quit"));
+    frame.render_widget(facts, facts_area);
+}
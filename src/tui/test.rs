@@ -0,0 +1,98 @@
+use super::*;
+use crate::fact_emitter::emit_facts;
+
+const PROGRAM: &str = "
+let x: i32;
+let r: &'r i32;
+bb0: {
+    r = &'L_x x;
+    goto bb1, bb2;
+}
+bb1: {
+    x = 4;
+    goto bb3;
+}
+bb2: {
+    goto bb3;
+}
+bb3: {
+    use(copy r);
+}
+";
+
+fn current(viewer: &NodeViewer) -> &str {
+    viewer.current().unwrap().0.as_str()
+}
+
+#[test]
+fn walking_the_cfg() {
+    let mut viewer = NodeViewer::new(emit_facts(PROGRAM).unwrap());
+    assert_eq!(current(&viewer), "bb0[0]");
+    assert_eq!(viewer.current_text(), "r = &'L_x x");
+    assert!(viewer.predecessors().is_empty());
+
+    // Following the second successor of the branch
+    viewer.select_next_successor();
+    assert_eq!(viewer.selected_successor().unwrap().0, "bb2[0]");
+    viewer.forward();
+    assert_eq!(current(&viewer), "bb2[0]");
+    viewer.forward();
+    assert_eq!(current(&viewer), "bb3[0]");
+
+    // Backward along the other predecessor, then back through the visited nodes
+    viewer.select_next_predecessor();
+    assert_eq!(viewer.selected_predecessor().unwrap().0, "bb2[0]");
+    viewer.select_next_predecessor();
+    viewer.backward();
+    assert_eq!(current(&viewer), "bb1[0]");
+    viewer.back();
+    viewer.back();
+    assert_eq!(current(&viewer), "bb2[0]");
+
+    // The first node stays current when there's nowhere to go
+    viewer.back();
+    viewer.back();
+    viewer.backward();
+    assert_eq!(current(&viewer), "bb0[0]");
+}
+
+#[test]
+fn current_lines() {
+    let source_map = SourceMap::new(PROGRAM);
+    let mut viewer = NodeViewer::new(emit_facts(PROGRAM).unwrap());
+    assert_eq!(viewer.current_lines(&source_map), [5]);
+    viewer.forward();
+    assert_eq!(current(&viewer), "bb1[0]");
+    assert_eq!(viewer.current_lines(&source_map), [9]);
+
+    // Fact files have no spans
+    let mut facts = emit_facts(PROGRAM).unwrap();
+    facts.node_span.clear();
+    assert!(NodeViewer::new(facts).current_lines(&source_map).is_empty());
+}
+
+#[test]
+fn node_details() {
+    let mut viewer = NodeViewer::new(emit_facts(PROGRAM).unwrap());
+    assert_eq!(
+        viewer.facts_at_current(),
+        [
+            "clear_origin('r)",
+            "clear_origin('L_x)",
+            "loan_issued_at('L_x, bw0)",
+            "introduce_subset('L_x, 'r)"
+        ]
+    );
+
+    // The loan is invalidated in `bb1`, where the error is reported, and accessed after it
+    viewer.forward();
+    assert_eq!(viewer.facts_at_current(), ["invalidate_origin('L_x)"]);
+    assert_eq!(viewer.errors_at_current().len(), 1);
+    viewer.forward();
+    assert_eq!(viewer.facts_at_current(), ["access_origin('r)"]);
+    assert_eq!(
+        viewer.loans_at_current(),
+        ["'L_x contains bw0", "'r contains bw0"]
+    );
+    assert!(viewer.errors_at_current().is_empty());
+}
@@ -0,0 +1,659 @@
+//! Checks of the program itself, before its facts are emitted, reported at spans of the input.
+//!
+//! The CFG must be well-formed for its facts to be emitted: there must be an entry block, block
+//! names must be unique, and successors must name existing blocks. Otherwise, the CFG edges
+//! would go to nodes which don't exist.
+//!
+//! The names the program refers to must also be declared: the variables of places, their fields,
+//! and the structs in types. Structs can't contain themselves, except behind references or raw
+//! pointers. The generic arguments of calls must match the generic parameters of the functions. The origins of borrows are their loans, which can't be origins of
+//! the declared types, and must always borrow the same place in the same way. Places are only
+//! checked once their variables have a type, so the places of variables whose types are inferred
+//! are only checked by the emitter.
+//!
+//! Syntax errors don't stop the validation: the statements failing to parse are left out, and
+//! the rest of the program is still checked, so that all its errors are reported at once.
+//!
+//! Blocks which are not reachable from the entry block are warned about: their facts are still
+//! emitted by default, and can be excluded with `EmitOptions::exclude_unreachable_blocks`. The
+//! blocks split by lowering from a reachable input block are only reported when they contain
+//! statements, e.g. the statements following an infinite `loop`.
+
+use crate::ast::{
+    walk_expr, AccessKind, Expr, GenericDecl, Name, Parameter, Place, Program, StructDecl, Ty,
+    VariableDecl, Visitor, ANONYMOUS_ORIGIN,
+};
+use crate::ast_parser::parse_ast_recovering;
+use crate::convert::RELATIONS;
+use crate::diagnostics::span_location_json;
+use crate::fact_emitter::{try_walk_place_tys, Origin};
+use crate::fact_parser::EXPECTED_GLOBAL_FACT_NAMES;
+use crate::graph::BlockGraph;
+use crate::json::Json;
+use crate::source_map::SourceMap;
+use crate::span::Span;
+use std::fmt;
+use std::path::PathBuf;
+
+#[cfg(test)]
+mod test;
+
+/// A problem in the program, at a span of the input.
+#[derive(Debug)]
+pub struct SourceDiagnostic {
+    pub severity: Severity,
+    pub message: String,
+
+    /// The file the span is in, when the program was loaded from files, see `Sources`.
+    pub path: Option<PathBuf>,
+    pub span: Span,
+
+    /// The text of the input at the span.
+    pub text: String,
+}
+
+/// Errors prevent the program's facts from being emitted, warnings don't.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl SourceDiagnostic {
+    pub(crate) fn new(severity: Severity, message: String, span: Span, input: &str) -> Self {
+        Self {
+            severity,
+            message,
+            path: None,
+            span,
+            text: input[span.start()..span.end()].to_string(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Renders the diagnostic in the style of rustc's, with the line and column of its span,
+    /// and the lines it's on, in the text of the source map.
+    pub fn render(&self, source_map: &SourceMap<'_>) -> String {
+        let mut rendered = match self.severity {
+            Severity::Error => format!("error: {}\n", self.message),
+            Severity::Warning => format!("warning: {}\n", self.message),
+        };
+        let (line, column) = source_map.line_col(self.span.start());
+        let (end_line, _) = source_map.line_col(self.span.end());
+        let path = self
+            .path
+            .as_ref()
+            .map_or(String::new(), |path| format!("{}:", path.display()));
+        let snippet = source_map.snippet(self.span);
+        let gutter = " ".repeat(end_line.to_string().len());
+        rendered += &format!("{}--> {}{}:{}\n", gutter, path, line, column);
+        rendered += &format!("{} |\n", gutter);
+        for (idx, (snippet_line, text)) in snippet.iter().enumerate() {
+            rendered += &format!(
+                "{:>width$} | {}\n",
+                snippet_line,
+                text,
+                width = gutter.len()
+            );
+
+            // The span is underlined on its first line only
+            if idx == 0 {
+                let (column, len) = source_map.underline(self.span);
+                let underline = "^".repeat(len);
+                rendered += &format!("{} | {}{}\n", gutter, " ".repeat(column - 1), underline);
+            }
+        }
+        rendered
+    }
+
+    /// The diagnostic as JSON, in the structure of rustc's `--error-format=json`, like
+    /// `Diagnostic::to_json`, with its span in the text of the source map. The file name is
+    /// `<input>` when the diagnostic has no path.
+    pub fn to_json(&self, source_map: &SourceMap<'_>) -> Json {
+        let file_name = self
+            .path
+            .as_ref()
+            .map_or("<input>".to_string(), |path| path.display().to_string());
+        let mut span = span_location_json(&file_name, source_map, self.span);
+        span.push(("is_primary".to_string(), Json::Bool(true)));
+        span.push(("label".to_string(), Json::Null));
+        let level = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        Json::Object(vec![
+            ("message".to_string(), Json::String(self.message.clone())),
+            ("code".to_string(), Json::Null),
+            ("level".to_string(), Json::String(level.to_string())),
+            ("spans".to_string(), Json::Array(vec![Json::Object(span)])),
+            ("children".to_string(), Json::Array(Vec::new())),
+            (
+                "rendered".to_string(),
+                Json::String(self.render(source_map)),
+            ),
+        ])
+    }
+}
+
+impl fmt::Display for SourceDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => writeln!(f, "error: {}", self.message)?,
+            Severity::Warning => writeln!(f, "warning: {}", self.message)?,
+        }
+        write!(f, "  --> ")?;
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+        }
+        writeln!(
+            f,
+            "{}..{} `{}`",
+            self.span.start(),
+            self.span.end(),
+            self.text
+        )
+    }
+}
+
+/// Parses the input program and returns its errors and warnings, in program order. Parsing
+/// keeps going after syntax errors: they are reported with the errors of the rest of the
+/// program, unless parsing can't recover from them.
+#[tracing::instrument(skip_all)]
+pub fn validate(input: &str) -> eyre::Result<Vec<SourceDiagnostic>> {
+    let (program, syntax_errors) = parse_ast_recovering(input);
+    let mut diagnostics: Vec<_> = syntax_errors
+        .into_iter()
+        .map(|(span, message)| SourceDiagnostic::new(Severity::Error, message, span, input))
+        .collect();
+    if let Some(program) = program {
+        diagnostics.extend(errors(&program, input));
+        diagnostics.extend(unreachable_blocks(&program, input));
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start());
+    Ok(diagnostics)
+}
+
+/// The errors preventing the program's facts from being emitted, in program order.
+pub(crate) fn errors(program: &Program, input: &str) -> Vec<SourceDiagnostic> {
+    let mut errors = cfg_errors(program, input);
+    errors.extend(name_errors(program, input));
+    errors.extend(recursive_struct_errors(program, input));
+    errors.extend(call_errors(program, input));
+    errors.extend(borrow_errors(program, input));
+    errors.extend(raw_fact_errors(program, input));
+    errors.sort_by_key(|error| error.span.start());
+    errors
+}
+
+/// The errors making the CFG ill-formed. The missing entry block is reported at the end of the
+/// input, and the other errors at the labels of the blocks involved.
+fn cfg_errors(program: &Program, input: &str) -> Vec<SourceDiagnostic> {
+    let mut errors = Vec::new();
+    if program.basic_blocks.is_empty() {
+        errors.push(SourceDiagnostic::new(
+            Severity::Error,
+            "missing entry block".to_string(),
+            Span::new(input.len(), input.len()),
+            input,
+        ));
+    }
+
+    for (idx, bb) in program.basic_blocks.iter().enumerate() {
+        if program.basic_blocks[..idx]
+            .iter()
+            .any(|previous| previous.name == bb.name)
+        {
+            errors.push(SourceDiagnostic::new(
+                Severity::Error,
+                format!("block `{}` is defined more than once", bb.name),
+                bb.span,
+                input,
+            ));
+        }
+
+        for successor in &bb.terminator.successors() {
+            if !program
+                .basic_blocks
+                .iter()
+                .any(|candidate| candidate.name == *successor)
+            {
+                errors.push(SourceDiagnostic::new(
+                    Severity::Error,
+                    format!("block `{}` goes to unknown block `{}`", bb.name, successor),
+                    bb.span,
+                    input,
+                ));
+            }
+        }
+    }
+    errors
+}
+
+// The references to undeclared structs in types, and the invalid places: their variables must be
+// declared, and their projections must apply to the types they project.
+fn name_errors(program: &Program, input: &str) -> Vec<SourceDiagnostic> {
+    let mut errors = Vec::new();
+    let mut check_ty = |ty: &Ty, generic_decls: &[GenericDecl], context: String, span| {
+        let mut structs = Vec::new();
+        collect_structs(ty, true, &mut structs);
+        for name in structs {
+            let is_declared = program.struct_decls.iter().any(|s| s.name == *name)
+                || generic_decls
+                    .iter()
+                    .any(|d| matches!(d, GenericDecl::Ty(param) if param == name));
+            if !is_declared {
+                errors.push(SourceDiagnostic::new(
+                    Severity::Error,
+                    format!("unknown struct `{}` in {}", name, context),
+                    span,
+                    input,
+                ));
+            }
+        }
+    };
+
+    for decl in &program.struct_decls {
+        for field in &decl.field_decls {
+            let context = format!("the type of field `{}.{}`", decl.name, field.name);
+            check_ty(&field.ty, &decl.generic_decls, context, field.span);
+        }
+    }
+    for prototype in &program.fn_prototypes {
+        let context = format!("the signature of `{}`", prototype.name);
+        for ty in prototype.arg_tys.iter().chain([&prototype.ret_ty]) {
+            check_ty(
+                ty,
+                &prototype.generic_decls,
+                context.clone(),
+                prototype.span,
+            );
+        }
+    }
+    for v in &program.variables {
+        check_ty(&v.ty, &[], format!("the type of `{}`", v.name), v.span);
+    }
+    for bb in &program.basic_blocks {
+        for s in &bb.statements {
+            let expr = match s.expr() {
+                Some(expr) => expr,
+                None => continue,
+            };
+            visit_exprs(expr, &mut |expr| match expr {
+                Expr::Cast { ty, .. } => check_ty(ty, &[], "a cast".to_string(), s.span()),
+                Expr::Call {
+                    name, generic_args, ..
+                } => {
+                    for arg in generic_args {
+                        if let Parameter::Ty(ty) = arg {
+                            let context = format!("the generic arguments of `{}`", name);
+                            check_ty(ty, &[], context, s.span());
+                        }
+                    }
+                }
+                _ => {}
+            });
+        }
+    }
+
+    // Structs are needed to check fields, so places are only checked when they're all declared
+    if !errors.is_empty() {
+        return errors;
+    }
+    for bb in &program.basic_blocks {
+        for s in &bb.statements {
+            struct Places<'p>(Vec<&'p Place>);
+            impl<'p> Visitor<'p> for Places<'p> {
+                fn visit_place(&mut self, place: &'p Place) {
+                    self.0.push(place);
+                }
+            }
+            let mut places = Places(Vec::new());
+            places.visit_statement(s);
+
+            for place in places.0 {
+                let is_inferred = program
+                    .variables
+                    .iter()
+                    .any(|v| v.name == place.base && v.ty == Ty::Infer);
+                if is_inferred {
+                    continue;
+                }
+                if let Err(message) = try_walk_place_tys(program, place, |_| ()) {
+                    errors.push(SourceDiagnostic::new(
+                        Severity::Error,
+                        message,
+                        s.span(),
+                        input,
+                    ));
+                }
+            }
+        }
+    }
+    errors
+}
+
+// The facts of `facts { .. }` sections which are not facts of the rules' inputs: their relation
+// must be known, global facts can't have a node and the others must, and they must have the
+// relation's arity, with the node as their last atom. The node texts and locations, source
+// locations and edge kinds can't be written there.
+fn raw_fact_errors(program: &Program, input: &str) -> Vec<SourceDiagnostic> {
+    let mut errors = Vec::new();
+    for fact in &program.raw_facts {
+        let relation = RELATIONS.iter().find(|(name, _)| {
+            ![
+                "node_text",
+                "source_location",
+                "node_location",
+                "cfg_edge_kind",
+            ]
+            .contains(name)
+                && fact.relation == *name
+        });
+        let message = match relation {
+            None => format!("unknown relation `{}`", fact.relation),
+            Some((name, columns)) => {
+                let arity = columns.len();
+                let is_global = EXPECTED_GLOBAL_FACT_NAMES.contains(name);
+                let atom_count = fact.arguments.len() + usize::from(fact.node.is_some());
+                if is_global && fact.node.is_some() {
+                    format!("`{}` facts are global, and can't have a node", name)
+                } else if !is_global && fact.node.is_none() {
+                    format!("`{}` facts need a node, e.g. `bb0[0]: {}(..)`", name, name)
+                } else if atom_count != arity {
+                    format!(
+                        "`{}` facts have {} atoms, found {}",
+                        name, arity, atom_count
+                    )
+                } else {
+                    continue;
+                }
+            }
+        };
+        errors.push(SourceDiagnostic::new(
+            Severity::Error,
+            message,
+            fact.span(),
+            input,
+        ));
+    }
+    errors
+}
+
+// The calls whose generic arguments don't match the generic parameters of their function. The
+// origins, and the types, can be left out of the generic arguments, but otherwise they must all be
+// given. Undeclared functions have no generic parameters.
+fn call_errors(program: &Program, input: &str) -> Vec<SourceDiagnostic> {
+    let mut errors = Vec::new();
+    for bb in &program.basic_blocks {
+        for s in &bb.statements {
+            let expr = match s.expr() {
+                Some(expr) => expr,
+                None => continue,
+            };
+            let (name, generic_args) = match expr {
+                Expr::Call {
+                    name, generic_args, ..
+                } if !generic_args.is_empty() => (name, generic_args),
+                _ => continue,
+            };
+            let prototype = match program.fn_prototypes.iter().find(|f| &f.name == name) {
+                Some(prototype) => prototype,
+                None => {
+                    errors.push(SourceDiagnostic::new(
+                        Severity::Error,
+                        format!(
+                            "undeclared function `{}` can't have generic arguments",
+                            name
+                        ),
+                        s.span(),
+                        input,
+                    ));
+                    continue;
+                }
+            };
+
+            let is_origin = |arg: &&Parameter| matches!(arg, Parameter::Origin(_));
+            let is_origin_decl = |decl: &&GenericDecl| matches!(decl, GenericDecl::Origin(_));
+            let origin_count = generic_args.iter().filter(is_origin).count();
+            let ty_count = generic_args.len() - origin_count;
+            let origin_decl_count = prototype
+                .generic_decls
+                .iter()
+                .filter(is_origin_decl)
+                .count();
+            let ty_decl_count = prototype.generic_decls.len() - origin_decl_count;
+            for (kind, count, decl_count) in [
+                ("origin", origin_count, origin_decl_count),
+                ("type", ty_count, ty_decl_count),
+            ] {
+                if count != 0 && count != decl_count {
+                    errors.push(SourceDiagnostic::new(
+                        Severity::Error,
+                        format!(
+                            "`{}` expects {} {} argument(s), but {} are given",
+                            name, decl_count, kind, count
+                        ),
+                        s.span(),
+                        input,
+                    ));
+                }
+            }
+        }
+    }
+    errors
+}
+
+// The structs containing themselves without indirection, which would have an infinite size. The
+// parameters of a struct are assumed to be contained in it, so `struct List { next: Vec<List> }`
+// is recursive, unless `Vec` is declared with a reference to its parameter. Each struct of a cycle
+// is reported at its first field leading back to itself.
+fn recursive_struct_errors(program: &Program, input: &str) -> Vec<SourceDiagnostic> {
+    // Whether the struct `to` is contained in the struct `from`, possibly through other structs
+    let contains = |from: &Name, to: &Name| {
+        let mut visited = vec![from];
+        let mut stack = vec![from];
+        while let Some(name) = stack.pop() {
+            let decl = match program.struct_decls.iter().find(|s| s.name == *name) {
+                Some(decl) => decl,
+                None => continue,
+            };
+            for field in &decl.field_decls {
+                for contained in contained_structs(decl, field) {
+                    if contained == to {
+                        return true;
+                    }
+                    if !visited.contains(&contained) {
+                        visited.push(contained);
+                        stack.push(contained);
+                    }
+                }
+            }
+        }
+        false
+    };
+
+    let mut errors = Vec::new();
+    for decl in &program.struct_decls {
+        let recursive_field = decl.field_decls.iter().find(|field| {
+            contained_structs(decl, field)
+                .into_iter()
+                .any(|contained| contained == &decl.name || contains(contained, &decl.name))
+        });
+        if let Some(field) = recursive_field {
+            errors.push(SourceDiagnostic::new(
+                Severity::Error,
+                format!(
+                    "recursive struct `{}` has infinite size, through field `{}.{}`",
+                    decl.name, decl.name, field.name
+                ),
+                field.span,
+                input,
+            ));
+        }
+    }
+    errors
+}
+
+// The structs contained in the field of the struct, without indirection, other than the
+// struct's parameters.
+fn contained_structs<'p>(decl: &'p StructDecl, field: &'p VariableDecl) -> Vec<&'p Name> {
+    let mut structs = Vec::new();
+    collect_structs(&field.ty, false, &mut structs);
+    structs.retain(|name| {
+        !decl
+            .generic_decls
+            .iter()
+            .any(|d| matches!(d, GenericDecl::Ty(param) if param == *name))
+    });
+    structs
+}
+
+// The borrows whose origins are not loans: their origin is an origin of the declared types, or
+// also the origin of a borrow of another place, or of another kind.
+fn borrow_errors(program: &Program, input: &str) -> Vec<SourceDiagnostic> {
+    let mut declared_origins: Vec<Origin> = program.placeholders.iter().map(Origin::from).collect();
+    let tys = program
+        .variables
+        .iter()
+        .map(|v| &v.ty)
+        .chain(program.fn_prototypes.iter().flat_map(|f| &f.arg_tys))
+        .chain(program.fn_prototypes.iter().map(|f| &f.ret_ty));
+    for ty in tys {
+        ty.collect_origins_into(&mut declared_origins);
+    }
+
+    let mut errors = Vec::new();
+    let mut loans: Vec<(&Name, bool, &Place)> = Vec::new();
+    for bb in &program.basic_blocks {
+        for s in &bb.statements {
+            let expr = match s.expr() {
+                Some(expr) => expr,
+                None => continue,
+            };
+            visit_exprs(expr, &mut |expr| {
+                let (origin, is_mut, place) = match expr {
+                    Expr::Access {
+                        kind: AccessKind::Borrow(origin),
+                        place,
+                    } => (origin, false, place),
+                    Expr::Access {
+                        kind: AccessKind::BorrowMut(origin),
+                        place,
+                    } => (origin, true, place),
+                    _ => return,
+                };
+                if origin == ANONYMOUS_ORIGIN {
+                    return;
+                }
+
+                let message = if declared_origins.contains(&Origin::from(origin)) {
+                    Some(format!(
+                        "the origin `{}` of a borrow is already used in the declarations",
+                        origin
+                    ))
+                } else {
+                    match loans.iter().find(|(loan, ..)| *loan == origin) {
+                        Some((_, loan_is_mut, loan_place))
+                            if *loan_is_mut != is_mut || *loan_place != place =>
+                        {
+                            Some(format!(
+                                "the origin `{}` is already used by another borrow, `{}{}`",
+                                origin,
+                                if *loan_is_mut { "&mut " } else { "&" },
+                                loan_place
+                            ))
+                        }
+                        Some(_) => None,
+                        None => {
+                            loans.push((origin, is_mut, place));
+                            None
+                        }
+                    }
+                };
+                if let Some(message) = message {
+                    errors.push(SourceDiagnostic::new(
+                        Severity::Error,
+                        message,
+                        s.span(),
+                        input,
+                    ));
+                }
+            });
+        }
+    }
+    errors
+}
+
+fn unreachable_blocks(program: &Program, input: &str) -> Vec<SourceDiagnostic> {
+    let text = |span: Span| &input[span.start()..span.end()];
+    let mut warnings = Vec::new();
+
+    // The spans of the unreachable input blocks: the blocks split from them are not reported
+    // again
+    let mut unreachable_labels = Vec::new();
+    for block in BlockGraph::from_program(program).unreachable_blocks() {
+        let bb = &program.basic_blocks[block];
+        let is_duplicate = program.basic_blocks[..block]
+            .iter()
+            .any(|previous| previous.name == bb.name);
+        if is_duplicate {
+            // The edges go to the first block with this name, and this one is already an error
+            continue;
+        } else if text(bb.span) == bb.name {
+            unreachable_labels.push(bb.span);
+            warnings.push(SourceDiagnostic::new(
+                Severity::Warning,
+                format!("unreachable block `{}`", bb.name),
+                bb.span,
+                input,
+            ));
+        } else if unreachable_labels.contains(&bb.span) {
+            continue;
+        } else if let Some(s) = bb.statements.first() {
+            warnings.push(SourceDiagnostic::new(
+                Severity::Warning,
+                "unreachable statement".to_string(),
+                s.span(),
+                input,
+            ));
+        }
+    }
+    warnings
+}
+
+// Calls `f` with the expression, and each of the expressions nested in it.
+pub(crate) fn visit_exprs<'e>(expr: &'e Expr, f: &mut impl FnMut(&'e Expr)) {
+    struct Exprs<F>(F);
+    impl<'e, F: FnMut(&'e Expr)> Visitor<'e> for Exprs<F> {
+        fn visit_expr(&mut self, expr: &'e Expr) {
+            (self.0)(expr);
+            walk_expr(self, expr);
+        }
+    }
+    Exprs(f).visit_expr(expr);
+}
+
+// Collects the names of the structs in the type, including in its parameters, and behind
+// references and raw pointers when `through_indirections` is true.
+fn collect_structs<'t>(ty: &'t Ty, through_indirections: bool, structs: &mut Vec<&'t Name>) {
+    match ty {
+        Ty::Ref { ty, .. } | Ty::RefMut { ty, .. } | Ty::RawPtr { ty } | Ty::RawPtrMut { ty } => {
+            if through_indirections {
+                collect_structs(ty, through_indirections, structs)
+            }
+        }
+        Ty::Array { ty, .. } => collect_structs(ty, through_indirections, structs),
+        Ty::Struct { name, parameters } => {
+            structs.push(name);
+            for parameter in parameters {
+                if let Parameter::Ty(ty) = parameter {
+                    collect_structs(ty, through_indirections, structs);
+                }
+            }
+        }
+        Ty::I32 | Ty::Unit | Ty::Infer => {}
+    }
+}
@@ -0,0 +1,358 @@
+use super::*;
+use crate::fact_emitter::emit_facts;
+use insta::assert_display_snapshot;
+
+// The diagnostics of the program, one after the other.
+fn expect_diagnostics(input: &str) -> String {
+    validate(input)
+        .unwrap()
+        .iter()
+        .map(SourceDiagnostic::to_string)
+        .collect()
+}
+
+#[test]
+fn unreachable_blocks() {
+    let program = "
+        let x: i32;
+
+        bb0: {
+            x = 0;
+        }
+
+        bb1: {
+            x = 1;
+            goto bb2;
+        }
+
+        bb2: {
+            goto bb1;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    warning: unreachable block `bb1`
+      --> 75..78 `bb1`
+    warning: unreachable block `bb2`
+      --> 142..145 `bb2`
+    "###);
+}
+
+#[test]
+fn statements_after_infinite_loops() {
+    // The block split from `bb0` after the loop is unreachable, but `bb0` itself isn't
+    let program = "
+        let x: i32;
+
+        bb0: {
+            loop {
+                x = 0;
+            }
+            x = 1;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    warning: unreachable statement
+      --> 105..111 `x = 1;`
+    "###);
+}
+
+#[test]
+fn reachable_blocks() {
+    let program = "
+        let x: i32;
+
+        bb0: {
+            while copy x {
+                x = 0;
+            }
+            goto bb1;
+        }
+
+        bb1: {
+            x = 1;
+        }
+    ";
+    assert!(validate(program).unwrap().is_empty());
+}
+
+#[test]
+fn ill_formed_cfg() {
+    let program = "
+        let x: i32;
+
+        bb0: {
+            x = 0;
+            goto bb1, bb3;
+        }
+
+        bb1: {
+            x = 1;
+        }
+
+        bb1: {
+            x = 2;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: block `bb0` goes to unknown block `bb3`
+      --> 30..33 `bb0`
+    error: block `bb1` is defined more than once
+      --> 147..150 `bb1`
+    "###);
+    assert_display_snapshot!(emit_facts(program).unwrap_err(), @r###"
+    the program is invalid:
+    error: block `bb0` goes to unknown block `bb3`
+      --> 30..33 `bb0`
+    error: block `bb1` is defined more than once
+      --> 147..150 `bb1`
+    "###);
+}
+
+#[test]
+fn missing_entry_block() {
+    let program = "
+        let x: i32;
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: missing entry block
+      --> 25..25 ``
+    "###);
+}
+
+#[test]
+fn undeclared_structs() {
+    let program = "
+        struct Pair<T> { first: T, second: Vec<T> }
+        fn make<T>(value: T) -> Option<T>;
+
+        let pair: Pair<i32>;
+        let v: &'v Vec<i32>;
+        let p: *const i32;
+
+        bb0: {
+            p = &'L_pair pair as *const Pair<i32>;
+            p = &'L_v v as *const Unknown;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: unknown struct `Vec` in the type of field `Pair.second`
+      --> 36..42 `second`
+    error: unknown struct `Option` in the signature of `make`
+      --> 64..68 `make`
+    error: unknown struct `Vec` in the type of `v`
+      --> 138..139 `v`
+    error: unknown struct `Unknown` in a cast
+      --> 261..291 `p = &'L_v v as *const Unknown;`
+    "###);
+}
+
+#[test]
+fn invalid_places() {
+    // Places are checked once all the structs are declared
+    let program = "
+        struct Pair { first: i32, second: i32 }
+
+        let pair: Pair;
+        let x: i32;
+        let r: &'r Pair;
+
+        bb0: {
+            x = copy pair.third;
+            use(copy y);
+            x = copy *x;
+            x = copy (*r).first;
+            x = copy x.f;
+            x = copy x[0];
+            x = copy (*r).first[i];
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: struct `Pair` has no field `third`
+      --> 147..167 `x = copy pair.third;`
+    error: unknown variable `y`
+      --> 180..192 `use(copy y);`
+    error: can't dereference `x`, which is not a reference
+      --> 205..217 `x = copy *x;`
+    error: can't access field `f` of `x`, which is not a struct
+      --> 263..276 `x = copy x.f;`
+    error: can't index `x`, which is not an array
+      --> 289..303 `x = copy x[0];`
+    error: unknown variable `i`
+      --> 316..339 `x = copy (*r).first[i];`
+    "###);
+}
+
+#[test]
+fn inconsistent_borrow_origins() {
+    let program = "
+        placeholder 'a;
+
+        let x: i32;
+        let y: i32;
+        let p: &'p i32;
+        let q: &'q mut i32;
+
+        bb0: {
+            p = &'a x;
+            p = &'p x;
+            p = &'L x;
+            p = &'L x;
+            p = &'L y;
+            q = &'L mut x;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: the origin `'a` of a borrow is already used in the declarations
+      --> 146..156 `p = &'a x;`
+    error: the origin `'p` of a borrow is already used in the declarations
+      --> 169..179 `p = &'p x;`
+    error: the origin `'L` is already used by another borrow, `&x`
+      --> 238..248 `p = &'L y;`
+    error: the origin `'L` is already used by another borrow, `&x`
+      --> 261..275 `q = &'L mut x;`
+    "###);
+}
+
+#[test]
+fn recursive_structs() {
+    // `List` contains itself through `Node`, but `Tree` only through references
+    let program = "
+        struct List { head: Node }
+        struct Node { value: i32, next: List }
+        struct Tree<'a> { left: &'a Tree<'a>, right: *const Tree<'a> }
+        struct Wrapper<T> { value: T }
+        struct Nested { inner: Wrapper<Nested> }
+
+        let x: i32;
+
+        bb0: {
+            x = 0;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: recursive struct `List` has infinite size, through field `List.head`
+      --> 23..27 `head`
+    error: recursive struct `Node` has infinite size, through field `Node.next`
+      --> 70..74 `next`
+    error: recursive struct `Nested` has infinite size, through field `Nested.inner`
+      --> 217..222 `inner`
+    "###);
+}
+
+#[test]
+fn invalid_generic_args() {
+    let program = "
+        fn id<'a, T>(x: &'a T) -> &'a T;
+
+        let x: &'x i32;
+
+        bb0: {
+            x = id::<i32>(copy x);
+            x = id::<'b, i32, i32>(copy x);
+            x = id::<'b, 'c>(copy x);
+            x = id::<Unknown>(copy x);
+            opaque::<i32>(copy x);
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: `id` expects 1 type argument(s), but 2 are given
+      --> 130..161 `x = id::<'b, i32, i32>(copy x);`
+    error: `id` expects 1 origin argument(s), but 2 are given
+      --> 174..199 `x = id::<'b, 'c>(copy x);`
+    error: unknown struct `Unknown` in the generic arguments of `id`
+      --> 212..238 `x = id::<Unknown>(copy x);`
+    error: undeclared function `opaque` can't have generic arguments
+      --> 251..273 `opaque::<i32>(copy x);`
+    "###);
+}
+
+#[test]
+fn syntax_errors() {
+    // Parsing keeps going after the erroneous statements, and the rest of the program is still
+    // checked
+    let program = "
+        let x: i32;
+
+        bb0: {
+            x = copy;
+            x = 1;
+            use(copy y);
+            x = = 2;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: syntax error, expected one of "(", "*", ".", "::", "[", an expression, an identifier
+      --> 57..58 `;`
+    error: unknown variable `y`
+      --> 90..102 `use(copy y);`
+    error: syntax error, expected one of "&", "(", "*", "copy", "move", a number, an identifier
+      --> 119..120 `=`
+    "###);
+
+    // Unknown chars are reported, and the rest of their statement is still parsed
+    let program = "
+        let x: i32;
+
+        bb0: {
+            x = 1 $;
+            x = ¤y;
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: unknown character `$`
+      --> 55..56 `$`
+    error: unknown character `¤`
+      --> 74..76 `¤`
+    error: syntax error, expected one of "(", ".", "::", "[", an expression
+      --> 77..78 `;`
+    "###);
+
+    // Errors which can't be recovered from stop the parsing
+    assert_display_snapshot!(expect_diagnostics("let x: i32; bb0: { x = 1;"), @r###"
+    error: syntax error, expected one of "@", "assert", "goto", "return", "switch", "unreachable", "}"
+      --> 25..25 ``
+    "###);
+}
+
+#[test]
+fn invalid_raw_facts() {
+    let program = "
+        bb0: { }
+        facts {
+            bb0[0]: loan_issued('a, L0)
+            bb0[0]: placeholder_origin('a)
+            clear_origin('a)
+            bb0[0]: introduce_subset('a)
+            bb0[0]: invalidate_origin('a)
+        }
+    ";
+    assert_display_snapshot!(expect_diagnostics(program), @r###"
+    error: unknown relation `loan_issued`
+      --> 46..73 `bb0[0]: loan_issued('a, L0)`
+    error: `placeholder_origin` facts are global, and can't have a node
+      --> 86..116 `bb0[0]: placeholder_origin('a)`
+    error: `clear_origin` facts need a node, e.g. `bb0[0]: clear_origin(..)`
+      --> 129..145 `clear_origin('a)`
+    error: `introduce_subset` facts have 3 atoms, found 2
+      --> 158..186 `bb0[0]: introduce_subset('a)`
+    "###);
+}
+
+#[test]
+fn non_ascii_identifiers() {
+    // Spans ending in a multi-byte char are rendered on the line of that char
+    let program = "let ü: i32; bb0: { ü = 1; } bbü: { ü = 2; }";
+    let source_map = SourceMap::new(program);
+    let rendered: String = validate(program)
+        .unwrap()
+        .iter()
+        .map(|diagnostic| diagnostic.render(&source_map))
+        .collect();
+    assert_display_snapshot!(rendered, @r###"
+    warning: unreachable block `bbü`
+     --> 1:29
+      |
+    1 | let ü: i32; bb0: { ü = 1; } bbü: { ü = 2; }
+      |                             ^^^
+    "###);
+}
@@ -0,0 +1,22 @@
+use std::path::Path;
+
+// The programs in `examples` are annotated with the errors they have.
+#[test]
+fn annotated_examples() -> eyre::Result<()> {
+    for entry in std::fs::read_dir("examples")? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("txt") {
+            continue;
+        }
+
+        let sources = polonius::Sources::load(Path::new(&path))?;
+        let mismatches = polonius::check_annotations(sources.text())?;
+        assert!(
+            mismatches.is_empty(),
+            "unexpected errors in {}:\n{}",
+            path.display(),
+            mismatches
+        );
+    }
+    Ok(())
+}
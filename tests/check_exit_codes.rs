@@ -0,0 +1,29 @@
+use std::process::Command;
+
+// The exit code of `check` on the arguments.
+fn check(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_polonius"))
+        .arg("check")
+        .args(args)
+        .output()
+        .expect("failed to run the binary")
+        .status
+        .code()
+}
+
+#[test]
+fn exit_codes() {
+    // No errors, errors, and programs which can't be analyzed
+    assert_eq!(check(&["examples/disjoint-loans.txt"]), Some(0));
+    assert_eq!(check(&["examples/borrow-conflicts.txt"]), Some(1));
+    assert_eq!(check(&["examples/missing.txt"]), Some(2));
+    assert_eq!(
+        check(&["examples/disjoint-loans.txt", "--unknown"]),
+        Some(2)
+    );
+
+    // Batches fail like their worst program
+    assert_eq!(check(&["examples", "--emit-only"]), Some(0));
+    assert_eq!(check(&["examples"]), Some(1));
+    assert_eq!(check(&["examples", "examples/missing.txt"]), Some(2));
+}